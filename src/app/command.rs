@@ -0,0 +1,420 @@
+//! Commands for the `:` command line
+//!
+//! Every list view action beyond moving around is available here by name,
+//! so actions without a dedicated key binding still have a home. Parsing and
+//! completion are kept free of UI state beyond the list being operated on.
+
+use crate::model::{FilterField, FlakeInput, SortOrder};
+use crate::ui::theme;
+
+use super::keymap::ListCommand;
+use super::state::ListState;
+
+/// A command offered by the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub args: &'static str,
+    pub description: &'static str,
+}
+
+/// Every command, in the order they are offered for completion
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "update",
+        args: "[input...]",
+        description: "Update the named inputs, or the selection",
+    },
+    CommandSpec {
+        name: "update-all",
+        args: "",
+        description: "Update all inputs",
+    },
+    CommandSpec {
+        name: "refresh",
+        args: "",
+        description: "Reload flake metadata",
+    },
+    CommandSpec {
+        name: "history",
+        args: "[input]",
+        description: "Open commit history to inspect or lock an input",
+    },
+    CommandSpec {
+        name: "lock",
+        args: "[input]",
+        description: "Pick a commit to lock an input to",
+    },
+    CommandSpec {
+        name: "undo-update",
+        args: "",
+        description: "Undo the last update or lock",
+    },
+    CommandSpec {
+        name: "branch",
+        args: "",
+        description: "Change the branch the current input tracks",
+    },
+    CommandSpec {
+        name: "lock-history",
+        args: "",
+        description: "Browse the git history of flake.lock",
+    },
+    CommandSpec {
+        name: "build",
+        args: "",
+        description: "Build the configured outputs",
+    },
+    CommandSpec {
+        name: "pr",
+        args: "",
+        description: "Open a pull request with the lock update",
+    },
+    CommandSpec {
+        name: "diff",
+        args: "",
+        description: "Show package versions changed by the last nixpkgs update",
+    },
+    CommandSpec {
+        name: "graph",
+        args: "",
+        description: "Show which transitive inputs follow which",
+    },
+    CommandSpec {
+        name: "filter",
+        args: "[text]",
+        description: "Show only inputs whose name contains text, or all",
+    },
+    CommandSpec {
+        name: "sort",
+        args: "[order]",
+        description: "Order inputs by the named order, or the next one",
+    },
+    CommandSpec {
+        name: "expand",
+        args: "",
+        description: "Expand or collapse the current input's own inputs",
+    },
+    CommandSpec {
+        name: "expand-all",
+        args: "",
+        description: "Expand or collapse every input",
+    },
+    CommandSpec {
+        name: "details",
+        args: "",
+        description: "Show or hide details of the current input",
+    },
+    CommandSpec {
+        name: "select",
+        args: "<filter>",
+        description: "Add inputs matching a filter to the selection",
+    },
+    CommandSpec {
+        name: "select-all",
+        args: "",
+        description: "Select all shown inputs",
+    },
+    CommandSpec {
+        name: "invert",
+        args: "",
+        description: "Invert the selection of shown inputs",
+    },
+    CommandSpec {
+        name: "clear",
+        args: "",
+        description: "Clear the selection",
+    },
+    CommandSpec {
+        name: "undo",
+        args: "",
+        description: "Revert the last view change",
+    },
+    CommandSpec {
+        name: "redo",
+        args: "",
+        description: "Reapply the last undone view change",
+    },
+    CommandSpec {
+        name: "flakes",
+        args: "",
+        description: "Switch to another discovered flake",
+    },
+    CommandSpec {
+        name: "stats",
+        args: "",
+        description: "Show clone cache and API metrics for this session",
+    },
+    CommandSpec {
+        name: "tasks",
+        args: "",
+        description: "Show background tasks",
+    },
+    CommandSpec {
+        name: "activity",
+        args: "",
+        description: "Show the log of operations and results",
+    },
+    CommandSpec {
+        name: "help",
+        args: "",
+        description: "Show key bindings",
+    },
+    CommandSpec {
+        name: "theme",
+        args: "[name]",
+        description: "Switch to the named color theme, or the next one",
+    },
+    CommandSpec {
+        name: "quit",
+        args: "",
+        description: "Quit melt",
+    },
+];
+
+/// A parsed command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Update(Vec<String>),
+    UpdateAll,
+    Refresh,
+    History(Option<String>),
+    Select(String),
+    Clear,
+    Undo,
+    Redo,
+    Flakes,
+    Stats,
+    Theme(Option<String>),
+    Filter(String),
+    Sort(Option<String>),
+    /// A list view action taking no arguments, run as its key runs it
+    List(ListCommand),
+    Quit,
+}
+
+impl Command {
+    /// Parse a command line. Returns `Ok(None)` for blank input.
+    pub fn parse(input: &str) -> Result<Option<Self>, String> {
+        let input = input.trim();
+        let (name, args) = input
+            .split_once(char::is_whitespace)
+            .map(|(name, args)| (name, args.trim()))
+            .unwrap_or((input, ""));
+        let words = || args.split_whitespace().map(str::to_string);
+
+        let command = match name {
+            "" => return Ok(None),
+            "update" | "u" => Command::Update(words().collect()),
+            "update-all" => Command::UpdateAll,
+            "refresh" | "r" => Command::Refresh,
+            "history" | "lock" => Command::History(words().next()),
+            "select" | "s" => Command::Select(args.to_string()),
+            "clear" => Command::Clear,
+            "undo" => Command::Undo,
+            "redo" => Command::Redo,
+            "flakes" => Command::Flakes,
+            "stats" => Command::Stats,
+            "theme" => Command::Theme(words().next()),
+            "filter" => Command::Filter(args.to_string()),
+            "sort" => Command::Sort(words().next()),
+            "undo-update" => Command::List(ListCommand::UndoOperation),
+            "branch" => Command::List(ListCommand::Branch),
+            "lock-history" => Command::List(ListCommand::LockHistory),
+            "build" => Command::List(ListCommand::Build),
+            "pr" => Command::List(ListCommand::PullRequest),
+            "diff" => Command::List(ListCommand::ClosureDiff),
+            "graph" => Command::List(ListCommand::FollowsGraph),
+            "expand" => Command::List(ListCommand::Expand),
+            "expand-all" => Command::List(ListCommand::ExpandAll),
+            "details" => Command::List(ListCommand::Detail),
+            "select-all" => Command::List(ListCommand::SelectAll),
+            "invert" => Command::List(ListCommand::InvertSelection),
+            "tasks" => Command::List(ListCommand::Tasks),
+            "activity" => Command::List(ListCommand::Activity),
+            "help" => Command::List(ListCommand::Help),
+            "quit" | "q" => Command::Quit,
+            other => return Err(format!("Unknown command: {}", other)),
+        };
+        Ok(Some(command))
+    }
+}
+
+/// Candidates for the last word of `input`.
+///
+/// Returns the text before that word and the full replacements for it.
+pub fn complete(input: &str, list: &ListState) -> (String, Vec<String>) {
+    let split = input
+        .rfind(char::is_whitespace)
+        .map(|idx| idx + 1)
+        .unwrap_or(0);
+    let (base, word) = input.split_at(split);
+
+    let candidates: Vec<String> = match base.split_whitespace().next() {
+        None => COMMANDS.iter().map(|spec| spec.name.to_string()).collect(),
+        Some("update" | "u") => input_names(list, |_| true),
+        Some("history" | "lock") if base.split_whitespace().count() == 1 => {
+            input_names(list, |input| matches!(input, FlakeInput::Git(_)))
+        }
+        Some("select" | "s") => filter_candidates(word, list),
+        Some("theme") => theme::names().into_iter().map(String::from).collect(),
+        Some("sort") => SortOrder::ALL
+            .iter()
+            .map(|order| order.name().to_string())
+            .collect(),
+        Some("filter") => input_names(list, |_| true),
+        Some(_) => Vec::new(),
+    };
+
+    let matching = candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .collect();
+    (base.to_string(), matching)
+}
+
+fn input_names(list: &ListState, keep: impl Fn(&FlakeInput) -> bool) -> Vec<String> {
+    list.flake
+        .inputs
+        .iter()
+        .filter(|input| keep(input))
+        .map(|input| input.name().to_string())
+        .collect()
+}
+
+/// Field keys, or known values once a `field=` prefix has been typed
+fn filter_candidates(word: &str, list: &ListState) -> Vec<String> {
+    let Some((key, _)) = word.split_once('=') else {
+        let mut keys: Vec<String> = FilterField::ALL
+            .iter()
+            .map(|field| format!("{}=", field.key()))
+            .collect();
+        keys.extend(input_names(list, |_| true));
+        return keys;
+    };
+
+    let values: Vec<String> = match key {
+        "name" => input_names(list, |_| true),
+        "type" => ["git", "path", "unsupported"].map(String::from).to_vec(),
+        "forge" => {
+            let mut forges: Vec<String> = list
+                .flake
+                .inputs
+                .iter()
+                .filter_map(|input| match input {
+                    FlakeInput::Git(git) => Some(git.repo().forge_name().to_string()),
+                    _ => None,
+                })
+                .collect();
+            forges.sort();
+            forges.dedup();
+            forges
+        }
+        "status" => ["ok", "behind", "error", "unknown"]
+            .map(String::from)
+            .to_vec(),
+        _ => Vec::new(),
+    };
+    values
+        .into_iter()
+        .map(|value| format!("{}={}", key, value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{FlakeData, PathInput};
+    use std::path::PathBuf;
+
+    fn list(names: &[&str]) -> ListState {
+        ListState::new(FlakeData {
+            path: PathBuf::from("/tmp/flake"),
+            warnings: Vec::new(),
+            input_trees: Vec::new(),
+            inputs: names
+                .iter()
+                .map(|name| {
+                    FlakeInput::Path(PathInput {
+                        name: name.to_string(),
+                        flake: true,
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn parses_commands_and_aliases() {
+        assert_eq!(Command::parse("  "), Ok(None));
+        assert_eq!(
+            Command::parse("update nixpkgs  home-manager"),
+            Ok(Some(Command::Update(vec![
+                "nixpkgs".to_string(),
+                "home-manager".to_string()
+            ])))
+        );
+        assert_eq!(
+            Command::parse("s forge=github status=behind"),
+            Ok(Some(Command::Select(
+                "forge=github status=behind".to_string()
+            )))
+        );
+        assert_eq!(Command::parse("history"), Ok(Some(Command::History(None))));
+        assert_eq!(Command::parse("stats"), Ok(Some(Command::Stats)));
+        assert_eq!(
+            Command::parse("lock nixpkgs"),
+            Ok(Some(Command::History(Some("nixpkgs".to_string()))))
+        );
+        assert_eq!(
+            Command::parse("pr"),
+            Ok(Some(Command::List(ListCommand::PullRequest)))
+        );
+        assert_eq!(
+            Command::parse("sort most-behind"),
+            Ok(Some(Command::Sort(Some("most-behind".to_string()))))
+        );
+        assert_eq!(
+            Command::parse("filter nix"),
+            Ok(Some(Command::Filter("nix".to_string())))
+        );
+        assert_eq!(
+            Command::parse("theme catppuccin-latte"),
+            Ok(Some(Command::Theme(Some("catppuccin-latte".to_string()))))
+        );
+        assert!(Command::parse("frobnicate").is_err());
+    }
+
+    #[test]
+    fn every_offered_command_parses() {
+        for spec in COMMANDS {
+            assert!(
+                matches!(Command::parse(spec.name), Ok(Some(_))),
+                "{} does not parse",
+                spec.name
+            );
+        }
+    }
+
+    #[test]
+    fn completes_command_names() {
+        let (base, candidates) = complete("up", &list(&[]));
+        assert_eq!(base, "");
+        assert_eq!(candidates, vec!["update", "update-all"]);
+    }
+
+    #[test]
+    fn completes_input_names_and_filter_fields() {
+        let list = list(&["nixpkgs", "nix-darwin", "flake-utils"]);
+
+        let (base, candidates) = complete("update nixpkgs nix", &list);
+        assert_eq!(base, "update nixpkgs ");
+        assert_eq!(candidates, vec!["nixpkgs", "nix-darwin"]);
+
+        let (_, candidates) = complete("select st", &list);
+        assert_eq!(candidates, vec!["status="]);
+
+        let (_, candidates) = complete("select status=b", &list);
+        assert_eq!(candidates, vec!["status=behind"]);
+    }
+}
@@ -2,12 +2,23 @@
 //!
 //! This module contains the input handling logic for different application states.
 
-use crossterm::event::{KeyCode, KeyEvent};
+use std::path::PathBuf;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::event::KeyEventExt;
-use crate::model::{FlakeInput, InputName, LockUrl};
+use crate::model::{FlakeInput, InputFilter, InputName, LockUrl, SortOrder};
 
-use super::state::{AppState, ChangelogState, ListMode, ListState, StateKind};
+use super::command::{self, Command};
+use super::keymap::{
+    self, ActivityCommand, ChangelogCommand, ConfirmCommand, HelpCommand, ListCommand, PaneCommand,
+};
+use super::scheduler::{Task, TaskId};
+use super::state::{
+    AppState, BranchPickerState, BuildState, ChangelogState, ChangelogView, ClosureDiffState,
+    CommandLine, FlakePickerState, FollowsGraphState, ListMode, ListState, LockHistoryState,
+    StateKind, TasksPaneState, TemplatePickerMode, TemplatePickerState,
+};
 
 /// Actions that can result from handling input
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -22,19 +33,133 @@ pub enum Action {
     UpdateSelected(Vec<InputName>),
     /// Update all inputs
     UpdateAll,
+    /// Restore flake.lock from before the last update or lock
+    UndoLastOperation,
+    /// Choose a branch for a git input to track
+    OpenBranchPicker { input: crate::model::GitInput },
+    /// Close the branch picker and return to the list
+    CloseBranchPicker,
+    /// Make the input track `reference` and re-lock it
+    ChangeRef {
+        input: crate::model::GitInput,
+        reference: String,
+    },
+    /// Show the package changes of the last nixpkgs update
+    OpenClosureDiff,
+    /// Close the package changes and return to the list
+    CloseClosureDiff,
+    /// Build the configured outputs
+    OpenBuild,
+    /// Build the outputs again after the last builds finished
+    Rebuild,
+    /// Close the build view and return to the list
+    CloseBuild,
+    /// Open the graph of which transitive inputs follow which
+    OpenFollowsGraph,
+    /// Close the follows graph and return to the list
+    CloseFollowsGraph,
+    /// Open the git history of flake.lock
+    OpenLockHistory,
+    /// Close the flake.lock history and return to the list
+    CloseLockHistory,
+    /// Restore flake.lock as committed in `sha`
+    RestoreLock { sha: String },
+    /// Ask to confirm pushing the lock update and opening a pull request
+    OpenPullRequest,
+    /// Push the lock update to `branch` and open a pull request for it
+    SubmitPullRequest { branch: String },
     /// Refresh flake data
     Refresh,
     /// Open commit history for a validated git input
     OpenChangelog { input: crate::model::GitInput },
     /// Close commit history and return to list
     CloseChangelog,
+    /// Fetch the upstream tags of the input shown in commit history
+    LoadTags { input: crate::model::GitInput },
+    /// Fetch the full commit `sha` of the input shown in commit history
+    LoadCommitDetail {
+        input: crate::model::GitInput,
+        sha: String,
+    },
+    /// Fetch line statistics from `base` to `head` of the input shown in
+    /// commit history, limited to `paths` when any are given
+    LoadDiffStats {
+        input: crate::model::GitInput,
+        base: String,
+        head: String,
+        paths: Vec<String>,
+    },
+    /// Fetch the release published for `tag` of the input shown in commit
+    /// history
+    LoadRelease {
+        input: crate::model::GitInput,
+        tag: String,
+    },
+    /// Ask the forge which pull request merged commit `sha` of the input
+    /// shown in commit history and open it in the web browser
+    OpenCommitPullRequest {
+        input: crate::model::GitInput,
+        sha: String,
+    },
     /// Confirm lock to commit
     ConfirmLock {
         input_name: InputName,
         lock_url: LockUrl,
     },
+    /// Open the picker for flakes discovered in the repository
+    OpenFlakePicker,
+    /// Switch to the flake at the given path
+    SelectFlake(PathBuf),
+    /// Show the next flake tab, or the previous one when `back`
+    SwitchTab { back: bool },
+    /// Close the flake picker and return to the list
+    CloseFlakePicker,
+    /// Open the template picker to create a flake at the current path
+    OpenTemplatePicker,
+    /// Initialize a flake from the given template reference
+    InitTemplate(String),
+    /// Open the tasks pane over the current view
+    OpenTasks,
+    /// Close the tasks pane
+    CloseTasks,
+    /// Cancel a queued or running background task
+    CancelTask(TaskId),
+    /// Open the activity log over the current view
+    OpenActivity,
+    /// Close the activity log
+    CloseActivity,
+    /// Open the cache metrics pane over the current view
+    OpenStats,
+    /// Close the cache metrics pane
+    CloseStats,
+    /// Open the key bindings overlay over the current view
+    OpenHelp,
+    /// Close the key bindings overlay
+    CloseHelp,
+    /// Draw the UI in the named theme, or the next installed one, and
+    /// save the choice to the config file
+    ChangeTheme(Option<String>),
+    /// Open a link in the web browser
+    OpenUrl(String),
     /// Show warning message
     ShowWarning(String),
+    /// Show a short-lived success message
+    ShowSuccess(String),
+}
+
+impl Action {
+    /// Whether running the action rewrites `flake.lock` or `flake.nix`
+    pub fn rewrites_flake(&self) -> bool {
+        matches!(
+            self,
+            Action::UpdateSelected(_)
+                | Action::UpdateAll
+                | Action::UndoLastOperation
+                | Action::ChangeRef { .. }
+                | Action::RestoreLock { .. }
+                | Action::ConfirmLock { .. }
+        )
+    }
 }
 
 pub fn handle_key(state: &mut AppState, key: KeyEvent) -> Action {
@@ -42,11 +167,21 @@ pub fn handle_key(state: &mut AppState, key: KeyEvent) -> Action {
         StateKind::Loading | StateKind::LoadingChangelog => {
             if key.is_quit() {
                 Action::CancelAndQuit
+            } else if key.code == KeyCode::Char('T') {
+                Action::OpenTasks
+            } else if key.code == KeyCode::Char('L') {
+                Action::OpenActivity
             } else {
                 Action::None
             }
         }
         StateKind::Error => Action::Quit,
+        StateKind::NoFlake => match key.code {
+            KeyCode::Char('i') => Action::OpenTemplatePicker,
+            KeyCode::Char('r') => Action::Refresh,
+            _ if key.is_quit() => Action::Quit,
+            _ => Action::None,
+        },
         StateKind::List => {
             if let AppState::List(list) = state {
                 handle_list_key(list, key)
@@ -61,6 +196,55 @@ pub fn handle_key(state: &mut AppState, key: KeyEvent) -> Action {
                 Action::None
             }
         }
+        StateKind::FlakePicker => {
+            if let AppState::FlakePicker(picker) = state {
+                handle_flake_picker_key(picker, key)
+            } else {
+                Action::None
+            }
+        }
+        StateKind::TemplatePicker => {
+            if let AppState::TemplatePicker(picker) = state {
+                handle_template_picker_key(picker, key)
+            } else {
+                Action::None
+            }
+        }
+        StateKind::BranchPicker => {
+            if let AppState::BranchPicker(picker) = state {
+                handle_branch_picker_key(picker.as_mut(), key)
+            } else {
+                Action::None
+            }
+        }
+        StateKind::ClosureDiff => {
+            if let AppState::ClosureDiff(view) = state {
+                handle_closure_diff_key(view.as_mut(), key)
+            } else {
+                Action::None
+            }
+        }
+        StateKind::Build => {
+            if let AppState::Build(build) = state {
+                handle_build_key(build.as_mut(), key)
+            } else {
+                Action::None
+            }
+        }
+        StateKind::FollowsGraph => {
+            if let AppState::FollowsGraph(graph) = state {
+                handle_follows_graph_key(graph.as_mut(), key)
+            } else {
+                Action::None
+            }
+        }
+        StateKind::LockHistory => {
+            if let AppState::LockHistory(history) = state {
+                handle_lock_history_key(history.as_mut(), key)
+            } else {
+                Action::None
+            }
+        }
         StateKind::Quitting => Action::None,
     }
 }
@@ -68,93 +252,610 @@ pub fn handle_key(state: &mut AppState, key: KeyEvent) -> Action {
 /// Handle key events in list view
 fn handle_list_key(list: &mut ListState, key: KeyEvent) -> Action {
     let input_count = list.input_count();
-    let has_selection = list.has_selection();
     let is_busy = list.mode.is_busy();
 
+    if list.command_line.is_some() {
+        return handle_command_line_key(list, key);
+    }
+    if list.search.as_ref().is_some_and(|search| search.editing) {
+        return handle_search_key(list, key);
+    }
+
+    let Some(command) = keymap::lookup(keymap::LIST, &key) else {
+        return Action::None;
+    };
+    match command {
+        ListCommand::Tasks => return Action::OpenTasks,
+        ListCommand::Activity => return Action::OpenActivity,
+        ListCommand::Theme => return Action::ChangeTheme(None),
+        ListCommand::Help => return Action::OpenHelp,
+        ListCommand::Quit => return Action::Quit,
+        _ => {}
+    }
+
     if input_count == 0 {
-        if key.is_quit() {
+        if command == ListCommand::Back {
             return Action::Quit;
         }
-        return Action::None;
+        if is_busy {
+            return Action::None;
+        }
+        return match command {
+            ListCommand::FlakePicker => Action::OpenFlakePicker,
+            ListCommand::NextTab => Action::SwitchTab { back: false },
+            ListCommand::PreviousTab => Action::SwitchTab { back: true },
+            ListCommand::Refresh => {
+                list.mode = ListMode::Refreshing;
+                Action::Refresh
+            }
+            ListCommand::CommandLine => {
+                list.command_line = Some(CommandLine::default());
+                Action::None
+            }
+            _ => Action::None,
+        };
     }
 
-    match key.code {
-        KeyCode::Char('q') | KeyCode::Esc => {
-            if has_selection {
+    list_command(list, command)
+}
+
+/// Run `command` on a list with inputs, as its key would
+fn list_command(list: &mut ListState, command: ListCommand) -> Action {
+    let has_selection = list.has_selection();
+    let is_busy = list.mode.is_busy();
+
+    match command {
+        ListCommand::Back => {
+            if list.detail {
+                list.detail = false;
+                Action::None
+            } else if list.search.is_some() {
+                list.clear_search();
+                Action::None
+            } else if has_selection {
                 list.clear_selection();
                 Action::None
             } else {
                 Action::Quit
             }
         }
-        KeyCode::Char('j') | KeyCode::Down => {
+        ListCommand::Detail => {
+            list.detail = !list.detail;
+            Action::None
+        }
+        ListCommand::Search => {
+            list.start_search();
+            Action::None
+        }
+        ListCommand::Down => {
             list.cursor_down();
             Action::None
         }
-        KeyCode::Char('k') | KeyCode::Up => {
+        ListCommand::Up => {
             list.cursor_up();
             Action::None
         }
-        KeyCode::Char(' ') => {
+        ListCommand::ToggleSelection => {
             if !is_busy {
                 list.toggle_selection();
             }
             Action::None
         }
-        KeyCode::Char('u') => {
+        ListCommand::SelectAll => {
+            if !is_busy {
+                list.select_all();
+            }
+            Action::None
+        }
+        ListCommand::InvertSelection => {
+            if !is_busy {
+                list.invert_selection();
+            }
+            Action::None
+        }
+        ListCommand::UndoSelection => undo(list),
+        ListCommand::RedoSelection => redo(list),
+        ListCommand::Update => {
             if is_busy {
                 return Action::None;
             }
-            let names: Vec<InputName> = list
-                .selected
-                .iter()
-                .filter(|name| {
-                    list.flake
-                        .inputs
-                        .iter()
-                        .any(|input| input.name() == name.as_str())
-                })
-                .cloned()
-                .collect();
-
-            if !names.is_empty() {
-                list.mode = ListMode::UpdatingSelected {
-                    inputs: names.clone(),
-                };
-                Action::UpdateSelected(names)
-            } else {
-                Action::ShowWarning("No inputs selected".to_string())
-            }
+            let names: Vec<InputName> = list.selected.iter().cloned().collect();
+            update_inputs(list, names)
         }
-        KeyCode::Char('U') => {
+        ListCommand::UpdateAll => {
             if is_busy {
                 return Action::None;
             }
             list.mode = ListMode::UpdatingAll;
             Action::UpdateAll
         }
-        KeyCode::Char('r') => {
+        ListCommand::UndoOperation => {
+            if is_busy {
+                return Action::None;
+            }
+            list.mode = ListMode::Refreshing;
+            Action::UndoLastOperation
+        }
+        ListCommand::LockHistory => {
+            if is_busy {
+                return Action::None;
+            }
+            Action::OpenLockHistory
+        }
+        ListCommand::FollowsGraph => Action::OpenFollowsGraph,
+        ListCommand::ClosureDiff => Action::OpenClosureDiff,
+        ListCommand::Build => {
+            if is_busy {
+                return Action::None;
+            }
+            Action::OpenBuild
+        }
+        ListCommand::PullRequest => {
+            if is_busy {
+                return Action::None;
+            }
+            Action::OpenPullRequest
+        }
+        ListCommand::Expand => {
+            if list.toggle_expanded() {
+                Action::None
+            } else {
+                Action::ShowWarning("This input declares no inputs of its own".to_string())
+            }
+        }
+        ListCommand::ExpandAll => {
+            list.toggle_expand_all();
+            Action::None
+        }
+        ListCommand::Branch => {
+            if is_busy {
+                return Action::None;
+            }
+            let input = list
+                .current_index()
+                .and_then(|idx| list.flake.inputs.get(idx));
+            match input {
+                Some(FlakeInput::Git(input)) => Action::OpenBranchPicker {
+                    input: input.clone(),
+                },
+                _ => Action::ShowWarning("Only git inputs track a branch".to_string()),
+            }
+        }
+        ListCommand::Refresh => {
             if is_busy {
                 return Action::None;
             }
             list.mode = ListMode::Refreshing;
             Action::Refresh
         }
-        KeyCode::Char('c') => {
+        ListCommand::History => {
             if is_busy {
                 return Action::None;
             }
-            let Some(idx) = list.current_index() else {
+            match list.current_index() {
+                Some(idx) => open_history(list, idx),
+                None => Action::None,
+            }
+        }
+        ListCommand::FlakePicker => {
+            if is_busy {
+                return Action::None;
+            }
+            Action::OpenFlakePicker
+        }
+        ListCommand::NextTab | ListCommand::PreviousTab => {
+            if is_busy {
                 return Action::None;
+            }
+            Action::SwitchTab {
+                back: command == ListCommand::PreviousTab,
+            }
+        }
+        ListCommand::CommandLine => {
+            if !is_busy {
+                list.command_line = Some(CommandLine::default());
+            }
+            Action::None
+        }
+        ListCommand::Tasks
+        | ListCommand::Activity
+        | ListCommand::Theme
+        | ListCommand::Help
+        | ListCommand::Quit => Action::None,
+    }
+}
+
+/// Handle keys typed into the `/` search; the list narrows as the query
+/// changes
+fn handle_search_key(list: &mut ListState, key: KeyEvent) -> Action {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Action::Quit;
+    }
+    match key.code {
+        KeyCode::Esc => list.clear_search(),
+        KeyCode::Enter => list.finish_search(),
+        KeyCode::Backspace => list.pop_search(),
+        KeyCode::Down => list.cursor_down(),
+        KeyCode::Up => list.cursor_up(),
+        KeyCode::Char(c) => list.push_search(c),
+        _ => {}
+    }
+    Action::None
+}
+
+/// Revert the last view change
+fn undo(list: &mut ListState) -> Action {
+    if list.undo() {
+        Action::None
+    } else {
+        Action::ShowWarning("Nothing to undo".to_string())
+    }
+}
+
+/// Reapply the last undone view change
+fn redo(list: &mut ListState) -> Action {
+    if list.redo() {
+        Action::None
+    } else {
+        Action::ShowWarning("Nothing to redo".to_string())
+    }
+}
+
+/// Start updating `names`, keeping only inputs that still exist
+fn update_inputs(list: &mut ListState, names: Vec<InputName>) -> Action {
+    let names: Vec<InputName> = names
+        .into_iter()
+        .filter(|name| {
+            list.flake
+                .inputs
+                .iter()
+                .any(|input| input.name() == name.as_str())
+        })
+        .collect();
+
+    if names.is_empty() {
+        return Action::ShowWarning("No inputs selected".to_string());
+    }
+    list.mode = ListMode::UpdatingSelected {
+        inputs: names.clone(),
+    };
+    Action::UpdateSelected(names)
+}
+
+/// Open commit history for the input at `idx`
+fn open_history(list: &ListState, idx: usize) -> Action {
+    if let Some(FlakeInput::Git(input)) = list.flake.inputs.get(idx) {
+        Action::OpenChangelog {
+            input: input.clone(),
+        }
+    } else {
+        Action::ShowWarning("Commit history only available for git inputs".to_string())
+    }
+}
+
+/// Handle key events while the `:` prompt is open
+fn handle_command_line_key(list: &mut ListState, key: KeyEvent) -> Action {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Action::Quit;
+    }
+    if !matches!(key.code, KeyCode::Tab | KeyCode::BackTab) {
+        if let Some(command_line) = list.command_line.as_mut() {
+            command_line.completion = None;
+        }
+    }
+
+    match key.code {
+        KeyCode::Esc => {
+            list.command_line = None;
+            Action::None
+        }
+        KeyCode::Enter => {
+            let input = list
+                .command_line
+                .take()
+                .map(|command_line| command_line.input)
+                .unwrap_or_default();
+            run_command(list, &input)
+        }
+        KeyCode::Tab | KeyCode::BackTab => {
+            let (base, candidates) = match list.command_line.as_ref() {
+                Some(command_line) if command_line.completion.is_none() => {
+                    command::complete(&command_line.input, list)
+                }
+                _ => (String::new(), Vec::new()),
             };
-            if let Some(FlakeInput::Git(input)) = list.flake.inputs.get(idx) {
-                Action::OpenChangelog {
-                    input: input.clone(),
+            if let Some(command_line) = list.command_line.as_mut() {
+                if command_line.completion.is_some() {
+                    command_line.cycle_completion(key.code == KeyCode::BackTab);
+                } else {
+                    command_line.start_completion(base, candidates);
+                }
+            }
+            Action::None
+        }
+        KeyCode::Backspace => {
+            if let Some(command_line) = list.command_line.as_mut() {
+                if command_line.input.pop().is_none() {
+                    list.command_line = None;
+                }
+            }
+            Action::None
+        }
+        KeyCode::Char(c) => {
+            if let Some(command_line) = list.command_line.as_mut() {
+                command_line.input.push(c);
+            }
+            Action::None
+        }
+        _ => Action::None,
+    }
+}
+
+/// Run a command entered at the `:` prompt
+fn run_command(list: &mut ListState, input: &str) -> Action {
+    let command = match Command::parse(input) {
+        Ok(Some(command)) => command,
+        Ok(None) => return Action::None,
+        Err(err) => return Action::ShowWarning(err),
+    };
+
+    let is_busy = list.mode.is_busy();
+    match command {
+        Command::Quit => Action::Quit,
+        Command::Stats => Action::OpenStats,
+        Command::Theme(name) => Action::ChangeTheme(name),
+        Command::Select(expr) => match InputFilter::parse(&expr) {
+            Ok(filter) => {
+                let matched = list.select_matching(&filter);
+                if matched == 0 {
+                    Action::ShowWarning(format!("No inputs match '{}'", expr))
+                } else {
+                    Action::ShowSuccess(format!(
+                        "Selected {} matching input(s), {} selected",
+                        matched,
+                        list.selected.len()
+                    ))
+                }
+            }
+            Err(err) => Action::ShowWarning(err.to_string()),
+        },
+        Command::Clear => {
+            list.clear_selection();
+            Action::None
+        }
+        Command::Undo => undo(list),
+        Command::Redo => redo(list),
+        Command::Filter(query) => {
+            list.set_search(&query);
+            Action::None
+        }
+        Command::List(ListCommand::Tasks) => Action::OpenTasks,
+        Command::List(ListCommand::Activity) => Action::OpenActivity,
+        Command::List(ListCommand::Help) => Action::OpenHelp,
+        Command::List(_) if list.input_count() == 0 => Action::None,
+        Command::List(command) => list_command(list, command),
+        _ if is_busy => Action::ShowWarning("Wait for the current operation to finish".to_string()),
+        Command::Sort(name) => {
+            let sort = match name {
+                None => list.sort.next(),
+                Some(name) => match SortOrder::named(&name) {
+                    Some(sort) => sort,
+                    None => return Action::ShowWarning(format!("Unknown sort order: {}", name)),
+                },
+            };
+            list.set_sort(sort);
+            Action::ShowSuccess(format!("Sorted {}", sort.name()))
+        }
+        Command::Update(names) if names.is_empty() => {
+            let names: Vec<InputName> = list.selected.iter().cloned().collect();
+            update_inputs(list, names)
+        }
+        Command::Update(names) => {
+            let mut valid = Vec::new();
+            for name in names {
+                match InputName::new(name.as_str()) {
+                    Ok(input_name) if list.flake.inputs.iter().any(|i| i.name() == name) => {
+                        valid.push(input_name)
+                    }
+                    _ => return Action::ShowWarning(format!("No input named '{}'", name)),
                 }
+            }
+            update_inputs(list, valid)
+        }
+        Command::UpdateAll => {
+            list.mode = ListMode::UpdatingAll;
+            Action::UpdateAll
+        }
+        Command::Refresh => {
+            list.mode = ListMode::Refreshing;
+            Action::Refresh
+        }
+        Command::History(None) => match list.current_index() {
+            Some(idx) => open_history(list, idx),
+            None => Action::None,
+        },
+        Command::History(Some(name)) => {
+            match list.flake.inputs.iter().position(|i| i.name() == name) {
+                Some(idx) => open_history(list, idx),
+                None => Action::ShowWarning(format!("No input named '{}'", name)),
+            }
+        }
+        Command::Flakes => Action::OpenFlakePicker,
+    }
+}
+
+/// Handle key events in the flake picker
+fn handle_flake_picker_key(picker: &mut FlakePickerState, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            if picker.parent_list.is_some() {
+                Action::CloseFlakePicker
             } else {
-                Action::ShowWarning("Commit history only available for git inputs".to_string())
+                Action::Quit
             }
         }
+        KeyCode::Char('c') if key.is_quit() => Action::Quit,
+        KeyCode::Char('j') | KeyCode::Down => {
+            picker.cursor_down();
+            Action::None
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            picker.cursor_up();
+            Action::None
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => match picker.selected_flake() {
+            Some(path) if picker.current.as_ref() == Some(path) => Action::CloseFlakePicker,
+            Some(path) => Action::SelectFlake(path.clone()),
+            None => Action::None,
+        },
+        _ => Action::None,
+    }
+}
+
+/// Handle key events in the branch picker
+fn handle_branch_picker_key(picker: &mut BranchPickerState, key: KeyEvent) -> Action {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Action::Quit;
+    }
+    if picker.switching.is_some() {
+        return Action::None;
+    }
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => Action::CloseBranchPicker,
+        KeyCode::Char('T') => Action::OpenTasks,
+        KeyCode::Char('L') => Action::OpenActivity,
+        KeyCode::Char('j') | KeyCode::Down => {
+            picker.cursor_down();
+            Action::None
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            picker.cursor_up();
+            Action::None
+        }
+        KeyCode::Enter => match picker.selected_branch() {
+            Some(branch) if picker.input.reference() == Some(branch) => {
+                Action::ShowWarning(format!("Already tracking {}", branch))
+            }
+            Some(branch) => {
+                let reference = branch.to_string();
+                picker.switching = Some(reference.clone());
+                Action::ChangeRef {
+                    input: picker.input.clone(),
+                    reference,
+                }
+            }
+            None => Action::None,
+        },
+        _ => Action::None,
+    }
+}
+
+/// Handle key events in the package changes view
+fn handle_closure_diff_key(view: &mut ClosureDiffState, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+        KeyCode::Char('q') | KeyCode::Esc => Action::CloseClosureDiff,
+        KeyCode::Char('T') => Action::OpenTasks,
+        KeyCode::Char('L') => Action::OpenActivity,
+        KeyCode::Char('j') | KeyCode::Down => {
+            view.cursor_down();
+            Action::None
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            view.cursor_up();
+            Action::None
+        }
+        _ => Action::None,
+    }
+}
+
+/// Handle key events in the build view
+fn handle_build_key(build: &mut BuildState, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+        KeyCode::Char('q') | KeyCode::Esc => Action::CloseBuild,
+        KeyCode::Char('T') => Action::OpenTasks,
+        KeyCode::Char('L') => Action::OpenActivity,
+        KeyCode::Char('j') | KeyCode::Down => {
+            build.cursor_down();
+            Action::None
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            build.cursor_up();
+            Action::None
+        }
+        KeyCode::Char('r') if !build.is_running() => Action::Rebuild,
+        _ => Action::None,
+    }
+}
+
+/// Handle key events in the follows graph
+fn handle_follows_graph_key(graph: &mut FollowsGraphState, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+        KeyCode::Char('q') | KeyCode::Esc => Action::CloseFollowsGraph,
+        KeyCode::Char('T') => Action::OpenTasks,
+        KeyCode::Char('L') => Action::OpenActivity,
+        KeyCode::Char('j') | KeyCode::Down => {
+            graph.scroll_by(1);
+            Action::None
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            graph.scroll_by(-1);
+            Action::None
+        }
+        KeyCode::PageDown => {
+            graph.scroll_by(10);
+            Action::None
+        }
+        KeyCode::PageUp => {
+            graph.scroll_by(-10);
+            Action::None
+        }
+        _ => Action::None,
+    }
+}
+
+/// Handle key events in the flake.lock history
+fn handle_lock_history_key(history: &mut LockHistoryState, key: KeyEvent) -> Action {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Action::Quit;
+    }
+    if history.confirming {
+        history.confirming = false;
+        return match (key.code, history.selected_revision()) {
+            (KeyCode::Char('y') | KeyCode::Enter, Some(revision)) => Action::RestoreLock {
+                sha: revision.sha.clone(),
+            },
+            _ => Action::None,
+        };
+    }
+
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => Action::CloseLockHistory,
+        KeyCode::Char('T') => Action::OpenTasks,
+        KeyCode::Char('L') => Action::OpenActivity,
+        KeyCode::Char('j') | KeyCode::Down => {
+            history.cursor_down();
+            Action::None
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            history.cursor_up();
+            Action::None
+        }
+        KeyCode::Char('i') => {
+            if history.toggle_focus() {
+                Action::None
+            } else {
+                Action::ShowWarning("No input to focus on".to_string())
+            }
+        }
+        KeyCode::Enter => {
+            if history.selected_revision().is_some() {
+                history.confirming = true;
+            }
+            Action::None
+        }
         _ => Action::None,
     }
 }
@@ -165,29 +866,194 @@ fn handle_changelog_key(cs: &mut ChangelogState, key: KeyEvent) -> Action {
     if cs.is_confirming() {
         return handle_confirm_key(cs, key);
     }
+    if cs.has_pane() {
+        return handle_pane_key(cs, key);
+    }
 
-    match key.code {
-        KeyCode::Char('q') | KeyCode::Esc => Action::CloseChangelog,
-        KeyCode::Char('j') | KeyCode::Down => {
+    let Some(command) = keymap::lookup(keymap::CHANGELOG, &key) else {
+        return Action::None;
+    };
+    match command {
+        ChangelogCommand::Back => Action::CloseChangelog,
+        ChangelogCommand::Down => {
             cs.cursor_down();
-            Action::None
+            load_diff_stats(cs)
         }
-        KeyCode::Char('k') | KeyCode::Up => {
+        ChangelogCommand::Up => {
             cs.cursor_up();
-            Action::None
+            load_diff_stats(cs)
         }
-        KeyCode::Char(' ') => {
+        ChangelogCommand::SelectLock => {
             cs.show_confirm();
             Action::None
         }
+        ChangelogCommand::Authors => {
+            cs.toggle_view();
+            Action::None
+        }
+        ChangelogCommand::Tags => {
+            if cs.toggle_tags() {
+                Action::LoadTags {
+                    input: cs.input.clone(),
+                }
+            } else {
+                Action::None
+            }
+        }
+        ChangelogCommand::Detail => {
+            let Some(sha) = cs
+                .selected_commit()
+                .map(|idx| cs.data.commits[idx].sha.clone())
+            else {
+                return Action::None;
+            };
+            cs.open_detail(sha.clone());
+            Action::LoadCommitDetail {
+                input: cs.input.clone(),
+                sha,
+            }
+        }
+        ChangelogCommand::OpenPullRequest if cs.view == ChangelogView::Commits => {
+            match cs.data.commits.get(cs.cursor) {
+                Some(commit) => Action::OpenCommitPullRequest {
+                    input: cs.input.clone(),
+                    sha: commit.sha.clone(),
+                },
+                None => Action::None,
+            }
+        }
+        ChangelogCommand::Release => {
+            let Some(tag) = cs.selected_tag().map(|tag| tag.name.clone()) else {
+                let hint = if cs.tags.is_none() {
+                    "No tags loaded; press t to load them"
+                } else {
+                    "No tag on this commit"
+                };
+                return Action::ShowWarning(hint.to_string());
+            };
+            cs.open_release(tag.clone());
+            Action::LoadRelease {
+                input: cs.input.clone(),
+                tag,
+            }
+        }
+        ChangelogCommand::OpenPullRequest => Action::None,
+        ChangelogCommand::Tasks => Action::OpenTasks,
+        ChangelogCommand::Activity => Action::OpenActivity,
+        ChangelogCommand::Help => Action::OpenHelp,
+    }
+}
+
+/// Load line statistics up to the commit under the cursor, if still unknown
+pub fn load_diff_stats(cs: &mut ChangelogState) -> Action {
+    match cs.range_stats_to_load() {
+        Some((base, head)) => Action::LoadDiffStats {
+            input: cs.input.clone(),
+            base,
+            head,
+            paths: cs
+                .data
+                .path_filter()
+                .map(|filter| filter.paths.clone())
+                .unwrap_or_default(),
+        },
+        None => Action::None,
+    }
+}
+
+/// Handle key events while release notes or commit detail are shown over
+/// commit history
+fn handle_pane_key(cs: &mut ChangelogState, key: KeyEvent) -> Action {
+    match keymap::lookup(keymap::PANE, &key) {
+        Some(PaneCommand::Close) => cs.close_pane(),
+        Some(PaneCommand::ScrollDown) => cs.scroll_pane(1),
+        Some(PaneCommand::ScrollUp) => cs.scroll_pane(-1),
+        Some(PaneCommand::PageDown) => cs.scroll_pane(10),
+        Some(PaneCommand::PageUp) => cs.scroll_pane(-10),
+        None => {}
+    }
+    Action::None
+}
+
+/// Handle key events while the help overlay is open
+pub fn handle_help_key(scroll: &mut u16, key: KeyEvent) -> Action {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Action::CancelAndQuit;
+    }
+
+    match keymap::lookup(keymap::HELP, &key) {
+        Some(HelpCommand::Close) => Action::CloseHelp,
+        Some(HelpCommand::ScrollDown) => {
+            *scroll = scroll.saturating_add(1);
+            Action::None
+        }
+        Some(HelpCommand::ScrollUp) => {
+            *scroll = scroll.saturating_sub(1);
+            Action::None
+        }
+        None => Action::None,
+    }
+}
+
+/// Handle key events while the activity log is open
+pub fn handle_activity_key(scroll: &mut u16, key: KeyEvent) -> Action {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Action::CancelAndQuit;
+    }
+
+    match keymap::lookup(keymap::ACTIVITY, &key) {
+        Some(ActivityCommand::Close) => return Action::CloseActivity,
+        Some(ActivityCommand::ScrollDown) => *scroll = scroll.saturating_add(1),
+        Some(ActivityCommand::ScrollUp) => *scroll = scroll.saturating_sub(1),
+        Some(ActivityCommand::Top) => *scroll = 0,
+        // The log clamps this to its last page when it renders
+        Some(ActivityCommand::Bottom) => *scroll = u16::MAX,
+        None => {}
+    }
+    Action::None
+}
+
+/// Handle key events while the tasks pane is open
+pub fn handle_tasks_key(pane: &mut TasksPaneState, tasks: &[Task], key: KeyEvent) -> Action {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Action::CancelAndQuit;
+    }
+
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Char('T') | KeyCode::Esc => Action::CloseTasks,
+        KeyCode::Char('j') | KeyCode::Down => {
+            pane.cursor_down(tasks.len());
+            Action::None
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            pane.cursor_up(tasks.len());
+            Action::None
+        }
+        KeyCode::Char('x') => match tasks.get(pane.cursor) {
+            Some(task) if !task.status.is_finished() => Action::CancelTask(task.id),
+            Some(_) => Action::ShowWarning("Task already finished".to_string()),
+            None => Action::None,
+        },
+        _ => Action::None,
+    }
+}
+
+/// Handle key events while the cache metrics pane is open
+pub fn handle_stats_key(key: KeyEvent) -> Action {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Action::CancelAndQuit;
+    }
+
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter => Action::CloseStats,
         _ => Action::None,
     }
 }
 
 /// Handle key events in confirm dialog
 fn handle_confirm_key(cs: &mut ChangelogState, key: KeyEvent) -> Action {
-    match key.code {
-        KeyCode::Char('y') => {
+    match keymap::lookup(keymap::CONFIRM, &key) {
+        Some(ConfirmCommand::Lock) => {
             let Some(target) = cs.lock_target() else {
                 return Action::None;
             };
@@ -201,10 +1067,44 @@ fn handle_confirm_key(cs: &mut ChangelogState, key: KeyEvent) -> Action {
                 lock_url,
             }
         }
-        KeyCode::Char('n') | KeyCode::Esc | KeyCode::Char('q') => {
+        Some(ConfirmCommand::Cancel) => {
             cs.hide_confirm();
             Action::None
         }
+        None => Action::None,
+    }
+}
+
+/// Handle key events in the template picker
+fn handle_template_picker_key(picker: &mut TemplatePickerState, key: KeyEvent) -> Action {
+    if picker.mode != TemplatePickerMode::Browsing {
+        return if key.is_quit() {
+            Action::CancelAndQuit
+        } else {
+            Action::None
+        };
+    }
+
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => {
+            picker.cursor_down();
+            Action::None
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            picker.cursor_up();
+            Action::None
+        }
+        KeyCode::Enter => match picker.selected_template() {
+            Some(template) => {
+                let reference = template.reference.clone();
+                picker.mode = TemplatePickerMode::Initializing {
+                    reference: reference.clone(),
+                };
+                Action::InitTemplate(reference)
+            }
+            None => Action::None,
+        },
+        _ if key.is_quit() => Action::Quit,
         _ => Action::None,
     }
 }
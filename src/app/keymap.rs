@@ -0,0 +1,565 @@
+//! Key bindings of the list and commit history views
+//!
+//! The handlers look keys up in these tables, and the `?` help overlay lists
+//! the same tables, so the overlay always shows what the keys do.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A key as bindings name it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Ctrl(char),
+    Enter,
+    Esc,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Tab,
+    BackTab,
+}
+
+impl Key {
+    /// Whether `event` is this key. Plain characters don't match with Ctrl
+    /// held; Shift is part of the character itself.
+    pub fn matches(self, event: &KeyEvent) -> bool {
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        match self {
+            Key::Char(c) => event.code == KeyCode::Char(c) && !ctrl,
+            Key::Ctrl(c) => event.code == KeyCode::Char(c) && ctrl,
+            Key::Enter => event.code == KeyCode::Enter,
+            Key::Esc => event.code == KeyCode::Esc,
+            Key::Up => event.code == KeyCode::Up,
+            Key::Down => event.code == KeyCode::Down,
+            Key::PageUp => event.code == KeyCode::PageUp,
+            Key::PageDown => event.code == KeyCode::PageDown,
+            Key::Tab => event.code == KeyCode::Tab,
+            Key::BackTab => event.code == KeyCode::BackTab,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Key::Char(' ') => "space".to_string(),
+            Key::Char(c) => c.to_string(),
+            Key::Ctrl(c) => format!("ctrl+{}", c),
+            Key::Enter => "enter".to_string(),
+            Key::Esc => "esc".to_string(),
+            Key::Up => "↑".to_string(),
+            Key::Down => "↓".to_string(),
+            Key::PageUp => "pgup".to_string(),
+            Key::PageDown => "pgdn".to_string(),
+            Key::Tab => "tab".to_string(),
+            Key::BackTab => "shift+tab".to_string(),
+        }
+    }
+}
+
+/// Keys that run `command` in a view
+#[derive(Debug, Clone, Copy)]
+pub struct KeyBinding<C: 'static> {
+    pub keys: &'static [Key],
+    pub command: C,
+    pub description: &'static str,
+}
+
+impl<C> KeyBinding<C> {
+    /// The keys joined with `/`, such as `j/↓`
+    pub fn label(&self) -> String {
+        self.keys
+            .iter()
+            .map(|key| key.label())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// Command bound to `event` in `bindings`, if any
+pub fn lookup<C: Copy>(bindings: &[KeyBinding<C>], event: &KeyEvent) -> Option<C> {
+    bindings
+        .iter()
+        .find(|binding| binding.keys.iter().any(|key| key.matches(event)))
+        .map(|binding| binding.command)
+}
+
+/// What a key does in the list view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListCommand {
+    Down,
+    Up,
+    ToggleSelection,
+    SelectAll,
+    InvertSelection,
+    Search,
+    Detail,
+    Update,
+    UpdateAll,
+    UndoOperation,
+    LockHistory,
+    Branch,
+    FollowsGraph,
+    Build,
+    PullRequest,
+    ClosureDiff,
+    Expand,
+    ExpandAll,
+    History,
+    Refresh,
+    FlakePicker,
+    NextTab,
+    PreviousTab,
+    CommandLine,
+    Tasks,
+    Activity,
+    Theme,
+    Help,
+    UndoSelection,
+    RedoSelection,
+    Back,
+    Quit,
+}
+
+pub const LIST: &[KeyBinding<ListCommand>] = &[
+    KeyBinding {
+        keys: &[Key::Char('j'), Key::Down],
+        command: ListCommand::Down,
+        description: "Move down",
+    },
+    KeyBinding {
+        keys: &[Key::Char('k'), Key::Up],
+        command: ListCommand::Up,
+        description: "Move up",
+    },
+    KeyBinding {
+        keys: &[Key::Char(' ')],
+        command: ListCommand::ToggleSelection,
+        description: "Toggle selection",
+    },
+    KeyBinding {
+        keys: &[Key::Char('a')],
+        command: ListCommand::SelectAll,
+        description: "Select all shown inputs",
+    },
+    KeyBinding {
+        keys: &[Key::Char('A'), Key::Char('*')],
+        command: ListCommand::InvertSelection,
+        description: "Invert the selection of shown inputs",
+    },
+    KeyBinding {
+        keys: &[Key::Char('/')],
+        command: ListCommand::Search,
+        description: "Filter inputs by name",
+    },
+    KeyBinding {
+        keys: &[Key::Enter, Key::Char('i')],
+        command: ListCommand::Detail,
+        description: "Show or hide details of the current input",
+    },
+    KeyBinding {
+        keys: &[Key::Char('u')],
+        command: ListCommand::Update,
+        description: "Update selected inputs",
+    },
+    KeyBinding {
+        keys: &[Key::Char('U')],
+        command: ListCommand::UpdateAll,
+        description: "Update all inputs",
+    },
+    KeyBinding {
+        keys: &[Key::Char('z')],
+        command: ListCommand::UndoOperation,
+        description: "Undo the last update or lock",
+    },
+    KeyBinding {
+        keys: &[Key::Char('h')],
+        command: ListCommand::LockHistory,
+        description: "Browse the git history of flake.lock",
+    },
+    KeyBinding {
+        keys: &[Key::Char('b')],
+        command: ListCommand::Branch,
+        description: "Change the branch the current input tracks",
+    },
+    KeyBinding {
+        keys: &[Key::Char('g')],
+        command: ListCommand::FollowsGraph,
+        description: "Show which transitive inputs follow which",
+    },
+    KeyBinding {
+        keys: &[Key::Char('B')],
+        command: ListCommand::Build,
+        description: "Build the configured outputs",
+    },
+    KeyBinding {
+        keys: &[Key::Char('P')],
+        command: ListCommand::PullRequest,
+        description: "Open a pull request with the lock update",
+    },
+    KeyBinding {
+        keys: &[Key::Char('d')],
+        command: ListCommand::ClosureDiff,
+        description: "Show package versions changed by the last nixpkgs update",
+    },
+    KeyBinding {
+        keys: &[Key::Char('t')],
+        command: ListCommand::Expand,
+        description: "Expand or collapse the current input's own inputs",
+    },
+    KeyBinding {
+        keys: &[Key::Char('e')],
+        command: ListCommand::ExpandAll,
+        description: "Expand or collapse every input",
+    },
+    KeyBinding {
+        keys: &[Key::Char('c')],
+        command: ListCommand::History,
+        description: "View commit history for current input",
+    },
+    KeyBinding {
+        keys: &[Key::Char('r')],
+        command: ListCommand::Refresh,
+        description: "Refresh flake metadata",
+    },
+    KeyBinding {
+        keys: &[Key::Char('f')],
+        command: ListCommand::FlakePicker,
+        description: "Switch to another discovered flake",
+    },
+    KeyBinding {
+        keys: &[Key::Tab],
+        command: ListCommand::NextTab,
+        description: "Show the next flake given on the command line",
+    },
+    KeyBinding {
+        keys: &[Key::BackTab],
+        command: ListCommand::PreviousTab,
+        description: "Show the previous flake given on the command line",
+    },
+    KeyBinding {
+        keys: &[Key::Char(':')],
+        command: ListCommand::CommandLine,
+        description: "Open the command line",
+    },
+    KeyBinding {
+        keys: &[Key::Char('T')],
+        command: ListCommand::Tasks,
+        description: "Show background tasks",
+    },
+    KeyBinding {
+        keys: &[Key::Char('L')],
+        command: ListCommand::Activity,
+        description: "Show the activity log",
+    },
+    KeyBinding {
+        keys: &[Key::Char('C')],
+        command: ListCommand::Theme,
+        description: "Switch to the next color theme",
+    },
+    KeyBinding {
+        keys: &[Key::Char('?')],
+        command: ListCommand::Help,
+        description: "Show key bindings",
+    },
+    KeyBinding {
+        keys: &[Key::Ctrl('z')],
+        command: ListCommand::UndoSelection,
+        description: "Undo the last view change",
+    },
+    KeyBinding {
+        keys: &[Key::Ctrl('y')],
+        command: ListCommand::RedoSelection,
+        description: "Redo the last undone change",
+    },
+    KeyBinding {
+        keys: &[Key::Char('q'), Key::Esc],
+        command: ListCommand::Back,
+        description: "Close details, clear the filter or selection, or quit",
+    },
+    KeyBinding {
+        keys: &[Key::Ctrl('c')],
+        command: ListCommand::Quit,
+        description: "Quit",
+    },
+];
+
+/// What a key does in the commit history view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangelogCommand {
+    Down,
+    Up,
+    Detail,
+    SelectLock,
+    Authors,
+    Tags,
+    Release,
+    OpenPullRequest,
+    Tasks,
+    Activity,
+    Help,
+    Back,
+}
+
+pub const CHANGELOG: &[KeyBinding<ChangelogCommand>] = &[
+    KeyBinding {
+        keys: &[Key::Char('j'), Key::Down],
+        command: ChangelogCommand::Down,
+        description: "Move down",
+    },
+    KeyBinding {
+        keys: &[Key::Char('k'), Key::Up],
+        command: ChangelogCommand::Up,
+        description: "Move up",
+    },
+    KeyBinding {
+        keys: &[Key::Enter],
+        command: ChangelogCommand::Detail,
+        description: "Show the full message and changed files of the commit",
+    },
+    KeyBinding {
+        keys: &[Key::Char(' ')],
+        command: ChangelogCommand::SelectLock,
+        description: "Select commit for locking",
+    },
+    KeyBinding {
+        keys: &[Key::Char('a')],
+        command: ChangelogCommand::Authors,
+        description: "Toggle grouping by author",
+    },
+    KeyBinding {
+        keys: &[Key::Char('t')],
+        command: ChangelogCommand::Tags,
+        description: "Toggle upstream tags newer than the locked revision",
+    },
+    KeyBinding {
+        keys: &[Key::Char('r')],
+        command: ChangelogCommand::Release,
+        description: "Show release notes for the tag on the current commit",
+    },
+    KeyBinding {
+        keys: &[Key::Char('o')],
+        command: ChangelogCommand::OpenPullRequest,
+        description: "Open the pull request that merged the commit, or else the commit",
+    },
+    KeyBinding {
+        keys: &[Key::Char('T')],
+        command: ChangelogCommand::Tasks,
+        description: "Show background tasks",
+    },
+    KeyBinding {
+        keys: &[Key::Char('L')],
+        command: ChangelogCommand::Activity,
+        description: "Show the activity log",
+    },
+    KeyBinding {
+        keys: &[Key::Char('?')],
+        command: ChangelogCommand::Help,
+        description: "Show key bindings",
+    },
+    KeyBinding {
+        keys: &[Key::Char('q'), Key::Esc],
+        command: ChangelogCommand::Back,
+        description: "Back to list",
+    },
+];
+
+/// What a key does while commit detail or release notes are shown over
+/// commit history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaneCommand {
+    ScrollDown,
+    ScrollUp,
+    PageDown,
+    PageUp,
+    Close,
+}
+
+pub const PANE: &[KeyBinding<PaneCommand>] = &[
+    KeyBinding {
+        keys: &[Key::Char('j'), Key::Down],
+        command: PaneCommand::ScrollDown,
+        description: "Scroll down",
+    },
+    KeyBinding {
+        keys: &[Key::Char('k'), Key::Up],
+        command: PaneCommand::ScrollUp,
+        description: "Scroll up",
+    },
+    KeyBinding {
+        keys: &[Key::PageDown],
+        command: PaneCommand::PageDown,
+        description: "Scroll down a page",
+    },
+    KeyBinding {
+        keys: &[Key::PageUp],
+        command: PaneCommand::PageUp,
+        description: "Scroll up a page",
+    },
+    KeyBinding {
+        keys: &[Key::Char('q'), Key::Char('r'), Key::Esc, Key::Enter],
+        command: PaneCommand::Close,
+        description: "Close the pane",
+    },
+];
+
+/// What a key does while asking to confirm a lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmCommand {
+    Lock,
+    Cancel,
+}
+
+pub const CONFIRM: &[KeyBinding<ConfirmCommand>] = &[
+    KeyBinding {
+        keys: &[Key::Char('y')],
+        command: ConfirmCommand::Lock,
+        description: "Lock the input to the selected commit",
+    },
+    KeyBinding {
+        keys: &[Key::Char('n'), Key::Esc, Key::Char('q')],
+        command: ConfirmCommand::Cancel,
+        description: "Cancel the lock",
+    },
+];
+
+/// What a key does in the help overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpCommand {
+    ScrollDown,
+    ScrollUp,
+    Close,
+}
+
+pub const HELP: &[KeyBinding<HelpCommand>] = &[
+    KeyBinding {
+        keys: &[Key::Char('j'), Key::Down],
+        command: HelpCommand::ScrollDown,
+        description: "Scroll down",
+    },
+    KeyBinding {
+        keys: &[Key::Char('k'), Key::Up],
+        command: HelpCommand::ScrollUp,
+        description: "Scroll up",
+    },
+    KeyBinding {
+        keys: &[Key::Char('?'), Key::Char('q'), Key::Esc],
+        command: HelpCommand::Close,
+        description: "Close help",
+    },
+];
+
+/// What a key does in the activity log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityCommand {
+    ScrollDown,
+    ScrollUp,
+    Top,
+    Bottom,
+    Close,
+}
+
+pub const ACTIVITY: &[KeyBinding<ActivityCommand>] = &[
+    KeyBinding {
+        keys: &[Key::Char('j'), Key::Down],
+        command: ActivityCommand::ScrollDown,
+        description: "Scroll down",
+    },
+    KeyBinding {
+        keys: &[Key::Char('k'), Key::Up],
+        command: ActivityCommand::ScrollUp,
+        description: "Scroll up",
+    },
+    KeyBinding {
+        keys: &[Key::Char('g')],
+        command: ActivityCommand::Top,
+        description: "Jump to the oldest entry",
+    },
+    KeyBinding {
+        keys: &[Key::Char('G')],
+        command: ActivityCommand::Bottom,
+        description: "Jump to the newest entry",
+    },
+    KeyBinding {
+        keys: &[Key::Char('L'), Key::Char('q'), Key::Esc],
+        command: ActivityCommand::Close,
+        description: "Close the activity log",
+    },
+];
+
+/// One view's bindings as the help overlay lists them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpSection {
+    pub title: &'static str,
+    /// Key labels and what they do
+    pub rows: Vec<(String, &'static str)>,
+}
+
+/// Every keymap, in the order the help overlay lists them
+pub fn help_sections() -> Vec<HelpSection> {
+    fn section<C>(title: &'static str, bindings: &[KeyBinding<C>]) -> HelpSection {
+        HelpSection {
+            title,
+            rows: bindings
+                .iter()
+                .map(|binding| (binding.label(), binding.description))
+                .collect(),
+        }
+    }
+
+    vec![
+        section("List", LIST),
+        section("Commit history", CHANGELOG),
+        section("Commit detail and release notes", PANE),
+        section("Lock confirmation", CONFIRM),
+        section("Activity log", ACTIVITY),
+        section("Help", HELP),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn lookup_tells_ctrl_from_plain_keys() {
+        assert_eq!(
+            lookup(LIST, &key(KeyCode::Char('z'), KeyModifiers::NONE)),
+            Some(ListCommand::UndoOperation)
+        );
+        assert_eq!(
+            lookup(LIST, &key(KeyCode::Char('z'), KeyModifiers::CONTROL)),
+            Some(ListCommand::UndoSelection)
+        );
+        assert_eq!(
+            lookup(LIST, &key(KeyCode::Char('A'), KeyModifiers::SHIFT)),
+            Some(ListCommand::InvertSelection)
+        );
+        assert_eq!(
+            lookup(LIST, &key(KeyCode::Char('x'), KeyModifiers::NONE)),
+            None
+        );
+    }
+
+    #[test]
+    fn no_key_is_bound_twice_in_a_view() {
+        fn check<C>(bindings: &[KeyBinding<C>]) {
+            let keys: Vec<Key> = bindings
+                .iter()
+                .flat_map(|b| b.keys.iter().copied())
+                .collect();
+            for (idx, key) in keys.iter().enumerate() {
+                assert!(!keys[idx + 1..].contains(key), "{:?} bound twice", key);
+            }
+        }
+        check(LIST);
+        check(CHANGELOG);
+        check(PANE);
+        check(CONFIRM);
+        check(HELP);
+        assert_eq!(help_sections()[0].rows[0], ("j/↓".to_string(), "Move down"));
+    }
+}
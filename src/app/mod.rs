@@ -4,68 +4,288 @@
 //! - `App`: The main application struct
 //! - `state`: State types for different views
 //! - `handler`: Input event handling
+//! - `command`: Commands for the `:` command line
+//! - `scheduler`: Background task scheduling
 
+pub mod command;
 pub mod handler;
+pub mod keymap;
+pub mod scheduler;
 pub mod state;
 
-use std::path::PathBuf;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
+use chrono::Utc;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
-use crate::error::AppResult;
+use crate::config::{self, Config, ServiceConfig};
+use crate::error::{AppError, AppResult, GitError};
 use crate::event::poll_key;
-use crate::model::{FlakeInput, GitInput, InputName, StatusMessage, UpdateStatus};
-use crate::service::{GitService, NixService};
+use crate::model::{
+    ActivityLog, ChangelogData, ClosureDiff, Commit, Digest, FlakeData, FlakeInput, GitInput,
+    GitRepo, InputName, SortOrder, StatusLevel, StatusMessage, Toasts, UpdateStatus,
+};
+use crate::service::{discover_flakes, discovery_root, ChangelogProgress, GitService, NixService};
 use crate::tui::Tui;
+use crate::ui::columns::{ListColumn, ListColumns};
 use crate::ui::render;
+use crate::ui::theme::{self, Theme};
+use crate::util::browser;
+use crate::util::format::week_start_of;
 
 pub use handler::Action;
-pub use state::{AppState, ChangelogLoadedData, ChangelogState, ListMode, ListState, TaskResult};
+pub use scheduler::{Job, JobOutcome, Scheduler, Task, TaskContext, TaskId, TaskStatus};
+pub use state::{
+    Announcements, AppState, BranchPickerState, BuildState, BuildStatus, ChangelogLoadedData,
+    ChangelogState, ClosureDiffState, FlakePickerState, FlakeTab, FlakeTabs, FollowsGraphState,
+    ListMode, ListState, LockHistoryState, TaskResult, TasksPaneState, TemplatePickerMode,
+    TemplatePickerState,
+};
+
+/// Template source offered by the template picker (the `templates` registry entry)
+const TEMPLATE_SOURCE: &str = "templates";
+
+/// Times a changelog load is retried after a network error
+const CHANGELOG_RETRIES: u32 = 2;
+
+/// GitHub inputs at which checks without a token are likely to run into the
+/// unauthenticated rate limit (60 requests per hour)
+const TOKEN_HINT_INPUTS: usize = 10;
+
+/// What the app does when it starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Startup {
+    /// Open the flake at the given path
+    OpenFlake,
+    /// Initialize a flake from a template, picking one interactively if `None`
+    InitTemplate(Option<String>),
+}
 
 /// Main application struct
 pub struct App {
     /// Path to the flake
     flake_path: PathBuf,
+    /// Directory flakes were discovered under
+    flake_root: PathBuf,
+    /// Flakes discovered under `flake_root`
+    discovered_flakes: Vec<PathBuf>,
     /// Current state
     state: AppState,
+    /// Startup behavior
+    startup: Startup,
     /// Nix service
     nix: NixService,
     /// Git service
     git: GitService,
     /// Cancellation token for async operations
     cancel_token: CancellationToken,
-    /// Status message to display
-    status_message: Option<StatusMessage>,
+    /// Status messages to display, newest last
+    toasts: Toasts,
     /// Tick count for animations
     tick_count: u64,
     /// Channel for receiving task results
     task_rx: mpsc::UnboundedReceiver<TaskResult>,
-    /// Channel for sending task results
-    task_tx: mpsc::UnboundedSender<TaskResult>,
+    /// Runs background tasks, including watch-mode checks
+    scheduler: Scheduler,
+    /// Columns shown in the list view
+    columns: ListColumns,
+    /// Colors the UI is drawn with
+    theme: Theme,
+    /// Order of the list view
+    sort: SortOrder,
+    /// Whether to suggest a GitHub token; cleared once the hint was shown
+    token_hint: bool,
+    /// Reload the flake on this interval while the list view is idle
+    auto_refresh: Option<Duration>,
+    /// When the flake was last (re)loaded, successfully or not
+    last_load: Instant,
+    /// Tasks pane, when open over the current view
+    tasks_pane: Option<TasksPaneState>,
+    /// Whether the cache metrics pane is open over the current view
+    stats_open: bool,
+    /// Lines the key bindings overlay is scrolled by, while it is open
+    help_scroll: Option<u16>,
+    /// Operations run this session and how they went
+    activity: ActivityLog,
+    /// Lines the activity log is scrolled by, while it is open
+    activity_scroll: Option<u16>,
+    /// Config file watched for changes, if hot-reload is enabled
+    config_file: Option<PathBuf>,
+    /// Modification time of `config_file` when it was last read
+    config_modified: Option<SystemTime>,
+    /// Directory weekly digests are written to, if enabled
+    digest_dir: Option<PathBuf>,
+    /// Activity collected for the current digest period
+    digest: Digest,
+    /// State changes announced in accessible mode; `None` when it is off
+    announcements: Option<Announcements>,
+    /// Status message text last announced, so each message is announced once
+    announced_status: Option<String>,
+    /// Installable and store path of the closure built before the running
+    /// update, to compare with the one after it
+    closure_before: Option<(String, String)>,
+    /// Package changes of the last nixpkgs update
+    closure_diff: Option<ClosureDiff>,
+    /// Action waiting for the user to confirm it, because it would write
+    /// over uncommitted changes to flake.nix or flake.lock or publish a
+    /// pull request
+    held_action: Option<Action>,
+    /// Commit histories viewed since the flake was opened, by input, to
+    /// describe pull requests with
+    changelogs: HashMap<String, ChangelogData>,
+    /// Flakes opened side by side, when more than one was given
+    tabs: FlakeTabs,
 }
 
 impl App {
     pub fn new(flake_path: PathBuf) -> Self {
+        Self::new_with_config(flake_path, ServiceConfig::default())
+    }
+
+    pub fn new_with_config(flake_path: PathBuf, config: ServiceConfig) -> Self {
         let cancel_token = CancellationToken::new();
         let (task_tx, task_rx) = mpsc::unbounded_channel();
+        let scheduler = Scheduler::new(task_tx, cancel_token.child_token());
         Self {
+            flake_root: discovery_root(&flake_path),
+            discovered_flakes: Vec::new(),
             flake_path,
             state: AppState::Loading,
-            nix: NixService::new(cancel_token.clone()),
-            git: GitService::new(cancel_token.clone()),
-            cancel_token,
-            status_message: None,
+            startup: Startup::OpenFlake,
+            nix: NixService::new_with_config(cancel_token.clone(), config.clone()),
+            git: GitService::new_with_config(cancel_token.clone(), config),
+            toasts: Toasts::default(),
             tick_count: 0,
+            cancel_token,
             task_rx,
-            task_tx,
+            scheduler,
+            columns: ListColumns::default(),
+            theme: Theme::default(),
+            sort: SortOrder::default(),
+            token_hint: true,
+            auto_refresh: None,
+            last_load: Instant::now(),
+            tasks_pane: None,
+            stats_open: false,
+            help_scroll: None,
+            activity: ActivityLog::default(),
+            activity_scroll: None,
+            config_file: None,
+            config_modified: None,
+            digest_dir: None,
+            digest: Digest::new(week_start_of(Utc::now())),
+            announcements: None,
+            announced_status: None,
+            closure_before: None,
+            closure_diff: None,
+            held_action: None,
+            changelogs: HashMap::new(),
+            tabs: FlakeTabs::default(),
+        }
+    }
+
+    /// Initialize a new flake from a template before opening it.
+    ///
+    /// When `template` is `None` the user picks one from the registry templates.
+    pub fn with_template(mut self, template: Option<String>) -> Self {
+        self.startup = Startup::InitTemplate(template);
+        self
+    }
+
+    /// Re-run update checks on `interval` while the list view is open
+    pub fn with_watch(mut self, interval: Option<Duration>) -> Self {
+        self.scheduler.set_periodic(interval);
+        self
+    }
+
+    /// Show `columns` in the list view
+    pub fn with_columns(mut self, columns: ListColumns) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Draw the UI in `theme`
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Order the list view by `sort`
+    pub fn with_sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Suggest setting a GitHub token for flakes with many GitHub inputs
+    pub fn with_token_hint(mut self, enabled: bool) -> Self {
+        self.token_hint = enabled;
+        self
+    }
+
+    /// Reload the flake and re-check updates on `interval` while the list
+    /// view is idle
+    pub fn with_auto_refresh(mut self, interval: Option<Duration>) -> Self {
+        self.auto_refresh = interval;
+        self
+    }
+
+    /// Re-read the config file at `path` whenever it changes
+    pub fn with_config_reload(mut self, path: PathBuf) -> Self {
+        self.config_modified = modified_time(&path);
+        self.config_file = Some(path);
+        self
+    }
+
+    /// Write a weekly markdown digest of update activity into `dir`,
+    /// carrying on with what an earlier run recorded this week
+    pub fn with_digest(mut self, dir: Option<PathBuf>) -> Self {
+        if let Some(saved) = dir
+            .as_ref()
+            .and_then(|dir| fs::read_to_string(dir.join(self.digest.state_file_name())).ok())
+            .and_then(|json| Digest::from_json(&json))
+            .filter(|saved| saved.started == self.digest.started)
+        {
+            self.digest = saved;
         }
+        self.digest_dir = dir;
+        self
+    }
+
+    /// Open `paths` too, each in a tab of its own after the first flake.
+    /// Hidden tabs load and check for updates in the background.
+    pub fn with_extra_flakes(mut self, paths: Vec<PathBuf>) -> Self {
+        let mut all = vec![self.flake_path.clone()];
+        all.extend(paths);
+        self.tabs = FlakeTabs::new(all);
+        self
+    }
+
+    /// Draw for screen readers: no borders or animation, with state changes
+    /// announced as plain lines below the list
+    pub fn with_accessible(mut self, enabled: bool) -> Self {
+        self.announcements = enabled.then(Announcements::default);
+        self
     }
 
     pub async fn run(&mut self, tui: &mut Tui) -> AppResult<()> {
-        self.spawn_load_flake();
+        self.spawn_check_nix_version();
+        match self.startup.clone() {
+            Startup::OpenFlake => self.open_initial_flake(),
+            Startup::InitTemplate(Some(template)) => {
+                self.spawn_init_template(template);
+            }
+            Startup::InitTemplate(None) => {
+                self.state =
+                    AppState::TemplatePicker(TemplatePickerState::new(self.flake_path.clone()));
+                self.spawn_load_templates();
+            }
+        }
 
         loop {
             if matches!(self.state, AppState::Quitting) {
@@ -87,46 +307,225 @@ impl App {
             }
 
             self.tick_count = self.tick_count.wrapping_add(1);
-
-            if let Some(ref msg) = self.status_message {
-                if msg.is_expired() {
-                    self.status_message = None;
-                }
+            self.scheduler.poll();
+            if let Some(pane) = &mut self.tasks_pane {
+                pane.clamp(self.scheduler.tasks().len());
             }
+            self.poll_watch();
+            self.poll_auto_refresh();
+            self.poll_config_reload();
+
+            self.toasts.expire();
+            self.announce_status();
+        }
+
+        if let Some(stats) = self.git.cache_stats() {
+            info!(
+                clone_hits = stats.clone_hits,
+                clone_misses = stats.clone_misses,
+                git_bytes = stats.git_bytes,
+                api_requests = stats.api_requests,
+                api_bytes = stats.api_bytes,
+                "Cache metrics for this session"
+            );
         }
 
         Ok(())
     }
 
+    /// Open the flake given on the command line, or the picker when only
+    /// nested flakes exist below it
+    fn open_initial_flake(&mut self) {
+        self.discovered_flakes = discover_flakes(&self.flake_root);
+        if !self.flake_root.join("flake.nix").exists() && !self.discovered_flakes.is_empty() {
+            self.state = AppState::FlakePicker(FlakePickerState::new(
+                self.flake_root.clone(),
+                self.discovered_flakes.clone(),
+                None,
+                None,
+            ));
+        } else {
+            self.spawn_load_flake();
+            let hidden: Vec<(usize, PathBuf)> = self
+                .tabs
+                .tabs()
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(idx, tab)| (idx, tab.path.clone()))
+                .collect();
+            for (idx, path) in hidden {
+                self.spawn_load_tab(idx, path);
+            }
+        }
+    }
+
     /// Render the application UI
     fn render(&mut self, frame: &mut ratatui::Frame) {
+        if render::is_too_small(frame.area()) {
+            render::render_too_small(frame, &self.theme);
+            return;
+        }
+
+        // Spinners stand still in accessible mode, so the screen only
+        // changes when the state does.
+        let tick_count = if self.announcements.is_some() {
+            0
+        } else {
+            self.tick_count
+        };
         match &mut self.state {
             AppState::Loading => {
-                render::render_loading(frame, "Loading flake...", self.tick_count);
+                render::render_loading(frame, "Loading flake...", tick_count, &self.theme);
             }
             AppState::Error(msg) => {
-                render::render_error(frame, msg);
+                render::render_error(frame, msg, &self.theme);
             }
-            AppState::List(list) => {
-                render::render_list(frame, list, self.status_message.as_ref(), self.tick_count);
+            AppState::NoFlake(path) => {
+                render::render_no_flake(frame, path, &self.theme);
             }
-            AppState::LoadingChangelog(list) => {
-                render::render_list(frame, list, self.status_message.as_ref(), self.tick_count);
+            AppState::List(list) | AppState::LoadingChangelog(list) => {
+                let context = render::ListContext {
+                    columns: &self.columns,
+                    status_message: self.toasts.latest(),
+                    tick_count,
+                    announcements: self.announcements.as_ref(),
+                    tabs: (!self.tabs.is_empty()).then_some(&self.tabs),
+                    theme: &self.theme,
+                };
+                render::render_list(frame, list, &context);
             }
             AppState::Changelog(cs) => {
-                render::render_changelog(frame, cs.as_mut(), self.status_message.as_ref());
+                render::render_changelog(frame, cs.as_mut(), self.toasts.latest(), &self.theme);
+            }
+            AppState::FlakePicker(picker) => {
+                render::render_flake_picker(frame, picker, self.toasts.latest(), &self.theme);
+            }
+            AppState::BranchPicker(picker) => {
+                render::render_branch_picker(
+                    frame,
+                    picker.as_mut(),
+                    self.toasts.latest(),
+                    &self.theme,
+                );
+            }
+            AppState::ClosureDiff(view) => {
+                render::render_closure_diff(frame, view, self.toasts.latest(), &self.theme);
+            }
+            AppState::Build(build) => {
+                render::render_build(frame, build, self.toasts.latest(), &self.theme);
+            }
+            AppState::FollowsGraph(graph) => {
+                render::render_follows_graph(frame, graph, self.toasts.latest(), &self.theme);
+            }
+            AppState::LockHistory(history) => {
+                render::render_lock_history(
+                    frame,
+                    history.as_mut(),
+                    self.toasts.latest(),
+                    &self.theme,
+                );
+            }
+            AppState::TemplatePicker(picker) => {
+                render::render_template_picker(
+                    frame,
+                    picker,
+                    self.toasts.latest(),
+                    tick_count,
+                    &self.theme,
+                );
             }
             AppState::Quitting => {}
         }
+        if !matches!(
+            self.state,
+            AppState::Loading | AppState::Error(_) | AppState::NoFlake(_) | AppState::Quitting
+        ) {
+            render::render_toasts(frame, &self.toasts, &self.theme);
+        }
+
+        if let Some(pane) = &mut self.tasks_pane {
+            render::render_tasks(
+                frame,
+                self.scheduler.tasks(),
+                pane,
+                Instant::now(),
+                &self.theme,
+            );
+        } else if let Some(stats) = self.git.cache_stats().filter(|_| self.stats_open) {
+            render::render_stats(frame, &stats, &self.theme);
+        }
+        if let Some(scroll) = &mut self.activity_scroll {
+            render::render_activity(frame, &self.activity, scroll, &self.theme);
+        }
+        if let Some(scroll) = &mut self.help_scroll {
+            render::render_help(frame, scroll, &self.theme);
+        }
     }
 
     /// Handle a key event
     async fn handle_key(&mut self, key: crossterm::event::KeyEvent) {
-        let action = handler::handle_key(&mut self.state, key);
+        self.toasts.dismiss();
+        if let Some(action) = self.held_action.take() {
+            if key.code == crossterm::event::KeyCode::Char('y') {
+                self.run_action(action).await;
+            } else {
+                let message = match action {
+                    Action::SubmitPullRequest { .. } => "Cancelled; nothing was pushed",
+                    _ => "Cancelled; flake files left as they are",
+                };
+                self.abandon_held_action();
+                self.notify(StatusMessage::warning(message));
+            }
+            return;
+        }
+        let action = if let Some(scroll) = &mut self.help_scroll {
+            handler::handle_help_key(scroll, key)
+        } else if let Some(scroll) = &mut self.activity_scroll {
+            handler::handle_activity_key(scroll, key)
+        } else {
+            match &mut self.tasks_pane {
+                Some(pane) => handler::handle_tasks_key(pane, self.scheduler.tasks(), key),
+                None if self.stats_open => handler::handle_stats_key(key),
+                None => handler::handle_key(&mut self.state, key),
+            }
+        };
         self.execute_action(action).await;
     }
 
+    /// Run `action`, first asking for confirmation if it would rewrite
+    /// flake.nix or flake.lock while either has uncommitted changes
     async fn execute_action(&mut self, action: Action) {
+        if action.rewrites_flake() {
+            match self.nix.uncommitted_flake_files(&self.flake_path) {
+                Ok(files) if !files.is_empty() => {
+                    self.notify(StatusMessage::hint(format!(
+                        "{} {} uncommitted changes; press y to go ahead anyway, any other key to cancel",
+                        files.join(" and "),
+                        if files.len() == 1 { "has" } else { "have" }
+                    )));
+                    self.held_action = Some(action);
+                    return;
+                }
+                Ok(_) => {}
+                Err(e) => debug!(error = %e, "Could not check flake files for uncommitted changes"),
+            }
+        }
+        self.run_action(action).await;
+    }
+
+    /// Put the view back the way it was before the key that produced the
+    /// held action, which will not run
+    fn abandon_held_action(&mut self) {
+        match &mut self.state {
+            AppState::List(list) => list.mode = ListMode::Idle,
+            AppState::BranchPicker(picker) => picker.switching = None,
+            AppState::Changelog(cs) => cs.hide_confirm(),
+            _ => {}
+        }
+    }
+
+    async fn run_action(&mut self, action: Action) {
         match action {
             Action::None => {}
             Action::Quit => {
@@ -137,24 +536,108 @@ impl App {
                 self.cancel_token.cancel();
                 self.state = AppState::Quitting;
             }
+            Action::OpenTasks => {
+                self.tasks_pane = Some(TasksPaneState::new(self.scheduler.tasks()));
+            }
+            Action::CloseTasks => {
+                self.tasks_pane = None;
+            }
+            Action::OpenActivity => {
+                // Open on the newest entries
+                self.activity_scroll = Some(u16::MAX);
+            }
+            Action::CloseActivity => {
+                self.activity_scroll = None;
+            }
+            Action::ChangeTheme(name) => {
+                let theme = match name {
+                    Some(name) => match Theme::named(&name) {
+                        Some(theme) => theme,
+                        None => {
+                            self.notify(StatusMessage::error(format!(
+                                "Unknown theme '{}' (available: {})",
+                                name,
+                                theme::names().join(", ")
+                            )));
+                            return;
+                        }
+                    },
+                    None => self.theme.next(),
+                };
+                self.theme = theme;
+                let saved = match self.config_file.clone() {
+                    Some(path) => config::save_theme(&path, &theme).map(|()| {
+                        // Not a change to reload
+                        self.config_modified = modified_time(&path);
+                    }),
+                    None => Ok(()),
+                };
+                match saved {
+                    Ok(()) => self.notify(StatusMessage::success(format!("Theme: {}", theme.name))),
+                    Err(e) => {
+                        warn!(error = %e, "Theme not saved");
+                        self.notify(StatusMessage::warning(format!(
+                            "Theme: {} (not saved: {})",
+                            theme.name, e
+                        )));
+                    }
+                }
+            }
+            Action::OpenStats => {
+                if self.git.cache_stats().is_some() {
+                    self.stats_open = true;
+                } else {
+                    self.notify(StatusMessage::warning(
+                        "Cache metrics are off; set metrics = true in config.toml",
+                    ));
+                }
+            }
+            Action::CloseStats => {
+                self.stats_open = false;
+            }
+            Action::OpenHelp => {
+                self.help_scroll = Some(0);
+            }
+            Action::CloseHelp => {
+                self.help_scroll = None;
+            }
+            Action::CancelTask(id) => {
+                let label = self
+                    .scheduler
+                    .tasks()
+                    .iter()
+                    .find(|task| task.id == id)
+                    .map(|task| task.label.clone())
+                    .unwrap_or_default();
+                if self.scheduler.cancel(id) {
+                    debug!(task = %label, "Cancelling task");
+                    self.notify(StatusMessage::info(format!("Cancelled: {}", label)));
+                } else {
+                    self.notify(StatusMessage::warning("Task already finished".to_string()));
+                }
+            }
             Action::UpdateSelected(names) => {
                 debug!(inputs = ?names, "Updating selected inputs");
-                self.status_message = Some(StatusMessage::info(format!(
+                self.notify(StatusMessage::info(format!(
                     "Updating {} input(s)...",
                     names.len()
                 )));
                 if let AppState::List(list) = &mut self.state {
                     for name in &names {
                         list.update_statuses
-                            .insert(name.clone(), UpdateStatus::Updating);
+                            .insert(name.clone(), UpdateStatus::Queued);
                     }
                     let path = list.flake.path.clone();
-                    self.spawn_update(path, names);
+                    let nixpkgs = list.flake.inputs.iter().any(|input| {
+                        input.is_nixpkgs() && names.iter().any(|name| name.as_str() == input.name())
+                    });
+                    let closure = self.closure_to_diff(&path, nixpkgs);
+                    self.spawn_update(path, names, closure);
                 }
             }
             Action::UpdateAll => {
                 debug!("Updating all inputs");
-                self.status_message = Some(StatusMessage::info("Updating all inputs..."));
+                self.notify(StatusMessage::info("Updating all inputs..."));
                 if let AppState::List(list) = &mut self.state {
                     for input in &list.flake.inputs {
                         if let Ok(name) = InputName::new(input.name()) {
@@ -162,18 +645,48 @@ impl App {
                         }
                     }
                     let path = list.flake.path.clone();
-                    self.spawn_update_all(path);
+                    let nixpkgs = list.flake.inputs.iter().any(FlakeInput::is_nixpkgs);
+                    let closure = self.closure_to_diff(&path, nixpkgs);
+                    self.spawn_update_all(path, closure);
+                }
+            }
+            Action::UndoLastOperation => {
+                self.notify(StatusMessage::info("Restoring flake.lock..."));
+                self.spawn_undo_last_operation();
+            }
+            Action::OpenPullRequest => match self.nix.pull_request_draft(&self.flake_path) {
+                Ok((branch, title)) => {
+                    self.notify(StatusMessage::hint(format!(
+                        "Push {} to origin and open \"{}\"? press y to go ahead, any other key to cancel",
+                        branch, title
+                    )));
+                    self.held_action = Some(Action::SubmitPullRequest { branch });
                 }
+                Err(e) => self.notify(StatusMessage::error(e.to_string())),
+            },
+            Action::SubmitPullRequest { branch } => {
+                self.notify(StatusMessage::info("Opening pull request..."));
+                self.spawn_open_pull_request(self.flake_path.clone(), branch);
             }
             Action::Refresh => {
-                self.status_message = Some(StatusMessage::info("Refreshing..."));
+                if matches!(self.state, AppState::NoFlake(_)) {
+                    self.state = AppState::Loading;
+                } else {
+                    self.notify(StatusMessage::info("Refreshing..."));
+                }
                 self.spawn_load_flake();
             }
+            Action::OpenTemplatePicker => {
+                self.toasts.clear_info();
+                self.state =
+                    AppState::TemplatePicker(TemplatePickerState::new(self.flake_path.clone()));
+                self.spawn_load_templates();
+            }
             Action::OpenChangelog { input } => {
                 if let AppState::List(list) = &self.state {
                     let mut parent = list.clone();
                     parent.mode = ListMode::Idle;
-                    self.status_message = Some(StatusMessage::info("Loading commit history..."));
+                    self.notify(StatusMessage::info("Loading commit history..."));
                     self.state = AppState::LoadingChangelog(parent.clone());
                     self.spawn_load_changelog(input, parent);
                 }
@@ -181,30 +694,251 @@ impl App {
             Action::CloseChangelog => {
                 self.close_changelog();
             }
+            Action::OpenBranchPicker { input } => {
+                if let AppState::List(list) = std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    self.state = AppState::BranchPicker(Box::new(BranchPickerState::new(
+                        input.clone(),
+                        list,
+                    )));
+                    self.spawn_load_branches(input);
+                }
+            }
+            Action::CloseBranchPicker => {
+                if let AppState::BranchPicker(picker) =
+                    std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    self.state = AppState::List(picker.parent_list);
+                }
+            }
+            Action::ChangeRef { input, reference } => {
+                if let AppState::BranchPicker(picker) = &self.state {
+                    let path = picker.parent_list.flake.path.clone();
+                    self.notify(StatusMessage::info(format!(
+                        "Switching {} to {}...",
+                        input.name(),
+                        reference
+                    )));
+                    self.spawn_change_ref(path, input, reference);
+                }
+            }
+            Action::OpenBuild => {
+                let outputs = match self.nix.build_outputs(&self.flake_path) {
+                    Ok(outputs) => outputs,
+                    Err(e) => {
+                        self.notify(StatusMessage::error(e.to_string()));
+                        return;
+                    }
+                };
+                if outputs.is_empty() {
+                    self.notify(StatusMessage::warning(
+                        "No outputs to build; set build_outputs in config.toml or .melt.toml"
+                            .to_string(),
+                    ));
+                    return;
+                }
+                if let AppState::List(list) = std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    let path = list.flake.path.clone();
+                    self.state = AppState::Build(Box::new(BuildState::new(outputs.clone(), list)));
+                    self.spawn_build(path, outputs);
+                }
+            }
+            Action::Rebuild => {
+                if let AppState::Build(build) = &mut self.state {
+                    build.restart();
+                    let path = build.parent_list.flake.path.clone();
+                    let outputs = build.outputs.iter().map(|o| o.target.clone()).collect();
+                    self.spawn_build(path, outputs);
+                }
+            }
+            Action::CloseBuild => {
+                if let AppState::Build(build) =
+                    std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    self.state = AppState::List(build.parent_list);
+                }
+            }
+            Action::OpenClosureDiff => match &self.closure_diff {
+                Some(diff) => {
+                    if let AppState::List(list) =
+                        std::mem::replace(&mut self.state, AppState::Loading)
+                    {
+                        self.state = AppState::ClosureDiff(Box::new(ClosureDiffState::new(
+                            diff.clone(),
+                            list,
+                        )));
+                    }
+                }
+                None => {
+                    self.notify(StatusMessage::warning(
+                        "No package changes yet; set diff_closure to compare closures across nixpkgs updates"
+                            .to_string(),
+                    ));
+                }
+            },
+            Action::CloseClosureDiff => {
+                if let AppState::ClosureDiff(view) =
+                    std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    self.state = AppState::List(view.parent_list);
+                }
+            }
+            Action::OpenFollowsGraph => {
+                if let AppState::List(list) = std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    let path = list.flake.path.clone();
+                    self.state = AppState::FollowsGraph(Box::new(FollowsGraphState::new(list)));
+                    self.spawn_load_input_tree(path);
+                }
+            }
+            Action::CloseFollowsGraph => {
+                if let AppState::FollowsGraph(graph) =
+                    std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    self.state = AppState::List(graph.parent_list);
+                }
+            }
+            Action::OpenLockHistory => {
+                if let AppState::List(list) = std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    let path = list.flake.path.clone();
+                    self.state = AppState::LockHistory(Box::new(LockHistoryState::new(list)));
+                    self.spawn_load_lock_history(path);
+                }
+            }
+            Action::CloseLockHistory => {
+                if let AppState::LockHistory(history) =
+                    std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    self.state = AppState::List(history.parent_list);
+                }
+            }
+            Action::RestoreLock { sha } => {
+                if let AppState::LockHistory(history) = &self.state {
+                    let path = history.parent_list.flake.path.clone();
+                    self.notify(StatusMessage::info(format!(
+                        "Restoring flake.lock from {}...",
+                        &sha[..7.min(sha.len())]
+                    )));
+                    self.spawn_restore_lock(path, sha);
+                }
+            }
+            Action::LoadTags { input } => {
+                self.spawn_load_tags(input);
+            }
+            Action::LoadCommitDetail { input, sha } => {
+                self.spawn_load_commit_detail(input, sha);
+            }
+            Action::OpenCommitPullRequest { input, sha } => {
+                self.spawn_find_pull_request(input, sha);
+            }
+            Action::LoadDiffStats {
+                input,
+                base,
+                head,
+                paths,
+            } => {
+                self.spawn_load_diff_stats(input, base, head, paths);
+            }
+            Action::LoadRelease { input, tag } => {
+                self.spawn_load_release(input, tag);
+            }
             Action::ConfirmLock {
                 input_name,
                 lock_url,
             } => {
                 debug!(input = %input_name, "Locking to commit");
                 if let AppState::Changelog(cs) = &self.state {
-                    if let Some(target) = cs.lock_target() {
-                        if let Some(commit) = cs.data.commits.get(target.commit_idx()) {
-                            self.status_message = Some(StatusMessage::info(format!(
-                                "Locking {} to {}...",
-                                input_name,
-                                commit.short_sha()
-                            )));
-                        }
+                    let path = cs.parent_list.flake.path.clone();
+                    let short_sha = cs
+                        .lock_target()
+                        .and_then(|target| cs.data.commits.get(target.commit_idx()))
+                        .map(|commit| commit.short_sha().to_string());
+                    if let Some(short_sha) = short_sha {
+                        self.notify(StatusMessage::info(format!(
+                            "Locking {} to {}...",
+                            input_name, short_sha
+                        )));
                     }
-                    self.spawn_lock(
-                        cs.parent_list.flake.path.clone(),
-                        input_name.into_string(),
-                        lock_url.into_string(),
-                    );
+                    self.spawn_lock(path, input_name.into_string(), lock_url.into_string());
                 }
             }
+            Action::OpenFlakePicker => {
+                if self.discovered_flakes.len() < 2 {
+                    self.notify(StatusMessage::warning(
+                        "No other flakes found in this directory",
+                    ));
+                    return;
+                }
+                if let AppState::List(list) = std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    let current = Some(list.flake.path.clone());
+                    self.state = AppState::FlakePicker(FlakePickerState::new(
+                        self.flake_root.clone(),
+                        self.discovered_flakes.clone(),
+                        current,
+                        Some(list),
+                    ));
+                }
+            }
+            Action::SwitchTab { back } => {
+                let Some(idx) = self.tabs.neighbour(back) else {
+                    self.notify(StatusMessage::warning(
+                        "Only one flake is open; give more paths to open them in tabs",
+                    ));
+                    return;
+                };
+                if let AppState::List(list) = std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    let Some((path, list)) = self.tabs.switch(list, self.flake_path.clone(), idx)
+                    else {
+                        return;
+                    };
+                    debug!(flake = ?path, "Switching tab");
+                    self.flake_path = path;
+                    self.changelogs.clear();
+                    self.closure_diff = None;
+                    self.toasts.clear_info();
+                    match list {
+                        Some(list) => self.state = AppState::List(list),
+                        None => self.spawn_load_flake(),
+                    }
+                }
+            }
+            Action::SelectFlake(path) => {
+                debug!(flake = ?path, "Switching flake");
+                self.flake_path = path;
+                self.changelogs.clear();
+                self.toasts.clear_info();
+                self.state = AppState::Loading;
+                self.spawn_load_flake();
+            }
+            Action::CloseFlakePicker => {
+                if let AppState::FlakePicker(picker) =
+                    std::mem::replace(&mut self.state, AppState::Loading)
+                {
+                    match picker.parent_list {
+                        Some(list) => self.state = AppState::List(list),
+                        None => self.state = AppState::Quitting,
+                    }
+                }
+            }
+            Action::InitTemplate(reference) => {
+                self.notify(StatusMessage::info(format!(
+                    "Initializing flake from {}...",
+                    reference
+                )));
+                self.spawn_init_template(reference);
+            }
+            Action::ShowSuccess(msg) => {
+                self.notify(StatusMessage::success(msg));
+            }
+            Action::OpenUrl(url) => {
+                self.open_url(&url);
+            }
             Action::ShowWarning(msg) => {
-                self.status_message = Some(StatusMessage::warning(msg));
+                self.notify(StatusMessage::warning(msg));
             }
         }
     }
@@ -212,167 +946,1491 @@ impl App {
     fn handle_task_result(&mut self, result: TaskResult) {
         match result {
             TaskResult::FlakeLoaded(Ok(flake)) => {
-                let inputs: Vec<GitInput> = flake
-                    .inputs
-                    .iter()
-                    .filter_map(|input| match input {
-                        FlakeInput::Git(git_input) => Some(git_input.clone()),
-                        _ => None,
-                    })
-                    .collect();
+                let path = flake.path.clone();
+                let inputs = git_inputs(&flake);
                 if let AppState::List(list) = &mut self.state {
+                    if list.flake.path == flake.path {
+                        let renames = flake.renames_since(&list.flake);
+                        for (from, to) in &renames {
+                            info!(from, to, "Input renamed");
+                            self.digest.rename_input(from, to);
+                        }
+                        if self.digest_dir.is_some() {
+                            record_applied_updates(&mut self.digest, &list.flake, &flake, &renames);
+                        }
+                    }
                     list.update_flake(flake);
+                    self.write_digest();
                 } else {
-                    self.state = AppState::List(ListState::new(flake));
+                    self.state = AppState::List(ListState::new(flake).with_sort(self.sort));
+                }
+                self.toasts.clear_info();
+                if let AppState::List(list) = &self.state {
+                    let loaded = format!(
+                        "Loaded {}: {} input(s)",
+                        list.flake.path.display(),
+                        list.flake.inputs.len()
+                    );
+                    self.announce(loaded);
+                }
+                if let AppState::List(list) = &self.state {
+                    for warning in &list.flake.warnings {
+                        warn!(input = %warning.input, "{}", warning.message);
+                    }
+                    if !list.flake.warnings.is_empty() {
+                        self.notify(StatusMessage::warning(format!(
+                            "flake.lock problems in {} input(s); see the log",
+                            list.flake.warnings.len()
+                        )));
+                    }
+                }
+                self.hint_github_token(&inputs);
+                if self.columns.as_slice().contains(&ListColumn::Version) {
+                    self.spawn_load_versions();
+                }
+                self.spawn_check_updates(path, inputs);
+                self.scheduler.reset_periodic();
+            }
+            TaskResult::TabLoaded { tab, flake } => match flake {
+                Ok(flake) => {
+                    let path = flake.path.clone();
+                    let inputs = git_inputs(&flake);
+                    self.tabs
+                        .set_list(tab, ListState::new(flake).with_sort(self.sort));
+                    if self.tabs.hidden_list_mut(&path).is_some() {
+                        self.spawn_check_updates(path, inputs);
+                    }
+                }
+                Err(e) => warn!(error = %e, tab, "Failed to load flake in the background"),
+            },
+            TaskResult::FlakeLoaded(Err(AppError::FlakeNotFound(path))) => {
+                warn!(path = %path.display(), "No flake.nix found");
+                self.toasts.clear_info();
+                self.state = AppState::NoFlake(path);
+            }
+            TaskResult::FlakeLoaded(Err(e)) if matches!(self.state, AppState::List(_)) => {
+                warn!(error = %e, "Failed to reload flake");
+                self.notify(StatusMessage::error(format!("Failed to load flake: {}", e)));
+                if let AppState::List(list) = &mut self.state {
+                    list.mode = ListMode::Idle;
                 }
-                self.status_message = None;
-                self.spawn_check_updates(inputs);
             }
             TaskResult::FlakeLoaded(Err(e)) => {
                 warn!(error = %e, "Failed to load flake");
                 self.state = AppState::Error(format!("Failed to load flake: {}", e));
             }
+            TaskResult::ClosureBuilt {
+                installable,
+                before,
+            } => {
+                self.closure_before = Some((installable, before));
+            }
+            TaskResult::ClosureDiffed(Ok(diff)) => {
+                let changed = diff.version_changes().len();
+                self.notify(StatusMessage::info(format!(
+                    "{} package version(s) changed; press d to see them",
+                    changed
+                )));
+                self.closure_diff = Some(diff);
+            }
+            TaskResult::ClosureDiffed(Err(e)) => {
+                warn!(error = %e, "Failed to diff closures");
+                self.notify(StatusMessage::warning(format!(
+                    "Could not compare closures: {}",
+                    e
+                )));
+            }
+            TaskResult::UpdateProgress {
+                name,
+                position,
+                total,
+            } => {
+                self.notify(StatusMessage::info(format!(
+                    "{}/{}: updating {}…",
+                    position + 1,
+                    total,
+                    name
+                )));
+                if let AppState::List(list) = &mut self.state {
+                    list.start_update(&name);
+                }
+            }
             TaskResult::UpdateComplete(Ok(())) => {
-                self.status_message = Some(StatusMessage::success("Update complete"));
+                if let Some((installable, before)) = self.closure_before.take() {
+                    self.spawn_diff_closure(self.flake_path.clone(), installable, before);
+                }
+                let outputs = self
+                    .nix
+                    .build_outputs(&self.flake_path)
+                    .map_or(0, |outputs| outputs.len());
+                self.notify(StatusMessage::success(if outputs == 0 {
+                    "Update complete".to_string()
+                } else {
+                    format!("Update complete; press B to build {} output(s)", outputs)
+                }));
                 if let AppState::List(list) = &mut self.state {
                     list.clear_selection();
-                    list.update_statuses
-                        .retain(|_, status| !matches!(status, UpdateStatus::Updating));
+                    list.finish_update(false);
                 }
+                self.spawn_commit_lock(self.flake_path.clone());
                 self.spawn_load_flake();
             }
             TaskResult::UpdateComplete(Err(e)) => {
                 warn!(error = %e, "Update failed");
-                self.status_message = Some(StatusMessage::error(format!("Update failed: {}", e)));
+                self.closure_before = None;
+                self.notify(StatusMessage::error(format!("Update failed: {}", e)));
                 if let AppState::List(list) = &mut self.state {
                     list.mode = ListMode::Idle;
-                    list.update_statuses
-                        .retain(|_, status| !matches!(status, UpdateStatus::Updating));
+                    list.finish_update(true);
                 }
             }
-            TaskResult::ChangelogLoaded(result) => match *result {
-                Ok(data) => {
-                    self.state = AppState::Changelog(Box::new(ChangelogState::new(
-                        data.input,
-                        data.data,
-                        data.parent_list,
-                    )));
-                    self.status_message = None;
+            TaskResult::ChangelogPage {
+                input,
+                offset,
+                commits,
+            } => match std::mem::replace(&mut self.state, AppState::Loading) {
+                AppState::LoadingChangelog(list) => {
+                    let mut cs = ChangelogState::loading(*input, list);
+                    cs.add_page(offset, commits);
+                    self.state = AppState::Changelog(Box::new(cs));
+                    self.toasts.clear_info();
                 }
+                AppState::Changelog(mut cs) if cs.loading && cs.input.name() == input.name() => {
+                    cs.add_page(offset, commits);
+                    self.state = AppState::Changelog(cs);
+                }
+                state => self.state = state,
+            },
+            TaskResult::ChangelogLoaded(result) => match *result {
+                Ok(data) => match std::mem::replace(&mut self.state, AppState::Loading) {
+                    AppState::LoadingChangelog(_) => {
+                        self.state = AppState::Changelog(Box::new(ChangelogState::new(
+                            data.input,
+                            data.data,
+                            data.parent_list,
+                        )));
+                        self.toasts.clear_info();
+                    }
+                    AppState::Changelog(mut cs)
+                        if cs.loading && cs.input.name() == data.input.name() =>
+                    {
+                        cs.finish_loading(data.data);
+                        self.state = AppState::Changelog(cs);
+                    }
+                    // Closed before loading finished
+                    state => self.state = state,
+                },
                 Err(e) => {
                     warn!(error = %e, "Failed to load changelog");
-                    self.status_message = Some(StatusMessage::error(format!(
+                    self.notify(StatusMessage::error(format!(
                         "Failed to load commit history: {}",
                         e
                     )));
-                    if let AppState::LoadingChangelog(list) =
-                        std::mem::replace(&mut self.state, AppState::Loading)
-                    {
-                        self.state = AppState::List(list);
+                    match std::mem::replace(&mut self.state, AppState::Loading) {
+                        AppState::LoadingChangelog(list) => self.state = AppState::List(list),
+                        // Keep the commits that did load
+                        AppState::Changelog(mut cs) => {
+                            cs.loading = false;
+                            self.state = AppState::Changelog(cs);
+                        }
+                        state => self.state = state,
+                    }
+                }
+            },
+            TaskResult::CommitDetailLoaded { name, sha, detail } => {
+                let AppState::Changelog(cs) = &mut self.state else {
+                    return;
+                };
+                if cs.input.input_name() != &name {
+                    return;
+                }
+                match *detail {
+                    Ok(detail) => cs.set_detail(&sha, detail),
+                    Err(e) => {
+                        warn!(error = %e, "Failed to load commit");
+                        cs.detail = None;
+                        self.notify(StatusMessage::error(format!(
+                            "Failed to load commit: {}",
+                            e
+                        )));
                     }
                 }
+            }
+            TaskResult::PullRequestFound { sha, url } => match url {
+                Ok(Some(url)) => self.open_url(&url),
+                Ok(None) => self.notify(StatusMessage::warning(format!(
+                    "No web page for commit {}",
+                    &sha[..7.min(sha.len())]
+                ))),
+                Err(e) => {
+                    warn!(error = %e, "Failed to look up pull request");
+                    self.notify(StatusMessage::error(format!(
+                        "Failed to look up pull request: {}",
+                        e
+                    )));
+                }
             },
+            TaskResult::ReleaseLoaded { name, tag, release } => {
+                let AppState::Changelog(cs) = &mut self.state else {
+                    return;
+                };
+                if cs.input.input_name() != &name {
+                    return;
+                }
+                match release {
+                    Ok(release) => cs.set_release(&tag, release),
+                    Err(e) => {
+                        warn!(error = %e, "Failed to load release notes");
+                        cs.release = None;
+                        self.notify(StatusMessage::error(format!(
+                            "Failed to load release notes: {}",
+                            e
+                        )));
+                    }
+                }
+            }
+            TaskResult::DiffStatsLoaded { name, head, stats } => {
+                let AppState::Changelog(cs) = &mut self.state else {
+                    return;
+                };
+                if cs.input.input_name() != &name {
+                    return;
+                }
+                let stats = stats.unwrap_or_else(|e| {
+                    debug!(error = %e, "Failed to compare commits");
+                    None
+                });
+                cs.set_range_stats(head, stats);
+                // The cursor may have moved on while these loaded
+                if let Action::LoadDiffStats {
+                    input,
+                    base,
+                    head,
+                    paths,
+                } = handler::load_diff_stats(cs)
+                {
+                    self.spawn_load_diff_stats(input, base, head, paths);
+                }
+            }
+            TaskResult::TagsLoaded { name, tags } => {
+                // The changelog may have been closed or switched meanwhile
+                let AppState::Changelog(cs) = &mut self.state else {
+                    return;
+                };
+                if cs.input.input_name() != &name {
+                    return;
+                }
+                match tags {
+                    Ok(tags) => cs.set_tags(tags),
+                    Err(e) => {
+                        warn!(error = %e, "Failed to load tags");
+                        cs.tags_loading = false;
+                        self.notify(StatusMessage::error(format!("Failed to load tags: {}", e)));
+                    }
+                }
+            }
             TaskResult::LockComplete(Ok(())) => {
-                self.status_message = Some(StatusMessage::success("Locked successfully"));
+                self.notify(StatusMessage::success("Locked successfully"));
                 if let AppState::Changelog(cs) =
                     std::mem::replace(&mut self.state, AppState::Loading)
                 {
+                    self.changelogs
+                        .insert(cs.input.name().to_string(), cs.data.clone());
                     let mut list = cs.parent_list;
                     list.mode = ListMode::Refreshing;
                     self.state = AppState::List(list);
                 }
+                self.spawn_commit_lock(self.flake_path.clone());
                 self.spawn_load_flake();
             }
-            TaskResult::LockComplete(Err(e)) => {
-                warn!(error = %e, "Lock failed");
-                self.status_message = Some(StatusMessage::error(format!("Lock failed: {}", e)));
-                if let AppState::Changelog(cs) = &mut self.state {
-                    cs.hide_confirm();
-                }
+            TaskResult::LockCommitted(Ok(Some(commit))) => {
+                self.notify(StatusMessage::success(format!("Committed {}", commit)));
             }
-            TaskResult::InputStatus { name, status } => {
-                if let AppState::List(list) = &mut self.state {
-                    list.update_statuses.insert(name, status);
-                }
+            TaskResult::LockCommitted(Ok(None)) => {}
+            TaskResult::NixVersionChecked(Ok(())) => {}
+            TaskResult::NixVersionChecked(Err(e @ AppError::UnsupportedNix(_))) => {
+                warn!(error = %e, "Unsupported nix");
+                self.notify(StatusMessage::error(e.to_string()));
             }
-        }
+            TaskResult::NixVersionChecked(Err(e)) => {
+                // Loading the flake reports a missing or broken nix.
+                debug!(error = %e, "Could not read the nix version");
+            }
+            TaskResult::PullRequestOpened(Ok(url)) => {
+                self.notify(StatusMessage::success(format!("Opened {}", url)));
+            }
+            TaskResult::PullRequestOpened(Err(e)) => {
+                warn!(error = %e, "Opening pull request failed");
+                self.notify(StatusMessage::error(e.to_string()));
+            }
+            TaskResult::LockCommitted(Err(e)) => {
+                warn!(error = %e, "Committing flake.lock failed");
+                self.notify(StatusMessage::error(format!(
+                    "Could not commit flake.lock: {}",
+                    e
+                )));
+            }
+            TaskResult::LockRestored(Ok(true)) => {
+                self.notify(StatusMessage::success(
+                    "Restored flake.lock from before the last update",
+                ));
+                self.spawn_load_flake();
+            }
+            TaskResult::LockRestored(result) => {
+                self.notify(match result {
+                    Ok(_) => StatusMessage::warning("Nothing to undo"),
+                    Err(e) => {
+                        warn!(error = %e, "Restoring flake.lock failed");
+                        StatusMessage::error(format!("Undo failed: {}", e))
+                    }
+                });
+                if let AppState::List(list) = &mut self.state {
+                    list.mode = ListMode::Idle;
+                }
+            }
+            TaskResult::BranchesLoaded { name, branches } => {
+                let AppState::BranchPicker(picker) = &mut self.state else {
+                    return;
+                };
+                if picker.input.input_name() != &name {
+                    return;
+                }
+                match branches {
+                    Ok(branches) => picker.set_branches(branches),
+                    Err(e) => {
+                        warn!(input = %name, error = %e, "Failed to load branches");
+                        self.notify(StatusMessage::error(format!(
+                            "Failed to load branches: {}",
+                            e
+                        )));
+                        if let AppState::BranchPicker(picker) =
+                            std::mem::replace(&mut self.state, AppState::Loading)
+                        {
+                            self.state = AppState::List(picker.parent_list);
+                        }
+                    }
+                }
+            }
+            TaskResult::RefChanged {
+                name,
+                reference,
+                result,
+            } => match result {
+                Ok(()) => {
+                    self.notify(StatusMessage::success(format!(
+                        "{} now tracks {}",
+                        name.as_str(),
+                        reference
+                    )));
+                    if let AppState::BranchPicker(picker) =
+                        std::mem::replace(&mut self.state, AppState::Loading)
+                    {
+                        let mut list = picker.parent_list;
+                        list.mode = ListMode::Refreshing;
+                        self.state = AppState::List(list);
+                    }
+                    self.spawn_load_flake();
+                }
+                Err(e) => {
+                    warn!(input = %name, error = %e, "Changing tracked branch failed");
+                    self.notify(StatusMessage::error(format!(
+                        "Switching to {} failed: {}",
+                        reference, e
+                    )));
+                    if let AppState::BranchPicker(picker) = &mut self.state {
+                        picker.switching = None;
+                    }
+                }
+            },
+            TaskResult::BuildStarted(index) => {
+                if let AppState::Build(build) = &mut self.state {
+                    build.start(index);
+                }
+            }
+            TaskResult::BuildLog { index, line } => {
+                if let AppState::Build(build) = &mut self.state {
+                    build.push_line(index, line);
+                }
+            }
+            TaskResult::BuildFinished { index, result } => {
+                let AppState::Build(build) = &mut self.state else {
+                    return;
+                };
+                match result {
+                    Ok(()) => build.finish(index, true),
+                    Err(e) => {
+                        // A failed build's log is already shown; other
+                        // errors (nix missing, say) are not.
+                        if !matches!(e, AppError::BuildFailed(..)) {
+                            build.push_line(index, e.to_string());
+                        }
+                        build.finish(index, false);
+                    }
+                }
+                if !build.is_running() {
+                    let failed = build
+                        .outputs
+                        .iter()
+                        .filter(|output| output.status == BuildStatus::Failed)
+                        .count();
+                    self.notify(if failed == 0 {
+                        StatusMessage::success("All outputs built".to_string())
+                    } else {
+                        StatusMessage::error(format!("{} output(s) failed to build", failed))
+                    });
+                }
+            }
+            TaskResult::BuildsCancelled => {
+                if let AppState::Build(build) = &mut self.state {
+                    build.cancel();
+                }
+            }
+            TaskResult::InputTreeLoaded(result) => {
+                let AppState::FollowsGraph(graph) = &mut self.state else {
+                    return;
+                };
+                match result {
+                    Ok(trees) => graph.set_trees(trees),
+                    Err(e) => {
+                        warn!(error = %e, "Failed to read the input tree");
+                        self.notify(StatusMessage::error(format!(
+                            "Failed to read flake.lock: {}",
+                            e
+                        )));
+                        if let AppState::FollowsGraph(graph) =
+                            std::mem::replace(&mut self.state, AppState::Loading)
+                        {
+                            self.state = AppState::List(graph.parent_list);
+                        }
+                    }
+                }
+            }
+            TaskResult::LockHistoryLoaded(result) => {
+                let AppState::LockHistory(history) = &mut self.state else {
+                    return;
+                };
+                match result {
+                    Ok(revisions) => history.set_revisions(revisions),
+                    Err(e) => {
+                        warn!(error = %e, "Failed to read flake.lock history");
+                        self.notify(StatusMessage::error(format!(
+                            "Failed to read flake.lock history: {}",
+                            e
+                        )));
+                        if let AppState::LockHistory(history) =
+                            std::mem::replace(&mut self.state, AppState::Loading)
+                        {
+                            self.state = AppState::List(history.parent_list);
+                        }
+                    }
+                }
+            }
+            TaskResult::LockRevisionRestored { sha, result } => match result {
+                Ok(()) => {
+                    self.notify(StatusMessage::success(format!(
+                        "Restored flake.lock from {} (z to undo)",
+                        &sha[..7.min(sha.len())]
+                    )));
+                    if let AppState::LockHistory(history) =
+                        std::mem::replace(&mut self.state, AppState::Loading)
+                    {
+                        let mut list = history.parent_list;
+                        list.mode = ListMode::Refreshing;
+                        self.state = AppState::List(list);
+                    }
+                    self.spawn_load_flake();
+                }
+                Err(e) => {
+                    warn!(error = %e, "Restoring flake.lock failed");
+                    self.notify(StatusMessage::error(format!("Restore failed: {}", e)));
+                }
+            },
+            TaskResult::LockComplete(Err(e)) => {
+                warn!(error = %e, "Lock failed");
+                self.notify(StatusMessage::error(format!("Lock failed: {}", e)));
+                if let AppState::Changelog(cs) = &mut self.state {
+                    cs.hide_confirm();
+                }
+            }
+            TaskResult::InputStatus {
+                flake,
+                name,
+                status,
+                clone_path,
+            } => {
+                if let UpdateStatus::Error(_) = status {
+                    self.activity.record(
+                        StatusLevel::Error,
+                        format!("{}: {}", name.as_str(), status.describe()),
+                    );
+                } else if clone_path.is_some() {
+                    self.activity.record(
+                        StatusLevel::Info,
+                        format!(
+                            "{}: no forge API answered; counted in a clone",
+                            name.as_str()
+                        ),
+                    );
+                }
+                if let Some(list) = self.tabs.hidden_list_mut(&flake) {
+                    list.record_check(name, status, clone_path);
+                    return;
+                }
+                if !matches!(status, UpdateStatus::Checking) {
+                    self.announce(format!("{}: {}", name.as_str(), status.describe()));
+                }
+                if let AppState::List(list) = &mut self.state {
+                    let behind = match status {
+                        UpdateStatus::Behind(count) => Some(count),
+                        _ => None,
+                    };
+                    let newly_available = list.record_check(name.clone(), status, clone_path);
+                    if newly_available {
+                        let mut names: Vec<&str> =
+                            list.new_updates.iter().map(InputName::as_str).collect();
+                        names.sort_unstable();
+                        let text = format!("New updates: {}", names.join(", "));
+                        self.notify(StatusMessage::success(text));
+                        if let (Some(behind), Some(_)) = (behind, &self.digest_dir) {
+                            self.digest
+                                .record_available(name.as_str(), behind, Utc::now());
+                            self.write_digest();
+                        }
+                    }
+                }
+            }
+            TaskResult::ChecksCancelled => {
+                if let AppState::List(list) = &mut self.state {
+                    list.cancel_checks();
+                }
+                for list in self.tabs.hidden_lists_mut() {
+                    list.cancel_checks();
+                }
+            }
+            TaskResult::VersionsLoaded { path, versions } => match versions {
+                Ok(versions) => {
+                    if let AppState::List(list) = &mut self.state {
+                        if list.flake.path == path {
+                            list.versions = versions;
+                        }
+                    }
+                }
+                // Versions are extra detail; the list is usable without them.
+                Err(e) => warn!(error = %e, "Failed to evaluate input versions"),
+            },
+            TaskResult::TemplatesLoaded(Ok(templates)) => {
+                if let AppState::TemplatePicker(picker) = &mut self.state {
+                    if templates.is_empty() {
+                        self.state =
+                            AppState::Error(format!("No templates found in '{}'", TEMPLATE_SOURCE));
+                    } else {
+                        picker.set_templates(templates);
+                    }
+                }
+            }
+            TaskResult::TemplatesLoaded(Err(e)) => {
+                warn!(error = %e, "Failed to load templates");
+                self.state = AppState::Error(format!("Failed to load templates: {}", e));
+            }
+            TaskResult::TemplateInitialized(Ok(())) => {
+                self.toasts.clear_info();
+                self.state = AppState::Loading;
+                self.spawn_load_flake();
+            }
+            TaskResult::TemplateInitialized(Err(e)) => {
+                warn!(error = %e, "Template initialization failed");
+                if let AppState::TemplatePicker(picker) = &mut self.state {
+                    picker.mode = TemplatePickerMode::Browsing;
+                    self.notify(StatusMessage::error(format!(
+                        "Failed to initialize flake: {}",
+                        e
+                    )));
+                } else {
+                    self.state = AppState::Error(format!("Failed to initialize flake: {}", e));
+                }
+            }
+        }
     }
 
-    fn spawn_load_flake(&self) {
+    fn spawn_load_flake(&mut self) {
+        self.last_load = Instant::now();
         let nix = self.nix.clone();
         let path = self.flake_path.clone();
-        let tx = self.task_tx.clone();
 
-        tokio::spawn(async move {
-            let result = nix.load_metadata(&path).await;
-            let _ = tx.send(TaskResult::FlakeLoaded(result));
-        });
+        let job = Job::new("Load flake", move |_| {
+            let (nix, path) = (nix.clone(), path.clone());
+            async move { outcome(nix.load_metadata(&path).await, TaskResult::FlakeLoaded) }
+        })
+        .on_cancel(TaskResult::FlakeLoaded(Err(cancelled())));
+        self.scheduler.submit(job);
     }
 
-    fn spawn_update(&self, path: PathBuf, names: Vec<InputName>) {
+    /// Load the flake of the hidden tab at `tab`
+    fn spawn_load_tab(&mut self, tab: usize, path: PathBuf) {
         let nix = self.nix.clone();
-        let tx = self.task_tx.clone();
+        let label = format!("Load flake {}", path.display());
 
-        tokio::spawn(async move {
-            let names: Vec<String> = names.into_iter().map(InputName::into_string).collect();
-            let result = nix.update_inputs(&path, &names).await;
-            let _ = tx.send(TaskResult::UpdateComplete(result));
+        let job = Job::new(label, move |_| {
+            let (nix, path) = (nix.clone(), path.clone());
+            async move {
+                let flake = nix.load_metadata(&path).await;
+                outcome(flake, |flake| TaskResult::TabLoaded { tab, flake })
+            }
+        })
+        .on_cancel(TaskResult::TabLoaded {
+            tab,
+            flake: Err(cancelled()),
         });
+        self.scheduler.submit(job);
     }
 
-    fn spawn_update_all(&self, path: PathBuf) {
+    fn spawn_load_versions(&mut self) {
         let nix = self.nix.clone();
-        let tx = self.task_tx.clone();
+        let path = match &self.state {
+            AppState::List(list) => list.flake.path.clone(),
+            _ => return,
+        };
 
-        tokio::spawn(async move {
-            let result = nix.update_all(&path).await;
-            let _ = tx.send(TaskResult::UpdateComplete(result));
+        let job = Job::new("Evaluate versions", move |_| {
+            let (nix, path) = (nix.clone(), path.clone());
+            async move {
+                let versions = nix.input_versions(&path).await;
+                JobOutcome::Done(Some(TaskResult::VersionsLoaded { path, versions }))
+            }
         });
+        self.scheduler.submit(job);
+    }
+
+    fn spawn_load_templates(&mut self) {
+        let nix = self.nix.clone();
+
+        let job = Job::new("Load templates", move |_| {
+            let nix = nix.clone();
+            async move {
+                outcome(
+                    nix.list_templates(TEMPLATE_SOURCE).await,
+                    TaskResult::TemplatesLoaded,
+                )
+            }
+        })
+        .on_cancel(TaskResult::TemplatesLoaded(Err(cancelled())));
+        self.scheduler.submit(job);
     }
 
-    fn spawn_load_changelog(&self, input: GitInput, parent_list: ListState) {
+    fn spawn_init_template(&mut self, template: String) {
+        let nix = self.nix.clone();
+        let path = self.flake_path.clone();
+        let label = format!("Initialize flake from {}", template);
+
+        let job = Job::new(label, move |_| {
+            let (nix, path, template) = (nix.clone(), path.clone(), template.clone());
+            async move {
+                outcome(
+                    nix.init_from_template(&path, &template).await,
+                    TaskResult::TemplateInitialized,
+                )
+            }
+        })
+        .on_cancel(TaskResult::TemplateInitialized(Err(cancelled())));
+        self.scheduler.submit(job.exclusive());
+    }
+
+    /// Installable whose closure to compare across an update, when one is
+    /// configured and the update touches nixpkgs
+    fn closure_to_diff(&mut self, path: &Path, nixpkgs: bool) -> Option<String> {
+        if !nixpkgs {
+            return None;
+        }
+        match self.nix.diff_closure(path) {
+            Ok(closure) => closure,
+            Err(e) => {
+                warn!(error = %e, "Failed to read diff_closure");
+                None
+            }
+        }
+    }
+
+    fn spawn_update(&mut self, path: PathBuf, names: Vec<InputName>, closure: Option<String>) {
+        let nix = self.nix.clone();
+        let names: Vec<String> = names.into_iter().map(InputName::into_string).collect();
+        let label = format!("Update {}", names.join(", "));
+
+        let job = Job::new(label, move |ctx| {
+            let (nix, path, names) = (nix.clone(), path.clone(), names.clone());
+            let closure = closure.clone();
+            async move {
+                build_closure_before(&nix, &path, closure, &ctx).await;
+                let total = names.len();
+                let result = nix
+                    .update_inputs_each(&path, &names, |position, name| {
+                        ctx.send(TaskResult::UpdateProgress {
+                            name: name.to_string(),
+                            position,
+                            total,
+                        })
+                    })
+                    .await;
+                outcome(result, TaskResult::UpdateComplete)
+            }
+        })
+        .on_cancel(TaskResult::UpdateComplete(Err(cancelled())));
+        self.scheduler.submit(job.exclusive());
+    }
+
+    fn spawn_update_all(&mut self, path: PathBuf, closure: Option<String>) {
+        let nix = self.nix.clone();
+
+        let job = Job::new("Update all inputs", move |ctx| {
+            let (nix, path, closure) = (nix.clone(), path.clone(), closure.clone());
+            async move {
+                build_closure_before(&nix, &path, closure, &ctx).await;
+                outcome(nix.update_all(&path).await, TaskResult::UpdateComplete)
+            }
+        })
+        .on_cancel(TaskResult::UpdateComplete(Err(cancelled())));
+        self.scheduler.submit(job.exclusive());
+    }
+
+    /// Build `installable` again after an update and compare its closure
+    /// with the one at `before`
+    fn spawn_diff_closure(&mut self, path: PathBuf, installable: String, before: String) {
+        let nix = self.nix.clone();
+
+        let job = Job::new("Compare closures", move |_| {
+            let (nix, path) = (nix.clone(), path.clone());
+            let (installable, before) = (installable.clone(), before.clone());
+            async move {
+                let result = async {
+                    let after = nix.build_output(&path, &installable, |_| {}).await?;
+                    let after = after.first().ok_or_else(|| {
+                        AppError::NixCommandFailed(format!("{} built nothing", installable))
+                    })?;
+                    nix.diff_closures(&installable, &before, after).await
+                };
+                outcome(result.await, TaskResult::ClosureDiffed)
+            }
+        })
+        .on_cancel(TaskResult::ClosureDiffed(Err(cancelled())));
+        self.scheduler.submit(job);
+    }
+
+    fn spawn_undo_last_operation(&mut self) {
+        let nix = self.nix.clone();
+        let path = self.flake_path.clone();
+
+        let job = Job::new("Undo last operation", move |_| {
+            let (nix, path) = (nix.clone(), path.clone());
+            async move {
+                outcome(
+                    nix.undo_last_operation(&path).await,
+                    TaskResult::LockRestored,
+                )
+            }
+        })
+        .on_cancel(TaskResult::LockRestored(Err(cancelled())));
+        self.scheduler.submit(job.exclusive());
+    }
+
+    fn spawn_load_branches(&mut self, input: GitInput) {
         let git = self.git.clone();
-        let tx = self.task_tx.clone();
+        let label = format!("Load branches of {}", input.name());
+        let cancelled = TaskResult::BranchesLoaded {
+            name: input.input_name().clone(),
+            branches: Err(GitError::CloneFailed("Cancelled".to_string())),
+        };
+
+        let job = Job::new(label, move |_| {
+            let (git, input) = (git.clone(), input.clone());
+            async move {
+                let name = input.input_name().clone();
+                outcome(git.branches(&input).await, |branches| {
+                    TaskResult::BranchesLoaded { name, branches }
+                })
+            }
+        })
+        .on_cancel(cancelled);
+        self.scheduler.submit(job);
+    }
 
-        tokio::spawn(async move {
-            let result = git.get_changelog(&input).await;
-            let _ = tx.send(TaskResult::ChangelogLoaded(Box::new(result.map(|data| {
-                ChangelogLoadedData {
+    fn spawn_change_ref(&mut self, path: PathBuf, input: GitInput, reference: String) {
+        let nix = self.nix.clone();
+        let label = format!("Switch {} to {}", input.name(), reference);
+        let cancelled = TaskResult::RefChanged {
+            name: input.input_name().clone(),
+            reference: reference.clone(),
+            result: Err(cancelled()),
+        };
+
+        let job = Job::new(label, move |_| {
+            let (nix, path, input, reference) =
+                (nix.clone(), path.clone(), input.clone(), reference.clone());
+            async move {
+                let result = nix.change_ref(&path, &input, &reference).await;
+                let name = input.input_name().clone();
+                outcome(result, |result| TaskResult::RefChanged {
+                    name,
+                    reference,
+                    result,
+                })
+            }
+        })
+        .on_cancel(cancelled);
+        self.scheduler.submit(job.exclusive());
+    }
+
+    fn spawn_build(&mut self, path: PathBuf, outputs: Vec<String>) {
+        let nix = self.nix.clone();
+
+        let job = Job::new("Build outputs", move |ctx| {
+            let (nix, path, outputs) = (nix.clone(), path.clone(), outputs.clone());
+            async move {
+                let mut failed = 0;
+                for (index, output) in outputs.iter().enumerate() {
+                    ctx.send(TaskResult::BuildStarted(index));
+                    let result = nix
+                        .build_output(&path, output, |line| {
+                            ctx.send(TaskResult::BuildLog { index, line })
+                        })
+                        .await
+                        .map(|_| ());
+                    failed += usize::from(result.is_err());
+                    ctx.send(TaskResult::BuildFinished { index, result });
+                }
+                if failed == 0 {
+                    JobOutcome::Done(None)
+                } else {
+                    // Every build has reported by now; this only marks the
+                    // task failed.
+                    JobOutcome::Failed(
+                        TaskResult::BuildsCancelled,
+                        format!("{} output(s) failed to build", failed),
+                    )
+                }
+            }
+        })
+        .exclusive()
+        .on_cancel(TaskResult::BuildsCancelled);
+        self.scheduler.submit(job);
+    }
+
+    fn spawn_load_input_tree(&mut self, path: PathBuf) {
+        let nix = self.nix.clone();
+
+        let job = Job::new("Read input tree", move |_| {
+            let (nix, path) = (nix.clone(), path.clone());
+            async move { outcome(nix.input_tree(&path).await, TaskResult::InputTreeLoaded) }
+        })
+        .on_cancel(TaskResult::InputTreeLoaded(Err(cancelled())));
+        self.scheduler.submit(job);
+    }
+
+    fn spawn_load_lock_history(&mut self, path: PathBuf) {
+        let nix = self.nix.clone();
+
+        let job = Job::new("Read flake.lock history", move |_| {
+            let (nix, path) = (nix.clone(), path.clone());
+            async move { outcome(nix.lock_history(&path).await, TaskResult::LockHistoryLoaded) }
+        })
+        .on_cancel(TaskResult::LockHistoryLoaded(Err(cancelled())));
+        self.scheduler.submit(job);
+    }
+
+    fn spawn_restore_lock(&mut self, path: PathBuf, sha: String) {
+        let nix = self.nix.clone();
+        let label = format!("Restore flake.lock from {}", &sha[..7.min(sha.len())]);
+        let cancelled = TaskResult::LockRevisionRestored {
+            sha: sha.clone(),
+            result: Err(cancelled()),
+        };
+
+        let job = Job::new(label, move |_| {
+            let (nix, path, sha) = (nix.clone(), path.clone(), sha.clone());
+            async move {
+                let result = nix.restore_lock_at(&path, &sha).await;
+                outcome(result, |result| TaskResult::LockRevisionRestored {
+                    sha,
+                    result,
+                })
+            }
+        })
+        .on_cancel(cancelled);
+        self.scheduler.submit(job.exclusive());
+    }
+
+    fn spawn_load_changelog(&mut self, input: GitInput, parent_list: ListState) {
+        let git = self.git.clone();
+        let label = format!("Load changelog for {}", input.name());
+
+        let job = Job::new(label, move |ctx| {
+            let (git, input, parent_list) = (git.clone(), input.clone(), parent_list.clone());
+            async move {
+                let page_input = Box::new(input.clone());
+                let on_page: ChangelogProgress = Arc::new(move |offset, commits: &[Commit]| {
+                    ctx.send(TaskResult::ChangelogPage {
+                        input: page_input.clone(),
+                        offset,
+                        commits: commits.to_vec(),
+                    });
+                });
+                let result = git.get_changelog_streaming(&input, on_page).await;
+                let transient = matches!(result, Err(GitError::NetworkError(_)));
+                let result = result.map(|data| ChangelogLoadedData {
                     input,
                     data,
                     parent_list,
+                });
+                match outcome(result, |result| {
+                    TaskResult::ChangelogLoaded(Box::new(result))
+                }) {
+                    JobOutcome::Failed(result, error) if transient => {
+                        JobOutcome::Transient(result, error)
+                    }
+                    outcome => outcome,
                 }
-            }))));
-        });
+            }
+        })
+        .on_cancel(TaskResult::ChangelogLoaded(Box::new(Err(
+            GitError::CloneFailed("Cancelled".to_string()),
+        ))));
+        self.scheduler.submit(job.with_retries(CHANGELOG_RETRIES));
     }
 
-    fn spawn_lock(&self, path: PathBuf, name: String, lock_url: String) {
-        let nix = self.nix.clone();
-        let tx = self.task_tx.clone();
+    fn spawn_load_tags(&mut self, input: GitInput) {
+        let git = self.git.clone();
+        let label = format!("Load tags for {}", input.name());
+        let cancelled = TaskResult::TagsLoaded {
+            name: input.input_name().clone(),
+            tags: Err(GitError::CloneFailed("Cancelled".to_string())),
+        };
 
-        tokio::spawn(async move {
-            let result = nix.lock_input(&path, &name, &lock_url).await;
-            let _ = tx.send(TaskResult::LockComplete(result));
-        });
+        let job = Job::new(label, move |_| {
+            let (git, input) = (git.clone(), input.clone());
+            async move {
+                let name = input.input_name().clone();
+                outcome(git.tags(&input).await, |tags| TaskResult::TagsLoaded {
+                    name,
+                    tags,
+                })
+            }
+        })
+        .on_cancel(cancelled);
+        self.scheduler.submit(job);
     }
 
-    fn spawn_check_updates(&self, inputs: Vec<GitInput>) {
+    fn spawn_load_commit_detail(&mut self, input: GitInput, sha: String) {
         let git = self.git.clone();
-        let tx = self.task_tx.clone();
+        let label = format!(
+            "Load commit {} of {}",
+            &sha[..7.min(sha.len())],
+            input.name()
+        );
+        let cancelled = TaskResult::CommitDetailLoaded {
+            name: input.input_name().clone(),
+            sha: sha.clone(),
+            detail: Box::new(Err(GitError::CloneFailed("Cancelled".to_string()))),
+        };
 
-        tokio::spawn(async move {
-            let _ = git
-                .check_updates(&inputs, |name, status| {
-                    let _ = tx.send(TaskResult::InputStatus { name, status });
+        let job = Job::new(label, move |_| {
+            let (git, input, sha) = (git.clone(), input.clone(), sha.clone());
+            async move {
+                let name = input.input_name().clone();
+                let detail = git.commit_detail(&input, &sha).await;
+                outcome(detail, |detail| TaskResult::CommitDetailLoaded {
+                    name,
+                    sha,
+                    detail: Box::new(detail),
                 })
-                .await;
+            }
+        })
+        .on_cancel(cancelled);
+        self.scheduler.submit(job);
+    }
+
+    fn spawn_find_pull_request(&mut self, input: GitInput, sha: String) {
+        let git = self.git.clone();
+        let label = format!(
+            "Find pull request for {} of {}",
+            &sha[..7.min(sha.len())],
+            input.name()
+        );
+        let cancelled = TaskResult::PullRequestFound {
+            sha: sha.clone(),
+            url: Err(GitError::CloneFailed("Cancelled".to_string())),
+        };
+
+        let job = Job::new(label, move |_| {
+            let (git, input, sha) = (git.clone(), input.clone(), sha.clone());
+            async move {
+                let url = git.pull_request_url(&input, &sha).await;
+                outcome(url, |url| TaskResult::PullRequestFound { sha, url })
+            }
+        })
+        .on_cancel(cancelled);
+        self.scheduler.submit(job);
+    }
+
+    fn spawn_load_diff_stats(
+        &mut self,
+        input: GitInput,
+        base: String,
+        head: String,
+        paths: Vec<String>,
+    ) {
+        let git = self.git.clone();
+        let label = format!(
+            "Compare {} up to {}",
+            input.name(),
+            &head[..7.min(head.len())]
+        );
+        let cancelled = TaskResult::DiffStatsLoaded {
+            name: input.input_name().clone(),
+            head: head.clone(),
+            stats: Err(GitError::CloneFailed("Cancelled".to_string())),
+        };
+
+        let job = Job::new(label, move |_| {
+            let (git, input, base, head, paths) = (
+                git.clone(),
+                input.clone(),
+                base.clone(),
+                head.clone(),
+                paths.clone(),
+            );
+            async move {
+                let name = input.input_name().clone();
+                let stats = git.diff_stats(&input, &base, &head, &paths).await;
+                outcome(stats, |stats| TaskResult::DiffStatsLoaded {
+                    name,
+                    head,
+                    stats,
+                })
+            }
+        })
+        .on_cancel(cancelled);
+        self.scheduler.submit(job);
+    }
+
+    fn spawn_load_release(&mut self, input: GitInput, tag: String) {
+        let git = self.git.clone();
+        let label = format!("Load release notes for {} {}", input.name(), tag);
+        let cancelled = TaskResult::ReleaseLoaded {
+            name: input.input_name().clone(),
+            tag: tag.clone(),
+            release: Err(GitError::CloneFailed("Cancelled".to_string())),
+        };
+
+        let job = Job::new(label, move |_| {
+            let (git, input, tag) = (git.clone(), input.clone(), tag.clone());
+            async move {
+                let name = input.input_name().clone();
+                let release = git.release(&input, &tag).await;
+                outcome(release, |release| TaskResult::ReleaseLoaded {
+                    name,
+                    tag,
+                    release,
+                })
+            }
+        })
+        .on_cancel(cancelled);
+        self.scheduler.submit(job);
+    }
+
+    /// Commit flake.lock after an update, when auto-commit is on
+    fn spawn_commit_lock(&mut self, path: PathBuf) {
+        let template = match self.nix.commit_template(&path) {
+            Ok(Some(template)) => template,
+            Ok(None) => return,
+            Err(e) => {
+                warn!(error = %e, "Failed to read auto_commit");
+                return;
+            }
+        };
+        let nix = self.nix.clone();
+
+        let job = Job::new("Commit flake.lock", move |_| {
+            let (nix, path, template) = (nix.clone(), path.clone(), template.clone());
+            async move {
+                outcome(
+                    nix.commit_lock(&path, template).await,
+                    TaskResult::LockCommitted,
+                )
+            }
+        })
+        .on_cancel(TaskResult::LockCommitted(Err(cancelled())));
+        self.scheduler.submit(job.exclusive());
+    }
+
+    /// Read the nix version up front, so an unsupported nix is reported
+    /// before the first update fails on it
+    fn spawn_check_nix_version(&mut self) {
+        let nix = self.nix.clone();
+
+        let job = Job::new("Detect nix version", move |_| {
+            let nix = nix.clone();
+            async move {
+                outcome(
+                    nix.nix_version().await.map(|_| ()),
+                    TaskResult::NixVersionChecked,
+                )
+            }
+        })
+        .on_cancel(TaskResult::NixVersionChecked(Err(cancelled())));
+        self.scheduler.submit(job);
+    }
+
+    fn spawn_open_pull_request(&mut self, path: PathBuf, branch: String) {
+        let nix = self.nix.clone();
+        let changelogs = self.changelogs.clone();
+
+        let job = Job::new("Open pull request", move |_| {
+            let (nix, path, branch, changelogs) = (
+                nix.clone(),
+                path.clone(),
+                branch.clone(),
+                changelogs.clone(),
+            );
+            async move {
+                outcome(
+                    nix.open_pull_request(&path, branch, changelogs).await,
+                    TaskResult::PullRequestOpened,
+                )
+            }
+        })
+        .on_cancel(TaskResult::PullRequestOpened(Err(cancelled())));
+        self.scheduler.submit(job.exclusive());
+    }
+
+    fn spawn_lock(&mut self, path: PathBuf, name: String, lock_url: String) {
+        let nix = self.nix.clone();
+        let label = format!("Lock {}", name);
+
+        let job = Job::new(label, move |_| {
+            let (nix, path, name, lock_url) =
+                (nix.clone(), path.clone(), name.clone(), lock_url.clone());
+            async move {
+                outcome(
+                    nix.lock_input(&path, &name, &lock_url).await,
+                    TaskResult::LockComplete,
+                )
+            }
+        })
+        .on_cancel(TaskResult::LockComplete(Err(cancelled())));
+        self.scheduler.submit(job.exclusive());
+    }
+
+    fn spawn_check_updates(&mut self, flake: PathBuf, inputs: Vec<GitInput>) {
+        let git = self.git.clone();
+        let label = format!("Check {} input(s) for updates", inputs.len());
+
+        let job = Job::new(label, move |ctx| {
+            let (git, flake, inputs) = (git.clone(), flake.clone(), inputs.clone());
+            async move {
+                let _ = git
+                    .check_updates(&inputs, |name, status, info| {
+                        ctx.send(TaskResult::InputStatus {
+                            flake: flake.clone(),
+                            name,
+                            status,
+                            clone_path: info.clone_path,
+                        });
+                    })
+                    .await;
+                JobOutcome::Done(None)
+            }
+        })
+        .on_cancel(TaskResult::ChecksCancelled);
+        self.scheduler.submit(job);
+    }
+
+    /// Suggest a GitHub token, once, before checks of many GitHub inputs
+    /// exhaust the unauthenticated rate limit
+    /// Add a line to the announcements, in accessible mode
+    fn announce(&mut self, line: impl Into<String>) {
+        if let Some(announcements) = &mut self.announcements {
+            announcements.push(line);
+        }
+    }
+
+    /// Announce the status message if it changed since the last one
+    fn announce_status(&mut self) {
+        let text = self.toasts.latest().map(|msg| msg.text.clone());
+        if text != self.announced_status {
+            if let Some(text) = &text {
+                self.announce(text.clone());
+            }
+            self.announced_status = text;
+        }
+    }
+
+    fn open_url(&mut self, url: &str) {
+        self.notify(match browser::open(url) {
+            Ok(()) => StatusMessage::info(format!("Opened {}", url)),
+            Err(e) => StatusMessage::error(format!("Failed to open {}: {}", url, e)),
         });
     }
 
+    /// Show a status message and keep it in the activity log
+    fn notify(&mut self, message: StatusMessage) {
+        self.activity.record_message(&message);
+        self.toasts.push(message);
+    }
+
+    fn hint_github_token(&mut self, inputs: &[GitInput]) {
+        let github_inputs = inputs
+            .iter()
+            .filter(|input| matches!(input.repo(), GitRepo::GitHub { .. }))
+            .count();
+        if !self.token_hint
+            || github_inputs < TOKEN_HINT_INPUTS
+            || !self.git.uses_anonymous_github_api()
+        {
+            return;
+        }
+        self.token_hint = false;
+        self.notify(StatusMessage::hint(
+            "Set GITHUB_TOKEN to avoid rate limits — 60 req/h unauthenticated (any key to dismiss)",
+        ));
+    }
+
+    /// Start a watch-mode update check once the interval has elapsed.
+    ///
+    /// Checks only start from an idle list view; otherwise the check waits
+    /// until the user returns to it.
+    fn poll_watch(&mut self) {
+        if self.digest_dir.is_some() && self.digest.is_due(Utc::now()) {
+            self.digest = Digest::new(self.digest.ends());
+            self.write_digest();
+        }
+
+        if !self.scheduler.periodic_due(Instant::now()) {
+            return;
+        }
+        let AppState::List(list) = &self.state else {
+            return;
+        };
+        if list.mode != ListMode::Idle || list.command_line.is_some() {
+            return;
+        }
+
+        let mut checks = vec![(list.flake.path.clone(), git_inputs(&list.flake))];
+        checks.extend(
+            self.tabs
+                .hidden_lists_mut()
+                .filter(|list| list.mode == ListMode::Idle)
+                .map(|list| (list.flake.path.clone(), git_inputs(&list.flake))),
+        );
+        for (flake, inputs) in checks {
+            debug!(flake = ?flake, inputs = inputs.len(), "Watch mode re-check");
+            self.spawn_recheck_updates(flake, inputs);
+        }
+        self.scheduler.reset_periodic();
+    }
+
+    /// Re-read the config file about once a second if it changed since it
+    /// was last read
+    fn poll_config_reload(&mut self) {
+        if self.tick_count % 60 != 0 {
+            return;
+        }
+        let Some(path) = &self.config_file else {
+            return;
+        };
+        let modified = modified_time(path);
+        if modified == self.config_modified {
+            return;
+        }
+        self.config_modified = modified;
+
+        match config::load_from(path) {
+            Ok(config) => {
+                debug!(path = %path.display(), "Reloaded config file");
+                self.apply_config(config);
+                self.notify(StatusMessage::success("Config reloaded"));
+            }
+            Err(err) => {
+                warn!(error = %err, "Config file not reloaded");
+                self.notify(StatusMessage::error(format!(
+                    "{} (keeping previous settings)",
+                    err
+                )));
+            }
+        }
+    }
+
+    /// Apply the settings of a reloaded config file that can change while
+    /// running. Command-line overrides only affect settings that cannot.
+    fn apply_config(&mut self, config: Config) {
+        self.columns = config.columns;
+        self.theme = config.theme;
+        self.sort = config.sort;
+        self.auto_refresh = config.refresh_interval;
+        self.nix.set_timeouts(&config.service.timeouts);
+        self.git.set_timeouts(config.service.timeouts);
+        if let AppState::List(list) = &mut self.state {
+            if list.sort != self.sort {
+                list.sort = self.sort;
+                list.sort_inputs();
+            }
+        }
+    }
+
+    /// Reload the flake once a full auto-refresh interval has passed since
+    /// the last load, waiting for the list view to be idle
+    fn poll_auto_refresh(&mut self) {
+        let Some(interval) = self.auto_refresh else {
+            return;
+        };
+        if self.last_load.elapsed() < interval || self.tasks_pane.is_some() {
+            return;
+        }
+        let AppState::List(list) = &mut self.state else {
+            return;
+        };
+        if list.mode != ListMode::Idle || list.command_line.is_some() {
+            return;
+        }
+
+        debug!("Auto-refreshing flake");
+        list.mode = ListMode::Refreshing;
+        self.spawn_load_flake();
+    }
+
+    /// Re-run update checks without resetting statuses to `Checking`, so the
+    /// list keeps showing the previous results until new ones arrive
+    fn spawn_recheck_updates(&mut self, flake: PathBuf, inputs: Vec<GitInput>) {
+        let git = self.git.clone();
+        let label = format!("Re-check {} input(s) for updates", inputs.len());
+
+        let job = Job::new(label, move |ctx| {
+            let (git, flake, inputs) = (git.clone(), flake.clone(), inputs.clone());
+            async move {
+                let _ = git
+                    .check_updates_timed(&inputs, |name, status, info| {
+                        ctx.send(TaskResult::InputStatus {
+                            flake: flake.clone(),
+                            name,
+                            status,
+                            clone_path: info.clone_path,
+                        });
+                    })
+                    .await;
+                JobOutcome::Done(None)
+            }
+        })
+        .on_cancel(TaskResult::ChecksCancelled);
+        self.scheduler.submit(job);
+    }
+
+    /// Write the current digest and its state, replacing the files for
+    /// its period
+    fn write_digest(&self) {
+        let Some(dir) = &self.digest_dir else {
+            return;
+        };
+        let markdown = self
+            .digest
+            .render_markdown(&discovery_root(&self.flake_path));
+        for (name, contents) in [
+            (self.digest.file_name(), markdown),
+            (self.digest.state_file_name(), self.digest.to_json()),
+        ] {
+            let path = dir.join(name);
+            if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(&path, contents)) {
+                warn!(path = %path.display(), error = %e, "Failed to write digest");
+            }
+        }
+    }
+
     /// Close commit history and return to list
     fn close_changelog(&mut self) {
         if let AppState::Changelog(cs) = std::mem::replace(&mut self.state, AppState::Loading) {
+            self.changelogs.insert(cs.input.name().to_string(), cs.data);
             self.state = AppState::List(cs.parent_list);
         }
     }
 }
+
+/// Error delivered for tasks the user cancelled
+/// Build `installable` before an update so its closure can be compared with
+/// the one after, reporting the store path to the app. Failing to build
+/// only skips the comparison.
+async fn build_closure_before(
+    nix: &NixService,
+    path: &Path,
+    installable: Option<String>,
+    ctx: &TaskContext,
+) {
+    let Some(installable) = installable else {
+        return;
+    };
+    match nix.build_output(path, &installable, |_| {}).await {
+        Ok(paths) => {
+            if let Some(before) = paths.into_iter().next() {
+                ctx.send(TaskResult::ClosureBuilt {
+                    installable,
+                    before,
+                });
+            }
+        }
+        Err(e) => warn!(error = %e, "Failed to build the closure before updating"),
+    }
+}
+
+fn cancelled() -> AppError {
+    AppError::NixCommandFailed("Operation cancelled".to_string())
+}
+
+/// Deliver `result` wrapped by `wrap`, marking the task failed on errors
+fn outcome<T, E: std::fmt::Display>(
+    result: Result<T, E>,
+    wrap: impl FnOnce(Result<T, E>) -> TaskResult,
+) -> JobOutcome {
+    match result.as_ref().err().map(ToString::to_string) {
+        Some(error) => JobOutcome::Failed(wrap(result), error),
+        None => JobOutcome::Done(Some(wrap(result))),
+    }
+}
+
+/// Git inputs of `flake`, the ones update checks look at
+fn git_inputs(flake: &FlakeData) -> Vec<GitInput> {
+    flake
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FlakeInput::Git(git_input) => Some(git_input.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Last modification time of `path`, or `None` if it does not exist
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Record inputs whose locked revision differs between two loads of the same flake
+fn record_applied_updates(
+    digest: &mut Digest,
+    before: &FlakeData,
+    after: &FlakeData,
+    renames: &[(String, String)],
+) {
+    let now = Utc::now();
+    for input in &after.inputs {
+        let Some(to) = input.short_rev() else {
+            continue;
+        };
+        let old_name = renames
+            .iter()
+            .find(|(_, new)| new == input.name())
+            .map_or(input.name(), |(old, _)| old.as_str());
+        let from = before
+            .inputs
+            .iter()
+            .find(|old| old.name() == old_name)
+            .and_then(FlakeInput::short_rev);
+        if let Some(from) = from.filter(|from| *from != to) {
+            digest.record_applied(input.name(), from, to, now);
+        }
+    }
+}
@@ -0,0 +1,581 @@
+//! Background task scheduler
+//!
+//! Every background operation goes through the [`Scheduler`] instead of a
+//! bare `tokio::spawn`, so the app can:
+//! - see what is queued, running, and recently finished
+//! - queue operations that rewrite `flake.lock` so they never overlap
+//! - retry transient failures with backoff
+//! - run the periodic watch-mode check on a timer
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use super::state::TaskResult;
+
+/// Number of finished tasks kept for introspection
+const FINISHED_HISTORY: usize = 20;
+
+/// Delay before the first retry; doubled for each further attempt
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// How one attempt of a job ended
+#[derive(Debug)]
+pub enum JobOutcome {
+    /// The job succeeded; the result, if any, is delivered to the app
+    Done(Option<TaskResult>),
+    /// The job failed; the result is delivered as is
+    Failed(TaskResult, String),
+    /// The job failed in a way that may pass on retry. The result is only
+    /// delivered once no retries are left.
+    Transient(TaskResult, String),
+}
+
+/// Handle a running job uses to report results and notice cancellation
+#[derive(Debug, Clone)]
+pub struct TaskContext {
+    results: mpsc::UnboundedSender<TaskResult>,
+    cancel: CancellationToken,
+}
+
+impl TaskContext {
+    /// Deliver an intermediate result, e.g. one input's update status
+    pub fn send(&self, result: TaskResult) {
+        let _ = self.results.send(result);
+    }
+
+    /// Token cancelled when the task is cancelled
+    pub fn cancel_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+}
+
+type JobFuture = Pin<Box<dyn Future<Output = JobOutcome> + Send>>;
+type JobFn = Box<dyn Fn(TaskContext) -> JobFuture + Send>;
+
+/// A unit of background work
+pub struct Job {
+    label: String,
+    exclusive: bool,
+    retries: u32,
+    on_cancel: Option<TaskResult>,
+    run: JobFn,
+}
+
+impl Job {
+    /// A job that runs `run` once, described by `label` in the task list
+    pub fn new<F, Fut>(label: impl Into<String>, run: F) -> Self
+    where
+        F: Fn(TaskContext) -> Fut + Send + 'static,
+        Fut: Future<Output = JobOutcome> + Send + 'static,
+    {
+        Self {
+            label: label.into(),
+            exclusive: false,
+            retries: 0,
+            on_cancel: None,
+            run: Box::new(move |ctx| Box::pin(run(ctx))),
+        }
+    }
+
+    /// Queue behind other exclusive jobs instead of running alongside them.
+    ///
+    /// Used for operations that rewrite `flake.lock`.
+    pub fn exclusive(mut self) -> Self {
+        self.exclusive = true;
+        self
+    }
+
+    /// Retry transient failures up to `retries` times
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Deliver `result` if the task is cancelled, so the app can leave
+    /// whatever state was waiting on it
+    pub fn on_cancel(mut self, result: TaskResult) -> Self {
+        self.on_cancel = Some(result);
+        self
+    }
+}
+
+/// Identifier of a submitted task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+/// Where a task is in its lifecycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    /// Waiting to retry after a transient failure
+    Retrying {
+        attempt: u32,
+        error: String,
+    },
+    Succeeded,
+    Failed(String),
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Succeeded | TaskStatus::Failed(_) | TaskStatus::Cancelled
+        )
+    }
+}
+
+/// A submitted task, as shown in the task list
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub id: TaskId,
+    pub label: String,
+    pub status: TaskStatus,
+    pub exclusive: bool,
+    pub queued_at: Instant,
+    pub started_at: Option<Instant>,
+    pub finished_at: Option<Instant>,
+    cancel: CancellationToken,
+}
+
+impl Task {
+    /// Time spent running, up to now for tasks that have not finished
+    pub fn elapsed(&self, now: Instant) -> Option<Duration> {
+        let started = self.started_at?;
+        Some(
+            self.finished_at
+                .unwrap_or(now)
+                .saturating_duration_since(started),
+        )
+    }
+}
+
+/// Progress reported by running tasks
+#[derive(Debug)]
+enum TaskEvent {
+    Retrying {
+        id: TaskId,
+        attempt: u32,
+        error: String,
+    },
+    Finished {
+        id: TaskId,
+        status: TaskStatus,
+    },
+}
+
+/// Periodic timer for watch mode
+#[derive(Debug, Clone, Copy)]
+struct Periodic {
+    interval: Duration,
+    next_due: Instant,
+}
+
+/// Runs background jobs and keeps track of them
+pub struct Scheduler {
+    results: mpsc::UnboundedSender<TaskResult>,
+    events_tx: mpsc::UnboundedSender<TaskEvent>,
+    events_rx: mpsc::UnboundedReceiver<TaskEvent>,
+    /// Parent of every task's cancellation token
+    cancel: CancellationToken,
+    tasks: Vec<Task>,
+    /// Exclusive jobs waiting for the running one to finish
+    queue: VecDeque<(TaskId, Job)>,
+    /// Exclusive job currently running, if any
+    exclusive_running: Option<TaskId>,
+    periodic: Option<Periodic>,
+    retry_delay: Duration,
+    next_id: u64,
+}
+
+impl Scheduler {
+    /// Create a scheduler delivering job results to `results`.
+    ///
+    /// Cancelling `cancel` cancels every task.
+    pub fn new(results: mpsc::UnboundedSender<TaskResult>, cancel: CancellationToken) -> Self {
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        Self {
+            results,
+            events_tx,
+            events_rx,
+            cancel,
+            tasks: Vec::new(),
+            queue: VecDeque::new(),
+            exclusive_running: None,
+            periodic: None,
+            retry_delay: RETRY_DELAY,
+            next_id: 0,
+        }
+    }
+
+    /// Submit a job, starting it now unless it has to queue
+    pub fn submit(&mut self, job: Job) -> TaskId {
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+        debug!(task = %job.label, exclusive = job.exclusive, "Submitting task");
+
+        self.tasks.push(Task {
+            id,
+            label: job.label.clone(),
+            status: TaskStatus::Queued,
+            exclusive: job.exclusive,
+            queued_at: Instant::now(),
+            started_at: None,
+            finished_at: None,
+            cancel: self.cancel.child_token(),
+        });
+
+        if job.exclusive && self.exclusive_running.is_some() {
+            self.queue.push_back((id, job));
+        } else {
+            self.start(id, job);
+        }
+        id
+    }
+
+    /// Process progress from running tasks and start queued ones.
+    ///
+    /// Call once per frame.
+    pub fn poll(&mut self) {
+        while let Ok(event) = self.events_rx.try_recv() {
+            match event {
+                TaskEvent::Retrying { id, attempt, error } => {
+                    if let Some(task) = self.task_mut(id) {
+                        task.status = TaskStatus::Retrying { attempt, error };
+                    }
+                }
+                TaskEvent::Finished { id, status } => self.finish(id, status),
+            }
+        }
+    }
+
+    /// Cancel a queued or running task. Returns `false` if it already finished.
+    pub fn cancel(&mut self, id: TaskId) -> bool {
+        let Some(task) = self.task_mut(id) else {
+            return false;
+        };
+        if task.status.is_finished() {
+            return false;
+        }
+        task.cancel.cancel();
+        if task.status == TaskStatus::Queued {
+            if let Some(pos) = self.queue.iter().position(|(queued, _)| *queued == id) {
+                if let Some((_, job)) = self.queue.remove(pos) {
+                    if let Some(result) = job.on_cancel {
+                        let _ = self.results.send(result);
+                    }
+                }
+            }
+            self.finish(id, TaskStatus::Cancelled);
+        }
+        true
+    }
+
+    /// Every known task: queued and running ones, plus the most recently
+    /// finished, in submission order
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    /// Whether any task is queued or running
+    pub fn is_busy(&self) -> bool {
+        self.tasks.iter().any(|task| !task.status.is_finished())
+    }
+
+    /// Run the periodic check every `interval`, or never when `None`
+    pub fn set_periodic(&mut self, interval: Option<Duration>) {
+        self.periodic = interval.map(|interval| Periodic {
+            interval,
+            next_due: Instant::now() + interval,
+        });
+    }
+
+    /// Push the next periodic check a full interval into the future
+    pub fn reset_periodic(&mut self) {
+        if let Some(periodic) = &mut self.periodic {
+            periodic.next_due = Instant::now() + periodic.interval;
+        }
+    }
+
+    /// Whether the periodic check is due.
+    ///
+    /// The caller runs the check (or decides to wait) and then calls
+    /// [`reset_periodic`](Self::reset_periodic).
+    pub fn periodic_due(&self, now: Instant) -> bool {
+        self.periodic
+            .is_some_and(|periodic| now >= periodic.next_due)
+    }
+
+    fn task_mut(&mut self, id: TaskId) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|task| task.id == id)
+    }
+
+    fn start(&mut self, id: TaskId, mut job: Job) {
+        let results = self.results.clone();
+        let Some(task) = self.task_mut(id) else {
+            return;
+        };
+        task.status = TaskStatus::Running;
+        task.started_at = Some(Instant::now());
+        let ctx = TaskContext {
+            results,
+            cancel: task.cancel.clone(),
+        };
+        if job.exclusive {
+            self.exclusive_running = Some(id);
+        }
+
+        let events = self.events_tx.clone();
+        let retry_delay = self.retry_delay;
+        let on_cancel = job.on_cancel.take();
+        tokio::spawn(async move {
+            let status = run_attempts(id, job, ctx.clone(), &events, retry_delay).await;
+            if let (TaskStatus::Cancelled, Some(result)) = (&status, on_cancel) {
+                ctx.send(result);
+            }
+            let _ = events.send(TaskEvent::Finished { id, status });
+        });
+    }
+
+    fn finish(&mut self, id: TaskId, status: TaskStatus) {
+        if let Some(task) = self.task_mut(id) {
+            if let TaskStatus::Failed(error) = &status {
+                warn!(task = %task.label, error = %error, "Task failed");
+            }
+            task.status = status;
+            task.finished_at = Some(Instant::now());
+        }
+
+        if self.exclusive_running == Some(id) {
+            self.exclusive_running = None;
+            if let Some((next, job)) = self.queue.pop_front() {
+                self.start(next, job);
+            }
+        }
+
+        // Keep the history bounded, dropping the oldest finished tasks first.
+        let finished = self
+            .tasks
+            .iter()
+            .filter(|task| task.status.is_finished())
+            .count();
+        let mut excess = finished.saturating_sub(FINISHED_HISTORY);
+        self.tasks.retain(|task| {
+            if excess > 0 && task.status.is_finished() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+/// Run `job` until it succeeds, fails for good, or is cancelled
+async fn run_attempts(
+    id: TaskId,
+    job: Job,
+    ctx: TaskContext,
+    events: &mpsc::UnboundedSender<TaskEvent>,
+    retry_delay: Duration,
+) -> TaskStatus {
+    let mut attempt = 0;
+    loop {
+        let outcome = tokio::select! {
+            outcome = (job.run)(ctx.clone()) => outcome,
+            _ = ctx.cancel.cancelled() => return TaskStatus::Cancelled,
+        };
+
+        match outcome {
+            JobOutcome::Done(result) => {
+                if let Some(result) = result {
+                    ctx.send(result);
+                }
+                return TaskStatus::Succeeded;
+            }
+            JobOutcome::Transient(_, error) if attempt < job.retries => {
+                attempt += 1;
+                debug!(task = %job.label, attempt, error = %error, "Retrying task");
+                let _ = events.send(TaskEvent::Retrying { id, attempt, error });
+                tokio::select! {
+                    _ = tokio::time::sleep(retry_delay * 2u32.pow(attempt - 1)) => {}
+                    _ = ctx.cancel.cancelled() => return TaskStatus::Cancelled,
+                }
+            }
+            JobOutcome::Failed(result, error) | JobOutcome::Transient(result, error) => {
+                ctx.send(result);
+                return TaskStatus::Failed(error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::error::AppError;
+
+    fn scheduler() -> (Scheduler, mpsc::UnboundedReceiver<TaskResult>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut scheduler = Scheduler::new(tx, CancellationToken::new());
+        scheduler.retry_delay = Duration::from_millis(1);
+        (scheduler, rx)
+    }
+
+    /// Poll until `done` holds, failing the test after a second
+    async fn poll_until(scheduler: &mut Scheduler, done: impl Fn(&Scheduler) -> bool) {
+        for _ in 0..1000 {
+            scheduler.poll();
+            if done(scheduler) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        panic!("scheduler did not settle: {:?}", scheduler.tasks());
+    }
+
+    fn status(scheduler: &Scheduler, id: TaskId) -> TaskStatus {
+        scheduler
+            .tasks()
+            .iter()
+            .find(|task| task.id == id)
+            .map(|task| task.status.clone())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn exclusive_jobs_queue_behind_each_other() {
+        let (mut scheduler, mut rx) = scheduler();
+        let release = Arc::new(Notify::new());
+
+        let gate = release.clone();
+        let first = scheduler.submit(
+            Job::new("update", move |_| {
+                let gate = gate.clone();
+                async move {
+                    gate.notified().await;
+                    JobOutcome::Done(Some(TaskResult::UpdateComplete(Ok(()))))
+                }
+            })
+            .exclusive(),
+        );
+        let second = scheduler.submit(
+            Job::new("lock", |_| async {
+                JobOutcome::Done(Some(TaskResult::LockComplete(Ok(()))))
+            })
+            .exclusive(),
+        );
+        let parallel = scheduler.submit(Job::new("load", |_| async { JobOutcome::Done(None) }));
+
+        poll_until(&mut scheduler, |s| {
+            status(s, parallel) == TaskStatus::Succeeded
+        })
+        .await;
+        assert_eq!(status(&scheduler, first), TaskStatus::Running);
+        assert_eq!(status(&scheduler, second), TaskStatus::Queued);
+
+        release.notify_one();
+        poll_until(&mut scheduler, |s| !s.is_busy()).await;
+        assert!(matches!(
+            rx.recv().await,
+            Some(TaskResult::UpdateComplete(_))
+        ));
+        assert!(matches!(rx.recv().await, Some(TaskResult::LockComplete(_))));
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() {
+        let (mut scheduler, mut rx) = scheduler();
+        let attempts = Arc::new(AtomicU32::new(0));
+
+        let counter = attempts.clone();
+        let id = scheduler.submit(
+            Job::new("changelog", move |_| {
+                let attempt = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if attempt < 2 {
+                        JobOutcome::Transient(
+                            TaskResult::UpdateComplete(Err(AppError::Output("net".into()))),
+                            "net".to_string(),
+                        )
+                    } else {
+                        JobOutcome::Done(Some(TaskResult::UpdateComplete(Ok(()))))
+                    }
+                }
+            })
+            .with_retries(2),
+        );
+
+        poll_until(&mut scheduler, |s| !s.is_busy()).await;
+        assert_eq!(status(&scheduler, id), TaskStatus::Succeeded);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        // Failed attempts are not delivered; only the final result is.
+        assert!(matches!(
+            rx.recv().await,
+            Some(TaskResult::UpdateComplete(Ok(())))
+        ));
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_running_task_stops_it() {
+        let (mut scheduler, mut rx) = scheduler();
+        let id = scheduler.submit(Job::new("slow", |_| async {
+            std::future::pending::<()>().await;
+            JobOutcome::Done(None)
+        }));
+
+        assert!(scheduler.cancel(id));
+        poll_until(&mut scheduler, |s| !s.is_busy()).await;
+        assert_eq!(status(&scheduler, id), TaskStatus::Cancelled);
+        assert!(!scheduler.cancel(id));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn cancelled_tasks_deliver_their_cancel_result() {
+        let (mut scheduler, mut rx) = scheduler();
+        let pending = || async {
+            std::future::pending::<()>().await;
+            JobOutcome::Done(None)
+        };
+        let cancelled = || TaskResult::UpdateComplete(Err(AppError::Output("cancelled".into())));
+
+        let running = scheduler.submit(
+            Job::new("update", move |_| pending())
+                .exclusive()
+                .on_cancel(cancelled()),
+        );
+        let queued = scheduler.submit(
+            Job::new("lock", move |_| pending())
+                .exclusive()
+                .on_cancel(cancelled()),
+        );
+
+        assert!(scheduler.cancel(queued));
+        assert_eq!(status(&scheduler, queued), TaskStatus::Cancelled);
+        assert!(matches!(
+            rx.recv().await,
+            Some(TaskResult::UpdateComplete(Err(_)))
+        ));
+
+        assert!(scheduler.cancel(running));
+        poll_until(&mut scheduler, |s| !s.is_busy()).await;
+        assert!(matches!(
+            rx.recv().await,
+            Some(TaskResult::UpdateComplete(Err(_)))
+        ));
+    }
+}
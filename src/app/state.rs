@@ -3,12 +3,20 @@
 //! This module contains all the state types used by the application,
 //! including the main AppState enum and view-specific states.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use ratatui::widgets::TableState;
 
+use super::scheduler::Task;
 use crate::error::{AppError, GitError};
-use crate::model::{ChangelogData, FlakeData, GitInput, GitRev, InputName, UpdateStatus};
+use crate::model::{
+    ChangelogData, ChangelogSummary, ClosureDiff, Commit, CommitDetail, DiffStats, DuplicateInput,
+    FlakeData, FlakeInput, FlakeTemplate, GitInput, GitRev, InputFilter, InputName, InputTree,
+    LockRevision, Release, SortOrder, Tag, TreeLine, UpdateStatus,
+};
 
 /// Application state machine
 #[derive(Debug)]
@@ -17,12 +25,28 @@ pub enum AppState {
     Loading,
     /// Error occurred
     Error(String),
+    /// No flake.nix exists at the opened path
+    NoFlake(PathBuf),
     /// Showing list of inputs
     List(ListState),
     /// Showing changelog for an input
     Changelog(Box<ChangelogState>),
     /// Loading changelog (keep parent list for display)
     LoadingChangelog(ListState),
+    /// Choosing between flakes discovered in the repository
+    FlakePicker(FlakePickerState),
+    /// Choosing a template to initialize a new flake from
+    TemplatePicker(TemplatePickerState),
+    /// Browsing the git history of flake.lock
+    LockHistory(Box<LockHistoryState>),
+    /// Choosing the branch an input tracks
+    BranchPicker(Box<BranchPickerState>),
+    /// Auditing which transitive inputs follow which
+    FollowsGraph(Box<FollowsGraphState>),
+    /// Building the configured outputs
+    Build(Box<BuildState>),
+    /// Package versions changed by the last nixpkgs update
+    ClosureDiff(Box<ClosureDiffState>),
     /// Quitting
     Quitting,
 }
@@ -33,9 +57,17 @@ impl AppState {
         match self {
             AppState::Loading => StateKind::Loading,
             AppState::Error(_) => StateKind::Error,
+            AppState::NoFlake(_) => StateKind::NoFlake,
             AppState::List(_) => StateKind::List,
             AppState::Changelog(_) => StateKind::Changelog,
             AppState::LoadingChangelog(_) => StateKind::LoadingChangelog,
+            AppState::FlakePicker(_) => StateKind::FlakePicker,
+            AppState::TemplatePicker(_) => StateKind::TemplatePicker,
+            AppState::LockHistory(_) => StateKind::LockHistory,
+            AppState::BranchPicker(_) => StateKind::BranchPicker,
+            AppState::FollowsGraph(_) => StateKind::FollowsGraph,
+            AppState::Build(_) => StateKind::Build,
+            AppState::ClosureDiff(_) => StateKind::ClosureDiff,
             AppState::Quitting => StateKind::Quitting,
         }
     }
@@ -46,9 +78,17 @@ impl AppState {
 pub enum StateKind {
     Loading,
     Error,
+    NoFlake,
     List,
     Changelog,
     LoadingChangelog,
+    FlakePicker,
+    TemplatePicker,
+    LockHistory,
+    BranchPicker,
+    FollowsGraph,
+    Build,
+    ClosureDiff,
     Quitting,
 }
 
@@ -75,6 +115,15 @@ impl ListCursor {
     }
 }
 
+/// One row of the list view: a root input, or an input it declares while
+/// it is expanded
+#[derive(Debug)]
+pub enum ListRow<'a> {
+    /// Index into the flake's inputs
+    Input(usize),
+    Transitive(TreeLine<'a>),
+}
+
 /// Current operation mode for the list view.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ListMode {
@@ -90,6 +139,129 @@ impl ListMode {
     }
 }
 
+/// How many view changes the undo stack remembers
+const UNDO_LIMIT: usize = 50;
+
+/// View state that undo and redo restore
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ViewSnapshot {
+    pub selected: HashSet<InputName>,
+    /// Query of the `/` search narrowing the list
+    pub search: Option<String>,
+    pub sort: SortOrder,
+}
+
+/// Undo and redo history for list view state.
+///
+/// This only covers how inputs are viewed and selected, including the `/`
+/// search and sort order; lock file changes are
+/// not undone here.
+#[derive(Debug, Clone, Default)]
+pub struct UndoStack {
+    undo: Vec<ViewSnapshot>,
+    redo: Vec<ViewSnapshot>,
+}
+
+impl UndoStack {
+    /// Remember `previous` before a change, dropping any redo history
+    pub fn record(&mut self, previous: ViewSnapshot) {
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(previous);
+        self.redo.clear();
+    }
+
+    /// Step back from `current`, returning the state to restore
+    pub fn undo(&mut self, current: ViewSnapshot) -> Option<ViewSnapshot> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// Step forward from `current`, returning the state to restore
+    pub fn redo(&mut self, current: ViewSnapshot) -> Option<ViewSnapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
+
+/// Completion candidates being cycled through with Tab
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// Text before the word being completed
+    pub base: String,
+    pub candidates: Vec<String>,
+    pub index: usize,
+}
+
+/// Incremental `/` search narrowing the list view to inputs whose name
+/// contains the query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputSearch {
+    pub query: String,
+    /// Whether keys are typed into the query rather than acting on the list
+    pub editing: bool,
+}
+
+impl InputSearch {
+    /// Whether the input named `name` is shown, ignoring case
+    pub fn matches(&self, name: &str) -> bool {
+        name.to_lowercase().contains(&self.query.to_lowercase())
+    }
+}
+
+/// Text typed at the `:` prompt in the list view
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandLine {
+    pub input: String,
+    /// Active completion, cleared by any key other than Tab
+    pub completion: Option<Completion>,
+}
+
+impl CommandLine {
+    /// Complete the last word with `candidates`.
+    ///
+    /// A single candidate is accepted directly; several start a cycle.
+    pub fn start_completion(&mut self, base: String, candidates: Vec<String>) {
+        match candidates.as_slice() {
+            [] => {}
+            [only] => {
+                self.input = format!("{}{}", base, only);
+                if !only.ends_with('=') {
+                    self.input.push(' ');
+                }
+            }
+            [first, ..] => {
+                self.input = format!("{}{}", base, first);
+                self.completion = Some(Completion {
+                    base,
+                    candidates,
+                    index: 0,
+                });
+            }
+        }
+    }
+
+    /// Move to the next (or previous) completion candidate
+    pub fn cycle_completion(&mut self, backwards: bool) {
+        let Some(completion) = self.completion.as_mut() else {
+            return;
+        };
+        let len = completion.candidates.len();
+        completion.index = if backwards {
+            (completion.index + len - 1) % len
+        } else {
+            (completion.index + 1) % len
+        };
+        self.input = format!(
+            "{}{}",
+            completion.base, completion.candidates[completion.index]
+        );
+    }
+}
+
 /// State for the list view
 #[derive(Debug)]
 pub struct ListState {
@@ -99,6 +271,33 @@ pub struct ListState {
     pub table_state: TableState,
     pub update_statuses: HashMap<InputName, UpdateStatus>,
     pub mode: ListMode,
+    /// Open `:` prompt, if any
+    pub command_line: Option<CommandLine>,
+    /// Undo history for selection, search and sort changes
+    pub history: UndoStack,
+    /// Inputs whose upstream gained commits since an earlier update check
+    pub new_updates: HashSet<InputName>,
+    /// When the flake was last loaded
+    pub refreshed_at: DateTime<Utc>,
+    /// Cached clones that update checks fell back to, by input
+    pub clone_paths: HashMap<InputName, PathBuf>,
+    /// Order of the inputs
+    pub sort: SortOrder,
+    /// Versions the inputs expose, by input name, for the `version` column
+    pub versions: HashMap<String, String>,
+    /// Inputs whose transitive inputs are shown below them
+    pub expanded: HashSet<InputName>,
+    /// Transitive row of the current input the cursor is on, counting from
+    /// 1 for the first row below it; 0 is the input itself
+    pub transitive_cursor: usize,
+    /// Whether the details of the input under the cursor are shown beside
+    /// the list
+    pub detail: bool,
+    /// Active `/` search, if any
+    pub search: Option<InputSearch>,
+    /// View before the `/` search being typed, recorded as one undo step
+    /// once typing stops
+    pub search_origin: Option<ViewSnapshot>,
 }
 
 impl ListState {
@@ -116,40 +315,246 @@ impl ListState {
             table_state,
             update_statuses: HashMap::new(),
             mode: ListMode::Idle,
+            command_line: None,
+            history: UndoStack::default(),
+            new_updates: HashSet::new(),
+            refreshed_at: Utc::now(),
+            clone_paths: HashMap::new(),
+            sort: SortOrder::default(),
+            versions: HashMap::new(),
+            expanded: HashSet::new(),
+            transitive_cursor: 0,
+            detail: false,
+            search: None,
+            search_origin: None,
         }
     }
 
-    /// Move cursor down
+    /// Order the inputs by `sort` instead of by name
+    pub fn with_sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self.sort_inputs();
+        self
+    }
+
+    /// Reorder the inputs by `self.sort`, keeping the cursor on the same input
+    pub fn sort_inputs(&mut self) {
+        let current = self
+            .cursor
+            .and_then(|cursor| self.flake.inputs.get(cursor.index()))
+            .map(|input| input.name().to_string());
+        let behind: HashMap<String, usize> = self
+            .flake
+            .inputs
+            .iter()
+            .filter_map(|input| match self.status_for(input)? {
+                UpdateStatus::Behind(count) => Some((input.name().to_string(), count.value())),
+                _ => None,
+            })
+            .collect();
+
+        self.sort.sort(&mut self.flake.inputs, |input| {
+            behind.get(input.name()).copied().unwrap_or(0)
+        });
+
+        if let Some(idx) = current.and_then(|name| {
+            self.flake
+                .inputs
+                .iter()
+                .position(|input| input.name() == name)
+        }) {
+            self.cursor = ListCursor::new(idx, self.flake.inputs.len());
+            self.table_state.select(Some(idx));
+        }
+    }
+
+    /// Move cursor down to the next row, stepping through the transitive
+    /// inputs of an expanded input before the next shown input
     pub fn cursor_down(&mut self) {
         let Some(cursor) = self.cursor else {
             return;
         };
-        let next = (cursor.index() + 1).min(self.flake.inputs.len().saturating_sub(1));
-        self.cursor = ListCursor::new(next, self.flake.inputs.len());
-        self.table_state.select(Some(next));
+        if self.transitive_row() < self.transitive_row_count(cursor.index()) {
+            self.transitive_cursor = self.transitive_row() + 1;
+            return;
+        }
+        if let Some(next) =
+            (cursor.index() + 1..self.flake.inputs.len()).find(|&i| self.is_shown(i))
+        {
+            self.move_cursor(next);
+        }
     }
 
-    /// Move cursor up
+    /// Move cursor up to the previous row, landing on the last transitive
+    /// input of an expanded input above
     pub fn cursor_up(&mut self) {
         let Some(cursor) = self.cursor else {
             return;
         };
-        let next = cursor.index().saturating_sub(1);
-        self.cursor = ListCursor::new(next, self.flake.inputs.len());
-        self.table_state.select(Some(next));
+        if self.transitive_row() > 0 {
+            self.transitive_cursor = self.transitive_row() - 1;
+            return;
+        }
+        if let Some(next) = (0..cursor.index()).rev().find(|&i| self.is_shown(i)) {
+            self.move_cursor(next);
+            self.transitive_cursor = self.transitive_row_count(next);
+        }
+    }
+
+    fn move_cursor(&mut self, idx: usize) {
+        self.cursor = ListCursor::new(idx, self.flake.inputs.len());
+        self.transitive_cursor = 0;
+        self.table_state.select(Some(idx));
+    }
+
+    /// Transitive row of the current input the cursor is on, 0 when it is
+    /// on the input itself
+    pub fn transitive_row(&self) -> usize {
+        self.current_index().map_or(0, |idx| {
+            self.transitive_cursor.min(self.transitive_row_count(idx))
+        })
+    }
+
+    /// Number of transitive rows shown below the input at `idx`
+    fn transitive_row_count(&self, idx: usize) -> usize {
+        self.flake
+            .inputs
+            .get(idx)
+            .map_or(0, |input| self.transitive_lines(input).len())
+    }
+
+    /// Transitive inputs shown below `input`, none unless it is expanded
+    fn transitive_lines(&self, input: &FlakeInput) -> Vec<TreeLine<'_>> {
+        if !self
+            .expanded
+            .iter()
+            .any(|name| name.as_str() == input.name())
+        {
+            return Vec::new();
+        }
+        let tree = self
+            .flake
+            .input_trees
+            .iter()
+            .find(|tree| tree.name == input.name());
+        // Lines of the input itself carry no guides; skip it.
+        tree.map(|tree| {
+            InputTree::lines(std::slice::from_ref(tree))
+                .into_iter()
+                .skip(1)
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    /// Whether the input at `idx` passes the `/` search
+    fn is_shown(&self, idx: usize) -> bool {
+        match (&self.search, self.flake.inputs.get(idx)) {
+            (Some(search), Some(input)) => search.matches(input.name()),
+            (None, input) => input.is_some(),
+            (_, None) => false,
+        }
+    }
+
+    /// Number of inputs the `/` search leaves shown
+    pub fn shown_count(&self) -> usize {
+        (0..self.flake.inputs.len())
+            .filter(|&idx| self.is_shown(idx))
+            .count()
+    }
+
+    /// Start typing a `/` search, continuing the current one if any
+    pub fn start_search(&mut self) {
+        if !self.search.as_ref().is_some_and(|search| search.editing) {
+            self.search_origin = Some(self.snapshot());
+        }
+        self.search.get_or_insert_with(InputSearch::default).editing = true;
+    }
+
+    /// Append `c` to the search query
+    pub fn push_search(&mut self, c: char) {
+        if let Some(search) = &mut self.search {
+            search.query.push(c);
+            self.follow_search();
+        }
+    }
+
+    /// Remove the last character of the search query
+    pub fn pop_search(&mut self) {
+        if let Some(search) = &mut self.search {
+            search.query.pop();
+            self.follow_search();
+        }
+    }
+
+    /// Stop typing, keeping the list narrowed unless the query is empty
+    pub fn finish_search(&mut self) {
+        if let Some(search) = &mut self.search {
+            search.editing = false;
+            if search.query.is_empty() {
+                self.search = None;
+            }
+        }
+        if let Some(before) = self.search_origin.take() {
+            self.record_change(before);
+        }
+    }
+
+    /// Drop the search and show every input again
+    pub fn clear_search(&mut self) {
+        let before = self.search_origin.take().unwrap_or_else(|| self.snapshot());
+        self.search = None;
+        self.record_change(before);
+    }
+
+    /// Show only inputs matching `query`, or every input when it is empty
+    pub fn set_search(&mut self, query: &str) {
+        if query.is_empty() {
+            self.clear_search();
+            return;
+        }
+        let before = self.snapshot();
+        self.search = Some(InputSearch {
+            query: query.to_string(),
+            editing: false,
+        });
+        self.follow_search();
+        self.record_change(before);
+    }
+
+    /// Order the inputs by `sort` from now on
+    pub fn set_sort(&mut self, sort: SortOrder) {
+        let before = self.snapshot();
+        self.sort = sort;
+        self.sort_inputs();
+        self.record_change(before);
+    }
+
+    /// Move the cursor to the first shown input when its input is hidden
+    fn follow_search(&mut self) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        if self.is_shown(cursor.index()) {
+            return;
+        }
+        if let Some(first) = (0..self.flake.inputs.len()).find(|&idx| self.is_shown(idx)) {
+            self.move_cursor(first);
+        }
     }
 
     /// Toggle selection at cursor
     pub fn toggle_selection(&mut self) {
-        let Some(cursor) = self.cursor else {
+        let Some(idx) = self.current_index() else {
             return;
         };
-        let Some(input) = self.flake.inputs.get(cursor.index()) else {
+        let Some(input) = self.flake.inputs.get(idx) else {
             return;
         };
         let Ok(name) = InputName::new(input.name()) else {
             return;
         };
+        self.history.record(self.snapshot());
         if self.selected.contains(&name) {
             self.selected.remove(&name);
         } else {
@@ -157,9 +562,181 @@ impl ListState {
         }
     }
 
+    /// Show or hide the transitive inputs of the input at the cursor.
+    ///
+    /// Returns `false` when the input declares no inputs of its own.
+    pub fn toggle_expanded(&mut self) -> bool {
+        let Some(input) = self
+            .current_index()
+            .and_then(|idx| self.flake.inputs.get(idx))
+        else {
+            return false;
+        };
+        if self.flake.transitive_inputs(input.name()).is_empty() {
+            return false;
+        }
+        let Ok(name) = InputName::new(input.name()) else {
+            return false;
+        };
+        if !self.expanded.remove(&name) {
+            self.expanded.insert(name);
+        }
+        true
+    }
+
+    /// Expand every input that declares inputs, or collapse them all when
+    /// any is expanded
+    pub fn toggle_expand_all(&mut self) {
+        if !self.expanded.is_empty() {
+            self.expanded.clear();
+            return;
+        }
+        self.expanded = self
+            .flake
+            .input_trees
+            .iter()
+            .filter(|tree| !tree.inputs.is_empty())
+            .filter_map(|tree| InputName::new(&tree.name).ok())
+            .collect();
+    }
+
+    /// Rows of the list view, with the transitive inputs of expanded inputs
+    /// below them
+    pub fn rows(&self) -> Vec<ListRow<'_>> {
+        let mut rows = Vec::new();
+        for (idx, input) in self.flake.inputs.iter().enumerate() {
+            if !self.is_shown(idx) {
+                continue;
+            }
+            rows.push(ListRow::Input(idx));
+            rows.extend(
+                self.transitive_lines(input)
+                    .into_iter()
+                    .map(ListRow::Transitive),
+            );
+        }
+        rows
+    }
+
+    /// Add every input matching `filter` to the selection, returning how many matched
+    pub fn select_matching(&mut self, filter: &InputFilter) -> usize {
+        let matching: Vec<InputName> = self
+            .flake
+            .inputs
+            .iter()
+            .filter_map(|input| {
+                let name = InputName::new(input.name()).ok()?;
+                filter
+                    .matches(input, self.status_for(input))
+                    .then_some(name)
+            })
+            .collect();
+        let count = matching.len();
+        if matching.iter().any(|name| !self.selected.contains(name)) {
+            self.history.record(self.snapshot());
+            self.selected.extend(matching);
+        }
+        count
+    }
+
+    /// Select every input the `/` search shows
+    pub fn select_all(&mut self) {
+        let shown = self.shown_names();
+        if shown.iter().any(|name| !self.selected.contains(name)) {
+            self.history.record(self.snapshot());
+            self.selected.extend(shown);
+        }
+    }
+
+    /// Select the shown inputs that are not selected, and deselect those
+    /// that are. Hidden inputs keep their selection.
+    pub fn invert_selection(&mut self) {
+        let shown = self.shown_names();
+        if shown.is_empty() {
+            return;
+        }
+        self.history.record(self.snapshot());
+        for name in shown {
+            if !self.selected.remove(&name) {
+                self.selected.insert(name);
+            }
+        }
+    }
+
+    fn shown_names(&self) -> Vec<InputName> {
+        (0..self.flake.inputs.len())
+            .filter(|&idx| self.is_shown(idx))
+            .filter_map(|idx| InputName::new(self.flake.inputs[idx].name()).ok())
+            .collect()
+    }
+
     /// Clear all selections
     pub fn clear_selection(&mut self) {
-        self.selected.clear();
+        if !self.selected.is_empty() {
+            self.history.record(self.snapshot());
+            self.selected.clear();
+        }
+    }
+
+    /// Capture the view state that undo restores
+    pub fn snapshot(&self) -> ViewSnapshot {
+        ViewSnapshot {
+            selected: self.selected.clone(),
+            search: self.search.as_ref().map(|search| search.query.clone()),
+            sort: self.sort,
+        }
+    }
+
+    /// Remember `before` for undo, unless the view is still the same
+    fn record_change(&mut self, before: ViewSnapshot) {
+        if before != self.snapshot() {
+            self.history.record(before);
+        }
+    }
+
+    /// Revert the last view change. Returns false when there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo(self.snapshot()) {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapply the last undone change. Returns false when there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo(self.snapshot()) {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Apply a snapshot, ignoring inputs that no longer exist after a refresh
+    fn restore(&mut self, snapshot: ViewSnapshot) {
+        self.selected = snapshot
+            .selected
+            .into_iter()
+            .filter(|name| {
+                self.flake
+                    .inputs
+                    .iter()
+                    .any(|input| input.name() == name.as_str())
+            })
+            .collect();
+        self.search = snapshot.search.map(|query| InputSearch {
+            query,
+            editing: false,
+        });
+        if self.sort != snapshot.sort {
+            self.sort = snapshot.sort;
+            self.sort_inputs();
+        }
+        self.follow_search();
     }
 
     /// Check if there are any selections
@@ -172,15 +749,30 @@ impl ListState {
         self.flake.inputs.len()
     }
 
-    /// Get the current cursor index, if the list is non-empty.
+    /// Get the current cursor index, if the list is non-empty and the `/`
+    /// search shows the input under it.
     pub fn current_index(&self) -> Option<usize> {
-        self.cursor.map(ListCursor::index)
+        self.cursor
+            .map(ListCursor::index)
+            .filter(|&idx| self.is_shown(idx))
     }
 
     /// Update with new flake data (for refresh)
     pub fn update_flake(&mut self, flake: FlakeData) {
+        // Selections follow inputs renamed in flake.nix
+        for (old, new) in flake.renames_since(&self.flake) {
+            if let (Ok(old), Ok(new)) = (InputName::new(old), InputName::new(new)) {
+                if self.selected.remove(&old) {
+                    self.selected.insert(new);
+                }
+            }
+        }
         self.flake = flake;
+        // Sort by the previous check results, so the order holds steady
+        // until new ones arrive.
+        self.sort_inputs();
         self.mode = ListMode::Idle;
+        self.refreshed_at = Utc::now();
         // Clamp cursor to new input count, or clear it for an empty list.
         let next_cursor = self
             .cursor
@@ -198,6 +790,126 @@ impl ListState {
         self.selected.retain(|name| existing_names.contains(name));
         // Clear old update statuses
         self.update_statuses.clear();
+        self.new_updates.clear();
+        self.clone_paths.clear();
+    }
+
+    /// Update status shown for an input.
+    ///
+    /// A `follows` alias is never checked itself; it mirrors the status of
+    /// the root input it follows.
+    pub fn status_for(&self, input: &FlakeInput) -> Option<&UpdateStatus> {
+        let name = match input {
+            FlakeInput::Follows(follows) => follows.root_target()?,
+            _ => input.name(),
+        };
+        self.update_statuses.get(name)
+    }
+
+    /// Record an update check result for an input.
+    ///
+    /// Returns `true` when the result shows commits that were not available
+    /// at the previous check, either because an up-to-date input fell behind
+    /// or because a behind input fell further behind.
+    pub fn record_status(&mut self, name: InputName, status: UpdateStatus) -> bool {
+        let newly_available = match (self.update_statuses.get(&name), &status) {
+            (Some(UpdateStatus::UpToDate), UpdateStatus::Behind(_)) => true,
+            (Some(UpdateStatus::Behind(before)), UpdateStatus::Behind(after)) => {
+                after.value() > before.value()
+            }
+            _ => false,
+        };
+
+        if newly_available {
+            self.new_updates.insert(name.clone());
+        } else if !matches!(status, UpdateStatus::Behind(_)) {
+            self.new_updates.remove(&name);
+        }
+        self.update_statuses.insert(name, status);
+        newly_available
+    }
+
+    /// Record the result of an update check, read from the clone at
+    /// `clone_path` if no forge API answered. Returns whether it found
+    /// updates that were not there before.
+    pub fn record_check(
+        &mut self,
+        name: InputName,
+        status: UpdateStatus,
+        clone_path: Option<PathBuf>,
+    ) -> bool {
+        if !matches!(
+            status,
+            UpdateStatus::Checking | UpdateStatus::RateLimited(_)
+        ) {
+            match clone_path {
+                Some(path) => self.clone_paths.insert(name.clone(), path),
+                None => self.clone_paths.remove(&name),
+            };
+        }
+        let newly_available = self.record_status(name, status);
+        // Most-behind order is only known once every check is in.
+        if self.sort == SortOrder::MostBehind
+            && !self.update_statuses.values().any(|status| {
+                matches!(
+                    status,
+                    UpdateStatus::Checking | UpdateStatus::RateLimited(_)
+                )
+            })
+        {
+            self.sort_inputs();
+        }
+        newly_available
+    }
+
+    /// Forget checks that were cancelled before they finished
+    pub fn cancel_checks(&mut self) {
+        for status in self.update_statuses.values_mut() {
+            if matches!(
+                status,
+                UpdateStatus::Checking | UpdateStatus::RateLimited(_)
+            ) {
+                *status = UpdateStatus::Unknown;
+            }
+        }
+    }
+
+    /// Mark the input named `name` as updating, and the one updated before
+    /// it as done
+    pub fn start_update(&mut self, name: &str) {
+        for status in self.update_statuses.values_mut() {
+            if matches!(status, UpdateStatus::Updating) {
+                *status = UpdateStatus::Updated;
+            }
+        }
+        if let Ok(name) = InputName::new(name) {
+            self.update_statuses.insert(name, UpdateStatus::Updating);
+        }
+    }
+
+    /// Drop the statuses of an update that is over. Inputs it already
+    /// updated keep showing so when `failed`.
+    pub fn finish_update(&mut self, failed: bool) {
+        self.update_statuses.retain(|_, status| match status {
+            UpdateStatus::Queued | UpdateStatus::Updating => false,
+            UpdateStatus::Updated => failed,
+            _ => true,
+        });
+    }
+
+    /// Number of checks held back by a forge rate limit, and how long until
+    /// the last of them is retried
+    pub fn rate_limit_wait(&self, now: Instant) -> Option<(usize, Duration)> {
+        let retry_ats: Vec<Instant> = self
+            .update_statuses
+            .values()
+            .filter_map(|status| match status {
+                UpdateStatus::RateLimited(at) => Some(*at),
+                _ => None,
+            })
+            .collect();
+        let last = retry_ats.iter().max()?;
+        Some((retry_ats.len(), last.saturating_duration_since(now)))
     }
 }
 
@@ -210,6 +922,18 @@ impl Clone for ListState {
             table_state: TableState::default().with_selected(self.table_state.selected()),
             update_statuses: self.update_statuses.clone(),
             mode: self.mode.clone(),
+            command_line: self.command_line.clone(),
+            history: self.history.clone(),
+            new_updates: self.new_updates.clone(),
+            refreshed_at: self.refreshed_at,
+            clone_paths: self.clone_paths.clone(),
+            sort: self.sort,
+            versions: self.versions.clone(),
+            expanded: self.expanded.clone(),
+            transitive_cursor: self.transitive_cursor,
+            detail: self.detail,
+            search: self.search.clone(),
+            search_origin: self.search_origin.clone(),
         }
     }
 }
@@ -245,6 +969,49 @@ pub enum ChangelogMode {
     ConfirmingLock { target: LockTarget },
 }
 
+/// Which grouping the changelog view shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangelogView {
+    /// One row per commit
+    Commits,
+    /// One row per author of the new commits
+    Authors,
+    /// One row per upstream tag on the new commits
+    Tags,
+}
+
+/// Content loaded on demand and shown in a scrollable pane over the
+/// changelog
+#[derive(Debug)]
+pub struct ChangelogPane<T> {
+    /// What the content was requested for: a tag name or commit SHA
+    pub id: String,
+    /// The content, once loaded; `None` after loading means there was none
+    pub content: Option<T>,
+    pub loading: bool,
+    /// Lines scrolled past
+    pub scroll: u16,
+}
+
+impl<T> ChangelogPane<T> {
+    fn new(id: String) -> Self {
+        Self {
+            id,
+            content: None,
+            loading: true,
+            scroll: 0,
+        }
+    }
+
+    /// Show `content` if it was loaded for what the pane is waiting on
+    fn finish(&mut self, id: &str, content: Option<T>) {
+        if self.id == id {
+            self.content = content;
+            self.loading = false;
+        }
+    }
+}
+
 /// State for the changelog view
 #[derive(Debug)]
 pub struct ChangelogState {
@@ -257,8 +1024,32 @@ pub struct ChangelogState {
     /// Table state for rendering
     pub table_state: TableState,
     pub mode: ChangelogMode,
+    pub view: ChangelogView,
+    /// Cursor position in the author view
+    pub author_cursor: usize,
+    /// Table state for rendering the author view
+    pub author_table_state: TableState,
+    /// Upstream tags, once loaded
+    pub tags: Option<Vec<Tag>>,
+    /// Tags are being fetched
+    pub tags_loading: bool,
+    /// Cursor position in the tag view
+    pub tag_cursor: usize,
+    /// Table state for rendering the tag view
+    pub tag_table_state: TableState,
+    /// Release notes pane, when open
+    pub release: Option<ChangelogPane<Release>>,
+    /// Commit detail pane, when open
+    pub detail: Option<ChangelogPane<CommitDetail>>,
+    /// Line statistics from the locked commit to new commits, by SHA;
+    /// `None` when neither the forge nor a clone could tell
+    range_stats: HashMap<String, Option<DiffStats>>,
+    /// SHA of the commit whose line statistics are loading
+    range_stats_loading: Option<String>,
     /// Parent list state (kept for returning)
     pub parent_list: ListState,
+    /// More commits are still loading
+    pub loading: bool,
 }
 
 impl ChangelogState {
@@ -275,103 +1066,1309 @@ impl ChangelogState {
             cursor,
             table_state,
             mode: ChangelogMode::Browsing,
+            view: ChangelogView::Commits,
+            author_cursor: 0,
+            author_table_state: TableState::default(),
+            tags: None,
+            tags_loading: false,
+            tag_cursor: 0,
+            tag_table_state: TableState::default(),
+            release: None,
+            detail: None,
+            range_stats: HashMap::new(),
+            range_stats_loading: None,
             parent_list,
+            loading: false,
         }
     }
 
-    /// Move cursor down
-    pub fn cursor_down(&mut self) {
-        if self.cursor < self.data.commits.len().saturating_sub(1) {
-            self.cursor += 1;
-            self.table_state.select(Some(self.cursor));
+    /// Open an empty changelog that fills in as pages arrive
+    pub fn loading(input: GitInput, parent_list: ListState) -> Self {
+        let data = ChangelogData::partial(Vec::new(), input.rev());
+        Self {
+            loading: true,
+            ..Self::new(input, data, parent_list)
         }
     }
 
-    /// Move cursor up
-    pub fn cursor_up(&mut self) {
-        if self.cursor > 0 {
-            self.cursor -= 1;
-            self.table_state.select(Some(self.cursor));
+    /// Add commits loaded from `offset` on, replacing any already there
+    pub fn add_page(&mut self, offset: usize, commits: Vec<Commit>) {
+        let mut all = std::mem::take(&mut self.data.commits);
+        if offset < all.len() {
+            // The load started over; a pending lock may point elsewhere now
+            self.hide_confirm();
         }
+        all.truncate(offset);
+        all.extend(commits);
+        self.data = ChangelogData::partial(all, self.input.rev());
+        self.clamp_cursors();
     }
 
-    /// Show confirm dialog for current cursor position
-    pub fn show_confirm(&mut self) {
-        if let Some(target) = LockTarget::new(self.cursor, &self.data.commits) {
-            self.mode = ChangelogMode::ConfirmingLock { target };
+    /// Replace the commits shown so far with the complete changelog
+    pub fn finish_loading(&mut self, data: ChangelogData) {
+        self.data = data;
+        self.loading = false;
+        self.clamp_cursors();
+    }
+
+    fn clamp_cursors(&mut self) {
+        let len = self.data.commits.len();
+        self.cursor = self.cursor.min(len.saturating_sub(1));
+        self.table_state.select((len > 0).then_some(self.cursor));
+        if self.view == ChangelogView::Authors {
+            let len = self.data.author_counts().len();
+            self.author_cursor = self.author_cursor.min(len.saturating_sub(1));
+            self.author_table_state
+                .select((len > 0).then_some(self.author_cursor));
+        }
+        if self.view == ChangelogView::Tags {
+            let len = self.new_tags().len();
+            self.tag_cursor = self.tag_cursor.min(len.saturating_sub(1));
+            self.tag_table_state
+                .select((len > 0).then_some(self.tag_cursor));
         }
     }
 
-    /// Hide confirm dialog
-    pub fn hide_confirm(&mut self) {
-        self.mode = ChangelogMode::Browsing;
+    /// Switch between the commit list and the author grouping
+    pub fn toggle_view(&mut self) {
+        self.view = match self.view {
+            ChangelogView::Commits | ChangelogView::Tags => {
+                self.author_cursor = 0;
+                let has_authors = !self.data.new_commits().is_empty();
+                self.author_table_state.select(has_authors.then_some(0));
+                ChangelogView::Authors
+            }
+            ChangelogView::Authors => ChangelogView::Commits,
+        };
     }
 
-    /// Check if confirm dialog is showing
-    pub fn is_confirming(&self) -> bool {
-        matches!(self.mode, ChangelogMode::ConfirmingLock { .. })
+    /// Switch between the tag list and the commit list. Returns whether the
+    /// tags still need to be fetched.
+    pub fn toggle_tags(&mut self) -> bool {
+        if self.view == ChangelogView::Tags {
+            self.view = ChangelogView::Commits;
+            return false;
+        }
+        self.view = ChangelogView::Tags;
+        self.tag_cursor = 0;
+        self.clamp_cursors();
+        let fetch = self.tags.is_none() && !self.tags_loading;
+        self.tags_loading |= fetch;
+        fetch
     }
 
-    pub fn lock_target(&self) -> Option<&LockTarget> {
-        match &self.mode {
-            ChangelogMode::ConfirmingLock { target } => Some(target),
-            ChangelogMode::Browsing => None,
+    /// Store the fetched upstream tags
+    pub fn set_tags(&mut self, tags: Vec<Tag>) {
+        self.tags = Some(tags);
+        self.tags_loading = false;
+        self.clamp_cursors();
+    }
+
+    /// Tags on the new commits, newest first, with the index of the commit
+    /// each points at
+    pub fn new_tags(&self) -> Vec<(usize, &Tag)> {
+        let Some(tags) = &self.tags else {
+            return Vec::new();
+        };
+        let mut found: Vec<(usize, &Tag)> = tags
+            .iter()
+            .filter_map(|tag| {
+                let idx = self
+                    .data
+                    .new_commits()
+                    .iter()
+                    .position(|c| c.sha == tag.sha)?;
+                Some((idx, tag))
+            })
+            .collect();
+        found.sort_by_key(|(idx, _)| *idx);
+        found
+    }
+
+    /// Tag pointing at the commit at `idx`, once tags are loaded
+    pub fn tag_on(&self, idx: usize) -> Option<&Tag> {
+        let commit = self.data.commits.get(idx)?;
+        self.tags.as_ref()?.iter().find(|tag| tag.sha == commit.sha)
+    }
+
+    /// Tag under the cursor: the selected row of the tag view, or the tag on
+    /// the current commit
+    pub fn selected_tag(&self) -> Option<&Tag> {
+        match self.view {
+            ChangelogView::Commits => self.tag_on(self.cursor),
+            ChangelogView::Tags => self.new_tags().get(self.tag_cursor).map(|(_, tag)| *tag),
+            ChangelogView::Authors => None,
         }
     }
-}
 
-/// Data returned when changelog is loaded
-#[derive(Debug)]
-pub struct ChangelogLoadedData {
-    pub input: GitInput,
-    pub data: ChangelogData,
-    pub parent_list: ListState,
-}
+    /// Index of the commit under the cursor, or of the commit the selected
+    /// tag points at
+    pub fn selected_commit(&self) -> Option<usize> {
+        let idx = match self.view {
+            ChangelogView::Commits => self.cursor,
+            ChangelogView::Tags => self.new_tags().get(self.tag_cursor)?.0,
+            ChangelogView::Authors => return None,
+        };
+        (idx < self.data.commits.len()).then_some(idx)
+    }
 
-/// Messages from background tasks
+    /// Open the release notes pane for `tag` while its release loads
+    pub fn open_release(&mut self, tag: String) {
+        self.release = Some(ChangelogPane::new(tag));
+    }
+
+    /// Show the loaded release for `tag`, if its pane is still open
+    pub fn set_release(&mut self, tag: &str, release: Option<Release>) {
+        if let Some(pane) = &mut self.release {
+            pane.finish(tag, release);
+        }
+    }
+
+    /// Open the detail pane for the commit `sha` while it loads
+    pub fn open_detail(&mut self, sha: String) {
+        self.detail = Some(ChangelogPane::new(sha));
+    }
+
+    /// Show the loaded detail of the commit `sha`, if its pane is still open
+    pub fn set_detail(&mut self, sha: &str, detail: CommitDetail) {
+        if let Some(pane) = &mut self.detail {
+            pane.finish(sha, Some(detail));
+        }
+    }
+
+    /// Whether a release notes or commit detail pane is open
+    pub fn has_pane(&self) -> bool {
+        self.release.is_some() || self.detail.is_some()
+    }
+
+    /// Close the open pane
+    pub fn close_pane(&mut self) {
+        self.release = None;
+        self.detail = None;
+    }
+
+    /// Scroll the open pane by `lines`, up when negative
+    pub fn scroll_pane(&mut self, lines: i16) {
+        let scroll = match (&mut self.release, &mut self.detail) {
+            (Some(pane), _) => &mut pane.scroll,
+            (_, Some(pane)) => &mut pane.scroll,
+            (None, None) => return,
+        };
+        *scroll = scroll.saturating_add_signed(lines);
+    }
+
+    /// Move cursor down
+    pub fn cursor_down(&mut self) {
+        if self.view == ChangelogView::Authors {
+            let len = self.data.author_counts().len();
+            if self.author_cursor < len.saturating_sub(1) {
+                self.author_cursor += 1;
+                self.author_table_state.select(Some(self.author_cursor));
+            }
+            return;
+        }
+        if self.view == ChangelogView::Tags {
+            if self.tag_cursor < self.new_tags().len().saturating_sub(1) {
+                self.tag_cursor += 1;
+                self.tag_table_state.select(Some(self.tag_cursor));
+            }
+            return;
+        }
+
+        if self.cursor < self.data.commits.len().saturating_sub(1) {
+            self.cursor += 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Move cursor up
+    pub fn cursor_up(&mut self) {
+        if self.view == ChangelogView::Authors {
+            if self.author_cursor > 0 {
+                self.author_cursor -= 1;
+                self.author_table_state.select(Some(self.author_cursor));
+            }
+            return;
+        }
+        if self.view == ChangelogView::Tags {
+            if self.tag_cursor > 0 {
+                self.tag_cursor -= 1;
+                self.tag_table_state.select(Some(self.tag_cursor));
+            }
+            return;
+        }
+
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Summary of what locking to the new commit under the cursor brings
+    /// in, with the commit, unless that is the newest one
+    pub fn cursor_summary(&self) -> Option<(&Commit, ChangelogSummary)> {
+        if self.view != ChangelogView::Commits || self.cursor == 0 {
+            return None;
+        }
+        let mut summary = self.data.summary_to(self.cursor)?;
+        let commit = &self.data.commits[self.cursor];
+        summary.diff_stats = self.range_stats.get(&commit.sha).copied().flatten();
+        Some((commit, summary))
+    }
+
+    /// Start loading line statistics up to the new commit under the cursor,
+    /// unless they are known or another load is under way. Returns the
+    /// locked and highlighted SHAs to compare.
+    pub fn range_stats_to_load(&mut self) -> Option<(String, String)> {
+        if self.loading || self.range_stats_loading.is_some() {
+            return None;
+        }
+        self.cursor_summary()?;
+        let locked = &self.data.commits[self.data.locked_index()?];
+        let head = &self.data.commits[self.cursor];
+        if self.range_stats.contains_key(&head.sha) {
+            return None;
+        }
+        self.range_stats_loading = Some(head.sha.clone());
+        Some((locked.sha.clone(), head.sha.clone()))
+    }
+
+    /// Store line statistics up to the commit `sha`
+    pub fn set_range_stats(&mut self, sha: String, stats: Option<DiffStats>) {
+        if self.range_stats_loading.as_ref() == Some(&sha) {
+            self.range_stats_loading = None;
+        }
+        self.range_stats.insert(sha, stats);
+    }
+
+    /// Show confirm dialog for current cursor position
+    pub fn show_confirm(&mut self) {
+        let Some(commit_idx) = self.selected_commit() else {
+            return;
+        };
+        if let Some(target) = LockTarget::new(commit_idx, &self.data.commits) {
+            self.mode = ChangelogMode::ConfirmingLock { target };
+        }
+    }
+
+    /// Hide confirm dialog
+    pub fn hide_confirm(&mut self) {
+        self.mode = ChangelogMode::Browsing;
+    }
+
+    /// Check if confirm dialog is showing
+    pub fn is_confirming(&self) -> bool {
+        matches!(self.mode, ChangelogMode::ConfirmingLock { .. })
+    }
+
+    pub fn lock_target(&self) -> Option<&LockTarget> {
+        match &self.mode {
+            ChangelogMode::ConfirmingLock { target } => Some(target),
+            ChangelogMode::Browsing => None,
+        }
+    }
+}
+
+/// Flakes given together on the command line, shown one tab each
+#[derive(Debug, Default)]
+pub struct FlakeTabs {
+    tabs: Vec<FlakeTab>,
+    active: usize,
+}
+
+/// One flake of [`FlakeTabs`]
+#[derive(Debug)]
+pub struct FlakeTab {
+    pub path: PathBuf,
+    /// The tab's list while another tab is shown, once loaded
+    pub list: Option<ListState>,
+}
+
+impl FlakeTabs {
+    /// Tabs for `paths`, the first shown; none for a single flake
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        if paths.len() < 2 {
+            return Self::default();
+        }
+        Self {
+            tabs: paths
+                .into_iter()
+                .map(|path| FlakeTab { path, list: None })
+                .collect(),
+            active: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tabs.is_empty()
+    }
+
+    pub fn tabs(&self) -> &[FlakeTab] {
+        &self.tabs
+    }
+
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Index of the tab after the shown one, or before it when `back`
+    pub fn neighbour(&self, back: bool) -> Option<usize> {
+        let len = self.tabs.len();
+        (len > 1).then(|| match back {
+            true => (self.active + len - 1) % len,
+            false => (self.active + 1) % len,
+        })
+    }
+
+    /// Keep `list` of the shown flake, now at `path`, and show the tab at
+    /// `idx`. Returns its path and list, which is `None` if it still has
+    /// to be loaded.
+    pub fn switch(
+        &mut self,
+        list: ListState,
+        path: PathBuf,
+        idx: usize,
+    ) -> Option<(PathBuf, Option<ListState>)> {
+        self.tabs.get(idx)?;
+        let shown = &mut self.tabs[self.active];
+        shown.path = path;
+        shown.list = Some(list);
+        self.active = idx;
+        let tab = &mut self.tabs[idx];
+        Some((tab.path.clone(), tab.list.take()))
+    }
+
+    /// Store the list of the hidden tab at `idx`, loaded in the background
+    pub fn set_list(&mut self, idx: usize, list: ListState) {
+        if idx != self.active {
+            if let Some(tab) = self.tabs.get_mut(idx) {
+                tab.list = Some(list);
+            }
+        }
+    }
+
+    /// List of the hidden tab showing the flake at `path`
+    pub fn hidden_list_mut(&mut self, path: &Path) -> Option<&mut ListState> {
+        self.tabs
+            .iter_mut()
+            .filter_map(|tab| tab.list.as_mut())
+            .find(|list| list.flake.path == path)
+    }
+
+    /// Lists of the hidden tabs that are loaded
+    pub fn hidden_lists_mut(&mut self) -> impl Iterator<Item = &mut ListState> {
+        self.tabs.iter_mut().filter_map(|tab| tab.list.as_mut())
+    }
+}
+
+/// State for the flake picker view
+#[derive(Debug)]
+pub struct FlakePickerState {
+    /// Directory the flakes were discovered under
+    pub root: PathBuf,
+    /// Discovered flake directories
+    pub flakes: Vec<PathBuf>,
+    /// Flake currently shown in the list view, if any
+    pub current: Option<PathBuf>,
+    /// Current cursor position
+    pub cursor: usize,
+    /// Table state for rendering
+    pub table_state: TableState,
+    /// Parent list state (kept for returning)
+    pub parent_list: Option<ListState>,
+}
+
+impl FlakePickerState {
+    /// Create a new FlakePickerState, starting on the current flake if present
+    pub fn new(
+        root: PathBuf,
+        flakes: Vec<PathBuf>,
+        current: Option<PathBuf>,
+        parent_list: Option<ListState>,
+    ) -> Self {
+        let cursor = current
+            .as_ref()
+            .and_then(|current| flakes.iter().position(|flake| flake == current))
+            .unwrap_or(0);
+        let mut table_state = TableState::default();
+        if !flakes.is_empty() {
+            table_state.select(Some(cursor));
+        }
+        Self {
+            root,
+            flakes,
+            current,
+            cursor,
+            table_state,
+            parent_list,
+        }
+    }
+
+    /// Move cursor down
+    pub fn cursor_down(&mut self) {
+        if self.cursor < self.flakes.len().saturating_sub(1) {
+            self.cursor += 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Move cursor up
+    pub fn cursor_up(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Get the flake under the cursor
+    pub fn selected_flake(&self) -> Option<&PathBuf> {
+        self.flakes.get(self.cursor)
+    }
+
+    /// Display a flake path relative to the discovery root
+    pub fn display_path(&self, flake: &Path) -> String {
+        match flake.strip_prefix(&self.root) {
+            Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+            Ok(rel) => format!("./{}", rel.display()),
+            Err(_) => flake.display().to_string(),
+        }
+    }
+}
+
+/// State for the flake.lock history view
+#[derive(Debug)]
+pub struct LockHistoryState {
+    /// Commits that changed flake.lock, newest first; `None` while loading
+    pub revisions: Option<Vec<LockRevision>>,
+    /// Input under the list cursor when the view was opened
+    pub focus: Option<String>,
+    /// Only show commits that changed the focused input
+    pub focus_only: bool,
+    /// Cursor position among the shown commits
+    pub cursor: usize,
+    /// Table state for rendering
+    pub table_state: TableState,
+    /// Whether restoring the lock of the selected commit awaits confirmation
+    pub confirming: bool,
+    /// Parent list state (kept for returning)
+    pub parent_list: ListState,
+}
+
+impl LockHistoryState {
+    /// Create a LockHistoryState that is waiting for the history to load
+    pub fn new(parent_list: ListState) -> Self {
+        let focus = parent_list
+            .current_index()
+            .and_then(|idx| parent_list.flake.inputs.get(idx))
+            .map(|input| input.name().to_string());
+        Self {
+            revisions: None,
+            focus,
+            focus_only: false,
+            cursor: 0,
+            table_state: TableState::default(),
+            confirming: false,
+            parent_list,
+        }
+    }
+
+    /// Show the loaded history
+    pub fn set_revisions(&mut self, revisions: Vec<LockRevision>) {
+        self.revisions = Some(revisions);
+        self.reset_cursor();
+    }
+
+    /// Commits currently shown, honoring the focus filter
+    pub fn visible(&self) -> Vec<&LockRevision> {
+        let focus = self.focus.as_deref().filter(|_| self.focus_only);
+        self.revisions
+            .iter()
+            .flatten()
+            .filter(|revision| {
+                focus.map_or(true, |name| {
+                    revision.changes.iter().any(|change| change.input == name)
+                })
+            })
+            .collect()
+    }
+
+    /// Switch between every commit and only those changing the focused
+    /// input. Returns false when no input is focused.
+    pub fn toggle_focus(&mut self) -> bool {
+        if self.focus.is_none() {
+            return false;
+        }
+        self.focus_only = !self.focus_only;
+        self.reset_cursor();
+        true
+    }
+
+    fn reset_cursor(&mut self) {
+        self.cursor = 0;
+        let selected = (!self.visible().is_empty()).then_some(0);
+        self.table_state.select(selected);
+    }
+
+    /// Move cursor down
+    pub fn cursor_down(&mut self) {
+        if self.cursor + 1 < self.visible().len() {
+            self.cursor += 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Move cursor up
+    pub fn cursor_up(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Get the commit under the cursor
+    pub fn selected_revision(&self) -> Option<&LockRevision> {
+        self.visible().get(self.cursor).copied()
+    }
+}
+
+/// State for the follows graph view
+#[derive(Debug)]
+pub struct FollowsGraphState {
+    /// The flake's inputs and what they declare; `None` while loading
+    pub trees: Option<Vec<InputTree>>,
+    /// Sources locked more than once, with the `follows` lines to fix them
+    pub duplicates: Vec<DuplicateInput>,
+    /// Lines scrolled past
+    pub scroll: u16,
+    /// Parent list state (kept for returning)
+    pub parent_list: ListState,
+}
+
+impl FollowsGraphState {
+    /// Create a FollowsGraphState that is waiting for the lock to be read
+    pub fn new(parent_list: ListState) -> Self {
+        Self {
+            trees: None,
+            duplicates: Vec::new(),
+            scroll: 0,
+            parent_list,
+        }
+    }
+
+    /// Show `trees`, finding the sources they lock more than once
+    pub fn set_trees(&mut self, trees: Vec<InputTree>) {
+        self.duplicates = InputTree::duplicates(&trees);
+        self.trees = Some(trees);
+    }
+
+    /// Scroll by `lines`, up when negative, staying within the tree
+    pub fn scroll_by(&mut self, lines: i16) {
+        let len = self
+            .trees
+            .as_deref()
+            .map_or(0, |trees| InputTree::lines(trees).len());
+        let max = u16::try_from(len.saturating_sub(1)).unwrap_or(u16::MAX);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max);
+    }
+
+    /// Transitive inputs that follow another input, and those locked
+    /// separately
+    pub fn follows_counts(&self) -> (usize, usize) {
+        self.trees
+            .iter()
+            .flatten()
+            .flat_map(InputTree::transitive)
+            .fold((0, 0), |(follows, own), tree| {
+                if tree.is_follows() {
+                    (follows + 1, own)
+                } else {
+                    (follows, own + 1)
+                }
+            })
+    }
+}
+
+/// State for choosing the branch an input tracks
+#[derive(Debug)]
+pub struct BranchPickerState {
+    pub input: GitInput,
+    /// Upstream branches; `None` while loading
+    pub branches: Option<Vec<String>>,
+    /// Branch being switched to, while flake.nix is edited and re-locked
+    pub switching: Option<String>,
+    /// Current cursor position
+    pub cursor: usize,
+    /// Table state for rendering
+    pub table_state: TableState,
+    /// Parent list state (kept for returning)
+    pub parent_list: ListState,
+}
+
+impl BranchPickerState {
+    /// Create a BranchPickerState that is waiting for the branches to load
+    pub fn new(input: GitInput, parent_list: ListState) -> Self {
+        Self {
+            input,
+            branches: None,
+            switching: None,
+            cursor: 0,
+            table_state: TableState::default(),
+            parent_list,
+        }
+    }
+
+    /// Show the loaded branches, sorted, starting on the tracked one
+    pub fn set_branches(&mut self, mut branches: Vec<String>) {
+        branches.sort();
+        self.cursor = self
+            .input
+            .reference()
+            .and_then(|current| branches.iter().position(|branch| branch == current))
+            .unwrap_or(0);
+        self.table_state
+            .select((!branches.is_empty()).then_some(self.cursor));
+        self.branches = Some(branches);
+    }
+
+    /// Move cursor down
+    pub fn cursor_down(&mut self) {
+        let len = self.branches.as_ref().map_or(0, Vec::len);
+        if self.cursor + 1 < len {
+            self.cursor += 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Move cursor up
+    pub fn cursor_up(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Get the branch under the cursor
+    pub fn selected_branch(&self) -> Option<&str> {
+        self.branches.as_ref()?.get(self.cursor).map(String::as_str)
+    }
+}
+
+/// Lines of each build's log kept for the build view
+const BUILD_LOG_LINES: usize = 500;
+
+/// How far building one output has got
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildStatus {
+    Pending,
+    Building,
+    Built(Duration),
+    Failed,
+    Cancelled,
+}
+
+/// An output of the build view, with its log so far
+#[derive(Debug)]
+pub struct BuildOutput {
+    /// Installable passed to `nix build`
+    pub target: String,
+    pub status: BuildStatus,
+    pub log: VecDeque<String>,
+    started: Option<Instant>,
+}
+
+/// State for building the configured outputs after an update
+#[derive(Debug)]
+pub struct BuildState {
+    pub outputs: Vec<BuildOutput>,
+    /// Current cursor position; follows the running build
+    pub cursor: usize,
+    /// Table state for rendering
+    pub table_state: TableState,
+    /// Parent list state (kept for returning)
+    pub parent_list: ListState,
+}
+
+impl BuildState {
+    /// Create a BuildState with every output of `targets` waiting to build
+    pub fn new(targets: Vec<String>, parent_list: ListState) -> Self {
+        let outputs = targets
+            .into_iter()
+            .map(|target| BuildOutput {
+                target,
+                status: BuildStatus::Pending,
+                log: VecDeque::new(),
+                started: None,
+            })
+            .collect();
+        Self {
+            outputs,
+            cursor: 0,
+            table_state: TableState::default().with_selected(Some(0)),
+            parent_list,
+        }
+    }
+
+    /// Whether any output is still waiting or building
+    pub fn is_running(&self) -> bool {
+        self.outputs
+            .iter()
+            .any(|output| matches!(output.status, BuildStatus::Pending | BuildStatus::Building))
+    }
+
+    /// Mark every output as waiting to build again, clearing the logs
+    pub fn restart(&mut self) {
+        for output in &mut self.outputs {
+            output.status = BuildStatus::Pending;
+            output.log.clear();
+            output.started = None;
+        }
+    }
+
+    /// Mark the output at `index` as building and move the cursor to it
+    pub fn start(&mut self, index: usize) {
+        if let Some(output) = self.outputs.get_mut(index) {
+            output.status = BuildStatus::Building;
+            output.started = Some(Instant::now());
+            self.cursor = index;
+            self.table_state.select(Some(index));
+        }
+    }
+
+    /// Append `line` to the log of the output at `index`
+    pub fn push_line(&mut self, index: usize, line: String) {
+        if let Some(output) = self.outputs.get_mut(index) {
+            if output.log.len() == BUILD_LOG_LINES {
+                output.log.pop_front();
+            }
+            output.log.push_back(line);
+        }
+    }
+
+    /// Record whether the output at `index` built
+    pub fn finish(&mut self, index: usize, built: bool) {
+        if let Some(output) = self.outputs.get_mut(index) {
+            output.status = if built {
+                BuildStatus::Built(output.started.map_or(Duration::ZERO, |at| at.elapsed()))
+            } else {
+                BuildStatus::Failed
+            };
+        }
+    }
+
+    /// Mark the outputs that had not finished as cancelled
+    pub fn cancel(&mut self) {
+        for output in &mut self.outputs {
+            if matches!(output.status, BuildStatus::Pending | BuildStatus::Building) {
+                output.status = BuildStatus::Cancelled;
+            }
+        }
+    }
+
+    /// Move cursor down
+    pub fn cursor_down(&mut self) {
+        if self.cursor + 1 < self.outputs.len() {
+            self.cursor += 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Move cursor up
+    pub fn cursor_up(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Get the output under the cursor
+    pub fn selected_output(&self) -> Option<&BuildOutput> {
+        self.outputs.get(self.cursor)
+    }
+}
+
+/// State for the package changes of the last nixpkgs update
+#[derive(Debug)]
+pub struct ClosureDiffState {
+    pub diff: ClosureDiff,
+    /// Current cursor position
+    pub cursor: usize,
+    /// Table state for rendering
+    pub table_state: TableState,
+    /// Parent list state (kept for returning)
+    pub parent_list: ListState,
+}
+
+impl ClosureDiffState {
+    pub fn new(diff: ClosureDiff, parent_list: ListState) -> Self {
+        Self {
+            diff,
+            cursor: 0,
+            table_state: TableState::default().with_selected(Some(0)),
+            parent_list,
+        }
+    }
+
+    /// Move cursor down
+    pub fn cursor_down(&mut self) {
+        if self.cursor + 1 < self.diff.version_changes().len() {
+            self.cursor += 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Move cursor up
+    pub fn cursor_up(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+}
+
+/// Current operation mode for the template picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePickerMode {
+    LoadingTemplates,
+    Browsing,
+    Initializing { reference: String },
+}
+
+/// State for the template picker view
+#[derive(Debug)]
+pub struct TemplatePickerState {
+    /// Directory the new flake is created in
+    pub path: PathBuf,
+    /// Templates offered by the template source
+    pub templates: Vec<FlakeTemplate>,
+    /// Current cursor position
+    pub cursor: usize,
+    /// Table state for rendering
+    pub table_state: TableState,
+    pub mode: TemplatePickerMode,
+}
+
+impl TemplatePickerState {
+    /// Create a picker that is waiting for the template list
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            templates: Vec::new(),
+            cursor: 0,
+            table_state: TableState::default(),
+            mode: TemplatePickerMode::LoadingTemplates,
+        }
+    }
+
+    /// Populate the picker once templates are loaded
+    pub fn set_templates(&mut self, templates: Vec<FlakeTemplate>) {
+        self.templates = templates;
+        self.cursor = 0;
+        self.table_state
+            .select((!self.templates.is_empty()).then_some(0));
+        self.mode = TemplatePickerMode::Browsing;
+    }
+
+    /// Move cursor down
+    pub fn cursor_down(&mut self) {
+        if self.cursor < self.templates.len().saturating_sub(1) {
+            self.cursor += 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Move cursor up
+    pub fn cursor_up(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.table_state.select(Some(self.cursor));
+        }
+    }
+
+    /// Get the template under the cursor
+    pub fn selected_template(&self) -> Option<&FlakeTemplate> {
+        self.templates.get(self.cursor)
+    }
+}
+
+/// State for the tasks pane, drawn over the current view
+#[derive(Debug, Default)]
+pub struct TasksPaneState {
+    /// Index of the highlighted task
+    pub cursor: usize,
+    /// Table state for rendering
+    pub table_state: TableState,
+}
+
+impl TasksPaneState {
+    /// Open the pane on the first task that has not finished
+    pub fn new(tasks: &[Task]) -> Self {
+        let mut pane = Self {
+            cursor: tasks
+                .iter()
+                .position(|task| !task.status.is_finished())
+                .unwrap_or(0),
+            table_state: TableState::default(),
+        };
+        pane.clamp(tasks.len());
+        pane
+    }
+
+    /// Move cursor down
+    pub fn cursor_down(&mut self, len: usize) {
+        if self.cursor + 1 < len {
+            self.cursor += 1;
+        }
+        self.clamp(len);
+    }
+
+    /// Move cursor up
+    pub fn cursor_up(&mut self, len: usize) {
+        self.cursor = self.cursor.saturating_sub(1);
+        self.clamp(len);
+    }
+
+    /// Keep the cursor on a task as finished ones drop out of the history
+    pub fn clamp(&mut self, len: usize) {
+        self.cursor = self.cursor.min(len.saturating_sub(1));
+        self.table_state.select((len > 0).then_some(self.cursor));
+    }
+}
+
+/// State changes announced one line at a time in accessible mode, so a
+/// screen reader can follow them without parsing the table
+#[derive(Debug, Default, Clone)]
+pub struct Announcements {
+    lines: VecDeque<String>,
+}
+
+impl Announcements {
+    /// Lines kept; older ones are dropped
+    const CAPACITY: usize = 200;
+
+    /// Add a line, unless it repeats the previous one
+    pub fn push(&mut self, line: impl Into<String>) {
+        let line = line.into();
+        if self.lines.back() == Some(&line) {
+            return;
+        }
+        if self.lines.len() == Self::CAPACITY {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    /// The last `count` lines, oldest first
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &str> {
+        self.lines
+            .iter()
+            .skip(self.lines.len().saturating_sub(count))
+            .map(String::as_str)
+    }
+}
+
+/// Data returned when changelog is loaded
+#[derive(Debug)]
+pub struct ChangelogLoadedData {
+    pub input: GitInput,
+    pub data: ChangelogData,
+    pub parent_list: ListState,
+}
+
+/// Messages from background tasks
 #[derive(Debug)]
 pub enum TaskResult {
     /// Flake metadata loaded
     FlakeLoaded(Result<FlakeData, AppError>),
+    /// Flake of the hidden tab at `tab` loaded in the background
+    TabLoaded {
+        tab: usize,
+        flake: Result<FlakeData, AppError>,
+    },
+    /// The input named `name`, `position` of `total` selected, started
+    /// updating
+    UpdateProgress {
+        name: String,
+        position: usize,
+        total: usize,
+    },
     /// Input update completed
     UpdateComplete(Result<(), AppError>),
     /// Changelog loaded
     ChangelogLoaded(Box<Result<ChangelogLoadedData, GitError>>),
+    /// Part of a changelog that is still loading
+    ChangelogPage {
+        input: Box<GitInput>,
+        /// Position of the first commit in the full list
+        offset: usize,
+        commits: Vec<Commit>,
+    },
+    /// Detail of the commit `sha` of the input named `name` loaded
+    CommitDetailLoaded {
+        name: InputName,
+        sha: String,
+        detail: Box<Result<CommitDetail, GitError>>,
+    },
+    /// Line statistics from the locked commit to `head` of the input named
+    /// `name` loaded
+    DiffStatsLoaded {
+        name: InputName,
+        head: String,
+        stats: Result<Option<DiffStats>, GitError>,
+    },
+    /// Release for `tag` of the input named `name` loaded
+    ReleaseLoaded {
+        name: InputName,
+        tag: String,
+        release: Result<Option<Release>, GitError>,
+    },
+    /// Web page of the pull request that merged commit `sha`, or of the
+    /// commit, looked up
+    PullRequestFound {
+        sha: String,
+        url: Result<Option<String>, GitError>,
+    },
+    /// Upstream tags of the input named `name` loaded
+    TagsLoaded {
+        name: InputName,
+        tags: Result<Vec<Tag>, GitError>,
+    },
     /// Lock completed
     LockComplete(Result<(), AppError>),
-    /// Status update for a single input
+    /// flake.lock restored from its backup; false when there was none
+    LockRestored(Result<bool, AppError>),
+    /// flake.lock committed after an update or lock; carries the commit's
+    /// short SHA and summary, `None` when the lock had no changes
+    LockCommitted(Result<Option<String>, AppError>),
+    /// Installed nix version read; fails when it is unsupported
+    NixVersionChecked(Result<(), AppError>),
+    /// Pull request opened for the lock update; carries its URL
+    PullRequestOpened(Result<String, AppError>),
+    /// Upstream branches of the input named `name` loaded
+    BranchesLoaded {
+        name: InputName,
+        branches: Result<Vec<String>, GitError>,
+    },
+    /// The input named `name` switched to track `reference` and re-locked
+    RefChanged {
+        name: InputName,
+        reference: String,
+        result: Result<(), AppError>,
+    },
+    /// Input tree of the flake's lock file read
+    InputTreeLoaded(Result<Vec<InputTree>, AppError>),
+    /// Building the output at `index` of the build view started
+    BuildStarted(usize),
+    /// A line of the log of the output at `index`
+    BuildLog { index: usize, line: String },
+    /// Building the output at `index` finished
+    BuildFinished {
+        index: usize,
+        result: Result<(), AppError>,
+    },
+    /// The remaining builds were cancelled
+    BuildsCancelled,
+    /// The closure to compare was built before an update, at `before`
+    ClosureBuilt { installable: String, before: String },
+    /// The closure was built again after an update and compared
+    ClosureDiffed(Result<ClosureDiff, AppError>),
+    /// Commits that changed flake.lock loaded
+    LockHistoryLoaded(Result<Vec<LockRevision>, AppError>),
+    /// flake.lock restored as committed in `sha`
+    LockRevisionRestored {
+        sha: String,
+        result: Result<(), AppError>,
+    },
+    /// Status update for a single input of the flake at `flake`
     InputStatus {
+        flake: PathBuf,
         name: InputName,
         status: UpdateStatus,
+        /// Cached clone the status was read from, if no forge API answered
+        clone_path: Option<PathBuf>,
     },
+    /// Input versions evaluated for the flake at `path`
+    VersionsLoaded {
+        path: PathBuf,
+        versions: Result<HashMap<String, String>, AppError>,
+    },
+    /// Template list loaded
+    TemplatesLoaded(Result<Vec<FlakeTemplate>, AppError>),
+    /// Flake initialized from a template
+    TemplateInitialized(Result<(), AppError>),
+    /// An update check was cancelled before every input reported
+    ChecksCancelled,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{FlakeInput, PathInput};
-    use std::path::PathBuf;
+    use crate::model::{FollowsInput, PathInput};
 
     fn flake(names: &[&str]) -> FlakeData {
         FlakeData {
             path: PathBuf::from("/tmp/flake"),
+            warnings: Vec::new(),
+            input_trees: Vec::new(),
             inputs: names
                 .iter()
                 .map(|name| {
                     FlakeInput::Path(PathInput {
                         name: (*name).to_string(),
+                        flake: true,
                     })
                 })
                 .collect(),
         }
     }
 
+    #[test]
+    fn build_state_tracks_each_output() {
+        let targets = vec![".#a".to_string(), ".#b".to_string(), ".#c".to_string()];
+        let mut build = BuildState::new(targets, ListState::new(flake(&["nixpkgs"])));
+        assert!(build.is_running());
+
+        build.start(0);
+        for idx in 0..BUILD_LOG_LINES + 1 {
+            build.push_line(0, format!("line {}", idx));
+        }
+        build.finish(0, true);
+        build.start(1);
+        build.finish(1, false);
+        build.start(2);
+        build.cancel();
+
+        let statuses: Vec<&BuildStatus> = build.outputs.iter().map(|o| &o.status).collect();
+        assert!(matches!(statuses[0], BuildStatus::Built(_)));
+        assert_eq!(statuses[1], &BuildStatus::Failed);
+        assert_eq!(statuses[2], &BuildStatus::Cancelled);
+        assert!(!build.is_running());
+        assert_eq!(build.cursor, 2);
+        assert_eq!(build.outputs[0].log.len(), BUILD_LOG_LINES);
+        assert_eq!(build.outputs[0].log.front().unwrap(), "line 1");
+
+        build.restart();
+        assert!(build.is_running());
+        assert!(build.outputs[0].log.is_empty());
+    }
+
+    #[test]
+    fn expanded_inputs_list_their_transitive_inputs() {
+        let leaf = |name: &str, follows: Option<&str>| InputTree {
+            name: name.to_string(),
+            node: Some(name.to_string()),
+            follows: follows.map(|f| vec![f.to_string()]),
+            source: None,
+            rev: None,
+            last_modified: None,
+            inputs: Vec::new(),
+        };
+        let mut data = flake(&["home-manager", "nixpkgs"]);
+        data.input_trees = vec![
+            InputTree {
+                inputs: vec![leaf("nixpkgs", Some("nixpkgs")), leaf("systems", None)],
+                ..leaf("home-manager", None)
+            },
+            leaf("nixpkgs", None),
+        ];
+        let mut list = ListState::new(data);
+
+        let names = |list: &ListState| -> Vec<String> {
+            list.rows()
+                .iter()
+                .map(|row| match row {
+                    ListRow::Input(idx) => list.flake.inputs[*idx].name().to_string(),
+                    ListRow::Transitive(line) => format!("{}{}", line.prefix, line.tree.name),
+                })
+                .collect()
+        };
+        assert_eq!(names(&list), ["home-manager", "nixpkgs"]);
+
+        assert!(list.toggle_expanded());
+        assert_eq!(
+            names(&list),
+            ["home-manager", "├─ nixpkgs", "└─ systems", "nixpkgs"]
+        );
+
+        // The cursor steps through the transitive rows, so a block taller
+        // than the screen can still be scrolled through.
+        list.cursor_down();
+        list.cursor_down();
+        assert_eq!((list.current_index(), list.transitive_row()), (Some(0), 2));
+        list.cursor_down();
+        assert_eq!((list.current_index(), list.transitive_row()), (Some(1), 0));
+        list.cursor_up();
+        assert_eq!((list.current_index(), list.transitive_row()), (Some(0), 2));
+
+        list.cursor_down();
+        assert!(!list.toggle_expanded());
+        list.toggle_expand_all();
+        assert_eq!(names(&list).len(), 2);
+        list.toggle_expand_all();
+        assert_eq!(names(&list).len(), 4);
+    }
+
+    #[test]
+    fn announcements_skip_repeats_and_keep_recent_lines() {
+        let mut announcements = Announcements::default();
+        announcements.push("nixpkgs: up to date");
+        announcements.push("nixpkgs: up to date");
+        for idx in 0..Announcements::CAPACITY {
+            announcements.push(format!("line {}", idx));
+        }
+        let recent: Vec<&str> = announcements.recent(2).collect();
+        assert_eq!(recent, ["line 198", "line 199"]);
+        assert_eq!(
+            announcements.recent(usize::MAX).count(),
+            Announcements::CAPACITY
+        );
+        assert_eq!(announcements.recent(usize::MAX).next(), Some("line 0"));
+    }
+
     #[test]
     fn list_state_empty_list_has_no_cursor() {
         let list = ListState::new(flake(&[]));
         assert_eq!(list.current_index(), None);
     }
 
+    #[test]
+    fn lock_history_focuses_on_the_input_under_the_cursor() {
+        use crate::model::{LockChange, LockChangeKind};
+
+        let revision = |sha: &str, inputs: &[&str]| LockRevision {
+            sha: sha.to_string(),
+            summary: String::new(),
+            author: String::new(),
+            date: 0,
+            changes: inputs
+                .iter()
+                .map(|input| LockChange {
+                    input: (*input).to_string(),
+                    kind: LockChangeKind::Added { rev: None },
+                })
+                .collect(),
+        };
+        let mut list = ListState::new(flake(&["a", "b"]));
+        list.cursor_down();
+        let mut history = LockHistoryState::new(list);
+        assert_eq!(history.focus.as_deref(), Some("b"));
+
+        history.set_revisions(vec![
+            revision("3", &["a"]),
+            revision("2", &["a", "b"]),
+            revision("1", &["b"]),
+        ]);
+        history.cursor_down();
+        assert_eq!(history.selected_revision().unwrap().sha, "2");
+
+        assert!(history.toggle_focus());
+        let shas: Vec<&str> = history.visible().iter().map(|r| r.sha.as_str()).collect();
+        assert_eq!(shas, ["2", "1"]);
+        assert_eq!(history.selected_revision().unwrap().sha, "2");
+        history.cursor_down();
+        history.cursor_down();
+        assert_eq!(history.selected_revision().unwrap().sha, "1");
+    }
+
     #[test]
     fn list_state_selection_survives_reorder_by_name() {
         let mut list = ListState::new(flake(&["a", "b"]));
@@ -385,6 +2382,610 @@ mod tests {
         assert!(!list.selected.contains(&InputName::new("a").unwrap()));
     }
 
+    #[test]
+    fn record_status_flags_newly_available_updates() {
+        use crate::model::CommitCount;
+
+        let mut list = ListState::new(flake(&["a", "b"]));
+        let a = InputName::new("a").unwrap();
+        let b = InputName::new("b").unwrap();
+
+        assert!(!list.record_status(a.clone(), UpdateStatus::Checking));
+        assert!(!list.record_status(a.clone(), UpdateStatus::UpToDate));
+        assert!(!list.record_status(b.clone(), UpdateStatus::Checking));
+        assert!(!list.record_status(b.clone(), UpdateStatus::Behind(CommitCount::Exact(2))));
+        assert!(list.new_updates.is_empty());
+
+        assert!(list.record_status(a.clone(), UpdateStatus::Behind(CommitCount::Exact(1))));
+        assert!(!list.record_status(b.clone(), UpdateStatus::Behind(CommitCount::Exact(2))));
+        assert!(list.record_status(b.clone(), UpdateStatus::Behind(CommitCount::Exact(3))));
+        assert_eq!(list.new_updates.len(), 2);
+
+        list.record_status(a.clone(), UpdateStatus::UpToDate);
+        assert!(!list.new_updates.contains(&a));
+    }
+
+    #[test]
+    fn flake_tabs_keep_the_list_of_each_flake() {
+        let mut tabs = FlakeTabs::new(vec![PathBuf::from("a"), PathBuf::from("b")]);
+        assert_eq!(tabs.neighbour(false), Some(1));
+        assert_eq!(tabs.neighbour(true), Some(1));
+
+        let mut first = ListState::new(flake(&["nixpkgs"]));
+        first.detail = true;
+        let (path, list) = tabs.switch(first, PathBuf::from("/a"), 1).unwrap();
+        assert_eq!(path, PathBuf::from("b"));
+        assert!(list.is_none());
+        assert_eq!(tabs.active(), 1);
+
+        tabs.set_list(1, ListState::new(flake(&[])));
+        assert!(tabs.tabs()[1].list.is_none());
+        assert!(tabs.hidden_list_mut(Path::new("/tmp/flake")).is_some());
+
+        let (path, list) = tabs
+            .switch(ListState::new(flake(&[])), PathBuf::from("/b"), 0)
+            .unwrap();
+        assert_eq!(path, PathBuf::from("/a"));
+        assert!(list.unwrap().detail);
+        assert!(FlakeTabs::new(vec![PathBuf::from("a")]).is_empty());
+    }
+
+    #[test]
+    fn batch_update_marks_one_input_updating_at_a_time() {
+        let mut list = ListState::new(flake(&["a", "b", "c"]));
+        for name in ["a", "b", "c"] {
+            list.record_status(InputName::new(name).unwrap(), UpdateStatus::Queued);
+        }
+        let status = |list: &ListState, name: &str| {
+            list.update_statuses
+                .get(&InputName::new(name).unwrap())
+                .map(UpdateStatus::describe)
+        };
+
+        list.start_update("a");
+        list.start_update("b");
+        assert_eq!(status(&list, "a"), Some("updated".to_string()));
+        assert_eq!(status(&list, "b"), Some("updating".to_string()));
+        assert_eq!(status(&list, "c"), Some("waiting to update".to_string()));
+
+        list.finish_update(true);
+        assert_eq!(status(&list, "a"), Some("updated".to_string()));
+        assert_eq!(status(&list, "b"), None);
+        assert_eq!(status(&list, "c"), None);
+        list.finish_update(false);
+        assert_eq!(status(&list, "a"), None);
+    }
+
+    #[test]
+    fn rate_limit_wait_counts_held_back_checks() {
+        let mut list = ListState::new(flake(&["a", "b", "c"]));
+        let now = Instant::now();
+        assert_eq!(list.rate_limit_wait(now), None);
+
+        let name = |n: &str| InputName::new(n).unwrap();
+        list.record_status(
+            name("a"),
+            UpdateStatus::RateLimited(now + Duration::from_secs(30)),
+        );
+        list.record_status(
+            name("b"),
+            UpdateStatus::RateLimited(now + Duration::from_secs(90)),
+        );
+        list.record_status(name("c"), UpdateStatus::Checking);
+        assert_eq!(
+            list.rate_limit_wait(now),
+            Some((2, Duration::from_secs(90)))
+        );
+        assert_eq!(
+            list.rate_limit_wait(now + Duration::from_secs(120)),
+            Some((2, Duration::ZERO))
+        );
+    }
+
+    #[test]
+    fn follows_alias_mirrors_target_status() {
+        use crate::model::CommitCount;
+
+        let mut data = flake(&["nixpkgs"]);
+        data.inputs.push(FlakeInput::Follows(FollowsInput {
+            name: "nixpkgs-alias".to_string(),
+            follows: vec!["nixpkgs".to_string()],
+        }));
+        let mut list = ListState::new(data);
+        assert!(list.status_for(&list.flake.inputs[1]).is_none());
+
+        list.record_status(
+            InputName::new("nixpkgs").unwrap(),
+            UpdateStatus::Behind(CommitCount::Exact(4)),
+        );
+        assert!(matches!(
+            list.status_for(&list.flake.inputs[1]),
+            Some(UpdateStatus::Behind(CommitCount::Exact(4)))
+        ));
+    }
+
+    #[test]
+    fn sorting_keeps_cursor_on_same_input() {
+        use crate::model::CommitCount;
+
+        let mut list = ListState::new(flake(&["a", "b", "c"]));
+        list.cursor_down();
+        list.record_status(
+            InputName::new("c").unwrap(),
+            UpdateStatus::Behind(CommitCount::Exact(5)),
+        );
+
+        let list = list.with_sort(SortOrder::MostBehind);
+        let names: Vec<&str> = list.flake.inputs.iter().map(FlakeInput::name).collect();
+        assert_eq!(names, ["c", "a", "b"]);
+        assert_eq!(list.current_index(), Some(2));
+    }
+
+    #[test]
+    fn flake_picker_starts_on_current_flake() {
+        let flakes = vec![PathBuf::from("/repo"), PathBuf::from("/repo/sub")];
+        let mut picker = FlakePickerState::new(
+            PathBuf::from("/repo"),
+            flakes,
+            Some(PathBuf::from("/repo/sub")),
+            None,
+        );
+        assert_eq!(picker.cursor, 1);
+
+        picker.cursor_down();
+        assert_eq!(picker.selected_flake(), Some(&PathBuf::from("/repo/sub")));
+        picker.cursor_up();
+        assert_eq!(picker.selected_flake(), Some(&PathBuf::from("/repo")));
+
+        assert_eq!(picker.display_path(Path::new("/repo")), ".");
+        assert_eq!(picker.display_path(Path::new("/repo/sub")), "./sub");
+        assert_eq!(picker.display_path(Path::new("/other")), "/other");
+    }
+
+    #[test]
+    fn template_picker_browses_loaded_templates() {
+        let mut picker = TemplatePickerState::new(PathBuf::from("/tmp/new"));
+        assert_eq!(picker.mode, TemplatePickerMode::LoadingTemplates);
+        assert!(picker.selected_template().is_none());
+
+        picker.set_templates(vec![
+            FlakeTemplate::new("templates", "bash-hello", ""),
+            FlakeTemplate::new("templates", "rust", "Rust template"),
+        ]);
+        picker.cursor_down();
+        picker.cursor_down();
+
+        assert_eq!(picker.mode, TemplatePickerMode::Browsing);
+        assert_eq!(
+            picker.selected_template().map(|t| t.reference.as_str()),
+            Some("templates#rust")
+        );
+    }
+
+    #[test]
+    fn branch_picker_starts_on_the_tracked_branch() {
+        use crate::model::{GitRef, GitRepo, Owner, RepoName};
+
+        let input = GitInput::new(
+            InputName::new("nixpkgs").unwrap(),
+            GitRepo::github(
+                Owner::new("NixOS").unwrap(),
+                RepoName::new("nixpkgs").unwrap(),
+            ),
+            Some(GitRef::new("nixos-24.05").unwrap()),
+            GitRev::new("abc1234").unwrap(),
+            0,
+            "github:NixOS/nixpkgs/nixos-24.05".to_string(),
+        );
+        let mut picker = BranchPickerState::new(input, ListState::new(flake(&[])));
+        assert_eq!(picker.selected_branch(), None);
+
+        picker.set_branches(vec![
+            "nixos-unstable".to_string(),
+            "master".to_string(),
+            "nixos-24.05".to_string(),
+        ]);
+        assert_eq!(picker.selected_branch(), Some("nixos-24.05"));
+        picker.cursor_down();
+        assert_eq!(picker.selected_branch(), Some("nixos-unstable"));
+        picker.cursor_down();
+        assert_eq!(picker.selected_branch(), Some("nixos-unstable"));
+    }
+
+    #[test]
+    fn changelog_author_view_has_its_own_cursor() {
+        use crate::model::{Commit, GitRepo, Owner, RepoName, SignatureStatus};
+        use chrono::Utc;
+
+        let input = GitInput::new(
+            InputName::new("nixpkgs").unwrap(),
+            GitRepo::github(
+                Owner::new("NixOS").unwrap(),
+                RepoName::new("nixpkgs").unwrap(),
+            ),
+            None,
+            GitRev::new("abc1234").unwrap(),
+            0,
+            "github:NixOS/nixpkgs".to_string(),
+        );
+        let commits = ["alice", "bob", "alice", "carol"]
+            .iter()
+            .enumerate()
+            .map(|(idx, author)| Commit {
+                sha: format!("abcdef{}", idx),
+                message: "message".to_string(),
+                author: author.to_string(),
+                date: Utc::now(),
+                signature: SignatureStatus::Unknown,
+            })
+            .collect();
+        let data = ChangelogData::new(commits, Some(3)).unwrap();
+        let mut cs = ChangelogState::new(input, data, ListState::new(flake(&[])));
+
+        cs.toggle_view();
+        assert_eq!(cs.view, ChangelogView::Authors);
+        cs.cursor_down();
+        cs.cursor_down();
+        assert_eq!(cs.author_cursor, 1);
+        assert_eq!(cs.cursor, 3);
+
+        cs.show_confirm();
+        assert!(!cs.is_confirming());
+
+        cs.toggle_view();
+        assert_eq!(cs.view, ChangelogView::Commits);
+    }
+
+    #[test]
+    fn changelog_loads_line_stats_one_highlighted_commit_at_a_time() {
+        use crate::model::{Commit, GitRepo, Owner, RepoName, SignatureStatus};
+        use chrono::Utc;
+
+        let input = GitInput::new(
+            InputName::new("nixpkgs").unwrap(),
+            GitRepo::github(
+                Owner::new("NixOS").unwrap(),
+                RepoName::new("nixpkgs").unwrap(),
+            ),
+            None,
+            GitRev::new("abcdef3").unwrap(),
+            0,
+            "github:NixOS/nixpkgs".to_string(),
+        );
+        let commits = (0..4)
+            .map(|idx| Commit {
+                sha: format!("abcdef{}", idx),
+                message: "message".to_string(),
+                author: "alice".to_string(),
+                date: Utc::now(),
+                signature: SignatureStatus::Unknown,
+            })
+            .collect();
+        let data = ChangelogData::new(commits, Some(3)).unwrap();
+        let mut cs = ChangelogState::new(input, data, ListState::new(flake(&[])));
+
+        // The locked commit brings nothing in
+        assert!(cs.cursor_summary().is_none());
+        assert_eq!(cs.range_stats_to_load(), None);
+
+        cs.cursor_up();
+        let (commit, summary) = cs.cursor_summary().unwrap();
+        assert_eq!(commit.sha, "abcdef2");
+        assert_eq!(summary.ahead.value(), 1);
+        assert_eq!(
+            cs.range_stats_to_load(),
+            Some(("abcdef3".to_string(), "abcdef2".to_string()))
+        );
+        cs.cursor_up();
+        assert_eq!(cs.range_stats_to_load(), None);
+
+        let stats = DiffStats {
+            files_changed: 1,
+            insertions: 2,
+            deletions: 3,
+            partial: false,
+        };
+        cs.set_range_stats("abcdef2".to_string(), Some(stats));
+        assert_eq!(
+            cs.range_stats_to_load(),
+            Some(("abcdef3".to_string(), "abcdef1".to_string()))
+        );
+        cs.cursor_down();
+        assert_eq!(cs.cursor_summary().unwrap().1.diff_stats, Some(stats));
+
+        // The newest commit is summed up by the whole changelog
+        cs.cursor_up();
+        cs.cursor_up();
+        assert!(cs.cursor_summary().is_none());
+    }
+
+    #[test]
+    fn changelog_tag_view_locks_to_tagged_commit() {
+        use crate::model::{Commit, GitRepo, Owner, RepoName, SignatureStatus};
+        use chrono::Utc;
+
+        let input = GitInput::new(
+            InputName::new("home-manager").unwrap(),
+            GitRepo::github(
+                Owner::new("nix-community").unwrap(),
+                RepoName::new("home-manager").unwrap(),
+            ),
+            None,
+            GitRev::new("abcdef3").unwrap(),
+            0,
+            "github:nix-community/home-manager".to_string(),
+        );
+        let commits = (0..4)
+            .map(|idx| Commit {
+                sha: format!("abcdef{}", idx),
+                message: "message".to_string(),
+                author: "alice".to_string(),
+                date: Utc::now(),
+                signature: SignatureStatus::Unknown,
+            })
+            .collect();
+        let data = ChangelogData::new(commits, Some(3)).unwrap();
+        let mut cs = ChangelogState::new(input, data, ListState::new(flake(&[])));
+
+        assert!(cs.toggle_tags());
+        assert!(!cs.toggle_tags());
+        assert!(!cs.toggle_tags());
+        assert_eq!(cs.view, ChangelogView::Tags);
+
+        let tag = |name: &str, sha: &str| Tag {
+            name: name.to_string(),
+            sha: sha.to_string(),
+        };
+        // The locked commit and unknown commits are not newer tags
+        cs.set_tags(vec![
+            tag("v1.0", "abcdef3"),
+            tag("v1.1", "abcdef2"),
+            tag("v1.2", "abcdef0"),
+            tag("other", "fedcba9"),
+        ]);
+        let names: Vec<&str> = cs.new_tags().iter().map(|(_, t)| t.name.as_str()).collect();
+        assert_eq!(names, ["v1.2", "v1.1"]);
+
+        cs.cursor_down();
+        cs.cursor_down();
+        cs.show_confirm();
+        assert_eq!(cs.lock_target().unwrap().commit_idx(), 2);
+        cs.hide_confirm();
+
+        assert_eq!(cs.selected_tag().map(|t| t.name.as_str()), Some("v1.1"));
+        cs.toggle_tags();
+        assert_eq!(cs.selected_tag().map(|t| t.name.as_str()), Some("v1.0"));
+
+        // A release arriving for a pane that was closed or replaced is dropped
+        cs.open_release("v1.0".to_string());
+        cs.set_release("v1.1", None);
+        assert!(cs.release.as_ref().unwrap().loading);
+        cs.set_release("v1.0", None);
+        assert!(!cs.release.as_ref().unwrap().loading);
+        cs.scroll_pane(-1);
+        cs.scroll_pane(3);
+        assert_eq!(cs.release.as_ref().unwrap().scroll, 3);
+        cs.close_pane();
+        assert!(!cs.has_pane());
+    }
+
+    #[test]
+    fn changelog_fills_in_as_pages_arrive() {
+        use crate::model::{CommitCount, GitRepo, Owner, RepoName, SignatureStatus};
+        use chrono::Utc;
+
+        let input = GitInput::new(
+            InputName::new("nixpkgs").unwrap(),
+            GitRepo::github(
+                Owner::new("NixOS").unwrap(),
+                RepoName::new("nixpkgs").unwrap(),
+            ),
+            None,
+            GitRev::new("abc1234").unwrap(),
+            0,
+            "github:NixOS/nixpkgs".to_string(),
+        );
+        let commits = |shas: &[&str]| -> Vec<Commit> {
+            shas.iter()
+                .map(|sha| Commit {
+                    sha: sha.to_string(),
+                    message: "message".to_string(),
+                    author: "alice".to_string(),
+                    date: Utc::now(),
+                    signature: SignatureStatus::Unknown,
+                })
+                .collect()
+        };
+
+        let mut cs = ChangelogState::loading(input, ListState::new(flake(&[])));
+        assert!(cs.loading);
+        cs.add_page(0, commits(&["fff", "eee"]));
+        assert_eq!(cs.data.commits_ahead_count(), CommitCount::AtLeast(2));
+        cs.cursor_down();
+
+        cs.add_page(2, commits(&["ddd", "abc1234"]));
+        assert_eq!(cs.data.locked_index(), Some(3));
+        assert_eq!(cs.cursor, 1);
+
+        // A fallback to the clone starts over from the first commit
+        cs.add_page(0, commits(&["fff"]));
+        assert_eq!(cs.data.commits.len(), 1);
+        assert_eq!(cs.cursor, 0);
+
+        let data = ChangelogData::new(commits(&["fff", "abc1234"]), Some(1)).unwrap();
+        cs.finish_loading(data);
+        assert!(!cs.loading);
+        assert_eq!(cs.data.commits_ahead_count(), CommitCount::Exact(1));
+    }
+
+    #[test]
+    fn search_narrows_rows_and_cursor_skips_hidden_inputs() {
+        let mut list = ListState::new(flake(&["home-manager", "nix-darwin", "nixpkgs", "utils"]));
+        list.start_search();
+        for c in "NIX".chars() {
+            list.push_search(c);
+        }
+
+        let shown = |list: &ListState| {
+            list.rows()
+                .iter()
+                .filter_map(|row| match row {
+                    ListRow::Input(idx) => Some(list.flake.inputs[*idx].name().to_string()),
+                    ListRow::Transitive(_) => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(shown(&list), ["nix-darwin", "nixpkgs"]);
+        assert_eq!(list.current_index(), Some(1));
+        list.cursor_down();
+        list.cursor_down();
+        assert_eq!(list.current_index(), Some(2));
+
+        list.push_search('x');
+        assert_eq!(list.shown_count(), 0);
+        assert_eq!(list.current_index(), None);
+        list.pop_search();
+        list.finish_search();
+        assert!(list.search.as_ref().is_some_and(|search| !search.editing));
+        assert_eq!(list.current_index(), Some(2));
+
+        list.clear_search();
+        assert_eq!(shown(&list).len(), 4);
+    }
+
+    #[test]
+    fn select_all_and_invert_apply_to_shown_inputs() {
+        let mut list = ListState::new(flake(&["home-manager", "nix-darwin", "nixpkgs", "utils"]));
+        list.select_all();
+        assert_eq!(list.selected.len(), 4);
+
+        list.clear_selection();
+        list.toggle_selection();
+        list.invert_selection();
+        let names = |list: &ListState| {
+            let mut names: Vec<String> = list.selected.iter().map(|n| n.to_string()).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(&list), ["nix-darwin", "nixpkgs", "utils"]);
+
+        list.start_search();
+        for c in "nix".chars() {
+            list.push_search(c);
+        }
+        list.invert_selection();
+        assert_eq!(names(&list), ["utils"]);
+        list.select_all();
+        assert_eq!(names(&list), ["nix-darwin", "nixpkgs", "utils"]);
+
+        assert!(list.undo());
+        assert_eq!(names(&list), ["utils"]);
+    }
+
+    #[test]
+    fn list_state_select_matching_adds_to_selection() {
+        let mut list = ListState::new(flake(&["nixpkgs", "nix-darwin", "home-manager"]));
+        list.selected
+            .insert(InputName::new("home-manager").unwrap());
+
+        let matched = list.select_matching(&InputFilter::parse("nix*").unwrap());
+
+        assert_eq!(matched, 2);
+        assert_eq!(list.selected.len(), 3);
+        assert_eq!(
+            list.select_matching(&InputFilter::parse("status=behind").unwrap()),
+            0
+        );
+    }
+
+    #[test]
+    fn list_state_handles_large_flakes() {
+        let mut list = ListState::new(crate::testing::synthetic_flake(5000));
+
+        let matched = list.select_matching(&InputFilter::parse("input-4*").unwrap());
+        assert_eq!(matched, 1000);
+
+        list.update_flake(crate::testing::synthetic_flake(4500));
+        assert_eq!(list.selected.len(), 500);
+    }
+
+    #[test]
+    fn list_state_undo_and_redo_selection_changes() {
+        let mut list = ListState::new(flake(&["a", "b"]));
+        list.toggle_selection();
+        list.cursor_down();
+        list.toggle_selection();
+        list.clear_selection();
+        assert!(!list.has_selection());
+
+        assert!(list.undo());
+        assert_eq!(list.selected.len(), 2);
+        assert!(list.undo());
+        assert_eq!(list.selected.len(), 1);
+
+        assert!(list.redo());
+        assert_eq!(list.selected.len(), 2);
+
+        list.toggle_selection();
+        assert!(!list.redo(), "a new change drops redo history");
+
+        list.clear_selection();
+        list.update_flake(flake(&["a"]));
+        assert!(list.undo());
+        assert_eq!(list.selected.len(), 1, "missing inputs are not restored");
+    }
+
+    #[test]
+    fn list_state_undo_restores_search_and_sort() {
+        let mut list = ListState::new(flake(&["nixpkgs", "nix-darwin", "utils"]));
+        list.start_search();
+        for c in "nix".chars() {
+            list.push_search(c);
+        }
+        list.finish_search();
+        list.start_search();
+        list.finish_search();
+        list.clear_search();
+        list.set_sort(SortOrder::MostBehind);
+        assert!(list.search.is_none());
+
+        assert!(list.undo());
+        assert_eq!(list.sort, SortOrder::Alphabetical);
+        assert!(list.undo());
+        assert_eq!(
+            list.search.as_ref().map(|search| search.query.as_str()),
+            Some("nix"),
+            "an unchanged search is not an undo step"
+        );
+        assert!(list.undo());
+        assert!(list.search.is_none());
+        assert!(!list.undo());
+
+        assert!(list.redo());
+        assert_eq!(list.shown_count(), 2);
+    }
+
+    #[test]
+    fn command_line_cycles_completions() {
+        let mut line = CommandLine::default();
+        line.start_completion(
+            "update ".to_string(),
+            vec!["nixpkgs".to_string(), "nix-darwin".to_string()],
+        );
+        assert_eq!(line.input, "update nixpkgs");
+        line.cycle_completion(false);
+        assert_eq!(line.input, "update nix-darwin");
+        line.cycle_completion(false);
+        assert_eq!(line.input, "update nixpkgs");
+        line.cycle_completion(true);
+        assert_eq!(line.input, "update nix-darwin");
+
+        let mut line = CommandLine::default();
+        line.start_completion(String::new(), vec!["refresh".to_string()]);
+        assert_eq!(line.input, "refresh ");
+        assert!(line.completion.is_none());
+    }
+
     #[test]
     fn list_state_selection_drops_missing_names_after_refresh() {
         let mut list = ListState::new(flake(&["a", "b"]));
@@ -397,4 +2998,22 @@ mod tests {
         assert_eq!(list.selected.len(), 1);
         assert!(list.selected.contains(&InputName::new("b").unwrap()));
     }
+
+    #[test]
+    fn tasks_pane_cursor_follows_the_task_list() {
+        let mut pane = TasksPaneState::default();
+        pane.cursor_down(3);
+        pane.cursor_down(3);
+        pane.cursor_down(3);
+        assert_eq!(pane.cursor, 2);
+        assert_eq!(pane.table_state.selected(), Some(2));
+
+        // Finished tasks dropping out of the history pull the cursor back.
+        pane.clamp(1);
+        assert_eq!(pane.cursor, 0);
+        pane.clamp(0);
+        assert_eq!(pane.table_state.selected(), None);
+        pane.cursor_up(0);
+        assert_eq!(pane.cursor, 0);
+    }
 }
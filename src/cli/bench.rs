@@ -0,0 +1,211 @@
+//! Benchmark harness for the update check pipeline
+//!
+//! `melt bench` loads a flake once, then runs the same update checks the TUI
+//! performs several times and prints per-input and per-forge timings, so
+//! performance changes in `GitService` can be measured.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::config::ServiceConfig;
+use crate::error::AppResult;
+use crate::model::{FlakeInput, UpdateStatus};
+use crate::service::{GitService, NixService};
+
+/// Timing of a single input check in one iteration
+#[derive(Debug, Clone)]
+pub struct BenchSample {
+    pub iteration: usize,
+    pub input: String,
+    pub forge: &'static str,
+    pub status: UpdateStatus,
+    pub elapsed: Duration,
+}
+
+/// Collected timings for a benchmark run
+#[derive(Debug, Clone, Default)]
+pub struct BenchReport {
+    /// Wall-clock time of each full pipeline run
+    pub iterations: Vec<Duration>,
+    /// Per-input samples across all iterations
+    pub samples: Vec<BenchSample>,
+}
+
+/// Load the flake at `path` and run the update check pipeline `iterations` times.
+pub async fn run(path: &Path, iterations: usize, config: ServiceConfig) -> AppResult<BenchReport> {
+    let cancel_token = CancellationToken::new();
+    let nix = NixService::new_with_config(cancel_token.clone(), config.clone());
+    let git = GitService::new_with_config(cancel_token, config);
+
+    let flake = nix.load_metadata(path).await?;
+    let inputs: Vec<_> = flake
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FlakeInput::Git(git_input) => Some(git_input.clone()),
+            _ => None,
+        })
+        .collect();
+    let forges: BTreeMap<String, &'static str> = inputs
+        .iter()
+        .map(|input| (input.name().to_string(), input.repo().forge_name()))
+        .collect();
+
+    let mut report = BenchReport::default();
+    for iteration in 1..=iterations.max(1) {
+        let started = Instant::now();
+        git.check_updates_timed(&inputs, |name, status, info| {
+            let forge = forges.get(name.as_str()).copied().unwrap_or("git");
+            report.samples.push(BenchSample {
+                iteration,
+                input: name.into_string(),
+                forge,
+                status,
+                elapsed: info.elapsed,
+            });
+        })
+        .await?;
+        report.iterations.push(started.elapsed());
+    }
+
+    Ok(report)
+}
+
+impl BenchReport {
+    /// Render the report as plain text
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let inputs: BTreeMap<&str, Vec<&BenchSample>> =
+            self.samples
+                .iter()
+                .fold(BTreeMap::new(), |mut acc, sample| {
+                    acc.entry(sample.input.as_str())
+                        .or_insert_with(Vec::new)
+                        .push(sample);
+                    acc
+                });
+
+        let _ = writeln!(
+            out,
+            "Checked {} git inputs over {} iterations",
+            inputs.len(),
+            self.iterations.len()
+        );
+
+        let _ = writeln!(out, "\nIterations");
+        for (idx, elapsed) in self.iterations.iter().enumerate() {
+            let _ = writeln!(out, "  #{:<4} {:>8} ms", idx + 1, elapsed.as_millis());
+        }
+        if let Some((min, mean, max)) = stats(&self.iterations) {
+            let _ = writeln!(out, "  min {} ms, mean {} ms, max {} ms", min, mean, max);
+        }
+
+        let mut forges: BTreeMap<&str, (usize, Vec<Duration>)> = BTreeMap::new();
+        for samples in inputs.values() {
+            let Some(first) = samples.first() else {
+                continue;
+            };
+            let entry = forges.entry(first.forge).or_default();
+            entry.0 += 1;
+            entry.1.extend(samples.iter().map(|sample| sample.elapsed));
+        }
+
+        let _ = writeln!(out, "\nPer forge");
+        let _ = writeln!(
+            out,
+            "  {:<12} {:>6} {:>10} {:>10}",
+            "FORGE", "INPUTS", "MEAN ms", "MAX ms"
+        );
+        for (forge, (count, durations)) in &forges {
+            if let Some((_, mean, max)) = stats(durations) {
+                let _ = writeln!(out, "  {:<12} {:>6} {:>10} {:>10}", forge, count, mean, max);
+            }
+        }
+
+        let mut rows: Vec<(&str, &str, u128, u128, u128, String)> = inputs
+            .iter()
+            .filter_map(|(input, samples)| {
+                let durations: Vec<Duration> = samples.iter().map(|s| s.elapsed).collect();
+                let (min, mean, max) = stats(&durations)?;
+                let last = samples.iter().max_by_key(|s| s.iteration)?;
+                Some((*input, last.forge, min, mean, max, last.status.display()))
+            })
+            .collect();
+        rows.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| a.0.cmp(b.0)));
+
+        let _ = writeln!(out, "\nPer input (slowest first)");
+        let _ = writeln!(
+            out,
+            "  {:<30} {:<10} {:>8} {:>8} {:>8}  STATUS",
+            "INPUT", "FORGE", "MIN ms", "MEAN ms", "MAX ms"
+        );
+        for (input, forge, min, mean, max, status) in rows {
+            let _ = writeln!(
+                out,
+                "  {:<30} {:<10} {:>8} {:>8} {:>8}  {}",
+                input, forge, min, mean, max, status
+            );
+        }
+
+        out
+    }
+}
+
+/// Minimum, mean, and maximum in milliseconds
+fn stats(durations: &[Duration]) -> Option<(u128, u128, u128)> {
+    let min = durations.iter().min()?.as_millis();
+    let max = durations.iter().max()?.as_millis();
+    let total: u128 = durations.iter().map(Duration::as_millis).sum();
+    Some((min, total / durations.len() as u128, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(iteration: usize, input: &str, forge: &'static str, ms: u64) -> BenchSample {
+        BenchSample {
+            iteration,
+            input: input.to_string(),
+            forge,
+            status: UpdateStatus::UpToDate,
+            elapsed: Duration::from_millis(ms),
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let durations = [
+            Duration::from_millis(10),
+            Duration::from_millis(20),
+            Duration::from_millis(60),
+        ];
+        assert_eq!(stats(&durations), Some((10, 30, 60)));
+        assert_eq!(stats(&[]), None);
+    }
+
+    #[test]
+    fn test_render_orders_inputs_slowest_first() {
+        let report = BenchReport {
+            iterations: vec![Duration::from_millis(100), Duration::from_millis(300)],
+            samples: vec![
+                sample(1, "fast", "github", 10),
+                sample(1, "slow", "git", 90),
+                sample(2, "fast", "github", 20),
+                sample(2, "slow", "git", 250),
+            ],
+        };
+
+        let rendered = report.render();
+
+        assert!(rendered.contains("Checked 2 git inputs over 2 iterations"));
+        assert!(rendered.contains("min 100 ms, mean 200 ms, max 300 ms"));
+        let slow = rendered.find("  slow").unwrap();
+        let fast = rendered.find("  fast").unwrap();
+        assert!(slow < fast);
+    }
+}
@@ -0,0 +1,209 @@
+//! Headless `melt changelog` subcommand
+//!
+//! Prints the commits an update of one input would pull in, without opening
+//! the TUI.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::ServiceConfig;
+use crate::error::{AppError, AppResult};
+use crate::model::{ChangelogData, Commit, FlakeInput};
+use crate::service::{GitService, NixService};
+
+/// Output format for `melt changelog`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ChangelogFormat {
+    /// One commit per line
+    #[default]
+    Text,
+    /// A JSON object with the commits and whether the list was truncated
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonCommit<'a> {
+    sha: &'a str,
+    date: String,
+    author: &'a str,
+    subject: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonChangelog<'a> {
+    input: &'a str,
+    commits: Vec<JsonCommit<'a>>,
+    truncated: bool,
+    /// Paths the commits were narrowed to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    paths: Option<&'a [String]>,
+}
+
+/// Load the flake at `path` and render the new commits for `input` in `format`.
+pub async fn run(
+    path: &Path,
+    input: &str,
+    format: ChangelogFormat,
+    config: ServiceConfig,
+) -> AppResult<String> {
+    let cancel_token = CancellationToken::new();
+    let nix = NixService::new_with_config(cancel_token.clone(), config.clone());
+    let git = GitService::new_with_config(cancel_token, config);
+
+    let flake = nix.load_metadata(path).await?;
+    let git_input = match flake.inputs.iter().find(|i| i.name() == input) {
+        Some(FlakeInput::Git(git_input)) => git_input,
+        Some(_) => return Err(AppError::NotGitInput(input.to_string())),
+        None => return Err(AppError::InputNotFound(input.to_string())),
+    };
+
+    let data = git.get_changelog(git_input).await?;
+    render(input, &data, format)
+}
+
+/// Render the new commits in `data` in `format`
+pub fn render(input: &str, data: &ChangelogData, format: ChangelogFormat) -> AppResult<String> {
+    let commits = data.new_commits();
+
+    match format {
+        ChangelogFormat::Text => Ok(render_text(commits, data)),
+        ChangelogFormat::Json => {
+            let changelog = JsonChangelog {
+                input,
+                commits: commits
+                    .iter()
+                    .map(|commit| JsonCommit {
+                        sha: &commit.sha,
+                        date: commit.date.to_rfc3339(),
+                        author: &commit.author,
+                        subject: subject(commit),
+                    })
+                    .collect(),
+                truncated: data.truncated_at().is_some(),
+                paths: data.path_filter().map(|filter| filter.paths.as_slice()),
+            };
+            serde_json::to_string_pretty(&changelog)
+                .map(|json| json + "\n")
+                .map_err(|e| AppError::Output(e.to_string()))
+        }
+    }
+}
+
+fn render_text(commits: &[Commit], data: &ChangelogData) -> String {
+    let mut out = String::new();
+    if commits.is_empty() {
+        match data.path_filter() {
+            Some(filter) if filter.total.value() > 0 => {
+                let _ = writeln!(
+                    out,
+                    "None of {} new commit(s) touch {}",
+                    filter.total,
+                    filter.paths.join(", ")
+                );
+            }
+            _ => {
+                let _ = writeln!(out, "Already up to date");
+            }
+        }
+        return out;
+    }
+
+    for commit in commits {
+        let _ = writeln!(
+            out,
+            "{}  {}  {}  {}",
+            commit.short_sha(),
+            commit.date.format("%Y-%m-%d"),
+            commit.author,
+            subject(commit)
+        );
+    }
+    match data.path_filter() {
+        Some(filter) => {
+            let _ = writeln!(
+                out,
+                "{} of {} new commit(s) touch {}",
+                data.commits_ahead_count(),
+                filter.total,
+                filter.paths.join(", ")
+            );
+        }
+        None => {
+            let _ = writeln!(out, "{} new commit(s)", data.commits_ahead_count());
+        }
+    }
+    out
+}
+
+/// First line of the commit message
+fn subject(commit: &Commit) -> &str {
+    commit.message.lines().next().unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn data() -> ChangelogData {
+        let commit = |sha: &str, message: &str| Commit {
+            sha: sha.to_string(),
+            message: message.to_string(),
+            author: "alice".to_string(),
+            date: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            signature: crate::model::SignatureStatus::Unknown,
+        };
+        ChangelogData::new(
+            vec![
+                commit("aaaaaaa111", "Fix build\n\nLong description"),
+                commit("bbbbbbb222", "Locked commit"),
+            ],
+            Some(1),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn renders_new_commits_as_text() {
+        let text = render("nixpkgs", &data(), ChangelogFormat::Text).unwrap();
+
+        assert_eq!(
+            text,
+            "aaaaaaa  2024-05-01  alice  Fix build\n1 new commit(s)\n"
+        );
+    }
+
+    #[test]
+    fn text_reports_path_narrowing() {
+        use crate::model::CommitCount;
+
+        let narrowed = |total| {
+            let data = data().with_path_filter(crate::model::PathFilter {
+                paths: vec!["pkgs/by-name/ri/ripgrep".to_string()],
+                total,
+            });
+            render("nixpkgs", &data, ChangelogFormat::Text).unwrap()
+        };
+
+        assert!(narrowed(CommitCount::Exact(40))
+            .ends_with("1 of 40 new commit(s) touch pkgs/by-name/ri/ripgrep\n"));
+        assert!(narrowed(CommitCount::AtLeast(500))
+            .ends_with("1 of 500+ new commit(s) touch pkgs/by-name/ri/ripgrep\n"));
+    }
+
+    #[test]
+    fn renders_json_with_subjects_only() {
+        let json = render("nixpkgs", &data(), ChangelogFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["input"], "nixpkgs");
+        assert_eq!(value["truncated"], false);
+        assert_eq!(value["commits"].as_array().unwrap().len(), 1);
+        assert_eq!(value["commits"][0]["subject"], "Fix build");
+        assert_eq!(value["commits"][0]["sha"], "aaaaaaa111");
+    }
+}
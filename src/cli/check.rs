@@ -0,0 +1,210 @@
+//! Headless `melt check` subcommand
+//!
+//! Runs the update checks the TUI performs and reports the result through the
+//! process exit code, so CI jobs can gate on flake freshness without parsing
+//! output.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use tokio_util::sync::CancellationToken;
+
+use crate::config::ServiceConfig;
+use crate::error::AppResult;
+use crate::model::{CommitCount, FlakeInput, UpdateStatus};
+use crate::service::{GitService, NixService};
+
+/// Exit code used when at least one input is more than `--max-behind` commits behind
+pub const EXIT_STALE: u8 = 2;
+
+/// Exit code used when no input is known to be stale, but at least one could
+/// not be checked
+pub const EXIT_UNCHECKED: u8 = 3;
+
+/// Update check result for one git input
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub input: String,
+    pub status: UpdateStatus,
+}
+
+/// Update check results in flake order
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    pub results: Vec<CheckResult>,
+}
+
+/// Load the flake at `path` and check every git input for updates.
+pub async fn run(path: &Path, config: ServiceConfig) -> AppResult<CheckReport> {
+    let cancel_token = CancellationToken::new();
+    let nix = NixService::new_with_config(cancel_token.clone(), config.clone());
+    let git = GitService::new_with_config(cancel_token, config);
+
+    let flake = nix.load_metadata(path).await?;
+    let inputs: Vec<_> = flake
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FlakeInput::Git(git_input) => Some(git_input.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let mut statuses = HashMap::new();
+    git.check_updates_timed(&inputs, |name, status, _| {
+        statuses.insert(name, status);
+    })
+    .await?;
+
+    let results = inputs
+        .iter()
+        .map(|input| CheckResult {
+            input: input.name().to_string(),
+            status: statuses.remove(input.input_name()).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(CheckReport { results })
+}
+
+impl CheckReport {
+    /// Inputs that are more than `max_behind` commits behind
+    pub fn stale(&self, max_behind: usize) -> Vec<&CheckResult> {
+        self.results
+            .iter()
+            .filter(|result| match result.status {
+                UpdateStatus::Behind(count) => count.value() > max_behind,
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Inputs whose check failed, and inputs whose lower-bound count is
+    /// within `max_behind` while the real number of commits may not be
+    pub fn unchecked(&self, max_behind: usize) -> Vec<&CheckResult> {
+        self.results
+            .iter()
+            .filter(|result| match result.status {
+                UpdateStatus::Error(_) | UpdateStatus::RateLimited(_) => true,
+                UpdateStatus::Behind(CommitCount::AtLeast(n)) => n <= max_behind,
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Process exit code for the report: stale inputs take precedence over
+    /// unchecked ones
+    pub fn exit_code(&self, max_behind: usize) -> u8 {
+        if !self.stale(max_behind).is_empty() {
+            EXIT_STALE
+        } else if !self.unchecked(max_behind).is_empty() {
+            EXIT_UNCHECKED
+        } else {
+            0
+        }
+    }
+
+    /// Render the report as plain text
+    pub fn render(&self, max_behind: usize) -> String {
+        let mut out = String::new();
+        let width = self
+            .results
+            .iter()
+            .map(|r| r.input.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        for result in &self.results {
+            let detail = match &result.status {
+                UpdateStatus::Error(err) => format!("? ({})", err),
+                status => status.display(),
+            };
+            let _ = writeln!(out, "{:<width$}  {}", result.input, detail);
+        }
+
+        let stale = self.stale(max_behind).len();
+        let unchecked = self.unchecked(max_behind);
+        if stale > 0 {
+            let _ = writeln!(
+                out,
+                "{} input(s) more than {} commits behind",
+                stale, max_behind
+            );
+        }
+        if !unchecked.is_empty() {
+            let names: Vec<&str> = unchecked.iter().map(|r| r.input.as_str()).collect();
+            let _ = writeln!(
+                out,
+                "{} input(s) could not be checked: {}",
+                unchecked.len(),
+                names.join(", ")
+            );
+        }
+        if stale == 0 && unchecked.is_empty() {
+            let _ = writeln!(out, "All inputs within {} commits of upstream", max_behind);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(statuses: Vec<UpdateStatus>) -> CheckReport {
+        CheckReport {
+            results: statuses
+                .into_iter()
+                .enumerate()
+                .map(|(idx, status)| CheckResult {
+                    input: format!("input{}", idx),
+                    status,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn stale_respects_threshold() {
+        let report = report(vec![
+            UpdateStatus::UpToDate,
+            UpdateStatus::Behind(CommitCount::Exact(3)),
+            UpdateStatus::Behind(CommitCount::Exact(10)),
+            UpdateStatus::Error("offline".to_string()),
+        ]);
+
+        assert_eq!(report.stale(0).len(), 2);
+        assert_eq!(report.stale(3).len(), 1);
+        assert_eq!(report.stale(10).len(), 0);
+        let text = report.render(3);
+        assert!(text.contains("1 input(s) more than 3 commits behind"));
+        assert!(text.contains("1 input(s) could not be checked: input3"));
+        assert_eq!(report.exit_code(3), EXIT_STALE);
+        assert_eq!(report.exit_code(10), EXIT_UNCHECKED);
+    }
+
+    #[test]
+    fn failed_checks_do_not_pass() {
+        let report = report(vec![
+            UpdateStatus::Error("offline".to_string()),
+            UpdateStatus::RateLimited(std::time::Instant::now()),
+        ]);
+
+        assert!(report.stale(0).is_empty());
+        assert_eq!(report.unchecked(0).len(), 2);
+        assert_eq!(report.exit_code(0), EXIT_UNCHECKED);
+        assert!(!report.render(0).contains("All inputs within"));
+    }
+
+    #[test]
+    fn lower_bound_counts_within_threshold_are_unchecked() {
+        let report = report(vec![UpdateStatus::Behind(CommitCount::AtLeast(500))]);
+
+        assert_eq!(report.stale(499).len(), 1);
+        assert!(report.stale(500).is_empty());
+        assert_eq!(report.unchecked(500).len(), 1);
+        assert_eq!(report.exit_code(501), EXIT_UNCHECKED);
+        assert!(report.unchecked(499).is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+//! Headless `melt diff` subcommand
+//!
+//! Compares the working tree `flake.lock` with the one committed at a git
+//! revision and summarizes how each input's locked revision moved.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use chrono::{TimeZone, Utc};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::ServiceConfig;
+use crate::error::AppResult;
+use crate::model::{CommitCount, FlakeData, FlakeInput, GitRev};
+use crate::service::{discovery_root, parse_lock_file, read_lock, read_lock_at_rev, GitService};
+use crate::util::format::format_count;
+
+/// How one input differs between the two lock files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputDiff {
+    Added {
+        rev: Option<String>,
+    },
+    Removed {
+        rev: Option<String>,
+    },
+    Changed {
+        from: Option<String>,
+        to: Option<String>,
+        from_date: Option<i64>,
+        to_date: Option<i64>,
+        /// Commits between the two revisions, or why they could not be counted
+        commits: Option<Result<CommitCount, String>>,
+    },
+}
+
+/// Per-input differences between two lock files
+#[derive(Debug, Clone, Default)]
+pub struct LockDiff {
+    pub base: String,
+    pub inputs: Vec<(String, InputDiff)>,
+    pub unchanged: usize,
+}
+
+/// Diff the working tree `flake.lock` at `path` against the one committed at `rev`.
+pub async fn run(path: &Path, rev: &str, config: ServiceConfig) -> AppResult<LockDiff> {
+    let dir = discovery_root(path);
+    let before = parse_lock_file(dir.clone(), &read_lock_at_rev(&dir, rev)?)?;
+    let after = parse_lock_file(dir.clone(), &read_lock(&dir)?)?;
+
+    let mut diff = compare(&before, &after);
+    diff.base = rev.to_string();
+
+    let git = GitService::new_with_config(CancellationToken::new(), config);
+    for (name, input_diff) in &mut diff.inputs {
+        let InputDiff::Changed { commits, .. } = input_diff else {
+            continue;
+        };
+        let Some(FlakeInput::Git(input)) = after.inputs.iter().find(|i| i.name() == name) else {
+            continue;
+        };
+        let Some(full_from) = before
+            .inputs
+            .iter()
+            .find(|i| i.name() == name)
+            .and_then(FlakeInput::locked_rev)
+        else {
+            continue;
+        };
+        let Ok(from_rev) = GitRev::new(full_from) else {
+            continue;
+        };
+        *commits = Some(
+            git.count_commits_between(input, &from_rev)
+                .await
+                .map_err(|e| e.to_string()),
+        );
+    }
+
+    Ok(diff)
+}
+
+/// Compare locked revisions by input name, without counting commits
+fn compare(before: &FlakeData, after: &FlakeData) -> LockDiff {
+    let mut diff = LockDiff::default();
+    let short = |input: &FlakeInput| input.short_rev().map(str::to_string);
+
+    for input in &after.inputs {
+        match before.inputs.iter().find(|old| old.name() == input.name()) {
+            None => diff.inputs.push((
+                input.name().to_string(),
+                InputDiff::Added { rev: short(input) },
+            )),
+            Some(old) if old.locked_rev() == input.locked_rev() => diff.unchanged += 1,
+            Some(old) => diff.inputs.push((
+                input.name().to_string(),
+                InputDiff::Changed {
+                    from: short(old),
+                    to: short(input),
+                    from_date: old.last_modified(),
+                    to_date: input.last_modified(),
+                    commits: None,
+                },
+            )),
+        }
+    }
+
+    for old in &before.inputs {
+        if !after.inputs.iter().any(|input| input.name() == old.name()) {
+            diff.inputs.push((
+                old.name().to_string(),
+                InputDiff::Removed { rev: short(old) },
+            ));
+        }
+    }
+
+    diff
+}
+
+fn format_date(timestamp: Option<i64>) -> String {
+    timestamp
+        .and_then(|t| Utc.timestamp_opt(t, 0).single())
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+impl LockDiff {
+    /// Render the diff as plain text
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        if self.inputs.is_empty() {
+            let _ = writeln!(out, "No changes to flake.lock since {}", self.base);
+            return out;
+        }
+
+        let width = self
+            .inputs
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let rev = |rev: &Option<String>| rev.clone().unwrap_or_else(|| "-".to_string());
+
+        for (name, input_diff) in &self.inputs {
+            let detail = match input_diff {
+                InputDiff::Added { rev: r } => format!("added    {}", rev(r)),
+                InputDiff::Removed { rev: r } => format!("removed  {}", rev(r)),
+                InputDiff::Changed {
+                    from,
+                    to,
+                    from_date,
+                    to_date,
+                    commits,
+                } => {
+                    let commits = match commits {
+                        Some(Ok(CommitCount::Exact(n))) => format!("+{} commits", format_count(*n)),
+                        Some(Ok(count)) => format!("{} commits", count),
+                        Some(Err(_)) | None => "? commits".to_string(),
+                    };
+                    format!(
+                        "{} -> {}  {}  ({} -> {})",
+                        rev(from),
+                        rev(to),
+                        commits,
+                        format_date(*from_date),
+                        format_date(*to_date)
+                    )
+                }
+            };
+            let _ = writeln!(out, "{:<width$}  {}", name, detail);
+        }
+        let _ = writeln!(
+            out,
+            "{} changed, {} unchanged since {}",
+            self.inputs.len(),
+            self.unchanged,
+            self.base
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{OtherInput, PathInput};
+    use std::path::PathBuf;
+
+    fn flake(inputs: Vec<FlakeInput>) -> FlakeData {
+        FlakeData {
+            path: PathBuf::from("/tmp/flake"),
+            warnings: Vec::new(),
+            input_trees: Vec::new(),
+            inputs,
+        }
+    }
+
+    fn tarball(name: &str, rev: &str, last_modified: i64) -> FlakeInput {
+        FlakeInput::Other(OtherInput {
+            name: name.to_string(),
+            rev: Some(rev.to_string()),
+            last_modified,
+            flake: true,
+        })
+    }
+
+    fn path(name: &str) -> FlakeInput {
+        FlakeInput::Path(PathInput {
+            name: name.to_string(),
+            flake: true,
+        })
+    }
+
+    #[test]
+    fn compare_reports_added_removed_and_changed() {
+        let before = flake(vec![
+            tarball("a", "1111111aaa", 1_700_000_000),
+            tarball("b", "2222222bbb", 0),
+            path("gone"),
+        ]);
+        let after = flake(vec![
+            tarball("a", "3333333ccc", 1_710_000_000),
+            tarball("b", "2222222bbb", 0),
+            path("new"),
+        ]);
+
+        let mut diff = compare(&before, &after);
+        diff.base = "HEAD".to_string();
+
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.inputs.len(), 3);
+        assert_eq!(
+            diff.inputs[1],
+            ("new".to_string(), InputDiff::Added { rev: None })
+        );
+        assert_eq!(
+            diff.inputs[2],
+            ("gone".to_string(), InputDiff::Removed { rev: None })
+        );
+
+        let text = diff.render();
+        assert!(text.contains("a     1111111 -> 3333333  ? commits  (2023-11-14 -> 2024-03-09)"));
+        assert!(text.contains("3 changed, 1 unchanged since HEAD"));
+    }
+}
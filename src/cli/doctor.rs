@@ -0,0 +1,514 @@
+//! Headless `melt doctor` subcommand
+//!
+//! Checks the environment melt depends on (nix and its flakes feature, the
+//! flake, forge tokens, network access, the git cache, and the terminal) and
+//! prints a fix for every problem, so setup issues can be diagnosed without
+//! reading logs.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crossterm::tty::IsTty;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{ForgeTokens, ServiceConfig};
+use crate::error::AppError;
+use crate::model::{FlakeData, FlakeInput, GitRepo};
+use crate::service::{discovery_root, token_lookup, GitService, NixService, NixVersion};
+
+/// Outcome of a single diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Outcome {
+    fn label(self) -> &'static str {
+        match self {
+            Outcome::Pass => "ok",
+            Outcome::Warn => "warn",
+            Outcome::Fail => "fail",
+        }
+    }
+}
+
+/// Result of one diagnostic, with a fix when it did not pass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub name: String,
+    pub outcome: Outcome,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Diagnostic {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            outcome: Outcome::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            outcome: Outcome::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            outcome: Outcome::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// All diagnostics in the order they ran
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Run every diagnostic for the flake at `path`.
+pub async fn run(path: &Path, config: ServiceConfig) -> DoctorReport {
+    let cancel_token = CancellationToken::new();
+    let nix = NixService::new_with_config(cancel_token.clone(), config.clone());
+    let tokens = config.forge_tokens.clone();
+    let token_in_config = tokens.get("github.com").is_some();
+    let git = GitService::new_with_config(cancel_token, config);
+    let mut diagnostics = Vec::new();
+
+    let nix_ok = match nix.version().await {
+        Ok(version) => match NixVersion::parse(&version).filter(|v| !v.is_supported()) {
+            Some(old) => {
+                diagnostics.push(Diagnostic::fail(
+                    "nix",
+                    AppError::UnsupportedNix(old.to_string()).to_string(),
+                    "Upgrade Nix, or point nix_binary at a newer one",
+                ));
+                false
+            }
+            None => {
+                diagnostics.push(Diagnostic::pass("nix", version));
+                true
+            }
+        },
+        Err(AppError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            diagnostics.push(Diagnostic::fail(
+                "nix",
+                match nix.nix_binary() {
+                    binary if binary.components().count() > 1 => {
+                        format!("{} not found", binary.display())
+                    }
+                    binary => format!("{} not found on PATH", binary.display()),
+                },
+                "Install Nix (https://nixos.org/download) and open a new shell",
+            ));
+            false
+        }
+        Err(e) => {
+            diagnostics.push(Diagnostic::fail(
+                "nix",
+                e.to_string(),
+                "Check that `nix --version` runs in this shell",
+            ));
+            false
+        }
+    };
+
+    if nix_ok {
+        diagnostics.push(check_flakes_enabled(&nix).await);
+    }
+    let flake = check_flake(&nix, path, nix_ok, &mut diagnostics).await;
+    diagnostics.push(check_github_token(&git, token_in_config));
+    diagnostics.extend(check_forge_tokens(&tokens, flake.as_ref()));
+    if let Some(flake) = &flake {
+        for host in forge_hosts(flake) {
+            diagnostics.push(check_host(&git, &host).await);
+        }
+    }
+    diagnostics.push(check_cache(git.cache_dir()));
+    diagnostics.push(check_terminal(
+        std::io::stdout().is_tty(),
+        std::env::var("TERM").ok().as_deref(),
+        crossterm::terminal::size().ok(),
+    ));
+
+    DoctorReport { diagnostics }
+}
+
+async fn check_flakes_enabled(nix: &NixService) -> Diagnostic {
+    const FIX: &str = "Add `experimental-features = nix-command flakes` to ~/.config/nix/nix.conf";
+    match nix.experimental_features().await {
+        Ok(features) if features.iter().any(|f| f == "flakes") => {
+            Diagnostic::pass("flakes", "enabled")
+        }
+        Ok(_) => Diagnostic::fail("flakes", "the flakes feature is not enabled", FIX),
+        Err(e) => Diagnostic::warn(
+            "flakes",
+            format!("could not read nix config: {}", e),
+            format!("If flake commands fail: {}", FIX),
+        ),
+    }
+}
+
+/// Check that `path` holds a flake nix can evaluate, returning it when it does
+async fn check_flake(
+    nix: &NixService,
+    path: &Path,
+    nix_ok: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<FlakeData> {
+    let dir = discovery_root(path);
+    if !dir.join("flake.nix").is_file() {
+        diagnostics.push(Diagnostic::fail(
+            "flake",
+            format!("No flake.nix found in {}", dir.display()),
+            "Pass --flake <dir>, or create one with `melt init`",
+        ));
+        return None;
+    }
+    if !nix_ok {
+        diagnostics.push(Diagnostic::warn(
+            "flake",
+            format!("{} not evaluated", dir.display()),
+            "Fix the nix check above, then run `melt doctor` again",
+        ));
+        return None;
+    }
+
+    match nix.load_metadata(&dir).await {
+        Ok(flake) => {
+            diagnostics.push(Diagnostic::pass(
+                "flake",
+                format!("{} ({} inputs)", dir.display(), flake.inputs.len()),
+            ));
+            Some(flake)
+        }
+        Err(e) => {
+            let message = e.to_string();
+            let fix = if message.contains("experimental") {
+                "Enable flakes: add `experimental-features = nix-command flakes` to ~/.config/nix/nix.conf"
+            } else {
+                "Run `nix flake metadata` in the flake directory to see the full error"
+            };
+            diagnostics.push(Diagnostic::fail("flake", message, fix));
+            None
+        }
+    }
+}
+
+fn check_github_token(git: &GitService, in_config: bool) -> Diagnostic {
+    let source = if in_config {
+        Some("the config file")
+    } else if std::env::var_os("GITHUB_TOKEN").is_some() {
+        Some("GITHUB_TOKEN")
+    } else if std::env::var_os("GH_TOKEN").is_some() {
+        Some("GH_TOKEN")
+    } else if git.has_github_token() {
+        Some("gh auth token")
+    } else {
+        None
+    };
+
+    match source {
+        Some(source) => Diagnostic::pass("github token", format!("found via {}", source)),
+        None => Diagnostic::warn(
+            "github token",
+            "not set; GitHub allows 60 API requests/hour",
+            "Set GITHUB_TOKEN (or run `gh auth login`) to raise the limit to 5000/hour",
+        ),
+    }
+}
+
+/// Check the token of every other forge host the flake's inputs use, and of
+/// every host with a token in the config file
+fn check_forge_tokens(tokens: &ForgeTokens, flake: Option<&FlakeData>) -> Vec<Diagnostic> {
+    let mut hosts: BTreeMap<String, &[&str]> = tokens
+        .hosts()
+        .map(|host| (host.to_string(), &[][..]))
+        .collect();
+    let inputs = flake.map_or(&[][..], |flake| flake.inputs.as_slice());
+    for input in inputs {
+        if let FlakeInput::Git(git) = input {
+            if let Some((host, env_vars)) = token_lookup(git.repo()) {
+                hosts.insert(host.to_ascii_lowercase(), env_vars);
+            }
+        }
+    }
+    // github.com has its own check, which also asks `gh`
+    hosts.remove("github.com");
+    hosts
+        .into_iter()
+        .map(|(host, env_vars)| check_token(tokens, &host, env_vars))
+        .collect()
+}
+
+fn check_token(tokens: &ForgeTokens, host: &str, env_vars: &[&str]) -> Diagnostic {
+    let name = format!("{} token", host);
+    if tokens.get(host).is_some() {
+        return Diagnostic::pass(name, "found via the config file");
+    }
+    let set = env_vars
+        .iter()
+        .find(|var| std::env::var_os(var).is_some_and(|value| !value.is_empty()));
+    if let Some(var) = set {
+        return Diagnostic::pass(name, format!("found via {}", var));
+    }
+    let fix = match env_vars.first() {
+        Some(var) => format!(
+            "Set {}, or add {} to [tokens] in the config file",
+            var, host
+        ),
+        None => format!("Add {} to [tokens] in the config file", host),
+    };
+    Diagnostic::warn(name, "not set; private repositories cannot be queried", fix)
+}
+
+async fn check_host(git: &GitService, host: &str) -> Diagnostic {
+    let name = format!("reach {}", host);
+    match git.probe_host(host).await {
+        Ok(()) => Diagnostic::pass(name, "reachable over HTTPS"),
+        Err(e) => Diagnostic::fail(
+            name,
+            e.to_string(),
+            "Check your network connection and proxy settings (HTTPS_PROXY)",
+        ),
+    }
+}
+
+/// Check that the git cache is writable and every cached clone opens
+fn check_cache(dir: &Path) -> Diagnostic {
+    let probe = dir.join(".melt-doctor");
+    if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(&probe, b"")) {
+        return Diagnostic::fail(
+            "git cache",
+            format!("{} is not writable: {}", dir.display(), e),
+            format!("Fix the permissions on {}", dir.display()),
+        );
+    }
+    let _ = fs::remove_file(&probe);
+
+    let mut clones = 0;
+    let mut broken = Vec::new();
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        clones += 1;
+        if git2::Repository::open(entry.path()).is_err() {
+            broken.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+
+    if broken.is_empty() {
+        Diagnostic::pass(
+            "git cache",
+            format!("{} ({} cached clones)", dir.display(), clones),
+        )
+    } else {
+        Diagnostic::warn(
+            "git cache",
+            format!(
+                "{} unreadable clone(s): {}",
+                broken.len(),
+                broken.join(", ")
+            ),
+            format!(
+                "Delete them from {}; melt clones them again when needed",
+                dir.display()
+            ),
+        )
+    }
+}
+
+/// Smallest terminal the list view renders comfortably in
+const MIN_TERMINAL_SIZE: (u16, u16) = (80, 24);
+
+/// Check that the TUI can run in this terminal
+fn check_terminal(is_tty: bool, term: Option<&str>, size: Option<(u16, u16)>) -> Diagnostic {
+    if !is_tty {
+        return Diagnostic::warn(
+            "terminal",
+            "stdout is not a terminal",
+            "Run melt directly in a terminal; headless subcommands work without one",
+        );
+    }
+    if matches!(term, None | Some("") | Some("dumb")) {
+        return Diagnostic::fail(
+            "terminal",
+            format!(
+                "TERM is {}",
+                term.filter(|t| !t.is_empty()).unwrap_or("unset")
+            ),
+            "Set TERM to your terminal's type, e.g. xterm-256color",
+        );
+    }
+
+    let (min_cols, min_rows) = MIN_TERMINAL_SIZE;
+    match size {
+        Some((cols, rows)) if cols < min_cols || rows < min_rows => Diagnostic::warn(
+            "terminal",
+            format!(
+                "{}x{} is smaller than {}x{}",
+                cols, rows, min_cols, min_rows
+            ),
+            "Enlarge the window so columns and help text are not cut off",
+        ),
+        Some((cols, rows)) => Diagnostic::pass(
+            "terminal",
+            format!("{} ({}x{})", term.unwrap_or_default(), cols, rows),
+        ),
+        None => Diagnostic::warn(
+            "terminal",
+            "could not read the terminal size",
+            "Check that the terminal reports its size (try `stty size`)",
+        ),
+    }
+}
+
+/// HTTPS hosts melt talks to for the flake's git inputs
+fn forge_hosts(flake: &FlakeData) -> BTreeSet<String> {
+    flake
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FlakeInput::Git(git) => forge_host(git.repo()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn forge_host(repo: &GitRepo) -> Option<String> {
+    match repo {
+        GitRepo::GitHub { .. } => Some("api.github.com".to_string()),
+        GitRepo::Codeberg { .. } => Some("codeberg.org".to_string()),
+        GitRepo::GitHubEnterprise { host, .. }
+        | GitRepo::GitLab { host, .. }
+        | GitRepo::SourceHut { host, .. }
+        | GitRepo::Gitea { host, .. } => Some(host.to_string()),
+        GitRepo::Generic { .. } => None,
+    }
+}
+
+impl DoctorReport {
+    /// Whether any diagnostic failed
+    pub fn has_failures(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.outcome == Outcome::Fail)
+    }
+
+    /// Render one line per diagnostic, with indented fixes
+    pub fn render(&self) -> String {
+        let width = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.name.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut out = String::new();
+        for diagnostic in &self.diagnostics {
+            let _ = writeln!(
+                out,
+                "[{:<4}] {:<width$}  {}",
+                diagnostic.outcome.label(),
+                diagnostic.name,
+                diagnostic.detail,
+                width = width
+            );
+            if let Some(fix) = &diagnostic.fix {
+                let _ = writeln!(out, "       {:<width$}  fix: {}", "", fix, width = width);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_lists_fixes_under_failed_checks() {
+        let report = DoctorReport {
+            diagnostics: vec![
+                Diagnostic::pass("nix", "nix (Nix) 2.24.1"),
+                Diagnostic::fail("flake", "No flake.nix found in /tmp", "Run `melt init`"),
+            ],
+        };
+
+        assert!(report.has_failures());
+        assert_eq!(
+            report.render(),
+            "[ok  ] nix    nix (Nix) 2.24.1\n\
+             [fail] flake  No flake.nix found in /tmp\n\
+             \x20             fix: Run `melt init`\n"
+        );
+    }
+
+    #[test]
+    fn token_checks_cover_configured_hosts() {
+        let mut tokens = ForgeTokens::default();
+        tokens.insert("github.com", "ghp_test");
+        tokens.insert("GitLab.Example.com", "glpat_test");
+
+        let diagnostics = check_forge_tokens(&tokens, None);
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::pass(
+                "gitlab.example.com token",
+                "found via the config file"
+            )]
+        );
+
+        let missing = check_token(&tokens, "git.example.org", &["MELT_DOCTOR_UNSET_TOKEN"]);
+        assert_eq!(missing.outcome, Outcome::Warn);
+        assert_eq!(
+            missing.fix.as_deref(),
+            Some("Set MELT_DOCTOR_UNSET_TOKEN, or add git.example.org to [tokens] in the config file")
+        );
+    }
+
+    #[test]
+    fn cache_check_reports_unreadable_clones() {
+        let tmp = tempfile::tempdir().unwrap();
+        git2::Repository::init_bare(tmp.path().join("good")).unwrap();
+        fs::create_dir(tmp.path().join("broken")).unwrap();
+
+        let diagnostic = check_cache(tmp.path());
+
+        assert_eq!(diagnostic.outcome, Outcome::Warn);
+        assert!(diagnostic.detail.contains("broken"));
+        assert!(!tmp.path().join(".melt-doctor").exists());
+    }
+
+    #[test]
+    fn terminal_check_flags_missing_term_and_small_windows() {
+        assert_eq!(check_terminal(false, None, None).outcome, Outcome::Warn);
+        assert_eq!(
+            check_terminal(true, Some("dumb"), Some((120, 40))).outcome,
+            Outcome::Fail
+        );
+        assert_eq!(
+            check_terminal(true, Some("xterm-256color"), Some((60, 20))).outcome,
+            Outcome::Warn
+        );
+        assert_eq!(
+            check_terminal(true, Some("xterm-256color"), Some((120, 40))),
+            Diagnostic::pass("terminal", "xterm-256color (120x40)")
+        );
+    }
+}
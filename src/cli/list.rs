@@ -0,0 +1,314 @@
+//! Headless `melt list` subcommand
+//!
+//! Dumps the parsed flake inputs so other tooling can reuse melt's reading of
+//! `flake.lock` instead of parsing it again, or prints the TUI's list view
+//! once update checks finish.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::app::ListState;
+use crate::config::ServiceConfig;
+use crate::error::{AppError, AppResult};
+use crate::model::{FlakeData, FlakeInput, SortOrder, UpdateStatus};
+use crate::service::{GitService, NixService};
+use crate::ui::columns::{ListColumn, ListColumns};
+use crate::ui::render::plain_rows;
+
+/// Output format for `melt list`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ListFormat {
+    /// Aligned columns for reading in a terminal
+    #[default]
+    Table,
+    /// A JSON array of inputs
+    Json,
+    /// Comma-separated values with a header row
+    Csv,
+    /// The list view's columns, in its order, once update checks finish
+    View,
+}
+
+/// One flake input as printed by `melt list`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListRow {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub rev: Option<String>,
+    pub last_modified: Option<i64>,
+    pub url: Option<String>,
+    pub forge: Option<&'static str>,
+    /// Input path followed by a `follows` alias
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follows: Option<String>,
+}
+
+impl From<&FlakeInput> for ListRow {
+    fn from(input: &FlakeInput) -> Self {
+        let (rev, url, forge) = match input {
+            FlakeInput::Git(git) => (
+                Some(git.rev().to_string()),
+                Some(git.url().to_string()),
+                Some(git.repo().forge_name()),
+            ),
+            FlakeInput::Path(_) => (None, None, None),
+            FlakeInput::Other(other) => (other.rev.clone(), None, None),
+            FlakeInput::Follows(_) => (None, None, None),
+        };
+        let follows = match input {
+            FlakeInput::Follows(follows) => Some(follows.target()),
+            _ => None,
+        };
+
+        Self {
+            name: input.name().to_string(),
+            kind: input.type_display(),
+            rev,
+            last_modified: input.last_modified(),
+            url,
+            forge,
+            follows,
+        }
+    }
+}
+
+/// How `melt list --format view` lays out the list view
+#[derive(Debug, Clone, Default)]
+pub struct ViewOptions {
+    pub columns: ListColumns,
+    pub sort: SortOrder,
+}
+
+/// Load the flake at `path` and render its inputs in `format`.
+pub async fn run(
+    path: &Path,
+    format: ListFormat,
+    config: ServiceConfig,
+    view: ViewOptions,
+) -> AppResult<String> {
+    let cancel_token = CancellationToken::new();
+    let nix = NixService::new_with_config(cancel_token.clone(), config.clone());
+    let flake = nix.load_metadata(path).await?;
+    for warning in &flake.warnings {
+        eprintln!("warning: {}", warning);
+    }
+    if format != ListFormat::View {
+        return render(&flake, format);
+    }
+
+    let git = GitService::new_with_config(cancel_token, config);
+    let inputs: Vec<_> = flake
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FlakeInput::Git(git_input) => Some(git_input.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut statuses = HashMap::new();
+    git.check_updates_timed(&inputs, |name, status, _| {
+        statuses.insert(name, status);
+    })
+    .await?;
+
+    let mut list = ListState::new(flake).with_sort(view.sort);
+    if view.columns.as_slice().contains(&ListColumn::Version) {
+        match nix.input_versions(path).await {
+            Ok(versions) => list.versions = versions,
+            Err(e) => eprintln!("warning: failed to evaluate input versions: {}", e),
+        }
+    }
+    for (name, status) in statuses {
+        if let UpdateStatus::Error(err) = &status {
+            eprintln!("warning: {}: {}", name.as_str(), err);
+        }
+        list.record_status(name, status);
+    }
+    list.sort_inputs();
+    Ok(render_view(&list, &view.columns))
+}
+
+/// Render the inputs of `flake` in `format`. The view format shows no
+/// update statuses, since none were checked.
+pub fn render(flake: &FlakeData, format: ListFormat) -> AppResult<String> {
+    let rows: Vec<ListRow> = flake.inputs.iter().map(ListRow::from).collect();
+
+    match format {
+        ListFormat::Table => Ok(render_table(&rows)),
+        ListFormat::Json => serde_json::to_string_pretty(&rows)
+            .map(|json| json + "\n")
+            .map_err(|e| AppError::Output(e.to_string())),
+        ListFormat::Csv => Ok(render_csv(&rows)),
+        ListFormat::View => Ok(render_view(
+            &ListState::new(flake.clone()),
+            &ListColumns::default(),
+        )),
+    }
+}
+
+/// The list view's `columns` as aligned plain text
+fn render_view(list: &ListState, columns: &ListColumns) -> String {
+    let header: Vec<String> = columns
+        .as_slice()
+        .iter()
+        .map(|column| column.header().to_string())
+        .collect();
+    aligned(std::iter::once(header).chain(plain_rows(list, columns)))
+}
+
+const HEADERS: [&str; 6] = ["name", "type", "rev", "last_modified", "url", "forge"];
+
+fn fields(row: &ListRow) -> [String; 6] {
+    [
+        row.name.clone(),
+        row.kind.to_string(),
+        row.rev.clone().unwrap_or_default(),
+        row.last_modified.map(|t| t.to_string()).unwrap_or_default(),
+        row.url.clone().unwrap_or_default(),
+        row.forge.unwrap_or_default().to_string(),
+    ]
+}
+
+fn render_table(rows: &[ListRow]) -> String {
+    let header = HEADERS.map(|h| h.to_uppercase()).to_vec();
+    aligned(std::iter::once(header).chain(rows.iter().map(|row| fields(row).to_vec())))
+}
+
+/// `lines` of cells padded into aligned columns
+fn aligned(lines: impl Iterator<Item = Vec<String>>) -> String {
+    let lines: Vec<Vec<String>> = lines.collect();
+    let mut widths: Vec<usize> = Vec::new();
+    for line in &lines {
+        widths.resize(widths.len().max(line.len()), 0);
+        for (width, cell) in widths.iter_mut().zip(line) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for line in &lines {
+        let padded: Vec<String> = line
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell))
+            .collect();
+        let _ = writeln!(out, "{}", padded.join("  ").trim_end());
+    }
+    out
+}
+
+fn render_csv(rows: &[ListRow]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", HEADERS.join(","));
+    for row in rows {
+        let escaped: Vec<String> = fields(row).iter().map(|f| csv_escape(f)).collect();
+        let _ = writeln!(out, "{}", escaped.join(","));
+    }
+    out
+}
+
+/// Quote a CSV field when it contains a delimiter, quote, or newline
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{OtherInput, PathInput};
+    use std::path::PathBuf;
+
+    fn flake() -> FlakeData {
+        FlakeData {
+            path: PathBuf::from("/tmp/flake"),
+            warnings: Vec::new(),
+            input_trees: Vec::new(),
+            inputs: vec![
+                FlakeInput::Path(PathInput {
+                    name: "local".to_string(),
+                    flake: true,
+                }),
+                FlakeInput::Other(OtherInput {
+                    name: "tarball".to_string(),
+                    rev: Some("abc1234".to_string()),
+                    last_modified: 1700000000,
+                    flake: true,
+                }),
+            ],
+        }
+    }
+
+    #[test]
+    fn renders_json_with_null_for_missing_fields() {
+        let json = render(&flake(), ListFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value[0]["name"], "local");
+        assert_eq!(value[0]["type"], "path");
+        assert!(value[0]["rev"].is_null());
+        assert_eq!(value[1]["type"], "unsupported");
+        assert_eq!(value[1]["last_modified"], 1700000000);
+    }
+
+    #[test]
+    fn renders_csv_with_header() {
+        let csv = render(&flake(), ListFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], "name,type,rev,last_modified,url,forge");
+        assert_eq!(lines[1], "local,path,,,,");
+        assert_eq!(lines[2], "tarball,unsupported,abc1234,1700000000,,");
+    }
+
+    #[test]
+    fn renders_view_in_list_order_with_statuses() {
+        use crate::model::{CommitCount, InputName};
+
+        let mut list =
+            ListState::new(crate::testing::synthetic_flake(3)).with_sort(SortOrder::MostBehind);
+        list.record_status(
+            InputName::new("input-0002").unwrap(),
+            UpdateStatus::Behind(CommitCount::Exact(42)),
+        );
+        list.record_status(
+            InputName::new("input-0000").unwrap(),
+            UpdateStatus::UpToDate,
+        );
+        list.sort_inputs();
+        let columns =
+            ListColumns::new(vec![ListColumn::Name, ListColumn::Rev, ListColumn::Status]).unwrap();
+
+        let view = render_view(&list, &columns);
+        let lines: Vec<&str> = view.lines().collect();
+        assert_eq!(lines[0], "NAME        REV      STATUS");
+        assert_eq!(lines[1], "input-0002  0000000  +42");
+        assert_eq!(lines[2], "input-0000  0000000  ok");
+        assert_eq!(lines[3], "input-0001  0000000  -");
+    }
+
+    #[test]
+    fn csv_escape_quotes_special_characters() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn renders_aligned_table() {
+        let table = render(&flake(), ListFormat::Table).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert!(lines[0].starts_with("NAME     TYPE"));
+        assert!(lines[2].starts_with("tarball  unsupported  abc1234"));
+    }
+}
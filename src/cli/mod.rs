@@ -0,0 +1,10 @@
+//! Headless subcommands that run without the TUI
+
+pub mod bench;
+pub mod changelog;
+pub mod check;
+pub mod diff;
+pub mod doctor;
+pub mod list;
+pub mod sbom;
+pub mod update;
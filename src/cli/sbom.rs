@@ -0,0 +1,282 @@
+//! Headless `melt export-sbom` subcommand
+//!
+//! Converts every node of `flake.lock`, including transitive inputs, into a
+//! software bill of materials for supply-chain tooling. Only the lock file is
+//! read, so no nix or network access is needed.
+
+use std::path::Path;
+
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use crate::error::{AppError, AppResult};
+use crate::model::LockNode;
+use crate::service::{discovery_root, parse_lock_nodes, read_lock};
+
+/// SBOM document format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SbomFormat {
+    /// SPDX 2.3 JSON
+    #[default]
+    Spdx,
+    /// CycloneDX 1.5 JSON
+    Cyclonedx,
+}
+
+/// Build an SBOM for the flake at `path`.
+pub fn run(path: &Path, format: SbomFormat) -> AppResult<String> {
+    let dir = discovery_root(path);
+    let nodes = parse_lock_nodes(&read_lock(&dir)?)?;
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "flake".to_string());
+
+    let document = match format {
+        SbomFormat::Spdx => spdx(&name, &nodes, Utc::now()),
+        SbomFormat::Cyclonedx => cyclonedx(&name, &nodes, Utc::now()),
+    };
+    serde_json::to_string_pretty(&document)
+        .map(|json| json + "\n")
+        .map_err(|e| AppError::Output(e.to_string()))
+}
+
+fn spdx(name: &str, nodes: &[LockNode], created: DateTime<Utc>) -> Value {
+    let created = created.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let spdx_id = |id: &str| format!("SPDXRef-node-{}", spdx_safe(id));
+
+    let mut packages = vec![json!({
+        "name": name,
+        "SPDXID": "SPDXRef-flake",
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+    })];
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": "SPDXRef-flake",
+    })];
+
+    for node in nodes {
+        let mut package = json!({
+            "name": node.name,
+            "SPDXID": spdx_id(&node.id),
+            "downloadLocation": node.source_url.as_deref().unwrap_or("NOASSERTION"),
+            "filesAnalyzed": false,
+            "comment": format!("flake.lock node '{}' ({})", node.id, node.source_type),
+        });
+        if let Some(rev) = &node.rev {
+            package["versionInfo"] = json!(rev);
+        }
+        if let Some(date) = node.last_modified.and_then(timestamp) {
+            package["releaseDate"] = json!(date);
+        }
+        if let Some(hash) = &node.nar_hash {
+            package["annotations"] = json!([{
+                "annotationType": "OTHER",
+                "annotator": "Tool: melt",
+                "annotationDate": created,
+                "comment": format!("narHash: {}", hash),
+            }]);
+        }
+        packages.push(package);
+
+        if node.direct {
+            relationships.push(json!({
+                "spdxElementId": "SPDXRef-flake",
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": spdx_id(&node.id),
+            }));
+        }
+        for dependency in &node.depends_on {
+            relationships.push(json!({
+                "spdxElementId": spdx_id(&node.id),
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": spdx_id(dependency),
+            }));
+        }
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!(
+            "https://github.com/mholtzscher/melt/spdx/{}-{}",
+            spdx_safe(name),
+            created
+        ),
+        "creationInfo": {
+            "created": created,
+            "creators": [format!("Tool: melt-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+fn cyclonedx(name: &str, nodes: &[LockNode], created: DateTime<Utc>) -> Value {
+    let components: Vec<Value> = nodes
+        .iter()
+        .map(|node| {
+            let mut component = json!({
+                "type": "library",
+                "bom-ref": node.id,
+                "name": node.name,
+            });
+            if let Some(rev) = &node.rev {
+                component["version"] = json!(rev);
+            }
+            if let Some(url) = &node.source_url {
+                let kind = if node.rev.is_some() {
+                    "vcs"
+                } else {
+                    "distribution"
+                };
+                component["externalReferences"] = json!([{ "type": kind, "url": url }]);
+            }
+
+            let mut properties =
+                vec![json!({ "name": "nix:sourceType", "value": node.source_type })];
+            if let Some(hash) = &node.nar_hash {
+                properties.push(json!({ "name": "nix:narHash", "value": hash }));
+            }
+            if let Some(date) = node.last_modified.and_then(timestamp) {
+                properties.push(json!({ "name": "nix:lastModified", "value": date }));
+            }
+            component["properties"] = json!(properties);
+            component
+        })
+        .collect();
+
+    let mut dependencies = vec![json!({
+        "ref": "flake",
+        "dependsOn": nodes
+            .iter()
+            .filter(|node| node.direct)
+            .map(|node| node.id.as_str())
+            .collect::<Vec<_>>(),
+    })];
+    dependencies.extend(
+        nodes
+            .iter()
+            .map(|node| json!({ "ref": node.id, "dependsOn": node.depends_on })),
+    );
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": created.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "tools": [{ "name": "melt", "version": env!("CARGO_PKG_VERSION") }],
+            "component": { "type": "application", "bom-ref": "flake", "name": name },
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+/// SPDX identifiers only allow letters, digits, `.` and `-`
+fn spdx_safe(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn timestamp(secs: i64) -> Option<String> {
+    Utc.timestamp_opt(secs, 0)
+        .single()
+        .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nodes() -> Vec<LockNode> {
+        vec![
+            LockNode {
+                id: "home-manager".to_string(),
+                name: "home-manager".to_string(),
+                source_type: "github".to_string(),
+                source_url: Some("https://github.com/nix-community/home-manager".to_string()),
+                rev: Some("abc1234".to_string()),
+                nar_hash: Some("sha256-AAAA".to_string()),
+                last_modified: Some(1_700_000_000),
+                direct: true,
+                depends_on: vec!["nixpkgs_2".to_string()],
+            },
+            LockNode {
+                id: "nixpkgs_2".to_string(),
+                name: "nixpkgs".to_string(),
+                source_type: "github".to_string(),
+                source_url: Some("https://github.com/NixOS/nixpkgs".to_string()),
+                rev: Some("def5678".to_string()),
+                nar_hash: None,
+                last_modified: None,
+                direct: false,
+                depends_on: Vec::new(),
+            },
+        ]
+    }
+
+    fn created() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn spdx_lists_packages_and_dependency_graph() {
+        let doc = spdx("my-flake", &nodes(), created());
+
+        assert_eq!(doc["spdxVersion"], "SPDX-2.3");
+        assert_eq!(doc["packages"].as_array().unwrap().len(), 3);
+        assert_eq!(doc["packages"][1]["SPDXID"], "SPDXRef-node-home-manager");
+        assert_eq!(doc["packages"][1]["versionInfo"], "abc1234");
+        assert_eq!(doc["packages"][1]["releaseDate"], "2023-11-14T22:13:20Z");
+        assert_eq!(doc["packages"][2]["SPDXID"], "SPDXRef-node-nixpkgs-2");
+
+        let relationships = doc["relationships"].as_array().unwrap();
+        assert!(relationships.contains(&json!({
+            "spdxElementId": "SPDXRef-node-home-manager",
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": "SPDXRef-node-nixpkgs-2",
+        })));
+        // Only direct inputs hang off the flake itself.
+        assert_eq!(
+            relationships
+                .iter()
+                .filter(|r| r["spdxElementId"] == "SPDXRef-flake")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn cyclonedx_lists_components_and_dependencies() {
+        let doc = cyclonedx("my-flake", &nodes(), created());
+
+        assert_eq!(doc["bomFormat"], "CycloneDX");
+        assert_eq!(doc["components"][0]["bom-ref"], "home-manager");
+        assert_eq!(
+            doc["components"][0]["externalReferences"][0]["url"],
+            "https://github.com/nix-community/home-manager"
+        );
+        assert_eq!(
+            doc["dependencies"][0],
+            json!({ "ref": "flake", "dependsOn": ["home-manager"] })
+        );
+        assert_eq!(
+            doc["dependencies"][1],
+            json!({ "ref": "home-manager", "dependsOn": ["nixpkgs_2"] })
+        );
+    }
+}
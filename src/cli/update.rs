@@ -0,0 +1,188 @@
+//! Headless `melt update` subcommand
+//!
+//! Updates some or all inputs with the same Nix calls the TUI uses, then
+//! reports which locked revisions changed.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use tokio_util::sync::CancellationToken;
+
+use crate::config::ServiceConfig;
+use crate::error::{AppError, AppResult};
+use crate::model::FlakeData;
+use crate::service::NixService;
+use crate::util::time::format_relative;
+
+/// A locked revision that moved during the update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputChange {
+    pub name: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub last_modified: Option<i64>,
+}
+
+/// Outcome of a headless update
+#[derive(Debug, Clone, Default)]
+pub struct UpdateSummary {
+    pub changes: Vec<InputChange>,
+    pub unchanged: usize,
+}
+
+/// Update `inputs` (or every input when empty) in the flake at `path`.
+///
+/// Progress is written to stderr so stdout only carries the summary.
+pub async fn run(
+    path: &Path,
+    inputs: &[String],
+    config: ServiceConfig,
+) -> AppResult<UpdateSummary> {
+    let nix = NixService::new_with_config(CancellationToken::new(), config);
+
+    eprintln!("Loading flake...");
+    let before = nix.load_metadata(path).await?;
+
+    if let Some(missing) = inputs
+        .iter()
+        .find(|name| !before.inputs.iter().any(|input| input.name() == *name))
+    {
+        return Err(AppError::InputNotFound(missing.clone()));
+    }
+
+    if inputs.is_empty() {
+        eprintln!("Updating all {} inputs...", before.inputs.len());
+        nix.update_all(&before.path).await?;
+    } else {
+        eprintln!("Updating {}...", inputs.join(", "));
+        nix.update_inputs(&before.path, inputs).await?;
+    }
+
+    eprintln!("Reloading flake...");
+    let after = nix.load_metadata(&before.path).await?;
+
+    Ok(summarize(&before, &after, inputs))
+}
+
+/// Compare locked revisions before and after an update
+fn summarize(before: &FlakeData, after: &FlakeData, inputs: &[String]) -> UpdateSummary {
+    let mut summary = UpdateSummary::default();
+
+    for input in &after.inputs {
+        if !inputs.is_empty() && !inputs.iter().any(|name| name == input.name()) {
+            continue;
+        }
+
+        let previous = before.inputs.iter().find(|old| old.name() == input.name());
+        let from = previous.and_then(|old| old.short_rev()).map(str::to_string);
+        let to = input.short_rev().map(str::to_string);
+        let moved = match previous {
+            Some(old) => from != to || old.last_modified() != input.last_modified(),
+            None => true,
+        };
+
+        if moved {
+            summary.changes.push(InputChange {
+                name: input.name().to_string(),
+                from,
+                to,
+                last_modified: input.last_modified(),
+            });
+        } else {
+            summary.unchanged += 1;
+        }
+    }
+
+    summary
+}
+
+impl UpdateSummary {
+    /// Render the summary as plain text
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        if self.changes.is_empty() {
+            let _ = writeln!(out, "All inputs already up to date");
+            return out;
+        }
+
+        let _ = writeln!(
+            out,
+            "Updated {} input(s), {} unchanged",
+            self.changes.len(),
+            self.unchanged
+        );
+        for change in &self.changes {
+            let _ = writeln!(
+                out,
+                "  {:<30} {} -> {}  ({})",
+                change.name,
+                change.from.as_deref().unwrap_or("-"),
+                change.to.as_deref().unwrap_or("-"),
+                change
+                    .last_modified
+                    .map(format_relative)
+                    .unwrap_or_else(|| "-".to_string())
+            );
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{FlakeInput, OtherInput, PathInput};
+    use std::path::PathBuf;
+
+    fn flake(inputs: &[(&str, Option<&str>)]) -> FlakeData {
+        FlakeData {
+            path: PathBuf::from("/tmp/flake"),
+            warnings: Vec::new(),
+            input_trees: Vec::new(),
+            inputs: inputs
+                .iter()
+                .map(|(name, rev)| match rev {
+                    Some(rev) => FlakeInput::Other(OtherInput {
+                        name: name.to_string(),
+                        rev: Some(rev.to_string()),
+                        last_modified: 0,
+                        flake: true,
+                    }),
+                    None => FlakeInput::Path(PathInput {
+                        name: name.to_string(),
+                        flake: true,
+                    }),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn summarize_reports_moved_revisions() {
+        let before = flake(&[("a", Some("1111111")), ("b", Some("2222222")), ("c", None)]);
+        let after = flake(&[("a", Some("3333333")), ("b", Some("2222222")), ("c", None)]);
+
+        let summary = summarize(&before, &after, &[]);
+
+        assert_eq!(summary.changes.len(), 1);
+        assert_eq!(summary.changes[0].name, "a");
+        assert_eq!(summary.changes[0].from.as_deref(), Some("1111111"));
+        assert_eq!(summary.changes[0].to.as_deref(), Some("3333333"));
+        assert_eq!(summary.unchanged, 2);
+        assert!(summary.render().contains("Updated 1 input(s), 2 unchanged"));
+    }
+
+    #[test]
+    fn summarize_only_counts_requested_inputs() {
+        let before = flake(&[("a", Some("1111111")), ("b", Some("2222222"))]);
+        let after = flake(&[("a", Some("1111111")), ("b", Some("2222222"))]);
+
+        let summary = summarize(&before, &after, &["a".to_string()]);
+
+        assert!(summary.changes.is_empty());
+        assert_eq!(summary.unchanged, 1);
+        assert_eq!(summary.render(), "All inputs already up to date\n");
+    }
+}
@@ -1,5 +1,16 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use serde::Deserialize;
+
+use crate::error::{AppError, AppResult};
+use crate::model::{glob_match, ChangelogPath, ChangelogPaths, InputGlobs, SortOrder};
+use crate::ui::columns::{ListColumn, ListColumns};
+use crate::ui::theme::{self, Theme};
+use crate::util::format::{ClockFormat, FormatPrefs, WeekStart};
+use crate::util::time::parse_interval;
+
 #[derive(Debug, Clone)]
 pub struct Timeouts {
     pub nix_command: Duration,
@@ -19,17 +30,1030 @@ impl Default for Timeouts {
     }
 }
 
+/// Limits on how many commits are fetched and shown.
+#[derive(Debug, Clone)]
+pub struct CommitLimits {
+    /// Maximum number of new commits fetched for update checks and commit history
+    pub max_commits: usize,
+    /// Number of commits shown starting at the locked revision
+    pub history_depth: usize,
+    /// Commits requested per page from forge commit APIs
+    pub per_page: usize,
+    /// Pages fetched from the GitHub commits API before giving up on
+    /// reaching the locked revision
+    pub max_api_pages: usize,
+    /// Overrides for particular inputs; the first matching rule wins
+    pub per_input: Vec<InputLimits>,
+}
+
+/// Commit limits for inputs whose name matches `input`, a case-insensitive
+/// glob. Unset fields keep the global limit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputLimits {
+    pub input: String,
+    pub max_commits: Option<usize>,
+    pub history_depth: Option<usize>,
+}
+
+impl CommitLimits {
+    /// Largest page size accepted by forge commit APIs
+    pub const MAX_API_PAGE_SIZE: usize = 100;
+
+    /// Page size to request from forge APIs
+    pub fn api_page_size(&self) -> usize {
+        self.per_page
+            .min(self.max_commits)
+            .clamp(1, Self::MAX_API_PAGE_SIZE)
+    }
+
+    /// Limits for the input named `name`, with its override applied
+    pub fn for_input(&self, name: &str) -> CommitLimits {
+        let name = name.to_lowercase();
+        let mut limits = self.clone();
+        if let Some(rule) = self
+            .per_input
+            .iter()
+            .find(|rule| glob_match(&rule.input.to_lowercase(), &name))
+        {
+            limits.max_commits = rule.max_commits.unwrap_or(limits.max_commits).max(1);
+            limits.history_depth = rule.history_depth.unwrap_or(limits.history_depth);
+        }
+        limits
+    }
+
+    /// Pages to request from forge APIs, never more than needed for `max_commits`
+    pub fn api_pages(&self) -> usize {
+        self.max_api_pages
+            .min(self.max_commits.div_ceil(self.api_page_size()))
+            .max(1)
+    }
+}
+
+impl Default for CommitLimits {
+    fn default() -> Self {
+        Self {
+            max_commits: 500,
+            history_depth: 50,
+            per_page: Self::MAX_API_PAGE_SIZE,
+            max_api_pages: 5,
+            per_input: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ServiceConfig {
+    /// nix executable, looked up on `PATH` unless it contains a slash
+    pub nix_binary: PathBuf,
+    /// Extra arguments passed to every nix command, before the subcommand
+    pub nix_args: Vec<String>,
     pub timeouts: Timeouts,
     pub git_concurrency: usize,
+    pub commit_limits: CommitLimits,
+    /// Globs restricting which inputs are loaded
+    pub input_globs: InputGlobs,
+    /// Paths each input's changelog is narrowed to
+    pub changelog_paths: ChangelogPaths,
+    /// Skip forge APIs and git fetches, reading only local data
+    pub offline: bool,
+    /// Read inputs from `flake.lock` instead of running `nix flake metadata`
+    pub read_lock_file: bool,
+    /// Forge API tokens keyed by host; environment variables are used for
+    /// hosts without one
+    pub forge_tokens: ForgeTokens,
+    /// Proxy URL for forge APIs and git fetches. Without one, the usual
+    /// `HTTPS_PROXY` / `HTTP_PROXY` / `ALL_PROXY` variables apply.
+    pub proxy: Option<String>,
+    /// Count clone cache hits and bytes fetched for the stats screen and log
+    pub metrics: bool,
+    /// Forge software run on self-hosted hosts that URLs don't reveal
+    pub forge_hosts: ForgeHosts,
+    /// `User-Agent` for forge API requests, instead of `melt/<version>`
+    pub user_agent: Option<String>,
+    /// Extra headers sent with forge API requests, keyed by host
+    pub headers: HostHeaders,
+    /// Commands that update particular inputs instead of nix
+    pub update_commands: UpdateCommands,
+    /// Flake outputs offered to `nix build` after an update (e.g.
+    /// `.#nixosConfigurations.host.config.system.build.toplevel`)
+    pub build_outputs: Vec<String>,
+    /// Installable whose closure is compared before and after nixpkgs
+    /// updates (e.g. `.#nixosConfigurations.host.config.system.build.toplevel`)
+    pub diff_closure: Option<String>,
+    /// Commit `flake.lock` after each successful update or lock
+    pub auto_commit: bool,
+    /// Message of those commits, with `{inputs}` and `{changes}`
+    /// placeholders; a default one is used when unset
+    pub commit_message: Option<String>,
+    /// Flake directories whose `.melt.toml` may run commands and commit
+    pub trusted_projects: TrustedProjects,
 }
 
 impl Default for ServiceConfig {
     fn default() -> Self {
         Self {
+            nix_binary: PathBuf::from("nix"),
+            nix_args: Vec::new(),
             timeouts: Timeouts::default(),
             git_concurrency: 10,
+            commit_limits: CommitLimits::default(),
+            input_globs: InputGlobs::default(),
+            changelog_paths: ChangelogPaths::default(),
+            offline: false,
+            read_lock_file: false,
+            forge_tokens: ForgeTokens::default(),
+            proxy: None,
+            metrics: false,
+            forge_hosts: ForgeHosts::default(),
+            user_agent: None,
+            headers: HostHeaders::default(),
+            update_commands: UpdateCommands::default(),
+            build_outputs: Vec::new(),
+            diff_closure: None,
+            auto_commit: false,
+            commit_message: None,
+            trusted_projects: TrustedProjects::default(),
+        }
+    }
+}
+
+/// Forge API tokens keyed by host (e.g. `github.com`, `gitlab.example.com`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForgeTokens {
+    by_host: BTreeMap<String, String>,
+}
+
+impl ForgeTokens {
+    /// Set the token for `host`. Empty tokens are ignored.
+    pub fn insert(&mut self, host: &str, token: impl Into<String>) {
+        let token = token.into();
+        if !token.is_empty() {
+            self.by_host.insert(host.to_ascii_lowercase(), token);
+        }
+    }
+
+    /// Token configured for `host`
+    pub fn get(&self, host: &str) -> Option<&str> {
+        self.by_host
+            .get(&host.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Hosts with a configured token
+    pub fn hosts(&self) -> impl Iterator<Item = &str> {
+        self.by_host.keys().map(String::as_str)
+    }
+
+    /// Token for `host`, falling back to the first of `env_vars` that is set
+    pub fn resolve(&self, host: &str, env_vars: &[&str]) -> Option<String> {
+        self.get(host).map(str::to_string).or_else(|| {
+            env_vars
+                .iter()
+                .find_map(|var| std::env::var(var).ok().filter(|token| !token.is_empty()))
+        })
+    }
+}
+
+/// Extra HTTP headers keyed by host, for forges that filter requests
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostHeaders {
+    by_host: BTreeMap<String, Vec<(String, String)>>,
+}
+
+impl HostHeaders {
+    /// Send `name: value` with every request to `host`
+    pub fn insert(&mut self, host: &str, name: impl Into<String>, value: impl Into<String>) {
+        self.by_host
+            .entry(host.to_ascii_lowercase())
+            .or_default()
+            .push((name.into(), value.into()));
+    }
+
+    /// Headers configured for `host`
+    pub fn get(&self, host: &str) -> &[(String, String)] {
+        self.by_host
+            .get(&host.to_ascii_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Forge software of a self-hosted host, chosen in `forge_hosts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    /// GitHub Enterprise Server
+    GitHub,
+    GitLab,
+    #[serde(alias = "forgejo")]
+    Gitea,
+    SourceHut,
+}
+
+/// Forge kinds keyed by host, for `git+https` inputs on self-hosted
+/// forges whose host name doesn't say which forge they run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForgeHosts {
+    by_host: BTreeMap<String, ForgeKind>,
+}
+
+impl ForgeHosts {
+    pub fn insert(&mut self, host: &str, kind: ForgeKind) {
+        self.by_host.insert(host.to_ascii_lowercase(), kind);
+    }
+
+    /// Forge configured for `host`
+    pub fn get(&self, host: &str) -> Option<ForgeKind> {
+        self.by_host.get(&host.to_ascii_lowercase()).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_host.is_empty()
+    }
+
+    /// Add every host of `other`, replacing existing entries
+    pub fn extend(&mut self, other: ForgeHosts) {
+        self.by_host.extend(other.by_host);
+    }
+}
+
+/// Commands run instead of `nix flake update` for particular inputs, as
+/// `sh -c` templates keyed by a case-insensitive input name glob
+///
+/// `{input}` is replaced with the input name and `{flake}` with the flake
+/// directory, both shell-quoted. Commands run in the flake directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateCommands {
+    rules: Vec<(String, String)>,
+}
+
+impl UpdateCommands {
+    pub fn insert(&mut self, input: &str, command: String) {
+        self.rules.push((input.to_string(), command));
+    }
+
+    /// Command template for the input named `name`; the first matching rule
+    /// wins
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.rules
+            .iter()
+            .find(|(input, _)| glob_match(&input.to_lowercase(), &name))
+            .map(|(_, command)| command.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Put the rules of `other` ahead of the existing ones
+    pub fn prepend(&mut self, other: UpdateCommands) {
+        let rules = std::mem::replace(&mut self.rules, other.rules);
+        self.rules.extend(rules);
+    }
+
+    /// `command` with its placeholders filled in for `input` of the flake
+    /// in `flake_dir`
+    pub fn expand(command: &str, input: &str, flake_dir: &Path) -> String {
+        command
+            .replace("{input}", &shell_quote(input))
+            .replace("{flake}", &shell_quote(&flake_dir.to_string_lossy()))
+    }
+}
+
+/// `value` quoted for `sh`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Settings read from the config file, before command-line overrides
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub service: ServiceConfig,
+    pub format: FormatPrefs,
+    /// Columns of the list view, in order
+    pub columns: ListColumns,
+    /// Suggest setting a GitHub token when many inputs would hit the
+    /// unauthenticated rate limit
+    pub token_hint: bool,
+    /// Reload the flake on this interval while the list view is idle
+    pub refresh_interval: Option<Duration>,
+    /// Order of the list view
+    pub sort: SortOrder,
+    /// Draw for screen readers, announcing state changes as plain lines
+    pub accessible: bool,
+    /// Colors the UI is drawn with
+    pub theme: Theme,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            service: ServiceConfig::default(),
+            format: FormatPrefs::default(),
+            columns: ListColumns::default(),
+            token_hint: true,
+            refresh_interval: None,
+            sort: SortOrder::default(),
+            accessible: false,
+            theme: Theme::default(),
+        }
+    }
+}
+
+/// Load `config.toml` from the melt config directory.
+///
+/// A missing file yields the defaults; an unreadable or invalid one is an
+/// error, so typos don't silently fall back to defaults.
+pub fn load() -> AppResult<Config> {
+    load_from(&crate::paths::config_file())
+}
+
+/// Load a config file from `path`, or the defaults if it does not exist
+pub fn load_from(path: &Path) -> AppResult<Config> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(AppError::Config(path.to_path_buf(), e.to_string())),
+    };
+    parse(&contents).map_err(|message| AppError::Config(path.to_path_buf(), message))
+}
+
+/// Layout of `config.toml`. Every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    max_commits: Option<usize>,
+    history_depth: Option<usize>,
+    per_page: Option<usize>,
+    max_api_pages: Option<usize>,
+    git_concurrency: Option<usize>,
+    offline: Option<bool>,
+    read_lock_file: Option<bool>,
+    proxy: Option<String>,
+    metrics: Option<bool>,
+    user_agent: Option<String>,
+    only: Vec<String>,
+    exclude: Vec<String>,
+    /// Input name globs that are never shown, checked, or updated
+    ignore: Vec<String>,
+    /// Input name globs that are shown but never updated
+    freeze: Vec<String>,
+    forge_hosts: BTreeMap<String, ForgeKind>,
+    /// Input name glob to the paths its changelog is narrowed to
+    changelog_paths: BTreeMap<String, Vec<String>>,
+    theme: Option<String>,
+    columns: Option<Vec<ListColumn>>,
+    sort: Option<SortOrder>,
+    token_hint: Option<bool>,
+    accessible: Option<bool>,
+    /// Interval such as `15m`
+    refresh_interval: Option<String>,
+    clock: Option<ClockFormat>,
+    week_start: Option<WeekStart>,
+    thousands_separator: Option<char>,
+    timeouts: TimeoutsFile,
+    /// Host to API token
+    tokens: BTreeMap<String, String>,
+    /// Host to header name to value
+    headers: BTreeMap<String, BTreeMap<String, String>>,
+    /// Input name glob to commit limits for matching inputs
+    input_limits: BTreeMap<String, InputLimitsFile>,
+    /// Input name glob to the command that updates matching inputs
+    update_commands: BTreeMap<String, String>,
+    build_outputs: Vec<String>,
+    diff_closure: Option<String>,
+    auto_commit: Option<bool>,
+    commit_message: Option<String>,
+    /// Flake directories whose `.melt.toml` is trusted; `~` is the home
+    /// directory
+    trusted_projects: Vec<PathBuf>,
+    nix_binary: Option<PathBuf>,
+    nix_args: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct InputLimitsFile {
+    max_commits: Option<usize>,
+    history_depth: Option<usize>,
+}
+
+/// Timeouts in seconds
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TimeoutsFile {
+    nix_command: Option<u64>,
+    git_update_check: Option<u64>,
+    git_changelog: Option<u64>,
+    http_request: Option<u64>,
+}
+
+fn parse(contents: &str) -> Result<Config, String> {
+    let file: ConfigFile = toml::from_str(contents).map_err(|e| e.message().to_string())?;
+
+    let theme = match &file.theme {
+        Some(name) => Theme::named(name).ok_or_else(|| {
+            format!(
+                "unknown theme '{}' (available: {})",
+                name,
+                theme::names().join(", ")
+            )
+        })?,
+        None => Theme::default(),
+    };
+
+    let columns = match file.columns {
+        Some(columns) => ListColumns::new(columns)?,
+        None => ListColumns::default(),
+    };
+
+    let refresh_interval = file
+        .refresh_interval
+        .as_deref()
+        .map(parse_interval)
+        .transpose()
+        .map_err(|e| format!("invalid refresh_interval: {}", e))?;
+
+    let mut service = ServiceConfig::default();
+    let limits = &mut service.commit_limits;
+    limits.max_commits = file.max_commits.unwrap_or(limits.max_commits).max(1);
+    limits.history_depth = file.history_depth.unwrap_or(limits.history_depth);
+    limits.per_page = file.per_page.unwrap_or(limits.per_page);
+    limits.max_api_pages = file.max_api_pages.unwrap_or(limits.max_api_pages);
+    limits.per_input = file
+        .input_limits
+        .into_iter()
+        .map(|(input, rule)| InputLimits {
+            input,
+            max_commits: rule.max_commits,
+            history_depth: rule.history_depth,
+        })
+        .collect();
+    service.git_concurrency = file
+        .git_concurrency
+        .unwrap_or(service.git_concurrency)
+        .max(1);
+    service.offline = file.offline.unwrap_or(false);
+    service.read_lock_file = file.read_lock_file.unwrap_or(false);
+    if let Some(proxy) = &file.proxy {
+        reqwest::Proxy::all(proxy.as_str())
+            .map_err(|e| format!("invalid proxy '{}': {}", proxy, e))?;
+    }
+    service.proxy = file.proxy;
+    service.metrics = file.metrics.unwrap_or(false);
+    service.input_globs = InputGlobs {
+        only: file.only,
+        exclude: file.exclude,
+        ignore: file.ignore,
+        freeze: file.freeze,
+    };
+    service.changelog_paths = ChangelogPaths {
+        rules: file
+            .changelog_paths
+            .into_iter()
+            .flat_map(|(input, paths)| {
+                paths.into_iter().map(move |path| ChangelogPath {
+                    input: input.clone(),
+                    path,
+                })
+            })
+            .collect(),
+    };
+    for (host, token) in file.tokens {
+        service.forge_tokens.insert(&host, token);
+    }
+    for (host, kind) in file.forge_hosts {
+        service.forge_hosts.insert(&host, kind);
+    }
+    if let Some(user_agent) = &file.user_agent {
+        reqwest::header::HeaderValue::from_str(user_agent)
+            .map_err(|_| format!("invalid user_agent '{}'", user_agent))?;
+    }
+    service.user_agent = file.user_agent;
+    for (host, headers) in file.headers {
+        for (name, value) in headers {
+            reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("invalid header name '{}' for {}", name, host))?;
+            reqwest::header::HeaderValue::from_str(&value)
+                .map_err(|_| format!("invalid value for header '{}' for {}", name, host))?;
+            service.headers.insert(&host, name, value);
         }
     }
+
+    for (input, command) in file.update_commands {
+        service.update_commands.insert(&input, command);
+    }
+    service.build_outputs = file.build_outputs;
+    service.diff_closure = file.diff_closure;
+    service.auto_commit = file.auto_commit.unwrap_or(false);
+    service.commit_message = file.commit_message;
+    for dir in file.trusted_projects {
+        service.trusted_projects.insert(&dir);
+    }
+    if let Some(nix_binary) = file.nix_binary {
+        service.nix_binary = nix_binary;
+    }
+    service.nix_args = file.nix_args;
+
+    let timeouts = &mut service.timeouts;
+    for (secs, timeout) in [
+        (file.timeouts.nix_command, &mut timeouts.nix_command),
+        (
+            file.timeouts.git_update_check,
+            &mut timeouts.git_update_check,
+        ),
+        (file.timeouts.git_changelog, &mut timeouts.git_changelog),
+        (file.timeouts.http_request, &mut timeouts.http_request),
+    ] {
+        if let Some(secs) = secs {
+            *timeout = Duration::from_secs(secs.max(1));
+        }
+    }
+
+    Ok(Config {
+        service,
+        format: FormatPrefs {
+            clock: file.clock.unwrap_or_default(),
+            week_start: file.week_start.unwrap_or_default(),
+            thousands_separator: file.thousands_separator,
+        },
+        columns,
+        token_hint: file.token_hint.unwrap_or(true),
+        refresh_interval,
+        sort: file.sort.unwrap_or_default(),
+        accessible: file.accessible.unwrap_or(false),
+        theme,
+    })
+}
+
+/// Set `theme` in the config file at `path`, leaving the rest of the file
+/// as it is. A missing file is created.
+pub fn save_theme(path: &Path, theme: &Theme) -> AppResult<()> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(AppError::Config(path.to_path_buf(), e.to_string())),
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, with_theme(&contents, theme.name))?;
+    Ok(())
+}
+
+/// `contents` with its top-level `theme` key set to `name`, added as the
+/// first line if there is none
+fn with_theme(contents: &str, name: &str) -> String {
+    let setting = format!("theme = \"{}\"", name);
+    let mut replaced = false;
+    let mut in_table = false;
+    let mut lines: Vec<String> = contents
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            in_table |= trimmed.starts_with('[');
+            let is_theme = !in_table
+                && !replaced
+                && trimmed
+                    .strip_prefix("theme")
+                    .is_some_and(|rest| rest.trim_start().starts_with('='));
+            if is_theme {
+                replaced = true;
+                setting.clone()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if !replaced {
+        lines.insert(0, setting);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Name of the per-project config file, next to `flake.nix`
+pub const PROJECT_FILE: &str = ".melt.toml";
+
+/// Settings from a flake's `.melt.toml`, overriding the config file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectConfig {
+    /// Replaces `ignore` from the config file, when set
+    pub ignore: Option<Vec<String>>,
+    /// Replaces `freeze` from the config file, when set
+    pub freeze: Option<Vec<String>>,
+    /// Added to `forge_hosts` from the config file
+    pub forge_hosts: ForgeHosts,
+    /// Checked before `update_commands` from the config file
+    pub update_commands: UpdateCommands,
+    /// Replaces `build_outputs` from the config file, when set
+    pub build_outputs: Option<Vec<String>>,
+    /// Replaces `diff_closure` from the config file, when set
+    pub diff_closure: Option<String>,
+    /// Replaces `auto_commit` from the config file, when set
+    pub auto_commit: Option<bool>,
+    /// Replaces `commit_message` from the config file, when set
+    pub commit_message: Option<String>,
+}
+
+impl ProjectConfig {
+    /// Whether this sets anything that runs commands or commits, which is
+    /// only honoured for trusted projects
+    pub fn needs_trust(&self) -> bool {
+        !self.update_commands.is_empty()
+            || self.build_outputs.is_some()
+            || self.diff_closure.is_some()
+            || self.auto_commit.is_some()
+            || self.commit_message.is_some()
+    }
+}
+
+/// Flake directories the user trusts to run what their `.melt.toml` asks for.
+/// A cloned repository is not trusted until its directory is listed in
+/// `trusted_projects`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProjects {
+    dirs: Vec<PathBuf>,
+}
+
+impl TrustedProjects {
+    /// Trust `dir`, with a leading `~` standing for the home directory
+    pub fn insert(&mut self, dir: &Path) {
+        let dir = match (dir.strip_prefix("~"), dirs::home_dir()) {
+            (Ok(rest), Some(home)) => home.join(rest),
+            _ => dir.to_path_buf(),
+        };
+        self.dirs.push(canonical(&dir));
+    }
+
+    /// Whether the flake in `flake_dir` is trusted
+    pub fn contains(&self, flake_dir: &Path) -> bool {
+        let flake_dir = canonical(flake_dir);
+        self.dirs.contains(&flake_dir)
+    }
+}
+
+/// `path` with symlinks and `..` resolved, or as given if it doesn't exist
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Layout of `.melt.toml`. Only settings that belong to the project are
+/// allowed; personal ones stay in `config.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProjectFile {
+    ignore: Option<Vec<String>>,
+    freeze: Option<Vec<String>>,
+    forge_hosts: BTreeMap<String, ForgeKind>,
+    update_commands: BTreeMap<String, String>,
+    build_outputs: Option<Vec<String>>,
+    diff_closure: Option<String>,
+    auto_commit: Option<bool>,
+    commit_message: Option<String>,
+}
+
+/// Load the `.melt.toml` in `flake_dir`, or no overrides if there is none
+pub fn load_project(flake_dir: &Path) -> AppResult<ProjectConfig> {
+    let path = flake_dir.join(PROJECT_FILE);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(ProjectConfig::default()),
+        Err(e) => return Err(AppError::Config(path, e.to_string())),
+    };
+    parse_project(&contents).map_err(|message| AppError::Config(path, message))
+}
+
+fn parse_project(contents: &str) -> Result<ProjectConfig, String> {
+    let file: ProjectFile = toml::from_str(contents).map_err(|e| e.message().to_string())?;
+    let mut forge_hosts = ForgeHosts::default();
+    for (host, kind) in file.forge_hosts {
+        forge_hosts.insert(&host, kind);
+    }
+    let mut update_commands = UpdateCommands::default();
+    for (input, command) in file.update_commands {
+        update_commands.insert(&input, command);
+    }
+    Ok(ProjectConfig {
+        ignore: file.ignore,
+        freeze: file.freeze,
+        forge_hosts,
+        update_commands,
+        build_outputs: file.build_outputs,
+        diff_closure: file.diff_closure,
+        auto_commit: file.auto_commit,
+        commit_message: file.commit_message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_section() {
+        let config = parse(
+            r#"
+            max_commits = 200
+            git_concurrency = 4
+            only = ["nix*"]
+            ignore = ["private-thing", "vendored-*"]
+            theme = "catppuccin-latte"
+            columns = ["name", "rev", "status", "url"]
+            sort = "most-behind"
+            token_hint = false
+            accessible = true
+            refresh_interval = "15m"
+            clock = "12h"
+            week_start = "sunday"
+            auto_commit = true
+            nix_binary = "/nix/var/nix/profiles/default/bin/nix"
+            nix_args = ["--accept-flake-config", "--option", "substituters", "https://cache.nixos.org"]
+
+            [timeouts]
+            http_request = 5
+
+            [tokens]
+            "github.com" = "ghp_example"
+            "GitLab.Example.com" = "glpat_example"
+
+            [changelog_paths]
+            nixpkgs = ["pkgs/by-name/ri/ripgrep", "nixos/modules/programs/git.nix"]
+
+            [input_limits.nixpkgs]
+            max_commits = 2000
+            history_depth = 20
+            "#,
+        )
+        .unwrap();
+
+        let service = &config.service;
+        assert_eq!(service.commit_limits.max_commits, 200);
+        assert_eq!(service.git_concurrency, 4);
+        assert_eq!(service.input_globs.only, vec!["nix*".to_string()]);
+        assert_eq!(
+            service.input_globs.ignore,
+            vec!["private-thing".to_string(), "vendored-*".to_string()]
+        );
+        assert_eq!(service.timeouts.http_request, Duration::from_secs(5));
+        assert_eq!(
+            service.timeouts.nix_command,
+            Timeouts::default().nix_command
+        );
+        assert_eq!(service.forge_tokens.get("github.com"), Some("ghp_example"));
+        assert_eq!(
+            service.forge_tokens.get("gitlab.example.com"),
+            Some("glpat_example")
+        );
+        assert_eq!(service.changelog_paths.for_input("nixpkgs").len(), 2);
+        let nixpkgs = service.commit_limits.for_input("NixPkgs");
+        assert_eq!((nixpkgs.max_commits, nixpkgs.history_depth), (2000, 20));
+        let other = service.commit_limits.for_input("home-manager");
+        assert_eq!((other.max_commits, other.history_depth), (200, 50));
+        assert_eq!(config.format.clock, ClockFormat::TwelveHour);
+        assert_eq!(config.format.week_start, WeekStart::Sunday);
+        assert!(!config.token_hint);
+        assert!(config.accessible);
+        assert_eq!(config.theme.name, "catppuccin-latte");
+        assert!(service.auto_commit);
+        assert_eq!(
+            service.nix_binary,
+            PathBuf::from("/nix/var/nix/profiles/default/bin/nix")
+        );
+        assert_eq!(service.nix_args.len(), 4);
+        assert_eq!(service.commit_message, None);
+        assert_eq!(config.refresh_interval, Some(Duration::from_secs(15 * 60)));
+        assert_eq!(config.sort, SortOrder::MostBehind);
+        assert_eq!(
+            config.columns.as_slice(),
+            [
+                ListColumn::Name,
+                ListColumn::Rev,
+                ListColumn::Status,
+                ListColumn::Url
+            ]
+        );
+    }
+
+    #[test]
+    fn api_pages_stop_at_max_commits() {
+        let mut limits = CommitLimits::default();
+        assert_eq!((limits.api_page_size(), limits.api_pages()), (100, 5));
+
+        limits.max_commits = 150;
+        assert_eq!((limits.api_page_size(), limits.api_pages()), (100, 2));
+
+        limits.per_page = 30;
+        limits.max_api_pages = 3;
+        assert_eq!((limits.api_page_size(), limits.api_pages()), (30, 3));
+
+        limits.max_commits = 10;
+        assert_eq!((limits.api_page_size(), limits.api_pages()), (10, 1));
+    }
+
+    #[test]
+    fn rejects_unknown_keys_themes_and_bad_proxies() {
+        assert!(parse("max_comits = 5").unwrap_err().contains("max_comits"));
+        assert!(parse("refresh_interval = \"soon\"")
+            .unwrap_err()
+            .contains("invalid refresh_interval"));
+        assert!(parse("theme = \"solarized\"")
+            .unwrap_err()
+            .contains("unknown theme"));
+        assert!(parse("proxy = \"http://[proxy\"")
+            .unwrap_err()
+            .contains("invalid proxy"));
+        let config = parse("proxy = \"http://proxy.corp:3128\"").unwrap();
+        assert_eq!(
+            config.service.proxy.as_deref(),
+            Some("http://proxy.corp:3128")
+        );
+        assert!(parse("metrics = true").unwrap().service.metrics);
+    }
+
+    #[test]
+    fn parses_user_agent_and_headers() {
+        let config = parse(
+            r#"
+            user_agent = "melt (ops@example.com)"
+
+            [headers."Git.Corp.example"]
+            "X-Client" = "melt"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.service.user_agent.as_deref(),
+            Some("melt (ops@example.com)")
+        );
+        assert_eq!(
+            config.service.headers.get("git.corp.example"),
+            [("X-Client".to_string(), "melt".to_string())]
+        );
+        assert!(config.service.headers.get("github.com").is_empty());
+
+        assert!(parse("[headers.\"h\"]\n\"bad name\" = \"x\"")
+            .unwrap_err()
+            .contains("invalid header name"));
+        assert!(parse("user_agent = \"line\\nbreak\"")
+            .unwrap_err()
+            .contains("invalid user_agent"));
+    }
+
+    #[test]
+    fn parses_project_overrides() {
+        let project = parse_project(
+            r#"
+            freeze = ["nixpkgs"]
+            build_outputs = [".#nixosConfigurations.host.config.system.build.toplevel"]
+            diff_closure = ".#packages.x86_64-linux.default"
+            commit_message = "chore(deps): bump {inputs}"
+
+            [forge_hosts]
+            "Git.Corp.example" = "gitlab"
+            "code.example.org" = "forgejo"
+            "ghe.example.com" = "github"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(project.ignore, None);
+        assert_eq!(project.freeze, Some(vec!["nixpkgs".to_string()]));
+        assert_eq!(
+            project.build_outputs,
+            Some(vec![
+                ".#nixosConfigurations.host.config.system.build.toplevel".to_string()
+            ])
+        );
+        assert_eq!(
+            project.diff_closure.as_deref(),
+            Some(".#packages.x86_64-linux.default")
+        );
+        assert_eq!(project.auto_commit, None);
+        assert_eq!(
+            project.commit_message.as_deref(),
+            Some("chore(deps): bump {inputs}")
+        );
+        assert_eq!(
+            project.forge_hosts.get("git.corp.example"),
+            Some(ForgeKind::GitLab)
+        );
+        assert_eq!(
+            project.forge_hosts.get("code.example.org"),
+            Some(ForgeKind::Gitea)
+        );
+        assert_eq!(
+            project.forge_hosts.get("ghe.example.com"),
+            Some(ForgeKind::GitHub)
+        );
+
+        // Personal settings stay in config.toml.
+        assert!(parse_project("theme = \"nord\"")
+            .unwrap_err()
+            .contains("unknown field"));
+    }
+
+    #[test]
+    fn project_update_commands_come_first() {
+        let mut config = parse(
+            r#"
+            [update_commands]
+            "my-*" = "./scripts/update.sh {input}"
+            "#,
+        )
+        .unwrap();
+        let project = parse_project(
+            r#"
+            [update_commands]
+            "My-Tool" = "nix flake lock --override-input {input} \"$(./latest-url)\""
+            "#,
+        )
+        .unwrap();
+        config
+            .service
+            .update_commands
+            .prepend(project.update_commands);
+
+        let commands = &config.service.update_commands;
+        assert_eq!(commands.get("my-lib"), Some("./scripts/update.sh {input}"));
+        assert!(commands
+            .get("my-tool")
+            .unwrap()
+            .starts_with("nix flake lock"));
+        assert_eq!(commands.get("nixpkgs"), None);
+        assert_eq!(
+            UpdateCommands::expand("run {input} {flake}", "it's", Path::new("/a b")),
+            "run 'it'\\''s' '/a b'"
+        );
+    }
+
+    #[test]
+    fn only_listed_projects_are_trusted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let trusted = tmp.path().join("trusted");
+        let cloned = tmp.path().join("cloned");
+        std::fs::create_dir_all(&trusted).unwrap();
+        std::fs::create_dir_all(&cloned).unwrap();
+
+        let config = parse(&format!(
+            "trusted_projects = [{:?}]",
+            tmp.path().join("cloned/../trusted")
+        ))
+        .unwrap();
+        let projects = &config.service.trusted_projects;
+        assert!(projects.contains(&trusted));
+        assert!(!projects.contains(&cloned));
+
+        assert!(!ProjectConfig::default().needs_trust());
+        assert!(!parse_project("freeze = [\"nixpkgs\"]")
+            .unwrap()
+            .needs_trust());
+        assert!(parse_project("auto_commit = false").unwrap().needs_trust());
+        assert!(parse_project("[update_commands]\n\"x\" = \"true\"")
+            .unwrap()
+            .needs_trust());
+    }
+
+    #[test]
+    fn missing_project_file_has_no_overrides() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(load_project(tmp.path()).unwrap(), ProjectConfig::default());
+
+        std::fs::write(tmp.path().join(PROJECT_FILE), "freeze = 1").unwrap();
+        assert!(load_project(tmp.path()).is_err());
+    }
+
+    #[test]
+    fn missing_file_uses_defaults() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = load_from(&tmp.path().join("config.toml")).unwrap();
+        assert_eq!(config.format, FormatPrefs::default());
+        assert_eq!(config.columns, ListColumns::default());
+        assert!(config.token_hint);
+        assert_eq!(config.refresh_interval, None);
+        assert_eq!(config.sort, SortOrder::Alphabetical);
+        assert!(!config.service.offline);
+        assert!(!config.service.read_lock_file);
+        assert!(!config.service.metrics);
+    }
+
+    #[test]
+    fn saving_a_theme_keeps_the_rest_of_the_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("melt/config.toml");
+        let latte = Theme::named("catppuccin-latte").unwrap();
+
+        save_theme(&path, &latte).unwrap();
+        assert_eq!(load_from(&path).unwrap().theme, latte);
+
+        let rest = "sort = \"most-behind\"\n\n[timeouts]\nhttp_request = 5\n";
+        std::fs::write(&path, rest).unwrap();
+        save_theme(&path, &latte).unwrap();
+        let config = load_from(&path).unwrap();
+        assert_eq!((config.theme, config.sort), (latte, SortOrder::MostBehind));
+
+        save_theme(&path, &Theme::default()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("theme = \"catppuccin-mocha\"\n{}", rest)
+        );
+    }
 }
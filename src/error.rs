@@ -13,6 +13,42 @@ pub enum AppError {
     #[error("Failed to parse flake metadata: {0}")]
     MetadataParseError(String),
 
+    #[error("Nix {0} is not supported; melt needs Nix 2.4 or newer")]
+    UnsupportedNix(String),
+
+    #[error("Unsupported lock version {0}; melt reads flake.lock versions 5 to 7")]
+    UnsupportedLockVersion(u64),
+
+    #[error("No input named '{0}' in flake")]
+    InputNotFound(String),
+
+    #[error("Input '{0}' is frozen and cannot be updated")]
+    InputFrozen(String),
+
+    #[error("Update command for '{0}' failed: {1}")]
+    UpdateCommandFailed(String, String),
+
+    #[error("Building {0} failed: {1}")]
+    BuildFailed(String, String),
+
+    #[error("flake.lock not found in revision '{0}'")]
+    LockNotInRevision(String),
+
+    #[error("Input '{0}' is not a git input")]
+    NotGitInput(String),
+
+    #[error("Cannot edit flake.nix: {0}")]
+    FlakeEdit(String),
+
+    #[error("Cannot open pull request: {0}")]
+    PullRequest(String),
+
+    #[error("Failed to write output: {0}")]
+    Output(String),
+
+    #[error("Invalid config file {0}: {1}")]
+    Config(PathBuf, String),
+
     #[error("Git error: {0}")]
     Git(#[from] GitError),
 
@@ -40,6 +76,15 @@ pub enum GitError {
 
     #[error("Cache directory error: {0}")]
     CacheError(String),
+
+    #[error("Not available offline: {0}")]
+    Offline(String),
+
+    #[error("{forge} API rate limit exceeded")]
+    RateLimited {
+        forge: &'static str,
+        retry_after: std::time::Duration,
+    },
 }
 
 impl From<git2::Error> for GitError {
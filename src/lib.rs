@@ -13,8 +13,10 @@
 //! The crate is organized into several modules:
 //!
 //! - [`app`]: Application core with state management and event handling
+//! - [`cli`]: Headless subcommands that run without the TUI
 //! - [`error`]: Error types for the application
 //! - [`model`]: Domain models for flakes, inputs, commits, etc.
+//! - [`paths`]: Cache, state, and config locations
 //! - [`service`]: Services for interacting with Nix and Git
 //! - [`ui`]: UI rendering and theming
 //!
@@ -33,23 +35,27 @@
 //! ```
 
 pub mod app;
+pub mod cli;
 pub mod config;
 pub mod error;
 pub mod event;
 pub mod logging;
 pub mod model;
+pub mod paths;
 pub mod service;
+#[cfg(test)]
+mod testing;
 pub mod tui;
 pub mod ui;
 pub mod util;
 
 // Re-export commonly used types at the crate root
 pub use app::App;
-pub use config::{ServiceConfig, Timeouts};
+pub use config::{CommitLimits, ServiceConfig, Timeouts};
 pub use error::{AppError, AppResult, GitError};
 pub use model::{
-    ChangelogData, Commit, FlakeData, FlakeInput, GitInput, OtherInput, PathInput, StatusLevel,
-    StatusMessage, UpdateStatus,
+    ChangelogData, Commit, CommitCount, FlakeData, FlakeInput, GitInput, OtherInput, PathInput,
+    StatusLevel, StatusMessage, UpdateStatus,
 };
 pub use service::{GitService, NixService};
 pub use tui::Tui;
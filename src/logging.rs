@@ -3,27 +3,29 @@
 //! Logs are written to a file since the TUI controls the terminal.
 //! Log level can be controlled via the `RUST_LOG` environment variable.
 //!
-//! Log file locations (platform-dependent):
+//! The log file lives in the state directory (see [`crate::paths`]):
+//! - Linux: `$XDG_STATE_HOME/melt/melt.log` (`~/.local/state/melt/melt.log`)
 //! - macOS: `~/Library/Application Support/melt/melt.log`
-//! - Linux: `~/.local/share/melt/melt.log`
 //! - Windows: `C:\Users\<user>\AppData\Local\melt\melt.log`
 
 use std::{fs, io, path::PathBuf};
 
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
+use crate::paths;
+
 /// Returns the path to the log file.
 ///
 /// Creates the parent directory if it doesn't exist.
 pub fn log_file_path() -> Option<PathBuf> {
-    let data_dir = dirs::data_local_dir()?.join("melt");
-    fs::create_dir_all(&data_dir).ok()?;
-    Some(data_dir.join("melt.log"))
+    let path = paths::log_file();
+    fs::create_dir_all(path.parent()?).ok()?;
+    Some(path)
 }
 
 /// Initialize the logging system.
 ///
-/// Logs are written to `~/.local/share/melt/melt.log`.
+/// Logs are written to [`log_file_path`].
 /// The log level defaults to `info` but can be overridden with `RUST_LOG`.
 ///
 /// Returns `Ok(())` if logging was initialized, `Err` if it failed
@@ -1,29 +1,175 @@
-mod app;
-mod config;
-mod error;
-mod event;
-mod logging;
-mod model;
-mod service;
-mod tui;
-mod ui;
-mod util;
+use std::{path::PathBuf, process::ExitCode, time::Duration};
 
-use std::{path::PathBuf, process::ExitCode};
+use clap::{Parser, Subcommand};
 
-use clap::Parser;
-
-use app::App;
-use error::AppResult;
-use tui::Tui;
+use melt::cli::changelog::ChangelogFormat;
+use melt::cli::list::ListFormat;
+use melt::cli::sbom::SbomFormat;
+use melt::config::{self, Config};
+use melt::model::{ChangelogPath, ChangelogPaths, InputGlobs};
+use melt::util::format::{self, ClockFormat, FormatPrefs, WeekStart};
+use melt::util::time::parse_interval;
+use melt::{cli, logging, paths, tui, App, AppResult, Tui};
 
 /// A TUI for managing Nix flake inputs
 #[derive(Parser, Debug)]
 #[command(name = "melt", version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
-    /// Path to flake directory or flake.nix file
+    /// Paths to flake directories or flake.nix files; more than one opens
+    /// a tab per flake
     #[arg(default_value = ".")]
-    flake: PathBuf,
+    flakes: Vec<PathBuf>,
+
+    /// Maximum number of new commits to fetch per input [default: 500]
+    #[arg(long, global = true)]
+    max_commits: Option<usize>,
+
+    /// Only load inputs whose name matches this glob (repeatable)
+    #[arg(long, global = true, value_name = "GLOB")]
+    only: Vec<String>,
+
+    /// Skip inputs whose name matches this glob (repeatable)
+    #[arg(long, global = true, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Only show changelog commits of INPUT (a name glob) that touch PATH, a
+    /// git pathspec in its source tree (repeatable, e.g. nixpkgs=pkgs/by-name/ri/ripgrep)
+    #[arg(long, global = true, value_name = "INPUT=PATH")]
+    changelog_path: Vec<ChangelogPath>,
+
+    /// Work without network access: read flake.lock directly, skip update
+    /// checks, and show commit history only from cached clones
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Read inputs from flake.lock instead of running `nix flake metadata`,
+    /// which is slow on large flakes
+    #[arg(long, global = true)]
+    read_lock_file: bool,
+
+    /// Show times of day in 24-hour or 12-hour format [default: 24h]
+    #[arg(long, global = true, value_enum)]
+    clock: Option<ClockFormat>,
+
+    /// First day of the week for weekly digests [default: monday]
+    #[arg(long, global = true, value_enum)]
+    week_start: Option<WeekStart>,
+
+    /// Group digits in commit counts with this separator (e.g. ',')
+    #[arg(long, global = true, value_name = "CHAR")]
+    thousands_separator: Option<char>,
+
+    /// Re-run update checks on this interval (e.g. 30s, 5m, 1h)
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_interval)]
+    watch: Option<Duration>,
+
+    /// Write a weekly markdown digest of update activity into this directory
+    #[arg(long, value_name = "DIR", requires = "watch")]
+    digest: Option<PathBuf>,
+
+    /// Draw for screen readers: no borders or animation, and state changes
+    /// announced as plain lines
+    #[arg(long, global = true)]
+    accessible: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Initialize a new flake from a template and open it
+    Init {
+        /// Directory to create the flake in
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Template to use (e.g. `templates#rust`); pick interactively if omitted
+        #[arg(short, long)]
+        template: Option<String>,
+    },
+    /// Run the update check pipeline repeatedly and print timings
+    Bench {
+        /// Path to flake directory or flake.nix file
+        #[arg(long, default_value = ".")]
+        flake: PathBuf,
+
+        /// Number of times to run the pipeline
+        #[arg(short = 'n', long, default_value_t = 3)]
+        iterations: usize,
+    },
+    /// Print the commits an update of an input would pull in
+    Changelog {
+        /// Name of the input
+        input: String,
+
+        /// Path to flake directory or flake.nix file
+        #[arg(long, default_value = ".")]
+        flake: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ChangelogFormat::Text)]
+        format: ChangelogFormat,
+    },
+    /// Check inputs for updates and exit non-zero if any are too far behind
+    Check {
+        /// Path to flake directory or flake.nix file
+        #[arg(long, default_value = ".")]
+        flake: PathBuf,
+
+        /// Number of commits an input may be behind before the check fails
+        #[arg(long, default_value_t = 0)]
+        max_behind: usize,
+    },
+    /// Compare flake.lock with the version committed at a git revision
+    Diff {
+        /// Path to flake directory or flake.nix file
+        #[arg(long, default_value = ".")]
+        flake: PathBuf,
+
+        /// Git revision to compare against
+        #[arg(long, default_value = "HEAD")]
+        rev: String,
+    },
+    /// Diagnose the environment melt depends on and suggest fixes
+    Doctor {
+        /// Path to flake directory or flake.nix file
+        #[arg(long, default_value = ".")]
+        flake: PathBuf,
+    },
+    /// Print where melt keeps its cache, state, and config
+    Paths,
+    /// Export all locked inputs, including transitive ones, as an SBOM
+    ExportSbom {
+        /// Path to flake directory or flake.nix file
+        #[arg(long, default_value = ".")]
+        flake: PathBuf,
+
+        /// Document format
+        #[arg(long, value_enum, default_value_t = SbomFormat::default())]
+        format: SbomFormat,
+    },
+    /// Print the flake inputs in a machine-readable format, or as the list
+    /// view shows them
+    List {
+        /// Path to flake directory or flake.nix file
+        #[arg(long, default_value = ".")]
+        flake: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+        format: ListFormat,
+    },
+    /// Update inputs without the TUI and print what changed
+    Update {
+        /// Path to flake directory or flake.nix file
+        #[arg(long, default_value = ".")]
+        flake: PathBuf,
+
+        /// Inputs to update (all inputs when omitted)
+        inputs: Vec<String>,
+    },
 }
 
 #[tokio::main]
@@ -34,7 +180,7 @@ async fn main() -> ExitCode {
     let _ = logging::init();
 
     match run().await {
-        Ok(()) => ExitCode::SUCCESS,
+        Ok(code) => code,
         Err(err) => {
             eprintln!("{err}");
             ExitCode::FAILURE
@@ -42,9 +188,127 @@ async fn main() -> ExitCode {
     }
 }
 
-async fn run() -> AppResult<()> {
+async fn run() -> AppResult<ExitCode> {
     let args = Args::parse();
+    // Shows where the config file lives, so it must work even when that file is broken.
+    if let Some(Command::Paths) = args.command {
+        print!("{}", paths::render());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Command-line flags override the config file.
+    let Config {
+        service: mut config,
+        format: file_format,
+        columns,
+        token_hint,
+        refresh_interval,
+        sort,
+        accessible,
+        theme,
+    } = config::load()?;
+    let accessible = accessible || args.accessible;
+    format::set_prefs(FormatPrefs {
+        clock: args.clock.unwrap_or(file_format.clock),
+        week_start: args.week_start.unwrap_or(file_format.week_start),
+        thousands_separator: args.thousands_separator.or(file_format.thousands_separator),
+    });
+    if let Some(max_commits) = args.max_commits {
+        config.commit_limits.max_commits = max_commits.max(1);
+    }
+    config.offline |= args.offline;
+    config.read_lock_file |= args.read_lock_file;
+    if !args.only.is_empty() || !args.exclude.is_empty() {
+        config.input_globs = InputGlobs {
+            only: args.only,
+            exclude: args.exclude,
+            ignore: config.input_globs.ignore,
+            freeze: config.input_globs.freeze,
+        };
+    }
+    if !args.changelog_path.is_empty() {
+        config.changelog_paths = ChangelogPaths {
+            rules: args.changelog_path,
+        };
+    }
+
+    let mut app = match args.command {
+        Some(Command::Init { path, template }) => App::new_with_config(path, config)
+            .with_template(template)
+            .with_config_reload(paths::config_file())
+            .with_columns(columns)
+            .with_sort(sort)
+            .with_token_hint(token_hint)
+            .with_auto_refresh(refresh_interval)
+            .with_accessible(accessible)
+            .with_theme(theme),
+        Some(Command::Bench { flake, iterations }) => {
+            let report = cli::bench::run(&flake, iterations, config).await?;
+            print!("{}", report.render());
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Changelog {
+            input,
+            flake,
+            format,
+        }) => {
+            print!(
+                "{}",
+                cli::changelog::run(&flake, &input, format, config).await?
+            );
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Check { flake, max_behind }) => {
+            let report = cli::check::run(&flake, config).await?;
+            print!("{}", report.render(max_behind));
+            return Ok(ExitCode::from(report.exit_code(max_behind)));
+        }
+        Some(Command::Diff { flake, rev }) => {
+            let diff = cli::diff::run(&flake, &rev, config).await?;
+            print!("{}", diff.render());
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Doctor { flake }) => {
+            let report = cli::doctor::run(&flake, config).await;
+            print!("{}", report.render());
+            return Ok(if report.has_failures() {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            });
+        }
+        Some(Command::ExportSbom { flake, format }) => {
+            print!("{}", cli::sbom::run(&flake, format)?);
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Paths) => unreachable!("handled before loading the config"),
+        Some(Command::List { flake, format }) => {
+            let view = cli::list::ViewOptions { columns, sort };
+            print!("{}", cli::list::run(&flake, format, config, view).await?);
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Update { flake, inputs }) => {
+            let summary = cli::update::run(&flake, &inputs, config).await?;
+            print!("{}", summary.render());
+            return Ok(ExitCode::SUCCESS);
+        }
+        None => {
+            let mut flakes = args.flakes.into_iter();
+            let first = flakes.next().unwrap_or_else(|| PathBuf::from("."));
+            App::new_with_config(first, config)
+                .with_extra_flakes(flakes.collect())
+                .with_watch(args.watch)
+                .with_digest(args.digest)
+                .with_config_reload(paths::config_file())
+                .with_columns(columns)
+                .with_sort(sort)
+                .with_token_hint(token_hint)
+                .with_auto_refresh(refresh_interval)
+                .with_accessible(accessible)
+                .with_theme(theme)
+        }
+    };
     let mut tui = Tui::new()?;
-    let mut app = App::new(args.flake);
-    app.run(&mut tui).await
+    app.run(&mut tui).await?;
+    Ok(ExitCode::SUCCESS)
 }
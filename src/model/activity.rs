@@ -0,0 +1,78 @@
+//! Session activity log
+//!
+//! Every operation melt runs and how it went is kept here, so results that
+//! scrolled past in the status bar, such as several background checks
+//! failing at once, can be read back later.
+
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+use super::{StatusLevel, StatusMessage};
+
+/// Most entries kept; the oldest make way for new ones
+const MAX_ENTRIES: usize = 500;
+
+/// One thing that happened during the session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivityEntry {
+    pub at: DateTime<Utc>,
+    pub level: StatusLevel,
+    pub text: String,
+}
+
+/// Ring buffer of activity entries, oldest first
+#[derive(Debug, Default)]
+pub struct ActivityLog {
+    entries: VecDeque<ActivityEntry>,
+}
+
+impl ActivityLog {
+    /// Record `text` at `level` as happening now
+    pub fn record(&mut self, level: StatusLevel, text: impl Into<String>) {
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ActivityEntry {
+            at: Utc::now(),
+            level,
+            text: text.into(),
+        });
+    }
+
+    /// Record what a status message reports
+    pub fn record_message(&mut self, message: &StatusMessage) {
+        self.record(message.level, message.text.clone());
+    }
+
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = &ActivityEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_drops_the_oldest_entries_when_full() {
+        let mut log = ActivityLog::default();
+        for i in 0..MAX_ENTRIES + 2 {
+            log.record(StatusLevel::Error, format!("check {} failed", i));
+        }
+        log.record_message(&StatusMessage::success("Updated nixpkgs"));
+
+        assert_eq!(log.len(), MAX_ENTRIES);
+        let texts: Vec<&str> = log.entries().map(|entry| entry.text.as_str()).collect();
+        assert_eq!(texts[0], "check 3 failed");
+        assert_eq!(texts[MAX_ENTRIES - 1], "Updated nixpkgs");
+    }
+}
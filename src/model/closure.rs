@@ -0,0 +1,129 @@
+//! Package version changes between two system or package closures
+
+/// Packages whose versions or sizes differ between two closures, as
+/// reported by `nix store diff-closures`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClosureDiff {
+    /// Installable the closures were built from
+    pub installable: String,
+    /// Store paths before and after the update
+    pub before: String,
+    pub after: String,
+    pub changes: Vec<PackageChange>,
+}
+
+impl ClosureDiff {
+    /// Changes that added, removed, or moved a package's version; the rest
+    /// only changed size
+    pub fn version_changes(&self) -> Vec<&PackageChange> {
+        self.changes
+            .iter()
+            .filter(|change| change.changes_version())
+            .collect()
+    }
+}
+
+/// One package of a closure diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageChange {
+    pub name: String,
+    /// Versions before, empty when the package was added
+    pub before: Vec<String>,
+    /// Versions after, empty when the package was removed
+    pub after: Vec<String>,
+    /// Size difference as nix prints it (e.g. `+12.3 KiB`)
+    pub size: Option<String>,
+}
+
+impl PackageChange {
+    pub fn changes_version(&self) -> bool {
+        self.before != self.after
+    }
+
+    /// Parse one line of `nix store diff-closures` output, such as
+    /// `firefox: 120.0 → 121.0, +5.2 MiB` or `zstd: ∅ → 1.5.5`
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = strip_ansi(line);
+        let (name, rest) = line.split_once(": ")?;
+        let (rest, size) = match rest.rsplit_once(", ") {
+            Some((rest, size)) if is_size(size) => (rest, Some(size)),
+            _ if is_size(rest) => ("", Some(rest)),
+            _ => (rest, None),
+        };
+        let (before, after) = match rest.split_once(" → ") {
+            Some((before, after)) => (versions(before), versions(after)),
+            None => (versions(rest), versions(rest)),
+        };
+        Some(Self {
+            name: name.trim().to_string(),
+            before,
+            after,
+            size: size.map(str::to_string),
+        })
+    }
+}
+
+/// Versions of one side of a change. `∅` means absent, and `ε` an empty
+/// version string.
+fn versions(side: &str) -> Vec<String> {
+    side.split(", ")
+        .map(str::trim)
+        .filter(|version| !version.is_empty() && *version != "∅")
+        .map(|version| if version == "ε" { "" } else { version }.to_string())
+        .collect()
+}
+
+fn is_size(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some('+' | '-')) && chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// `line` without terminal color codes
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip to the end of the `ESC [ ... m` sequence.
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_diff_closures_lines() {
+        let change = PackageChange::parse("firefox: 120.0 → 121.0, +5.2 MiB").unwrap();
+        assert_eq!(change.name, "firefox");
+        assert_eq!(change.before, ["120.0"]);
+        assert_eq!(change.after, ["121.0"]);
+        assert_eq!(change.size.as_deref(), Some("+5.2 MiB"));
+
+        let added = PackageChange::parse("zstd: ∅ → 1.5.5, \x1b[31;1m+1234.5 KiB\x1b[0m").unwrap();
+        assert!(added.before.is_empty());
+        assert_eq!(added.size.as_deref(), Some("+1234.5 KiB"));
+
+        let multi = PackageChange::parse("python3: 3.11.6, 3.12.0 → 3.12.1").unwrap();
+        assert_eq!(multi.before, ["3.11.6", "3.12.0"]);
+        assert_eq!(multi.size, None);
+
+        let size_only = PackageChange::parse("glibc: -8.0 KiB").unwrap();
+        assert!(!size_only.changes_version());
+        assert_eq!(size_only.size.as_deref(), Some("-8.0 KiB"));
+
+        let unversioned = PackageChange::parse("etc: ε → ∅").unwrap();
+        assert_eq!(unversioned.before, [""]);
+        assert!(unversioned.after.is_empty());
+        assert!(PackageChange::parse("no separator").is_none());
+    }
+}
@@ -1,5 +1,10 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
 use chrono::{DateTime, Utc};
 
+use super::CommitCount;
+
 /// A git commit.
 #[derive(Debug, Clone)]
 pub struct Commit {
@@ -7,6 +12,20 @@ pub struct Commit {
     pub message: String,
     pub author: String,
     pub date: DateTime<Utc>,
+    pub signature: SignatureStatus,
+}
+
+/// What is known about a commit's GPG or SSH signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignatureStatus {
+    /// The commit's source does not report signatures
+    #[default]
+    Unknown,
+    Unsigned,
+    /// Signed, but the signature was not or could not be verified
+    Unverified,
+    /// The forge verified the signature
+    Verified,
 }
 
 impl Commit {
@@ -16,6 +35,56 @@ impl Commit {
     }
 }
 
+/// A tag and the commit it points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    pub sha: String,
+}
+
+/// A commit with its full message and the files it changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitDetail {
+    pub sha: String,
+    /// Full message, subject and body
+    pub message: String,
+    pub author: String,
+    pub email: String,
+    pub date: DateTime<Utc>,
+    pub files: Vec<FileChange>,
+}
+
+impl CommitDetail {
+    /// Totals over the changed files
+    pub fn stats(&self) -> DiffStats {
+        DiffStats {
+            files_changed: self.files.len(),
+            insertions: self.files.iter().map(|f| f.insertions).sum(),
+            deletions: self.files.iter().map(|f| f.deletions).sum(),
+            partial: false,
+        }
+    }
+}
+
+/// Lines a commit changed in one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: String,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// A release published for a tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    /// Release title, or the tag name when it has none
+    pub name: String,
+    /// Release notes as written, usually markdown
+    pub body: String,
+    /// Web page of the release
+    pub url: String,
+}
+
 /// Valid commit index into a `ChangelogData` commit list.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CommitIndex {
@@ -38,11 +107,52 @@ pub enum ChangelogDataError {
     LockedIndexOutOfRange { index: usize, len: usize },
 }
 
+/// Lines changed between the locked revision and the upstream revision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffStats {
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Only some of the changed files were counted, so the totals are
+    /// lower bounds
+    pub partial: bool,
+}
+
+/// Paths a changelog was narrowed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathFilter {
+    pub paths: Vec<String>,
+    /// Number of new commits before narrowing, a lower bound when they
+    /// were cut off at the commit limit
+    pub total: CommitCount,
+}
+
+/// Summary of the commits a pending update would bring in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogSummary {
+    pub ahead: CommitCount,
+    pub authors: usize,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+    pub diff_stats: Option<DiffStats>,
+}
+
+/// Number of new commits written by one author.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorCount {
+    pub author: String,
+    pub commits: usize,
+}
+
 /// Result of fetching changelog for an input.
 #[derive(Debug, Clone)]
 pub struct ChangelogData {
     pub commits: Vec<Commit>,
     locked: Option<CommitIndex>,
+    truncated_at: Option<usize>,
+    diff_stats: Option<DiffStats>,
+    path_filter: Option<PathFilter>,
+    clone_path: Option<PathBuf>,
 }
 
 impl ChangelogData {
@@ -60,7 +170,125 @@ impl ChangelogData {
             None => None,
         };
 
-        Ok(Self { commits, locked })
+        Ok(Self {
+            commits,
+            locked,
+            truncated_at: None,
+            diff_stats: None,
+            path_filter: None,
+            clone_path: None,
+        })
+    }
+
+    /// Commits loaded so far while the rest are on the way. Until the locked
+    /// revision shows up, the new commit count is a lower bound.
+    pub fn partial(commits: Vec<Commit>, rev: &str) -> Self {
+        let locked = commits
+            .iter()
+            .rposition(|c| c.sha.starts_with(rev))
+            .and_then(|index| CommitIndex::new(index, commits.len()));
+        let truncated_at = locked.is_none().then_some(commits.len());
+        Self {
+            commits,
+            locked,
+            truncated_at,
+            diff_stats: None,
+            path_filter: None,
+            clone_path: None,
+        }
+    }
+
+    /// Attach line statistics for the pending update.
+    pub fn with_diff_stats(mut self, stats: DiffStats) -> Self {
+        self.diff_stats = Some(stats);
+        self
+    }
+
+    /// Line statistics for the pending update, when the source provides them.
+    pub fn diff_stats(&self) -> Option<DiffStats> {
+        self.diff_stats
+    }
+
+    /// Mark the new commits as narrowed to those touching `filter.paths`.
+    pub fn with_path_filter(mut self, filter: PathFilter) -> Self {
+        self.path_filter = Some(filter);
+        self
+    }
+
+    /// Paths the new commits were narrowed to, if any.
+    pub fn path_filter(&self) -> Option<&PathFilter> {
+        self.path_filter.as_ref()
+    }
+
+    /// Mark the commits as read from the cached clone at `path` rather
+    /// than a forge API.
+    pub fn with_clone_path(mut self, path: PathBuf) -> Self {
+        self.clone_path = Some(path);
+        self
+    }
+
+    /// Cached clone the commits were read from, if no forge API was used.
+    pub fn clone_path(&self) -> Option<&Path> {
+        self.clone_path.as_deref()
+    }
+
+    /// New commits (ahead of the locked commit), newest first.
+    pub fn new_commits(&self) -> &[Commit] {
+        &self.commits[..self.commits_ahead()]
+    }
+
+    /// Summarize the new commits for a quick risk gauge before locking.
+    pub fn summary(&self) -> ChangelogSummary {
+        summarize(
+            self.new_commits(),
+            self.commits_ahead_count(),
+            self.diff_stats,
+        )
+    }
+
+    /// Summarize the commits locking to the new commit at `index` brings
+    /// in: those after the locked commit up to and including it. Line
+    /// statistics are only known for the newest commit.
+    pub fn summary_to(&self, index: usize) -> Option<ChangelogSummary> {
+        let locked = self.locked_index()?;
+        let commits = self.commits.get(index..locked).filter(|c| !c.is_empty())?;
+        let diff_stats = if index == 0 { self.diff_stats } else { None };
+        Some(summarize(
+            commits,
+            CommitCount::Exact(commits.len()),
+            diff_stats,
+        ))
+    }
+
+    /// Group the new commits by author, most active first.
+    ///
+    /// Ties are ordered by author name so the list is stable between renders.
+    pub fn author_counts(&self) -> Vec<AuthorCount> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for commit in self.new_commits() {
+            *counts.entry(commit.author.as_str()).or_default() += 1;
+        }
+
+        let mut authors: Vec<AuthorCount> = counts
+            .into_iter()
+            .map(|(author, commits)| AuthorCount {
+                author: author.to_string(),
+                commits,
+            })
+            .collect();
+        authors.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.author.cmp(&b.author)));
+        authors
+    }
+
+    /// Mark the commit list as cut off by a fetch limit of `limit` commits.
+    pub fn with_truncation(mut self, limit: usize) -> Self {
+        self.truncated_at = Some(limit);
+        self
+    }
+
+    /// Fetch limit that cut off the commit list, if more commits exist upstream.
+    pub fn truncated_at(&self) -> Option<usize> {
+        self.truncated_at
     }
 
     pub fn locked_index(&self) -> Option<usize> {
@@ -76,6 +304,14 @@ impl ChangelogData {
         self.locked_index().unwrap_or(self.commits.len())
     }
 
+    /// Get the number of new commits, as a lower bound when the list was truncated
+    pub fn commits_ahead_count(&self) -> CommitCount {
+        match self.truncated_at {
+            Some(_) => CommitCount::AtLeast(self.commits_ahead()),
+            None => CommitCount::Exact(self.commits_ahead()),
+        }
+    }
+
     /// Get the number of older commits (including and after locked)
     pub fn commits_behind(&self) -> usize {
         match self.locked_index() {
@@ -85,6 +321,21 @@ impl ChangelogData {
     }
 }
 
+fn summarize(
+    commits: &[Commit],
+    ahead: CommitCount,
+    diff_stats: Option<DiffStats>,
+) -> ChangelogSummary {
+    let authors: HashSet<&str> = commits.iter().map(|c| c.author.as_str()).collect();
+    ChangelogSummary {
+        ahead,
+        authors: authors.len(),
+        oldest: commits.iter().map(|c| c.date).min(),
+        newest: commits.iter().map(|c| c.date).max(),
+        diff_stats,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,10 +347,22 @@ mod tests {
                 message: "message".to_string(),
                 author: "author".to_string(),
                 date: Utc::now(),
+                signature: SignatureStatus::Unknown,
             })
             .collect()
     }
 
+    #[test]
+    fn test_partial_counts_are_lower_bounds_until_locked_found() {
+        let loading = ChangelogData::partial(commits(3), "abcdef9");
+        assert_eq!(loading.locked_index(), None);
+        assert_eq!(loading.commits_ahead_count(), CommitCount::AtLeast(3));
+
+        let found = ChangelogData::partial(commits(3), "abcdef1");
+        assert_eq!(found.locked_index(), Some(1));
+        assert_eq!(found.commits_ahead_count(), CommitCount::Exact(1));
+    }
+
     #[test]
     fn test_short_sha() {
         let commit = Commit {
@@ -107,6 +370,7 @@ mod tests {
             message: String::new(),
             author: String::new(),
             date: Utc::now(),
+            signature: SignatureStatus::Unknown,
         };
         assert_eq!(commit.short_sha(), "abcdef1");
 
@@ -115,6 +379,7 @@ mod tests {
             message: String::new(),
             author: String::new(),
             date: Utc::now(),
+            signature: SignatureStatus::Unknown,
         };
         assert_eq!(short.short_sha(), "abc");
     }
@@ -147,6 +412,98 @@ mod tests {
         assert_eq!(empty.commits_behind(), 0);
     }
 
+    #[test]
+    fn test_changelog_truncation() {
+        let data = ChangelogData::new(commits(3), None).unwrap();
+        assert_eq!(data.truncated_at(), None);
+        assert_eq!(data.commits_ahead_count(), CommitCount::Exact(3));
+
+        let truncated = data.with_truncation(3);
+        assert_eq!(truncated.truncated_at(), Some(3));
+        assert_eq!(truncated.commits_ahead_count(), CommitCount::AtLeast(3));
+    }
+
+    #[test]
+    fn test_changelog_summary_counts_new_commits_only() {
+        let mut list = commits(4);
+        list[0].author = "alice".to_string();
+        list[1].author = "bob".to_string();
+        list[2].author = "alice".to_string();
+        let stats = DiffStats {
+            files_changed: 2,
+            insertions: 10,
+            deletions: 4,
+            partial: false,
+        };
+        let data = ChangelogData::new(list, Some(3))
+            .unwrap()
+            .with_diff_stats(stats);
+
+        let summary = data.summary();
+
+        assert_eq!(summary.ahead, CommitCount::Exact(3));
+        assert_eq!(summary.authors, 2);
+        assert!(summary.oldest <= summary.newest);
+        assert_eq!(summary.diff_stats, Some(stats));
+
+        let empty = ChangelogData::new(Vec::new(), None).unwrap().summary();
+        assert_eq!(empty.authors, 0);
+        assert_eq!(empty.newest, None);
+    }
+
+    #[test]
+    fn test_changelog_summary_to_spans_commits_after_locked() {
+        let mut list = commits(5);
+        list[1].author = "alice".to_string();
+        list[2].author = "bob".to_string();
+        let stats = DiffStats {
+            files_changed: 1,
+            insertions: 1,
+            deletions: 0,
+            partial: false,
+        };
+        let data = ChangelogData::new(list, Some(3))
+            .unwrap()
+            .with_diff_stats(stats);
+
+        let partial = data.summary_to(1).unwrap();
+        assert_eq!(partial.ahead, CommitCount::Exact(2));
+        assert_eq!(partial.authors, 2);
+        assert_eq!(partial.diff_stats, None);
+        assert_eq!(data.summary_to(0).unwrap().diff_stats, Some(stats));
+        assert_eq!(data.summary_to(3), None);
+        assert_eq!(data.summary_to(4), None);
+    }
+
+    #[test]
+    fn test_changelog_author_counts() {
+        let mut list = commits(5);
+        for (commit, author) in list.iter_mut().zip(["bot", "alice", "bot", "carol", "zed"]) {
+            commit.author = author.to_string();
+        }
+        let data = ChangelogData::new(list, Some(4)).unwrap();
+
+        let authors = data.author_counts();
+
+        assert_eq!(
+            authors,
+            vec![
+                AuthorCount {
+                    author: "bot".to_string(),
+                    commits: 2
+                },
+                AuthorCount {
+                    author: "alice".to_string(),
+                    commits: 1
+                },
+                AuthorCount {
+                    author: "carol".to_string(),
+                    commits: 1
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_changelog_rejects_out_of_range_locked_commit() {
         assert!(matches!(
@@ -0,0 +1,294 @@
+//! Periodic digest of flake input activity
+//!
+//! A digest covers one week, starting on the configured first day, and
+//! collects which inputs gained upstream commits while melt was watching,
+//! and which inputs had their locked revision changed. It renders as
+//! markdown for maintenance notes, and is kept as JSON next to it so a
+//! restart picks the period up where it was.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{CommitCount, UpdateStatus};
+use crate::util::format::format_datetime;
+
+/// An input whose upstream gained commits during the digest period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvailableUpdate {
+    /// When new commits were first noticed
+    pub first_seen: DateTime<Utc>,
+    /// Commits behind at the most recent check
+    pub behind: CommitCount,
+}
+
+/// An input whose locked revision changed during the digest period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedUpdate {
+    pub input: String,
+    pub from: String,
+    pub to: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Activity collected over one digest period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    pub started: DateTime<Utc>,
+    pub available: BTreeMap<String, AvailableUpdate>,
+    pub applied: Vec<AppliedUpdate>,
+}
+
+impl Digest {
+    pub fn new(started: DateTime<Utc>) -> Self {
+        Self {
+            started,
+            available: BTreeMap::new(),
+            applied: Vec::new(),
+        }
+    }
+
+    /// Length of a digest period
+    pub fn period() -> Duration {
+        Duration::weeks(1)
+    }
+
+    /// When this digest's period ends
+    pub fn ends(&self) -> DateTime<Utc> {
+        self.started + Self::period()
+    }
+
+    /// Whether the period has ended and a new digest should start
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        now >= self.ends()
+    }
+
+    /// File name for this digest, keyed by the day the period started
+    pub fn file_name(&self) -> String {
+        format!("melt-digest-{}.md", self.started.format("%Y-%m-%d"))
+    }
+
+    /// Name of the file this digest's state is kept in, next to its markdown
+    pub fn state_file_name(&self) -> String {
+        format!(".melt-digest-{}.json", self.started.format("%Y-%m-%d"))
+    }
+
+    /// This digest's state, as read back by [`Digest::from_json`]
+    pub fn to_json(&self) -> String {
+        let file = DigestFile {
+            started: self.started.timestamp(),
+            available: self
+                .available
+                .iter()
+                .map(|(input, update)| {
+                    let (behind, at_least) = match update.behind {
+                        CommitCount::Exact(n) => (n, false),
+                        CommitCount::AtLeast(n) => (n, true),
+                    };
+                    let available = AvailableFile {
+                        first_seen: update.first_seen.timestamp(),
+                        behind,
+                        at_least,
+                    };
+                    (input.clone(), available)
+                })
+                .collect(),
+            applied: self
+                .applied
+                .iter()
+                .map(|update| AppliedFile {
+                    input: update.input.clone(),
+                    from: update.from.clone(),
+                    to: update.to.clone(),
+                    at: update.at.timestamp(),
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&file).unwrap_or_default()
+    }
+
+    /// A digest saved by [`Digest::to_json`], or `None` if `json` isn't one
+    pub fn from_json(json: &str) -> Option<Self> {
+        let file: DigestFile = serde_json::from_str(json).ok()?;
+        let time = |secs| DateTime::<Utc>::from_timestamp(secs, 0);
+        let mut available = BTreeMap::new();
+        for (input, update) in file.available {
+            let behind = if update.at_least {
+                CommitCount::AtLeast(update.behind)
+            } else {
+                CommitCount::Exact(update.behind)
+            };
+            let first_seen = time(update.first_seen)?;
+            available.insert(input, AvailableUpdate { first_seen, behind });
+        }
+        let mut applied = Vec::new();
+        for update in file.applied {
+            applied.push(AppliedUpdate {
+                input: update.input,
+                from: update.from,
+                to: update.to,
+                at: time(update.at)?,
+            });
+        }
+        Some(Self {
+            started: time(file.started)?,
+            available,
+            applied,
+        })
+    }
+
+    /// Record that `input` is `behind` commits after gaining new ones
+    pub fn record_available(&mut self, input: &str, behind: CommitCount, at: DateTime<Utc>) {
+        self.available
+            .entry(input.to_string())
+            .and_modify(|update| update.behind = behind)
+            .or_insert(AvailableUpdate {
+                first_seen: at,
+                behind,
+            });
+    }
+
+    /// Record that `input` moved from revision `from` to `to`
+    pub fn record_applied(&mut self, input: &str, from: &str, to: &str, at: DateTime<Utc>) {
+        self.applied.push(AppliedUpdate {
+            input: input.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            at,
+        });
+    }
+
+    /// Move what was recorded for `old` over to its new name
+    pub fn rename_input(&mut self, old: &str, new: &str) {
+        if let Some(update) = self.available.remove(old) {
+            self.available.insert(new.to_string(), update);
+        }
+        for applied in self.applied.iter_mut().filter(|a| a.input == old) {
+            applied.input = new.to_string();
+        }
+    }
+
+    /// Render the digest as markdown
+    pub fn render_markdown(&self, flake: &Path) -> String {
+        let mut out = format!("# melt digest: {}\n\n", flake.display());
+        out.push_str(&format!(
+            "Week of {} to {} (UTC)\n\n",
+            self.started.format("%Y-%m-%d"),
+            self.ends().format("%Y-%m-%d")
+        ));
+
+        out.push_str("## Updates available\n\n");
+        if self.available.is_empty() {
+            out.push_str("_None_\n");
+        } else {
+            out.push_str("| Input | Behind | First seen |\n| --- | --- | --- |\n");
+            for (input, update) in &self.available {
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    input,
+                    UpdateStatus::Behind(update.behind).display(),
+                    format_datetime(update.first_seen)
+                ));
+            }
+        }
+
+        out.push_str("\n## Updates applied\n\n");
+        if self.applied.is_empty() {
+            out.push_str("_None_\n");
+        } else {
+            out.push_str("| Input | From | To | Applied |\n| --- | --- | --- | --- |\n");
+            for update in &self.applied {
+                out.push_str(&format!(
+                    "| {} | `{}` | `{}` | {} |\n",
+                    update.input,
+                    update.from,
+                    update.to,
+                    format_datetime(update.at)
+                ));
+            }
+        }
+
+        out
+    }
+}
+
+/// Layout of a digest's state file, with times in Unix seconds
+#[derive(Serialize, Deserialize)]
+struct DigestFile {
+    started: i64,
+    available: BTreeMap<String, AvailableFile>,
+    applied: Vec<AppliedFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AvailableFile {
+    first_seen: i64,
+    behind: usize,
+    at_least: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AppliedFile {
+    input: String,
+    from: String,
+    to: String,
+    at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn digest_tracks_period_and_latest_behind_count() {
+        let mut digest = Digest::new(at(12, 0));
+        digest.record_available("nixpkgs", CommitCount::Exact(3), at(13, 9));
+        digest.record_available("nixpkgs", CommitCount::Exact(8), at(14, 9));
+
+        let update = &digest.available["nixpkgs"];
+        assert_eq!(update.first_seen, at(13, 9));
+        assert_eq!(update.behind, CommitCount::Exact(8));
+        assert_eq!(digest.file_name(), "melt-digest-2026-10-12.md");
+        assert!(!digest.is_due(at(18, 23)));
+        assert!(digest.is_due(at(19, 0)));
+
+        digest.rename_input("nixpkgs", "nixpkgs-unstable");
+        assert!(!digest.available.contains_key("nixpkgs"));
+        assert_eq!(digest.available["nixpkgs-unstable"].first_seen, at(13, 9));
+    }
+
+    #[test]
+    fn digest_state_round_trips() {
+        let mut digest = Digest::new(at(12, 0));
+        digest.record_available("nixpkgs", CommitCount::AtLeast(500), at(13, 9));
+        digest.record_available("home-manager", CommitCount::Exact(4), at(13, 10));
+        digest.record_applied("home-manager", "abc1234", "def5678", at(14, 10));
+
+        assert_eq!(digest.state_file_name(), ".melt-digest-2026-10-12.json");
+        assert_eq!(Digest::from_json(&digest.to_json()), Some(digest));
+        assert_eq!(Digest::from_json("not json"), None);
+    }
+
+    #[test]
+    fn digest_renders_markdown_sections() {
+        let mut digest = Digest::new(at(12, 0));
+        assert!(digest
+            .render_markdown(Path::new("/flake"))
+            .contains("## Updates available\n\n_None_\n"));
+
+        digest.record_available("nixpkgs", CommitCount::AtLeast(500), at(13, 9));
+        digest.record_applied("home-manager", "abc1234", "def5678", at(14, 10));
+        let markdown = digest.render_markdown(Path::new("/flake"));
+
+        assert!(markdown.starts_with("# melt digest: /flake\n\nWeek of 2026-10-12 to 2026-10-19"));
+        assert!(markdown.contains("| nixpkgs | 500+ | 2026-10-13 09:00 |"));
+        assert!(markdown.contains("| home-manager | `abc1234` | `def5678` | 2026-10-14 10:00 |"));
+    }
+}
@@ -0,0 +1,356 @@
+//! Filter expressions for matching flake inputs
+//!
+//! An expression is a whitespace-separated list of terms that must all match.
+//! A bare term is a glob on the input name (`nix*`); `key=value` terms match a
+//! specific field (`forge=github status=behind`). Values are case-insensitive
+//! globs where `*` matches any run of characters and `?` a single character.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{FlakeInput, UpdateStatus};
+
+/// Field an expression term is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterField {
+    Name,
+    Type,
+    Forge,
+    Status,
+}
+
+impl FilterField {
+    /// Every field, in the order they are offered for completion
+    pub const ALL: [FilterField; 4] = [
+        FilterField::Name,
+        FilterField::Type,
+        FilterField::Forge,
+        FilterField::Status,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            FilterField::Name => "name",
+            FilterField::Type => "type",
+            FilterField::Forge => "forge",
+            FilterField::Status => "status",
+        }
+    }
+
+    fn parse(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.key().eq_ignore_ascii_case(key))
+    }
+}
+
+/// Error returned when a filter expression cannot be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterError {
+    Empty,
+    UnknownField(String),
+    MissingValue(String),
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::Empty => write!(f, "Empty filter expression"),
+            FilterError::UnknownField(key) => write!(
+                f,
+                "Unknown filter field '{}' (expected name, type, forge, or status)",
+                key
+            ),
+            FilterError::MissingValue(key) => write!(f, "Missing value for '{}='", key),
+        }
+    }
+}
+
+/// A parsed filter expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputFilter {
+    terms: Vec<(FilterField, String)>,
+}
+
+impl InputFilter {
+    pub fn parse(expr: &str) -> Result<Self, FilterError> {
+        let terms = expr
+            .split_whitespace()
+            .map(|term| match term.split_once('=') {
+                Some((key, value)) => {
+                    let field = FilterField::parse(key)
+                        .ok_or_else(|| FilterError::UnknownField(key.to_string()))?;
+                    if value.is_empty() {
+                        return Err(FilterError::MissingValue(key.to_string()));
+                    }
+                    Ok((field, value.to_lowercase()))
+                }
+                None => Ok((FilterField::Name, term.to_lowercase())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if terms.is_empty() {
+            return Err(FilterError::Empty);
+        }
+        Ok(Self { terms })
+    }
+
+    /// Whether `input`, with its current update status, satisfies every term
+    pub fn matches(&self, input: &FlakeInput, status: Option<&UpdateStatus>) -> bool {
+        self.terms.iter().all(|(field, pattern)| {
+            let value = match field {
+                FilterField::Name => input.name().to_lowercase(),
+                FilterField::Type => input.type_display().to_string(),
+                FilterField::Forge => match input {
+                    FlakeInput::Git(git) => git.repo().forge_name().to_string(),
+                    _ => String::new(),
+                },
+                FilterField::Status => status_key(status.unwrap_or(&UpdateStatus::Unknown)),
+            };
+            glob_match(pattern, &value)
+        })
+    }
+}
+
+/// Name globs that restrict which inputs melt loads (`--only` / `--exclude`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputGlobs {
+    pub only: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Inputs that are always skipped, from `ignore` in the config file.
+    /// Unlike `exclude`, command-line globs do not replace these.
+    pub ignore: Vec<String>,
+    /// Inputs that are shown and checked but never updated, from `freeze`
+    pub freeze: Vec<String>,
+}
+
+impl InputGlobs {
+    /// Whether no input is skipped or frozen
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty()
+            && self.exclude.is_empty()
+            && self.ignore.is_empty()
+            && self.freeze.is_empty()
+    }
+
+    /// Whether updates skip an input named `name`
+    pub fn is_frozen(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.freeze
+            .iter()
+            .any(|glob| glob_match(&glob.to_lowercase(), &name))
+    }
+
+    /// Whether an input named `name` is kept.
+    ///
+    /// A name is kept when it matches any `only` glob (or none are given) and
+    /// no `exclude` or `ignore` glob. Matching is case-insensitive.
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        let matches_any = |globs: &[String]| {
+            globs
+                .iter()
+                .any(|glob| glob_match(&glob.to_lowercase(), &name))
+        };
+        (self.only.is_empty() || matches_any(&self.only))
+            && !matches_any(&self.exclude)
+            && !matches_any(&self.ignore)
+    }
+}
+
+/// A path the flake uses inside an input's source tree (`INPUT=PATH`)
+///
+/// `input` is a case-insensitive name glob; `path` is a git pathspec, so
+/// `pkgs/by-name/ri/ripgrep` covers that directory and `nixos/modules/*.nix`
+/// uses fnmatch-style wildcards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogPath {
+    pub input: String,
+    pub path: String,
+}
+
+impl FromStr for ChangelogPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((input, path)) if !input.is_empty() && !path.is_empty() => Ok(Self {
+                input: input.to_string(),
+                path: path.to_string(),
+            }),
+            _ => Err(format!("expected INPUT=PATH, got '{}'", s)),
+        }
+    }
+}
+
+/// Paths changelogs are narrowed to, per input (`--changelog-path`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangelogPaths {
+    pub rules: Vec<ChangelogPath>,
+}
+
+impl ChangelogPaths {
+    /// Pathspecs that apply to the input named `name`, in the order given
+    pub fn for_input(&self, name: &str) -> Vec<String> {
+        let name = name.to_lowercase();
+        self.rules
+            .iter()
+            .filter(|rule| glob_match(&rule.input.to_lowercase(), &name))
+            .map(|rule| rule.path.clone())
+            .collect()
+    }
+}
+
+/// Name an update status is matched by in `status=` terms
+fn status_key(status: &UpdateStatus) -> String {
+    match status {
+        UpdateStatus::Unknown => "unknown",
+        UpdateStatus::Checking => "checking",
+        UpdateStatus::Queued => "queued",
+        UpdateStatus::Updating => "updating",
+        UpdateStatus::Updated => "updated",
+        UpdateStatus::UpToDate => "ok",
+        UpdateStatus::Behind(_) => "behind",
+        UpdateStatus::Error(_) => "error",
+        UpdateStatus::RateLimited(_) => "waiting",
+    }
+    .to_string()
+}
+
+/// Match `text` against a glob supporting `*` and `?`
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::RepoName;
+    use crate::model::{CommitCount, GitInput, GitRepo, GitRev, InputName, Owner, PathInput};
+
+    fn github(name: &str) -> FlakeInput {
+        FlakeInput::Git(GitInput::new(
+            InputName::new(name).unwrap(),
+            GitRepo::github(Owner::new("NixOS").unwrap(), RepoName::new(name).unwrap()),
+            None,
+            GitRev::new("abc1234").unwrap(),
+            0,
+            format!("github:NixOS/{}", name),
+        ))
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_match("nix*", "nixpkgs"));
+        assert!(glob_match("*pkgs", "nixpkgs"));
+        assert!(glob_match("n?x*s", "nixpkgs"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("nix", "nixpkgs"));
+        assert!(!glob_match("home*", "nixpkgs"));
+    }
+
+    #[test]
+    fn input_globs_combine_only_and_exclude() {
+        let globs = InputGlobs {
+            only: vec!["nix*".to_string(), "home-manager".to_string()],
+            exclude: vec!["nixos-hardware".to_string()],
+            ignore: vec!["nix-vendored-*".to_string()],
+            freeze: vec!["nixpkgs".to_string()],
+        };
+
+        assert!(globs.matches("nixpkgs"));
+        assert!(!globs.matches("nix-vendored-foo"));
+        assert!(globs.matches("Home-Manager"));
+        assert!(!globs.matches("nixos-hardware"));
+        assert!(!globs.matches("flake-utils"));
+        assert!(InputGlobs::default().matches("anything"));
+
+        assert!(globs.matches("nixpkgs") && globs.is_frozen("NixPkgs"));
+        assert!(!globs.is_frozen("home-manager"));
+    }
+
+    #[test]
+    fn changelog_paths_apply_by_input_glob() {
+        let paths = ChangelogPaths {
+            rules: vec![
+                "nixpkgs*=pkgs/by-name/ri/ripgrep".parse().unwrap(),
+                "nixpkgs=nixos/modules/services/web-servers/caddy"
+                    .parse()
+                    .unwrap(),
+                "home-manager=modules/programs/git.nix".parse().unwrap(),
+            ],
+        };
+
+        assert_eq!(
+            paths.for_input("NixPkgs"),
+            vec![
+                "pkgs/by-name/ri/ripgrep".to_string(),
+                "nixos/modules/services/web-servers/caddy".to_string()
+            ]
+        );
+        assert!(paths.for_input("flake-utils").is_empty());
+        assert!("nixpkgs".parse::<ChangelogPath>().is_err());
+        assert!("=pkgs".parse::<ChangelogPath>().is_err());
+    }
+
+    #[test]
+    fn bare_terms_match_names_case_insensitively() {
+        let filter = InputFilter::parse("NIX*").unwrap();
+        assert!(filter.matches(&github("nixpkgs"), None));
+        assert!(!filter.matches(&github("home-manager"), None));
+    }
+
+    #[test]
+    fn field_terms_are_combined() {
+        let filter = InputFilter::parse("forge=github status=behind").unwrap();
+        let behind = UpdateStatus::Behind(CommitCount::Exact(3));
+
+        assert!(filter.matches(&github("nixpkgs"), Some(&behind)));
+        assert!(!filter.matches(&github("nixpkgs"), Some(&UpdateStatus::UpToDate)));
+        assert!(!filter.matches(
+            &FlakeInput::Path(PathInput {
+                name: "local".to_string(),
+                flake: true,
+            }),
+            Some(&behind)
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert_eq!(InputFilter::parse("  "), Err(FilterError::Empty));
+        assert_eq!(
+            InputFilter::parse("owner=NixOS"),
+            Err(FilterError::UnknownField("owner".to_string()))
+        );
+        assert_eq!(
+            InputFilter::parse("forge="),
+            Err(FilterError::MissingValue("forge".to_string()))
+        );
+    }
+}
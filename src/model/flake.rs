@@ -1,12 +1,101 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
-use super::{CloneUrl, DomainError, GitHost, GitRef, GitRev, InputName, LockUrl, Owner, RepoName};
+use super::{
+    CloneUrl, DomainError, GitHost, GitRef, GitRev, InputName, InputTree, LockUrl, LockWarning,
+    Owner, RepoName,
+};
 
 /// Data about a loaded flake
 #[derive(Debug, Clone)]
 pub struct FlakeData {
     pub path: PathBuf,
     pub inputs: Vec<FlakeInput>,
+    /// Problems in the lock file that affected single inputs
+    pub warnings: Vec<LockWarning>,
+    /// Every root input with the inputs it declares, down to the leaves of
+    /// the lock graph
+    pub input_trees: Vec<InputTree>,
+}
+
+impl FlakeData {
+    /// Inputs that the root input `name` declares in turn
+    pub fn transitive_inputs(&self, name: &str) -> &[InputTree] {
+        self.input_trees
+            .iter()
+            .find(|tree| tree.name == name)
+            .map_or(&[], |tree| tree.inputs.as_slice())
+    }
+
+    /// Inputs that follow the root input `name`, by their path from the
+    /// root (e.g. `pkgs` or `home-manager/nixpkgs`)
+    pub fn followers(&self, name: &str) -> Vec<String> {
+        fn walk(trees: &[InputTree], prefix: &str, name: &str, out: &mut Vec<String>) {
+            for tree in trees {
+                let path = if prefix.is_empty() {
+                    tree.name.clone()
+                } else {
+                    format!("{}/{}", prefix, tree.name)
+                };
+                if matches!(tree.follows.as_deref(), Some([target]) if target == name) {
+                    out.push(path.clone());
+                }
+                walk(&tree.inputs, &path, name, out);
+            }
+        }
+
+        let mut out = Vec::new();
+        walk(&self.input_trees, "", name, &mut out);
+        out
+    }
+
+    /// Inputs renamed since `before`, as `(old, new)` name pairs.
+    ///
+    /// A git input counts as renamed when its name is gone and exactly one
+    /// new name tracks the same repository and branch.
+    pub fn renames_since(&self, before: &FlakeData) -> Vec<(String, String)> {
+        let added = added_git_inputs(self, before);
+        let removed = added_git_inputs(before, self);
+
+        let mut added_by_source: HashMap<String, Vec<&str>> = HashMap::new();
+        for input in &added {
+            added_by_source
+                .entry(input.source_id())
+                .or_default()
+                .push(input.name());
+        }
+        let mut removed_by_source: HashMap<String, usize> = HashMap::new();
+        for input in &removed {
+            *removed_by_source.entry(input.source_id()).or_default() += 1;
+        }
+
+        removed
+            .iter()
+            .filter_map(|old| {
+                let source = old.source_id();
+                match (
+                    added_by_source.get(&source)?.as_slice(),
+                    removed_by_source[&source],
+                ) {
+                    ([new], 1) => Some((old.name().to_string(), new.to_string())),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Git inputs of `flake` whose names are not in `other`
+fn added_git_inputs<'a>(flake: &'a FlakeData, other: &FlakeData) -> Vec<&'a GitInput> {
+    flake
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FlakeInput::Git(git) => Some(git),
+            _ => None,
+        })
+        .filter(|git| !other.inputs.iter().any(|input| input.name() == git.name()))
+        .collect()
 }
 
 /// A flake input - can be git-based, a local path, or something else
@@ -15,6 +104,7 @@ pub enum FlakeInput {
     Git(GitInput),
     Path(PathInput),
     Other(OtherInput),
+    Follows(FollowsInput),
 }
 
 /// Validated, actionable git-based flake input.
@@ -26,6 +116,7 @@ pub struct GitInput {
     rev: GitRev,
     last_modified: i64,
     url: String,
+    flake: bool,
 }
 
 impl GitInput {
@@ -44,9 +135,15 @@ impl GitInput {
             rev,
             last_modified,
             url,
+            flake: true,
         }
     }
 
+    /// Whether the input is a flake; `false` for `flake = false` sources
+    pub fn is_flake(&self) -> bool {
+        self.flake
+    }
+
     pub fn name(&self) -> &str {
         self.name.as_str()
     }
@@ -75,6 +172,11 @@ impl GitInput {
         &self.repo
     }
 
+    /// The same input, hosted at `repo`
+    pub fn with_repo(self, repo: GitRepo) -> Self {
+        Self { repo, ..self }
+    }
+
     pub fn clone_url(&self) -> Result<CloneUrl, DomainError> {
         self.repo.clone_url()
     }
@@ -82,12 +184,23 @@ impl GitInput {
     pub fn lock_url(&self, rev: &GitRev) -> Result<LockUrl, DomainError> {
         self.repo.lock_url(rev)
     }
+
+    /// Repository and tracked branch, independent of the input's name
+    fn source_id(&self) -> String {
+        format!(
+            "{}#{}",
+            self.repo.identity(),
+            self.reference().unwrap_or("")
+        )
+    }
 }
 
 /// Local path input
 #[derive(Debug, Clone)]
 pub struct PathInput {
     pub name: String,
+    /// Whether the input is a flake; `false` for `flake = false` sources
+    pub flake: bool,
 }
 
 /// Other input types (tarball, file, etc.)
@@ -96,6 +209,31 @@ pub struct OtherInput {
     pub name: String,
     pub rev: Option<String>,
     pub last_modified: i64,
+    /// Whether the input is a flake; `false` for `flake = false` sources
+    pub flake: bool,
+}
+
+/// Input that is an alias for another input via `follows`
+#[derive(Debug, Clone)]
+pub struct FollowsInput {
+    pub name: String,
+    /// Input path being followed, from the root flake (e.g. `["nixpkgs"]`)
+    pub follows: Vec<String>,
+}
+
+impl FollowsInput {
+    /// Followed input path joined with `/` (e.g. `home-manager/nixpkgs`)
+    pub fn target(&self) -> String {
+        self.follows.join("/")
+    }
+
+    /// Name of the root input this aliases, when it follows one directly
+    pub fn root_target(&self) -> Option<&str> {
+        match self.follows.as_slice() {
+            [name] => Some(name),
+            _ => None,
+        }
+    }
 }
 
 /// Validated repository location. Required forge-specific data is carried by
@@ -107,6 +245,12 @@ pub enum GitRepo {
         owner: Owner,
         repo: RepoName,
     },
+    /// A GitHub Enterprise Server instance
+    GitHubEnterprise {
+        host: GitHost,
+        owner: Owner,
+        repo: RepoName,
+    },
     GitLab {
         host: GitHost,
         owner: Owner,
@@ -136,6 +280,10 @@ impl GitRepo {
         Self::GitHub { owner, repo }
     }
 
+    pub fn github_enterprise(host: GitHost, owner: Owner, repo: RepoName) -> Self {
+        Self::GitHubEnterprise { host, owner, repo }
+    }
+
     pub fn gitlab(
         host: Option<GitHost>,
         owner: Owner,
@@ -172,12 +320,65 @@ impl GitRepo {
         Self::Generic { clone_url }
     }
 
+    /// Short forge label used in reports (e.g. `github`, `gitlab`, `git`)
+    pub fn forge_name(&self) -> &'static str {
+        match self {
+            Self::GitHub { .. } | Self::GitHubEnterprise { .. } => "github",
+            Self::GitLab { .. } => "gitlab",
+            Self::SourceHut { .. } => "sourcehut",
+            Self::Codeberg { .. } => "codeberg",
+            Self::Gitea { .. } => "gitea",
+            Self::Generic { .. } => "git",
+        }
+    }
+
+    /// Host the repository lives on; `None` for generic repositories
+    pub fn host(&self) -> Option<&str> {
+        match self {
+            Self::GitHub { .. } => Some("github.com"),
+            Self::Codeberg { .. } => Some("codeberg.org"),
+            Self::GitHubEnterprise { host, .. }
+            | Self::GitLab { host, .. }
+            | Self::SourceHut { host, .. }
+            | Self::Gitea { host, .. } => Some(host.as_str()),
+            Self::Generic { .. } => None,
+        }
+    }
+
+    /// `owner/repo`; `None` for generic repositories
+    pub fn owner_repo(&self) -> Option<String> {
+        match self {
+            Self::GitHub { owner, repo }
+            | Self::Codeberg { owner, repo }
+            | Self::GitHubEnterprise { owner, repo, .. }
+            | Self::GitLab { owner, repo, .. }
+            | Self::SourceHut { owner, repo, .. }
+            | Self::Gitea { owner, repo, .. } => Some(format!("{}/{}", owner, repo)),
+            Self::Generic { .. } => None,
+        }
+    }
+
+    /// Host, owner, and repository, compared without case; the clone URL
+    /// for generic repositories
+    pub fn identity(&self) -> String {
+        let identity = match self {
+            Self::GitHub { owner, repo } => format!("github.com/{}/{}", owner, repo),
+            Self::Codeberg { owner, repo } => format!("codeberg.org/{}/{}", owner, repo),
+            Self::GitHubEnterprise { host, owner, repo }
+            | Self::GitLab { host, owner, repo }
+            | Self::SourceHut { host, owner, repo }
+            | Self::Gitea { host, owner, repo } => format!("{}/{}/{}", host, owner, repo),
+            Self::Generic { clone_url } => clone_url.as_str().trim_end_matches(".git").to_string(),
+        };
+        identity.to_lowercase()
+    }
+
     pub fn clone_url(&self) -> Result<CloneUrl, DomainError> {
         match self {
             Self::GitHub { owner, repo } => {
                 CloneUrl::new(format!("https://github.com/{}/{}.git", owner, repo))
             }
-            Self::GitLab { host, owner, repo } => {
+            Self::GitHubEnterprise { host, owner, repo } | Self::GitLab { host, owner, repo } => {
                 CloneUrl::new(format!("https://{}/{}/{}.git", host, owner, repo))
             }
             Self::SourceHut { host, owner, repo } => {
@@ -199,6 +400,9 @@ impl GitRepo {
             Self::GitHub { owner, repo } => {
                 LockUrl::new(format!("github:{}/{}/{}", owner, repo, rev))
             }
+            Self::GitHubEnterprise { host, owner, repo } => {
+                LockUrl::new(format!("github:{}/{}/{}?host={}", owner, repo, rev, host))
+            }
             Self::GitLab { host, owner, repo } if host.as_str() == "gitlab.com" => {
                 LockUrl::new(format!("gitlab:{}/{}/{}", owner, repo, rev))
             }
@@ -232,12 +436,22 @@ fn sourcehut_owner(owner: &str) -> String {
 }
 
 impl FlakeInput {
+    /// Whether the input is a nixpkgs repository (or a fork of one), whose
+    /// updates change the versions of most packages
+    pub fn is_nixpkgs(&self) -> bool {
+        match self {
+            FlakeInput::Git(g) => g.repo().identity().rsplit('/').next() == Some("nixpkgs"),
+            _ => false,
+        }
+    }
+
     /// Get the name of the input
     pub fn name(&self) -> &str {
         match self {
             FlakeInput::Git(g) => g.name(),
             FlakeInput::Path(p) => &p.name,
             FlakeInput::Other(o) => &o.name,
+            FlakeInput::Follows(f) => &f.name,
         }
     }
 
@@ -250,21 +464,63 @@ impl FlakeInput {
         }
     }
 
+    /// Get the full locked revision if available
+    pub fn locked_rev(&self) -> Option<&str> {
+        match self {
+            FlakeInput::Git(g) => Some(g.rev()),
+            FlakeInput::Other(o) => o.rev.as_deref(),
+            FlakeInput::Path(_) | FlakeInput::Follows(_) => None,
+        }
+    }
+
+    /// Whether the input is a flake. Inputs declared with `flake = false`
+    /// are plain sources without inputs of their own.
+    pub fn is_flake(&self) -> bool {
+        match self {
+            FlakeInput::Git(g) => g.is_flake(),
+            FlakeInput::Path(p) => p.flake,
+            FlakeInput::Other(o) => o.flake,
+            FlakeInput::Follows(_) => true,
+        }
+    }
+
+    /// The same input, marked as a flake or a `flake = false` source
+    pub fn with_flake(self, flake: bool) -> Self {
+        match self {
+            FlakeInput::Git(g) => FlakeInput::Git(GitInput { flake, ..g }),
+            FlakeInput::Path(p) => FlakeInput::Path(PathInput { flake, ..p }),
+            FlakeInput::Other(o) => FlakeInput::Other(OtherInput { flake, ..o }),
+            FlakeInput::Follows(f) => FlakeInput::Follows(f),
+        }
+    }
+
+    /// Get the source URL, for git inputs
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            FlakeInput::Git(g) => Some(g.url()),
+            _ => None,
+        }
+    }
+
     /// Get the last modified timestamp if available
     pub fn last_modified(&self) -> Option<i64> {
         match self {
             FlakeInput::Git(g) => Some(g.last_modified()),
-            FlakeInput::Path(_) => None,
+            FlakeInput::Path(_) | FlakeInput::Follows(_) => None,
             FlakeInput::Other(o) => Some(o.last_modified),
         }
     }
 
     /// Get a display string for the type
     pub fn type_display(&self) -> &'static str {
-        match self {
-            FlakeInput::Git(_) => "git",
-            FlakeInput::Path(_) => "path",
-            FlakeInput::Other(_) => "unsupported",
+        match (self, self.is_flake()) {
+            (FlakeInput::Git(_), true) => "git",
+            (FlakeInput::Git(_), false) => "git-src",
+            (FlakeInput::Path(_), true) => "path",
+            (FlakeInput::Path(_), false) => "path-src",
+            (FlakeInput::Other(_), true) => "unsupported",
+            (FlakeInput::Other(_), false) => "unsupported-src",
+            (FlakeInput::Follows(_), _) => "follows",
         }
     }
 }
@@ -289,6 +545,86 @@ mod tests {
         GitRev::new(value).unwrap()
     }
 
+    fn git(name: &str, repo: &str, reference: Option<&str>) -> FlakeInput {
+        FlakeInput::Git(GitInput::new(
+            InputName::new(name).unwrap(),
+            GitRepo::github(owner("NixOS"), repo_name(repo)),
+            reference.map(|r| GitRef::new(r).unwrap()),
+            rev("abc1234"),
+            0,
+            String::new(),
+        ))
+    }
+
+    fn flake(inputs: Vec<FlakeInput>) -> FlakeData {
+        FlakeData {
+            path: PathBuf::from("/tmp/flake"),
+            warnings: Vec::new(),
+            input_trees: Vec::new(),
+            inputs,
+        }
+    }
+
+    #[test]
+    fn test_followers_include_aliases_and_transitive_inputs() {
+        let tree = |name: &str, follows: Option<&str>, inputs: Vec<InputTree>| InputTree {
+            name: name.to_string(),
+            node: None,
+            follows: follows.map(|f| f.split('/').map(str::to_string).collect()),
+            source: None,
+            rev: None,
+            last_modified: None,
+            inputs,
+        };
+        let mut data = flake(Vec::new());
+        data.input_trees = vec![
+            tree("nixpkgs", None, Vec::new()),
+            tree("pkgs", Some("nixpkgs"), Vec::new()),
+            tree(
+                "home-manager",
+                None,
+                vec![
+                    tree("nixpkgs", Some("nixpkgs"), Vec::new()),
+                    tree("utils", Some("home-manager/nixpkgs"), Vec::new()),
+                ],
+            ),
+        ];
+
+        assert_eq!(data.followers("nixpkgs"), ["pkgs", "home-manager/nixpkgs"]);
+        assert!(data.followers("home-manager").is_empty());
+    }
+
+    #[test]
+    fn test_renames_match_repository_and_branch() {
+        let before = flake(vec![
+            git("nixpkgs", "nixpkgs", None),
+            git("stable", "nixpkgs", Some("nixos-24.05")),
+            git("hm", "home-manager", None),
+        ]);
+        let after = flake(vec![
+            git("nixpkgs", "nixpkgs", None),
+            git("nixpkgs-stable", "NixPkgs", Some("nixos-24.05")),
+            git("home-manager", "home-manager", None),
+        ]);
+        let mut renames = after.renames_since(&before);
+        renames.sort();
+        assert_eq!(
+            renames,
+            [
+                ("hm".to_string(), "home-manager".to_string()),
+                ("stable".to_string(), "nixpkgs-stable".to_string()),
+            ]
+        );
+
+        // Two new names for one source can't be told apart
+        let split = flake(vec![
+            git("nixpkgs", "nixpkgs", None),
+            git("a", "home-manager", None),
+            git("b", "home-manager", None),
+        ]);
+        assert!(split.renames_since(&before).is_empty());
+    }
+
     #[test]
     fn test_git_repo_urls() {
         assert_eq!(
@@ -299,6 +635,14 @@ mod tests {
             "https://github.com/NixOS/nixpkgs.git"
         );
 
+        assert_eq!(
+            GitRepo::github_enterprise(host("ghe.example.com"), owner("ops"), repo_name("tools"))
+                .lock_url(&rev("abc1234"))
+                .unwrap()
+                .as_str(),
+            "github:ops/tools/abc1234?host=ghe.example.com"
+        );
+
         assert_eq!(
             GitRepo::gitlab(
                 Some(host("gitlab.gnome.org")),
@@ -360,6 +704,7 @@ mod tests {
             name: "archive".to_string(),
             rev: Some("abc".to_string()),
             last_modified: 0,
+            flake: true,
         });
         assert_eq!(short.short_rev(), Some("abc"));
 
@@ -367,11 +712,13 @@ mod tests {
             name: "archive".to_string(),
             rev: None,
             last_modified: 0,
+            flake: true,
         });
         assert_eq!(empty.short_rev(), None);
 
         let path = FlakeInput::Path(PathInput {
             name: "local".to_string(),
+            flake: true,
         });
         assert_eq!(path.short_rev(), None);
     }
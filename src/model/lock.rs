@@ -0,0 +1,279 @@
+//! Nodes of a `flake.lock`, including transitive inputs
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One locked node of a `flake.lock`.
+///
+/// Unlike [`FlakeInput`](super::FlakeInput), which only covers the root
+/// flake's own inputs, nodes cover the whole dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockNode {
+    /// Node key in `flake.lock` (e.g. `nixpkgs_2`)
+    pub id: String,
+    /// Human-readable name: the repository name when known, else the key
+    pub name: String,
+    /// Fetcher type (`github`, `git`, `path`, `tarball`, ...)
+    pub source_type: String,
+    /// Where the source can be fetched from, when it has a location
+    pub source_url: Option<String>,
+    pub rev: Option<String>,
+    /// SRI hash of the unpacked source (`sha256-...`)
+    pub nar_hash: Option<String>,
+    pub last_modified: Option<i64>,
+    /// Whether the root flake declares this node as an input
+    pub direct: bool,
+    /// Keys of the nodes this node's inputs resolve to, after `follows`
+    pub depends_on: Vec<String>,
+}
+
+/// An input as declared by the flake or by one of its inputs, with the
+/// inputs it declares in turn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputTree {
+    /// Name the declaring flake gives the input
+    pub name: String,
+    /// Node key in `flake.lock` the input resolves to, if any
+    pub node: Option<String>,
+    /// Path of the input this one follows (e.g. `["nixpkgs"]`)
+    pub follows: Option<Vec<String>>,
+    /// Where the locked source comes from (e.g.
+    /// `https://github.com/NixOS/nixpkgs`)
+    pub source: Option<String>,
+    pub rev: Option<String>,
+    pub last_modified: Option<i64>,
+    /// Inputs this one declares. Empty for inputs that follow another,
+    /// whose inputs are listed where they are locked.
+    pub inputs: Vec<InputTree>,
+}
+
+/// A source locked more than once across the lock graph, with the
+/// `follows` lines that would leave a single copy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateInput {
+    /// Where the copies come from
+    pub source: String,
+    /// Distinct `flake.lock` nodes locking the source
+    pub copies: usize,
+    /// Input the transitive copies should follow: a root input when the
+    /// flake declares one, else the shallowest copy (e.g. `hyprland/nixpkgs`)
+    pub target: String,
+    /// Lines to add to `flake.nix`, such as
+    /// `inputs.home-manager.inputs.nixpkgs.follows = "nixpkgs";`
+    pub suggestions: Vec<String>,
+}
+
+/// One line of an [`InputTree`] drawn as an indented tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeLine<'a> {
+    /// Guides drawn before the name (e.g. `│  └─ `)
+    pub prefix: String,
+    /// 0 for the flake's own inputs
+    pub depth: usize,
+    pub tree: &'a InputTree,
+}
+
+impl InputTree {
+    /// Whether the input follows another instead of having a lock of its own
+    pub fn is_follows(&self) -> bool {
+        self.follows.is_some()
+    }
+
+    /// Inputs declared below this one, at any depth
+    pub fn transitive(&self) -> impl Iterator<Item = &InputTree> {
+        let mut stack: Vec<&InputTree> = self.inputs.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let tree = stack.pop()?;
+            stack.extend(tree.inputs.iter().rev());
+            Some(tree)
+        })
+    }
+
+    /// `trees` and everything below them, depth first, one line each
+    pub fn lines(trees: &[InputTree]) -> Vec<TreeLine<'_>> {
+        fn walk<'a>(
+            trees: &'a [InputTree],
+            guides: &str,
+            depth: usize,
+            out: &mut Vec<TreeLine<'a>>,
+        ) {
+            for (idx, tree) in trees.iter().enumerate() {
+                let last = idx + 1 == trees.len();
+                let (branch, guide) = match (depth, last) {
+                    (0, _) => ("", ""),
+                    (_, false) => ("├─ ", "│  "),
+                    (_, true) => ("└─ ", "   "),
+                };
+                out.push(TreeLine {
+                    prefix: format!("{}{}", guides, branch),
+                    depth,
+                    tree,
+                });
+                walk(
+                    &tree.inputs,
+                    &format!("{}{}", guides, guide),
+                    depth + 1,
+                    out,
+                );
+            }
+        }
+
+        let mut out = Vec::new();
+        walk(trees, "", 0, &mut out);
+        out
+    }
+
+    /// Sources that `trees` lock more than once, by source
+    pub fn duplicates(trees: &[InputTree]) -> Vec<DuplicateInput> {
+        /// Declarations with a lock of their own, with the input names
+        /// leading to them from the root
+        fn walk<'a>(
+            trees: &'a [InputTree],
+            path: &mut Vec<&'a str>,
+            out: &mut Vec<(Vec<&'a str>, &'a InputTree)>,
+        ) {
+            for tree in trees.iter().filter(|tree| !tree.is_follows()) {
+                path.push(&tree.name);
+                out.push((path.clone(), tree));
+                walk(&tree.inputs, path, out);
+                path.pop();
+            }
+        }
+
+        let mut declarations = Vec::new();
+        walk(trees, &mut Vec::new(), &mut declarations);
+
+        let mut by_source: BTreeMap<String, Vec<(Vec<&str>, &InputTree)>> = BTreeMap::new();
+        for (path, tree) in declarations {
+            if let (Some(source), Some(_)) = (&tree.source, &tree.node) {
+                by_source
+                    .entry(source.to_lowercase())
+                    .or_default()
+                    .push((path, tree));
+            }
+        }
+
+        by_source
+            .into_values()
+            .filter_map(|mut copies| {
+                let nodes: BTreeSet<&str> = copies
+                    .iter()
+                    .filter_map(|(_, tree)| tree.node.as_deref())
+                    .collect();
+                if nodes.len() < 2 {
+                    return None;
+                }
+                copies.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+                let (target_path, target) = &copies[0];
+                // Root inputs locking the same source twice (say, two
+                // nixpkgs branches) do so on purpose.
+                let suggestions: Vec<String> = copies[1..]
+                    .iter()
+                    .filter(|(path, tree)| path.len() > 1 && tree.node != target.node)
+                    .map(|(path, _)| {
+                        let attrs: Vec<String> = path.iter().map(|name| nix_attr(name)).collect();
+                        format!(
+                            "inputs.{}.follows = \"{}\";",
+                            attrs.join(".inputs."),
+                            target_path.join("/")
+                        )
+                    })
+                    .collect();
+                if suggestions.is_empty() {
+                    return None;
+                }
+                Some(DuplicateInput {
+                    source: target.source.clone().unwrap_or_default(),
+                    copies: nodes.len(),
+                    target: target_path.join("/"),
+                    suggestions,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `name` as a Nix attribute name, quoted unless it is a plain identifier
+fn nix_attr(name: &str) -> String {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\''));
+    if plain {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+/// A commit of the flake's own repository that changed its `flake.lock`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockRevision {
+    pub sha: String,
+    /// First line of the commit message
+    pub summary: String,
+    pub author: String,
+    pub date: i64,
+    /// How each input's locked revision moved in this commit
+    pub changes: Vec<LockChange>,
+}
+
+impl LockRevision {
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..7.min(self.sha.len())]
+    }
+}
+
+/// How one root input's locked revision moved between two lock files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockChange {
+    pub input: String,
+    pub kind: LockChangeKind,
+}
+
+/// Short revisions of an input before and after a lock change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockChangeKind {
+    Added {
+        rev: Option<String>,
+    },
+    Removed {
+        rev: Option<String>,
+    },
+    Changed {
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+impl std::fmt::Display for LockChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            LockChangeKind::Added { .. } => write!(f, "+{}", self.input),
+            LockChangeKind::Removed { .. } => write!(f, "-{}", self.input),
+            LockChangeKind::Changed { from, to } => write!(
+                f,
+                "{} {} → {}",
+                self.input,
+                from.as_deref().unwrap_or("?"),
+                to.as_deref().unwrap_or("?")
+            ),
+        }
+    }
+}
+
+/// A `flake.lock` problem confined to one input, which is still listed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockWarning {
+    /// Name of the affected root input
+    pub input: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for LockWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.input, self.message)
+    }
+}
@@ -1,11 +1,36 @@
+mod activity;
+mod closure;
 mod commit;
+mod digest;
 mod domain;
+mod filter;
 mod flake;
+mod lock;
+mod pull_request;
+mod sort;
 mod status;
+mod template;
 
-pub use commit::{ChangelogData, Commit};
+pub use activity::{ActivityEntry, ActivityLog};
+pub use closure::{ClosureDiff, PackageChange};
+pub use commit::{
+    AuthorCount, ChangelogData, ChangelogSummary, Commit, CommitDetail, DiffStats, FileChange,
+    PathFilter, Release, SignatureStatus, Tag,
+};
+pub use digest::{AppliedUpdate, AvailableUpdate, Digest};
 pub use domain::{
     CloneUrl, DomainError, GitHost, GitRef, GitRev, InputName, LockUrl, Owner, RepoName,
 };
-pub use flake::{FlakeData, FlakeInput, GitInput, GitRepo, OtherInput, PathInput};
-pub use status::{StatusLevel, StatusMessage, UpdateStatus};
+pub(crate) use filter::glob_match;
+pub use filter::{
+    ChangelogPath, ChangelogPaths, FilterError, FilterField, InputFilter, InputGlobs,
+};
+pub use flake::{FlakeData, FlakeInput, FollowsInput, GitInput, GitRepo, OtherInput, PathInput};
+pub use lock::{
+    DuplicateInput, InputTree, LockChange, LockChangeKind, LockNode, LockRevision, LockWarning,
+    TreeLine,
+};
+pub use pull_request::PullRequest;
+pub use sort::SortOrder;
+pub use status::{CheckInfo, CommitCount, StatusLevel, StatusMessage, Toasts, UpdateStatus};
+pub use template::FlakeTemplate;
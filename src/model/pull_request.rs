@@ -0,0 +1,154 @@
+//! Pull requests proposing a flake.lock update
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::{ChangelogData, Commit, LockChange, LockChangeKind};
+
+/// Commits listed under each input in a pull request body
+const MAX_COMMITS_PER_INPUT: usize = 20;
+
+/// A pull request to open for a branch holding a lock update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequest {
+    pub branch: String,
+    pub title: String,
+    pub body: String,
+}
+
+impl PullRequest {
+    /// Pull request for `branch`, titled with the first line of the commit
+    /// `message`. The body lists how each input moved and, for inputs in
+    /// `changelogs`, the commits the update pulls in.
+    pub fn new(
+        branch: String,
+        message: &str,
+        changes: &[LockChange],
+        changelogs: &HashMap<String, ChangelogData>,
+    ) -> Self {
+        Self {
+            branch,
+            title: message.lines().next().unwrap_or_default().to_string(),
+            body: body(changes, changelogs),
+        }
+    }
+}
+
+fn body(changes: &[LockChange], changelogs: &HashMap<String, ChangelogData>) -> String {
+    let mut out = String::new();
+    if changes.is_empty() {
+        let _ = writeln!(out, "Updates transitive inputs in `flake.lock`.");
+        return out;
+    }
+    let _ = writeln!(out, "Updates `flake.lock`:\n");
+    let rev = |rev: &Option<String>| rev.clone().unwrap_or_else(|| "?".to_string());
+    for change in changes {
+        match &change.kind {
+            LockChangeKind::Added { rev: added } => {
+                let _ = writeln!(out, "- **{}** added at `{}`", change.input, rev(added));
+            }
+            LockChangeKind::Removed { .. } => {
+                let _ = writeln!(out, "- **{}** removed", change.input);
+            }
+            LockChangeKind::Changed { from, to } => {
+                let commits = changelogs
+                    .get(&change.input)
+                    .and_then(|data| commits_between(data, from.as_deref()?, to.as_deref()?));
+                let _ = write!(
+                    out,
+                    "- **{}** `{}` → `{}`",
+                    change.input,
+                    rev(from),
+                    rev(to)
+                );
+                let Some(commits) = commits else {
+                    let _ = writeln!(out);
+                    continue;
+                };
+                let _ = writeln!(out, ", {} commit(s)", commits.len());
+                for commit in commits.iter().take(MAX_COMMITS_PER_INPUT) {
+                    let subject = commit.message.lines().next().unwrap_or_default();
+                    let _ = writeln!(out, "  - `{}` {}", commit.short_sha(), subject);
+                }
+                if commits.len() > MAX_COMMITS_PER_INPUT {
+                    let _ = writeln!(
+                        out,
+                        "  - … and {} more",
+                        commits.len() - MAX_COMMITS_PER_INPUT
+                    );
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Commits after `from` up to and including `to`, newest first, when the
+/// changelog has both
+fn commits_between<'a>(data: &'a ChangelogData, from: &str, to: &str) -> Option<&'a [Commit]> {
+    let position = |rev: &str| data.commits.iter().position(|c| c.sha.starts_with(rev));
+    let (newest, oldest) = (position(to)?, position(from)?);
+    data.commits.get(newest..oldest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn commit(sha: &str, message: &str) -> Commit {
+        Commit {
+            sha: sha.repeat(40),
+            message: message.to_string(),
+            author: "alice".to_string(),
+            date: Utc::now(),
+            signature: Default::default(),
+        }
+    }
+
+    #[test]
+    fn lists_fetched_commits_between_the_locked_revisions() {
+        let commits = vec![
+            commit("d", "newest"),
+            commit("c", "fix: things (#12)"),
+            commit("b", "feat: stuff"),
+            commit("a", "locked"),
+        ];
+        let changelogs = HashMap::from([(
+            "nixpkgs".to_string(),
+            ChangelogData::new(commits, Some(3)).unwrap(),
+        )]);
+        let changes = [
+            LockChange {
+                input: "nixpkgs".to_string(),
+                kind: LockChangeKind::Changed {
+                    from: Some("aaaaaaa".to_string()),
+                    to: Some("ccccccc".to_string()),
+                },
+            },
+            LockChange {
+                input: "utils".to_string(),
+                kind: LockChangeKind::Changed {
+                    from: Some("1111111".to_string()),
+                    to: Some("2222222".to_string()),
+                },
+            },
+        ];
+
+        let pr = PullRequest::new(
+            "melt/update".to_string(),
+            "flake.lock: Update nixpkgs, utils\n\n- nixpkgs",
+            &changes,
+            &changelogs,
+        );
+        assert_eq!(pr.title, "flake.lock: Update nixpkgs, utils");
+        assert_eq!(
+            pr.body,
+            "Updates `flake.lock`:\n\n\
+             - **nixpkgs** `aaaaaaa` → `ccccccc`, 2 commit(s)\n\
+             \x20 - `ccccccc` fix: things (#12)\n\
+             \x20 - `bbbbbbb` feat: stuff\n\
+             - **utils** `1111111` → `2222222`\n"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Ordering of flake inputs in the list view
+
+use std::cmp::Reverse;
+
+use serde::Deserialize;
+
+use super::FlakeInput;
+
+/// Order of the input list, chosen with `sort` in the config file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortOrder {
+    /// By name, ignoring case
+    #[default]
+    Alphabetical,
+    /// Most commits behind upstream first, once update checks report
+    MostBehind,
+    /// Oldest locked revision first; inputs without a date come last
+    OldestUpdated,
+}
+
+impl SortOrder {
+    /// Every order, in the order `:sort` cycles through them
+    pub const ALL: [SortOrder; 3] = [
+        SortOrder::Alphabetical,
+        SortOrder::MostBehind,
+        SortOrder::OldestUpdated,
+    ];
+
+    /// Name used in the config file and by `:sort`
+    pub fn name(self) -> &'static str {
+        match self {
+            SortOrder::Alphabetical => "alphabetical",
+            SortOrder::MostBehind => "most-behind",
+            SortOrder::OldestUpdated => "oldest-updated",
+        }
+    }
+
+    /// The order called `name`
+    pub fn named(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|order| order.name() == name)
+    }
+
+    /// The order after this one
+    pub fn next(self) -> Self {
+        let idx = Self::ALL
+            .iter()
+            .position(|&order| order == self)
+            .unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    /// Sort `inputs`, breaking ties by name.
+    ///
+    /// `behind` gives the number of commits an input is behind upstream,
+    /// 0 when unknown.
+    pub fn sort(self, inputs: &mut [FlakeInput], behind: impl Fn(&FlakeInput) -> usize) {
+        inputs.sort_by_key(|input| input.name().to_lowercase());
+        match self {
+            SortOrder::Alphabetical => {}
+            SortOrder::MostBehind => inputs.sort_by_key(|input| Reverse(behind(input))),
+            SortOrder::OldestUpdated => {
+                inputs.sort_by_key(|input| input.last_modified().unwrap_or(i64::MAX))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{FollowsInput, GitInput, GitRepo, GitRev, InputName, Owner, RepoName};
+
+    fn git(name: &str, last_modified: i64) -> FlakeInput {
+        FlakeInput::Git(GitInput::new(
+            InputName::new(name).unwrap(),
+            GitRepo::github(Owner::new("o").unwrap(), RepoName::new(name).unwrap()),
+            None,
+            GitRev::new("abc1234").unwrap(),
+            last_modified,
+            String::new(),
+        ))
+    }
+
+    fn names(inputs: &[FlakeInput]) -> Vec<&str> {
+        inputs.iter().map(FlakeInput::name).collect()
+    }
+
+    #[test]
+    fn names_round_trip_and_cycle() {
+        for order in SortOrder::ALL {
+            assert_eq!(SortOrder::named(order.name()), Some(order));
+        }
+        assert_eq!(SortOrder::named("newest"), None);
+        assert_eq!(SortOrder::Alphabetical.next(), SortOrder::MostBehind);
+        assert_eq!(SortOrder::OldestUpdated.next(), SortOrder::Alphabetical);
+    }
+
+    #[test]
+    fn sorts_by_each_order() {
+        let mut inputs = vec![
+            git("nixpkgs", 300),
+            FlakeInput::Follows(FollowsInput {
+                name: "alias".to_string(),
+                follows: vec!["nixpkgs".to_string()],
+            }),
+            git("Home-manager", 100),
+            git("flake-utils", 200),
+        ];
+        let behind = |input: &FlakeInput| match input.name() {
+            "nixpkgs" => 40,
+            "flake-utils" => 3,
+            _ => 0,
+        };
+
+        SortOrder::Alphabetical.sort(&mut inputs, behind);
+        assert_eq!(
+            names(&inputs),
+            ["alias", "flake-utils", "Home-manager", "nixpkgs"]
+        );
+
+        SortOrder::MostBehind.sort(&mut inputs, behind);
+        assert_eq!(
+            names(&inputs),
+            ["nixpkgs", "flake-utils", "alias", "Home-manager"]
+        );
+
+        SortOrder::OldestUpdated.sort(&mut inputs, behind);
+        assert_eq!(
+            names(&inputs),
+            ["Home-manager", "flake-utils", "nixpkgs", "alias"]
+        );
+    }
+}
@@ -1,5 +1,59 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
+use crate::util::format::{format_count, group_digits, prefs};
+
+/// Number of commits, either exact or a lower bound when a fetch limit was hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitCount {
+    /// The exact number of commits
+    Exact(usize),
+    /// At least this many commits; counting stopped at a fetch limit
+    AtLeast(usize),
+}
+
+impl CommitCount {
+    /// Count `found` commits, treating `found >= limit` as a lower bound
+    pub fn from_limited(found: usize, limit: usize) -> Self {
+        if found >= limit {
+            CommitCount::AtLeast(found)
+        } else {
+            CommitCount::Exact(found)
+        }
+    }
+
+    /// The counted number of commits (a lower bound for `AtLeast`)
+    pub fn value(self) -> usize {
+        match self {
+            CommitCount::Exact(n) | CommitCount::AtLeast(n) => n,
+        }
+    }
+
+    pub fn is_exact(self) -> bool {
+        matches!(self, CommitCount::Exact(_))
+    }
+}
+
+/// How an update check was answered
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckInfo {
+    /// Time the check took
+    pub elapsed: Duration,
+    /// Cached clone the check was read from, when no forge API answered
+    pub clone_path: Option<PathBuf>,
+}
+
+impl fmt::Display for CommitCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommitCount::Exact(n) => write!(f, "{}", format_count(*n)),
+            CommitCount::AtLeast(n) => write!(f, "{}+", format_count(*n)),
+        }
+    }
+}
+
 /// Status of update check for an input
 #[derive(Debug, Clone, Default)]
 pub enum UpdateStatus {
@@ -8,26 +62,65 @@ pub enum UpdateStatus {
     Unknown,
     /// Currently checking for updates
     Checking,
+    /// Selected for an update that has not reached it yet
+    Queued,
     /// Currently being updated
     Updating,
+    /// Updated by the update under way
+    Updated,
     /// Input is up to date with remote
     UpToDate,
-    /// Input is behind remote by N commits
-    Behind(usize),
+    /// Input is behind remote by N commits (or at least N when truncated)
+    Behind(CommitCount),
     /// Error occurred while checking
     Error(String),
+    /// Held back by a forge rate limit; checked again at the given time
+    RateLimited(Instant),
 }
 
 impl UpdateStatus {
     /// Get display string for the status
     pub fn display(&self) -> String {
+        self.display_with(prefs().thousands_separator)
+    }
+
+    fn display_with(&self, separator: Option<char>) -> String {
         match self {
             UpdateStatus::Unknown => "-".to_string(),
             UpdateStatus::Checking => "...".to_string(),
+            UpdateStatus::Queued => "queued".to_string(),
             UpdateStatus::Updating => "...".to_string(),
+            UpdateStatus::Updated => "done".to_string(),
             UpdateStatus::UpToDate => "ok".to_string(),
-            UpdateStatus::Behind(n) => format!("+{}", n),
+            UpdateStatus::Behind(CommitCount::Exact(n)) => {
+                format!("+{}", group_digits(*n, separator))
+            }
+            UpdateStatus::Behind(CommitCount::AtLeast(n)) => {
+                format!("{}+", group_digits(*n, separator))
+            }
             UpdateStatus::Error(_) => "?".to_string(),
+            UpdateStatus::RateLimited(_) => "wait".to_string(),
+        }
+    }
+
+    /// The status spelled out, for announcements
+    pub fn describe(&self) -> String {
+        match self {
+            UpdateStatus::Unknown => "not checked".to_string(),
+            UpdateStatus::Checking => "checking".to_string(),
+            UpdateStatus::Queued => "waiting to update".to_string(),
+            UpdateStatus::Updating => "updating".to_string(),
+            UpdateStatus::Updated => "updated".to_string(),
+            UpdateStatus::UpToDate => "up to date".to_string(),
+            UpdateStatus::Behind(CommitCount::Exact(1)) => "1 commit behind".to_string(),
+            UpdateStatus::Behind(CommitCount::Exact(n)) => {
+                format!("{} commits behind", format_count(*n))
+            }
+            UpdateStatus::Behind(CommitCount::AtLeast(n)) => {
+                format!("at least {} commits behind", format_count(*n))
+            }
+            UpdateStatus::Error(err) => format!("check failed: {}", err),
+            UpdateStatus::RateLimited(_) => "rate limited, retrying later".to_string(),
         }
     }
 }
@@ -38,6 +131,8 @@ pub struct StatusMessage {
     pub text: String,
     pub level: StatusLevel,
     pub expires: Option<Instant>,
+    /// Cleared by the next key press instead of expiring
+    pub dismissible: bool,
 }
 
 /// Level of status message (affects styling)
@@ -56,6 +151,7 @@ impl StatusMessage {
             text: text.into(),
             level: StatusLevel::Info,
             expires: None,
+            dismissible: false,
         }
     }
 
@@ -65,6 +161,7 @@ impl StatusMessage {
             text: text.into(),
             level: StatusLevel::Success,
             expires: Some(Instant::now() + Duration::from_secs(3)),
+            dismissible: false,
         }
     }
 
@@ -74,6 +171,7 @@ impl StatusMessage {
             text: text.into(),
             level: StatusLevel::Error,
             expires: Some(Instant::now() + Duration::from_secs(5)),
+            dismissible: false,
         }
     }
 
@@ -83,6 +181,17 @@ impl StatusMessage {
             text: text.into(),
             level: StatusLevel::Warning,
             expires: Some(Instant::now() + Duration::from_secs(4)),
+            dismissible: false,
+        }
+    }
+
+    /// Create an advisory warning that stays until the next key press
+    pub fn hint(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            level: StatusLevel::Warning,
+            expires: None,
+            dismissible: true,
         }
     }
 
@@ -92,20 +201,157 @@ impl StatusMessage {
     }
 }
 
+/// Most status messages shown at once; older ones make way for new ones
+const MAX_TOASTS: usize = 4;
+
+/// Status messages stacked oldest first, so a new message does not hide
+/// one about something else before it expires.
+///
+/// Info messages report what is under way and never expire, so any newer
+/// message, which usually reports how it went, replaces them.
+#[derive(Debug, Default)]
+pub struct Toasts {
+    messages: VecDeque<StatusMessage>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, message: StatusMessage) {
+        self.messages
+            .retain(|shown| shown.text != message.text && shown.level != StatusLevel::Info);
+        if self.messages.len() == MAX_TOASTS {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message);
+    }
+
+    /// Drop the info message, once what it reported is over
+    pub fn clear_info(&mut self) {
+        self.messages.retain(|msg| msg.level != StatusLevel::Info);
+    }
+
+    /// Drop expired messages
+    pub fn expire(&mut self) {
+        self.messages.retain(|msg| !msg.is_expired());
+    }
+
+    /// Drop messages that stay until the next key press
+    pub fn dismiss(&mut self) {
+        self.messages.retain(|msg| !msg.dismissible);
+    }
+
+    /// The newest message, shown in the help bar
+    pub fn latest(&self) -> Option<&StatusMessage> {
+        self.messages.back()
+    }
+
+    /// Messages older than the newest, oldest first
+    pub fn earlier(&self) -> impl DoubleEndedIterator<Item = &StatusMessage> {
+        self.messages
+            .iter()
+            .take(self.messages.len().saturating_sub(1))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn behind_counts_use_the_thousands_separator() {
+        let behind = |count| UpdateStatus::Behind(count).display_with(Some(','));
+        assert_eq!(behind(CommitCount::Exact(1234)), "+1,234");
+        assert_eq!(behind(CommitCount::AtLeast(5000)), "5,000+");
+        assert_eq!(
+            UpdateStatus::Behind(CommitCount::Exact(1234)).display_with(None),
+            "+1234"
+        );
+    }
+
+    #[test]
+    fn toasts_keep_earlier_messages_but_only_the_latest_info() {
+        let mut toasts = Toasts::default();
+        toasts.push(StatusMessage::error("Failed to load tags"));
+        toasts.push(StatusMessage::info("Updating 2 input(s)..."));
+        toasts.push(StatusMessage::info("2/2: updating utils…"));
+        assert_eq!(
+            toasts.latest().map(|msg| msg.text.as_str()),
+            Some("2/2: updating utils…")
+        );
+        toasts.push(StatusMessage::success("Update complete"));
+        let texts = |toasts: &Toasts| -> Vec<String> {
+            toasts
+                .earlier()
+                .chain(toasts.latest())
+                .map(|msg| msg.text.clone())
+                .collect()
+        };
+        assert_eq!(texts(&toasts), ["Failed to load tags", "Update complete"]);
+
+        toasts.push(StatusMessage::info("Refreshing..."));
+        toasts.clear_info();
+        toasts.push(StatusMessage::hint("Set a token"));
+        toasts.push(StatusMessage::success("Update complete"));
+        assert_eq!(
+            texts(&toasts),
+            ["Failed to load tags", "Set a token", "Update complete"]
+        );
+        toasts.dismiss();
+        assert_eq!(texts(&toasts), ["Failed to load tags", "Update complete"]);
+
+        for n in 0..5 {
+            toasts.push(StatusMessage::error(format!("error {}", n)));
+        }
+        assert_eq!(texts(&toasts), ["error 1", "error 2", "error 3", "error 4"]);
+    }
+
     #[test]
     fn test_update_status_display() {
         assert_eq!(UpdateStatus::Unknown.display(), "-");
         assert_eq!(UpdateStatus::Checking.display(), "...");
+        assert_eq!(UpdateStatus::Queued.display(), "queued");
         assert_eq!(UpdateStatus::Updating.display(), "...");
+        assert_eq!(UpdateStatus::Updated.display(), "done");
         assert_eq!(UpdateStatus::UpToDate.display(), "ok");
-        assert_eq!(UpdateStatus::Behind(12).display(), "+12");
+        assert_eq!(
+            UpdateStatus::Behind(CommitCount::Exact(12)).display(),
+            "+12"
+        );
+        assert_eq!(
+            UpdateStatus::Behind(CommitCount::AtLeast(500)).display(),
+            "500+"
+        );
         assert_eq!(UpdateStatus::Error("failed".to_string()).display(), "?");
     }
 
+    #[test]
+    fn test_update_status_describe() {
+        assert_eq!(
+            UpdateStatus::Behind(CommitCount::Exact(42)).describe(),
+            "42 commits behind"
+        );
+        assert_eq!(
+            UpdateStatus::Behind(CommitCount::Exact(1)).describe(),
+            "1 commit behind"
+        );
+        assert_eq!(
+            UpdateStatus::Behind(CommitCount::AtLeast(500)).describe(),
+            "at least 500 commits behind"
+        );
+        assert_eq!(UpdateStatus::UpToDate.describe(), "up to date");
+    }
+
+    #[test]
+    fn test_commit_count_from_limited() {
+        assert_eq!(CommitCount::from_limited(12, 500), CommitCount::Exact(12));
+        assert_eq!(
+            CommitCount::from_limited(500, 500),
+            CommitCount::AtLeast(500)
+        );
+        assert!(CommitCount::Exact(3).is_exact());
+        assert_eq!(CommitCount::AtLeast(100).value(), 100);
+        assert_eq!(CommitCount::AtLeast(100).to_string(), "100+");
+    }
+
     #[test]
     fn test_status_message_constructors() {
         let info = StatusMessage::info("loading");
@@ -133,6 +379,7 @@ mod tests {
             text: "old".to_string(),
             level: StatusLevel::Info,
             expires: Some(Instant::now() - Duration::from_secs(1)),
+            dismissible: false,
         };
         assert!(expired.is_expired());
 
@@ -140,6 +387,7 @@ mod tests {
             text: "new".to_string(),
             level: StatusLevel::Info,
             expires: Some(Instant::now() + Duration::from_secs(1)),
+            dismissible: false,
         };
         assert!(!active.is_expired());
     }
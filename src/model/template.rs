@@ -0,0 +1,33 @@
+/// A flake template offered by a template source such as the `templates` registry entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlakeTemplate {
+    /// Template attribute name (e.g. `rust`)
+    pub name: String,
+    /// Human-readable description from the template definition
+    pub description: String,
+    /// Full reference passed to `nix flake init -t` (e.g. `templates#rust`)
+    pub reference: String,
+}
+
+impl FlakeTemplate {
+    pub fn new(source: &str, name: impl Into<String>, description: impl Into<String>) -> Self {
+        let name = name.into();
+        Self {
+            reference: format!("{}#{}", source, name),
+            name,
+            description: description.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_reference() {
+        let template = FlakeTemplate::new("templates", "rust", "Rust template");
+        assert_eq!(template.reference, "templates#rust");
+        assert_eq!(template.name, "rust");
+    }
+}
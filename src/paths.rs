@@ -0,0 +1,111 @@
+//! Where melt keeps files on disk
+//!
+//! Files are split by how disposable they are, following the XDG base
+//! directory spec:
+//! - cache (`$XDG_CACHE_HOME/melt`): cached git clones and API responses,
+//!   safe to delete
+//! - state (`$XDG_STATE_HOME/melt`): logs and other data that should survive
+//!   restarts but is not worth backing up
+//! - config (`$XDG_CONFIG_HOME/melt`): user configuration
+//!
+//! The XDG variables are honored on every platform when set to an absolute
+//! path; otherwise the platform default from the `dirs` crate is used.
+
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+const APP_DIR: &str = "melt";
+
+/// Directory for disposable caches
+pub fn cache_dir() -> PathBuf {
+    resolve(std::env::var_os("XDG_CACHE_HOME"), dirs::cache_dir()).join(APP_DIR)
+}
+
+/// Directory for persistent state such as logs
+pub fn state_dir() -> PathBuf {
+    let fallback = dirs::state_dir().or_else(dirs::data_local_dir);
+    resolve(std::env::var_os("XDG_STATE_HOME"), fallback).join(APP_DIR)
+}
+
+/// Directory for user configuration
+pub fn config_dir() -> PathBuf {
+    resolve(std::env::var_os("XDG_CONFIG_HOME"), dirs::config_dir()).join(APP_DIR)
+}
+
+/// Path of the config file
+pub fn config_file() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+/// Directory cached git clones are kept in
+pub fn git_cache_dir() -> PathBuf {
+    cache_dir().join("git")
+}
+
+/// Directory forge API responses are cached in for conditional requests
+pub fn api_cache_dir() -> PathBuf {
+    cache_dir().join("api")
+}
+
+/// Directory `flake.lock` is copied to before updates, for undoing them
+pub fn lock_backup_dir() -> PathBuf {
+    cache_dir().join("lock-backups")
+}
+
+/// Path of the log file
+pub fn log_file() -> PathBuf {
+    state_dir().join("melt.log")
+}
+
+/// Pick the XDG variable when it is an absolute path, else the platform default.
+///
+/// The spec says relative paths in XDG variables are invalid and should be
+/// ignored. Without either, paths are relative to the working directory.
+fn resolve(xdg: Option<OsString>, fallback: Option<PathBuf>) -> PathBuf {
+    xdg.map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or(fallback)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Render every path melt uses, one per line, for `melt paths`
+pub fn render() -> String {
+    let mut out = String::new();
+    for (label, path) in [
+        ("config", config_file()),
+        ("state", state_dir()),
+        ("log", log_file()),
+        ("cache", cache_dir()),
+        ("git cache", git_cache_dir()),
+        ("api cache", api_cache_dir()),
+        ("lock backups", lock_backup_dir()),
+    ] {
+        let _ = writeln!(out, "{:<12}  {}", label, path.display());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_prefers_absolute_xdg_paths() {
+        let fallback = Some(PathBuf::from("/home/me/.local/state"));
+
+        assert_eq!(
+            resolve(Some("/xdg/state".into()), fallback.clone()),
+            PathBuf::from("/xdg/state")
+        );
+        assert_eq!(
+            resolve(Some("relative/state".into()), fallback.clone()),
+            PathBuf::from("/home/me/.local/state")
+        );
+        assert_eq!(
+            resolve(None, fallback),
+            PathBuf::from("/home/me/.local/state")
+        );
+        assert_eq!(resolve(None, None), PathBuf::from("."));
+    }
+}
@@ -0,0 +1,127 @@
+//! Discovery of flakes nested inside a repository
+//!
+//! Monorepos often keep several flakes side by side (`./`, `./templates/*`,
+//! `./subprojects/*`). Discovery walks the directory tree below a root and
+//! returns every directory that contains a `flake.nix`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How many directory levels below the root are scanned.
+const MAX_DEPTH: usize = 3;
+
+/// Directory names that never contain flakes worth offering.
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "result", "vendor"];
+
+/// Find all flake directories below `root`, including `root` itself.
+///
+/// The root flake (if any) comes first, followed by nested flakes sorted by
+/// path. Hidden directories, build outputs, and symlinks are not traversed.
+pub fn discover_flakes(root: &Path) -> Vec<PathBuf> {
+    let root = discovery_root(root);
+
+    let mut nested = Vec::new();
+    walk(&root, 1, &mut nested);
+    nested.sort();
+
+    let mut flakes = Vec::with_capacity(nested.len() + 1);
+    if root.join("flake.nix").is_file() {
+        flakes.push(root);
+    }
+    flakes.extend(nested);
+    flakes
+}
+
+/// Resolve the directory discovery starts from.
+///
+/// Accepts either a directory or a path to a `flake.nix` file.
+pub fn discovery_root(path: &Path) -> PathBuf {
+    let dir = if path.ends_with("flake.nix") {
+        path.parent().unwrap_or(path)
+    } else {
+        path
+    };
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+fn walk(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if !file_type.is_dir() {
+            continue;
+        }
+
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()) {
+            continue;
+        }
+
+        let path = entry.path();
+        if path.join("flake.nix").is_file() {
+            found.push(path.clone());
+        }
+        walk(&path, depth + 1, found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch_flake(dir: &Path) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("flake.nix"), "{ outputs = _: { }; }").unwrap();
+    }
+
+    #[test]
+    fn discovers_root_and_nested_flakes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        touch_flake(&root);
+        touch_flake(&root.join("templates/rust"));
+        touch_flake(&root.join("subprojects/api"));
+        fs::create_dir_all(root.join("docs")).unwrap();
+
+        let flakes = discover_flakes(&root);
+
+        assert_eq!(
+            flakes,
+            vec![
+                root.clone(),
+                root.join("subprojects/api"),
+                root.join("templates/rust"),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_hidden_and_build_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        touch_flake(&root.join(".git/modules/sub"));
+        touch_flake(&root.join("node_modules/pkg"));
+        touch_flake(&root.join("a/b/c/d"));
+
+        assert!(discover_flakes(&root).is_empty());
+    }
+
+    #[test]
+    fn accepts_flake_nix_path_as_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        touch_flake(&root);
+
+        assert_eq!(discover_flakes(&root.join("flake.nix")), vec![root]);
+    }
+}
@@ -0,0 +1,248 @@
+//! Editing input URLs in `flake.nix`
+//!
+//! `flake.nix` is a Nix expression melt cannot evaluate, so edits are made
+//! to the string literal holding an input's URL and leave everything around
+//! it untouched.
+
+use crate::error::{AppError, AppResult};
+use crate::model::{GitInput, GitRepo};
+
+/// Flake reference schemes that take the branch as a path segment after
+/// `owner/repo`
+const SHORTHAND_SCHEMES: [&str; 3] = ["github:", "gitlab:", "sourcehut:"];
+
+/// `contents` of a `flake.nix` with the URL of `input` changed to track
+/// `new_ref`.
+///
+/// The URL is the string literal naming `input`'s repository and its
+/// current branch. Fails when there is no such literal, or more than one.
+pub fn set_input_ref(contents: &str, input: &GitInput, new_ref: &str) -> AppResult<String> {
+    let needle = repo_needle(input.repo());
+    let candidates: Vec<(usize, usize)> = string_literals(contents)
+        .into_iter()
+        .filter(|&(start, end)| {
+            let url = &contents[start..end];
+            names_repo(url, &needle) && url_ref(url).as_deref() == input.reference()
+        })
+        .collect();
+
+    let (start, end) = match candidates.as_slice() {
+        [one] => *one,
+        [] => {
+            return Err(AppError::FlakeEdit(format!(
+                "no URL of {} found in flake.nix",
+                input.name()
+            )))
+        }
+        _ => {
+            return Err(AppError::FlakeEdit(format!(
+                "{} URLs in flake.nix could belong to {}",
+                candidates.len(),
+                input.name()
+            )))
+        }
+    };
+
+    let url = with_ref(&contents[start..end], new_ref);
+    Ok(format!("{}{}{}", &contents[..start], url, &contents[end..]))
+}
+
+/// Byte ranges of the contents of every double-quoted string in `contents`
+fn string_literals(contents: &str) -> Vec<(usize, usize)> {
+    let mut literals = Vec::new();
+    let mut start = None;
+    let mut escaped = false;
+    for (idx, c) in contents.char_indices() {
+        match (start, c) {
+            (Some(_), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), '"') if !escaped => {
+                literals.push((open, idx));
+                start = None;
+            }
+            (None, '"') => start = Some(idx + 1),
+            _ => {}
+        }
+        escaped = false;
+    }
+    literals
+}
+
+/// The part of a flake URL that identifies `repo`: `owner/repo` on forges,
+/// the host and path for plain git repositories
+fn repo_needle(repo: &GitRepo) -> String {
+    let identity = repo.identity();
+    let location = identity
+        .split_once("://")
+        .map_or(identity.as_str(), |(_, rest)| rest);
+    match repo {
+        GitRepo::Generic { .. } => location.to_string(),
+        _ => location
+            .split_once('/')
+            .map_or(location, |(_, path)| path)
+            .to_string(),
+    }
+}
+
+/// Whether `url` is a flake reference to the repository `needle` names
+fn names_repo(url: &str, needle: &str) -> bool {
+    if !url.contains(':') {
+        return false;
+    }
+    let url = url.to_lowercase();
+    url.match_indices(needle).any(|(idx, _)| {
+        let before = url[..idx].chars().next_back();
+        let after = url[idx + needle.len()..].chars().next();
+        matches!(before, Some('/' | ':' | '~'))
+            && matches!(after, None | Some('/' | '?' | '.' | '#'))
+    })
+}
+
+/// Split `url` into the part before its query and the query's parameters
+fn split_query(url: &str) -> (&str, Vec<&str>) {
+    match url.split_once('?') {
+        Some((base, query)) => (base, query.split('&').filter(|p| !p.is_empty()).collect()),
+        None => (url, Vec::new()),
+    }
+}
+
+/// Whether `url` uses a scheme that puts the branch in the path
+fn is_shorthand(url: &str) -> bool {
+    SHORTHAND_SCHEMES
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+}
+
+/// The branch or tag `url` tracks, if it names one
+fn url_ref(url: &str) -> Option<String> {
+    let (base, params) = split_query(url);
+    if let Some(value) = params.iter().find_map(|p| p.strip_prefix("ref=")) {
+        return Some(value.to_string());
+    }
+    if is_shorthand(base) {
+        let (_, path) = base.split_once(':')?;
+        let segments: Vec<&str> = path.splitn(3, '/').collect();
+        if let [_, _, reference] = segments.as_slice() {
+            return Some(reference.to_string());
+        }
+    }
+    None
+}
+
+/// `url` changed to track `new_ref`, in the form the URL already uses
+fn with_ref(url: &str, new_ref: &str) -> String {
+    let (base, mut params) = split_query(url);
+    let mut base = base.to_string();
+    let has_ref_param = params.iter().any(|p| p.starts_with("ref="));
+
+    // Shorthand paths cannot hold a branch with a slash in it.
+    if is_shorthand(&base) && !has_ref_param && !new_ref.contains('/') {
+        if let Some((scheme, path)) = base.split_once(':') {
+            let repo: Vec<&str> = path.splitn(3, '/').take(2).collect();
+            base = format!("{}:{}/{}", scheme, repo.join("/"), new_ref);
+        }
+        return join_query(&base, &params);
+    }
+
+    if is_shorthand(&base) {
+        if let Some((scheme, path)) = base.split_once(':') {
+            let repo: Vec<&str> = path.splitn(3, '/').take(2).collect();
+            base = format!("{}:{}", scheme, repo.join("/"));
+        }
+    }
+    let param = format!("ref={}", new_ref);
+    params.retain(|p| !p.starts_with("ref="));
+    params.insert(0, &param);
+    join_query(&base, &params)
+}
+
+fn join_query(base: &str, params: &[&str]) -> String {
+    if params.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}?{}", base, params.join("&"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CloneUrl, GitRef, GitRev, InputName, Owner, RepoName};
+
+    fn input(repo: GitRepo, reference: Option<&str>) -> GitInput {
+        GitInput::new(
+            InputName::new("nixpkgs").unwrap(),
+            repo,
+            reference.map(|r| GitRef::new(r).unwrap()),
+            GitRev::new("a".repeat(40)).unwrap(),
+            0,
+            String::new(),
+        )
+    }
+
+    fn nixpkgs(reference: Option<&str>) -> GitInput {
+        let repo = GitRepo::github(
+            Owner::new("NixOS").unwrap(),
+            RepoName::new("nixpkgs").unwrap(),
+        );
+        input(repo, reference)
+    }
+
+    #[test]
+    fn rewrites_the_branch_where_the_url_keeps_it() {
+        let flake = r#"{
+  inputs.nixpkgs.url = "github:NixOS/nixpkgs/nixos-24.05";
+  inputs.nixpkgs-unstable.url = "github:nixos/nixpkgs/nixos-unstable";
+  inputs.home-manager.url = "github:nix-community/home-manager?ref=release-24.05";
+}"#;
+        let edited = set_input_ref(flake, &nixpkgs(Some("nixos-24.05")), "nixos-24.11").unwrap();
+        assert!(edited.contains(r#""github:NixOS/nixpkgs/nixos-24.11""#));
+        assert!(edited.contains(r#""github:nixos/nixpkgs/nixos-unstable""#));
+
+        assert_eq!(
+            with_ref("github:o/r?ref=a&dir=sub", "b"),
+            "github:o/r?ref=b&dir=sub"
+        );
+        assert_eq!(
+            with_ref("github:o/r", "release/1.0"),
+            "github:o/r?ref=release/1.0"
+        );
+        assert_eq!(
+            with_ref("git+https://example.com/o/r.git?rev=abc", "main"),
+            "git+https://example.com/o/r.git?ref=main&rev=abc"
+        );
+    }
+
+    #[test]
+    fn finds_urls_of_inputs_on_the_default_branch() {
+        let flake = r#"{ inputs.nixpkgs = { url = "github:NixOS/nixpkgs"; }; }"#;
+        let edited = set_input_ref(flake, &nixpkgs(None), "nixos-unstable").unwrap();
+        assert_eq!(
+            edited,
+            r#"{ inputs.nixpkgs = { url = "github:NixOS/nixpkgs/nixos-unstable"; }; }"#
+        );
+
+        let generic =
+            GitRepo::generic(CloneUrl::new("https://git.example.com/team/tool.git").unwrap());
+        let flake = r#"{ inputs.tool.url = "git+https://git.example.com/team/tool"; }"#;
+        let edited = set_input_ref(flake, &input(generic, None), "next").unwrap();
+        assert!(edited.contains(r#""git+https://git.example.com/team/tool?ref=next""#));
+    }
+
+    #[test]
+    fn refuses_missing_and_ambiguous_urls() {
+        let flake = r#"{ inputs.nixpkgs.url = "github:NixOS/nixpkgs-lib"; }"#;
+        assert!(matches!(
+            set_input_ref(flake, &nixpkgs(None), "main"),
+            Err(AppError::FlakeEdit(_))
+        ));
+
+        let flake = r#"{ a.url = "github:NixOS/nixpkgs"; b.url = "github:NixOS/nixpkgs"; }"#;
+        assert!(matches!(
+            set_input_ref(flake, &nixpkgs(None), "main"),
+            Err(AppError::FlakeEdit(_))
+        ));
+    }
+}
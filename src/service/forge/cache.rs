@@ -0,0 +1,255 @@
+//! Conditional request cache for forge API responses
+//!
+//! Responses that carry an `ETag` are kept on disk. Later requests for the
+//! same URL send `If-None-Match`, and a `304 Not Modified` answer is served
+//! from the stored body; GitHub does not count those against the rate limit.
+//!
+//! Most URLs name revisions, so entries are rarely reused for long. The
+//! cache is pruned every so often, dropping old entries and then the
+//! oldest ones until it is back under its size limit.
+
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use fnv::FnvHasher;
+
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::error::GitError;
+use crate::service::CacheMetrics;
+
+/// Entries older than this are removed when the cache is pruned
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Size the cache is pruned down to, removing the oldest entries first
+const MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Responses stored between prunes; the first one prunes too
+const PRUNE_EVERY: usize = 200;
+
+/// On-disk cache of ETag'd response bodies, keyed by URL
+#[derive(Debug, Clone, Default)]
+pub struct ResponseCache {
+    /// `None` disables caching
+    dir: Option<PathBuf>,
+    /// Responses stored so far, shared by clones
+    stored: Arc<AtomicUsize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    etag: String,
+    body: String,
+}
+
+impl ResponseCache {
+    /// Cache responses in `dir`
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir: Some(dir),
+            stored: Arc::default(),
+        }
+    }
+
+    /// File for `url`, named by a hash that stays the same across Rust
+    /// releases so the cache outlives toolchain updates
+    fn path(&self, url: &str) -> Option<PathBuf> {
+        let mut hasher = FnvHasher::default();
+        hasher.write(url.as_bytes());
+        let dir = self.dir.as_ref()?;
+        Some(dir.join(format!("{:016x}.json", hasher.finish())))
+    }
+
+    async fn load(&self, url: &str) -> Option<Entry> {
+        let contents = tokio::fs::read(self.path(url)?).await.ok()?;
+        serde_json::from_slice::<Entry>(&contents)
+            .ok()
+            .filter(|entry| entry.url == url)
+    }
+
+    /// Ask the server to answer `304 Not Modified` if the response cached
+    /// for `url` is still current
+    pub async fn conditional(&self, req: RequestBuilder, url: &str) -> RequestBuilder {
+        match self.load(url).await {
+            Some(entry) => req.header(IF_NONE_MATCH, entry.etag),
+            None => req,
+        }
+    }
+
+    /// Whether `resp` can be read with [`ResponseCache::body`]
+    pub fn is_usable(resp: &Response) -> bool {
+        resp.status().is_success() || resp.status() == StatusCode::NOT_MODIFIED
+    }
+
+    /// Body of the response to `url`: the cached one when the server
+    /// answered `304`, otherwise the new one, which is cached if it has an
+    /// `ETag`
+    pub async fn body(
+        &self,
+        url: &str,
+        resp: Response,
+        metrics: &CacheMetrics,
+    ) -> Result<Vec<u8>, GitError> {
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            return match self.load(url).await {
+                Some(entry) => Ok(entry.body.into_bytes()),
+                None => Err(GitError::NetworkError(format!(
+                    "Not modified, but no cached response for {}",
+                    url
+                ))),
+            };
+        }
+
+        let etag = resp
+            .headers()
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = resp
+            .bytes()
+            .await
+            .map_err(|e| GitError::NetworkError(e.to_string()))?;
+        metrics.record_api_bytes(body.len() as u64);
+
+        if let (Some(etag), Some(path), Ok(text)) =
+            (etag, self.path(url), std::str::from_utf8(&body))
+        {
+            let entry = Entry {
+                url: url.to_string(),
+                etag,
+                body: text.to_string(),
+            };
+            if let Err(e) = self.store(path, &entry).await {
+                debug!(url, error = %e, "Failed to cache API response");
+            }
+        }
+        Ok(body.to_vec())
+    }
+
+    async fn store(&self, path: PathBuf, entry: &Entry) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_vec(entry)?).await?;
+
+        if let Some(dir) = self.dir.clone() {
+            if self.stored.fetch_add(1, Ordering::Relaxed) % PRUNE_EVERY == 0 {
+                tokio::task::spawn_blocking(move || {
+                    if let Err(e) = prune(&dir, MAX_AGE, MAX_BYTES, SystemTime::now()) {
+                        debug!(error = %e, "Failed to prune API response cache");
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Remove entries in `dir` last written more than `max_age` before `now`,
+/// then the oldest remaining ones until they take at most `max_bytes`
+fn prune(dir: &Path, max_age: Duration, max_bytes: u64, now: SystemTime) -> std::io::Result<()> {
+    let mut entries = Vec::new();
+    for file in std::fs::read_dir(dir)? {
+        let file = file?;
+        let path = file.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let metadata = file.metadata()?;
+            entries.push((metadata.modified()?, metadata.len(), path));
+        }
+    }
+    // Newest first, so the oldest are past the size limit
+    entries.sort_by_key(|(modified, _, _)| std::cmp::Reverse(*modified));
+
+    let mut total = 0;
+    for (modified, len, path) in entries {
+        let age = now.duration_since(modified).unwrap_or_default();
+        total += len;
+        if age > max_age || total > max_bytes {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Client;
+    use wiremock::matchers::{header, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_serves_not_modified_from_cache() {
+        let server = MockServer::start().await;
+        Mock::given(path("/compare"))
+            .and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/compare"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_string(r#"{"ahead_by":3}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::new(tmp.path().join("api"));
+        let metrics = CacheMetrics::new(true);
+        let url = format!("{}/compare", server.uri());
+        let client = Client::new();
+
+        for _ in 0..2 {
+            let req = cache.conditional(client.get(&url), &url).await;
+            let resp = req.send().await.unwrap();
+            assert!(ResponseCache::is_usable(&resp));
+            let body = cache.body(&url, resp, &metrics).await.unwrap();
+            assert_eq!(body, br#"{"ahead_by":3}"#);
+        }
+        // Only the first response had a body to read.
+        assert_eq!(metrics.snapshot().unwrap().api_bytes, 14);
+    }
+
+    #[test]
+    fn test_cache_paths_are_stable() {
+        let cache = ResponseCache::new(PathBuf::from("/cache"));
+        assert_eq!(
+            cache.path("https://api.github.com/repos/NixOS/nixpkgs"),
+            Some(PathBuf::from("/cache/ae02554963b6a34b.json"))
+        );
+    }
+
+    #[test]
+    fn test_prune_drops_old_then_oldest_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        for (name, days_old) in [("new", 0), ("older", 1), ("oldest", 2), ("stale", 40)] {
+            let path = tmp.path().join(format!("{}.json", name));
+            std::fs::write(&path, [0; 10]).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - day * days_old).unwrap();
+        }
+        std::fs::write(tmp.path().join("notes.txt"), "kept").unwrap();
+
+        prune(tmp.path(), MAX_AGE, 25, now).unwrap();
+
+        let mut left: Vec<_> = std::fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|file| file.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["new.json", "notes.txt", "older.json"]);
+    }
+}
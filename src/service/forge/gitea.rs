@@ -0,0 +1,518 @@
+//! Gitea and Forgejo REST API client, used for Codeberg as well
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use reqwest::{Client, Response};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use super::{
+    changelog_data, json, json_items, send, with_host_headers, ForgeClient, ForgeFuture, Named,
+    NamedTag, PageSink,
+};
+use crate::config::{CommitLimits, ForgeTokens, HostHeaders};
+use crate::error::GitError;
+use crate::model::{ChangelogData, Commit, CommitCount, GitInput, GitRepo, SignatureStatus, Tag};
+use crate::service::CacheMetrics;
+
+/// Largest page Gitea serves with its default `MAX_RESPONSE_ITEMS`
+const MAX_PAGE_SIZE: usize = 50;
+
+pub(super) const CODEBERG_HOST: &str = "codeberg.org";
+
+/// Client for Codeberg and self-hosted Gitea or Forgejo inputs
+#[derive(Clone)]
+pub struct GiteaClient {
+    client: Client,
+    /// API tokens, keyed by host
+    tokens: ForgeTokens,
+    /// Replaces `https://<host>` in API URLs
+    api_base: Option<String>,
+    metrics: Arc<CacheMetrics>,
+    headers: HostHeaders,
+}
+
+impl GiteaClient {
+    pub fn new(client: Client, tokens: ForgeTokens) -> Self {
+        Self {
+            client,
+            tokens,
+            api_base: None,
+            metrics: Arc::default(),
+            headers: HostHeaders::default(),
+        }
+    }
+
+    /// Send requests to `api_base` instead of each input's host
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = Some(api_base.into());
+        self
+    }
+
+    /// Count requests and response bytes in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<CacheMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Send the headers configured for each input's host with its requests
+    pub fn with_headers(mut self, headers: HostHeaders) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Host and repository API URL for `input`, if it is hosted on Gitea
+    /// or Forgejo
+    fn repo_url<'a>(&self, input: &'a GitInput) -> Option<(&'a str, String)> {
+        let (host, owner, repo) = match input.repo() {
+            GitRepo::Gitea { host, owner, repo } => (host.as_str(), owner, repo),
+            GitRepo::Codeberg { owner, repo } => (CODEBERG_HOST, owner, repo),
+            _ => return None,
+        };
+        let base = match &self.api_base {
+            Some(base) => base.clone(),
+            None => format!("https://{}", host),
+        };
+        Some((host, format!("{}/api/v1/repos/{}/{}", base, owner, repo)))
+    }
+
+    /// Send a GET request, returning `None` for unsuccessful responses
+    async fn fetch(&self, host: &str, url: &str) -> Result<Option<Response>, GitError> {
+        let mut req = with_host_headers(self.client.get(url), &self.headers, host);
+        if let Some(token) = self.tokens.resolve(host, token_env_vars(host)) {
+            req = req.header("Authorization", format!("token {}", token));
+        }
+        let resp = send(req, &self.metrics).await?;
+        Ok(resp.status().is_success().then_some(resp))
+    }
+
+    /// The tracked branch, or the repository's default branch for inputs
+    /// without one. Gitea's compare endpoint does not resolve `HEAD`.
+    async fn branch(
+        &self,
+        input: &GitInput,
+        host: &str,
+        repo_url: &str,
+    ) -> Result<Option<String>, GitError> {
+        if let Some(reference) = input.reference() {
+            return Ok(Some(reference.to_string()));
+        }
+
+        #[derive(Deserialize)]
+        struct Repository {
+            default_branch: String,
+        }
+
+        let Some(resp) = self.fetch(host, repo_url).await? else {
+            return Ok(None);
+        };
+        let repo: Repository = json(resp, &self.metrics).await?;
+        Ok(Some(repo.default_branch))
+    }
+
+    /// The `kind` listing (`tags` or `branches`) of the input's repository
+    async fn listing<T: DeserializeOwned>(
+        &self,
+        input: &GitInput,
+        kind: &str,
+    ) -> Result<Option<Vec<T>>, GitError> {
+        let Some((host, repo_url)) = self.repo_url(input) else {
+            return Ok(None);
+        };
+        let url = format!("{}/{}?limit={}", repo_url, kind, MAX_PAGE_SIZE);
+        let Some(resp) = self.fetch(host, &url).await? else {
+            return Ok(None);
+        };
+        Ok(Some(json(resp, &self.metrics).await?))
+    }
+
+    async fn count_updates(&self, input: &GitInput) -> Result<Option<CommitCount>, GitError> {
+        let Some((host, repo_url)) = self.repo_url(input) else {
+            return Ok(None);
+        };
+        let Some(branch) = self.branch(input, host, &repo_url).await? else {
+            return Ok(None);
+        };
+        self.compare(input, input.rev(), &branch).await
+    }
+
+    async fn get_changelog(
+        &self,
+        input: &GitInput,
+        limits: CommitLimits,
+        on_page: PageSink<'_>,
+    ) -> Result<Option<ChangelogData>, GitError> {
+        let Some((host, repo_url)) = self.repo_url(input) else {
+            return Ok(None);
+        };
+
+        #[derive(Deserialize)]
+        struct GiteaAuthor {
+            name: String,
+            date: String,
+        }
+
+        #[derive(Deserialize)]
+        struct GiteaCommitData {
+            message: String,
+            author: GiteaAuthor,
+        }
+
+        #[derive(Deserialize)]
+        struct GiteaCommit {
+            sha: String,
+            commit: GiteaCommitData,
+        }
+
+        let to_commit = |c: GiteaCommit| Commit {
+            date: chrono::DateTime::parse_from_rfc3339(&c.commit.author.date)
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            message: c.commit.message.lines().next().unwrap_or("").to_string(),
+            sha: c.sha,
+            author: c.commit.author.name,
+            signature: SignatureStatus::Unknown,
+        };
+
+        // Without a `sha` the default branch is listed. Stats, signature
+        // checks, and file lists are skipped; only subjects are shown.
+        let branch = input
+            .reference()
+            .map(|reference| format!("&sha={}", reference))
+            .unwrap_or_default();
+        let per_page = limits.api_page_size().min(MAX_PAGE_SIZE);
+        let pages = limits
+            .max_api_pages
+            .min(limits.max_commits.div_ceil(per_page))
+            .max(1);
+        let mut commits: Vec<Commit> = Vec::new();
+        let mut exhausted = false;
+        for page in 1..=pages {
+            let url = format!(
+                "{}/commits?limit={}&page={}&stat=false&verification=false&files=false{}",
+                repo_url, per_page, page, branch
+            );
+            let Some(resp) = self.fetch(host, &url).await? else {
+                return Ok(None);
+            };
+
+            let page_commits = json_items(resp, &self.metrics, to_commit).await?;
+            let last_page = page_commits.len() < per_page;
+            let found_locked = page_commits.iter().any(|c| c.sha.starts_with(input.rev()));
+            let offset = commits.len();
+            commits.extend(page_commits);
+
+            if last_page {
+                exhausted = true;
+                break;
+            }
+            if found_locked || commits.len() >= limits.max_commits {
+                break;
+            }
+            on_page(offset, &commits[offset..]);
+        }
+
+        let fetched = commits.len();
+        changelog_data(commits, input.rev(), (!exhausted).then_some(fetched)).map(Some)
+    }
+}
+
+/// Environment variables holding a token for `host`. Only Codeberg has one;
+/// tokens for other instances come from the config file.
+pub(super) fn token_env_vars(host: &str) -> &'static [&'static str] {
+    if host.eq_ignore_ascii_case(CODEBERG_HOST) {
+        &["CODEBERG_TOKEN"]
+    } else {
+        &[]
+    }
+}
+
+impl ForgeClient for GiteaClient {
+    fn compare<'a>(
+        &'a self,
+        input: &'a GitInput,
+        base: &'a str,
+        head: &'a str,
+    ) -> ForgeFuture<'a, Option<CommitCount>> {
+        Box::pin(async move {
+            let Some((host, repo_url)) = self.repo_url(input) else {
+                return Ok(None);
+            };
+            let url = format!("{}/compare/{}...{}", repo_url, base, head);
+            let Some(resp) = self.fetch(host, &url).await? else {
+                return Ok(None);
+            };
+
+            #[derive(Deserialize)]
+            struct CompareResponse {
+                total_commits: usize,
+            }
+
+            let data: CompareResponse = json(resp, &self.metrics).await?;
+            Ok(Some(CommitCount::Exact(data.total_commits)))
+        })
+    }
+
+    fn check_updates<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<CommitCount>> {
+        Box::pin(self.count_updates(input))
+    }
+
+    fn changelog<'a>(
+        &'a self,
+        input: &'a GitInput,
+        limits: CommitLimits,
+        on_page: PageSink<'a>,
+    ) -> ForgeFuture<'a, Option<ChangelogData>> {
+        Box::pin(self.get_changelog(input, limits, on_page))
+    }
+
+    fn tags<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<Vec<Tag>>> {
+        Box::pin(async move {
+            let tags: Option<Vec<NamedTag>> = self.listing(input, "tags").await?;
+            Ok(tags.map(|tags| tags.into_iter().map(Tag::from).collect()))
+        })
+    }
+
+    fn branches<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<Vec<String>>> {
+        Box::pin(async move {
+            let names: Option<Vec<Named>> = self.listing(input, "branches").await?;
+            Ok(names.map(|names| names.into_iter().map(|n| n.name).collect()))
+        })
+    }
+
+    fn compare_url(&self, input: &GitInput, base: &str, head: &str) -> Option<String> {
+        let (host, owner, repo) = match input.repo() {
+            GitRepo::Gitea { host, owner, repo } => (host.as_str(), owner, repo),
+            GitRepo::Codeberg { owner, repo } => (CODEBERG_HOST, owner, repo),
+            _ => return None,
+        };
+        Some(format!(
+            "https://{}/{}/{}/compare/{}...{}",
+            host, owner, repo, base, head
+        ))
+    }
+
+    fn commit_url(&self, input: &GitInput, sha: &str) -> Option<String> {
+        let (host, owner, repo) = match input.repo() {
+            GitRepo::Gitea { host, owner, repo } => (host.as_str(), owner, repo),
+            GitRepo::Codeberg { owner, repo } => (CODEBERG_HOST, owner, repo),
+            _ => return None,
+        };
+        Some(format!(
+            "https://{}/{}/{}/commit/{}",
+            host, owner, repo, sha
+        ))
+    }
+
+    fn pull_request<'a>(
+        &'a self,
+        input: &'a GitInput,
+        sha: &'a str,
+    ) -> ForgeFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let Some((host, repo_url)) = self.repo_url(input) else {
+                return Ok(None);
+            };
+            // Answers 404 when no pull request merged the commit
+            let url = format!("{}/commits/{}/pull", repo_url, sha);
+            let Some(resp) = self.fetch(host, &url).await? else {
+                return Ok(None);
+            };
+
+            #[derive(Deserialize)]
+            struct PullRequest {
+                html_url: String,
+            }
+
+            let pull: PullRequest = json(resp, &self.metrics).await?;
+            Ok(Some(pull.html_url))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GitHost, GitRef, GitRev, InputName, Owner, RepoName};
+    use serde_json::json;
+    use wiremock::matchers::{header, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const REPO: &str = "/api/v1/repos/forgejo/forgejo";
+
+    fn input(reference: Option<&str>) -> GitInput {
+        GitInput::new(
+            InputName::new("forgejo").unwrap(),
+            GitRepo::codeberg(
+                Owner::new("forgejo").unwrap(),
+                RepoName::new("forgejo").unwrap(),
+            ),
+            reference.map(|r| GitRef::new(r).unwrap()),
+            GitRev::new("abc123").unwrap(),
+            0,
+            String::new(),
+        )
+    }
+
+    fn client(server: &MockServer) -> GiteaClient {
+        let mut tokens = ForgeTokens::default();
+        tokens.insert("codeberg.org", "cb_test".to_string());
+        GiteaClient::new(Client::new(), tokens).with_api_base(server.uri())
+    }
+
+    fn commits(shas: &[&str]) -> serde_json::Value {
+        shas.iter()
+            .map(|sha| {
+                json!({
+                    "sha": sha,
+                    "commit": {
+                        "message": format!("{} subject\n\nbody", sha),
+                        "author": {"name": "dev", "date": "2026-10-01T12:00:00Z"}
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compare_url() {
+        let client = GiteaClient::new(Client::new(), ForgeTokens::default());
+        assert_eq!(
+            client.compare_url(&input(None), "abc", "def").as_deref(),
+            Some("https://codeberg.org/forgejo/forgejo/compare/abc...def")
+        );
+
+        let selfhosted = GitInput::new(
+            InputName::new("tools").unwrap(),
+            GitRepo::gitea(
+                GitHost::new("git.example.org").unwrap(),
+                Owner::new("ops").unwrap(),
+                RepoName::new("tools").unwrap(),
+            ),
+            None,
+            GitRev::new("abc123").unwrap(),
+            0,
+            String::new(),
+        );
+        let (host, url) = client.repo_url(&selfhosted).unwrap();
+        assert_eq!(host, "git.example.org");
+        assert_eq!(url, "https://git.example.org/api/v1/repos/ops/tools");
+    }
+
+    #[test]
+    fn test_codeberg_token_env_var() {
+        assert_eq!(token_env_vars("Codeberg.org"), ["CODEBERG_TOKEN"]);
+        assert!(token_env_vars("git.example.org").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_updates_compares_with_default_branch() {
+        let server = MockServer::start().await;
+        Mock::given(path(REPO))
+            .and(header("Authorization", "token cb_test"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"default_branch": "forgejo"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path(format!("{}/compare/abc123...forgejo", REPO)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"total_commits": 7})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let count = client(&server).check_updates(&input(None)).await.unwrap();
+        assert_eq!(count, Some(CommitCount::Exact(7)));
+    }
+
+    #[tokio::test]
+    async fn test_check_updates_uses_tracked_branch_and_falls_back_on_errors() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!("{}/compare/abc123...v9.0/forgejo", REPO)))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let count = client(&server)
+            .check_updates(&input(Some("v9.0/forgejo")))
+            .await
+            .unwrap();
+        assert_eq!(count, None);
+    }
+
+    #[tokio::test]
+    async fn test_changelog_pages_until_locked_commit() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!("{}/commits", REPO)))
+            .and(query_param("page", "1"))
+            .and(query_param("stat", "false"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(commits(&["fff", "eee"])))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path(format!("{}/commits", REPO)))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(commits(&["ddd", "abc123"])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let limits = CommitLimits {
+            per_page: 2,
+            ..CommitLimits::default()
+        };
+        let data = client(&server)
+            .changelog(&input(None), limits, &|_, _| {})
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.commits.len(), 4);
+        assert_eq!(data.locked_index(), Some(3));
+        assert_eq!(data.commits[0].message, "fff subject");
+        assert!(data.truncated_at().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_branches() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!("{}/branches", REPO)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{"name": "forgejo"}])))
+            .mount(&server)
+            .await;
+
+        assert_eq!(
+            client(&server).branches(&input(None)).await.unwrap(),
+            Some(vec!["forgejo".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pull_request_that_merged_a_commit() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!("{}/commits/abc/pull", REPO)))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(
+                    json!({"html_url": "https://codeberg.org/forgejo/forgejo/pulls/7"}),
+                ),
+            )
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        assert_eq!(
+            client
+                .pull_request(&input(None), "abc")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("https://codeberg.org/forgejo/forgejo/pulls/7")
+        );
+        assert_eq!(
+            client.pull_request(&input(None), "def").await.unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,1236 @@
+//! GitHub REST and GraphQL API client, for github.com and GitHub Enterprise
+//! Server
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Utc;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::warn;
+
+use super::{
+    changelog_data, decode, json, parse_items, rate_limit_wait, send, with_host_headers,
+    ForgeClient, ForgeFuture, Named, NamedTag, PageSink, ResponseCache,
+};
+use crate::config::{CommitLimits, ForgeTokens, HostHeaders};
+use crate::error::GitError;
+use crate::model::{
+    ChangelogData, Commit, CommitCount, CommitDetail, DiffStats, FileChange, GitInput, GitRepo,
+    Owner, Release, RepoName, SignatureStatus, Tag,
+};
+use crate::service::CacheMetrics;
+
+const API_BASE: &str = "https://api.github.com";
+
+const GITHUB_HOST: &str = "github.com";
+
+/// Environment variables holding a token for the GitHub Enterprise host
+/// named by `GH_HOST`, as read by the `gh` CLI
+const ENTERPRISE_TOKEN_VARS: &[&str] = &["GH_ENTERPRISE_TOKEN", "GITHUB_ENTERPRISE_TOKEN"];
+
+/// Inputs compared per GraphQL query, well under GitHub's node limits
+const GRAPHQL_BATCH_SIZE: usize = 50;
+
+/// Most files the compare API lists; larger comparisons are cut off there
+const COMPARE_FILES_LIMIT: usize = 300;
+
+/// Client for github.com and GitHub Enterprise Server inputs
+#[derive(Clone)]
+pub struct GitHubClient {
+    client: Client,
+    /// Token for github.com
+    token: Option<String>,
+    api_base: String,
+    /// Tokens for Enterprise hosts
+    tokens: ForgeTokens,
+    /// Replaces `https://<host>` in Enterprise API URLs
+    enterprise_base: Option<String>,
+    /// Revalidated compare and commit responses
+    cache: ResponseCache,
+    metrics: Arc<CacheMetrics>,
+    headers: HostHeaders,
+}
+
+/// Host, API URL, and token for the repository of one input
+struct RepoApi<'a> {
+    host: &'a str,
+    /// `<api>/repos/<owner>/<repo>`
+    url: String,
+    token: Option<String>,
+}
+
+impl GitHubClient {
+    pub fn new(client: Client, token: Option<String>) -> Self {
+        Self {
+            client,
+            token,
+            api_base: API_BASE.to_string(),
+            tokens: ForgeTokens::default(),
+            enterprise_base: None,
+            cache: ResponseCache::default(),
+            metrics: Arc::default(),
+            headers: HostHeaders::default(),
+        }
+    }
+
+    /// Authenticate to GitHub Enterprise hosts with `tokens`
+    pub fn with_tokens(mut self, tokens: ForgeTokens) -> Self {
+        self.tokens = tokens;
+        self
+    }
+
+    /// Send requests for Enterprise inputs to `base` instead of their host
+    pub fn with_enterprise_base(mut self, base: impl Into<String>) -> Self {
+        self.enterprise_base = Some(base.into());
+        self
+    }
+
+    /// Send the headers configured for each input's host with its requests
+    pub fn with_headers(mut self, headers: HostHeaders) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Revalidate compare and commit responses kept in `cache` instead of
+    /// downloading them again
+    pub fn with_response_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Count requests and response bytes in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<CacheMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Send requests to `api_base` instead of api.github.com
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into();
+        self
+    }
+
+    /// Whether github.com requests are authenticated
+    pub fn has_token(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// Owner and name of `input` if it is hosted on github.com
+    fn repo(input: &GitInput) -> Option<(&Owner, &RepoName)> {
+        match input.repo() {
+            GitRepo::GitHub { owner, repo } => Some((owner, repo)),
+            _ => None,
+        }
+    }
+
+    /// API location of the repository of `input`, if it is hosted on
+    /// GitHub. Enterprise Server serves the REST API under `/api/v3`.
+    fn repo_api<'a>(&self, input: &'a GitInput) -> Option<RepoApi<'a>> {
+        match input.repo() {
+            GitRepo::GitHub { owner, repo } => Some(RepoApi {
+                host: GITHUB_HOST,
+                url: format!("{}/repos/{}/{}", self.api_base, owner, repo),
+                token: self.token.clone(),
+            }),
+            GitRepo::GitHubEnterprise { host, owner, repo } => {
+                let base = match &self.enterprise_base {
+                    Some(base) => base.clone(),
+                    None => format!("https://{}", host),
+                };
+                Some(RepoApi {
+                    host: host.as_str(),
+                    url: format!("{}/api/v3/repos/{}/{}", base, owner, repo),
+                    token: self.tokens.resolve(
+                        host.as_str(),
+                        enterprise_token_env_vars(
+                            host.as_str(),
+                            std::env::var("GH_HOST").ok().as_deref(),
+                        ),
+                    ),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn authorized(&self, req: RequestBuilder, host: &str, token: Option<&str>) -> RequestBuilder {
+        let req = with_host_headers(req, &self.headers, host);
+        match token {
+            Some(token) => req.header("Authorization", format!("Bearer {}", token)),
+            None => req,
+        }
+    }
+
+    /// Send a request, returning `None` for unsuccessful responses.
+    ///
+    /// An exhausted rate limit is an error: falling back to a clone for
+    /// every remaining input would be far slower than waiting for the
+    /// limit to reset.
+    async fn send_checked(
+        &self,
+        req: RequestBuilder,
+        host: &str,
+        token: Option<&str>,
+    ) -> Result<Option<Response>, GitError> {
+        let resp = self.send_limited(req, host, token).await?;
+        Ok(resp.status().is_success().then_some(resp))
+    }
+
+    /// Send a request, failing if the rate limit is exhausted
+    async fn send_limited(
+        &self,
+        req: RequestBuilder,
+        host: &str,
+        token: Option<&str>,
+    ) -> Result<Response, GitError> {
+        let resp = send(self.authorized(req, host, token), &self.metrics).await?;
+        let status = resp.status();
+
+        if status.as_u16() == 403 || status.as_u16() == 429 {
+            let headers = resp.headers();
+            let remaining = headers
+                .get("x-ratelimit-remaining")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or(0);
+
+            // Secondary limits send `retry-after` with quota still left
+            if remaining == 0 || headers.contains_key("retry-after") {
+                let retry_after = rate_limit_wait(headers);
+                warn!(url = %resp.url(), ?retry_after, "GitHub API rate limit exceeded");
+                return Err(GitError::RateLimited {
+                    forge: "GitHub",
+                    retry_after,
+                });
+            }
+        }
+
+        Ok(resp)
+    }
+
+    async fn fetch(&self, api: &RepoApi<'_>, url: &str) -> Result<Option<Response>, GitError> {
+        self.send_checked(self.client.get(url), api.host, api.token.as_deref())
+            .await
+    }
+
+    /// GET the body of `url`, revalidating a cached copy with its ETag.
+    /// Returns `None` for unsuccessful responses.
+    async fn fetch_cached(
+        &self,
+        api: &RepoApi<'_>,
+        url: &str,
+    ) -> Result<Option<Vec<u8>>, GitError> {
+        let req = self.cache.conditional(self.client.get(url), url).await;
+        let resp = self
+            .send_limited(req, api.host, api.token.as_deref())
+            .await?;
+        if !ResponseCache::is_usable(&resp) {
+            return Ok(None);
+        }
+        self.cache.body(url, resp, &self.metrics).await.map(Some)
+    }
+
+    /// Count the updates for many inputs with one GraphQL query per
+    /// [`GRAPHQL_BATCH_SIZE`] inputs, instead of a REST compare each.
+    ///
+    /// Results line up with `inputs`; `None` marks inputs hosted elsewhere,
+    /// including on Enterprise Server, or ones the query could not answer, which are left to
+    /// [`ForgeClient::check_updates`]. GraphQL requires a token, so without
+    /// one nothing is answered.
+    pub async fn check_updates_batch(
+        &self,
+        inputs: &[GitInput],
+    ) -> Result<Vec<Option<CommitCount>>, GitError> {
+        let mut counts = vec![None; inputs.len()];
+        if self.token.is_none() {
+            return Ok(counts);
+        }
+
+        let github: Vec<usize> = (0..inputs.len())
+            .filter(|&i| Self::repo(&inputs[i]).is_some())
+            .collect();
+        for batch in github.chunks(GRAPHQL_BATCH_SIZE) {
+            let batch_inputs: Vec<&GitInput> = batch.iter().map(|&i| &inputs[i]).collect();
+            let batch_counts = self.query_batch(&batch_inputs).await?;
+            for (&i, count) in batch.iter().zip(batch_counts) {
+                counts[i] = count;
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn query_batch(
+        &self,
+        inputs: &[&GitInput],
+    ) -> Result<Vec<Option<CommitCount>>, GitError> {
+        // Each input gets an aliased `repository` field comparing its
+        // tracked branch (base) with the locked revision (head), so
+        // `behindBy` is the number of new commits.
+        let mut params = Vec::new();
+        let mut fields = String::new();
+        let mut variables = serde_json::Map::new();
+        for (i, input) in inputs.iter().enumerate() {
+            let Some((owner, repo)) = Self::repo(input) else {
+                continue;
+            };
+            params.push(format!("$o{i}: String!, $n{i}: String!, $h{i}: String!"));
+            variables.insert(format!("o{i}"), owner.as_str().into());
+            variables.insert(format!("n{i}"), repo.as_str().into());
+            variables.insert(format!("h{i}"), input.rev().into());
+            let target = match input.reference() {
+                Some(reference) => {
+                    params.push(format!("$r{i}: String!"));
+                    variables.insert(format!("r{i}"), reference.into());
+                    format!("ref(qualifiedName: $r{i})")
+                }
+                None => "defaultBranchRef".to_string(),
+            };
+            fields.push_str(&format!(
+                "r{i}: repository(owner: $o{i}, name: $n{i}) {{ \
+                 target: {target} {{ compare(headRef: $h{i}) {{ behindBy }} }} }} "
+            ));
+        }
+        let query = format!("query({}) {{ {}}}", params.join(", "), fields);
+
+        let req = self
+            .client
+            .post(format!("{}/graphql", self.api_base))
+            .json(&serde_json::json!({ "query": query, "variables": variables }));
+        let Some(resp) = self
+            .send_checked(req, GITHUB_HOST, self.token.as_deref())
+            .await?
+        else {
+            return Ok(vec![None; inputs.len()]);
+        };
+
+        #[derive(Deserialize)]
+        struct Comparison {
+            #[serde(rename = "behindBy")]
+            behind_by: usize,
+        }
+
+        #[derive(Deserialize)]
+        struct Target {
+            compare: Option<Comparison>,
+        }
+
+        #[derive(Deserialize)]
+        struct Repository {
+            target: Option<Target>,
+        }
+
+        // Repositories, refs, or revisions that don't resolve come back as
+        // `null` next to an `errors` entry; the rest of the batch still
+        // answers.
+        #[derive(Deserialize)]
+        struct GraphQlResponse {
+            data: Option<HashMap<String, Option<Repository>>>,
+        }
+
+        let mut data = json::<GraphQlResponse>(resp, &self.metrics)
+            .await?
+            .data
+            .unwrap_or_default();
+        Ok((0..inputs.len())
+            .map(|i| {
+                let compare = data.remove(&format!("r{i}"))??.target?.compare?;
+                Some(CommitCount::Exact(compare.behind_by))
+            })
+            .collect())
+    }
+
+    /// The `kind` listing (`tags` or `branches`) of the input's repository
+    async fn listing<T: DeserializeOwned>(
+        &self,
+        input: &GitInput,
+        kind: &str,
+    ) -> Result<Option<Vec<T>>, GitError> {
+        let Some(api) = self.repo_api(input) else {
+            return Ok(None);
+        };
+        let url = format!("{}/{}?per_page=100", api.url, kind);
+        let Some(resp) = self.fetch(&api, &url).await? else {
+            return Ok(None);
+        };
+        Ok(Some(json(resp, &self.metrics).await?))
+    }
+
+    async fn get_changelog(
+        &self,
+        input: &GitInput,
+        limits: CommitLimits,
+        on_page: PageSink<'_>,
+    ) -> Result<Option<ChangelogData>, GitError> {
+        let Some(api) = self.repo_api(input) else {
+            return Ok(None);
+        };
+        let branch = input.reference().unwrap_or("HEAD");
+
+        #[derive(Deserialize)]
+        struct GitHubAuthor {
+            name: Option<String>,
+            date: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        struct GitHubVerification {
+            verified: bool,
+            reason: String,
+        }
+
+        #[derive(Deserialize)]
+        struct GitHubCommitData {
+            message: String,
+            author: Option<GitHubAuthor>,
+            verification: Option<GitHubVerification>,
+        }
+
+        #[derive(Deserialize)]
+        struct GitHubCommit {
+            sha: String,
+            commit: GitHubCommitData,
+        }
+
+        let to_commit = |c: GitHubCommit| {
+            let signature = match &c.commit.verification {
+                Some(v) if v.verified => SignatureStatus::Verified,
+                Some(v) if v.reason == "unsigned" => SignatureStatus::Unsigned,
+                Some(_) => SignatureStatus::Unverified,
+                None => SignatureStatus::Unknown,
+            };
+            let author = c.commit.author.as_ref();
+            let date = author
+                .and_then(|a| a.date.as_ref())
+                .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_else(Utc::now);
+            let name = author
+                .and_then(|a| a.name.clone())
+                .unwrap_or_else(|| "Unknown".to_string());
+
+            Commit {
+                message: c.commit.message.lines().next().unwrap_or("").to_string(),
+                sha: c.sha,
+                author: name,
+                date,
+                signature,
+            }
+        };
+
+        // Page back from the branch head until the locked commit shows up,
+        // stopping at the page and commit caps.
+        let per_page = limits.api_page_size();
+        let mut commits: Vec<Commit> = Vec::new();
+        let mut exhausted = false;
+        for page in 1..=limits.api_pages() {
+            let url = format!(
+                "{}/commits?sha={}&per_page={}&page={}",
+                api.url, branch, per_page, page
+            );
+
+            let Some(body) = self.fetch_cached(&api, &url).await? else {
+                return Ok(None);
+            };
+
+            let page_commits = decode(&body, |body| parse_items(body, to_commit))?;
+            let last_page = page_commits.len() < per_page;
+            let found_locked = page_commits.iter().any(|c| c.sha.starts_with(input.rev()));
+            let offset = commits.len();
+            commits.extend(page_commits);
+
+            if last_page {
+                exhausted = true;
+                break;
+            }
+            if found_locked || commits.len() >= limits.max_commits {
+                break;
+            }
+            on_page(offset, &commits[offset..]);
+        }
+
+        let fetched = commits.len();
+        changelog_data(commits, input.rev(), (!exhausted).then_some(fetched)).map(Some)
+    }
+}
+
+/// Environment variables holding a token for the GitHub Enterprise `host`.
+/// Like `gh`, they only apply to the host `gh_host` names; tokens for other
+/// instances come from the config file.
+pub(super) fn enterprise_token_env_vars(
+    host: &str,
+    gh_host: Option<&str>,
+) -> &'static [&'static str] {
+    if gh_host.is_some_and(|gh_host| gh_host.eq_ignore_ascii_case(host)) {
+        ENTERPRISE_TOKEN_VARS
+    } else {
+        &[]
+    }
+}
+
+impl ForgeClient for GitHubClient {
+    fn compare<'a>(
+        &'a self,
+        input: &'a GitInput,
+        base: &'a str,
+        head: &'a str,
+    ) -> ForgeFuture<'a, Option<CommitCount>> {
+        Box::pin(async move {
+            let Some(api) = self.repo_api(input) else {
+                return Ok(None);
+            };
+            // Only `ahead_by` is read; a one-commit page keeps the commit
+            // list out of the response.
+            let url = format!("{}/compare/{}...{}?per_page=1", api.url, base, head);
+            let Some(body) = self.fetch_cached(&api, &url).await? else {
+                return Ok(None);
+            };
+
+            #[derive(Deserialize)]
+            struct CompareResponse {
+                ahead_by: usize,
+            }
+
+            let data: CompareResponse = decode(&body, |body| serde_json::from_slice(body))?;
+            Ok(Some(CommitCount::Exact(data.ahead_by)))
+        })
+    }
+
+    fn diff_stats<'a>(
+        &'a self,
+        input: &'a GitInput,
+        base: &'a str,
+        head: &'a str,
+    ) -> ForgeFuture<'a, Option<DiffStats>> {
+        Box::pin(async move {
+            let Some(api) = self.repo_api(input) else {
+                return Ok(None);
+            };
+            // The same request as `compare`, whose response lists the
+            // changed files whatever the commit page size
+            let url = format!("{}/compare/{}...{}?per_page=1", api.url, base, head);
+            let Some(body) = self.fetch_cached(&api, &url).await? else {
+                return Ok(None);
+            };
+
+            #[derive(Deserialize)]
+            struct GitHubFile {
+                additions: usize,
+                deletions: usize,
+            }
+
+            #[derive(Deserialize)]
+            struct CompareResponse {
+                #[serde(default)]
+                files: Vec<GitHubFile>,
+            }
+
+            let data: CompareResponse = decode(&body, |body| serde_json::from_slice(body))?;
+            Ok(Some(DiffStats {
+                files_changed: data.files.len(),
+                insertions: data.files.iter().map(|f| f.additions).sum(),
+                deletions: data.files.iter().map(|f| f.deletions).sum(),
+                partial: data.files.len() >= COMPARE_FILES_LIMIT,
+            }))
+        })
+    }
+
+    fn changelog<'a>(
+        &'a self,
+        input: &'a GitInput,
+        limits: CommitLimits,
+        on_page: PageSink<'a>,
+    ) -> ForgeFuture<'a, Option<ChangelogData>> {
+        Box::pin(self.get_changelog(input, limits, on_page))
+    }
+
+    fn tags<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<Vec<Tag>>> {
+        Box::pin(async move {
+            let tags: Option<Vec<NamedTag>> = self.listing(input, "tags").await?;
+            Ok(tags.map(|tags| tags.into_iter().map(Tag::from).collect()))
+        })
+    }
+
+    fn branches<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<Vec<String>>> {
+        Box::pin(async move {
+            let names: Option<Vec<Named>> = self.listing(input, "branches").await?;
+            Ok(names.map(|names| names.into_iter().map(|n| n.name).collect()))
+        })
+    }
+
+    fn commit_detail<'a>(
+        &'a self,
+        input: &'a GitInput,
+        sha: &'a str,
+    ) -> ForgeFuture<'a, Option<CommitDetail>> {
+        Box::pin(async move {
+            let Some(api) = self.repo_api(input) else {
+                return Ok(None);
+            };
+            // Commits never change, so a cached copy always revalidates
+            let url = format!("{}/commits/{}", api.url, sha);
+            let Some(body) = self.fetch_cached(&api, &url).await? else {
+                return Ok(None);
+            };
+
+            #[derive(Deserialize)]
+            struct GitHubAuthor {
+                name: Option<String>,
+                email: Option<String>,
+                date: Option<String>,
+            }
+
+            #[derive(Deserialize)]
+            struct GitHubCommitData {
+                message: String,
+                author: Option<GitHubAuthor>,
+            }
+
+            #[derive(Deserialize)]
+            struct GitHubFile {
+                filename: String,
+                additions: usize,
+                deletions: usize,
+            }
+
+            #[derive(Deserialize)]
+            struct GitHubCommit {
+                sha: String,
+                commit: GitHubCommitData,
+                #[serde(default)]
+                files: Vec<GitHubFile>,
+            }
+
+            let c: GitHubCommit = decode(&body, |body| serde_json::from_slice(body))?;
+            let author = c.commit.author;
+            let date = author
+                .as_ref()
+                .and_then(|a| a.date.as_ref())
+                .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_else(Utc::now);
+            let (name, email) = author.map(|a| (a.name, a.email)).unwrap_or_default();
+            Ok(Some(CommitDetail {
+                sha: c.sha,
+                message: c.commit.message,
+                author: name.unwrap_or_else(|| "Unknown".to_string()),
+                email: email.unwrap_or_default(),
+                date,
+                files: c
+                    .files
+                    .into_iter()
+                    .map(|f| FileChange {
+                        path: f.filename,
+                        insertions: f.additions,
+                        deletions: f.deletions,
+                    })
+                    .collect(),
+            }))
+        })
+    }
+
+    fn release<'a>(
+        &'a self,
+        input: &'a GitInput,
+        tag: &'a str,
+    ) -> ForgeFuture<'a, Option<Release>> {
+        Box::pin(async move {
+            let Some(api) = self.repo_api(input) else {
+                return Ok(None);
+            };
+            // Tags without a published release answer 404
+            let url = format!("{}/releases/tags/{}", api.url, tag);
+            let Some(resp) = self.fetch(&api, &url).await? else {
+                return Ok(None);
+            };
+
+            #[derive(Deserialize)]
+            struct GitHubRelease {
+                name: Option<String>,
+                body: Option<String>,
+                html_url: String,
+            }
+
+            let release: GitHubRelease = json(resp, &self.metrics).await?;
+            Ok(Some(Release {
+                name: release
+                    .name
+                    .filter(|name| !name.trim().is_empty())
+                    .unwrap_or_else(|| tag.to_string()),
+                body: release.body.unwrap_or_default(),
+                url: release.html_url,
+            }))
+        })
+    }
+
+    fn compare_url(&self, input: &GitInput, base: &str, head: &str) -> Option<String> {
+        let (host, owner, repo) = match input.repo() {
+            GitRepo::GitHub { owner, repo } => (GITHUB_HOST, owner, repo),
+            GitRepo::GitHubEnterprise { host, owner, repo } => (host.as_str(), owner, repo),
+            _ => return None,
+        };
+        Some(format!(
+            "https://{}/{}/{}/compare/{}...{}",
+            host, owner, repo, base, head
+        ))
+    }
+
+    fn commit_url(&self, input: &GitInput, sha: &str) -> Option<String> {
+        let (host, owner, repo) = match input.repo() {
+            GitRepo::GitHub { owner, repo } => (GITHUB_HOST, owner, repo),
+            GitRepo::GitHubEnterprise { host, owner, repo } => (host.as_str(), owner, repo),
+            _ => return None,
+        };
+        Some(format!(
+            "https://{}/{}/{}/commit/{}",
+            host, owner, repo, sha
+        ))
+    }
+
+    fn pull_request<'a>(
+        &'a self,
+        input: &'a GitInput,
+        sha: &'a str,
+    ) -> ForgeFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let Some(api) = self.repo_api(input) else {
+                return Ok(None);
+            };
+            let url = format!("{}/commits/{}/pulls", api.url, sha);
+            let Some(resp) = self.fetch(&api, &url).await? else {
+                return Ok(None);
+            };
+
+            #[derive(Deserialize)]
+            struct GitHubPull {
+                html_url: String,
+                merged_at: Option<String>,
+            }
+
+            // A commit can be part of several pull requests; the one that
+            // merged it is the one it came in with.
+            let pulls: Vec<GitHubPull> = json(resp, &self.metrics).await?;
+            let merged = pulls.iter().position(|pull| pull.merged_at.is_some());
+            Ok(pulls
+                .into_iter()
+                .nth(merged.unwrap_or(0))
+                .map(|pull| pull.html_url))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CloneUrl, GitHost, GitRef, GitRev, InputName};
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, header_regex, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn input(repo: GitRepo) -> GitInput {
+        GitInput::new(
+            InputName::new("nixpkgs").unwrap(),
+            repo,
+            None,
+            GitRev::new("abc123").unwrap(),
+            0,
+            String::new(),
+        )
+    }
+
+    fn nixpkgs() -> GitInput {
+        input(GitRepo::github(
+            Owner::new("NixOS").unwrap(),
+            RepoName::new("nixpkgs").unwrap(),
+        ))
+    }
+
+    fn client(server: &MockServer) -> GitHubClient {
+        GitHubClient::new(Client::new(), Some("ghp_test".to_string())).with_api_base(server.uri())
+    }
+
+    fn commits(shas: &[&str]) -> serde_json::Value {
+        shas.iter()
+            .map(|sha| {
+                json!({
+                    "sha": sha,
+                    "commit": {
+                        "message": format!("{} subject\n\nbody", sha),
+                        "author": {"name": "dev", "date": "2026-10-01T12:00:00Z"}
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn limits(per_page: usize, max_commits: usize) -> CommitLimits {
+        CommitLimits {
+            per_page,
+            max_commits,
+            ..CommitLimits::default()
+        }
+    }
+
+    #[test]
+    fn test_compare_url() {
+        let client = GitHubClient::new(Client::new(), None);
+        assert_eq!(
+            client.compare_url(&nixpkgs(), "abc", "def").as_deref(),
+            Some("https://github.com/NixOS/nixpkgs/compare/abc...def")
+        );
+
+        let generic = input(GitRepo::generic(
+            CloneUrl::new("https://example.com/repo.git").unwrap(),
+        ));
+        assert_eq!(client.compare_url(&generic, "abc", "def"), None);
+    }
+
+    #[tokio::test]
+    async fn test_compare_counts_commits_with_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/NixOS/nixpkgs/compare/abc123...HEAD"))
+            .and(query_param("per_page", "1"))
+            .and(header("Authorization", "Bearer ghp_test"))
+            .and(header_regex("accept-encoding", "gzip"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ahead_by": 42})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let count = client(&server).check_updates(&nixpkgs()).await.unwrap();
+        assert_eq!(count, Some(CommitCount::Exact(42)));
+    }
+
+    #[tokio::test]
+    async fn test_diff_stats_sum_the_compared_files() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/NixOS/nixpkgs/compare/abc123...def456"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "ahead_by": 2,
+                "files": [
+                    {"filename": "a.nix", "additions": 3, "deletions": 1},
+                    {"filename": "b.nix", "additions": 0, "deletions": 5}
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let stats = client(&server)
+            .diff_stats(&nixpkgs(), "abc123", "def456")
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            Some(DiffStats {
+                files_changed: 2,
+                insertions: 3,
+                deletions: 6,
+                partial: false,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_diff_stats_at_file_limit_are_partial() {
+        let server = MockServer::start().await;
+        let files: Vec<_> = (0..COMPARE_FILES_LIMIT)
+            .map(|i| json!({"filename": format!("{}.nix", i), "additions": 1, "deletions": 0}))
+            .collect();
+        Mock::given(method("GET"))
+            .and(path("/repos/NixOS/nixpkgs/compare/abc123...def456"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "files": files })))
+            .mount(&server)
+            .await;
+
+        let stats = client(&server)
+            .diff_stats(&nixpkgs(), "abc123", "def456")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.files_changed, COMPARE_FILES_LIMIT);
+        assert!(stats.partial);
+    }
+
+    #[test]
+    fn test_enterprise_token_env_vars_only_for_gh_host() {
+        assert_eq!(
+            enterprise_token_env_vars("GHE.example.com", Some("ghe.example.com")),
+            ENTERPRISE_TOKEN_VARS
+        );
+        assert!(enterprise_token_env_vars("ghe.example.com", Some("other.example.com")).is_empty());
+        assert!(enterprise_token_env_vars("ghe.example.com", None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_enterprise_uses_api_v3_and_host_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/ops/tools/compare/abc123...HEAD"))
+            .and(header("Authorization", "Bearer ghe_test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ahead_by": 3})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut tokens = ForgeTokens::default();
+        tokens.insert("ghe.example.com", "ghe_test");
+        let client = GitHubClient::new(Client::new(), Some("ghp_test".to_string()))
+            .with_tokens(tokens)
+            .with_enterprise_base(server.uri());
+        let tools = input(GitRepo::github_enterprise(
+            GitHost::new("ghe.example.com").unwrap(),
+            Owner::new("ops").unwrap(),
+            RepoName::new("tools").unwrap(),
+        ));
+
+        let count = client.check_updates(&tools).await.unwrap();
+        assert_eq!(count, Some(CommitCount::Exact(3)));
+        assert_eq!(
+            client.compare_url(&tools, "abc", "def").as_deref(),
+            Some("https://ghe.example.com/ops/tools/compare/abc...def")
+        );
+        // Enterprise inputs are left out of the github.com GraphQL batch
+        assert_eq!(client.check_updates_batch(&[tools]).await.unwrap(), [None]);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_is_an_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403).insert_header("x-ratelimit-remaining", "0"))
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        let err = client
+            .compare(&nixpkgs(), "abc123", "HEAD")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            GitError::RateLimited {
+                forge: "GitHub",
+                ..
+            }
+        ));
+        assert!(client
+            .changelog(&nixpkgs(), CommitLimits::default(), &|_, _| {})
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_requests_fall_back() {
+        let server = MockServer::start().await;
+        // A 403 with requests left is a permission problem, not a rate limit.
+        Mock::given(path("/repos/NixOS/nixpkgs/compare/abc123...HEAD"))
+            .respond_with(ResponseTemplate::new(403).insert_header("x-ratelimit-remaining", "12"))
+            .mount(&server)
+            .await;
+        Mock::given(path("/repos/NixOS/nixpkgs/commits"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        assert_eq!(client.check_updates(&nixpkgs()).await.unwrap(), None);
+        let changelog = client
+            .changelog(&nixpkgs(), CommitLimits::default(), &|_, _| {})
+            .await
+            .unwrap();
+        assert!(changelog.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_json_is_a_network_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>"))
+            .mount(&server)
+            .await;
+
+        let err = client(&server)
+            .compare(&nixpkgs(), "abc123", "HEAD")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, GitError::NetworkError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_check_updates_batch_uses_one_graphql_query() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .and(header("authorization", "Bearer ghp_test"))
+            .and(body_partial_json(json!({
+                "variables": {"o0": "NixOS", "h0": "abc123", "r1": "release-24.05"}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {
+                    "r0": {"target": {"compare": {"behindBy": 3}}},
+                    "r1": null
+                },
+                "errors": [{"message": "Could not resolve to a Repository"}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let release = GitInput::new(
+            InputName::new("home-manager").unwrap(),
+            GitRepo::github(
+                Owner::new("nix-community").unwrap(),
+                RepoName::new("home-manager").unwrap(),
+            ),
+            Some(GitRef::new("release-24.05").unwrap()),
+            GitRev::new("def456").unwrap(),
+            0,
+            String::new(),
+        );
+        let gitlab = input(
+            GitRepo::gitlab(None, Owner::new("o").unwrap(), RepoName::new("r").unwrap()).unwrap(),
+        );
+
+        let counts = client(&server)
+            .check_updates_batch(&[nixpkgs(), gitlab, release])
+            .await
+            .unwrap();
+        assert_eq!(counts, [Some(CommitCount::Exact(3)), None, None]);
+
+        let anonymous = GitHubClient::new(Client::new(), None).with_api_base(server.uri());
+        let counts = anonymous.check_updates_batch(&[nixpkgs()]).await.unwrap();
+        assert_eq!(counts, [None]);
+    }
+
+    #[tokio::test]
+    async fn test_changelog_pages_until_locked_commit() {
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/NixOS/nixpkgs/commits"))
+            .and(query_param("page", "1"))
+            .and(query_param("per_page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(commits(&["fff", "eee"])))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/repos/NixOS/nixpkgs/commits"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(commits(&["ddd", "abc123"])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pages = std::sync::Mutex::new(Vec::new());
+        let on_page = |offset: usize, commits: &[Commit]| {
+            let shas = commits.iter().map(|c| c.sha.clone()).collect::<Vec<_>>();
+            pages.lock().unwrap().push((offset, shas));
+        };
+        let data = client(&server)
+            .changelog(&nixpkgs(), limits(2, 10), &on_page)
+            .await
+            .unwrap()
+            .unwrap();
+        // Only pages followed by another request are reported early
+        assert_eq!(
+            pages.into_inner().unwrap(),
+            [(0, vec!["fff".to_string(), "eee".to_string()])]
+        );
+        assert_eq!(data.commits.len(), 4);
+        assert_eq!(data.locked_index(), Some(3));
+        assert_eq!(data.commits[0].message, "fff subject");
+        assert!(data.truncated_at().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_changelog_reads_signature_verification() {
+        let server = MockServer::start().await;
+        let mut body = commits(&["fff", "eee", "ddd", "abc123"]);
+        let verification = [
+            json!({"verified": true, "reason": "valid"}),
+            json!({"verified": false, "reason": "unknown_key"}),
+            json!({"verified": false, "reason": "unsigned"}),
+        ];
+        for (commit, verification) in body.as_array_mut().unwrap().iter_mut().zip(verification) {
+            commit["commit"]["verification"] = verification;
+        }
+        Mock::given(path("/repos/NixOS/nixpkgs/commits"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+
+        let data = client(&server)
+            .changelog(&nixpkgs(), limits(10, 10), &|_, _| {})
+            .await
+            .unwrap()
+            .unwrap();
+        let signatures: Vec<SignatureStatus> = data.commits.iter().map(|c| c.signature).collect();
+        assert_eq!(
+            signatures,
+            [
+                SignatureStatus::Verified,
+                SignatureStatus::Unverified,
+                SignatureStatus::Unsigned,
+                SignatureStatus::Unknown
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_changelog_truncates_at_commit_cap() {
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/NixOS/nixpkgs/commits"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(commits(&["fff", "eee"])))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let data = client(&server)
+            .changelog(&nixpkgs(), limits(2, 4), &|_, _| {})
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.locked_index(), None);
+        assert_eq!(data.truncated_at(), Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_changelog_short_page_is_complete() {
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/NixOS/nixpkgs/commits"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(commits(&["fff"])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let data = client(&server)
+            .changelog(&nixpkgs(), limits(2, 10), &|_, _| {})
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(data.truncated_at().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_commit_detail() {
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/NixOS/nixpkgs/commits/abc123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "sha": "abc123",
+                "commit": {
+                    "message": "ripgrep: 14.1.0 -> 14.1.1\n\nChangelog: ...",
+                    "author": {"name": "dev", "email": "dev@example.com", "date": "2026-10-01T12:00:00Z"}
+                },
+                "files": [
+                    {"filename": "pkgs/by-name/ri/ripgrep/package.nix", "additions": 2, "deletions": 2},
+                    {"filename": "pkgs/by-name/ri/ripgrep/tests.nix", "additions": 5, "deletions": 0}
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let detail = client(&server)
+            .commit_detail(&nixpkgs(), "abc123")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(detail.email, "dev@example.com");
+        assert!(detail.message.ends_with("Changelog: ..."));
+        assert_eq!(
+            detail.stats(),
+            crate::model::DiffStats {
+                files_changed: 2,
+                insertions: 7,
+                deletions: 2,
+                partial: false,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pull_request_that_merged_a_commit() {
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/NixOS/nixpkgs/commits/abc/pulls"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                {"html_url": "https://github.com/NixOS/nixpkgs/pull/12", "merged_at": null},
+                {"html_url": "https://github.com/NixOS/nixpkgs/pull/34", "merged_at": "2026-05-01T12:00:00Z"}
+            ])))
+            .mount(&server)
+            .await;
+        Mock::given(path("/repos/NixOS/nixpkgs/commits/def/pulls"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        assert_eq!(
+            client
+                .pull_request(&nixpkgs(), "abc")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("https://github.com/NixOS/nixpkgs/pull/34")
+        );
+        assert_eq!(client.pull_request(&nixpkgs(), "def").await.unwrap(), None);
+        assert_eq!(
+            client.commit_url(&nixpkgs(), "def").as_deref(),
+            Some("https://github.com/NixOS/nixpkgs/commit/def")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_release_notes() {
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/NixOS/nixpkgs/releases/tags/26.05"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": null,
+                "body": "## Highlights",
+                "html_url": "https://github.com/NixOS/nixpkgs/releases/tag/26.05"
+            })))
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        let release = client.release(&nixpkgs(), "26.05").await.unwrap().unwrap();
+        assert_eq!(release.name, "26.05");
+        assert_eq!(release.body, "## Highlights");
+        // No release for this tag: the mock server answers 404
+        assert_eq!(client.release(&nixpkgs(), "25.11").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_tags_and_branches() {
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/NixOS/nixpkgs/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                {"name": "26.05", "commit": {"sha": "aaa"}},
+                {"name": "25.11", "commit": {"sha": "bbb"}}
+            ])))
+            .mount(&server)
+            .await;
+        Mock::given(path("/repos/NixOS/nixpkgs/branches"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{"name": "master"}])))
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        assert_eq!(
+            client.tags(&nixpkgs()).await.unwrap(),
+            Some(vec![
+                Tag {
+                    name: "26.05".to_string(),
+                    sha: "aaa".to_string()
+                },
+                Tag {
+                    name: "25.11".to_string(),
+                    sha: "bbb".to_string()
+                }
+            ])
+        );
+        assert_eq!(
+            client.branches(&nixpkgs()).await.unwrap(),
+            Some(vec!["master".to_string()])
+        );
+    }
+}
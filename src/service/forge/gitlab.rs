@@ -0,0 +1,546 @@
+//! GitLab REST API client
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
+use tracing::warn;
+
+use super::{
+    changelog_data, decode, json, parse_items, rate_limit_wait, send, urlencoding,
+    with_host_headers, ForgeClient, ForgeFuture, Named, NamedTag, PageSink, ResponseCache,
+};
+use crate::config::{CommitLimits, ForgeTokens, HostHeaders};
+use crate::error::GitError;
+use crate::model::{
+    ChangelogData, Commit, CommitCount, GitHost, GitInput, GitRepo, SignatureStatus, Tag,
+};
+use crate::service::CacheMetrics;
+
+const GITLAB_HOST: &str = "gitlab.com";
+
+/// Client for gitlab.com and self-hosted GitLab inputs
+#[derive(Clone)]
+pub struct GitLabClient {
+    client: Client,
+    /// API tokens, keyed by host
+    tokens: ForgeTokens,
+    /// Replaces `https://<host>` in API URLs
+    api_base: Option<String>,
+    /// Revalidated compare and commit responses
+    cache: ResponseCache,
+    metrics: Arc<CacheMetrics>,
+    headers: HostHeaders,
+}
+
+impl GitLabClient {
+    pub fn new(client: Client, tokens: ForgeTokens) -> Self {
+        Self {
+            client,
+            tokens,
+            api_base: None,
+            cache: ResponseCache::default(),
+            metrics: Arc::default(),
+            headers: HostHeaders::default(),
+        }
+    }
+
+    /// Send the headers configured for each input's host with its requests
+    pub fn with_headers(mut self, headers: HostHeaders) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Revalidate compare and commit responses kept in `cache` instead of
+    /// downloading them again
+    pub fn with_response_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Count requests and response bytes in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<CacheMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Send requests to `api_base` instead of each input's host
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = Some(api_base.into());
+        self
+    }
+
+    /// Project API URL for `input`, if it is hosted on GitLab
+    fn project_url<'a>(&self, input: &'a GitInput) -> Option<(&'a GitHost, String)> {
+        let GitRepo::GitLab { host, owner, repo } = input.repo() else {
+            return None;
+        };
+        let base = match &self.api_base {
+            Some(base) => base.clone(),
+            None => format!("https://{}", host),
+        };
+        let project = urlencoding(&format!("{}/{}", owner, repo));
+        Some((host, format!("{}/api/v4/projects/{}", base, project)))
+    }
+
+    /// GET request for `url` with the headers and token for `host`
+    fn request(&self, host: &GitHost, url: &str) -> RequestBuilder {
+        let req = with_host_headers(self.client.get(url), &self.headers, host.as_str());
+        match self
+            .tokens
+            .resolve(host.as_str(), token_env_vars(host.as_str()))
+        {
+            Some(token) => req.header("PRIVATE-TOKEN", token),
+            None => req,
+        }
+    }
+
+    /// Send a GET request, returning `None` for unsuccessful responses and
+    /// an error once the rate limit is exhausted
+    async fn fetch(&self, host: &GitHost, url: &str) -> Result<Option<Response>, GitError> {
+        let resp = self.send_limited(self.request(host, url)).await?;
+        Ok(resp.status().is_success().then_some(resp))
+    }
+
+    /// Like [`GitLabClient::fetch`], but revalidating a cached body with its
+    /// ETag
+    async fn fetch_cached(&self, host: &GitHost, url: &str) -> Result<Option<Vec<u8>>, GitError> {
+        let req = self.cache.conditional(self.request(host, url), url).await;
+        let resp = self.send_limited(req).await?;
+        if !ResponseCache::is_usable(&resp) {
+            return Ok(None);
+        }
+        self.cache.body(url, resp, &self.metrics).await.map(Some)
+    }
+
+    async fn send_limited(&self, req: RequestBuilder) -> Result<Response, GitError> {
+        let resp = send(req, &self.metrics).await?;
+        if resp.status().as_u16() == 429 {
+            let retry_after = rate_limit_wait(resp.headers());
+            warn!(url = %resp.url(), ?retry_after, "GitLab API rate limit exceeded");
+            return Err(GitError::RateLimited {
+                forge: "GitLab",
+                retry_after,
+            });
+        }
+        Ok(resp)
+    }
+
+    /// The `kind` listing (`tags` or `branches`) of the input's repository
+    async fn listing<T: DeserializeOwned>(
+        &self,
+        input: &GitInput,
+        kind: &str,
+    ) -> Result<Option<Vec<T>>, GitError> {
+        let Some((host, project_url)) = self.project_url(input) else {
+            return Ok(None);
+        };
+        let url = format!("{}/repository/{}?per_page=100", project_url, kind);
+        let Some(resp) = self.fetch(host, &url).await? else {
+            return Ok(None);
+        };
+        Ok(Some(json(resp, &self.metrics).await?))
+    }
+
+    async fn get_changelog(
+        &self,
+        input: &GitInput,
+        limits: CommitLimits,
+    ) -> Result<Option<ChangelogData>, GitError> {
+        let Some((host, project_url)) = self.project_url(input) else {
+            return Ok(None);
+        };
+        let branch = input.reference().unwrap_or("HEAD");
+        let per_page = limits.api_page_size();
+        let url = format!(
+            "{}/repository/commits?ref_name={}&per_page={}",
+            project_url, branch, per_page
+        );
+
+        let Some(body) = self.fetch_cached(host, &url).await? else {
+            return Ok(None);
+        };
+
+        #[derive(Deserialize)]
+        struct GitLabCommit {
+            id: String,
+            title: String,
+            author_name: String,
+            created_at: String,
+        }
+
+        let to_commit = |c: GitLabCommit| Commit {
+            date: chrono::DateTime::parse_from_rfc3339(&c.created_at)
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            sha: c.id,
+            message: c.title,
+            author: c.author_name,
+            signature: SignatureStatus::Unknown,
+        };
+        let commits = decode(&body, |body| parse_items(body, to_commit))?;
+        let full_page = commits.len() >= per_page;
+
+        changelog_data(commits, input.rev(), full_page.then_some(per_page)).map(Some)
+    }
+}
+
+/// Environment variables holding a token for `host`. Only gitlab.com has
+/// one; tokens for self-hosted instances come from the config file.
+pub(super) fn token_env_vars(host: &str) -> &'static [&'static str] {
+    if host.eq_ignore_ascii_case(GITLAB_HOST) {
+        &["GITLAB_TOKEN"]
+    } else {
+        &[]
+    }
+}
+
+impl ForgeClient for GitLabClient {
+    fn compare<'a>(
+        &'a self,
+        input: &'a GitInput,
+        base: &'a str,
+        head: &'a str,
+    ) -> ForgeFuture<'a, Option<CommitCount>> {
+        Box::pin(async move {
+            let Some((host, project_url)) = self.project_url(input) else {
+                return Ok(None);
+            };
+            let url = format!(
+                "{}/repository/compare?from={}&to={}",
+                project_url, base, head
+            );
+            let Some(body) = self.fetch_cached(host, &url).await? else {
+                return Ok(None);
+            };
+
+            // Only the number of commits is needed; skip their contents.
+            #[derive(Deserialize)]
+            struct CompareResponse {
+                commits: Vec<IgnoredAny>,
+            }
+
+            let data: CompareResponse = decode(&body, |body| serde_json::from_slice(body))?;
+            Ok(Some(CommitCount::Exact(data.commits.len())))
+        })
+    }
+
+    fn changelog<'a>(
+        &'a self,
+        input: &'a GitInput,
+        limits: CommitLimits,
+        _on_page: PageSink<'a>,
+    ) -> ForgeFuture<'a, Option<ChangelogData>> {
+        // A single page is fetched, so there is nothing to show early
+        Box::pin(self.get_changelog(input, limits))
+    }
+
+    fn tags<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<Vec<Tag>>> {
+        Box::pin(async move {
+            let tags: Option<Vec<NamedTag>> = self.listing(input, "tags").await?;
+            Ok(tags.map(|tags| tags.into_iter().map(Tag::from).collect()))
+        })
+    }
+
+    fn branches<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<Vec<String>>> {
+        Box::pin(async move {
+            let names: Option<Vec<Named>> = self.listing(input, "branches").await?;
+            Ok(names.map(|names| names.into_iter().map(|n| n.name).collect()))
+        })
+    }
+
+    fn compare_url(&self, input: &GitInput, base: &str, head: &str) -> Option<String> {
+        let GitRepo::GitLab { host, owner, repo } = input.repo() else {
+            return None;
+        };
+        Some(format!(
+            "https://{}/{}/{}/-/compare/{}...{}",
+            host, owner, repo, base, head
+        ))
+    }
+
+    fn commit_url(&self, input: &GitInput, sha: &str) -> Option<String> {
+        let GitRepo::GitLab { host, owner, repo } = input.repo() else {
+            return None;
+        };
+        Some(format!(
+            "https://{}/{}/{}/-/commit/{}",
+            host, owner, repo, sha
+        ))
+    }
+
+    fn pull_request<'a>(
+        &'a self,
+        input: &'a GitInput,
+        sha: &'a str,
+    ) -> ForgeFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let Some((host, project_url)) = self.project_url(input) else {
+                return Ok(None);
+            };
+            let url = format!("{}/repository/commits/{}/merge_requests", project_url, sha);
+            let Some(resp) = self.fetch(host, &url).await? else {
+                return Ok(None);
+            };
+
+            #[derive(Deserialize)]
+            struct MergeRequest {
+                web_url: String,
+                state: String,
+            }
+
+            let requests: Vec<MergeRequest> = json(resp, &self.metrics).await?;
+            let merged = requests.iter().position(|mr| mr.state == "merged");
+            Ok(requests
+                .into_iter()
+                .nth(merged.unwrap_or(0))
+                .map(|mr| mr.web_url))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GitRev, InputName, Owner, RepoName};
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const PROJECT: &str = "/api/v4/projects/group%2Ftools";
+
+    fn input() -> GitInput {
+        GitInput::new(
+            InputName::new("tools").unwrap(),
+            GitRepo::gitlab(
+                Some(GitHost::new("gitlab.example.com").unwrap()),
+                Owner::new("group").unwrap(),
+                RepoName::new("tools").unwrap(),
+            )
+            .unwrap(),
+            None,
+            GitRev::new("abc123").unwrap(),
+            0,
+            String::new(),
+        )
+    }
+
+    fn client(server: &MockServer) -> GitLabClient {
+        let mut tokens = ForgeTokens::default();
+        tokens.insert("gitlab.example.com", "glpat_test".to_string());
+        GitLabClient::new(Client::new(), tokens).with_api_base(server.uri())
+    }
+
+    fn commits(ids: &[&str]) -> serde_json::Value {
+        ids.iter()
+            .map(|id| {
+                json!({
+                    "id": id,
+                    "title": format!("{} subject", id),
+                    "author_name": "dev",
+                    "created_at": "2026-10-01T12:00:00Z"
+                })
+            })
+            .collect()
+    }
+
+    fn limits(per_page: usize) -> CommitLimits {
+        CommitLimits {
+            per_page,
+            ..CommitLimits::default()
+        }
+    }
+
+    #[test]
+    fn test_project_url() {
+        let client = GitLabClient::new(Client::new(), ForgeTokens::default());
+        let input = input();
+        let (host, url) = client.project_url(&input).unwrap();
+        assert_eq!(host.as_str(), "gitlab.example.com");
+        assert_eq!(
+            url,
+            "https://gitlab.example.com/api/v4/projects/group%2Ftools"
+        );
+
+        let client = client.with_api_base("http://127.0.0.1:8080");
+        let (_, url) = client.project_url(&input).unwrap();
+        assert_eq!(url, "http://127.0.0.1:8080/api/v4/projects/group%2Ftools");
+    }
+
+    #[test]
+    fn test_gitlab_token_env_var() {
+        assert_eq!(token_env_vars("GitLab.com"), ["GITLAB_TOKEN"]);
+        assert!(token_env_vars("gitlab.example.com").is_empty());
+    }
+
+    #[test]
+    fn test_compare_url() {
+        let client = GitLabClient::new(Client::new(), ForgeTokens::default());
+        assert_eq!(
+            client.compare_url(&input(), "abc", "def").as_deref(),
+            Some("https://gitlab.example.com/group/tools/-/compare/abc...def")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compare_counts_commits_with_host_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("{}/repository/compare", PROJECT)))
+            .and(query_param("from", "abc123"))
+            .and(query_param("to", "HEAD"))
+            .and(header("PRIVATE-TOKEN", "glpat_test"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"commits": commits(&["b", "a"])})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let count = client(&server).check_updates(&input()).await.unwrap();
+        assert_eq!(count, Some(CommitCount::Exact(2)));
+    }
+
+    #[tokio::test]
+    async fn test_sends_configured_host_headers() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!("{}/repository/tags", PROJECT)))
+            .and(header("X-Client", "melt"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut headers = HostHeaders::default();
+        headers.insert("gitlab.example.com", "X-Client", "melt");
+        headers.insert("other.example.com", "X-Other", "no");
+        let client = client(&server).with_headers(headers);
+        assert_eq!(client.tags(&input()).await.unwrap(), Some(Vec::new()));
+    }
+
+    #[tokio::test]
+    async fn test_errors_fall_back_and_bad_json_fails() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!("{}/repository/compare", PROJECT)))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        Mock::given(path(format!("{}/repository/commits", PROJECT)))
+            .respond_with(ResponseTemplate::new(200).set_body_string("not json"))
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        assert_eq!(client.check_updates(&input()).await.unwrap(), None);
+        let err = client
+            .changelog(&input(), limits(10), &|_, _| {})
+            .await
+            .unwrap_err();
+        assert!(matches!(err, GitError::NetworkError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_reports_wait() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!("{}/repository/compare", PROJECT)))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "42"))
+            .mount(&server)
+            .await;
+
+        let err = client(&server).check_updates(&input()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            GitError::RateLimited { forge: "GitLab", retry_after } if retry_after.as_secs() == 42
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_changelog_finds_locked_commit() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!("{}/repository/commits", PROJECT)))
+            .and(query_param("ref_name", "HEAD"))
+            .and(query_param("per_page", "3"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(commits(&["ccc", "abc123"])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let data = client(&server)
+            .changelog(&input(), limits(3), &|_, _| {})
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.locked_index(), Some(1));
+        assert_eq!(data.commits[0].message, "ccc subject");
+        assert!(data.truncated_at().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_changelog_full_page_without_locked_commit_is_truncated() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!("{}/repository/commits", PROJECT)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(commits(&["ccc", "bbb"])))
+            .mount(&server)
+            .await;
+
+        let data = client(&server)
+            .changelog(&input(), limits(2), &|_, _| {})
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.locked_index(), None);
+        assert_eq!(data.truncated_at(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_tags() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!("{}/repository/tags", PROJECT)))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!([{"name": "v1.2.0", "commit": {"id": "ccc"}}])),
+            )
+            .mount(&server)
+            .await;
+
+        assert_eq!(
+            client(&server).tags(&input()).await.unwrap(),
+            Some(vec![Tag {
+                name: "v1.2.0".to_string(),
+                sha: "ccc".to_string()
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_merge_request_that_merged_a_commit() {
+        let server = MockServer::start().await;
+        Mock::given(path(format!(
+            "{}/repository/commits/abc/merge_requests",
+            PROJECT
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"web_url": "https://gitlab.example.com/group/tools/-/merge_requests/3", "state": "closed"},
+            {"web_url": "https://gitlab.example.com/group/tools/-/merge_requests/4", "state": "merged"}
+        ])))
+        .mount(&server)
+        .await;
+
+        let client = client(&server);
+        assert_eq!(
+            client
+                .pull_request(&input(), "abc")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("https://gitlab.example.com/group/tools/-/merge_requests/4")
+        );
+        assert_eq!(client.pull_request(&input(), "def").await.unwrap(), None);
+        assert_eq!(
+            client.commit_url(&input(), "def").as_deref(),
+            Some("https://gitlab.example.com/group/tools/-/commit/def")
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! Forge API clients
+//!
+//! Each forge with a usable HTTP API gets one [`ForgeClient`]. Clients only
+//! answer what their API can; `Ok(None)` tells [`GitService`](super::GitService)
+//! to fall back to a local clone.
+
+mod cache;
+mod gitea;
+mod github;
+mod gitlab;
+mod sourcehut;
+
+use std::fmt;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Response};
+use serde::de::{DeserializeOwned, Deserializer as _, SeqAccess, Visitor};
+use serde::Deserialize;
+
+use crate::config::{CommitLimits, HostHeaders};
+use crate::error::GitError;
+use crate::model::{
+    ChangelogData, Commit, CommitCount, CommitDetail, DiffStats, GitInput, GitRepo, Release, Tag,
+};
+use crate::service::CacheMetrics;
+
+pub use cache::ResponseCache;
+pub use gitea::GiteaClient;
+pub use github::GitHubClient;
+pub use gitlab::GitLabClient;
+pub use sourcehut::SourceHutClient;
+
+/// Host a token for `repo` is configured under in `[tokens]`, and the
+/// environment variables it is read from otherwise. `None` for repositories
+/// no forge API serves.
+pub fn token_lookup(repo: &GitRepo) -> Option<(&str, &'static [&'static str])> {
+    match repo {
+        GitRepo::GitHub { .. } => Some(("github.com", &["GITHUB_TOKEN", "GH_TOKEN"])),
+        GitRepo::GitHubEnterprise { host, .. } => {
+            let gh_host = std::env::var("GH_HOST").ok();
+            let vars = github::enterprise_token_env_vars(host.as_str(), gh_host.as_deref());
+            Some((host.as_str(), vars))
+        }
+        GitRepo::GitLab { host, .. } => {
+            Some((host.as_str(), gitlab::token_env_vars(host.as_str())))
+        }
+        GitRepo::Codeberg { .. } => Some((
+            gitea::CODEBERG_HOST,
+            gitea::token_env_vars(gitea::CODEBERG_HOST),
+        )),
+        GitRepo::Gitea { host, .. } => Some((host.as_str(), gitea::token_env_vars(host.as_str()))),
+        GitRepo::SourceHut { host, .. } => {
+            Some((host.as_str(), sourcehut::token_env_vars(host.as_str())))
+        }
+        GitRepo::Generic { .. } => None,
+    }
+}
+
+/// Future returned by [`ForgeClient`] methods
+pub type ForgeFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, GitError>> + Send + 'a>>;
+
+/// Called with each page of changelog commits before the next is requested:
+/// the position of the page's first commit in the full list, then its commits
+pub type PageSink<'a> = &'a (dyn Fn(usize, &[Commit]) + Send + Sync);
+
+/// Read access to one forge's HTTP API.
+///
+/// Inputs hosted elsewhere, and requests the API cannot answer, return
+/// `Ok(None)`; errors are reserved for failures a clone would not fix, such
+/// as network errors and rate limits.
+pub trait ForgeClient: Send + Sync {
+    /// Number of commits `head` is ahead of `base`
+    fn compare<'a>(
+        &'a self,
+        input: &'a GitInput,
+        base: &'a str,
+        head: &'a str,
+    ) -> ForgeFuture<'a, Option<CommitCount>>;
+
+    /// Files and lines changed from `base` to `head`
+    fn diff_stats<'a>(
+        &'a self,
+        input: &'a GitInput,
+        base: &'a str,
+        head: &'a str,
+    ) -> ForgeFuture<'a, Option<DiffStats>> {
+        let _ = (input, base, head);
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Number of commits on the tracked branch since the locked revision
+    fn check_updates<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<CommitCount>> {
+        self.compare(input, input.rev(), input.reference().unwrap_or("HEAD"))
+    }
+
+    /// Commits from the branch head back to, and past, the locked revision.
+    ///
+    /// Clients that page through history pass each page to `on_page` so
+    /// the changelog can be shown before the last one arrives.
+    fn changelog<'a>(
+        &'a self,
+        input: &'a GitInput,
+        limits: CommitLimits,
+        on_page: PageSink<'a>,
+    ) -> ForgeFuture<'a, Option<ChangelogData>>;
+
+    /// Tags and their commits, newest first as the forge orders them
+    fn tags<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<Vec<Tag>>>;
+
+    /// Branch names
+    fn branches<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<Vec<String>>>;
+
+    /// Full message and changed files of the commit `sha`
+    fn commit_detail<'a>(
+        &'a self,
+        input: &'a GitInput,
+        sha: &'a str,
+    ) -> ForgeFuture<'a, Option<CommitDetail>> {
+        let _ = (input, sha);
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Release published for `tag`, if the forge has one
+    fn release<'a>(
+        &'a self,
+        input: &'a GitInput,
+        tag: &'a str,
+    ) -> ForgeFuture<'a, Option<Release>> {
+        let _ = (input, tag);
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Web page comparing `base` to `head`
+    fn compare_url(&self, input: &GitInput, base: &str, head: &str) -> Option<String>;
+
+    /// Web page of commit `sha`
+    fn commit_url(&self, input: &GitInput, sha: &str) -> Option<String>;
+
+    /// Web page of the pull or merge request that brought in commit `sha`,
+    /// as the API reports it
+    fn pull_request<'a>(
+        &'a self,
+        input: &'a GitInput,
+        sha: &'a str,
+    ) -> ForgeFuture<'a, Option<String>> {
+        let _ = (input, sha);
+        Box::pin(async { Ok(None) })
+    }
+}
+
+/// Tag or branch entry; both APIs name it the same way
+#[derive(Deserialize)]
+struct Named {
+    name: String,
+}
+
+/// Tag entry with its commit, which GitLab calls `id` and the others `sha`
+#[derive(Deserialize)]
+struct NamedTag {
+    name: String,
+    commit: TagCommit,
+}
+
+#[derive(Deserialize)]
+struct TagCommit {
+    #[serde(alias = "id")]
+    sha: String,
+}
+
+impl From<NamedTag> for Tag {
+    fn from(tag: NamedTag) -> Self {
+        Tag {
+            name: tag.name,
+            sha: tag.commit.sha,
+        }
+    }
+}
+
+/// Add the headers configured for `host` to `req`
+pub(super) fn with_host_headers(
+    req: RequestBuilder,
+    headers: &HostHeaders,
+    host: &str,
+) -> RequestBuilder {
+    headers
+        .get(host)
+        .iter()
+        .fold(req, |req, (name, value)| req.header(name, value))
+}
+
+async fn send(req: RequestBuilder, metrics: &CacheMetrics) -> Result<Response, GitError> {
+    metrics.record_api_request();
+    req.send()
+        .await
+        .map_err(|e| GitError::NetworkError(e.to_string()))
+}
+
+/// Wait before retrying when a rate-limited response names no reset time
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// How long a rate-limited response asks clients to wait, from
+/// `Retry-After` or the reset time in `x-ratelimit-reset` (GitHub) or
+/// `ratelimit-reset` (GitLab)
+fn rate_limit_wait(headers: &HeaderMap) -> Duration {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(retry_after) = header("retry-after") {
+        if let Ok(secs) = retry_after.trim().parse::<u64>() {
+            return Duration::from_secs(secs);
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(retry_after) {
+            return until(date.timestamp());
+        }
+    }
+    header("x-ratelimit-reset")
+        .or_else(|| header("ratelimit-reset"))
+        .and_then(|reset| reset.trim().parse::<i64>().ok())
+        .map_or(DEFAULT_RATE_LIMIT_WAIT, until)
+}
+
+/// Time left until the Unix timestamp `reset`, at least one second
+fn until(reset: i64) -> Duration {
+    let secs = reset - chrono::Utc::now().timestamp();
+    Duration::from_secs(secs.max(1) as u64)
+}
+
+/// Read the response body, recording its size, and parse it with `parse`
+async fn parse_body<R>(
+    resp: Response,
+    metrics: &CacheMetrics,
+    parse: impl FnOnce(&[u8]) -> serde_json::Result<R>,
+) -> Result<R, GitError> {
+    let body = resp
+        .bytes()
+        .await
+        .map_err(|e| GitError::NetworkError(e.to_string()))?;
+    metrics.record_api_bytes(body.len() as u64);
+    decode(&body, parse)
+}
+
+/// Parse a response body that was already read, e.g. by [`ResponseCache`]
+fn decode<R>(
+    body: &[u8],
+    parse: impl FnOnce(&[u8]) -> serde_json::Result<R>,
+) -> Result<R, GitError> {
+    parse(body).map_err(|e| GitError::NetworkError(e.to_string()))
+}
+
+async fn json<T: DeserializeOwned>(resp: Response, metrics: &CacheMetrics) -> Result<T, GitError> {
+    parse_body(resp, metrics, |body| serde_json::from_slice(body)).await
+}
+
+/// Read a JSON array, converting each element as soon as it is parsed.
+///
+/// Only one raw element is alive at a time, so commit pages never hold
+/// both the API structs and the converted commits.
+async fn json_items<T, U>(
+    resp: Response,
+    metrics: &CacheMetrics,
+    convert: impl FnMut(T) -> U,
+) -> Result<Vec<U>, GitError>
+where
+    T: DeserializeOwned,
+{
+    parse_body(resp, metrics, |body| parse_items(body, convert)).await
+}
+
+fn parse_items<T, U>(body: &[u8], convert: impl FnMut(T) -> U) -> serde_json::Result<Vec<U>>
+where
+    T: DeserializeOwned,
+{
+    struct Items<T, F> {
+        convert: F,
+        item: PhantomData<fn() -> T>,
+    }
+
+    impl<'de, T, U, F> Visitor<'de> for Items<T, F>
+    where
+        T: Deserialize<'de>,
+        F: FnMut(T) -> U,
+    {
+        type Value = Vec<U>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a JSON array")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<Vec<U>, A::Error> {
+            let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(item) = seq.next_element()? {
+                items.push((self.convert)(item));
+            }
+            Ok(items)
+        }
+    }
+
+    let mut de = serde_json::Deserializer::from_slice(body);
+    let items = (&mut de).deserialize_seq(Items {
+        convert,
+        item: PhantomData,
+    })?;
+    de.end()?;
+    Ok(items)
+}
+
+/// Build changelog data from API commits, newest first.
+///
+/// `truncated_at` is the number of commits fetched when the locked revision
+/// may lie beyond them.
+fn changelog_data(
+    commits: Vec<Commit>,
+    rev: &str,
+    truncated_at: Option<usize>,
+) -> Result<ChangelogData, GitError> {
+    let locked_idx = commits.iter().rposition(|c| c.sha.starts_with(rev));
+    let data = ChangelogData::new(commits, locked_idx)
+        .map_err(|e| GitError::CloneFailed(format!("Invalid changelog data: {:?}", e)))?;
+    Ok(match truncated_at {
+        Some(fetched) if locked_idx.is_none() => data.with_truncation(fetched),
+        _ => data,
+    })
+}
+
+/// Simple URL encoding for project paths
+fn urlencoding(s: &str) -> String {
+    s.replace('/', "%2F")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn commit(sha: &str) -> Commit {
+        Commit {
+            sha: sha.to_string(),
+            message: String::new(),
+            author: String::new(),
+            date: Utc::now(),
+            signature: crate::model::SignatureStatus::Unknown,
+        }
+    }
+
+    #[test]
+    fn test_urlencoding() {
+        assert_eq!(urlencoding("owner/repo"), "owner%2Frepo");
+        assert_eq!(urlencoding("simple"), "simple");
+    }
+
+    #[test]
+    fn test_rate_limit_wait() {
+        let headers = |pairs: &[(&'static str, String)]| {
+            let mut map = HeaderMap::new();
+            for (name, value) in pairs {
+                map.insert(*name, value.parse().unwrap());
+            }
+            map
+        };
+
+        let retry_after = headers(&[("retry-after", "30".to_string())]);
+        assert_eq!(rate_limit_wait(&retry_after), Duration::from_secs(30));
+
+        let reset = (Utc::now().timestamp() + 120).to_string();
+        let wait = rate_limit_wait(&headers(&[("x-ratelimit-reset", reset)]));
+        assert!(wait > Duration::from_secs(110) && wait <= Duration::from_secs(120));
+
+        let past = headers(&[("ratelimit-reset", "1".to_string())]);
+        assert_eq!(rate_limit_wait(&past), Duration::from_secs(1));
+        assert_eq!(rate_limit_wait(&HeaderMap::new()), DEFAULT_RATE_LIMIT_WAIT);
+    }
+
+    #[test]
+    fn test_parse_items_converts_each_element() {
+        let names = parse_items(br#"[{"name":"a"},{"name":"b","extra":1}]"#, |n: Named| {
+            n.name.to_uppercase()
+        })
+        .unwrap();
+        assert_eq!(names, ["A", "B"]);
+
+        assert!(parse_items(br#"{"name":"a"}"#, |n: Named| n.name).is_err());
+        assert!(parse_items(br#"[] []"#, |n: Named| n.name).is_err());
+    }
+
+    #[test]
+    fn test_changelog_data_truncation() {
+        let found = changelog_data(vec![commit("aaa"), commit("bbb")], "bb", Some(2)).unwrap();
+        assert!(found.truncated_at().is_none());
+
+        let missing = changelog_data(vec![commit("aaa"), commit("bbb")], "cc", Some(2)).unwrap();
+        assert_eq!(missing.truncated_at(), Some(2));
+
+        let complete = changelog_data(vec![commit("aaa")], "cc", None).unwrap();
+        assert!(complete.truncated_at().is_none());
+    }
+}
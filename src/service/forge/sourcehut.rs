@@ -0,0 +1,494 @@
+//! SourceHut GraphQL API client for git.sr.ht
+//!
+//! The git.sr.ht API only answers authenticated requests, so inputs are
+//! queried only when a token is configured; otherwise they are cloned.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json as json_value;
+
+use super::{changelog_data, json, send, with_host_headers, ForgeClient, ForgeFuture, PageSink};
+use crate::config::{CommitLimits, ForgeTokens, HostHeaders};
+use crate::error::GitError;
+use crate::model::{ChangelogData, Commit, CommitCount, GitInput, GitRepo, SignatureStatus, Tag};
+use crate::service::CacheMetrics;
+
+const SOURCEHUT_HOST: &str = "git.sr.ht";
+
+/// Log pages walked by a comparison before falling back to a clone
+const MAX_COMPARE_PAGES: usize = 5;
+
+const LOG_QUERY: &str = "query($owner: String!, $repo: String!, $from: String, $cursor: Cursor) {
+  user(username: $owner) { repository(name: $repo) {
+    log(from: $from, cursor: $cursor) { results { id message author { name time } } cursor }
+  } }
+}";
+
+const REFERENCES_QUERY: &str = "query($owner: String!, $repo: String!) {
+  user(username: $owner) { repository(name: $repo) {
+    references { results { name target } }
+  } }
+}";
+
+/// Client for inputs hosted on git.sr.ht or a self-hosted SourceHut
+#[derive(Clone)]
+pub struct SourceHutClient {
+    client: Client,
+    /// API tokens, keyed by host
+    tokens: ForgeTokens,
+    /// Replaces `https://<host>` in API URLs
+    api_base: Option<String>,
+    metrics: Arc<CacheMetrics>,
+    headers: HostHeaders,
+}
+
+#[derive(Deserialize)]
+struct QueryResponse<T> {
+    data: Option<UserData<T>>,
+}
+
+#[derive(Deserialize)]
+struct UserData<T> {
+    user: Option<RepositoryData<T>>,
+}
+
+#[derive(Deserialize)]
+struct RepositoryData<T> {
+    repository: Option<T>,
+}
+
+#[derive(Deserialize)]
+struct LogData {
+    log: LogPage,
+}
+
+#[derive(Deserialize)]
+struct LogPage {
+    results: Vec<SourceHutCommit>,
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SourceHutCommit {
+    id: String,
+    message: String,
+    author: Signature,
+}
+
+#[derive(Deserialize)]
+struct Signature {
+    name: String,
+    time: String,
+}
+
+impl From<SourceHutCommit> for Commit {
+    fn from(c: SourceHutCommit) -> Self {
+        Commit {
+            date: chrono::DateTime::parse_from_rfc3339(&c.author.time)
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            message: c.message.lines().next().unwrap_or("").to_string(),
+            sha: c.id,
+            author: c.author.name,
+            signature: SignatureStatus::Unknown,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ReferencesData {
+    references: ReferencePage,
+}
+
+#[derive(Deserialize)]
+struct ReferencePage {
+    results: Vec<Reference>,
+}
+
+/// A git reference and the object it points at
+#[derive(Deserialize)]
+struct Reference {
+    name: String,
+    target: String,
+}
+
+impl SourceHutClient {
+    pub fn new(client: Client, tokens: ForgeTokens) -> Self {
+        Self {
+            client,
+            tokens,
+            api_base: None,
+            metrics: Arc::default(),
+            headers: HostHeaders::default(),
+        }
+    }
+
+    /// Send requests to `api_base` instead of each input's host
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = Some(api_base.into());
+        self
+    }
+
+    /// Count requests and response bytes in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<CacheMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Send the headers configured for each input's host with its requests
+    pub fn with_headers(mut self, headers: HostHeaders) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Run `query` against the repository of `input`, returning `None` when
+    /// the input is not on SourceHut, no token is set, or the repository
+    /// could not be read
+    async fn query<T: DeserializeOwned>(
+        &self,
+        input: &GitInput,
+        query: &str,
+        mut variables: serde_json::Value,
+    ) -> Result<Option<T>, GitError> {
+        let GitRepo::SourceHut { host, owner, repo } = input.repo() else {
+            return Ok(None);
+        };
+        let host = host.as_str();
+        let Some(token) = self.tokens.resolve(host, token_env_vars(host)) else {
+            return Ok(None);
+        };
+        let base = match &self.api_base {
+            Some(base) => base.clone(),
+            None => format!("https://{}", host),
+        };
+        variables["owner"] = owner.as_str().trim_start_matches('~').into();
+        variables["repo"] = repo.as_str().into();
+
+        let req = with_host_headers(
+            self.client.post(format!("{}/query", base)),
+            &self.headers,
+            host,
+        )
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json_value!({ "query": query, "variables": variables }));
+        let resp = send(req, &self.metrics).await?;
+        if !resp.status().is_success() {
+            return Ok(None);
+        }
+        // GraphQL errors come back as a null `data` or a null repository.
+        let data: QueryResponse<T> = json(resp, &self.metrics).await?;
+        Ok(data.data.and_then(|d| d.user).and_then(|u| u.repository))
+    }
+
+    async fn log_page(
+        &self,
+        input: &GitInput,
+        from: Option<&str>,
+        cursor: Option<&str>,
+    ) -> Result<Option<LogPage>, GitError> {
+        let variables = json_value!({ "from": from, "cursor": cursor });
+        let data: Option<LogData> = self.query(input, LOG_QUERY, variables).await?;
+        Ok(data.map(|d| d.log))
+    }
+
+    async fn count_between(
+        &self,
+        input: &GitInput,
+        base: &str,
+        head: &str,
+    ) -> Result<Option<CommitCount>, GitError> {
+        let mut cursor: Option<String> = None;
+        let mut counted = 0;
+        for _ in 0..MAX_COMPARE_PAGES {
+            let Some(page) = self.log_page(input, Some(head), cursor.as_deref()).await? else {
+                return Ok(None);
+            };
+            if let Some(idx) = page.results.iter().position(|c| c.id.starts_with(base)) {
+                return Ok(Some(CommitCount::Exact(counted + idx)));
+            }
+            counted += page.results.len();
+            cursor = page.cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        // `base` is not an ancestor of `head`, or is too far behind it
+        Ok(None)
+    }
+
+    async fn get_changelog(
+        &self,
+        input: &GitInput,
+        limits: CommitLimits,
+        on_page: PageSink<'_>,
+    ) -> Result<Option<ChangelogData>, GitError> {
+        let mut commits: Vec<Commit> = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut exhausted = false;
+        for _ in 0..limits.max_api_pages.max(1) {
+            let Some(page) = self
+                .log_page(input, input.reference(), cursor.as_deref())
+                .await?
+            else {
+                return Ok(None);
+            };
+
+            let found_locked = page.results.iter().any(|c| c.id.starts_with(input.rev()));
+            let offset = commits.len();
+            commits.extend(page.results.into_iter().map(Commit::from));
+            cursor = page.cursor;
+
+            if cursor.is_none() {
+                exhausted = true;
+                break;
+            }
+            if found_locked || commits.len() >= limits.max_commits {
+                break;
+            }
+            on_page(offset, &commits[offset..]);
+        }
+
+        let fetched = commits.len();
+        changelog_data(commits, input.rev(), (!exhausted).then_some(fetched)).map(Some)
+    }
+
+    /// Reference names under `prefix`, with the prefix removed
+    async fn references(
+        &self,
+        input: &GitInput,
+        prefix: &str,
+    ) -> Result<Option<Vec<Tag>>, GitError> {
+        let data: Option<ReferencesData> =
+            self.query(input, REFERENCES_QUERY, json_value!({})).await?;
+        Ok(data.map(|d| {
+            d.references
+                .results
+                .into_iter()
+                .filter_map(|r| {
+                    let name = r.name.strip_prefix(prefix)?.to_string();
+                    Some(Tag {
+                        name,
+                        sha: r.target,
+                    })
+                })
+                .collect()
+        }))
+    }
+}
+
+/// Environment variables holding a token for `host`. Only git.sr.ht has
+/// one; tokens for other instances come from the config file.
+pub(super) fn token_env_vars(host: &str) -> &'static [&'static str] {
+    if host.eq_ignore_ascii_case(SOURCEHUT_HOST) {
+        &["SRHT_TOKEN"]
+    } else {
+        &[]
+    }
+}
+
+impl ForgeClient for SourceHutClient {
+    fn compare<'a>(
+        &'a self,
+        input: &'a GitInput,
+        base: &'a str,
+        head: &'a str,
+    ) -> ForgeFuture<'a, Option<CommitCount>> {
+        Box::pin(self.count_between(input, base, head))
+    }
+
+    fn check_updates<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<CommitCount>> {
+        Box::pin(async move {
+            let head = input.reference().unwrap_or("HEAD");
+            self.count_between(input, input.rev(), head).await
+        })
+    }
+
+    fn changelog<'a>(
+        &'a self,
+        input: &'a GitInput,
+        limits: CommitLimits,
+        on_page: PageSink<'a>,
+    ) -> ForgeFuture<'a, Option<ChangelogData>> {
+        Box::pin(self.get_changelog(input, limits, on_page))
+    }
+
+    fn tags<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<Vec<Tag>>> {
+        Box::pin(self.references(input, "refs/tags/"))
+    }
+
+    fn branches<'a>(&'a self, input: &'a GitInput) -> ForgeFuture<'a, Option<Vec<String>>> {
+        Box::pin(async move {
+            let branches = self.references(input, "refs/heads/").await?;
+            Ok(branches.map(|refs| refs.into_iter().map(|r| r.name).collect()))
+        })
+    }
+
+    fn compare_url(&self, _input: &GitInput, _base: &str, _head: &str) -> Option<String> {
+        // git.sr.ht has no compare view
+        None
+    }
+
+    fn commit_url(&self, input: &GitInput, sha: &str) -> Option<String> {
+        if !matches!(input.repo(), GitRepo::SourceHut { .. }) {
+            return None;
+        }
+        let repo_url = input.repo().clone_url().ok()?;
+        Some(format!("{}/commit/{}", repo_url, sha))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GitRef, GitRev, InputName, Owner, RepoName};
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn input(reference: Option<&str>) -> GitInput {
+        GitInput::new(
+            InputName::new("hut").unwrap(),
+            GitRepo::sourcehut(
+                None,
+                Owner::new("~emersion").unwrap(),
+                RepoName::new("hut").unwrap(),
+            )
+            .unwrap(),
+            reference.map(|r| GitRef::new(r).unwrap()),
+            GitRev::new("abc123").unwrap(),
+            0,
+            String::new(),
+        )
+    }
+
+    fn client(server: &MockServer) -> SourceHutClient {
+        let mut tokens = ForgeTokens::default();
+        tokens.insert("git.sr.ht", "srht_test".to_string());
+        SourceHutClient::new(Client::new(), tokens).with_api_base(server.uri())
+    }
+
+    fn log(shas: &[&str], cursor: Option<&str>) -> serde_json::Value {
+        let results: Vec<_> = shas
+            .iter()
+            .map(|sha| {
+                json!({
+                    "id": sha,
+                    "message": format!("{} subject\n\nbody", sha),
+                    "author": {"name": "dev", "time": "2026-10-01T12:00:00Z"}
+                })
+            })
+            .collect();
+        json!({"data": {"user": {"repository": {
+            "log": {"results": results, "cursor": cursor}
+        }}}})
+    }
+
+    #[test]
+    fn test_sourcehut_token_env_var() {
+        assert_eq!(token_env_vars("git.sr.ht"), ["SRHT_TOKEN"]);
+        assert!(token_env_vars("git.example.org").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_updates_sends_token_and_walks_log() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/query"))
+            .and(header("Authorization", "Bearer srht_test"))
+            .and(body_partial_json(json!({"variables": {
+                "owner": "emersion", "repo": "hut", "from": "HEAD", "cursor": null
+            }})))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(log(&["fff", "eee"], Some("c1"))),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/query"))
+            .and(body_partial_json(json!({"variables": {"cursor": "c1"}})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(log(&["ddd", "abc123"], None)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let count = client(&server).check_updates(&input(None)).await.unwrap();
+        assert_eq!(count, Some(CommitCount::Exact(3)));
+    }
+
+    #[tokio::test]
+    async fn test_without_token_falls_back() {
+        let server = MockServer::start().await;
+        Mock::given(path("/query"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let client =
+            SourceHutClient::new(Client::new(), ForgeTokens::default()).with_api_base(server.uri());
+        // SRHT_TOKEN only applies to git.sr.ht, so use another host
+        let selfhosted = GitInput::new(
+            InputName::new("hut").unwrap(),
+            GitRepo::sourcehut(
+                Some(crate::model::GitHost::new("git.example.org").unwrap()),
+                Owner::new("~emersion").unwrap(),
+                RepoName::new("hut").unwrap(),
+            )
+            .unwrap(),
+            None,
+            GitRev::new("abc123").unwrap(),
+            0,
+            String::new(),
+        );
+        assert_eq!(client.check_updates(&selfhosted).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_changelog_and_references() {
+        let server = MockServer::start().await;
+        Mock::given(path("/query"))
+            .and(body_partial_json(json!({"variables": {"from": "dev"}})))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(log(&["fff", "abc123", "999"], Some("c1"))),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/query"))
+            .and(body_partial_json(json!({"variables": {}})))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"data": {"user": {
+                    "repository": {"references": {"results": [
+                        {"name": "refs/heads/dev", "target": "fff"},
+                        {"name": "refs/tags/v0.4.0", "target": "abc123"}
+                    ]}}
+                }}})),
+            )
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        let data = client
+            .changelog(&input(Some("dev")), CommitLimits::default(), &|_, _| {})
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.commits.len(), 3);
+        assert_eq!(data.locked_index(), Some(1));
+        assert_eq!(data.commits[0].message, "fff subject");
+
+        assert_eq!(
+            client.tags(&input(None)).await.unwrap(),
+            Some(vec![Tag {
+                name: "v0.4.0".to_string(),
+                sha: "abc123".to_string()
+            }])
+        );
+    }
+}
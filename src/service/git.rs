@@ -1,21 +1,48 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use chrono::{TimeZone, Utc};
-use git2::{Cred, FetchOptions, RemoteCallbacks, Repository};
-use reqwest::Client;
-use serde::Deserialize;
+use git2::{Cred, DiffOptions, FetchOptions, ProxyOptions, RemoteCallbacks, Repository};
+use reqwest::{Client, Proxy};
 use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
-use crate::config::ServiceConfig;
+use super::forge::{
+    with_host_headers, ForgeClient, GitHubClient, GitLabClient, GiteaClient, ResponseCache,
+    SourceHutClient,
+};
+use super::metrics::{CacheMetrics, CacheStats};
+use crate::config::{CommitLimits, HostHeaders, ServiceConfig};
 use crate::error::GitError;
 use crate::model::{
-    ChangelogData, Commit, GitHost, GitInput, GitRepo, InputName, Owner, RepoName, UpdateStatus,
+    ChangelogData, ChangelogPaths, CheckInfo, Commit, CommitCount, CommitDetail, DiffStats,
+    FileChange, GitInput, GitRepo, GitRev, InputName, PathFilter, Release, SignatureStatus, Tag,
+    UpdateStatus,
 };
 
+/// Number of inputs checked per wave in `check_updates_timed`
+const CHECK_WAVE_SIZE: usize = 64;
+
+/// Rounds of checks retried after a forge rate limit resets before the
+/// inputs it held back are reported as errors
+const RATE_LIMIT_RETRIES: usize = 3;
+
+fn update_status(name: &InputName, count: CommitCount) -> UpdateStatus {
+    if count == CommitCount::Exact(0) {
+        UpdateStatus::UpToDate
+    } else {
+        debug!(input = %name, behind = %count, "Updates available");
+        UpdateStatus::Behind(count)
+    }
+}
+
+/// Receives changelog commits as they load: the position of the first
+/// commit in the full list, then the commits
+pub type ChangelogProgress = Arc<dyn Fn(usize, &[Commit]) + Send + Sync>;
+
 /// Service for git operations - uses APIs where possible, falls back to git2
 #[derive(Clone)]
 pub struct GitService {
@@ -23,11 +50,22 @@ pub struct GitService {
     cancel_token: CancellationToken,
     /// Semaphore to limit concurrent operations
     semaphore: Arc<Semaphore>,
-    /// HTTP client for API requests
+    /// HTTP client for host probes
     client: Client,
-    /// GitHub token for API authentication (optional)
-    github_token: Option<String>,
+    github: GitHubClient,
+    gitlab: GitLabClient,
+    gitea: GiteaClient,
+    sourcehut: SourceHutClient,
     timeouts: crate::config::Timeouts,
+    limits: CommitLimits,
+    /// Skip forge APIs and fetches; only cached clones are read
+    offline: bool,
+    /// Proxy for git fetches, when one is set explicitly
+    proxy: Option<String>,
+    changelog_paths: ChangelogPaths,
+    metrics: Arc<CacheMetrics>,
+    /// Extra headers for host probes
+    headers: HostHeaders,
 }
 
 impl GitService {
@@ -37,29 +75,120 @@ impl GitService {
     }
 
     pub fn new_with_config(cancel_token: CancellationToken, config: ServiceConfig) -> Self {
-        let cache_dir = get_cache_dir();
+        let cache_dir = crate::paths::git_cache_dir();
         let timeouts = config.timeouts.clone();
-        let client = Client::builder()
+        let user_agent = config
+            .user_agent
+            .clone()
+            .unwrap_or_else(|| format!("melt/{}", env!("CARGO_PKG_VERSION")));
+        let mut builder = Client::builder()
             .timeout(timeouts.http_request)
-            .user_agent("melt/0.1.0")
-            .build()
-            .unwrap_or_default();
+            .user_agent(user_agent);
+        // reqwest already honors the proxy environment variables.
+        if let Some(proxy) = config.proxy.as_deref().and_then(|url| Proxy::all(url).ok()) {
+            builder = builder.proxy(proxy);
+        }
+        let client = builder.build().unwrap_or_default();
 
-        let github_token = std::env::var("GITHUB_TOKEN")
-            .or_else(|_| std::env::var("GH_TOKEN"))
-            .ok()
+        let github_token = config
+            .forge_tokens
+            .resolve("github.com", &["GITHUB_TOKEN", "GH_TOKEN"])
             .or_else(Self::github_token_from_gh);
 
+        let metrics = Arc::new(CacheMetrics::new(config.metrics));
+        let response_cache = ResponseCache::new(crate::paths::api_cache_dir());
+
         Self {
             cache_dir,
             cancel_token,
             semaphore: Arc::new(Semaphore::new(config.git_concurrency)),
+            github: GitHubClient::new(client.clone(), github_token)
+                .with_tokens(config.forge_tokens.clone())
+                .with_response_cache(response_cache.clone())
+                .with_metrics(metrics.clone())
+                .with_headers(config.headers.clone()),
+            gitlab: GitLabClient::new(client.clone(), config.forge_tokens.clone())
+                .with_response_cache(response_cache)
+                .with_metrics(metrics.clone())
+                .with_headers(config.headers.clone()),
+            gitea: GiteaClient::new(client.clone(), config.forge_tokens.clone())
+                .with_metrics(metrics.clone())
+                .with_headers(config.headers.clone()),
+            sourcehut: SourceHutClient::new(client.clone(), config.forge_tokens)
+                .with_metrics(metrics.clone())
+                .with_headers(config.headers.clone()),
+            headers: config.headers,
             client,
-            github_token,
             timeouts,
+            limits: config.commit_limits,
+            offline: config.offline,
+            proxy: git_proxy(config.proxy.as_deref()),
+            changelog_paths: config.changelog_paths,
+            metrics,
+        }
+    }
+
+    /// API client for the forge hosting `input`, unless offline or the
+    /// forge has no supported API
+    fn forge(&self, input: &GitInput) -> Option<&dyn ForgeClient> {
+        if self.offline {
+            return None;
+        }
+        self.web_forge(input)
+    }
+
+    /// Client for the forge hosting `input`, for building web links, which
+    /// work offline too
+    fn web_forge(&self, input: &GitInput) -> Option<&dyn ForgeClient> {
+        match input.repo() {
+            GitRepo::GitHub { .. } | GitRepo::GitHubEnterprise { .. } => Some(&self.github),
+            GitRepo::GitLab { .. } => Some(&self.gitlab),
+            GitRepo::Codeberg { .. } | GitRepo::Gitea { .. } => Some(&self.gitea),
+            GitRepo::SourceHut { .. } => Some(&self.sourcehut),
+            GitRepo::Generic { .. } => None,
         }
     }
 
+    /// Whether a GitHub token is available for API requests
+    pub fn has_github_token(&self) -> bool {
+        self.github.has_token()
+    }
+
+    /// Whether update checks will call the GitHub API without a token,
+    /// subject to the unauthenticated rate limit
+    pub fn uses_anonymous_github_api(&self) -> bool {
+        !self.offline && !self.github.has_token()
+    }
+
+    /// Use `timeouts` for operations started from now on. The HTTP request
+    /// timeout is fixed when the service is created.
+    pub fn set_timeouts(&mut self, timeouts: crate::config::Timeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// Clone cache and API totals so far, if `metrics` is enabled
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.metrics.snapshot()
+    }
+
+    /// Directory cached clones are kept in
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Check that `host` answers HTTPS requests.
+    ///
+    /// Any HTTP response counts as reachable; only connection failures and
+    /// timeouts are errors.
+    pub async fn probe_host(&self, host: &str) -> Result<(), GitError> {
+        let req = self.client.head(format!("https://{}/", host));
+        with_host_headers(req, &self.headers, host)
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| GitError::NetworkError(e.to_string()))
+    }
+
     /// Try to get a GitHub token from the `gh` CLI.
     fn github_token_from_gh() -> Option<String> {
         let output = std::process::Command::new("gh")
@@ -80,27 +209,147 @@ impl GitService {
         }
     }
 
-    /// Check for updates on multiple inputs
+    /// Check for updates on multiple inputs, first reporting each as `Checking`
     pub async fn check_updates<F>(
         &self,
         inputs: &[GitInput],
         mut on_status: F,
     ) -> Result<(), GitError>
     where
-        F: FnMut(InputName, UpdateStatus) + Send,
+        F: FnMut(InputName, UpdateStatus, CheckInfo) + Send,
+    {
+        for input in inputs {
+            on_status(
+                input.input_name().clone(),
+                UpdateStatus::Checking,
+                CheckInfo::default(),
+            );
+        }
+
+        self.check_updates_timed(inputs, on_status).await
+    }
+
+    /// Check for updates on multiple inputs, reporting how long each check
+    /// took and whether it fell back to a clone
+    pub async fn check_updates_timed<F>(
+        &self,
+        inputs: &[GitInput],
+        mut on_result: F,
+    ) -> Result<(), GitError>
+    where
+        F: FnMut(InputName, UpdateStatus, CheckInfo) + Send,
     {
         debug!(git_inputs = inputs.len(), "Checking for updates");
 
-        for input in inputs {
-            on_status(input.input_name().clone(), UpdateStatus::Checking);
+        if self.offline {
+            for input in inputs {
+                on_result(
+                    input.input_name().clone(),
+                    UpdateStatus::Unknown,
+                    CheckInfo::default(),
+                );
+            }
+            return Ok(());
         }
 
+        // Checks run in waves so a lock with hundreds of inputs never holds
+        // more than one wave of pending tasks and responses at once. Inputs
+        // a forge rate-limits are checked again in another round once the
+        // longest wait it asked for has passed.
+        let mut pending = inputs.to_vec();
+        for round in 0..=RATE_LIMIT_RETRIES {
+            let retry = round < RATE_LIMIT_RETRIES;
+            let mut limited = Vec::new();
+            for wave in pending.chunks(CHECK_WAVE_SIZE) {
+                if self.cancel_token.is_cancelled() {
+                    return Ok(());
+                }
+                self.check_wave(wave, retry, &mut on_result, &mut limited)
+                    .await;
+            }
+
+            let Some(wait) = limited.iter().map(|(_, wait)| *wait).max() else {
+                break;
+            };
+            info!(
+                inputs = limited.len(),
+                ?wait,
+                "Waiting for forge rate limits to reset"
+            );
+            let retry_at = Instant::now() + wait;
+            for (input, _) in &limited {
+                on_result(
+                    input.input_name().clone(),
+                    UpdateStatus::RateLimited(retry_at),
+                    CheckInfo::default(),
+                );
+            }
+            tokio::select! {
+                _ = self.cancel_token.cancelled() => return Ok(()),
+                _ = tokio::time::sleep(wait) => {}
+            }
+
+            pending = limited.into_iter().map(|(input, _)| input).collect();
+            for input in &pending {
+                on_result(
+                    input.input_name().clone(),
+                    UpdateStatus::Checking,
+                    CheckInfo::default(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check one wave of inputs concurrently, reporting results as they
+    /// finish.
+    ///
+    /// With `retry`, rate-limited inputs are added to `limited` with the
+    /// wait their forge asked for instead of being reported.
+    async fn check_wave<F>(
+        &self,
+        wave: &[GitInput],
+        retry: bool,
+        on_result: &mut F,
+        limited: &mut Vec<(GitInput, Duration)>,
+    ) where
+        F: FnMut(InputName, UpdateStatus, CheckInfo) + Send,
+    {
         let mut join_set = JoinSet::new();
 
-        for input in inputs {
+        // GitHub inputs are answered together by one GraphQL query where
+        // possible; anything it leaves open gets its own check below.
+        let started = Instant::now();
+        let batched = tokio::select! {
+            _ = self.cancel_token.cancelled() => return,
+            batched = self.github.check_updates_batch(wave) => batched,
+        };
+        let batched = match batched {
+            Ok(counts) => counts,
+            Err(e) => {
+                warn!(error = %e, "Batched GitHub update check failed");
+                vec![None; wave.len()]
+            }
+        };
+        let batch_info = CheckInfo {
+            elapsed: started.elapsed(),
+            clone_path: None,
+        };
+
+        for (input, batched) in wave.iter().zip(batched) {
             if self.cancel_token.is_cancelled() {
                 break;
             }
+            if let Some(count) = batched {
+                let name = input.input_name().clone();
+                on_result(
+                    name.clone(),
+                    update_status(&name, count),
+                    batch_info.clone(),
+                );
+                continue;
+            }
 
             let input = input.clone();
             let service = self.clone();
@@ -112,17 +361,24 @@ impl GitService {
                     Ok(permit) => permit,
                     Err(_) => {
                         return (
-                            name,
-                            UpdateStatus::Error("Failed to acquire semaphore".to_string()),
+                            input,
+                            Ok(UpdateStatus::Error(
+                                "Failed to acquire semaphore".to_string(),
+                            )),
+                            CheckInfo::default(),
                         );
                     }
                 };
 
+                let started = Instant::now();
+                let mut clone_path = None;
                 let status = match service.check_input_updates(&input).await {
-                    Ok(0) => UpdateStatus::UpToDate,
-                    Ok(count) => {
-                        debug!(input = %name, behind = count, "Updates available");
-                        UpdateStatus::Behind(count)
+                    Ok((count, path)) => {
+                        clone_path = path;
+                        update_status(&name, count)
+                    }
+                    Err(GitError::RateLimited { retry_after, .. }) if retry => {
+                        return (input, Err(retry_after), CheckInfo::default());
                     }
                     Err(e) => {
                         warn!(input = %name, error = %e, "Failed to check input");
@@ -130,7 +386,11 @@ impl GitService {
                     }
                 };
 
-                (name, status)
+                let info = CheckInfo {
+                    elapsed: started.elapsed(),
+                    clone_path,
+                };
+                (input, Ok(status), info)
             });
         }
 
@@ -142,7 +402,10 @@ impl GitService {
                 }
                 next = join_set.join_next() => {
                     match next {
-                        Some(Ok((name, status))) => on_status(name, status),
+                        Some(Ok((input, Ok(status), info))) => {
+                            on_result(input.input_name().clone(), status, info)
+                        }
+                        Some(Ok((input, Err(wait), _))) => limited.push((input, wait)),
                         Some(Err(e)) if e.is_cancelled() => {}
                         Some(Err(e)) => warn!(error = %e, "Update check task failed"),
                         None => break,
@@ -150,133 +413,84 @@ impl GitService {
                 }
             }
         }
-
-        Ok(())
     }
 
-    async fn check_input_updates(&self, input: &GitInput) -> Result<usize, GitError> {
-        match input.repo() {
-            GitRepo::GitHub { owner, repo } => self.check_github_updates(input, owner, repo).await,
-            GitRepo::GitLab { host, owner, repo } => {
-                self.check_gitlab_updates(input, host, owner, repo).await
-            }
-            GitRepo::SourceHut { .. }
-            | GitRepo::Codeberg { .. }
-            | GitRepo::Gitea { .. }
-            | GitRepo::Generic { .. } => self.check_git_updates(input).await,
-        }
-    }
-
-    async fn check_github_updates(
+    /// Commits the input is behind, and the clone they were counted in
+    /// when no forge API answered
+    async fn check_input_updates(
         &self,
         input: &GitInput,
-        owner: &Owner,
-        repo: &RepoName,
-    ) -> Result<usize, GitError> {
-        let branch = input.reference().unwrap_or("HEAD");
-        let url = format!(
-            "https://api.github.com/repos/{}/{}/compare/{}...{}",
-            owner,
-            repo,
-            input.rev(),
-            branch
-        );
-
-        let mut req = self.client.get(&url);
-        if let Some(token) = &self.github_token {
-            req = req.header("Authorization", format!("Bearer {}", token));
-        }
-
-        let resp = req
-            .send()
-            .await
-            .map_err(|e| GitError::NetworkError(e.to_string()))?;
-
-        let status = resp.status();
-
-        if status.as_u16() == 403 || status.as_u16() == 429 {
-            let remaining = resp
-                .headers()
-                .get("x-ratelimit-remaining")
-                .and_then(|v| v.to_str().ok())
-                .and_then(|v| v.parse::<u32>().ok())
-                .unwrap_or(0);
-
-            if remaining == 0 {
-                warn!(input = %input.name(), "GitHub API rate limit exceeded");
-                return Err(GitError::NetworkError(
-                    "GitHub API rate limit exceeded. Set GITHUB_TOKEN for higher limits."
-                        .to_string(),
-                ));
+    ) -> Result<(CommitCount, Option<PathBuf>), GitError> {
+        if let Some(forge) = self.forge(input) {
+            if let Some(count) = forge.check_updates(input).await? {
+                return Ok((count, None));
             }
         }
-
-        if !status.is_success() {
-            return self.check_git_updates(input).await;
-        }
-
-        #[derive(Deserialize)]
-        struct CompareResponse {
-            ahead_by: usize,
-        }
-
-        let data: CompareResponse = resp
-            .json()
-            .await
-            .map_err(|e| GitError::NetworkError(e.to_string()))?;
-
-        Ok(data.ahead_by)
+        let count = self.check_git_updates(input).await?;
+        Ok((count, self.clone_path(input)))
     }
 
-    async fn check_gitlab_updates(
+    /// Count the commits between an older locked revision `from` and the
+    /// revision `input` is locked to now.
+    pub async fn count_commits_between(
         &self,
         input: &GitInput,
-        host: &GitHost,
-        owner: &Owner,
-        repo: &RepoName,
-    ) -> Result<usize, GitError> {
-        let branch = input.reference().unwrap_or("HEAD");
-        let project = format!("{}/{}", owner, repo);
-        let encoded_project = urlencoding(&project);
-
-        let url = format!(
-            "https://{}/api/v4/projects/{}/repository/compare?from={}&to={}",
-            host,
-            encoded_project,
-            input.rev(),
-            branch
-        );
-
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| GitError::NetworkError(e.to_string()))?;
-
-        if !resp.status().is_success() {
-            return self.check_git_updates(input).await;
+        from: &GitRev,
+    ) -> Result<CommitCount, GitError> {
+        if let Some(forge) = self.forge(input) {
+            if let Some(count) = forge.compare(input, from.as_str(), input.rev()).await? {
+                return Ok(count);
+            }
         }
 
-        #[derive(Deserialize)]
-        struct CompareResponse {
-            commits: Vec<serde_json::Value>,
-        }
+        let clone_url = ensure_clone_url(input)?;
+        let cache_path = self.cache_path(&clone_url);
+        let reference = input.reference().map(ToOwned::to_owned);
+        let head = input.rev().to_string();
+        let base = from.as_str().to_string();
+        let cancel = self.cancel_token.clone();
+        let max_commits = self.limits.for_input(input.name()).max_commits;
+        let offline = self.offline;
+        let proxy = self.proxy.clone();
+        let metrics = self.metrics.clone();
 
-        let data: CompareResponse = resp
-            .json()
-            .await
-            .map_err(|e| GitError::NetworkError(e.to_string()))?;
+        let result = tokio::time::timeout(
+            self.timeouts.git_update_check,
+            tokio::task::spawn_blocking(move || {
+                let repo = ensure_repo(
+                    &cache_path,
+                    &clone_url,
+                    reference.as_deref(),
+                    offline,
+                    proxy.as_deref(),
+                    &cancel,
+                    &metrics,
+                )?;
+                let count = count_commits_between_revs(&repo, &base, &head, max_commits)?;
+                Ok(CommitCount::from_limited(count, max_commits))
+            }),
+        )
+        .await;
 
-        Ok(data.commits.len())
+        match result {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => Err(GitError::CloneFailed(format!("Task failed: {}", e))),
+            Err(_) => Err(GitError::NetworkError(
+                "Timeout counting commits".to_string(),
+            )),
+        }
     }
 
-    async fn check_git_updates(&self, input: &GitInput) -> Result<usize, GitError> {
+    async fn check_git_updates(&self, input: &GitInput) -> Result<CommitCount, GitError> {
         let clone_url = ensure_clone_url(input)?;
         let cache_path = self.cache_path(&clone_url);
         let reference = input.reference().map(ToOwned::to_owned);
         let rev = input.rev().to_string();
         let cancel = self.cancel_token.clone();
+        let max_commits = self.limits.for_input(input.name()).max_commits;
+        let offline = self.offline;
+        let proxy = self.proxy.clone();
+        let metrics = self.metrics.clone();
 
         debug!(input = %input.name(), "Using git2 fallback");
 
@@ -287,9 +501,17 @@ impl GitService {
                     return Err(GitError::CloneFailed("Cancelled".to_string()));
                 }
 
-                let repo = ensure_repo(&cache_path, &clone_url, reference.as_deref(), &cancel)?;
-                let commits = get_commits_since(&repo, &rev, reference.as_deref())?;
-                Ok(commits.len())
+                let repo = ensure_repo(
+                    &cache_path,
+                    &clone_url,
+                    reference.as_deref(),
+                    offline,
+                    proxy.as_deref(),
+                    &cancel,
+                    &metrics,
+                )?;
+                let count = count_commits_since(&repo, &rev, reference.as_deref(), max_commits)?;
+                Ok(CommitCount::from_limited(count, max_commits))
             }),
         )
         .await;
@@ -305,239 +527,332 @@ impl GitService {
     }
 
     pub async fn get_changelog(&self, input: &GitInput) -> Result<ChangelogData, GitError> {
+        self.get_changelog_streaming(input, Arc::new(|_, _| {}))
+            .await
+    }
+
+    /// Load the changelog, passing commits to `on_page` as they arrive so
+    /// they can be shown before the whole history is in.
+    ///
+    /// A later call may start over at an earlier offset, e.g. when the
+    /// forge API gives up and the clone is read instead.
+    pub async fn get_changelog_streaming(
+        &self,
+        input: &GitInput,
+        on_page: ChangelogProgress,
+    ) -> Result<ChangelogData, GitError> {
         debug!(input = %input.name(), repo = ?input.repo(), "Loading changelog");
 
-        match input.repo() {
-            GitRepo::GitHub { owner, repo } => self.get_github_changelog(input, owner, repo).await,
-            GitRepo::GitLab { host, owner, repo } => {
-                self.get_gitlab_changelog(input, host, owner, repo).await
+        // Forge APIs don't list the files each commit touches, so narrowing
+        // to paths always goes through a local clone.
+        let paths = self.changelog_paths.for_input(input.name());
+        if paths.is_empty() {
+            if let Some(forge) = self.forge(input) {
+                let limits = self.limits.for_input(input.name());
+                if let Some(data) = forge.changelog(input, limits, &*on_page).await? {
+                    return Ok(data);
+                }
+            }
+        }
+        self.get_git_changelog(input, paths, on_page).await
+    }
+
+    /// Web page of the pull or merge request that brought in commit `sha`,
+    /// asked of the forge API, or of the commit itself when no request
+    /// merged it or the forge can't be asked
+    pub async fn pull_request_url(
+        &self,
+        input: &GitInput,
+        sha: &str,
+    ) -> Result<Option<String>, GitError> {
+        if let Some(forge) = self.forge(input) {
+            if let Some(url) = forge.pull_request(input, sha).await? {
+                return Ok(Some(url));
             }
-            GitRepo::SourceHut { .. }
-            | GitRepo::Codeberg { .. }
-            | GitRepo::Gitea { .. }
-            | GitRepo::Generic { .. } => self.get_git_changelog(input).await,
         }
+        Ok(self
+            .web_forge(input)
+            .and_then(|forge| forge.commit_url(input, sha)))
     }
 
-    /// Get changelog via GitHub API
-    async fn get_github_changelog(
+    async fn get_git_changelog(
         &self,
         input: &GitInput,
-        owner: &Owner,
-        repo: &RepoName,
+        paths: Vec<String>,
+        on_page: ChangelogProgress,
     ) -> Result<ChangelogData, GitError> {
-        let branch = input.reference().unwrap_or("HEAD");
+        let clone_url = ensure_clone_url(input)?;
+        let cache_path = self.cache_path(&clone_url);
+        let clone_path = cache_path.clone();
+        let reference = input.reference().map(ToOwned::to_owned);
+        let rev = input.rev().to_string();
+        let cancel = self.cancel_token.clone();
+        let limits = self.limits.for_input(input.name());
+        let offline = self.offline;
+        let proxy = self.proxy.clone();
+        let metrics = self.metrics.clone();
 
-        // Get commits from branch
-        let url = format!(
-            "https://api.github.com/repos/{}/{}/commits?sha={}&per_page=100",
-            owner, repo, branch
-        );
+        let result = tokio::time::timeout(
+            self.timeouts.git_changelog,
+            tokio::task::spawn_blocking(move || {
+                if cancel.is_cancelled() {
+                    return Err(GitError::CloneFailed("Cancelled".to_string()));
+                }
+
+                let repo = ensure_repo(
+                    &cache_path,
+                    &clone_url,
+                    reference.as_deref(),
+                    offline,
+                    proxy.as_deref(),
+                    &cancel,
+                    &metrics,
+                )?;
+
+                let mut commits_ahead =
+                    get_commits_since(&repo, &rev, reference.as_deref(), limits.max_commits)?;
+                let commits_from_locked = get_commits_from(&repo, &rev, limits.history_depth)?;
+
+                let truncated = commits_ahead.len() >= limits.max_commits;
+                let total = CommitCount::from_limited(commits_ahead.len(), limits.max_commits);
+                if !paths.is_empty() {
+                    commits_ahead.retain(|commit| touches_paths(&repo, &commit.sha, &paths));
+                }
+                // The new commits are what the view opens on; history past
+                // the locked revision and line stats follow.
+                on_page(0, &commits_ahead);
+                let mut all_commits = commits_ahead;
+                let locked_idx = if !commits_from_locked.is_empty() {
+                    let idx = all_commits.len();
+                    all_commits.extend(commits_from_locked);
+                    Some(idx)
+                } else {
+                    None
+                };
+
+                let mut data = ChangelogData::new(all_commits, locked_idx).map_err(|e| {
+                    GitError::CloneFailed(format!("Invalid changelog data: {:?}", e))
+                })?;
+                if !paths.is_empty() {
+                    data = data.with_path_filter(PathFilter {
+                        paths: paths.clone(),
+                        total,
+                    });
+                }
+                if truncated {
+                    return Ok(data.with_truncation(limits.max_commits));
+                }
+                // Line stats need a full tree diff, so only compute them for
+                // updates small enough to fit under the commit limit.
+                Ok(
+                    match diff_stats(&repo, &rev, reference.as_deref(), &paths) {
+                        Some(stats) => data.with_diff_stats(stats),
+                        None => data,
+                    },
+                )
+            }),
+        )
+        .await;
 
-        let mut req = self.client.get(&url);
-        if let Some(token) = &self.github_token {
-            req = req.header("Authorization", format!("Bearer {}", token));
+        match result {
+            Ok(Ok(Ok(data))) => Ok(data.with_clone_path(clone_path)),
+            Ok(Ok(Err(e))) => Err(e),
+            Ok(Err(e)) => Err(GitError::CloneFailed(format!("Task failed: {}", e))),
+            Err(_) => Err(GitError::NetworkError(
+                "Timeout loading changelog".to_string(),
+            )),
         }
+    }
 
-        let resp = req
-            .send()
-            .await
-            .map_err(|e| GitError::NetworkError(e.to_string()))?;
-
-        let status = resp.status();
-
-        // Check for rate limiting
-        if status.as_u16() == 403 || status.as_u16() == 429 {
-            let remaining = resp
-                .headers()
-                .get("x-ratelimit-remaining")
-                .and_then(|v| v.to_str().ok())
-                .and_then(|v| v.parse::<u32>().ok())
-                .unwrap_or(0);
-
-            if remaining == 0 {
-                return Err(GitError::NetworkError(
-                    "GitHub API rate limit exceeded. Set GITHUB_TOKEN for higher limits."
-                        .to_string(),
-                ));
+    /// Upstream tags of `input`'s repository, from the forge API or else the
+    /// cached clone
+    pub async fn tags(&self, input: &GitInput) -> Result<Vec<Tag>, GitError> {
+        if let Some(forge) = self.forge(input) {
+            if let Some(tags) = forge.tags(input).await? {
+                return Ok(tags);
             }
         }
 
-        if !status.is_success() {
-            return self.get_git_changelog(input).await;
-        }
+        let clone_url = ensure_clone_url(input)?;
+        let cache_path = self.cache_path(&clone_url);
+        let reference = input.reference().map(ToOwned::to_owned);
+        let cancel = self.cancel_token.clone();
+        let offline = self.offline;
+        let proxy = self.proxy.clone();
+        let metrics = self.metrics.clone();
 
-        #[derive(Deserialize)]
-        struct GitHubAuthor {
-            name: Option<String>,
-            date: Option<String>,
-        }
+        let result = tokio::time::timeout(
+            self.timeouts.git_changelog,
+            tokio::task::spawn_blocking(move || {
+                let repo = ensure_repo(
+                    &cache_path,
+                    &clone_url,
+                    reference.as_deref(),
+                    offline,
+                    proxy.as_deref(),
+                    &cancel,
+                    &metrics,
+                )?;
+                list_tags(&repo)
+            }),
+        )
+        .await;
 
-        #[derive(Deserialize)]
-        struct GitHubCommitData {
-            message: String,
-            author: Option<GitHubAuthor>,
+        match result {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => Err(GitError::CloneFailed(format!("Task failed: {}", e))),
+            Err(_) => Err(GitError::NetworkError("Timeout listing tags".to_string())),
         }
+    }
 
-        #[derive(Deserialize)]
-        struct GitHubCommit {
-            sha: String,
-            commit: GitHubCommitData,
+    /// Upstream branches of `input`'s repository, from the forge API or
+    /// else the cached clone
+    pub async fn branches(&self, input: &GitInput) -> Result<Vec<String>, GitError> {
+        if let Some(forge) = self.forge(input) {
+            if let Some(branches) = forge.branches(input).await? {
+                return Ok(branches);
+            }
         }
 
-        let commits: Vec<GitHubCommit> = resp
-            .json()
-            .await
-            .map_err(|e| GitError::NetworkError(e.to_string()))?;
-
-        let mut result_commits = Vec::new();
-        let mut locked_idx = None;
+        let clone_url = ensure_clone_url(input)?;
+        let cache_path = self.cache_path(&clone_url);
+        let reference = input.reference().map(ToOwned::to_owned);
+        let cancel = self.cancel_token.clone();
+        let offline = self.offline;
+        let proxy = self.proxy.clone();
+        let metrics = self.metrics.clone();
 
-        for (idx, c) in commits.iter().enumerate() {
-            if c.sha.starts_with(input.rev()) || c.sha == input.rev() {
-                locked_idx = Some(idx);
-            }
+        let result = tokio::time::timeout(
+            self.timeouts.git_changelog,
+            tokio::task::spawn_blocking(move || {
+                let repo = ensure_repo(
+                    &cache_path,
+                    &clone_url,
+                    reference.as_deref(),
+                    offline,
+                    proxy.as_deref(),
+                    &cancel,
+                    &metrics,
+                )?;
+                list_branches(&repo)
+            }),
+        )
+        .await;
 
-            let date = c
-                .commit
-                .author
-                .as_ref()
-                .and_then(|a| a.date.as_ref())
-                .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
-                .map(|d| d.with_timezone(&Utc))
-                .unwrap_or_else(Utc::now);
-
-            let author = c
-                .commit
-                .author
-                .as_ref()
-                .and_then(|a| a.name.clone())
-                .unwrap_or_else(|| "Unknown".to_string());
-
-            let message = c.commit.message.lines().next().unwrap_or("").to_string();
-
-            result_commits.push(Commit {
-                sha: c.sha.clone(),
-                message,
-                author,
-                date,
-            });
+        match result {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => Err(GitError::CloneFailed(format!("Task failed: {}", e))),
+            Err(_) => Err(GitError::NetworkError(
+                "Timeout listing branches".to_string(),
+            )),
         }
-
-        ChangelogData::new(result_commits, locked_idx)
-            .map_err(|e| GitError::CloneFailed(format!("Invalid changelog data: {:?}", e)))
     }
 
-    /// Get changelog via GitLab API
-    async fn get_gitlab_changelog(
+    /// Files and lines changed from `base` to `head`, limited to `paths`
+    /// when any are given. Asks the forge API, or else reads a clone that
+    /// is already cached; `None` when neither can tell. When the forge only
+    /// counted some of the files, a cached clone is read for the full
+    /// totals, and the forge's partial ones are kept without it.
+    pub async fn diff_stats(
         &self,
         input: &GitInput,
-        host: &GitHost,
-        owner: &Owner,
-        repo: &RepoName,
-    ) -> Result<ChangelogData, GitError> {
-        let branch = input.reference().unwrap_or("HEAD");
-
-        let project = format!("{}/{}", owner, repo);
-        let encoded_project = urlencoding(&project);
-
-        let url = format!(
-            "https://{}/api/v4/projects/{}/repository/commits?ref_name={}&per_page=100",
-            host, encoded_project, branch
-        );
-
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| GitError::NetworkError(e.to_string()))?;
-
-        if !resp.status().is_success() {
-            return self.get_git_changelog(input).await;
-        }
-
-        #[derive(Deserialize)]
-        struct GitLabCommit {
-            id: String,
-            title: String,
-            author_name: String,
-            created_at: String,
+        base: &str,
+        head: &str,
+        paths: &[String],
+    ) -> Result<Option<DiffStats>, GitError> {
+        let mut partial = None;
+        if paths.is_empty() {
+            if let Some(forge) = self.forge(input) {
+                match forge.diff_stats(input, base, head).await? {
+                    Some(stats) if stats.partial => partial = Some(stats),
+                    Some(stats) => return Ok(Some(stats)),
+                    None => {}
+                }
+            }
         }
 
-        let commits: Vec<GitLabCommit> = resp
-            .json()
-            .await
-            .map_err(|e| GitError::NetworkError(e.to_string()))?;
+        let Some(cache_path) = self.clone_path(input).filter(|path| path.exists()) else {
+            return Ok(partial);
+        };
+        let (base, head, paths) = (base.to_string(), head.to_string(), paths.to_vec());
+        let result = tokio::time::timeout(
+            self.timeouts.git_changelog,
+            tokio::task::spawn_blocking(move || {
+                let repo = Repository::open_bare(&cache_path)?;
+                Ok(diff_stats_between(&repo, &base, &head, &paths))
+            }),
+        )
+        .await;
 
-        let mut result_commits = Vec::new();
-        let mut locked_idx = None;
+        match result {
+            Ok(Ok(result)) => result.map(|stats| stats.or(partial)),
+            Ok(Err(e)) => Err(GitError::CloneFailed(format!("Task failed: {}", e))),
+            Err(_) => Err(GitError::NetworkError(
+                "Timeout comparing commits".to_string(),
+            )),
+        }
+    }
 
-        for (idx, c) in commits.iter().enumerate() {
-            if c.id.starts_with(input.rev()) || c.id == input.rev() {
-                locked_idx = Some(idx);
+    /// Full message and changed files of the commit `sha`, from the forge
+    /// API or else the cached clone
+    pub async fn commit_detail(
+        &self,
+        input: &GitInput,
+        sha: &str,
+    ) -> Result<CommitDetail, GitError> {
+        if let Some(forge) = self.forge(input) {
+            if let Some(detail) = forge.commit_detail(input, sha).await? {
+                return Ok(detail);
             }
-
-            let date = chrono::DateTime::parse_from_rfc3339(&c.created_at)
-                .map(|d| d.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now());
-
-            result_commits.push(Commit {
-                sha: c.id.clone(),
-                message: c.title.clone(),
-                author: c.author_name.clone(),
-                date,
-            });
         }
 
-        ChangelogData::new(result_commits, locked_idx)
-            .map_err(|e| GitError::CloneFailed(format!("Invalid changelog data: {:?}", e)))
-    }
-
-    async fn get_git_changelog(&self, input: &GitInput) -> Result<ChangelogData, GitError> {
         let clone_url = ensure_clone_url(input)?;
         let cache_path = self.cache_path(&clone_url);
         let reference = input.reference().map(ToOwned::to_owned);
-        let rev = input.rev().to_string();
+        let sha = sha.to_string();
         let cancel = self.cancel_token.clone();
+        let offline = self.offline;
+        let proxy = self.proxy.clone();
+        let metrics = self.metrics.clone();
 
         let result = tokio::time::timeout(
             self.timeouts.git_changelog,
             tokio::task::spawn_blocking(move || {
-                if cancel.is_cancelled() {
-                    return Err(GitError::CloneFailed("Cancelled".to_string()));
-                }
-
-                let repo = ensure_repo(&cache_path, &clone_url, reference.as_deref(), &cancel)?;
-
-                let commits_ahead = get_commits_since(&repo, &rev, reference.as_deref())?;
-                let commits_from_locked = get_commits_from(&repo, &rev, 50)?;
-
-                let mut all_commits = commits_ahead;
-                let locked_idx = if !commits_from_locked.is_empty() {
-                    let idx = all_commits.len();
-                    all_commits.extend(commits_from_locked);
-                    Some(idx)
-                } else {
-                    None
-                };
-
-                ChangelogData::new(all_commits, locked_idx)
-                    .map_err(|e| GitError::CloneFailed(format!("Invalid changelog data: {:?}", e)))
+                let repo = ensure_repo(
+                    &cache_path,
+                    &clone_url,
+                    reference.as_deref(),
+                    offline,
+                    proxy.as_deref(),
+                    &cancel,
+                    &metrics,
+                )?;
+                read_commit_detail(&repo, &sha)
             }),
         )
         .await;
 
         match result {
-            Ok(Ok(Ok(data))) => Ok(data),
-            Ok(Ok(Err(e))) => Err(e),
+            Ok(Ok(result)) => result,
             Ok(Err(e)) => Err(GitError::CloneFailed(format!("Task failed: {}", e))),
-            Err(_) => Err(GitError::NetworkError(
-                "Timeout loading changelog".to_string(),
-            )),
+            Err(_) => Err(GitError::NetworkError("Timeout loading commit".to_string())),
+        }
+    }
+
+    /// Release notes published for `tag`, if `input` is hosted on a forge
+    /// that has them
+    pub async fn release(&self, input: &GitInput, tag: &str) -> Result<Option<Release>, GitError> {
+        match self.forge(input) {
+            Some(forge) => forge.release(input, tag).await,
+            None => Ok(None),
         }
     }
 
+    /// Cached clone used for `input` when no forge API answers
+    pub fn clone_path(&self, input: &GitInput) -> Option<PathBuf> {
+        get_clone_url(input).map(|url| self.cache_path(&url))
+    }
+
     /// Get the cache path for a URL
     fn cache_path(&self, url: &str) -> PathBuf {
         use std::collections::hash_map::DefaultHasher;
@@ -557,19 +872,6 @@ impl GitService {
     }
 }
 
-/// Simple URL encoding for project paths
-fn urlencoding(s: &str) -> String {
-    s.replace('/', "%2F")
-}
-
-/// Get the XDG cache directory for melt
-fn get_cache_dir() -> PathBuf {
-    dirs::cache_dir()
-        .unwrap_or_else(|| PathBuf::from(".cache"))
-        .join("melt")
-        .join("git")
-}
-
 /// Get the clone URL for a git input
 fn get_clone_url(input: &GitInput) -> Option<String> {
     input.clone_url().ok().map(|url| url.into_string())
@@ -581,8 +883,24 @@ fn ensure_clone_url(input: &GitInput) -> Result<String, GitError> {
     })
 }
 
+/// Proxy for git fetches: the configured one, else `ALL_PROXY`.
+///
+/// With neither, libgit2 detects `http.proxy` from the git config and the
+/// `HTTPS_PROXY` / `HTTP_PROXY` variables itself.
+fn git_proxy(configured: Option<&str>) -> Option<String> {
+    configured.map(ToOwned::to_owned).or_else(|| {
+        ["ALL_PROXY", "all_proxy"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|url| !url.is_empty()))
+    })
+}
+
 /// Create git fetch options with SSH agent authentication
-fn create_fetch_options<'a>(cancel: &CancellationToken) -> FetchOptions<'a> {
+fn create_fetch_options<'a>(
+    proxy: Option<&str>,
+    cancel: &CancellationToken,
+    metrics: &'a CacheMetrics,
+) -> FetchOptions<'a> {
     let cancel_for_progress = cancel.clone();
 
     let mut callbacks = RemoteCallbacks::new();
@@ -598,18 +916,36 @@ fn create_fetch_options<'a>(cancel: &CancellationToken) -> FetchOptions<'a> {
         }
     });
 
-    callbacks.transfer_progress(move |_stats| !cancel_for_progress.is_cancelled());
+    // Progress reports running totals; only the growth since the last
+    // report is new.
+    let mut received = 0;
+    callbacks.transfer_progress(move |stats| {
+        metrics.record_git_bytes(stats.received_bytes().saturating_sub(received) as u64);
+        received = stats.received_bytes();
+        !cancel_for_progress.is_cancelled()
+    });
+
+    let mut proxy_options = ProxyOptions::new();
+    match proxy {
+        Some(url) => proxy_options.url(url),
+        None => proxy_options.auto(),
+    };
 
     let mut fetch_options = FetchOptions::new();
     fetch_options.remote_callbacks(callbacks);
+    fetch_options.proxy_options(proxy_options);
     fetch_options
 }
 
+/// Open the cached clone of `url`, fetching or cloning it unless `offline`
 fn ensure_repo(
     cache_path: &Path,
     url: &str,
     reference: Option<&str>,
+    offline: bool,
+    proxy: Option<&str>,
     cancel: &CancellationToken,
+    metrics: &CacheMetrics,
 ) -> Result<Repository, GitError> {
     if cancel.is_cancelled() {
         return Err(GitError::CloneFailed("Cancelled".to_string()));
@@ -620,11 +956,18 @@ fn ensure_repo(
     }
 
     if cache_path.exists() {
+        metrics.record_clone_hit();
         let repo = Repository::open_bare(cache_path)?;
-        fetch_repo(&repo, cancel)?;
+        if !offline {
+            fetch_repo(&repo, proxy, cancel, metrics)?;
+        }
         Ok(repo)
+    } else if offline {
+        metrics.record_clone_miss();
+        Err(GitError::Offline(format!("no cached clone of {}", url)))
     } else {
-        clone_repo(cache_path, url, reference, cancel)
+        metrics.record_clone_miss();
+        clone_repo(cache_path, url, reference, proxy, cancel, metrics)
     }
 }
 
@@ -632,13 +975,15 @@ fn clone_repo(
     cache_path: &Path,
     url: &str,
     reference: Option<&str>,
+    proxy: Option<&str>,
     cancel: &CancellationToken,
+    metrics: &CacheMetrics,
 ) -> Result<Repository, GitError> {
     debug!(url = %url, "Cloning repository");
 
     let mut builder = git2::build::RepoBuilder::new();
     builder.bare(true);
-    builder.fetch_options(create_fetch_options(cancel));
+    builder.fetch_options(create_fetch_options(proxy, cancel, metrics));
 
     if let Some(r) = reference {
         builder.branch(r);
@@ -647,7 +992,12 @@ fn clone_repo(
     builder.clone(url, cache_path).map_err(GitError::from)
 }
 
-fn fetch_repo(repo: &Repository, cancel: &CancellationToken) -> Result<(), GitError> {
+fn fetch_repo(
+    repo: &Repository,
+    proxy: Option<&str>,
+    cancel: &CancellationToken,
+    metrics: &CacheMetrics,
+) -> Result<(), GitError> {
     let mut remote = repo.find_remote("origin")?;
     let refspecs: Vec<String> = remote
         .refspecs()
@@ -655,43 +1005,177 @@ fn fetch_repo(repo: &Repository, cancel: &CancellationToken) -> Result<(), GitEr
         .collect();
     let refspec_strs: Vec<&str> = refspecs.iter().map(|s| s.as_str()).collect();
 
-    let mut fetch_options = create_fetch_options(cancel);
+    let mut fetch_options = create_fetch_options(proxy, cancel, metrics);
     remote.fetch(&refspec_strs, Some(&mut fetch_options), None)?;
     Ok(())
 }
 
-/// Get commits since a given revision
+/// Full message of the commit `sha` and the files it changed relative to
+/// its first parent
+fn read_commit_detail(repo: &Repository, sha: &str) -> Result<CommitDetail, GitError> {
+    let oid = git2::Oid::from_str(sha).map_err(|_| GitError::RevisionNotFound(sha.to_string()))?;
+    let commit = repo
+        .find_commit(oid)
+        .map_err(|_| GitError::RevisionNotFound(sha.to_string()))?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+
+    let mut files = Vec::new();
+    for idx in 0..diff.deltas().len() {
+        let Some(patch) = git2::Patch::from_diff(&diff, idx)? else {
+            continue;
+        };
+        let delta = patch.delta();
+        let path = delta.new_file().path().or_else(|| delta.old_file().path());
+        let (_, insertions, deletions) = patch.line_stats()?;
+        files.push(FileChange {
+            path: path.map(|p| p.display().to_string()).unwrap_or_default(),
+            insertions,
+            deletions,
+        });
+    }
+
+    let summary = commit_to_model(repo, &commit);
+    let author = commit.author();
+    Ok(CommitDetail {
+        sha: summary.sha,
+        message: commit.message().unwrap_or("").to_string(),
+        author: summary.author,
+        email: author.email().unwrap_or("").to_string(),
+        date: summary.date,
+        files,
+    })
+}
+
+/// Tags in `repo`, peeled to the commits they point at
+fn list_tags(repo: &Repository) -> Result<Vec<Tag>, GitError> {
+    let mut tags = Vec::new();
+    for reference in repo.references_glob("refs/tags/*")? {
+        let reference = reference?;
+        let (Some(name), Ok(commit)) = (reference.shorthand(), reference.peel_to_commit()) else {
+            continue;
+        };
+        tags.push(Tag {
+            name: name.to_string(),
+            sha: commit.id().to_string(),
+        });
+    }
+    Ok(tags)
+}
+
+/// Branches of a bare clone: its own, and those fetched from `origin`
+fn list_branches(repo: &Repository) -> Result<Vec<String>, GitError> {
+    let mut branches = Vec::new();
+    for glob in ["refs/heads/*", "refs/remotes/origin/*"] {
+        for reference in repo.references_glob(glob)? {
+            let reference = reference?;
+            let Some(name) = reference.name() else {
+                continue;
+            };
+            let name = name
+                .trim_start_matches("refs/heads/")
+                .trim_start_matches("refs/remotes/origin/");
+            if name != "HEAD" && !branches.iter().any(|branch| branch == name) {
+                branches.push(name.to_string());
+            }
+        }
+    }
+    Ok(branches)
+}
+
+/// Get up to `limit` commits since a given revision
 fn get_commits_since(
     repo: &Repository,
     base_rev: &str,
     head_ref: Option<&str>,
+    limit: usize,
 ) -> Result<Vec<Commit>, GitError> {
-    let head_ref = head_ref.unwrap_or("HEAD");
+    let Some(revwalk) = revwalk_since(repo, base_rev, head_ref)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut commits = Vec::new();
+    for oid_result in revwalk.take(limit) {
+        let oid = oid_result?;
+        if let Ok(commit) = repo.find_commit(oid) {
+            commits.push(commit_to_model(repo, &commit));
+        }
+    }
+
+    Ok(commits)
+}
+
+/// Count commits since a base revision without loading them
+fn count_commits_since(
+    repo: &Repository,
+    base_rev: &str,
+    head_ref: Option<&str>,
+    limit: usize,
+) -> Result<usize, GitError> {
+    let Some(revwalk) = revwalk_since(repo, base_rev, head_ref)? else {
+        return Ok(0);
+    };
 
-    let head_oid = resolve_ref(repo, head_ref)?;
+    let mut count = 0;
+    for oid_result in revwalk.take(limit) {
+        oid_result?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Count commits reachable from `head_rev` but not from `base_rev`
+fn count_commits_between_revs(
+    repo: &Repository,
+    base_rev: &str,
+    head_rev: &str,
+    limit: usize,
+) -> Result<usize, GitError> {
+    let head_oid = repo
+        .revparse_single(head_rev)
+        .map_err(|_| GitError::RevisionNotFound(head_rev.to_string()))?
+        .id();
+    let base_oid = repo
+        .revparse_single(base_rev)
+        .map_err(|_| GitError::RevisionNotFound(base_rev.to_string()))?
+        .id();
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head_oid)?;
+    revwalk.hide(base_oid)?;
+
+    let mut count = 0;
+    for oid_result in revwalk.take(limit) {
+        oid_result?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Walk from `head_ref` back to (excluding) `base_rev`, or `None` when there is nothing to walk
+fn revwalk_since<'repo>(
+    repo: &'repo Repository,
+    base_rev: &str,
+    head_ref: Option<&str>,
+) -> Result<Option<git2::Revwalk<'repo>>, GitError> {
+    let head_oid = resolve_ref(repo, head_ref.unwrap_or("HEAD"))?;
 
     let base_oid = match repo.revparse_single(base_rev) {
         Ok(obj) => obj.id(),
-        Err(_) => return Ok(Vec::new()),
+        Err(_) => return Ok(None),
     };
 
     if head_oid == base_oid {
-        return Ok(Vec::new());
+        return Ok(None);
     }
 
     let mut revwalk = repo.revwalk()?;
     revwalk.push(head_oid)?;
     let _ = revwalk.hide(base_oid);
-
-    let mut commits = Vec::new();
-    for oid_result in revwalk.take(500) {
-        let oid = oid_result?;
-        if let Ok(commit) = repo.find_commit(oid) {
-            commits.push(commit_to_model(&commit));
-        }
-    }
-
-    Ok(commits)
+    Ok(Some(revwalk))
 }
 
 /// Get commits starting from a revision going back
@@ -708,13 +1192,80 @@ fn get_commits_from(repo: &Repository, rev: &str, limit: usize) -> Result<Vec<Co
     for oid_result in revwalk.take(limit) {
         let oid = oid_result?;
         if let Ok(commit) = repo.find_commit(oid) {
-            commits.push(commit_to_model(&commit));
+            commits.push(commit_to_model(repo, &commit));
         }
     }
 
     Ok(commits)
 }
 
+/// Compute line statistics between a base revision and the head of a ref,
+/// limited to `paths` when any are given
+fn diff_stats(
+    repo: &Repository,
+    base_rev: &str,
+    head_ref: Option<&str>,
+    paths: &[String],
+) -> Option<DiffStats> {
+    let head_oid = resolve_ref(repo, head_ref.unwrap_or("HEAD")).ok()?;
+    diff_stats_between(repo, base_rev, &head_oid.to_string(), paths)
+}
+
+/// Compute line statistics between two revisions, limited to `paths` when
+/// any are given
+fn diff_stats_between(
+    repo: &Repository,
+    base_rev: &str,
+    head_rev: &str,
+    paths: &[String],
+) -> Option<DiffStats> {
+    let base_tree = repo.revparse_single(base_rev).ok()?.peel_to_tree().ok()?;
+    let head_tree = repo.revparse_single(head_rev).ok()?.peel_to_tree().ok()?;
+    let diff = repo
+        .diff_tree_to_tree(
+            Some(&base_tree),
+            Some(&head_tree),
+            Some(&mut pathspec_options(paths)),
+        )
+        .ok()?;
+    let stats = diff.stats().ok()?;
+    Some(DiffStats {
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+        partial: false,
+    })
+}
+
+/// Whether the commit `sha` changes any file matched by `paths`.
+///
+/// Commits are compared against their first parent, so merges only count
+/// when they bring changes to those paths into the mainline.
+fn touches_paths(repo: &Repository, sha: &str, paths: &[String]) -> bool {
+    let touches = || -> Result<bool, git2::Error> {
+        let commit = repo.find_commit(git2::Oid::from_str(sha)?)?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut pathspec_options(paths)),
+        )?;
+        Ok(diff.deltas().len() > 0)
+    };
+    touches().unwrap_or(false)
+}
+
+fn pathspec_options(paths: &[String]) -> DiffOptions {
+    let mut options = DiffOptions::new();
+    for path in paths {
+        options.pathspec(path);
+    }
+    options
+}
+
 /// Resolve a reference to an OID
 fn resolve_ref(repo: &Repository, refname: &str) -> Result<git2::Oid, GitError> {
     if let Ok(reference) = repo.find_reference(&format!("refs/remotes/origin/{}", refname)) {
@@ -790,7 +1341,7 @@ fn resolve_ref(repo: &Repository, refname: &str) -> Result<git2::Oid, GitError>
 }
 
 /// Convert a git2 commit to our Commit model
-fn commit_to_model(commit: &git2::Commit) -> Commit {
+fn commit_to_model(repo: &Repository, commit: &git2::Commit) -> Commit {
     let sha = commit.id().to_string();
     let message = commit.summary().unwrap_or("").to_string();
     let author = commit.author().name().unwrap_or("Unknown").to_string();
@@ -805,6 +1356,17 @@ fn commit_to_model(commit: &git2::Commit) -> Commit {
         message,
         author,
         date,
+        signature: signature_status(repo, commit.id()),
+    }
+}
+
+/// Whether the commit `oid` is signed. Verifying the signature would need
+/// the signer's keys, so signed commits are reported as unverified.
+fn signature_status(repo: &Repository, oid: git2::Oid) -> SignatureStatus {
+    match repo.extract_signature(&oid, None) {
+        Ok(_) => SignatureStatus::Unverified,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => SignatureStatus::Unsigned,
+        Err(_) => SignatureStatus::Unknown,
     }
 }
 
@@ -826,6 +1388,122 @@ mod tests {
         assert_ne!(path1, path3);
     }
 
+    #[test]
+    fn test_touches_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tmp.path()).unwrap();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+
+        let commit_file = |path: &str| {
+            let file = tmp.path().join(path);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(&file, path).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new(path)).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            repo.commit(Some("HEAD"), &signature, &signature, path, &tree, &parents)
+                .unwrap()
+                .to_string()
+        };
+        let ripgrep = commit_file("pkgs/by-name/ri/ripgrep/package.nix");
+        let caddy = commit_file("nixos/modules/caddy.nix");
+
+        let paths = vec!["pkgs/by-name/ri/ripgrep".to_string()];
+        assert!(touches_paths(&repo, &ripgrep, &paths));
+        assert!(!touches_paths(&repo, &caddy, &paths));
+        assert!(touches_paths(&repo, &caddy, &["nixos/*.nix".to_string()]));
+    }
+
+    #[test]
+    fn test_read_commit_detail() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tmp.path()).unwrap();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+        let commit = |path: &str, contents: &str, message: &str| {
+            std::fs::write(tmp.path().join(path), contents).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new(path)).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap()
+            .to_string()
+        };
+        commit("a.nix", "one\ntwo\n", "init");
+        let sha = commit("a.nix", "one\nthree\nfour\n", "a: update\n\nWhy it changed");
+
+        let detail = read_commit_detail(&repo, &sha).unwrap();
+        assert_eq!(detail.message, "a: update\n\nWhy it changed");
+        assert_eq!(detail.email, "test@example.com");
+        assert_eq!(
+            detail.files,
+            [FileChange {
+                path: "a.nix".to_string(),
+                insertions: 2,
+                deletions: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn test_signature_status() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tmp.path()).unwrap();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let unsigned = repo
+            .commit(None, &signature, &signature, "unsigned", &tree, &[])
+            .unwrap();
+        let buffer = repo
+            .commit_create_buffer(&signature, &signature, "signed", &tree, &[])
+            .unwrap();
+        let signed = repo
+            .commit_signed(
+                buffer.as_str().unwrap(),
+                "-----BEGIN SSH SIGNATURE-----",
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(signature_status(&repo, unsigned), SignatureStatus::Unsigned);
+        assert_eq!(signature_status(&repo, signed), SignatureStatus::Unverified);
+    }
+
+    #[test]
+    fn test_list_tags_peels_annotated_tags() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tmp.path()).unwrap();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let oid = repo
+            .commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        let commit = repo.find_object(oid, None).unwrap();
+        repo.tag_lightweight("v1.0.0", &commit, false).unwrap();
+        repo.tag("v1.1.0", &commit, &signature, "release", false)
+            .unwrap();
+
+        let mut tags = list_tags(&repo).unwrap();
+        tags.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["v1.0.0", "v1.1.0"]);
+        assert!(tags.iter().all(|t| t.sha == oid.to_string()));
+    }
+
     fn git_input(repo: GitRepo, url: &str) -> GitInput {
         GitInput::new(
             InputName::new("nixpkgs").unwrap(),
@@ -837,6 +1515,32 @@ mod tests {
         )
     }
 
+    #[tokio::test]
+    async fn test_offline_check_reports_unknown() {
+        let config = ServiceConfig {
+            offline: true,
+            ..ServiceConfig::default()
+        };
+        let service = GitService::new_with_config(CancellationToken::new(), config);
+        let input = git_input(
+            GitRepo::github(
+                Owner::new("NixOS").unwrap(),
+                RepoName::new("nixpkgs").unwrap(),
+            ),
+            "github:NixOS/nixpkgs",
+        );
+
+        let mut results = Vec::new();
+        service
+            .check_updates_timed(&[input], |name, status, _| results.push((name, status)))
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.as_str(), "nixpkgs");
+        assert!(matches!(results[0].1, UpdateStatus::Unknown));
+    }
+
     #[test]
     fn test_get_clone_url() {
         let input = git_input(
@@ -878,10 +1582,4 @@ mod tests {
             Some("https://codeberg.org/forgejo/forgejo".to_string())
         );
     }
-
-    #[test]
-    fn test_urlencoding() {
-        assert_eq!(urlencoding("owner/repo"), "owner%2Frepo");
-        assert_eq!(urlencoding("simple"), "simple");
-    }
 }
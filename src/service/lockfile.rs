@@ -0,0 +1,723 @@
+//! Reading `flake.lock` from disk and from git history, committing it, and
+//! keeping a backup of it to undo the last update
+
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+use fnv::FnvHasher;
+
+use git2::build::TreeUpdateBuilder;
+use git2::{Commit, ErrorCode, FileMode, Oid, Repository, Status, Tree};
+
+use crate::error::{AppError, AppResult, GitError};
+use crate::model::{FlakeData, FlakeInput, LockChange, LockChangeKind, LockRevision};
+
+use super::nix::parse_lock_file;
+
+/// Path of `flake_dir`'s lock file in the tree of `repo`, with forward
+/// slashes as git uses
+fn lock_path_in_repo(repo: &Repository, flake_dir: &Path) -> AppResult<String> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::CloneFailed("Repository has no working tree".to_string()))?;
+    let workdir = workdir.canonicalize()?;
+    let flake_dir = flake_dir.canonicalize()?;
+    let relative = flake_dir
+        .strip_prefix(&workdir)
+        .unwrap_or(Path::new(""))
+        .join("flake.lock");
+    Ok(relative.to_string_lossy().replace('\\', "/"))
+}
+
+/// Which of `flake.nix` and `flake.lock` in `flake_dir` differ from `HEAD`,
+/// staged or not. Files git does not track yet are not counted, and a flake
+/// outside any git repository has nothing to lose.
+pub fn uncommitted_flake_files(flake_dir: &Path) -> AppResult<Vec<String>> {
+    let repo = match Repository::discover(flake_dir) {
+        Ok(repo) => repo,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(GitError::from(e).into()),
+    };
+    if repo.is_bare() {
+        return Ok(Vec::new());
+    }
+    let lock = lock_path_in_repo(&repo, flake_dir)?;
+    let dir = lock.strip_suffix("flake.lock").unwrap_or_default();
+
+    let changed = Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE
+        | Status::WT_MODIFIED
+        | Status::WT_DELETED
+        | Status::WT_RENAMED
+        | Status::WT_TYPECHANGE
+        | Status::CONFLICTED;
+    let mut dirty = Vec::new();
+    for file in ["flake.nix", "flake.lock"] {
+        let status = match repo.status_file(Path::new(&format!("{}{}", dir, file))) {
+            Ok(status) => status,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => continue,
+            Err(e) => return Err(GitError::from(e).into()),
+        };
+        if status.intersects(changed) {
+            dirty.push(file.to_string());
+        }
+    }
+    Ok(dirty)
+}
+
+/// Read `flake.lock` in `flake_dir` as committed at `rev` (e.g. `HEAD`, a branch, or a SHA).
+pub fn read_lock_at_rev(flake_dir: &Path, rev: &str) -> AppResult<String> {
+    let repo = Repository::discover(flake_dir).map_err(GitError::from)?;
+    let relative = lock_path_in_repo(&repo, flake_dir)?;
+
+    let spec = format!("{}:{}", rev, relative);
+    let object = repo
+        .revparse_single(&spec)
+        .map_err(|_| AppError::LockNotInRevision(rev.to_string()))?;
+    let blob = object
+        .peel_to_blob()
+        .map_err(|_| AppError::LockNotInRevision(rev.to_string()))?;
+
+    String::from_utf8(blob.content().to_vec())
+        .map_err(|e| AppError::MetadataParseError(e.to_string()))
+}
+
+/// The commits on the first-parent history of `HEAD` that changed
+/// `flake_dir`'s lock file, newest first, up to `limit` of them
+pub fn lock_history(flake_dir: &Path, limit: usize) -> AppResult<Vec<LockRevision>> {
+    let repo = Repository::discover(flake_dir).map_err(GitError::from)?;
+    let relative = lock_path_in_repo(&repo, flake_dir)?;
+    let lock_blob = |commit: &Commit| lock_blob(commit, &relative);
+    let parse = |blob: Option<Oid>| lock_in_blob(&repo, flake_dir, blob);
+
+    let mut revwalk = repo.revwalk().map_err(GitError::from)?;
+    revwalk.push_head().map_err(GitError::from)?;
+    revwalk.simplify_first_parent().map_err(GitError::from)?;
+
+    let mut history = Vec::new();
+    for oid in revwalk {
+        let commit = repo
+            .find_commit(oid.map_err(GitError::from)?)
+            .map_err(GitError::from)?;
+        let after = lock_blob(&commit);
+        let before = commit.parent(0).ok().and_then(|parent| lock_blob(&parent));
+        if after == before {
+            continue;
+        }
+        // A lock melt cannot read still marks when it changed.
+        let changes = match (parse(before), parse(after)) {
+            (Some(before), Some(after)) => lock_changes(&before, &after),
+            _ => Vec::new(),
+        };
+        history.push(LockRevision {
+            sha: commit.id().to_string(),
+            summary: commit.summary().unwrap_or("").to_string(),
+            author: commit.author().name().unwrap_or("").to_string(),
+            date: commit.time().seconds(),
+            changes,
+        });
+        if history.len() == limit {
+            break;
+        }
+    }
+    Ok(history)
+}
+
+/// Blob of the lock file at `relative` in `commit`'s tree
+fn lock_blob(commit: &Commit, relative: &str) -> Option<Oid> {
+    let entry = commit.tree().ok()?.get_path(Path::new(relative)).ok()?;
+    Some(entry.id())
+}
+
+/// The lock file stored in `blob`, or one without inputs when there is no
+/// blob. `None` when the blob is not a lock file melt can read.
+fn lock_in_blob(repo: &Repository, flake_dir: &Path, blob: Option<Oid>) -> Option<FlakeData> {
+    match blob {
+        Some(id) => {
+            let blob = repo.find_blob(id).ok()?;
+            let contents = std::str::from_utf8(blob.content()).ok()?;
+            parse_lock_file(flake_dir.to_path_buf(), contents).ok()
+        }
+        None => Some(FlakeData {
+            path: flake_dir.to_path_buf(),
+            inputs: Vec::new(),
+            warnings: Vec::new(),
+            input_trees: Vec::new(),
+        }),
+    }
+}
+
+/// Message used when no `commit_message` is configured
+pub const DEFAULT_COMMIT_MESSAGE: &str = "flake.lock: Update {inputs}\n\n{changes}";
+
+/// `template` with `{inputs}` replaced by the names of the inputs that
+/// moved and `{changes}` by a line per input with how it moved
+pub fn commit_message(template: &str, changes: &[LockChange]) -> String {
+    let inputs = if changes.is_empty() {
+        "transitive inputs".to_string()
+    } else {
+        changes
+            .iter()
+            .map(|change| change.input.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let lines = changes
+        .iter()
+        .map(|change| format!("- {}", change))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let message = template
+        .replace("{inputs}", &inputs)
+        .replace("{changes}", &lines);
+    format!("{}\n", message.trim_end())
+}
+
+/// A commit of `flake_dir`'s working tree lock file on top of `HEAD`,
+/// waiting to be written
+struct LockCommit<'r> {
+    parent: Option<Commit<'r>>,
+    tree: Tree<'r>,
+    changes: Vec<LockChange>,
+}
+
+impl<'r> LockCommit<'r> {
+    /// `None` when the lock file matches `HEAD`'s
+    fn prepare(repo: &'r Repository, flake_dir: &Path) -> AppResult<Option<Self>> {
+        let relative = lock_path_in_repo(repo, flake_dir)?;
+        let contents = read_lock(flake_dir)?;
+
+        let parent = head_commit(repo)?;
+        let base = match &parent {
+            Some(commit) => commit.tree().map_err(GitError::from)?,
+            None => {
+                let empty = repo
+                    .treebuilder(None)
+                    .and_then(|builder| builder.write())
+                    .map_err(GitError::from)?;
+                repo.find_tree(empty).map_err(GitError::from)?
+            }
+        };
+        let committed = base
+            .get_path(Path::new(&relative))
+            .ok()
+            .map(|entry| entry.id());
+        let blob = repo.blob(contents.as_bytes()).map_err(GitError::from)?;
+        if committed == Some(blob) {
+            return Ok(None);
+        }
+
+        let after = parse_lock_file(flake_dir.to_path_buf(), &contents)?;
+        let changes = lock_in_blob(repo, flake_dir, committed)
+            .map(|before| lock_changes(&before, &after))
+            .unwrap_or_default();
+        let tree = TreeUpdateBuilder::new()
+            .upsert(&relative, blob, FileMode::Blob)
+            .create_updated(repo, &base)
+            .and_then(|id| repo.find_tree(id))
+            .map_err(GitError::from)?;
+        Ok(Some(Self {
+            parent,
+            tree,
+            changes,
+        }))
+    }
+
+    /// Write the commit with `message` and move `reference` to it
+    fn write(&self, repo: &Repository, reference: &str, message: &str) -> AppResult<Oid> {
+        let signature = repo.signature().map_err(GitError::from)?;
+        let parents: Vec<&Commit> = self.parent.iter().collect();
+        let id = repo
+            .commit(
+                Some(reference),
+                &signature,
+                &signature,
+                message,
+                &self.tree,
+                &parents,
+            )
+            .map_err(GitError::from)?;
+        Ok(id)
+    }
+}
+
+/// The commit `HEAD` points to, or `None` before the first commit
+fn head_commit(repo: &Repository) -> AppResult<Option<Commit<'_>>> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_commit().map_err(GitError::from)?)),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => Ok(None),
+        Err(e) => Err(GitError::from(e).into()),
+    }
+}
+
+/// Commit `flake_dir`'s lock file as it is in the working tree, with a
+/// message made from `template` for the inputs that moved since `HEAD`.
+/// Only the lock file goes into the commit, whatever else is staged.
+///
+/// Returns the short SHA and summary of the commit, or `None` when the lock
+/// file matches `HEAD`.
+pub fn commit_lock(flake_dir: &Path, template: &str) -> AppResult<Option<String>> {
+    let repo = Repository::discover(flake_dir).map_err(GitError::from)?;
+    let Some(commit) = LockCommit::prepare(&repo, flake_dir)? else {
+        return Ok(None);
+    };
+    let message = commit_message(template, &commit.changes);
+    let id = commit.write(&repo, "HEAD", &message)?;
+
+    // Stage the lock as committed, so the index doesn't undo the commit.
+    let relative = lock_path_in_repo(&repo, flake_dir)?;
+    let mut index = repo.index().map_err(GitError::from)?;
+    index
+        .add_path(Path::new(&relative))
+        .and_then(|_| index.write())
+        .map_err(GitError::from)?;
+
+    let summary = message.lines().next().unwrap_or_default();
+    Ok(Some(format!("{} {}", &id.to_string()[..7], summary)))
+}
+
+/// The lock update a pull request would propose
+enum ProposedLock<'r> {
+    /// The working tree lock, to be committed on top of `HEAD`
+    Uncommitted(LockCommit<'r>),
+    /// `HEAD` itself, a commit that changes nothing but the lock
+    Head(Commit<'r>, Vec<LockChange>),
+}
+
+impl<'r> ProposedLock<'r> {
+    /// The working tree lock when it differs from `HEAD`'s. Otherwise the
+    /// update is taken to be `HEAD` itself, as left by `auto_commit`, but
+    /// only when that commit touches nothing besides the lock, so other
+    /// local work never ends up in the pull request.
+    fn find(repo: &'r Repository, flake_dir: &Path) -> AppResult<Self> {
+        if let Some(commit) = LockCommit::prepare(repo, flake_dir)? {
+            return Ok(Self::Uncommitted(commit));
+        }
+
+        let no_update = || AppError::PullRequest("flake.lock has no update to propose".to_string());
+        let head = head_commit(repo)?.ok_or_else(no_update)?;
+        let relative = lock_path_in_repo(repo, flake_dir)?;
+        let after = lock_blob(&head, &relative);
+        let parent = head.parent(0).ok();
+        let before = parent
+            .as_ref()
+            .and_then(|parent| lock_blob(parent, &relative));
+        if after.is_none() || after == before {
+            return Err(no_update());
+        }
+
+        let parent_tree = match &parent {
+            Some(parent) => Some(parent.tree().map_err(GitError::from)?),
+            None => None,
+        };
+        let head_tree = head.tree().map_err(GitError::from)?;
+        let diff = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&head_tree), None)
+            .map_err(GitError::from)?;
+        let only_lock = diff.deltas().all(|delta| {
+            [delta.old_file().path(), delta.new_file().path()]
+                .into_iter()
+                .flatten()
+                .all(|path| path.to_string_lossy().replace('\\', "/") == relative)
+        });
+        if !only_lock {
+            return Err(AppError::PullRequest(
+                "HEAD changes more than flake.lock; update the lock again or commit it on its own"
+                    .to_string(),
+            ));
+        }
+
+        let changes = match (
+            lock_in_blob(repo, flake_dir, before),
+            lock_in_blob(repo, flake_dir, after),
+        ) {
+            (Some(before), Some(after)) => lock_changes(&before, &after),
+            _ => Vec::new(),
+        };
+        Ok(Self::Head(head, changes))
+    }
+
+    /// Commit message of the update, made from `template` unless it is
+    /// `HEAD`'s own
+    fn message(&self, template: &str) -> String {
+        match self {
+            Self::Uncommitted(commit) => commit_message(template, &commit.changes),
+            Self::Head(head, _) => head.message().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// Commit message of the lock update [`branch_lock`] would propose for
+/// `flake_dir`, without creating anything
+pub fn proposed_lock_message(flake_dir: &Path, template: &str) -> AppResult<String> {
+    let repo = Repository::discover(flake_dir).map_err(GitError::from)?;
+    let message = ProposedLock::find(&repo, flake_dir)?.message(template);
+    Ok(message)
+}
+
+/// Create `branch` holding the lock update to propose, without touching
+/// `HEAD`, the index, or the working tree.
+///
+/// When the working tree lock differs from `HEAD`'s, the branch gets a
+/// commit of it on top of `HEAD`, with a message made from `template`.
+/// Otherwise the branch starts at `HEAD`, which must change nothing but the
+/// lock. Returns the commit message and how the root inputs moved.
+pub fn branch_lock(
+    flake_dir: &Path,
+    branch: &str,
+    template: &str,
+) -> AppResult<(String, Vec<LockChange>)> {
+    let repo = Repository::discover(flake_dir).map_err(GitError::from)?;
+    let proposed = ProposedLock::find(&repo, flake_dir)?;
+    let message = proposed.message(template);
+    match proposed {
+        ProposedLock::Uncommitted(commit) => {
+            commit.write(&repo, &format!("refs/heads/{}", branch), &message)?;
+            Ok((message, commit.changes))
+        }
+        ProposedLock::Head(head, changes) => {
+            repo.branch(branch, &head, false).map_err(GitError::from)?;
+            Ok((message, changes))
+        }
+    }
+}
+
+/// Root inputs whose locked revision differs between `before` and `after`
+fn lock_changes(before: &FlakeData, after: &FlakeData) -> Vec<LockChange> {
+    let short = |input: &FlakeInput| input.short_rev().map(str::to_string);
+    let mut changes = Vec::new();
+    for input in &after.inputs {
+        let kind = match before.inputs.iter().find(|old| old.name() == input.name()) {
+            None => LockChangeKind::Added { rev: short(input) },
+            Some(old) if old.locked_rev() == input.locked_rev() => continue,
+            Some(old) => LockChangeKind::Changed {
+                from: short(old),
+                to: short(input),
+            },
+        };
+        changes.push(LockChange {
+            input: input.name().to_string(),
+            kind,
+        });
+    }
+    for old in &before.inputs {
+        if !after.inputs.iter().any(|input| input.name() == old.name()) {
+            changes.push(LockChange {
+                input: old.name().to_string(),
+                kind: LockChangeKind::Removed { rev: short(old) },
+            });
+        }
+    }
+    changes
+}
+
+/// Read the `flake.lock` in `flake_dir` from the working tree
+pub fn read_lock(flake_dir: &Path) -> AppResult<String> {
+    let path = flake_dir.join("flake.lock");
+    if !path.exists() {
+        return Err(AppError::FlakeNotFound(flake_dir.to_path_buf()));
+    }
+    Ok(std::fs::read_to_string(path)?)
+}
+
+/// Where the backup of `flake_dir`'s lock file is kept in `backup_dir`,
+/// named by a hash that stays the same across Rust releases
+fn backup_path(flake_dir: &Path, backup_dir: &Path) -> PathBuf {
+    let flake_dir = flake_dir
+        .canonicalize()
+        .unwrap_or_else(|_| flake_dir.to_path_buf());
+    let mut hasher = FnvHasher::default();
+    hasher.write(flake_dir.as_os_str().as_encoded_bytes());
+    backup_dir.join(format!("{:016x}.lock", hasher.finish()))
+}
+
+/// Copy `flake.lock` in `flake_dir` to `backup_dir`, replacing the previous
+/// backup. Without a lock file, the previous backup is removed so it cannot
+/// be restored over a lock it never belonged to.
+pub fn backup_lock(flake_dir: &Path, backup_dir: &Path) -> AppResult<()> {
+    let lock = flake_dir.join("flake.lock");
+    let backup = backup_path(flake_dir, backup_dir);
+    if !lock.exists() {
+        return match std::fs::remove_file(&backup) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+    std::fs::create_dir_all(backup_dir)?;
+    std::fs::copy(lock, backup)?;
+    Ok(())
+}
+
+/// Put the backup made by [`backup_lock`] back in place and remove it.
+/// Returns false when there is no backup to restore.
+pub fn restore_lock(flake_dir: &Path, backup_dir: &Path) -> AppResult<bool> {
+    let backup = backup_path(flake_dir, backup_dir);
+    if !backup.exists() {
+        return Ok(false);
+    }
+    std::fs::copy(&backup, flake_dir.join("flake.lock"))?;
+    std::fs::remove_file(backup)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit_all(repo: &Repository, message: &str) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap();
+    }
+
+    #[test]
+    fn reads_committed_lock_from_nested_flake() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tmp.path()).unwrap();
+        let flake_dir = tmp.path().join("sub");
+        std::fs::create_dir_all(&flake_dir).unwrap();
+        std::fs::write(flake_dir.join("flake.lock"), "old").unwrap();
+        commit_all(&repo, "initial");
+        std::fs::write(flake_dir.join("flake.lock"), "new").unwrap();
+
+        assert_eq!(read_lock_at_rev(&flake_dir, "HEAD").unwrap(), "old");
+        assert_eq!(read_lock(&flake_dir).unwrap(), "new");
+        assert!(matches!(
+            read_lock_at_rev(&flake_dir, "does-not-exist"),
+            Err(AppError::LockNotInRevision(_))
+        ));
+    }
+
+    #[test]
+    fn reports_flake_files_with_uncommitted_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(uncommitted_flake_files(tmp.path()).unwrap().is_empty());
+
+        let repo = Repository::init(tmp.path()).unwrap();
+        let flake_dir = tmp.path().join("sub");
+        std::fs::create_dir_all(&flake_dir).unwrap();
+        std::fs::write(flake_dir.join("flake.nix"), "{ }").unwrap();
+        std::fs::write(flake_dir.join("flake.lock"), "old").unwrap();
+        commit_all(&repo, "initial");
+        assert!(uncommitted_flake_files(&flake_dir).unwrap().is_empty());
+
+        std::fs::write(flake_dir.join("flake.lock"), "new").unwrap();
+        assert_eq!(uncommitted_flake_files(&flake_dir).unwrap(), ["flake.lock"]);
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("sub/flake.lock")).unwrap();
+        index.write().unwrap();
+        std::fs::write(flake_dir.join("flake.nix"), "{ inputs = { }; }").unwrap();
+        assert_eq!(
+            uncommitted_flake_files(&flake_dir).unwrap(),
+            ["flake.nix", "flake.lock"]
+        );
+    }
+
+    #[test]
+    fn commits_only_the_lock_with_a_message_of_its_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tmp.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        let (old, new) = ("a".repeat(40), "b".repeat(40));
+        std::fs::write(
+            tmp.path().join("flake.lock"),
+            lock_with(&[("nixpkgs", &old), ("utils", &old)]),
+        )
+        .unwrap();
+        commit_all(&repo, "initial");
+        assert_eq!(
+            commit_lock(tmp.path(), DEFAULT_COMMIT_MESSAGE).unwrap(),
+            None
+        );
+
+        std::fs::write(
+            tmp.path().join("flake.lock"),
+            lock_with(&[("nixpkgs", &new), ("utils", &old)]),
+        )
+        .unwrap();
+        std::fs::write(tmp.path().join("flake.nix"), "{ }").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("flake.nix")).unwrap();
+        index.write().unwrap();
+
+        let commit = commit_lock(tmp.path(), DEFAULT_COMMIT_MESSAGE)
+            .unwrap()
+            .unwrap();
+        assert!(commit.ends_with(" flake.lock: Update nixpkgs"));
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(
+            head.message(),
+            Some("flake.lock: Update nixpkgs\n\n- nixpkgs aaaaaaa → bbbbbbb\n")
+        );
+        assert!(head
+            .tree()
+            .unwrap()
+            .get_path(Path::new("flake.nix"))
+            .is_err());
+        assert_eq!(
+            repo.status_file(Path::new("flake.nix")).unwrap(),
+            Status::INDEX_NEW
+        );
+        assert!(repo
+            .status_file(Path::new("flake.lock"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn branches_the_lock_update_without_touching_head() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tmp.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        let (old, new) = ("a".repeat(40), "b".repeat(40));
+        let lock = tmp.path().join("flake.lock");
+        std::fs::write(&lock, lock_with(&[("nixpkgs", &old)])).unwrap();
+        commit_all(&repo, "initial");
+        std::fs::write(tmp.path().join("README"), "docs").unwrap();
+        commit_all(&repo, "docs");
+        assert!(matches!(
+            branch_lock(tmp.path(), "melt/none", DEFAULT_COMMIT_MESSAGE),
+            Err(AppError::PullRequest(_))
+        ));
+
+        std::fs::write(&lock, lock_with(&[("nixpkgs", &new)])).unwrap();
+        let head = repo.head().unwrap().target();
+        let (message, changes) =
+            branch_lock(tmp.path(), "melt/update", DEFAULT_COMMIT_MESSAGE).unwrap();
+        assert!(message.starts_with("flake.lock: Update nixpkgs\n"));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(repo.head().unwrap().target(), head);
+        let branch = repo
+            .find_branch("melt/update", git2::BranchType::Local)
+            .unwrap();
+        let tip = branch.get().peel_to_commit().unwrap();
+        assert_eq!(tip.parent_id(0).ok(), head);
+        assert!(!repo
+            .status_file(Path::new("flake.lock"))
+            .unwrap()
+            .is_empty());
+
+        // Once committed, the update is HEAD itself.
+        commit_lock(tmp.path(), "bump").unwrap();
+        assert_eq!(
+            proposed_lock_message(tmp.path(), DEFAULT_COMMIT_MESSAGE).unwrap(),
+            "bump\n"
+        );
+        let (message, changes) =
+            branch_lock(tmp.path(), "melt/committed", DEFAULT_COMMIT_MESSAGE).unwrap();
+        assert_eq!((message.as_str(), changes.len()), ("bump\n", 1));
+
+        // A HEAD that changes anything else would carry that into the pull
+        // request too.
+        std::fs::write(&lock, lock_with(&[("nixpkgs", &old)])).unwrap();
+        std::fs::write(tmp.path().join("README"), "more docs").unwrap();
+        commit_all(&repo, "revert and docs");
+        assert!(matches!(
+            branch_lock(tmp.path(), "melt/mixed", DEFAULT_COMMIT_MESSAGE),
+            Err(AppError::PullRequest(_))
+        ));
+        assert!(repo
+            .find_branch("melt/mixed", git2::BranchType::Local)
+            .is_err());
+    }
+
+    fn lock_with(inputs: &[(&str, &str)]) -> String {
+        let roots: Vec<String> = inputs
+            .iter()
+            .map(|(name, _)| format!(r#""{0}": "{0}""#, name))
+            .collect();
+        let nodes: Vec<String> = inputs
+            .iter()
+            .map(|(name, rev)| {
+                format!(
+                    r#""{}": {{ "locked": {{ "type": "github", "owner": "o", "repo": "{}", "rev": "{}" }} }}"#,
+                    name, name, rev
+                )
+            })
+            .collect();
+        format!(
+            r#"{{ "root": "root", "version": 7, "nodes": {{ "root": {{ "inputs": {{ {} }} }}, {} }} }}"#,
+            roots.join(", "),
+            nodes.join(", ")
+        )
+    }
+
+    #[test]
+    fn lock_history_lists_commits_that_changed_the_lock() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tmp.path()).unwrap();
+        let old = "a".repeat(40);
+        let new = "b".repeat(40);
+        std::fs::write(
+            tmp.path().join("flake.lock"),
+            lock_with(&[("nixpkgs", &old)]),
+        )
+        .unwrap();
+        commit_all(&repo, "initial");
+        std::fs::write(tmp.path().join("README"), "docs").unwrap();
+        commit_all(&repo, "docs");
+        std::fs::write(
+            tmp.path().join("flake.lock"),
+            lock_with(&[("nixpkgs", &new), ("utils", &old)]),
+        )
+        .unwrap();
+        commit_all(&repo, "flake.lock: update");
+
+        let history = lock_history(tmp.path(), 10).unwrap();
+        let summaries: Vec<&str> = history.iter().map(|r| r.summary.as_str()).collect();
+        assert_eq!(summaries, ["flake.lock: update", "initial"]);
+        assert_eq!(
+            history[0].changes,
+            [
+                LockChange {
+                    input: "nixpkgs".to_string(),
+                    kind: LockChangeKind::Changed {
+                        from: Some("aaaaaaa".to_string()),
+                        to: Some("bbbbbbb".to_string()),
+                    },
+                },
+                LockChange {
+                    input: "utils".to_string(),
+                    kind: LockChangeKind::Added {
+                        rev: Some("aaaaaaa".to_string()),
+                    },
+                },
+            ]
+        );
+        assert_eq!(history[1].changes.len(), 1);
+        assert_eq!(lock_history(tmp.path(), 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn restores_lock_backup_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let flake_dir = tmp.path().join("flake");
+        let backup_dir = tmp.path().join("backups");
+        std::fs::create_dir_all(&flake_dir).unwrap();
+        assert!(!restore_lock(&flake_dir, &backup_dir).unwrap());
+
+        std::fs::write(flake_dir.join("flake.lock"), "before").unwrap();
+        backup_lock(&flake_dir, &backup_dir).unwrap();
+        std::fs::write(flake_dir.join("flake.lock"), "after").unwrap();
+
+        assert!(restore_lock(&flake_dir, &backup_dir).unwrap());
+        assert_eq!(read_lock(&flake_dir).unwrap(), "before");
+        assert!(!restore_lock(&flake_dir, &backup_dir).unwrap());
+    }
+}
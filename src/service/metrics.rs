@@ -0,0 +1,131 @@
+//! Session counters for the clone cache and forge API traffic
+//!
+//! Counting is opt-in with `metrics = true` in the config file; disabled
+//! counters ignore every record call.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counters shared by every clone of a [`GitService`](super::GitService)
+#[derive(Debug, Default)]
+pub struct CacheMetrics {
+    enabled: bool,
+    clone_hits: AtomicU64,
+    clone_misses: AtomicU64,
+    git_bytes: AtomicU64,
+    api_requests: AtomicU64,
+    api_bytes: AtomicU64,
+}
+
+impl CacheMetrics {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn add(&self, counter: &AtomicU64, n: u64) {
+        if self.enabled {
+            counter.fetch_add(n, Ordering::Relaxed);
+        }
+    }
+
+    /// A cached clone was reused
+    pub fn record_clone_hit(&self) {
+        self.add(&self.clone_hits, 1);
+    }
+
+    /// No cached clone existed, so one was cloned (or, offline, none was available)
+    pub fn record_clone_miss(&self) {
+        self.add(&self.clone_misses, 1);
+    }
+
+    /// Bytes received by a git clone or fetch
+    pub fn record_git_bytes(&self, bytes: u64) {
+        self.add(&self.git_bytes, bytes);
+    }
+
+    /// A forge API request was sent
+    pub fn record_api_request(&self) {
+        self.add(&self.api_requests, 1);
+    }
+
+    /// Bytes read from a forge API response body
+    pub fn record_api_bytes(&self, bytes: u64) {
+        self.add(&self.api_bytes, bytes);
+    }
+
+    /// Current totals, or `None` when metrics are disabled
+    pub fn snapshot(&self) -> Option<CacheStats> {
+        self.enabled.then(|| CacheStats {
+            clone_hits: self.clone_hits.load(Ordering::Relaxed),
+            clone_misses: self.clone_misses.load(Ordering::Relaxed),
+            git_bytes: self.git_bytes.load(Ordering::Relaxed),
+            api_requests: self.api_requests.load(Ordering::Relaxed),
+            api_bytes: self.api_bytes.load(Ordering::Relaxed),
+        })
+    }
+}
+
+/// Totals recorded by [`CacheMetrics`] so far this session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub clone_hits: u64,
+    pub clone_misses: u64,
+    pub git_bytes: u64,
+    pub api_requests: u64,
+    pub api_bytes: u64,
+}
+
+impl CacheStats {
+    /// Share of clone lookups answered by the cache, if there were any
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.clone_hits + self.clone_misses;
+        (total > 0).then(|| self.clone_hits as f64 / total as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_metrics_record_nothing() {
+        let metrics = CacheMetrics::new(false);
+        metrics.record_clone_hit();
+        metrics.record_git_bytes(10);
+        assert_eq!(metrics.snapshot(), None);
+    }
+
+    #[test]
+    fn records_totals_and_hit_rate() {
+        let metrics = CacheMetrics::new(true);
+        assert_eq!(metrics.snapshot().unwrap().hit_rate(), None);
+
+        metrics.record_clone_hit();
+        metrics.record_clone_hit();
+        metrics.record_clone_hit();
+        metrics.record_clone_miss();
+        metrics.record_git_bytes(1000);
+        metrics.record_git_bytes(24);
+        metrics.record_api_request();
+        metrics.record_api_bytes(512);
+
+        let stats = metrics.snapshot().unwrap();
+        assert_eq!(
+            stats,
+            CacheStats {
+                clone_hits: 3,
+                clone_misses: 1,
+                git_bytes: 1024,
+                api_requests: 1,
+                api_bytes: 512,
+            }
+        );
+        assert_eq!(stats.hit_rate(), Some(0.75));
+    }
+}
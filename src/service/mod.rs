@@ -1,5 +1,20 @@
+mod discovery;
+mod flake_nix;
+mod forge;
 mod git;
+mod lockfile;
+mod metrics;
 mod nix;
+mod nix_version;
+mod registry;
 
-pub use git::GitService;
-pub use nix::NixService;
+pub use discovery::{discover_flakes, discovery_root};
+pub use forge::{
+    token_lookup, ForgeClient, ForgeFuture, GitHubClient, GitLabClient, GiteaClient,
+    SourceHutClient,
+};
+pub use git::{ChangelogProgress, GitService};
+pub use lockfile::{read_lock, read_lock_at_rev};
+pub use metrics::{CacheMetrics, CacheStats};
+pub use nix::{parse_lock_file, parse_lock_nodes, NixService};
+pub use nix_version::{FlakeUpdate, NixVersion};
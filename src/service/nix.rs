@@ -1,25 +1,67 @@
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::Arc;
 use std::time::Duration;
 
 use serde::Deserialize;
 use tracing::{debug, warn};
 
-use crate::config::ServiceConfig;
+use crate::config::{
+    self, ForgeHosts, ForgeKind, ServiceConfig, Timeouts, TrustedProjects, UpdateCommands,
+};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 use tokio::process::Command;
+use tokio::sync::OnceCell;
 use tokio_util::sync::CancellationToken;
 
-use crate::error::{AppError, AppResult};
+use crate::error::{AppError, AppResult, GitError};
 use crate::model::{
-    CloneUrl, FlakeData, FlakeInput, GitHost, GitInput, GitRef, GitRepo, GitRev, InputName,
-    OtherInput, Owner, PathInput, RepoName,
+    ChangelogData, CloneUrl, ClosureDiff, FlakeData, FlakeInput, FlakeTemplate, FollowsInput,
+    GitHost, GitInput, GitRef, GitRepo, GitRev, InputGlobs, InputName, InputTree, LockNode,
+    LockRevision, LockWarning, OtherInput, Owner, PackageChange, PathInput, PullRequest, RepoName,
+};
+
+use super::flake_nix::set_input_ref;
+use super::lockfile::{
+    backup_lock, branch_lock, commit_lock, lock_history, proposed_lock_message, read_lock,
+    read_lock_at_rev, restore_lock, uncommitted_flake_files, DEFAULT_COMMIT_MESSAGE,
 };
+use super::nix_version::{FlakeUpdate, NixVersion};
+use super::registry::{FlakeRef, FlakeRegistry};
+
+/// How many `flake.lock` changes the lock history goes back
+const LOCK_HISTORY_LIMIT: usize = 200;
+
+/// Lines of a failed build's log kept in its error
+const BUILD_FAILURE_LINES: usize = 20;
+
+/// Remote lock updates are pushed to for pull requests
+const PR_REMOTE: &str = "origin";
 
 /// Service for interacting with Nix flakes
 #[derive(Clone)]
 pub struct NixService {
     cancel_token: CancellationToken,
+    nix_binary: PathBuf,
+    nix_args: Vec<String>,
+    /// Version of `nix_binary`, read on first use
+    nix_version: Arc<OnceCell<NixVersion>>,
+    /// Flake registry `indirect` inputs resolve through, read on first use
+    registry: Arc<OnceCell<FlakeRegistry>>,
     nix_command_timeout: Duration,
+    input_globs: InputGlobs,
+    forge_hosts: ForgeHosts,
+    update_commands: UpdateCommands,
+    offline: bool,
+    read_lock_file: bool,
+    /// Where `flake.lock` is backed up before each update
+    lock_backup_dir: PathBuf,
+    build_outputs: Vec<String>,
+    diff_closure: Option<String>,
+    auto_commit: bool,
+    commit_message: Option<String>,
+    trusted_projects: TrustedProjects,
 }
 
 impl NixService {
@@ -31,68 +73,642 @@ impl NixService {
     pub fn new_with_config(cancel_token: CancellationToken, config: ServiceConfig) -> Self {
         Self {
             cancel_token,
+            nix_binary: config.nix_binary,
+            nix_args: config.nix_args,
+            nix_version: Arc::new(OnceCell::new()),
+            registry: Arc::new(OnceCell::new()),
             nix_command_timeout: config.timeouts.nix_command,
+            input_globs: config.input_globs,
+            forge_hosts: config.forge_hosts,
+            update_commands: config.update_commands,
+            offline: config.offline,
+            read_lock_file: config.read_lock_file,
+            lock_backup_dir: crate::paths::lock_backup_dir(),
+            build_outputs: config.build_outputs,
+            diff_closure: config.diff_closure,
+            auto_commit: config.auto_commit,
+            commit_message: config.commit_message,
+            trusted_projects: config.trusted_projects,
         }
     }
 
+    /// Back up lock files in `dir` instead of the cache directory
+    pub fn with_lock_backup_dir(mut self, dir: PathBuf) -> Self {
+        self.lock_backup_dir = dir;
+        self
+    }
+
+    /// Use `timeouts` for commands started from now on
+    pub fn set_timeouts(&mut self, timeouts: &Timeouts) {
+        self.nix_command_timeout = timeouts.nix_command;
+    }
+
+    /// This service with the overrides of the `.melt.toml` next to the
+    /// flake at `flake_path` applied. Settings that run commands or commit
+    /// only apply when the flake is in `trusted_projects`, so a cloned
+    /// repository can't run anything just by being updated.
+    fn for_flake(&self, flake_path: &Path) -> AppResult<Self> {
+        let project = config::load_project(flake_path)?;
+        let needs_trust = project.needs_trust();
+        let mut nix = self.clone();
+        if let Some(ignore) = project.ignore {
+            nix.input_globs.ignore = ignore;
+        }
+        if let Some(freeze) = project.freeze {
+            nix.input_globs.freeze = freeze;
+        }
+        nix.forge_hosts.extend(project.forge_hosts);
+        if !self.trusted_projects.contains(flake_path) {
+            if needs_trust {
+                warn!(
+                    "Ignoring commands and commit settings in {} of untrusted project {}",
+                    config::PROJECT_FILE,
+                    flake_path.display()
+                );
+            }
+            return Ok(nix);
+        }
+        nix.update_commands.prepend(project.update_commands);
+        if let Some(build_outputs) = project.build_outputs {
+            nix.build_outputs = build_outputs;
+        }
+        if let Some(diff_closure) = project.diff_closure {
+            nix.diff_closure = Some(diff_closure);
+        }
+        if let Some(auto_commit) = project.auto_commit {
+            nix.auto_commit = auto_commit;
+        }
+        if let Some(commit_message) = project.commit_message {
+            nix.commit_message = Some(commit_message);
+        }
+        Ok(nix)
+    }
+
+    /// Load the flake's inputs with `nix flake metadata`, or straight from
+    /// `flake.lock` when offline, when configured to, or when nix is missing
+    /// or times out
     pub async fn load_metadata(&self, path: &Path) -> AppResult<FlakeData> {
+        if self.offline || self.read_lock_file {
+            // `nix flake metadata` may fetch inputs, and is slow even when
+            // it doesn't.
+            return self.load_lock_file(path).await;
+        }
+        let flake_path = resolve_flake_path(path)?;
+
+        if !flake_path.join("flake.nix").exists() {
+            return Err(AppError::FlakeNotFound(flake_path));
+        }
+        let has_lock = flake_path.join("flake.lock").exists();
+        let output = match self.run_nix_metadata(&flake_path).await {
+            Ok(output) => output,
+            Err(e) if has_lock && is_nix_unavailable(&e) => {
+                warn!(error = %e, "`nix flake metadata` unavailable; reading flake.lock");
+                return self.load_lock_file(path).await;
+            }
+            Err(e) => return Err(e),
+        };
+        let flake = match serde_json::from_str::<NixFlakeMetadata>(&output) {
+            Ok(mut metadata) => {
+                metadata.locks.check_version()?;
+                self.resolve_indirect(&mut metadata.locks).await;
+                parse_metadata(flake_path.clone(), metadata)
+            }
+            // A nix whose output changed shape can still leave a lock file
+            // melt knows how to read.
+            Err(e) if has_lock => {
+                warn!(error = %e, "Unexpected `nix flake metadata` output; reading flake.lock");
+                return self.load_lock_file(path).await;
+            }
+            Err(e) => return Err(AppError::MetadataParseError(e.to_string())),
+        };
+        Ok(self.for_flake(&flake_path)?.select_inputs(flake))
+    }
+
+    /// Load the flake's inputs by parsing its `flake.lock`, without running
+    /// nix. Inputs that are not locked yet are missing, and registry inputs
+    /// are shown as locked.
+    pub async fn load_lock_file(&self, path: &Path) -> AppResult<FlakeData> {
         let flake_path = resolve_flake_path(path)?;
 
         if !flake_path.join("flake.nix").exists() {
             return Err(AppError::FlakeNotFound(flake_path));
         }
+        if !flake_path.join("flake.lock").exists() {
+            return Err(AppError::MetadataParseError(
+                "No flake.lock to read".to_string(),
+            ));
+        }
+        let locks = read_locks(&read_lock(&flake_path)?)?;
+        let flake = parse_locks(flake_path.clone(), locks);
+        Ok(self.for_flake(&flake_path)?.select_inputs(flake))
+    }
 
-        let output = self.run_nix_metadata(&flake_path).await?;
-        let metadata: NixFlakeMetadata = serde_json::from_str(&output)
-            .map_err(|e| AppError::MetadataParseError(e.to_string()))?;
+    /// Point `indirect` inputs of `locks` at what the flake registry maps
+    /// them to. Inputs stay as locked when the registry cannot be listed.
+    async fn resolve_indirect(&self, locks: &mut NixLocks) {
+        if !locks.has_indirect() {
+            return;
+        }
+        let registry = self
+            .registry
+            .get_or_try_init(|| async {
+                let output = self.run_nix_command(&["registry", "list"]).await?;
+                Ok::<_, AppError>(FlakeRegistry::parse(&output))
+            })
+            .await;
+        match registry {
+            Ok(registry) => locks.resolve_indirect(registry),
+            Err(e) => debug!(error = %e, "Cannot list the flake registry"),
+        }
+    }
 
-        Ok(parse_metadata(flake_path, metadata))
+    /// Drop the inputs excluded by the input globs and apply the configured
+    /// forge hosts to the rest
+    fn select_inputs(&self, mut flake: FlakeData) -> FlakeData {
+        flake
+            .inputs
+            .retain(|input| self.input_globs.matches(input.name()));
+        if !self.forge_hosts.is_empty() {
+            flake.inputs = flake
+                .inputs
+                .into_iter()
+                .map(|input| apply_forge_hosts(input, &self.forge_hosts))
+                .collect();
+        }
+        flake
     }
 
+    /// Resolve the flake directory and its project settings for updating
+    /// `names`, and back up the lock file. Fails if one of them is frozen.
+    fn prepare_update(&self, path: &Path, names: &[String]) -> AppResult<(PathBuf, Self)> {
+        let flake_dir = resolve_flake_path(path)?;
+        let nix = self.for_flake(&flake_dir)?;
+        if let Some(frozen) = names.iter().find(|name| nix.input_globs.is_frozen(name)) {
+            return Err(AppError::InputFrozen(frozen.clone()));
+        }
+        backup_lock(&flake_dir, &self.lock_backup_dir)?;
+        Ok((flake_dir, nix))
+    }
+
+    /// Update the named inputs. Fails without updating anything if one of
+    /// them is frozen. Inputs with an update command are updated by running
+    /// it, after nix has updated the rest.
     pub async fn update_inputs(&self, path: &Path, names: &[String]) -> AppResult<()> {
         if names.is_empty() {
             return Ok(());
         }
+        let (flake_dir, nix) = self.prepare_update(path, names)?;
+        let (custom, names): (Vec<&String>, Vec<&String>) = names
+            .iter()
+            .partition(|name| nix.update_commands.get(name).is_some());
+
+        if !names.is_empty() {
+            debug!(inputs = ?names, "Updating inputs");
+
+            let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+            let version = self.nix_version().await?;
+            let args =
+                version.flake_update_args(FlakeUpdate::Inputs(&names), &path.to_string_lossy());
+            self.run_nix_args(&args).await?;
+        }
 
-        debug!(inputs = ?names, "Updating inputs");
-
-        let mut args = vec!["flake", "update"];
-        for name in names {
-            args.push(name);
+        for name in custom {
+            if let Some(command) = nix.update_commands.get(name) {
+                let command = UpdateCommands::expand(command, name, &flake_dir);
+                self.run_update_command(name, &command, &flake_dir).await?;
+            }
         }
-        args.push("--flake");
-        let path_str = path.to_string_lossy();
-        args.push(&path_str);
+        Ok(())
+    }
 
-        self.run_nix_command(&args).await?;
+    /// Update the named inputs one at a time, in order, calling `on_input`
+    /// with the position and name of each as it starts. Fails without
+    /// updating anything if one of them is frozen, and stops at the first
+    /// input that fails to update.
+    pub async fn update_inputs_each(
+        &self,
+        path: &Path,
+        names: &[String],
+        on_input: impl Fn(usize, &str) + Send,
+    ) -> AppResult<()> {
+        if names.is_empty() {
+            return Ok(());
+        }
+        let (flake_dir, nix) = self.prepare_update(path, names)?;
+
+        for (position, name) in names.iter().enumerate() {
+            on_input(position, name);
+            if let Some(command) = nix.update_commands.get(name) {
+                let command = UpdateCommands::expand(command, name, &flake_dir);
+                self.run_update_command(name, &command, &flake_dir).await?;
+                continue;
+            }
+            debug!(input = %name, "Updating input");
+            let version = self.nix_version().await?;
+            let args = version.flake_update_args(
+                FlakeUpdate::Inputs(&[name.as_str()]),
+                &path.to_string_lossy(),
+            );
+            self.run_nix_args(&args).await?;
+        }
         Ok(())
     }
 
+    /// Update every input, or only the inputs kept by `--only` / `--exclude`
+    /// that are not frozen
     pub async fn update_all(&self, path: &Path) -> AppResult<()> {
+        let nix = self.for_flake(&resolve_flake_path(path)?)?;
+        if !nix.input_globs.is_empty() || !nix.update_commands.is_empty() {
+            let flake = self.load_metadata(path).await?;
+            let names: Vec<String> = flake
+                .inputs
+                .iter()
+                .map(|input| input.name().to_string())
+                .filter(|name| !nix.input_globs.is_frozen(name))
+                .collect();
+            return self.update_inputs(path, &names).await;
+        }
+
         debug!("Updating all inputs");
-        let path_str = path.to_string_lossy();
-        self.run_nix_command(&["flake", "update", "--flake", &path_str])
-            .await?;
+        backup_lock(&resolve_flake_path(path)?, &self.lock_backup_dir)?;
+        let version = self.nix_version().await?;
+        let args = version.flake_update_args(FlakeUpdate::All, &path.to_string_lossy());
+        self.run_nix_args(&args).await?;
         Ok(())
     }
 
     pub async fn lock_input(&self, path: &Path, name: &str, override_url: &str) -> AppResult<()> {
         debug!(input = %name, "Locking input");
-        let path_str = path.to_string_lossy();
-        self.run_nix_command(&[
-            "flake",
-            "update",
+        backup_lock(&resolve_flake_path(path)?, &self.lock_backup_dir)?;
+        let version = self.nix_version().await?;
+        let update = FlakeUpdate::Override {
             name,
-            "--override-input",
-            name,
-            override_url,
-            "--flake",
-            &path_str,
+            url: override_url,
+        };
+        let args = version.flake_update_args(update, &path.to_string_lossy());
+        self.run_nix_args(&args).await?;
+        Ok(())
+    }
+
+    /// Restore `flake.lock` as it was before the last update or lock.
+    /// Returns false when there is nothing to restore.
+    pub async fn undo_last_operation(&self, path: &Path) -> AppResult<bool> {
+        let flake_dir = resolve_flake_path(path)?;
+        let restored = restore_lock(&flake_dir, &self.lock_backup_dir)?;
+        if restored {
+            debug!(path = %flake_dir.display(), "Restored flake.lock");
+        }
+        Ok(restored)
+    }
+
+    /// Make `input` track `new_ref` by rewriting its URL in flake.nix, then
+    /// re-lock it. flake.nix is put back if locking fails.
+    pub async fn change_ref(&self, path: &Path, input: &GitInput, new_ref: &str) -> AppResult<()> {
+        let flake_dir = resolve_flake_path(path)?;
+        let flake_nix = flake_dir.join("flake.nix");
+        let original = std::fs::read_to_string(&flake_nix)?;
+        let edited = set_input_ref(&original, input, new_ref)?;
+
+        debug!(input = %input.name(), reference = %new_ref, "Changing tracked ref");
+        std::fs::write(&flake_nix, edited)?;
+        if let Err(e) = self.update_inputs(path, &[input.name().to_string()]).await {
+            std::fs::write(&flake_nix, original)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// The flake's inputs and, below each, the inputs it declares, read from
+    /// `flake.lock`
+    pub async fn input_tree(&self, path: &Path) -> AppResult<Vec<InputTree>> {
+        let flake_dir = resolve_flake_path(path)?;
+        parse_input_tree(&read_lock(&flake_dir)?)
+    }
+
+    /// Commits of the flake's repository that changed its `flake.lock`,
+    /// newest first
+    pub async fn lock_history(&self, path: &Path) -> AppResult<Vec<LockRevision>> {
+        let flake_dir = resolve_flake_path(path)?;
+        tokio::task::spawn_blocking(move || lock_history(&flake_dir, LOCK_HISTORY_LIMIT))
+            .await
+            .map_err(|e| GitError::CloneFailed(format!("Task failed: {}", e)))?
+    }
+
+    /// Replace `flake.lock` with the one committed at `rev`, backing up the
+    /// current one first so the restore can be undone
+    pub async fn restore_lock_at(&self, path: &Path, rev: &str) -> AppResult<()> {
+        let flake_dir = resolve_flake_path(path)?;
+        let contents = read_lock_at_rev(&flake_dir, rev)?;
+        debug!(rev = %rev, "Restoring flake.lock");
+        backup_lock(&flake_dir, &self.lock_backup_dir)?;
+        std::fs::write(flake_dir.join("flake.lock"), contents)?;
+        Ok(())
+    }
+
+    /// Which of `flake.nix` and `flake.lock` at `path` have changes that are
+    /// not committed
+    pub fn uncommitted_flake_files(&self, path: &Path) -> AppResult<Vec<String>> {
+        let flake_dir = resolve_flake_path(path)?;
+        uncommitted_flake_files(&flake_dir)
+    }
+
+    /// Message template to commit `flake.lock` with after updating the flake
+    /// at `path`, or `None` when auto-commit is off
+    pub fn commit_template(&self, path: &Path) -> AppResult<Option<String>> {
+        let flake_dir = resolve_flake_path(path)?;
+        let nix = self.for_flake(&flake_dir)?;
+        Ok(nix.auto_commit.then(|| {
+            nix.commit_message
+                .unwrap_or_else(|| DEFAULT_COMMIT_MESSAGE.to_string())
+        }))
+    }
+
+    /// Commit the flake's `flake.lock`, returning the short SHA and summary
+    /// of the commit, or `None` when there was nothing to commit
+    pub async fn commit_lock(&self, path: &Path, template: String) -> AppResult<Option<String>> {
+        let flake_dir = resolve_flake_path(path)?;
+        tokio::task::spawn_blocking(move || commit_lock(&flake_dir, &template))
+            .await
+            .map_err(|e| GitError::CloneFailed(format!("Task failed: {}", e)))?
+    }
+
+    /// Branch and title [`NixService::open_pull_request`] would use for the
+    /// flake's lock update, without creating anything, so they can be
+    /// confirmed first
+    pub fn pull_request_draft(&self, path: &Path) -> AppResult<(String, String)> {
+        let flake_dir = resolve_flake_path(path)?;
+        let nix = self.for_flake(&flake_dir)?;
+        let template = nix
+            .commit_message
+            .unwrap_or_else(|| DEFAULT_COMMIT_MESSAGE.to_string());
+        let message = proposed_lock_message(&flake_dir, &template)?;
+        let branch = format!("melt/update-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S"));
+        let title = message.lines().next().unwrap_or_default().to_string();
+        Ok((branch, title))
+    }
+
+    /// Push the flake's lock update to `branch` on `origin` and open a
+    /// pull request for it with `gh`, or with `glab` when `origin` is on
+    /// GitLab. Commits in `changelogs` are listed in the description.
+    /// Returns the pull request's URL.
+    pub async fn open_pull_request(
+        &self,
+        path: &Path,
+        branch: String,
+        changelogs: HashMap<String, ChangelogData>,
+    ) -> AppResult<String> {
+        let flake_dir = resolve_flake_path(path)?;
+        let nix = self.for_flake(&flake_dir)?;
+        let template = nix
+            .commit_message
+            .unwrap_or_else(|| DEFAULT_COMMIT_MESSAGE.to_string());
+
+        let (dir, name) = (flake_dir.clone(), branch.clone());
+        let (message, changes) =
+            tokio::task::spawn_blocking(move || branch_lock(&dir, &name, &template))
+                .await
+                .map_err(|e| GitError::CloneFailed(format!("Task failed: {}", e)))??;
+        let pr = PullRequest::new(branch, &message, &changes, &changelogs);
+        debug!(branch = %pr.branch, "Opening pull request");
+
+        self.run_pull_request_step(&flake_dir, "git", &["push", PR_REMOTE, &pr.branch])
+            .await?;
+        let gitlab = remote_host(&flake_dir, PR_REMOTE).is_some_and(|host| {
+            host == "gitlab.com" || nix.forge_hosts.get(&host) == Some(ForgeKind::GitLab)
+        });
+        let output = if gitlab {
+            let args = [
+                "mr",
+                "create",
+                "--source-branch",
+                &pr.branch,
+                "--title",
+                &pr.title,
+                "--description",
+                &pr.body,
+                "--yes",
+            ];
+            self.run_pull_request_step(&flake_dir, "glab", &args)
+                .await?
+        } else {
+            let args = [
+                "pr", "create", "--head", &pr.branch, "--title", &pr.title, "--body", &pr.body,
+            ];
+            self.run_pull_request_step(&flake_dir, "gh", &args).await?
+        };
+        let url = output
+            .lines()
+            .rev()
+            .find(|line| line.starts_with("https://"))
+            .unwrap_or_else(|| output.trim());
+        Ok(url.to_string())
+    }
+
+    /// Run `program` in `dir` for [`NixService::open_pull_request`],
+    /// returning its output
+    async fn run_pull_request_step(
+        &self,
+        dir: &Path,
+        program: &str,
+        args: &[&str],
+    ) -> AppResult<String> {
+        let mut cmd = Command::new(program);
+        cmd.args(args).current_dir(dir).stdin(Stdio::null());
+        let output = self.run(cmd).await.map_err(|e| match e {
+            AppError::NixCommandFailed(message) => AppError::PullRequest(message),
+            AppError::Io(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                AppError::PullRequest(format!("{} is not installed", program))
+            }
+            e => e,
+        })?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            warn!(program, "Pull request step failed");
+            return Err(AppError::PullRequest(format!(
+                "{} failed: {}",
+                program,
+                stderr.trim()
+            )));
+        }
+        Ok(format!("{}{}", stdout, stderr))
+    }
+
+    /// Outputs to offer to build after updating the flake at `path`
+    pub fn build_outputs(&self, path: &Path) -> AppResult<Vec<String>> {
+        let flake_dir = resolve_flake_path(path)?;
+        Ok(self.for_flake(&flake_dir)?.build_outputs)
+    }
+
+    /// Installable whose closure is compared across nixpkgs updates of the
+    /// flake at `path`, if one is configured
+    pub fn diff_closure(&self, path: &Path) -> AppResult<Option<String>> {
+        let flake_dir = resolve_flake_path(path)?;
+        Ok(self.for_flake(&flake_dir)?.diff_closure)
+    }
+
+    /// Build `output` of the flake at `path` with `nix build`, passing each
+    /// line of its log to `on_line` as it is written, and return its store
+    /// paths.
+    ///
+    /// Builds can take far longer than other nix commands, so only
+    /// cancellation stops them. A failed build's error ends with the last
+    /// lines of its log.
+    pub async fn build_output(
+        &self,
+        path: &Path,
+        output: &str,
+        on_line: impl Fn(String),
+    ) -> AppResult<Vec<String>> {
+        let flake_dir = resolve_flake_path(path)?;
+        debug!(output = %output, "Building output");
+        let mut cmd = self.nix_command();
+        cmd.args([
+            "build",
+            "--no-link",
+            "--print-out-paths",
+            "--print-build-logs",
+            output,
         ])
-        .await?;
+        .current_dir(&flake_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+        let mut child = cmd.spawn()?;
+        let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+            return Err(AppError::NixCommandFailed("No build log".to_string()));
+        };
+        let mut out_paths = String::new();
+        let mut lines = BufReader::new(stderr).lines();
+        let mut tail: VecDeque<String> = VecDeque::with_capacity(BUILD_FAILURE_LINES);
+
+        let build = async {
+            while let Some(line) = lines.next_line().await? {
+                if tail.len() == BUILD_FAILURE_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line.clone());
+                on_line(line);
+            }
+            // Store paths are printed once the build is done, and are short
+            // enough not to fill the pipe before then.
+            BufReader::new(stdout)
+                .read_to_string(&mut out_paths)
+                .await?;
+            child.wait().await
+        };
+        let status = tokio::select! {
+            status = build => status?,
+            _ = self.cancel_token.cancelled() => {
+                return Err(AppError::NixCommandFailed("Operation cancelled".to_string()));
+            }
+        };
+
+        if !status.success() {
+            warn!(output = %output, "Build failed");
+            let log = Vec::from(tail).join("\n");
+            return Err(AppError::BuildFailed(output.to_string(), log));
+        }
+        Ok(out_paths.lines().map(str::to_string).collect())
+    }
+
+    /// Package version and size changes between the closures of the store
+    /// paths `before` and `after`, built from `installable`
+    pub async fn diff_closures(
+        &self,
+        installable: &str,
+        before: &str,
+        after: &str,
+    ) -> AppResult<ClosureDiff> {
+        debug!(before = %before, after = %after, "Diffing closures");
+        let output = self
+            .run_nix_command(&["store", "diff-closures", before, after])
+            .await?;
+        Ok(ClosureDiff {
+            installable: installable.to_string(),
+            before: before.to_string(),
+            after: after.to_string(),
+            changes: output.lines().filter_map(PackageChange::parse).collect(),
+        })
+    }
+
+    /// List the templates exposed by a template source (e.g. the `templates` registry entry)
+    pub async fn list_templates(&self, source: &str) -> AppResult<Vec<FlakeTemplate>> {
+        debug!(source = %source, "Listing templates");
+        let output = self
+            .run_nix_command(&["flake", "show", "--json", source])
+            .await?;
+        parse_templates(source, &output)
+    }
+
+    /// Initialize a flake in `path` from a template with `nix flake init -t`
+    pub async fn init_from_template(&self, path: &Path, template: &str) -> AppResult<()> {
+        debug!(path = ?path, template = %template, "Initializing flake from template");
+        std::fs::create_dir_all(path)?;
+        self.run_nix_command_in(Some(path), &["flake", "init", "--template", template])
+            .await?;
         Ok(())
     }
 
+    /// Get the version string reported by `nix --version`
+    pub async fn version(&self) -> AppResult<String> {
+        let output = self.run_nix_command(&["--version"]).await?;
+        Ok(output.trim().to_string())
+    }
+
+    /// Versions the flake's inputs expose, by input name: nixpkgs-style
+    /// `lib.version`, else the version of the default package for the
+    /// current system. Inputs without one, or whose evaluation fails, are
+    /// left out.
+    pub async fn input_versions(&self, path: &Path) -> AppResult<HashMap<String, String>> {
+        let flake_dir = resolve_flake_path(path)?;
+        debug!(path = ?flake_dir, "Evaluating input versions");
+        let output = self
+            .run_nix_command(&[
+                "eval",
+                "--json",
+                "--impure",
+                "--expr",
+                &versions_expr(&flake_dir),
+            ])
+            .await?;
+        parse_versions(&output)
+    }
+
+    /// The installed nix's version, read once. Fails when nix is older
+    /// than melt supports; versions melt cannot read are taken to be
+    /// current.
+    pub async fn nix_version(&self) -> AppResult<NixVersion> {
+        let version = *self
+            .nix_version
+            .get_or_try_init(|| async {
+                let output = self.version().await?;
+                debug!(version = %output, "Detected nix version");
+                Ok::<_, AppError>(NixVersion::parse(&output).unwrap_or_default())
+            })
+            .await?;
+        if !version.is_supported() {
+            return Err(AppError::UnsupportedNix(version.to_string()));
+        }
+        Ok(version)
+    }
+
+    /// Get the experimental features nix has enabled
+    pub async fn experimental_features(&self) -> AppResult<Vec<String>> {
+        let version = self.nix_version().await?;
+        let output = self
+            .run_nix_args(&version.config_show_args("experimental-features"))
+            .await?;
+        // `nix show-config` lists every setting as `name = value`.
+        let features = output
+            .lines()
+            .find_map(|line| line.strip_prefix("experimental-features = "))
+            .unwrap_or(&output);
+        Ok(features.split_whitespace().map(str::to_string).collect())
+    }
+
     /// Run `nix flake metadata --json` and return the output
     async fn run_nix_metadata(&self, path: &Path) -> AppResult<String> {
         let path_str = path.to_string_lossy();
@@ -107,16 +723,93 @@ impl NixService {
     }
 
     async fn run_nix_command(&self, args: &[&str]) -> AppResult<String> {
+        self.run_nix_command_in(None, args).await
+    }
+
+    async fn run_nix_args(&self, args: &[String]) -> AppResult<String> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.run_nix_command(&args).await
+    }
+
+    /// Run an input's update command with `sh -c` in `flake_dir`, logging
+    /// its output
+    async fn run_update_command(
+        &self,
+        name: &str,
+        command: &str,
+        flake_dir: &Path,
+    ) -> AppResult<()> {
+        debug!(input = %name, command = %command, "Running update command");
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command).current_dir(flake_dir);
+        let fail = |message: String| AppError::UpdateCommandFailed(name.to_string(), message);
+        let output = self.run(cmd).await.map_err(|e| match e {
+            AppError::NixCommandFailed(message) => fail(message),
+            e => e,
+        })?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            warn!(input = %name, command = %command, "Update command failed");
+            let message = if stderr.trim().is_empty() {
+                stdout.trim()
+            } else {
+                stderr.trim()
+            };
+            return Err(fail(message.to_string()));
+        }
+        for line in stdout.lines().chain(stderr.lines()) {
+            debug!(input = %name, "{}", line);
+        }
+        Ok(())
+    }
+
+    /// The nix executable with the options every command gets; the
+    /// subcommand and its arguments follow
+    fn nix_command(&self) -> Command {
+        let mut cmd = Command::new(&self.nix_binary);
+        cmd.arg("--option").arg("warn-dirty").arg("false");
+        if self.offline {
+            cmd.arg("--offline");
+        }
+        cmd.args(&self.nix_args);
+        cmd
+    }
+
+    /// The nix executable commands run
+    pub fn nix_binary(&self) -> &Path {
+        &self.nix_binary
+    }
+
+    async fn run_nix_command_in(&self, dir: Option<&Path>, args: &[&str]) -> AppResult<String> {
+        let mut cmd = self.nix_command();
+        cmd.args(args);
+        if let Some(dir) = dir {
+            cmd.current_dir(dir);
+        }
+
+        let output = self.run(cmd).await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            warn!(args = ?args, "Nix command failed");
+            return Err(AppError::NixCommandFailed(stderr.trim().to_string()));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Run `cmd` to completion under the nix command timeout, unless
+    /// cancelled first
+    async fn run(&self, mut cmd: Command) -> AppResult<std::process::Output> {
         if self.cancel_token.is_cancelled() {
             return Err(AppError::NixCommandFailed(
                 "Operation cancelled".to_string(),
             ));
         }
-
-        let mut cmd = Command::new("nix");
-        cmd.arg("--option").arg("warn-dirty").arg("false");
-        cmd.args(args).stdout(Stdio::piped()).stderr(Stdio::piped());
-
+        // Cancelling a task drops this future; take the command down with it.
+        cmd.stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
         let timeout = tokio::time::timeout(self.nix_command_timeout, cmd.output());
 
         let output = tokio::select! {
@@ -131,17 +824,58 @@ impl NixService {
                 return Err(AppError::NixCommandFailed("Operation cancelled".to_string()));
             }
         };
+        Ok(output)
+    }
+}
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            warn!(args = ?args, "Nix command failed");
-            return Err(AppError::NixCommandFailed(stderr.trim().to_string()));
-        }
-
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+/// Whether `err` means nix could not be run at all or took too long, rather
+/// than that it rejected the flake
+fn is_nix_unavailable(err: &AppError) -> bool {
+    match err {
+        AppError::Io(e) => e.kind() == std::io::ErrorKind::NotFound,
+        AppError::NixCommandFailed(message) => message == "Command timed out",
+        _ => false,
     }
 }
 
+/// Nix expression mapping each input of the flake in `flake_dir` to its
+/// version, or null
+fn versions_expr(flake_dir: &Path) -> String {
+    // A JSON string is a nix string once `${` can't start an interpolation.
+    let flake_ref = serde_json::Value::from(flake_dir.to_string_lossy()).to_string();
+    let flake_ref = flake_ref.replace("${", "\\${");
+    format!(
+        r#"let
+  flake = builtins.getFlake {flake_ref};
+  system = builtins.currentSystem;
+  version = input:
+    let
+      v = input.lib.version or input.packages.${{system}}.default.version or null;
+      result = builtins.tryEval (builtins.deepSeq v v);
+    in
+    if result.success && builtins.isString result.value then result.value else null;
+in
+builtins.mapAttrs (_: version) flake.inputs"#
+    )
+}
+
+/// Parse the output of [`versions_expr`], dropping inputs without a version
+fn parse_versions(json: &str) -> AppResult<HashMap<String, String>> {
+    let versions: HashMap<String, Option<String>> =
+        serde_json::from_str(json).map_err(|e| AppError::MetadataParseError(e.to_string()))?;
+    Ok(versions
+        .into_iter()
+        .filter_map(|(name, version)| Some((name, version?)))
+        .collect())
+}
+
+/// Host of the git remote `remote` of the repository holding `flake_dir`
+fn remote_host(flake_dir: &Path, remote: &str) -> Option<String> {
+    let repo = git2::Repository::discover(flake_dir).ok()?;
+    let remote = repo.find_remote(remote).ok()?;
+    url_host(remote.url()?).map(str::to_ascii_lowercase)
+}
+
 fn resolve_flake_path(path: &Path) -> AppResult<PathBuf> {
     let path = if path.to_string_lossy().is_empty() || path.to_string_lossy() == "." {
         std::env::current_dir()?
@@ -162,6 +896,32 @@ fn resolve_flake_path(path: &Path) -> AppResult<PathBuf> {
         .map_err(|_| AppError::FlakeNotFound(resolved))
 }
 
+// JSON structure for `nix flake show --json` on a template source
+
+#[derive(Debug, Deserialize)]
+struct NixFlakeShow {
+    #[serde(default)]
+    templates: std::collections::BTreeMap<String, NixTemplate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NixTemplate {
+    #[serde(default)]
+    description: Option<String>,
+}
+
+fn parse_templates(source: &str, output: &str) -> AppResult<Vec<FlakeTemplate>> {
+    let show: NixFlakeShow =
+        serde_json::from_str(output).map_err(|e| AppError::MetadataParseError(e.to_string()))?;
+    Ok(show
+        .templates
+        .into_iter()
+        .map(|(name, template)| {
+            FlakeTemplate::new(source, name, template.description.unwrap_or_default())
+        })
+        .collect())
+}
+
 // JSON structures for nix flake metadata
 // Using deny_unknown_fields = false (default) to handle different nix versions
 
@@ -179,9 +939,59 @@ struct NixLocks {
     nodes: std::collections::HashMap<String, NixNode>,
     #[serde(default)]
     root: String,
+    #[serde(default)]
+    version: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+/// `flake.lock` schema versions whose layout melt understands. Nix 2.4
+/// introduced version 5; 6 and 7 only changed how nodes are locked.
+const SUPPORTED_LOCK_VERSIONS: std::ops::RangeInclusive<u64> = 5..=7;
+
+impl NixLocks {
+    /// Reject schema versions that could be misread as known ones
+    fn check_version(&self) -> AppResult<()> {
+        match self.version {
+            Some(version) if !SUPPORTED_LOCK_VERSIONS.contains(&version) => {
+                Err(AppError::UnsupportedLockVersion(version))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn has_indirect(&self) -> bool {
+        self.nodes.values().any(|node| node.indirect_id().is_some())
+    }
+
+    /// Fill in what the registry says about `indirect` inputs: where an input
+    /// locked as `indirect` lives, and the branch an input declared without
+    /// one follows
+    fn resolve_indirect(&mut self, registry: &FlakeRegistry) {
+        for node in self.nodes.values_mut() {
+            let Some(target) = node.indirect_id().and_then(|id| registry.get(id)) else {
+                continue;
+            };
+            let Some(locked) = node.locked.as_mut() else {
+                continue;
+            };
+            if locked.type_.as_deref() == Some("indirect") {
+                locked.type_ = Some(target.type_.clone());
+                locked.owner = locked.owner.take().or_else(|| target.owner.clone());
+                locked.repo = locked.repo.take().or_else(|| target.repo.clone());
+                locked.host = locked.host.take().or_else(|| target.host.clone());
+                locked.url = locked.url.take().or_else(|| target.url.clone());
+            } else if !locked.is_source_of(target) {
+                // The lock predates a registry change; its branch is unknown.
+                continue;
+            }
+            let original = node.original.get_or_insert_with(NixOriginal::default);
+            if original.reference.is_none() {
+                original.reference = target.reference.clone();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
 struct NixNode {
     #[serde(default)]
     inputs: Option<std::collections::HashMap<String, serde_json::Value>>,
@@ -189,6 +999,30 @@ struct NixNode {
     locked: Option<NixLocked>,
     #[serde(default)]
     original: Option<NixOriginal>,
+    /// `false` for inputs declared with `flake = false`
+    #[serde(default = "default_flake")]
+    flake: bool,
+}
+
+impl NixNode {
+    /// Registry id of an input declared or locked as `indirect`
+    fn indirect_id(&self) -> Option<&str> {
+        let locked = self
+            .locked
+            .as_ref()
+            .filter(|l| l.type_.as_deref() == Some("indirect"))
+            .and_then(|l| l.id.as_deref());
+        let original = self
+            .original
+            .as_ref()
+            .filter(|o| o.type_.as_deref() == Some("indirect"))
+            .and_then(|o| o.id.as_deref());
+        locked.or(original)
+    }
+}
+
+fn default_flake() -> bool {
+    true
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -196,6 +1030,9 @@ struct NixNode {
 struct NixLocked {
     #[serde(rename = "type", default)]
     type_: Option<String>,
+    /// Registry id of an `indirect` input
+    #[serde(default)]
+    id: Option<String>,
     #[serde(default)]
     owner: Option<String>,
     #[serde(default)]
@@ -204,6 +1041,8 @@ struct NixLocked {
     rev: Option<String>,
     #[serde(rename = "lastModified", default)]
     last_modified: Option<i64>,
+    #[serde(rename = "narHash", default)]
+    nar_hash: Option<String>,
     #[serde(default)]
     url: Option<String>,
     #[serde(default)]
@@ -218,6 +1057,8 @@ struct NixOriginal {
     #[serde(rename = "type", default)]
     type_: Option<String>,
     #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
     owner: Option<String>,
     #[serde(default)]
     repo: Option<String>,
@@ -231,30 +1072,317 @@ struct NixOriginal {
     host: Option<String>,
 }
 
-fn parse_metadata(path: PathBuf, metadata: NixFlakeMetadata) -> FlakeData {
-    let root_node = metadata.locks.nodes.get(&metadata.locks.root);
-    let mut inputs: Vec<FlakeInput> = root_node
-        .and_then(|n| n.inputs.as_ref())
+impl NixLocked {
+    /// Whether this lock is of the repository `target` names
+    fn is_source_of(&self, target: &FlakeRef) -> bool {
+        let same = |a: &Option<String>, b: &Option<String>| match (a, b) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            _ => false,
+        };
+        self.type_.as_deref() == Some(target.type_.as_str())
+            && match target.type_.as_str() {
+                "github" | "gitlab" | "sourcehut" => {
+                    same(&self.owner, &target.owner) && same(&self.repo, &target.repo)
+                }
+                _ => same(&self.url, &target.url),
+            }
+    }
+}
+
+/// Parse the contents of a `flake.lock` file without invoking nix.
+///
+/// `nix flake metadata` embeds the lock file under `locks`, so both go
+/// through the same input parsing.
+pub fn parse_lock_file(path: PathBuf, contents: &str) -> AppResult<FlakeData> {
+    Ok(parse_locks(path, read_locks(contents)?))
+}
+
+fn read_locks(contents: &str) -> AppResult<NixLocks> {
+    let locks: NixLocks =
+        serde_json::from_str(contents).map_err(|e| AppError::MetadataParseError(e.to_string()))?;
+    locks.check_version()?;
+    Ok(locks)
+}
+
+fn parse_locks(path: PathBuf, locks: NixLocks) -> FlakeData {
+    parse_metadata(
+        path,
+        NixFlakeMetadata {
+            description: None,
+            locks,
+        },
+    )
+}
+
+/// Parse every node of a `flake.lock`, including transitive inputs.
+///
+/// The root node is omitted; nodes are sorted by key.
+pub fn parse_lock_nodes(contents: &str) -> AppResult<Vec<LockNode>> {
+    let locks: NixLocks =
+        serde_json::from_str(contents).map_err(|e| AppError::MetadataParseError(e.to_string()))?;
+    locks.check_version()?;
+
+    let direct: HashSet<String> = locks
+        .nodes
+        .get(&locks.root)
+        .and_then(|root| root.inputs.as_ref())
         .map(|inputs| {
             inputs
-                .iter()
-                .filter_map(|(name, value)| {
-                    let node_name = match value {
-                        serde_json::Value::String(s) => s.clone(),
-                        serde_json::Value::Array(arr) => arr.first()?.as_str()?.to_string(),
-                        _ => return None,
-                    };
-
-                    let node = metadata.locks.nodes.get(&node_name)?;
-                    parse_input(name, node)
-                })
+                .values()
+                .filter_map(|value| resolve_input_node(&locks, value, 0))
                 .collect()
         })
         .unwrap_or_default();
 
+    let mut nodes: Vec<LockNode> = locks
+        .nodes
+        .iter()
+        .filter(|(id, _)| **id != locks.root)
+        .map(|(id, node)| {
+            let locked = node.locked.as_ref();
+            // A non-flake source has no inputs of its own, whatever the
+            // lock file claims.
+            let mut depends_on: Vec<String> = node
+                .inputs
+                .iter()
+                .flatten()
+                .filter(|_| node.flake)
+                .filter_map(|(_, value)| resolve_input_node(&locks, value, 0))
+                // Drop self-references and dangling keys of corrupted locks
+                .filter(|dep| dep != id && locks.nodes.contains_key(dep))
+                .collect();
+            depends_on.sort();
+            depends_on.dedup();
+
+            LockNode {
+                id: id.clone(),
+                name: locked
+                    .and_then(|l| l.repo.clone())
+                    .unwrap_or_else(|| id.clone()),
+                source_type: locked
+                    .and_then(|l| l.type_.clone())
+                    .unwrap_or_else(|| "unknown".to_string()),
+                source_url: locked.and_then(source_location),
+                rev: locked.and_then(|l| l.rev.clone()),
+                nar_hash: locked.and_then(|l| l.nar_hash.clone()),
+                last_modified: locked.and_then(|l| l.last_modified),
+                direct: direct.contains(id),
+                depends_on,
+            }
+        })
+        .collect();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(nodes)
+}
+
+/// The flake's inputs with the inputs each of them declares, down to the
+/// leaves of the lock graph
+pub fn parse_input_tree(contents: &str) -> AppResult<Vec<InputTree>> {
+    let locks: NixLocks =
+        serde_json::from_str(contents).map_err(|e| AppError::MetadataParseError(e.to_string()))?;
+    locks.check_version()?;
+
+    let Some(root) = locks.nodes.get(&locks.root) else {
+        return Ok(Vec::new());
+    };
+    let mut path = vec![locks.root.clone()];
+    Ok(input_trees(&locks, root, &mut path))
+}
+
+/// Trees of the inputs `node` declares. `path` holds the nodes above, so
+/// cycles in malformed lock files end the walk.
+fn input_trees(locks: &NixLocks, node: &NixNode, path: &mut Vec<String>) -> Vec<InputTree> {
+    let mut trees: Vec<InputTree> = node
+        .inputs
+        .iter()
+        .flatten()
+        .map(|(name, value)| {
+            let resolved = resolve_input_node(locks, value, 0);
+            let target = resolved.as_ref().and_then(|id| locks.nodes.get(id));
+            let locked = target.and_then(|target| target.locked.as_ref());
+            let follows = value.as_array().map(|segments| {
+                segments
+                    .iter()
+                    .filter_map(|segment| segment.as_str().map(str::to_string))
+                    .collect()
+            });
+            let inputs = match (&follows, &resolved, target) {
+                (None, Some(id), Some(target)) if target.flake && !path.contains(id) => {
+                    path.push(id.clone());
+                    let inputs = input_trees(locks, target, path);
+                    path.pop();
+                    inputs
+                }
+                _ => Vec::new(),
+            };
+            InputTree {
+                name: name.clone(),
+                node: resolved,
+                follows,
+                source: locked.and_then(source_location),
+                rev: locked.and_then(|l| l.rev.clone()),
+                last_modified: locked.and_then(|l| l.last_modified),
+                inputs,
+            }
+        })
+        .collect();
+    trees.sort_by(|a, b| a.name.cmp(&b.name));
+    trees
+}
+
+/// Resolve an input reference to a node key.
+///
+/// A string names a node directly; an array is a `follows` path walked from
+/// the root node.
+fn resolve_input_node(locks: &NixLocks, value: &serde_json::Value, depth: usize) -> Option<String> {
+    // Guard against follows cycles in malformed lock files.
+    if depth > locks.nodes.len() {
+        return None;
+    }
+    match value {
+        serde_json::Value::String(id) => Some(id.clone()),
+        serde_json::Value::Array(path) => {
+            let mut current = locks.root.clone();
+            for segment in path {
+                // `follows` can't reach through a `flake = false` source
+                let node = locks.nodes.get(&current).filter(|node| node.flake)?;
+                let next = node.inputs.as_ref()?.get(segment.as_str()?)?;
+                current = resolve_input_node(locks, next, depth + 1)?;
+            }
+            Some(current)
+        }
+        _ => None,
+    }
+}
+
+/// Download location of a locked source
+fn source_location(locked: &NixLocked) -> Option<String> {
+    let owner = locked.owner.as_deref();
+    let repo = locked.repo.as_deref();
+    match locked.type_.as_deref()? {
+        "github" => Some(format!("https://github.com/{}/{}", owner?, repo?)),
+        "gitlab" => Some(format!(
+            "https://{}/{}/{}",
+            locked.host.as_deref().unwrap_or("gitlab.com"),
+            owner?,
+            repo?
+        )),
+        "sourcehut" => Some(format!(
+            "https://{}/~{}/{}",
+            locked.host.as_deref().unwrap_or("git.sr.ht"),
+            owner?.trim_start_matches('~'),
+            repo?
+        )),
+        "path" => locked.path.clone(),
+        _ => locked.url.clone(),
+    }
+}
+
+fn parse_metadata(path: PathBuf, metadata: NixFlakeMetadata) -> FlakeData {
+    let locks = &metadata.locks;
+    let mut warnings = Vec::new();
+    let root_inputs: Vec<(&String, &serde_json::Value)> = match locks.nodes.get(&locks.root) {
+        Some(root) => root.inputs.iter().flatten().collect(),
+        None => {
+            if !locks.nodes.is_empty() {
+                warnings.push(LockWarning {
+                    input: locks.root.clone(),
+                    message: "root node is missing".to_string(),
+                });
+            }
+            Vec::new()
+        }
+    };
+    let mut inputs: Vec<FlakeInput> = root_inputs
+        .into_iter()
+        .filter_map(|(name, value)| parse_root_input(locks, name, value, &mut warnings))
+        .collect();
+
     inputs.sort_by_key(|a| a.name().to_lowercase());
+    warnings.sort_by(|a, b| a.input.cmp(&b.input));
 
-    FlakeData { path, inputs }
+    let input_trees = match locks.nodes.get(&locks.root) {
+        Some(root) => input_trees(locks, root, &mut vec![locks.root.clone()]),
+        None => Vec::new(),
+    };
+
+    FlakeData {
+        path,
+        inputs,
+        warnings,
+        input_trees,
+    }
+}
+
+/// Parse one input of the root node.
+///
+/// An input whose node is missing or unlocked stays listed as unsupported,
+/// and a `follows` that leads nowhere is kept as is; both add a warning
+/// rather than dropping the input.
+fn parse_root_input(
+    locks: &NixLocks,
+    name: &str,
+    value: &serde_json::Value,
+    warnings: &mut Vec<LockWarning>,
+) -> Option<FlakeInput> {
+    if name.is_empty() {
+        return None;
+    }
+    let mut warn = |message: String| {
+        warnings.push(LockWarning {
+            input: name.to_string(),
+            message,
+        })
+    };
+    let unresolved = || {
+        FlakeInput::Other(OtherInput {
+            name: name.to_string(),
+            rev: None,
+            last_modified: 0,
+            flake: true,
+        })
+    };
+
+    match value {
+        serde_json::Value::String(node_name) => {
+            let Some(node) = locks.nodes.get(node_name) else {
+                warn(format!("locked node `{}` is missing", node_name));
+                return Some(unresolved());
+            };
+            if node.locked.is_none() {
+                warn(format!("node `{}` has no locked source", node_name));
+                return Some(unresolved());
+            }
+            parse_input(name, node)
+        }
+        // A path means the input follows another input rather than having
+        // a node of its own.
+        serde_json::Value::Array(path) => {
+            let follows: Option<Vec<String>> = path
+                .iter()
+                .map(|segment| segment.as_str().map(str::to_string))
+                .collect();
+            let Some(follows) = follows.filter(|follows| !follows.is_empty()) else {
+                warn("malformed follows path".to_string());
+                return Some(unresolved());
+            };
+            match resolve_input_node(locks, value, 0) {
+                Some(target) if target != locks.root => {}
+                _ => warn(format!(
+                    "follows `{}`, which does not lead to a locked node",
+                    follows.join("/")
+                )),
+            }
+            Some(FlakeInput::Follows(FollowsInput {
+                name: name.to_string(),
+                follows,
+            }))
+        }
+        _ => {
+            warn("input is neither a node nor a follows path".to_string());
+            Some(unresolved())
+        }
+    }
 }
 
 /// Parse owner and repo from a git URL
@@ -372,6 +1500,7 @@ fn parse_raw_input(name: &str, node: &NixNode) -> RawInputParseResult {
                     name: name.to_string(),
                     rev: locked.rev.clone().filter(|rev| !rev.trim().is_empty()),
                     last_modified: locked.last_modified.unwrap_or(0),
+                    flake: true,
                 }));
             };
             let host = locked
@@ -384,6 +1513,7 @@ fn parse_raw_input(name: &str, node: &NixNode) -> RawInputParseResult {
                     name: name.to_string(),
                     rev: None,
                     last_modified: locked.last_modified.unwrap_or(0),
+                    flake: true,
                 }));
             };
             let url = build_url(type_, &owner, &repo, host.as_deref(), locked, original);
@@ -396,6 +1526,7 @@ fn parse_raw_input(name: &str, node: &NixNode) -> RawInputParseResult {
                     name: name.to_string(),
                     rev: Some(rev),
                     last_modified: locked.last_modified.unwrap_or(0),
+                    flake: true,
                 }));
             };
             let Some(git_repo) = build_git_repo(forge_type, owner, repo, host, locked, original)
@@ -404,6 +1535,7 @@ fn parse_raw_input(name: &str, node: &NixNode) -> RawInputParseResult {
                     name: name.to_string(),
                     rev: Some(git_rev.as_str().to_string()),
                     last_modified: locked.last_modified.unwrap_or(0),
+                    flake: true,
                 }));
             };
             let reference = reference.and_then(|reference| GitRef::new(reference).ok());
@@ -419,18 +1551,89 @@ fn parse_raw_input(name: &str, node: &NixNode) -> RawInputParseResult {
         }
         "path" => RawInputParseResult::DisplayOnly(FlakeInput::Path(PathInput {
             name: name.to_string(),
+            flake: true,
         })),
+        "tarball" => match github_tarball_input(name, locked, original) {
+            Some(input) => RawInputParseResult::ActionableGit(input),
+            None => RawInputParseResult::DisplayOnly(FlakeInput::Other(OtherInput {
+                name: name.to_string(),
+                rev: locked.rev.clone().filter(|rev| !rev.trim().is_empty()),
+                last_modified: locked.last_modified.unwrap_or(0),
+                flake: true,
+            })),
+        },
         _ => RawInputParseResult::DisplayOnly(FlakeInput::Other(OtherInput {
             name: name.to_string(),
             rev: locked.rev.clone().filter(|rev| !rev.trim().is_empty()),
             last_modified: locked.last_modified.unwrap_or(0),
+            flake: true,
         })),
     }
 }
 
+/// A `tarball` input fetched from a GitHub archive URL, as a GitHub input
+/// tracking the branch or tag of its original URL
+fn github_tarball_input(
+    name: &str,
+    locked: &NixLocked,
+    original: Option<&NixOriginal>,
+) -> Option<GitInput> {
+    let locked_url = locked.url.as_deref()?;
+    let (owner, repo, archived) = parse_github_archive_url(locked_url)?;
+    let rev = locked
+        .rev
+        .clone()
+        .filter(|rev| is_commit_sha(rev))
+        .or_else(|| is_commit_sha(&archived).then_some(archived))?;
+    let original_url = original.and_then(|o| o.url.as_deref());
+    let reference = original_url
+        .and_then(parse_github_archive_url)
+        .map(|(_, _, reference)| reference)
+        .filter(|reference| !is_commit_sha(reference))
+        .and_then(|reference| GitRef::new(reference).ok());
+
+    Some(GitInput::new(
+        InputName::new(name).ok()?,
+        GitRepo::github(Owner::new(owner).ok()?, RepoName::new(repo).ok()?),
+        reference,
+        GitRev::new(rev).ok()?,
+        locked.last_modified.unwrap_or(0),
+        original_url.unwrap_or(locked_url).to_string(),
+    ))
+}
+
+/// Owner, repo, and archived ref or commit of a GitHub archive URL such as
+/// `https://github.com/NixOS/nixpkgs/archive/nixos-24.05.tar.gz`
+fn parse_github_archive_url(url: &str) -> Option<(String, String, String)> {
+    let url = url.split(['?', '#']).next()?;
+    let path = url
+        .strip_prefix("https://github.com/")
+        .or_else(|| url.strip_prefix("http://github.com/"))?;
+    let (owner, rest) = path.split_once('/')?;
+    let (repo, archive) = rest.split_once("/archive/")?;
+    let archived = archive
+        .strip_suffix(".tar.gz")
+        .or_else(|| archive.strip_suffix(".zip"))?;
+    let archived = archived
+        .strip_prefix("refs/heads/")
+        .or_else(|| archived.strip_prefix("refs/tags/"))
+        .unwrap_or(archived);
+    if owner.is_empty() || repo.is_empty() || repo.contains('/') || archived.is_empty() {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string(), archived.to_string()))
+}
+
+/// Whether `rev` is a full commit hash rather than a branch or tag
+fn is_commit_sha(rev: &str) -> bool {
+    rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
 /// Parse a single input node
 fn parse_input(name: &str, node: &NixNode) -> Option<FlakeInput> {
-    parse_raw_input(name, node).into_flake_input()
+    parse_raw_input(name, node)
+        .into_flake_input()
+        .map(|input| input.with_flake(node.flake))
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -456,7 +1659,13 @@ fn build_git_repo(
     let host = host.and_then(|host| GitHost::new(host).ok());
 
     match forge_type {
-        RawForgeType::GitHub => Some(GitRepo::github(owner, repo)),
+        // `github:` inputs with a `host` live on GitHub Enterprise Server
+        RawForgeType::GitHub => Some(match host {
+            Some(host) if !host.as_str().eq_ignore_ascii_case("github.com") => {
+                GitRepo::github_enterprise(host, owner, repo)
+            }
+            _ => GitRepo::github(owner, repo),
+        }),
         RawForgeType::GitLab => GitRepo::gitlab(host, owner, repo).ok(),
         RawForgeType::SourceHut => GitRepo::sourcehut(host, owner, repo).ok(),
         RawForgeType::Codeberg => Some(GitRepo::codeberg(owner, repo)),
@@ -473,6 +1682,44 @@ fn build_git_repo(
     }
 }
 
+/// Treat a generic git input on a host listed in `forge_hosts` as a
+/// repository of that forge
+fn apply_forge_hosts(input: FlakeInput, forge_hosts: &ForgeHosts) -> FlakeInput {
+    let FlakeInput::Git(git) = input else {
+        return input;
+    };
+    let GitRepo::Generic { clone_url } = git.repo() else {
+        return FlakeInput::Git(git);
+    };
+    let url = clone_url.as_str();
+    let repo = url_host(url)
+        .and_then(|host| Some((host, forge_hosts.get(host)?)))
+        .zip(parse_owner_repo_from_url(url))
+        .and_then(|((host, kind), (owner, repo))| {
+            let host = GitHost::new(host).ok()?;
+            let owner = Owner::new(owner).ok()?;
+            let repo = RepoName::new(repo).ok()?;
+            match kind {
+                ForgeKind::GitHub => Some(GitRepo::github_enterprise(host, owner, repo)),
+                ForgeKind::GitLab => GitRepo::gitlab(Some(host), owner, repo).ok(),
+                ForgeKind::Gitea => Some(GitRepo::gitea(host, owner, repo)),
+                ForgeKind::SourceHut => GitRepo::sourcehut(Some(host), owner, repo).ok(),
+            }
+        });
+    match repo {
+        Some(repo) => FlakeInput::Git(git.with_repo(repo)),
+        None => FlakeInput::Git(git),
+    }
+}
+
+/// Host of a clone URL such as `https://host/o/r` or `git@host:o/r`
+fn url_host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', ':']).next()?;
+    let host = authority.rsplit('@').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
 /// Detect the forge type from the input type and metadata
 fn detect_forge_type(
     type_: &str,
@@ -519,7 +1766,10 @@ fn build_url(
     original: Option<&NixOriginal>,
 ) -> String {
     match type_ {
-        "github" => format!("github:{}/{}", owner, repo),
+        "github" => match host {
+            Some(h) if h != "github.com" => format!("github:{}/{} ({})", owner, repo, h),
+            _ => format!("github:{}/{}", owner, repo),
+        },
         "gitlab" => {
             if let Some(h) = host {
                 if h != "gitlab.com" {
@@ -555,14 +1805,455 @@ mod tests {
         let _ = resolve_flake_path(Path::new("."));
     }
 
+    #[test]
+    fn test_nix_command_uses_configured_binary_and_args() {
+        let config = ServiceConfig {
+            nix_binary: PathBuf::from("/opt/nix/bin/nix"),
+            nix_args: vec!["--accept-flake-config".to_string()],
+            offline: true,
+            ..ServiceConfig::default()
+        };
+        let nix = NixService::new_with_config(CancellationToken::new(), config);
+        let cmd = nix.nix_command();
+        let cmd = cmd.as_std();
+        assert_eq!(cmd.get_program(), "/opt/nix/bin/nix");
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "--option",
+                "warn-dirty",
+                "false",
+                "--offline",
+                "--accept-flake-config"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_lock_file() {
+        let contents = std::fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/minimal/flake.lock"),
+        )
+        .unwrap();
+
+        let flake = parse_lock_file(PathBuf::from("/tmp/minimal"), &contents).unwrap();
+
+        assert!(!flake.inputs.is_empty());
+        assert!(parse_lock_file(PathBuf::from("/tmp"), "not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_lock_file_skips_nix() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("flake.nix"), "{ }").unwrap();
+        let config = ServiceConfig {
+            read_lock_file: true,
+            ..Default::default()
+        };
+        let nix = NixService::new_with_config(CancellationToken::new(), config);
+
+        let err = nix.load_metadata(tmp.path()).await.unwrap_err();
+        assert!(matches!(err, AppError::MetadataParseError(_)));
+
+        std::fs::copy(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/minimal/flake.lock"),
+            tmp.path().join("flake.lock"),
+        )
+        .unwrap();
+        let flake = nix.load_metadata(tmp.path()).await.unwrap();
+        assert!(!flake.inputs.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_read_lock_file_leaves_registry_inputs_as_locked() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("flake.nix"), "{ }").unwrap();
+        std::fs::write(
+            tmp.path().join("flake.lock"),
+            r#"{
+                "root": "root",
+                "version": 7,
+                "nodes": {
+                    "root": { "inputs": { "nixpkgs": "nixpkgs" } },
+                    "nixpkgs": {
+                        "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "abc1234" },
+                        "original": { "type": "indirect", "id": "nixpkgs" }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let ran = tmp.path().join("ran");
+        let fake_nix = tmp.path().join("nix");
+        std::fs::write(&fake_nix, format!("#!/bin/sh\ntouch {}\n", ran.display())).unwrap();
+        std::fs::set_permissions(&fake_nix, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let config = ServiceConfig {
+            nix_binary: fake_nix,
+            read_lock_file: true,
+            ..Default::default()
+        };
+        let nix = NixService::new_with_config(CancellationToken::new(), config);
+
+        let flake = nix.load_metadata(tmp.path()).await.unwrap();
+        assert_eq!(flake.inputs.len(), 1);
+        assert!(!ran.exists(), "nix registry list ran");
+    }
+
+    #[test]
+    fn test_is_nix_unavailable() {
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "nix");
+        assert!(is_nix_unavailable(&AppError::Io(missing)));
+        assert!(is_nix_unavailable(&AppError::NixCommandFailed(
+            "Command timed out".to_string()
+        )));
+        assert!(!is_nix_unavailable(&AppError::NixCommandFailed(
+            "error: flake.nix has a syntax error".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_parse_lock_nodes_resolves_follows() {
+        let contents = r#"{
+            "root": "root",
+            "version": 7,
+            "nodes": {
+                "root": { "inputs": { "nixpkgs": "nixpkgs", "hm": "home-manager" } },
+                "home-manager": {
+                    "inputs": { "nixpkgs": ["nixpkgs"] },
+                    "locked": {
+                        "type": "github", "owner": "nix-community", "repo": "home-manager",
+                        "rev": "abc1234", "narHash": "sha256-AAAA", "lastModified": 1700000000
+                    }
+                },
+                "nixpkgs": {
+                    "locked": { "type": "tarball", "url": "https://example.com/nixpkgs.tar.gz" }
+                }
+            }
+        }"#;
+
+        let nodes = parse_lock_nodes(contents).unwrap();
+
+        assert_eq!(nodes.len(), 2);
+        let hm = &nodes[0];
+        assert_eq!(hm.id, "home-manager");
+        assert!(hm.direct);
+        assert_eq!(hm.depends_on, vec!["nixpkgs".to_string()]);
+        assert_eq!(
+            hm.source_url.as_deref(),
+            Some("https://github.com/nix-community/home-manager")
+        );
+        assert_eq!(hm.nar_hash.as_deref(), Some("sha256-AAAA"));
+        assert_eq!(
+            nodes[1].source_url.as_deref(),
+            Some("https://example.com/nixpkgs.tar.gz")
+        );
+    }
+
+    #[test]
+    fn test_parse_input_tree_shows_what_follows_what() {
+        let contents = r#"{
+            "root": "root",
+            "version": 7,
+            "nodes": {
+                "root": { "inputs": { "nixpkgs": "nixpkgs", "home-manager": "home-manager", "hyprland": "hyprland" } },
+                "nixpkgs": { "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "aaa" } },
+                "home-manager": {
+                    "inputs": { "nixpkgs": ["nixpkgs"] },
+                    "locked": { "type": "github", "owner": "nix-community", "repo": "home-manager", "rev": "bbb" }
+                },
+                "hyprland": {
+                    "inputs": { "nixpkgs": "nixpkgs_2", "systems": ["hyprland", "nixpkgs"] },
+                    "locked": { "type": "github", "owner": "hyprwm", "repo": "Hyprland", "rev": "ccc" }
+                },
+                "nixpkgs_2": { "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "ddd" } }
+            }
+        }"#;
+
+        let trees = parse_input_tree(contents).unwrap();
+        let lines: Vec<String> = InputTree::lines(&trees)
+            .iter()
+            .map(|line| {
+                let target = match &line.tree.follows {
+                    Some(follows) => format!("-> {}", follows.join("/")),
+                    None => line.tree.rev.clone().unwrap_or_default(),
+                };
+                format!("{}{} {}", line.prefix, line.tree.name, target)
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "home-manager bbb",
+                "└─ nixpkgs -> nixpkgs",
+                "hyprland ccc",
+                "├─ nixpkgs ddd",
+                "└─ systems -> hyprland/nixpkgs",
+                "nixpkgs aaa",
+            ]
+        );
+        assert_eq!(trees[1].transitive().filter(|t| t.is_follows()).count(), 1);
+        assert_eq!(trees[1].inputs[1].rev.as_deref(), Some("ddd"));
+    }
+
+    #[test]
+    fn test_duplicates_suggest_follows_lines() {
+        let contents = r#"{
+            "root": "root",
+            "version": 7,
+            "nodes": {
+                "root": { "inputs": { "nixpkgs": "nixpkgs", "unstable": "nixpkgs_3", "hyprland": "hyprland" } },
+                "nixpkgs": { "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "aaa" } },
+                "nixpkgs_3": { "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "eee" } },
+                "hyprland": {
+                    "inputs": { "nixpkgs": "nixpkgs_2", "aquamarine": "aquamarine" },
+                    "locked": { "type": "github", "owner": "hyprwm", "repo": "Hyprland", "rev": "ccc" }
+                },
+                "aquamarine": {
+                    "inputs": { "nixpkgs": ["hyprland", "nixpkgs"], "hyprutils": "hyprutils" },
+                    "locked": { "type": "github", "owner": "hyprwm", "repo": "aquamarine", "rev": "fff" }
+                },
+                "hyprutils": {
+                    "inputs": { "nixpkgs": "nixpkgs_4" },
+                    "locked": { "type": "github", "owner": "hyprwm", "repo": "hyprutils", "rev": "111" }
+                },
+                "nixpkgs_2": { "locked": { "type": "github", "owner": "nixos", "repo": "nixpkgs", "rev": "ddd" } },
+                "nixpkgs_4": { "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "ddd" } }
+            }
+        }"#;
+
+        let trees = parse_input_tree(contents).unwrap();
+        let duplicates = InputTree::duplicates(&trees);
+        assert_eq!(duplicates.len(), 1);
+        let nixpkgs = &duplicates[0];
+        assert_eq!(nixpkgs.source, "https://github.com/NixOS/nixpkgs");
+        assert_eq!(nixpkgs.copies, 4);
+        assert_eq!(nixpkgs.target, "nixpkgs");
+        // `unstable` is a root input of its own and is left alone.
+        assert_eq!(
+            nixpkgs.suggestions,
+            [
+                r#"inputs.hyprland.inputs.nixpkgs.follows = "nixpkgs";"#,
+                r#"inputs.hyprland.inputs.aquamarine.inputs.hyprutils.inputs.nixpkgs.follows = "nixpkgs";"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_lock_file_keeps_root_follows_aliases() {
+        let contents = r#"{
+            "root": "root",
+            "version": 7,
+            "nodes": {
+                "root": { "inputs": { "nixpkgs": "nixpkgs", "pkgs": ["nixpkgs"] } },
+                "nixpkgs": {
+                    "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "abc1234" }
+                }
+            }
+        }"#;
+
+        let flake = parse_lock_file(PathBuf::from("/tmp/follows"), contents).unwrap();
+
+        let alias = flake
+            .inputs
+            .iter()
+            .find_map(|input| match input {
+                FlakeInput::Follows(follows) => Some(follows),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(alias.name, "pkgs");
+        assert_eq!(alias.root_target(), Some("nixpkgs"));
+        assert_eq!(flake.inputs.len(), 2);
+    }
+
+    #[test]
+    fn test_indirect_inputs_resolve_through_the_registry() {
+        let contents = r#"{
+            "root": "root",
+            "version": 7,
+            "nodes": {
+                "root": { "inputs": { "nixpkgs": "nixpkgs", "utils": "utils", "hydra": "hydra" } },
+                "nixpkgs": {
+                    "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "abc1234" },
+                    "original": { "type": "indirect", "id": "nixpkgs" }
+                },
+                "utils": {
+                    "locked": { "type": "indirect", "id": "flake-utils", "rev": "def5678" },
+                    "original": { "type": "indirect", "id": "flake-utils" }
+                },
+                "hydra": {
+                    "locked": { "type": "github", "owner": "someone", "repo": "hydra", "rev": "0123abc" },
+                    "original": { "type": "indirect", "id": "hydra" }
+                }
+            }
+        }"#;
+        let registry = FlakeRegistry::parse(
+            "global flake:nixpkgs github:NixOS/nixpkgs/nixpkgs-unstable\n\
+             global flake:flake-utils github:numtide/flake-utils\n\
+             global flake:hydra github:NixOS/hydra/master\n",
+        );
+        let mut locks = read_locks(contents).unwrap();
+        assert!(locks.has_indirect());
+        locks.resolve_indirect(&registry);
+        let flake = parse_locks(PathBuf::from("/tmp/indirect"), locks);
+
+        let git = |name: &str| match flake.inputs.iter().find(|i| i.name() == name) {
+            Some(FlakeInput::Git(git)) => git.clone(),
+            other => panic!("{} is not a git input: {:?}", name, other),
+        };
+        assert_eq!(git("nixpkgs").reference(), Some("nixpkgs-unstable"));
+        let utils = git("utils");
+        assert_eq!(utils.url(), "github:numtide/flake-utils");
+        assert_eq!(utils.rev(), "def5678");
+        // Locked from a repository the registry no longer points at
+        assert_eq!(git("hydra").reference(), None);
+    }
+
+    #[test]
+    fn test_parse_lock_rejects_unknown_versions() {
+        let lock = |version: u64| {
+            format!(
+                r#"{{"root": "root", "version": {}, "nodes": {{"root": {{}}}}}}"#,
+                version
+            )
+        };
+        for version in [5, 6, 7] {
+            assert!(parse_lock_file(PathBuf::from("/tmp"), &lock(version)).is_ok());
+        }
+        for version in [4, 8] {
+            let err = parse_lock_file(PathBuf::from("/tmp"), &lock(version)).unwrap_err();
+            assert!(matches!(err, AppError::UnsupportedLockVersion(v) if v == version));
+            assert!(parse_lock_nodes(&lock(version)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_lock_marks_non_flake_sources() {
+        let contents = r#"{
+            "root": "root",
+            "version": 7,
+            "nodes": {
+                "root": { "inputs": { "src": "src", "pkgs": ["src", "nixpkgs"] } },
+                "src": {
+                    "flake": false,
+                    "inputs": { "nixpkgs": "nixpkgs" },
+                    "locked": { "type": "github", "owner": "o", "repo": "src", "rev": "abc1234" }
+                },
+                "nixpkgs": {
+                    "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "def5678" }
+                }
+            }
+        }"#;
+
+        let flake = parse_lock_file(PathBuf::from("/tmp/src"), contents).unwrap();
+        let src = flake.inputs.iter().find(|i| i.name() == "src").unwrap();
+        assert!(matches!(src, FlakeInput::Git(_)));
+        assert!(!src.is_flake());
+        assert_eq!(src.type_display(), "git-src");
+
+        let nodes = parse_lock_nodes(contents).unwrap();
+        let src = nodes.iter().find(|node| node.id == "src").unwrap();
+        assert!(src.depends_on.is_empty());
+        let direct = nodes.iter().filter(|node| node.direct).count();
+        assert_eq!(direct, 1, "`follows` through a source resolves to nothing");
+    }
+
+    #[test]
+    fn test_parse_pathological_lock_degrades_per_input() {
+        let count = 600;
+        let contents = crate::testing::pathological_lock_json(count);
+
+        let flake = parse_lock_file(PathBuf::from("/tmp/pathological"), &contents).unwrap();
+
+        // Every input stays listed; the missing, self-following, and
+        // unlocked ones each carry a warning.
+        assert_eq!(flake.inputs.len(), count);
+        assert_eq!(flake.warnings.len(), count / 2);
+        let git = flake
+            .inputs
+            .iter()
+            .filter(|input| matches!(input, FlakeInput::Git(_)))
+            .count();
+        assert_eq!(git, count / 2);
+        let warning = &flake.warnings[0];
+        assert_eq!(warning.input, "input-0001");
+        assert_eq!(
+            warning.message,
+            "locked node `missing-input-0001` is missing"
+        );
+
+        let nodes = parse_lock_nodes(&contents).unwrap();
+        let ids: HashSet<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+        assert!(nodes.iter().all(|node| node
+            .depends_on
+            .iter()
+            .all(|dep| *dep != node.id && ids.contains(dep.as_str()))));
+    }
+
+    #[test]
+    fn test_parse_lock_without_root_node_warns() {
+        let contents = r#"{
+            "root": "root",
+            "version": 7,
+            "nodes": { "nixpkgs": { "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "abc1234" } } }
+        }"#;
+
+        let flake = parse_lock_file(PathBuf::from("/tmp/rootless"), contents).unwrap();
+
+        assert!(flake.inputs.is_empty());
+        assert_eq!(flake.warnings[0].to_string(), "root: root node is missing");
+    }
+
+    #[test]
+    fn test_parse_synthetic_large_lock() {
+        let json = crate::testing::synthetic_metadata_json(5000);
+        let metadata: NixFlakeMetadata = serde_json::from_str(&json).unwrap();
+
+        let flake = parse_metadata(PathBuf::from("/tmp/synthetic"), metadata);
+
+        assert_eq!(flake.inputs.len(), 5000);
+        assert!(flake
+            .inputs
+            .iter()
+            .all(|input| matches!(input, FlakeInput::Git(_))));
+    }
+
+    #[test]
+    fn test_parse_templates() {
+        let output = r#"{
+            "defaultTemplate": {"description": "default", "type": "template"},
+            "templates": {
+                "rust": {"description": "Rust template", "type": "template"},
+                "bash-hello": {"type": "template"}
+            }
+        }"#;
+
+        let templates = parse_templates("templates", output).unwrap();
+
+        assert_eq!(
+            templates,
+            vec![
+                FlakeTemplate::new("templates", "bash-hello", ""),
+                FlakeTemplate::new("templates", "rust", "Rust template"),
+            ]
+        );
+        assert!(parse_templates("templates", "not json").is_err());
+    }
+
     #[test]
     fn test_detect_forge_type() {
         let locked = NixLocked {
             type_: Some("github".to_string()),
+            id: None,
             owner: None,
             repo: None,
             rev: None,
             last_modified: None,
+            nar_hash: None,
             url: None,
             path: None,
             host: None,
@@ -654,15 +2345,18 @@ mod tests {
             inputs: None,
             locked: Some(NixLocked {
                 type_: Some(type_.to_string()),
+                id: None,
                 owner: owner.map(ToOwned::to_owned),
                 repo: repo.map(ToOwned::to_owned),
                 rev: rev.map(ToOwned::to_owned),
                 last_modified: Some(0),
+                nar_hash: None,
                 url: url.map(ToOwned::to_owned),
                 path: None,
                 host: host.map(ToOwned::to_owned),
             }),
             original: None,
+            flake: true,
         }
     }
 
@@ -696,6 +2390,77 @@ mod tests {
         assert!(matches!(input, FlakeInput::Other(_)));
     }
 
+    #[test]
+    fn test_forge_hosts_turn_generic_inputs_into_forge_repos() {
+        let node = git_node(
+            "git",
+            None,
+            None,
+            Some("abc1234"),
+            Some("git+https://git.corp.example/platform/tools.git"),
+            None,
+        );
+        let input = parse_input("tools", &node).unwrap();
+        assert!(matches!(
+            &input,
+            FlakeInput::Git(git) if matches!(git.repo(), GitRepo::Generic { .. })
+        ));
+
+        let mut hosts = ForgeHosts::default();
+        hosts.insert("git.corp.example", ForgeKind::GitLab);
+        let FlakeInput::Git(git) = apply_forge_hosts(input, &hosts) else {
+            panic!("expected a git input");
+        };
+        assert_eq!(
+            git.repo(),
+            &GitRepo::gitlab(
+                Some(GitHost::new("git.corp.example").unwrap()),
+                Owner::new("platform").unwrap(),
+                RepoName::new("tools").unwrap(),
+            )
+            .unwrap()
+        );
+        assert_eq!(git.rev(), "abc1234");
+    }
+
+    #[test]
+    fn test_github_input_with_host_is_enterprise() {
+        let node = git_node(
+            "github",
+            Some("ops"),
+            Some("tools"),
+            Some("abc1234"),
+            None,
+            Some("ghe.example.com"),
+        );
+        let Some(FlakeInput::Git(git)) = parse_input("tools", &node) else {
+            panic!("expected a git input");
+        };
+        assert_eq!(
+            git.repo(),
+            &GitRepo::github_enterprise(
+                GitHost::new("ghe.example.com").unwrap(),
+                Owner::new("ops").unwrap(),
+                RepoName::new("tools").unwrap(),
+            )
+        );
+        assert_eq!(git.url(), "github:ops/tools (ghe.example.com)");
+    }
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(
+            url_host("https://git.corp.example/o/r"),
+            Some("git.corp.example")
+        );
+        assert_eq!(
+            url_host("ssh://git@host.example:2222/o/r"),
+            Some("host.example")
+        );
+        assert_eq!(url_host("git@host.example:o/r.git"), Some("host.example"));
+        assert_eq!(url_host(""), None);
+    }
+
     #[test]
     fn test_parse_input_empty_input_name_is_skipped() {
         let node = git_node(
@@ -746,4 +2511,155 @@ mod tests {
         let input = parse_input("nixpkgs", &node).unwrap();
         assert!(matches!(input, FlakeInput::Git(_)));
     }
+
+    #[tokio::test]
+    async fn test_update_command_replaces_nix_for_matching_inputs() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("flake.nix"), "{ }").unwrap();
+        let mut config = ServiceConfig::default();
+        config
+            .update_commands
+            .insert("vendored-*", "echo {input} >> updated".to_string());
+        config
+            .update_commands
+            .insert("broken", "echo no network >&2; exit 1".to_string());
+        let nix = NixService::new_with_config(CancellationToken::new(), config)
+            .with_lock_backup_dir(tmp.path().join("backups"));
+
+        let names = ["vendored-a".to_string(), "vendored-b".to_string()];
+        nix.update_inputs(tmp.path(), &names).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("updated")).unwrap(),
+            "vendored-a\nvendored-b\n"
+        );
+
+        let err = nix
+            .update_inputs(tmp.path(), &["broken".to_string()])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Update command for 'broken' failed: no network"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_inputs_each_reports_inputs_in_order_until_one_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("flake.nix"), "{ }").unwrap();
+        let mut config = ServiceConfig::default();
+        config
+            .update_commands
+            .insert("vendored-*", "echo {input} >> updated".to_string());
+        config
+            .update_commands
+            .insert("broken", "exit 1".to_string());
+        let nix = NixService::new_with_config(CancellationToken::new(), config)
+            .with_lock_backup_dir(tmp.path().join("backups"));
+
+        let started = std::sync::Mutex::new(Vec::new());
+        let names = ["vendored-a", "broken", "vendored-b"].map(str::to_string);
+        let result = nix
+            .update_inputs_each(tmp.path(), &names, |position, name| {
+                started
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}:{}", position, name));
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(*started.lock().unwrap(), ["0:vendored-a", "1:broken"]);
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("updated")).unwrap(),
+            "vendored-a\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_undo_restores_lock_from_before_update() {
+        let tmp = tempfile::tempdir().unwrap();
+        let flake_dir = tmp.path().join("flake");
+        std::fs::create_dir_all(&flake_dir).unwrap();
+        std::fs::write(flake_dir.join("flake.nix"), "{ }").unwrap();
+        std::fs::write(flake_dir.join("flake.lock"), "before").unwrap();
+        let mut config = ServiceConfig::default();
+        config
+            .update_commands
+            .insert("vendored", "echo after > flake.lock".to_string());
+        let nix = NixService::new_with_config(CancellationToken::new(), config)
+            .with_lock_backup_dir(tmp.path().join("backups"));
+
+        nix.update_inputs(&flake_dir, &["vendored".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(read_lock(&flake_dir).unwrap(), "after\n");
+
+        assert!(nix.undo_last_operation(&flake_dir).await.unwrap());
+        assert_eq!(read_lock(&flake_dir).unwrap(), "before");
+        assert!(!nix.undo_last_operation(&flake_dir).await.unwrap());
+    }
+
+    #[test]
+    fn test_parse_versions_skips_inputs_without_one() {
+        let versions =
+            parse_versions(r#"{"nixpkgs": "24.11pre-git", "flake-utils": null}"#).unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions["nixpkgs"], "24.11pre-git");
+        assert!(parse_versions("error").is_err());
+
+        let expr = versions_expr(Path::new("/src/${evil}"));
+        assert!(expr.contains(r#"builtins.getFlake "/src/\${evil}";"#));
+        assert!(expr.contains("packages.${system}.default.version"));
+    }
+
+    #[test]
+    fn test_parse_github_tarball_input_tracks_original_branch() {
+        let rev = "0123456789abcdef0123456789abcdef01234567";
+        let mut node = git_node(
+            "tarball",
+            None,
+            None,
+            None,
+            Some(&format!(
+                "https://github.com/NixOS/nixpkgs/archive/{}.tar.gz",
+                rev
+            )),
+            None,
+        );
+        node.original = Some(NixOriginal {
+            type_: Some("tarball".to_string()),
+            url: Some(
+                "https://github.com/NixOS/nixpkgs/archive/refs/heads/nixos-24.05.tar.gz"
+                    .to_string(),
+            ),
+            ..Default::default()
+        });
+
+        let RawInputParseResult::ActionableGit(input) = parse_raw_input("nixpkgs", &node) else {
+            panic!("expected a git input");
+        };
+        assert_eq!(
+            input.repo(),
+            &GitRepo::github(
+                Owner::new("NixOS").unwrap(),
+                RepoName::new("nixpkgs").unwrap()
+            )
+        );
+        assert_eq!(input.rev(), rev);
+        assert_eq!(input.reference(), Some("nixos-24.05"));
+
+        // Other tarballs, and archives of a branch that aren't locked to a
+        // commit, stay display-only.
+        for url in [
+            "https://example.com/src.tar.gz",
+            "https://github.com/NixOS/nixpkgs/archive/master.tar.gz",
+        ] {
+            let node = git_node("tarball", None, None, None, Some(url), None);
+            assert!(matches!(
+                parse_raw_input("src", &node),
+                RawInputParseResult::DisplayOnly(FlakeInput::Other(_))
+            ));
+        }
+    }
 }
@@ -0,0 +1,186 @@
+//! Installed nix versions and the command syntax each one understands
+//!
+//! Nix 2.19 replaced `nix flake lock --update-input NAME` with
+//! `nix flake update NAME --flake FLAKE`, and 2.20 renamed `nix show-config`
+//! to `nix config show`. melt builds those commands for the nix it finds.
+
+use std::fmt;
+
+/// Oldest nix with flakes, which melt is built on
+pub const MIN_SUPPORTED: NixVersion = NixVersion::new(2, 4, 0);
+
+/// First nix whose `nix flake update` takes input names and `--flake`
+const FLAKE_UPDATE_INPUTS: NixVersion = NixVersion::new(2, 19, 0);
+
+/// First nix with `nix config show`
+const CONFIG_SHOW: NixVersion = NixVersion::new(2, 20, 0);
+
+/// A nix release number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NixVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+/// Which inputs a `nix flake` command updates
+#[derive(Debug, Clone, Copy)]
+pub enum FlakeUpdate<'a> {
+    All,
+    Inputs(&'a [&'a str]),
+    /// Lock the input named `name` to `url`
+    Override {
+        name: &'a str,
+        url: &'a str,
+    },
+}
+
+impl NixVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse the output of `nix --version`, such as `nix (Nix) 2.18.1` or
+    /// `nix (Determinate Nix 3.6.2) 2.29.0`. The nix version is the last
+    /// word, which may carry a suffix like `pre20240101_abcdef`.
+    pub fn parse(output: &str) -> Option<Self> {
+        let word = output.split_whitespace().last()?;
+        let mut parts = word.split('.').map(|part| {
+            let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse::<u32>().ok()
+        });
+        let major = parts.next()??;
+        let minor = parts.next()??;
+        let patch = parts.next().flatten().unwrap_or(0);
+        Some(Self::new(major, minor, patch))
+    }
+
+    pub fn is_supported(self) -> bool {
+        self >= MIN_SUPPORTED
+    }
+
+    /// Arguments of the `nix flake` command that performs `update` on the
+    /// flake at `flake`
+    pub fn flake_update_args(self, update: FlakeUpdate, flake: &str) -> Vec<String> {
+        let mut args: Vec<&str> = vec!["flake"];
+        if self >= FLAKE_UPDATE_INPUTS {
+            args.push("update");
+            match update {
+                FlakeUpdate::All => {}
+                FlakeUpdate::Inputs(names) => args.extend(names),
+                FlakeUpdate::Override { name, url } => {
+                    args.extend([name, "--override-input", name, url])
+                }
+            }
+            args.extend(["--flake", flake]);
+        } else {
+            match update {
+                FlakeUpdate::All => args.push("update"),
+                FlakeUpdate::Inputs(names) => {
+                    args.push("lock");
+                    for name in names {
+                        args.extend(["--update-input", name]);
+                    }
+                }
+                FlakeUpdate::Override { name, url } => {
+                    args.extend(["lock", "--override-input", name, url])
+                }
+            }
+            args.push(flake);
+        }
+        args.into_iter().map(str::to_string).collect()
+    }
+
+    /// Arguments of the command printing the nix setting `name`
+    pub fn config_show_args(self, name: &str) -> Vec<String> {
+        if self >= CONFIG_SHOW {
+            vec!["config".to_string(), "show".to_string(), name.to_string()]
+        } else {
+            vec!["show-config".to_string()]
+        }
+    }
+}
+
+impl Default for NixVersion {
+    /// Versions melt cannot read are taken to be current
+    fn default() -> Self {
+        Self::new(u32::MAX, 0, 0)
+    }
+}
+
+impl fmt::Display for NixVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_version_output_of_nix_and_its_forks() {
+        assert_eq!(
+            NixVersion::parse("nix (Nix) 2.18.1\n"),
+            Some(NixVersion::new(2, 18, 1))
+        );
+        assert_eq!(
+            NixVersion::parse("nix (Determinate Nix 3.6.2) 2.29.0"),
+            Some(NixVersion::new(2, 29, 0))
+        );
+        assert_eq!(
+            NixVersion::parse("nix (Lix, like Nix) 2.91.1"),
+            Some(NixVersion::new(2, 91, 1))
+        );
+        assert_eq!(
+            NixVersion::parse("nix (Nix) 2.25pre20240920_ca3fc169"),
+            Some(NixVersion::new(2, 25, 0))
+        );
+        assert_eq!(NixVersion::parse("nix"), None);
+        assert!(!NixVersion::new(2, 3, 16).is_supported());
+    }
+
+    #[test]
+    fn builds_update_commands_for_old_and_new_nix() {
+        let old = NixVersion::new(2, 18, 1);
+        let new = NixVersion::new(2, 19, 0);
+        let names = ["nixpkgs", "utils"];
+
+        assert_eq!(
+            old.flake_update_args(FlakeUpdate::Inputs(&names), "/f")
+                .join(" "),
+            "flake lock --update-input nixpkgs --update-input utils /f"
+        );
+        assert_eq!(
+            new.flake_update_args(FlakeUpdate::Inputs(&names), "/f")
+                .join(" "),
+            "flake update nixpkgs utils --flake /f"
+        );
+        assert_eq!(
+            old.flake_update_args(FlakeUpdate::All, "/f").join(" "),
+            "flake update /f"
+        );
+        assert_eq!(
+            new.flake_update_args(FlakeUpdate::All, "/f").join(" "),
+            "flake update --flake /f"
+        );
+        let lock = FlakeUpdate::Override {
+            name: "nixpkgs",
+            url: "github:NixOS/nixpkgs/abc",
+        };
+        assert_eq!(
+            old.flake_update_args(lock, "/f").join(" "),
+            "flake lock --override-input nixpkgs github:NixOS/nixpkgs/abc /f"
+        );
+        assert_eq!(
+            NixVersion::default()
+                .flake_update_args(lock, "/f")
+                .join(" "),
+            "flake update nixpkgs --override-input nixpkgs github:NixOS/nixpkgs/abc --flake /f"
+        );
+    }
+}
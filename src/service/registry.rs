@@ -0,0 +1,136 @@
+//! The flake registry, which maps ids such as `nixpkgs` to flake references
+//!
+//! Inputs declared as `nixpkgs` or `nixpkgs/nixos-24.05` are `indirect`:
+//! nix looks the id up in the registry to find what to fetch, so the branch
+//! an update follows comes from there rather than from the lock.
+
+use std::collections::HashMap;
+
+/// Registry entries by id, as `nix registry list` reports them
+#[derive(Debug, Clone, Default)]
+pub struct FlakeRegistry {
+    entries: HashMap<String, FlakeRef>,
+}
+
+/// The parts of a flake reference melt reads to locate an input
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlakeRef {
+    pub type_: String,
+    pub owner: Option<String>,
+    pub repo: Option<String>,
+    pub reference: Option<String>,
+    pub host: Option<String>,
+    pub url: Option<String>,
+}
+
+impl FlakeRegistry {
+    /// Parse `nix registry list` output, one `scope flake:id target` entry
+    /// per line. Nix lists user entries before system and global ones, and
+    /// the first entry for an id is the one it uses.
+    pub fn parse(output: &str) -> Self {
+        let mut entries = HashMap::new();
+        for line in output.lines() {
+            let mut fields = line.split_whitespace().skip(1);
+            let (Some(from), Some(to)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let Some(id) = from.strip_prefix("flake:").filter(|id| !id.contains('/')) else {
+                continue;
+            };
+            if let Some(target) = FlakeRef::parse(to) {
+                entries.entry(id.to_string()).or_insert(target);
+            }
+        }
+        Self { entries }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&FlakeRef> {
+        self.entries.get(id)
+    }
+}
+
+impl FlakeRef {
+    /// Parse a flake reference in URL-like syntax, such as
+    /// `github:NixOS/nixpkgs/nixpkgs-unstable` or
+    /// `git+https://example.com/repo?ref=main`. Indirect and unknown
+    /// references give `None`.
+    pub fn parse(reference: &str) -> Option<Self> {
+        let (base, query) = reference
+            .split_once('?')
+            .map_or((reference, ""), |(base, query)| (base, query));
+        let param = |key: &str| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix(key)?.strip_prefix('='))
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let (scheme, rest) = base.split_once(':')?;
+        match scheme {
+            "github" | "gitlab" | "sourcehut" => {
+                let mut segments = rest.split('/').filter(|s| !s.is_empty());
+                let owner = segments.next()?.to_string();
+                let repo = segments.next()?.to_string();
+                let path_ref = segments.collect::<Vec<_>>().join("/");
+                Some(Self {
+                    type_: scheme.to_string(),
+                    owner: Some(owner),
+                    repo: Some(repo),
+                    reference: param("ref").or((!path_ref.is_empty()).then_some(path_ref)),
+                    host: param("host"),
+                    url: None,
+                })
+            }
+            "git+https" | "git+http" | "git+ssh" | "git+file" => Some(Self {
+                type_: "git".to_string(),
+                reference: param("ref"),
+                url: Some(base.trim_start_matches("git+").to_string()),
+                ..Self::default()
+            }),
+            "https" | "http" | "tarball+https" | "tarball+http" => Some(Self {
+                type_: "tarball".to_string(),
+                url: Some(base.trim_start_matches("tarball+").to_string()),
+                ..Self::default()
+            }),
+            "path" => Some(Self {
+                type_: "path".to_string(),
+                url: Some(rest.to_string()),
+                ..Self::default()
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_listed_entry_for_an_id_wins() {
+        let registry = FlakeRegistry::parse(
+            "user   flake:nixpkgs github:NixOS/nixpkgs/nixos-24.05\n\
+             global flake:nixpkgs github:NixOS/nixpkgs/nixpkgs-unstable\n\
+             global flake:hydra github:NixOS/hydra\n\
+             global flake:sops git+https://git.example.com/sops.git?ref=main&shallow=1\n\
+             global flake:nixpkgs/stable github:NixOS/nixpkgs/nixos-24.05\n\
+             malformed\n",
+        );
+
+        let nixpkgs = registry.get("nixpkgs").unwrap();
+        assert_eq!(nixpkgs.type_, "github");
+        assert_eq!(nixpkgs.owner.as_deref(), Some("NixOS"));
+        assert_eq!(nixpkgs.repo.as_deref(), Some("nixpkgs"));
+        assert_eq!(nixpkgs.reference.as_deref(), Some("nixos-24.05"));
+        assert_eq!(registry.get("hydra").unwrap().reference, None);
+        let sops = registry.get("sops").unwrap();
+        assert_eq!(sops.type_, "git");
+        assert_eq!(
+            sops.url.as_deref(),
+            Some("https://git.example.com/sops.git")
+        );
+        assert_eq!(sops.reference.as_deref(), Some("main"));
+        assert!(registry.get("nixpkgs/stable").is_none());
+        assert!(FlakeRef::parse("flake:nixpkgs").is_none());
+    }
+}
@@ -0,0 +1,136 @@
+//! Synthetic fixtures for stress tests
+//!
+//! Real-world locks top out around a few hundred inputs; these generators
+//! build arbitrarily large ones so tests can exercise parsing, selection,
+//! and rendering at sizes where per-frame or per-input costs show up.
+
+use std::path::PathBuf;
+
+use crate::model::{FlakeData, FlakeInput, GitInput, GitRepo, GitRev, InputName, Owner, RepoName};
+
+/// Name of the `idx`-th synthetic input, zero padded so names sort in order
+pub fn synthetic_name(idx: usize) -> String {
+    format!("input-{:04}", idx)
+}
+
+/// A flake with `count` GitHub inputs
+pub fn synthetic_flake(count: usize) -> FlakeData {
+    FlakeData {
+        path: PathBuf::from("/tmp/synthetic"),
+        warnings: Vec::new(),
+        input_trees: Vec::new(),
+        inputs: (0..count)
+            .map(|idx| {
+                let name = synthetic_name(idx);
+                FlakeInput::Git(GitInput::new(
+                    InputName::new(name.as_str()).unwrap(),
+                    GitRepo::github(
+                        Owner::new("synthetic").unwrap(),
+                        RepoName::new(name.as_str()).unwrap(),
+                    ),
+                    None,
+                    GitRev::new(format!("{:040x}", idx)).unwrap(),
+                    1_700_000_000 + idx as i64,
+                    format!("github:synthetic/{}", name),
+                ))
+            })
+            .collect(),
+    }
+}
+
+/// `nix flake metadata --json` output for a lock with `count` GitHub inputs
+pub fn synthetic_metadata_json(count: usize) -> String {
+    let mut root_inputs = serde_json::Map::new();
+    let mut nodes = serde_json::Map::new();
+
+    for idx in 0..count {
+        let name = synthetic_name(idx);
+        root_inputs.insert(name.clone(), serde_json::json!(name));
+        nodes.insert(
+            name.clone(),
+            serde_json::json!({
+                "locked": {
+                    "type": "github",
+                    "owner": "synthetic",
+                    "repo": name,
+                    "rev": format!("{:040x}", idx),
+                    "lastModified": 1_700_000_000 + idx as i64,
+                },
+                "original": {
+                    "type": "github",
+                    "owner": "synthetic",
+                    "repo": name,
+                },
+            }),
+        );
+    }
+    nodes.insert(
+        "root".to_string(),
+        serde_json::json!({ "inputs": root_inputs }),
+    );
+
+    serde_json::json!({
+        "description": "synthetic stress fixture",
+        "locks": { "nodes": nodes, "root": "root", "version": 7 },
+    })
+    .to_string()
+}
+
+/// A `flake.lock` with `count` root inputs, each broken in one of six ways
+/// in turn:
+///
+/// 0. healthy
+/// 1. points to a node that does not exist
+/// 2. shares the node of the healthy input two before it
+/// 3. follows itself
+/// 4. has a node whose own inputs point back at it
+/// 5. has a node without a `locked` section
+pub fn pathological_lock_json(count: usize) -> String {
+    let mut root_inputs = serde_json::Map::new();
+    let mut nodes = serde_json::Map::new();
+    let locked = |name: &str, idx: usize| {
+        serde_json::json!({
+            "type": "github",
+            "owner": "synthetic",
+            "repo": name,
+            "rev": format!("{:040x}", idx),
+        })
+    };
+
+    for idx in 0..count {
+        let name = synthetic_name(idx);
+        let reference = match idx % 6 {
+            1 => serde_json::json!(format!("missing-{}", name)),
+            2 => serde_json::json!(synthetic_name(idx - 2)),
+            3 => serde_json::json!([name]),
+            4 => {
+                nodes.insert(
+                    name.clone(),
+                    serde_json::json!({
+                        "inputs": { "self": name, "again": [name, "self"] },
+                        "locked": locked(&name, idx),
+                    }),
+                );
+                serde_json::json!(name)
+            }
+            5 => {
+                nodes.insert(name.clone(), serde_json::json!({ "inputs": {} }));
+                serde_json::json!(name)
+            }
+            _ => {
+                nodes.insert(
+                    name.clone(),
+                    serde_json::json!({ "locked": locked(&name, idx) }),
+                );
+                serde_json::json!(name)
+            }
+        };
+        root_inputs.insert(name, reference);
+    }
+    nodes.insert(
+        "root".to_string(),
+        serde_json::json!({ "inputs": root_inputs }),
+    );
+
+    serde_json::json!({ "nodes": nodes, "root": "root", "version": 7 }).to_string()
+}
@@ -0,0 +1,87 @@
+//! Columns of the list view
+
+use serde::Deserialize;
+
+/// A column the list view can show, chosen with `columns` in the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListColumn {
+    Name,
+    Type,
+    Rev,
+    Updated,
+    Status,
+    /// Source URL of git inputs
+    Url,
+    /// Version the input exposes, evaluated with nix after the flake loads
+    Version,
+}
+
+impl ListColumn {
+    pub fn header(self) -> &'static str {
+        match self {
+            ListColumn::Name => "NAME",
+            ListColumn::Type => "TYPE",
+            ListColumn::Rev => "REV",
+            ListColumn::Updated => "UPDATED",
+            ListColumn::Status => "STATUS",
+            ListColumn::Url => "URL",
+            ListColumn::Version => "VERSION",
+        }
+    }
+}
+
+/// Ordered, non-empty set of list columns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListColumns(Vec<ListColumn>);
+
+impl ListColumns {
+    /// Columns shown when the config file does not choose any
+    pub const DEFAULT: &'static [ListColumn] = &[
+        ListColumn::Name,
+        ListColumn::Type,
+        ListColumn::Rev,
+        ListColumn::Updated,
+        ListColumn::Status,
+    ];
+
+    pub fn new(columns: Vec<ListColumn>) -> Result<Self, String> {
+        if columns.is_empty() {
+            return Err("columns must list at least one column".to_string());
+        }
+        for (idx, column) in columns.iter().enumerate() {
+            if columns[..idx].contains(column) {
+                return Err(format!(
+                    "column '{}' is listed more than once",
+                    column.header().to_lowercase()
+                ));
+            }
+        }
+        Ok(Self(columns))
+    }
+
+    pub fn as_slice(&self) -> &[ListColumn] {
+        &self.0
+    }
+}
+
+impl Default for ListColumns {
+    fn default() -> Self {
+        Self(Self::DEFAULT.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_empty_and_duplicate_columns() {
+        assert!(ListColumns::new(Vec::new()).is_err());
+        assert_eq!(
+            ListColumns::new(vec![ListColumn::Name, ListColumn::Url, ListColumn::Name]),
+            Err("column 'name' is listed more than once".to_string())
+        );
+        assert!(ListColumns::new(vec![ListColumn::Url, ListColumn::Name]).is_ok());
+    }
+}
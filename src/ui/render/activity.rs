@@ -0,0 +1,88 @@
+//! Activity log rendering
+
+use chrono::Local;
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::model::{ActivityLog, StatusLevel};
+use crate::ui::theme::Theme;
+use crate::util::format::format_time;
+
+/// Render the session's activity over the whole screen, oldest first,
+/// scrolled by `scroll` lines. `scroll` is clamped so the newest entry
+/// stays in view.
+pub fn render_activity(frame: &mut Frame, log: &ActivityLog, scroll: &mut u16, theme: &Theme) {
+    let area = frame.area();
+    frame.render_widget(Clear, area);
+
+    let dim = Style::default().fg(theme.text_dim);
+    let lines: Vec<Line> = if log.is_empty() {
+        vec![Line::styled(" Nothing has happened yet", dim)]
+    } else {
+        log.entries()
+            .map(|entry| {
+                let color = match entry.level {
+                    StatusLevel::Info => theme.text,
+                    StatusLevel::Success => theme.success,
+                    StatusLevel::Warning => theme.warning,
+                    StatusLevel::Error => theme.error,
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!(" {}  ", format_time(entry.at.with_timezone(&Local))),
+                        Style::default().fg(theme.text_muted),
+                    ),
+                    Span::styled(entry.text.clone(), Style::default().fg(color)),
+                ])
+            })
+            .collect()
+    };
+    let height = area.height.saturating_sub(2);
+    let max = u16::try_from(lines.len())
+        .unwrap_or(u16::MAX)
+        .saturating_sub(height);
+    *scroll = (*scroll).min(max);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(format!(" Activity ({}) ", log.len()))
+        .title_style(Style::default().fg(theme.text))
+        .title_bottom(
+            Line::styled(" j/k scroll · g/G oldest/newest · L/q/esc close ", dim).right_aligned(),
+        )
+        .style(Style::default().bg(theme.bg_dark));
+    frame.render_widget(
+        Paragraph::new(lines).block(block).scroll((*scroll, 0)),
+        area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn activity_log_opens_on_the_newest_entries() {
+        let mut log = ActivityLog::default();
+        for i in 0..30 {
+            log.record(StatusLevel::Error, format!("input-{:02}: check failed", i));
+        }
+        let mut scroll = u16::MAX;
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+        terminal
+            .draw(|frame| render_activity(frame, &log, &mut scroll, &Theme::default()))
+            .unwrap();
+
+        assert_eq!(scroll, 20);
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("input-29: check failed"));
+        assert!(!text.contains("input-19: check failed"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Build view rendering
+
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table},
+    Frame,
+};
+
+use crate::app::state::{BuildState, BuildStatus};
+use crate::model::{StatusLevel, StatusMessage};
+use crate::ui::theme::Theme;
+
+use super::tasks::format_elapsed;
+
+/// Most rows the output table takes before the log gets the rest
+const MAX_OUTPUT_ROWS: u16 = 8;
+
+/// Render the build view
+pub fn render_build(
+    frame: &mut Frame,
+    build: &mut BuildState,
+    status_message: Option<&StatusMessage>,
+    theme: &Theme,
+) {
+    let area = frame.area();
+    let table_height = (build.outputs.len() as u16).min(MAX_OUTPUT_ROWS) + 2;
+    let chunks = Layout::vertical([
+        Constraint::Length(table_height),
+        Constraint::Min(3),
+        Constraint::Length(3),
+    ])
+    .split(area);
+
+    render_outputs_table(frame, build, chunks[0], theme);
+    render_build_log(frame, build, chunks[1], theme);
+    render_build_help_bar(frame, build, status_message, chunks[2], theme);
+}
+
+/// Render the outputs and how far each has got
+fn render_outputs_table(frame: &mut Frame, build: &mut BuildState, area: Rect, theme: &Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title(" Build outputs ")
+        .title_style(Style::default().fg(theme.text));
+
+    let rows: Vec<Row> = build
+        .outputs
+        .iter()
+        .map(|output| {
+            let (status, color) = match &output.status {
+                BuildStatus::Pending => ("waiting".to_string(), theme.text_dim),
+                BuildStatus::Building => ("building".to_string(), theme.info),
+                BuildStatus::Built(took) => {
+                    (format!("built in {}", format_elapsed(*took)), theme.success)
+                }
+                BuildStatus::Failed => ("failed".to_string(), theme.error),
+                BuildStatus::Cancelled => ("cancelled".to_string(), theme.warning),
+            };
+            Row::new(vec![
+                Span::styled(status, Style::default().fg(color)),
+                Span::styled(output.target.as_str(), Style::default().fg(theme.text)),
+            ])
+        })
+        .collect();
+
+    let widths = [Constraint::Length(18), Constraint::Min(20)];
+    let table = Table::new(rows, widths).block(block).row_highlight_style(
+        Style::default()
+            .bg(theme.bg_highlight)
+            .fg(theme.cursor)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    frame.render_stateful_widget(table, area, &mut build.table_state);
+}
+
+/// Render the end of the selected output's log
+fn render_build_log(frame: &mut Frame, build: &BuildState, area: Rect, theme: &Theme) {
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border));
+    let Some(output) = build.selected_output() else {
+        frame.render_widget(block, area);
+        return;
+    };
+    block = block
+        .title(format!(" {} ", output.target))
+        .title_style(Style::default().fg(theme.text));
+
+    if output.log.is_empty() {
+        let text = match output.status {
+            BuildStatus::Pending => "Waiting for the builds before it",
+            BuildStatus::Building => "Evaluating...",
+            _ => "Nothing was logged",
+        };
+        let msg = Paragraph::new(text)
+            .style(Style::default().fg(theme.text_muted))
+            .alignment(Alignment::Center)
+            .block(block);
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    // Keep the newest lines in view, like a terminal would.
+    let height = area.height.saturating_sub(2) as usize;
+    let color = if output.status == BuildStatus::Failed {
+        theme.error
+    } else {
+        theme.text_muted
+    };
+    let lines: Vec<Line> = output
+        .log
+        .iter()
+        .skip(output.log.len().saturating_sub(height))
+        .map(|line| Line::styled(line.as_str(), Style::default().fg(color)))
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Render the build view help bar
+fn render_build_help_bar(
+    frame: &mut Frame,
+    build: &BuildState,
+    status_message: Option<&StatusMessage>,
+    area: Rect,
+    theme: &Theme,
+) {
+    let mut shortcuts = vec![("j/k", "nav")];
+    if !build.is_running() {
+        shortcuts.push(("r", "rebuild"));
+    }
+    shortcuts.push(("q/esc", "back"));
+    let mut spans: Vec<Span> = shortcuts
+        .iter()
+        .flat_map(|(key, desc)| {
+            vec![
+                Span::styled(*key, Style::default().fg(theme.key_hint)),
+                Span::styled(format!(" {} ", desc), Style::default().fg(theme.text_dim)),
+            ]
+        })
+        .collect();
+
+    if let Some(msg) = status_message {
+        let color = match msg.level {
+            StatusLevel::Info => theme.info,
+            StatusLevel::Success => theme.success,
+            StatusLevel::Warning => theme.warning,
+            StatusLevel::Error => theme.error,
+        };
+        spans.push(Span::styled(
+            format!(" | {}", msg.text),
+            Style::default().fg(color),
+        ));
+    }
+
+    let help = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border)),
+    );
+
+    frame.render_widget(help, area);
+}
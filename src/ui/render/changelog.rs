@@ -4,13 +4,16 @@ use ratatui::{
     layout::{Alignment, Constraint, Layout, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph, Row, Table},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, Wrap},
     Frame,
 };
 
-use crate::app::state::ChangelogState;
-use crate::model::{StatusLevel, StatusMessage};
-use crate::ui::theme;
+use crate::app::state::{ChangelogPane, ChangelogState, ChangelogView};
+use crate::model::{
+    Commit, CommitCount, CommitDetail, Release, SignatureStatus, StatusLevel, StatusMessage,
+};
+use crate::ui::theme::Theme;
+use crate::util::format::{format_count, format_datetime};
 use crate::util::text::truncate_with_ellipsis;
 use crate::util::time::format_relative_short;
 
@@ -19,29 +22,303 @@ pub fn render_changelog(
     frame: &mut Frame,
     cs: &mut ChangelogState,
     status_message: Option<&StatusMessage>,
+    theme: &Theme,
 ) {
     let area = frame.area();
-    let chunks = Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).split(area);
+    let chunks = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(3),
+        Constraint::Length(3),
+    ])
+    .split(area);
 
-    render_commits_table(frame, cs, chunks[0]);
-    render_changelog_help_bar(frame, cs, status_message, chunks[1]);
+    match cs.view {
+        ChangelogView::Commits => {
+            render_summary_header(frame, cs, chunks[0], theme);
+            render_commits_table(frame, cs, chunks[1], theme);
+        }
+        ChangelogView::Authors => {
+            render_author_header(frame, cs, chunks[0], theme);
+            render_authors_table(frame, cs, chunks[1], theme);
+        }
+        ChangelogView::Tags => {
+            render_summary_header(frame, cs, chunks[0], theme);
+            render_tags_table(frame, cs, chunks[1], theme);
+        }
+    }
+    render_changelog_help_bar(frame, cs, status_message, chunks[2], theme);
 
     if cs.is_confirming() {
-        render_confirm_dialog(frame, cs, area);
+        render_confirm_dialog(frame, cs, area, theme);
+    } else if let Some(pane) = &cs.release {
+        render_release_pane(frame, pane, area, theme);
+    } else if let Some(pane) = &cs.detail {
+        render_detail_pane(frame, pane, &cs.data.commits, area, theme);
+    }
+}
+
+/// Render the one-line summary of the pending update, or of locking to the
+/// new commit under the cursor
+fn render_summary_header(frame: &mut Frame, cs: &ChangelogState, area: Rect, theme: &Theme) {
+    let separator = || Span::styled(" · ", Style::default().fg(theme.text_dim));
+
+    let mut spans = vec![Span::raw(" ")];
+    let summary = match cs.cursor_summary() {
+        Some((commit, summary)) => {
+            spans.push(Span::styled(
+                format!("Up to {}: ", commit.short_sha()),
+                Style::default().fg(theme.sha),
+            ));
+            summary
+        }
+        None => cs.data.summary(),
+    };
+    if summary.ahead.value() == 0 {
+        spans.push(Span::styled(
+            "No new commits",
+            Style::default().fg(theme.text_muted),
+        ));
+    } else {
+        let noun = if summary.ahead.value() == 1 {
+            "commit"
+        } else {
+            "commits"
+        };
+        spans.push(Span::styled(
+            format!("{} new {}", summary.ahead, noun),
+            Style::default()
+                .fg(theme.success)
+                .add_modifier(Modifier::BOLD),
+        ));
+        spans.push(separator());
+        let noun = if summary.authors == 1 {
+            "author"
+        } else {
+            "authors"
+        };
+        spans.push(Span::styled(
+            format!("{} {}", summary.authors, noun),
+            Style::default().fg(theme.info),
+        ));
+        if let (Some(oldest), Some(newest)) = (summary.oldest, summary.newest) {
+            spans.push(separator());
+            spans.push(Span::styled(
+                format!(
+                    "{} → {}",
+                    oldest.format("%b %d, %Y"),
+                    newest.format("%b %d, %Y")
+                ),
+                Style::default().fg(theme.text_muted),
+            ));
+        }
+    }
+
+    if let Some(stats) = summary.diff_stats {
+        // Partial totals only count the files the forge listed
+        let more = if stats.partial { "+" } else { "" };
+        spans.push(separator());
+        spans.push(Span::styled(
+            format!("{}{} files ", stats.files_changed, more),
+            Style::default().fg(theme.text_muted),
+        ));
+        spans.push(Span::styled(
+            format!("+{}{}", stats.insertions, more),
+            Style::default().fg(theme.success),
+        ));
+        spans.push(Span::styled(
+            format!(" -{}{}", stats.deletions, more),
+            Style::default().fg(theme.error),
+        ));
+    }
+
+    if cs.loading {
+        spans.push(separator());
+        spans.push(Span::styled(
+            "loading more…",
+            Style::default().fg(theme.text_dim),
+        ));
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// Render the author breakdown line, e.g. "142 commits by 37 authors — top: K900 (24)"
+fn render_author_header(frame: &mut Frame, cs: &ChangelogState, area: Rect, theme: &Theme) {
+    let authors = cs.data.author_counts();
+    let ahead = cs.data.commits_ahead_count();
+
+    let mut spans = vec![Span::raw(" ")];
+    if authors.is_empty() {
+        spans.push(Span::styled(
+            "No new commits",
+            Style::default().fg(theme.text_muted),
+        ));
+    } else {
+        spans.push(Span::styled(
+            format!("{} commits by {} authors", ahead, authors.len()),
+            Style::default()
+                .fg(theme.success)
+                .add_modifier(Modifier::BOLD),
+        ));
+        let top = authors
+            .iter()
+            .take(3)
+            .map(|a| format!("{} ({})", a.author, a.commits))
+            .collect::<Vec<_>>()
+            .join(", ");
+        spans.push(Span::styled(
+            " — top: ",
+            Style::default().fg(theme.text_dim),
+        ));
+        spans.push(Span::styled(top, Style::default().fg(theme.info)));
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// Render the new commits grouped by author
+fn render_authors_table(frame: &mut Frame, cs: &mut ChangelogState, area: Rect, theme: &Theme) {
+    let authors = cs.data.author_counts();
+    let total = cs.data.commits_ahead().max(1);
+    let title = format!(" {} authors ({}) ", cs.input.name(), cs.input.url());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title(title)
+        .title_style(Style::default().fg(theme.text));
+
+    if authors.is_empty() {
+        let msg = Paragraph::new("Already up to date!")
+            .style(Style::default().fg(theme.success))
+            .alignment(Alignment::Center)
+            .block(block);
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    let rows: Vec<Row> = authors
+        .iter()
+        .map(|a| {
+            let share = a.commits * 100 / total;
+            Row::new(vec![
+                Span::styled(
+                    truncate_with_ellipsis(&a.author, 30),
+                    Style::default().fg(theme.info),
+                ),
+                Span::styled(a.commits.to_string(), Style::default().fg(theme.text)),
+                Span::styled(format!("{}%", share), Style::default().fg(theme.text_dim)),
+                Span::styled(
+                    "█".repeat((share / 5).max(1)),
+                    Style::default().fg(theme.accent),
+                ),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(31),
+        Constraint::Length(7),
+        Constraint::Length(5),
+        Constraint::Min(20),
+    ];
+
+    let table = Table::new(rows, widths).block(block).row_highlight_style(
+        Style::default()
+            .bg(theme.bg_highlight)
+            .fg(theme.cursor)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    frame.render_stateful_widget(table, area, &mut cs.author_table_state);
+}
+
+/// Render the upstream tags on the new commits
+fn render_tags_table(frame: &mut Frame, cs: &mut ChangelogState, area: Rect, theme: &Theme) {
+    let title = format!(" {} tags ({}) ", cs.input.name(), cs.input.url());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title(title)
+        .title_style(Style::default().fg(theme.text));
+
+    let tags = cs.new_tags();
+    if tags.is_empty() {
+        let text = if cs.tags.is_none() {
+            "Loading tags..."
+        } else {
+            "No tags newer than the locked revision"
+        };
+        let msg = Paragraph::new(text)
+            .style(Style::default().fg(theme.text_muted))
+            .alignment(Alignment::Center)
+            .block(block);
+        frame.render_widget(msg, area);
+        return;
     }
+
+    let rows: Vec<Row> = tags
+        .iter()
+        .map(|(idx, tag)| {
+            let commit = &cs.data.commits[*idx];
+            Row::new(vec![
+                Span::styled(
+                    truncate_with_ellipsis(&tag.name, 24),
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(commit.short_sha(), Style::default().fg(theme.sha)),
+                Span::styled(
+                    format_relative_short(commit.date),
+                    Style::default().fg(theme.text_dim),
+                ),
+                Span::styled(
+                    truncate_with_ellipsis(&commit.message, 55),
+                    Style::default().fg(theme.text),
+                ),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(25),
+        Constraint::Length(9),
+        Constraint::Length(10),
+        Constraint::Min(20),
+    ];
+
+    let table = Table::new(rows, widths).block(block).row_highlight_style(
+        Style::default()
+            .bg(theme.bg_highlight)
+            .fg(theme.cursor)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    frame.render_stateful_widget(table, area, &mut cs.tag_table_state);
 }
 
 /// Render the commits table
-fn render_commits_table(frame: &mut Frame, cs: &mut ChangelogState, area: Rect) {
-    if cs.data.commits.is_empty() {
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme::BORDER))
-            .title(format!(" {} ({}) ", cs.input.name(), cs.input.url()))
-            .title_style(Style::default().fg(theme::TEXT));
+fn render_commits_table(frame: &mut Frame, cs: &mut ChangelogState, area: Rect, theme: &Theme) {
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title(format!(" {} ({}) ", cs.input.name(), cs.input.url()))
+        .title_style(Style::default().fg(theme.text));
+    // Name the clone when no forge API answered, since that is where the
+    // time and disk space went.
+    if let Some(path) = cs.data.clone_path() {
+        block = block.title(
+            Line::styled(
+                format!(" from clone {} ", path.display()),
+                Style::default().fg(theme.text_dim),
+            )
+            .right_aligned(),
+        );
+    }
 
+    if cs.data.commits.is_empty() {
         let msg = Paragraph::new("Already up to date!")
-            .style(Style::default().fg(theme::SUCCESS))
+            .style(Style::default().fg(theme.success))
             .alignment(Alignment::Center)
             .block(block);
 
@@ -57,11 +334,7 @@ fn render_commits_table(frame: &mut Frame, cs: &mut ChangelogState, area: Rect)
         .map(|(idx, commit)| {
             let is_locked = cs.data.is_locked(idx);
             let lock_icon = if is_locked { "🔒" } else { "  " };
-            let sha_color = if is_locked {
-                theme::WARNING
-            } else {
-                theme::SHA
-            };
+            let sha_color = if is_locked { theme.warning } else { theme.sha };
 
             let author = if commit.author.chars().count() > 15 {
                 truncate_with_ellipsis(&commit.author, 15)
@@ -69,89 +342,316 @@ fn render_commits_table(frame: &mut Frame, cs: &mut ChangelogState, area: Rect)
                 format!("{:14}", commit.author)
             };
 
-            let message = truncate_with_ellipsis(&commit.message, 55);
+            let mut message = Line::default();
+            if let Some(tag) = cs.tag_on(idx) {
+                message.push_span(Span::styled(
+                    format!("[{}] ", tag.name),
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                ));
+            }
+            message.push_span(Span::styled(
+                truncate_with_ellipsis(&commit.message, 55),
+                Style::default().fg(theme.text),
+            ));
 
             Row::new(vec![
-                Span::styled(lock_icon, Style::default().fg(theme::WARNING)),
-                Span::styled(commit.short_sha(), Style::default().fg(sha_color)),
-                Span::styled(author, Style::default().fg(theme::INFO)),
-                Span::styled(
+                Line::styled(lock_icon, Style::default().fg(theme.warning)),
+                Line::styled(commit.short_sha(), Style::default().fg(sha_color)),
+                signature_badge(commit.signature, theme),
+                Line::styled(author, Style::default().fg(theme.info)),
+                Line::styled(
                     format_relative_short(commit.date),
-                    Style::default().fg(theme::TEXT_DIM),
+                    Style::default().fg(theme.text_dim),
                 ),
-                Span::styled(message, Style::default().fg(theme::TEXT)),
+                message,
             ])
         })
         .collect();
 
     let widths = [
         Constraint::Length(3),
-        Constraint::Length(9),
+        Constraint::Length(8),
+        Constraint::Length(2),
         Constraint::Length(16),
         Constraint::Length(10),
         Constraint::Min(20),
     ];
 
-    let title = format!(" {} ({}) ", cs.input.name(), cs.input.url());
-    let table = Table::new(rows, widths)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(theme::BORDER))
-                .title(title)
-                .title_style(Style::default().fg(theme::TEXT)),
-        )
-        .row_highlight_style(
-            Style::default()
-                .bg(theme::BG_HIGHLIGHT)
-                .fg(theme::CURSOR)
-                .add_modifier(Modifier::BOLD),
-        );
+    let table = Table::new(rows, widths).block(block).row_highlight_style(
+        Style::default()
+            .bg(theme.bg_highlight)
+            .fg(theme.cursor)
+            .add_modifier(Modifier::BOLD),
+    );
 
     frame.render_stateful_widget(table, area, &mut cs.table_state);
 }
 
+/// `✓` for verified signatures and `?` for ones that are present but
+/// unverified; nothing when unsigned or unknown
+fn signature_badge(signature: SignatureStatus, theme: &Theme) -> Line<'static> {
+    match signature {
+        SignatureStatus::Verified => Line::styled("✓", Style::default().fg(theme.success)),
+        SignatureStatus::Unverified => Line::styled("?", Style::default().fg(theme.warning)),
+        SignatureStatus::Unsigned | SignatureStatus::Unknown => Line::default(),
+    }
+}
+
+/// Render the release notes of a tag over the changelog
+fn render_release_pane(
+    frame: &mut Frame,
+    pane: &ChangelogPane<Release>,
+    area: Rect,
+    theme: &Theme,
+) {
+    let dim = Style::default().fg(theme.text_dim);
+    let text: Vec<Line> = match &pane.content {
+        _ if pane.loading => vec![Line::styled("Loading release notes...", dim)],
+        None => vec![Line::styled(
+            format!("No release published for {}", pane.id),
+            dim,
+        )],
+        Some(release) => {
+            let mut lines = vec![
+                Line::styled(
+                    release.name.clone(),
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Line::styled(release.url.clone(), dim),
+                Line::from(""),
+            ];
+            if release.body.trim().is_empty() {
+                lines.push(Line::styled("No release notes", dim));
+            }
+            lines.extend(
+                release
+                    .body
+                    .lines()
+                    .map(|line| Line::styled(line.to_string(), Style::default().fg(theme.text))),
+            );
+            lines
+        }
+    };
+
+    let title = format!(" {} release notes ", pane.id);
+    render_pane(frame, area, title, text, pane.scroll, theme);
+}
+
+/// Render the full message and changed files of a commit over the changelog.
+/// The message of the listed commit shows while its files load.
+fn render_detail_pane(
+    frame: &mut Frame,
+    pane: &ChangelogPane<CommitDetail>,
+    commits: &[Commit],
+    area: Rect,
+    theme: &Theme,
+) {
+    let dim = Style::default().fg(theme.text_dim);
+    let message_lines = |message: &str| -> Vec<Line> {
+        message
+            .lines()
+            .map(|line| Line::styled(line.to_string(), Style::default().fg(theme.text)))
+            .collect()
+    };
+    let text: Vec<Line> = match &pane.content {
+        Some(detail) if !pane.loading => {
+            let stats = detail.stats();
+            let mut lines = vec![
+                Line::from(vec![
+                    Span::styled(
+                        detail.sha.clone(),
+                        Style::default().fg(theme.sha).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(format!("  {} UTC", format_datetime(detail.date)), dim),
+                ]),
+                Line::from(vec![
+                    Span::styled(detail.author.clone(), Style::default().fg(theme.info)),
+                    Span::styled(format!(" <{}>", detail.email), dim),
+                ]),
+                Line::from(""),
+            ];
+            lines.extend(message_lines(&detail.message));
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{} files ", stats.files_changed),
+                    Style::default().fg(theme.text_muted),
+                ),
+                Span::styled(
+                    format!("+{}", stats.insertions),
+                    Style::default().fg(theme.success),
+                ),
+                Span::styled(
+                    format!(" -{}", stats.deletions),
+                    Style::default().fg(theme.error),
+                ),
+            ]));
+            lines.extend(detail.files.iter().map(|file| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:>6}", format!("+{}", file.insertions)),
+                        Style::default().fg(theme.success),
+                    ),
+                    Span::styled(
+                        format!("{:>6}  ", format!("-{}", file.deletions)),
+                        Style::default().fg(theme.error),
+                    ),
+                    Span::styled(file.path.clone(), Style::default().fg(theme.text)),
+                ])
+            }));
+            lines
+        }
+        _ => match commits.iter().find(|commit| commit.sha == pane.id) {
+            Some(commit) => {
+                let mut lines = vec![
+                    Line::from(vec![
+                        Span::styled(
+                            commit.sha.clone(),
+                            Style::default().fg(theme.sha).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(format!("  {} UTC", format_datetime(commit.date)), dim),
+                    ]),
+                    Line::styled(commit.author.clone(), Style::default().fg(theme.info)),
+                    Line::from(""),
+                ];
+                lines.extend(message_lines(&commit.message));
+                lines.push(Line::from(""));
+                lines.push(Line::styled("Loading changed files...", dim));
+                lines
+            }
+            None => vec![Line::styled("Loading commit...", dim)],
+        },
+    };
+
+    let title = format!(" Commit {} ", &pane.id[..7.min(pane.id.len())]);
+    render_pane(frame, area, title, text, pane.scroll, theme);
+}
+
+/// Render a scrollable pane of `text` centered over `area`
+fn render_pane(
+    frame: &mut Frame,
+    area: Rect,
+    title: String,
+    text: Vec<Line>,
+    scroll: u16,
+    theme: &Theme,
+) {
+    let width = area.width.saturating_sub(4).min(100);
+    let height = area.height.saturating_sub(2);
+    let pane_area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    frame.render_widget(Clear, pane_area);
+
+    let dim = Style::default().fg(theme.text_dim);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(title)
+        .title_style(Style::default().fg(theme.text))
+        .title_bottom(Line::styled(" j/k scroll · q/esc close ", dim).right_aligned())
+        .style(Style::default().bg(theme.bg_dark));
+    let paragraph = Paragraph::new(text)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0));
+    frame.render_widget(paragraph, pane_area);
+}
+
 /// Render the changelog help bar
 fn render_changelog_help_bar(
     frame: &mut Frame,
     cs: &ChangelogState,
     status_message: Option<&StatusMessage>,
     area: Rect,
+    theme: &Theme,
 ) {
-    let shortcuts = [("j/k", "nav"), ("space", "lock"), ("q/esc", "back")];
+    let shortcuts: &[(&str, &str)] = match cs.view {
+        ChangelogView::Commits => &[
+            ("j/k", "nav"),
+            ("enter", "detail"),
+            ("space", "lock"),
+            ("a", "authors"),
+            ("t", "tags"),
+            ("r", "release"),
+            ("o", "open PR"),
+            ("?", "help"),
+            ("q/esc", "back"),
+        ],
+        ChangelogView::Authors => &[
+            ("j/k", "nav"),
+            ("a", "commits"),
+            ("?", "help"),
+            ("q/esc", "back"),
+        ],
+        ChangelogView::Tags => &[
+            ("j/k", "nav"),
+            ("space", "lock"),
+            ("r", "release"),
+            ("t", "commits"),
+            ("?", "help"),
+            ("q/esc", "back"),
+        ],
+    };
 
     let mut spans: Vec<Span> = shortcuts
         .iter()
         .flat_map(|(key, desc)| {
             vec![
-                Span::styled(*key, Style::default().fg(theme::KEY_HINT)),
-                Span::styled(format!(" {} ", desc), Style::default().fg(theme::TEXT_DIM)),
+                Span::styled(*key, Style::default().fg(theme.key_hint)),
+                Span::styled(format!(" {} ", desc), Style::default().fg(theme.text_dim)),
             ]
         })
         .collect();
 
     if !cs.data.commits.is_empty() {
-        let ahead = cs.data.commits_ahead();
+        let ahead = match cs.data.commits_ahead_count() {
+            CommitCount::Exact(n) => format!("+{} new", format_count(n)),
+            CommitCount::AtLeast(n) => format!("{}+ new", format_count(n)),
+        };
         let behind = cs.data.commits_behind();
 
-        spans.push(Span::styled(" | ", Style::default().fg(theme::TEXT_DIM)));
-        spans.push(Span::styled(
-            format!("+{} new", ahead),
-            Style::default().fg(theme::SUCCESS),
-        ));
-        spans.push(Span::styled(" 🔒 ", Style::default().fg(theme::WARNING)));
+        spans.push(Span::styled(" | ", Style::default().fg(theme.text_dim)));
+        spans.push(Span::styled(ahead, Style::default().fg(theme.success)));
+        spans.push(Span::styled(" 🔒 ", Style::default().fg(theme.warning)));
         spans.push(Span::styled(
             format!("{} older", behind),
-            Style::default().fg(theme::TEXT_MUTED),
+            Style::default().fg(theme.text_muted),
         ));
+
+        if let Some(filter) = cs.data.path_filter() {
+            spans.push(Span::styled(
+                format!(
+                    " | {} of {} touch {}",
+                    cs.data.commits_ahead(),
+                    filter.total,
+                    filter.paths.join(", ")
+                ),
+                Style::default().fg(theme.info),
+            ));
+        }
+
+        if let Some(limit) = cs.data.truncated_at() {
+            spans.push(Span::styled(
+                format!(" | showing first {}", limit),
+                Style::default().fg(theme.warning),
+            ));
+        }
     }
 
     if let Some(msg) = status_message {
         let color = match msg.level {
-            StatusLevel::Info => theme::INFO,
-            StatusLevel::Success => theme::SUCCESS,
-            StatusLevel::Warning => theme::WARNING,
-            StatusLevel::Error => theme::ERROR,
+            StatusLevel::Info => theme.info,
+            StatusLevel::Success => theme.success,
+            StatusLevel::Warning => theme.warning,
+            StatusLevel::Error => theme.error,
         };
         spans.push(Span::styled(
             format!(" | {}", msg.text),
@@ -162,14 +662,14 @@ fn render_changelog_help_bar(
     let help = Paragraph::new(Line::from(spans)).block(
         Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme::BORDER)),
+            .border_style(Style::default().fg(theme.border)),
     );
 
     frame.render_widget(help, area);
 }
 
 /// Render the confirmation dialog
-fn render_confirm_dialog(frame: &mut Frame, cs: &ChangelogState, area: Rect) {
+fn render_confirm_dialog(frame: &mut Frame, cs: &ChangelogState, area: Rect, theme: &Theme) {
     let Some(target) = cs.lock_target() else {
         return;
     };
@@ -184,7 +684,7 @@ fn render_confirm_dialog(frame: &mut Frame, cs: &ChangelogState, area: Rect) {
     let x = (area.width.saturating_sub(dialog_width)) / 2;
     let y = (area.height.saturating_sub(dialog_height)) / 2;
 
-    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height).intersection(area);
 
     frame.render_widget(Clear, dialog_area);
 
@@ -192,39 +692,39 @@ fn render_confirm_dialog(frame: &mut Frame, cs: &ChangelogState, area: Rect) {
 
     let text = vec![
         Line::from(vec![
-            Span::styled("Lock ", Style::default().fg(theme::TEXT)),
+            Span::styled("Lock ", Style::default().fg(theme.text)),
             Span::styled(
                 cs.input.name(),
                 Style::default()
-                    .fg(theme::ACCENT)
+                    .fg(theme.accent)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(" to ", Style::default().fg(theme::TEXT)),
+            Span::styled(" to ", Style::default().fg(theme.text)),
             Span::styled(
                 commit.short_sha(),
-                Style::default().fg(theme::SHA).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.sha).add_modifier(Modifier::BOLD),
             ),
-            Span::styled("?", Style::default().fg(theme::TEXT)),
+            Span::styled("?", Style::default().fg(theme.text)),
         ]),
         Line::from(""),
         Line::from(Span::styled(
             msg_preview,
-            Style::default().fg(theme::TEXT_DIM),
+            Style::default().fg(theme.text_dim),
         )),
         Line::from(""),
         Line::from(vec![
-            Span::styled("y", Style::default().fg(theme::SUCCESS)),
-            Span::styled(" confirm  ", Style::default().fg(theme::TEXT_DIM)),
-            Span::styled("n/q", Style::default().fg(theme::ERROR)),
-            Span::styled(" cancel", Style::default().fg(theme::TEXT_DIM)),
+            Span::styled("y", Style::default().fg(theme.success)),
+            Span::styled(" confirm  ", Style::default().fg(theme.text_dim)),
+            Span::styled("n/q", Style::default().fg(theme.error)),
+            Span::styled(" cancel", Style::default().fg(theme.text_dim)),
         ]),
     ];
 
     let dialog = Paragraph::new(text).alignment(Alignment::Center).block(
         Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme::ACCENT))
-            .style(Style::default().bg(theme::BG_DARK)),
+            .border_style(Style::default().fg(theme.accent))
+            .style(Style::default().bg(theme.bg_dark)),
     );
 
     frame.render_widget(dialog, dialog_area);
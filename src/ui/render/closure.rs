@@ -0,0 +1,132 @@
+//! Package changes rendering
+
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table},
+    Frame,
+};
+
+use crate::app::state::ClosureDiffState;
+use crate::model::{StatusLevel, StatusMessage};
+use crate::ui::theme::Theme;
+
+/// Render the package changes of the last nixpkgs update
+pub fn render_closure_diff(
+    frame: &mut Frame,
+    view: &mut ClosureDiffState,
+    status_message: Option<&StatusMessage>,
+    theme: &Theme,
+) {
+    let area = frame.area();
+    let chunks = Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).split(area);
+
+    render_changes_table(frame, view, chunks[0], theme);
+    render_closure_diff_help_bar(frame, status_message, chunks[1], theme);
+}
+
+/// Render one row per package whose version changed
+fn render_changes_table(frame: &mut Frame, view: &mut ClosureDiffState, area: Rect, theme: &Theme) {
+    let changes = view.diff.version_changes();
+    let size_only = view.diff.changes.len() - changes.len();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title(format!(
+            " {}: {} version change(s), {} size-only ",
+            view.diff.installable,
+            changes.len(),
+            size_only
+        ))
+        .title_style(Style::default().fg(theme.text));
+
+    if changes.is_empty() {
+        let msg = Paragraph::new("No package changed version")
+            .style(Style::default().fg(theme.text_muted))
+            .alignment(Alignment::Center)
+            .block(block);
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    let rows: Vec<Row> = changes
+        .iter()
+        .map(|change| {
+            let (before, after, color) = match (change.before.is_empty(), change.after.is_empty()) {
+                (true, _) => ("added".to_string(), change.after.join(", "), theme.success),
+                (_, true) => (change.before.join(", "), "removed".to_string(), theme.error),
+                _ => (change.before.join(", "), change.after.join(", "), theme.sha),
+            };
+            Row::new(vec![
+                Span::styled(change.name.as_str(), Style::default().fg(theme.text)),
+                Span::styled(before, Style::default().fg(theme.text_muted)),
+                Span::styled(after, Style::default().fg(color)),
+                Span::styled(
+                    change.size.as_deref().unwrap_or(""),
+                    Style::default().fg(theme.text_dim),
+                ),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Min(20),
+        Constraint::Length(24),
+        Constraint::Length(24),
+        Constraint::Length(14),
+    ];
+    let header =
+        Row::new(["PACKAGE", "BEFORE", "AFTER", "SIZE"]).style(Style::default().fg(theme.text_dim));
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .bg(theme.bg_highlight)
+                .fg(theme.cursor)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    frame.render_stateful_widget(table, area, &mut view.table_state);
+}
+
+/// Render the package changes help bar
+fn render_closure_diff_help_bar(
+    frame: &mut Frame,
+    status_message: Option<&StatusMessage>,
+    area: Rect,
+    theme: &Theme,
+) {
+    let shortcuts = [("j/k", "nav"), ("q/esc", "back")];
+    let mut spans: Vec<Span> = shortcuts
+        .iter()
+        .flat_map(|(key, desc)| {
+            vec![
+                Span::styled(*key, Style::default().fg(theme.key_hint)),
+                Span::styled(format!(" {} ", desc), Style::default().fg(theme.text_dim)),
+            ]
+        })
+        .collect();
+
+    if let Some(msg) = status_message {
+        let color = match msg.level {
+            StatusLevel::Info => theme.info,
+            StatusLevel::Success => theme.success,
+            StatusLevel::Warning => theme.warning,
+            StatusLevel::Error => theme.error,
+        };
+        spans.push(Span::styled(
+            format!(" | {}", msg.text),
+            Style::default().fg(color),
+        ));
+    }
+
+    let help = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border)),
+    );
+
+    frame.render_widget(help, area);
+}
@@ -1,37 +1,76 @@
 //! Common rendering utilities
 
 use ratatui::{
-    layout::{Alignment, Constraint, Layout},
+    layout::{Alignment, Constraint, Layout, Rect},
     style::Style,
     text::{Line, Span},
-    widgets::Paragraph,
+    widgets::{Clear, Paragraph, Wrap},
     Frame,
 };
 
-use crate::ui::theme;
+use crate::model::{StatusLevel, Toasts};
+use crate::ui::theme::Theme;
+use crate::util::text::truncate_with_ellipsis;
 
 /// Spinner animation frames
 const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
+/// Smallest terminal the views are laid out for
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 10;
+
+/// Whether `area` is below the minimum size, where fixed layouts would overlap
+pub fn is_too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+/// Render the screen shown instead of a view when the terminal is too small
+pub fn render_too_small(frame: &mut Frame, theme: &Theme) {
+    let area = frame.area();
+
+    let text = vec![
+        Line::from(Span::styled(
+            "Terminal too small",
+            Style::default().fg(theme.warning),
+        )),
+        Line::from(Span::styled(
+            format!(
+                "{}x{}, need {}x{}",
+                area.width, area.height, MIN_WIDTH, MIN_HEIGHT
+            ),
+            Style::default().fg(theme.text_dim),
+        )),
+    ];
+
+    // Center vertically when there is room; otherwise start at the top.
+    let top = area.height.saturating_sub(text.len() as u16) / 2;
+    let text_area = Rect::new(area.x, area.y + top, area.width, area.height - top);
+
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, text_area);
+}
+
 /// Get the current spinner frame based on tick count
 pub fn get_spinner_frame(tick: u64) -> &'static str {
     SPINNER_FRAMES[(tick as usize / 2) % SPINNER_FRAMES.len()]
 }
 
 /// Render loading screen
-pub fn render_loading(frame: &mut Frame, message: &str, tick_count: u64) {
+pub fn render_loading(frame: &mut Frame, message: &str, tick_count: u64, theme: &Theme) {
     let area = frame.area();
     let spinner = get_spinner_frame(tick_count);
 
     let text = vec![
         Line::from(vec![
-            Span::styled(spinner, Style::default().fg(theme::ACCENT)),
-            Span::styled(format!(" {}", message), Style::default().fg(theme::TEXT)),
+            Span::styled(spinner, Style::default().fg(theme.accent)),
+            Span::styled(format!(" {}", message), Style::default().fg(theme.text)),
         ]),
         Line::from(""),
         Line::from(Span::styled(
             "Press q or Ctrl+C to cancel",
-            Style::default().fg(theme::TEXT_DIM),
+            Style::default().fg(theme.text_dim),
         )),
     ];
 
@@ -48,18 +87,18 @@ pub fn render_loading(frame: &mut Frame, message: &str, tick_count: u64) {
 }
 
 /// Render error screen
-pub fn render_error(frame: &mut Frame, error: &str) {
+pub fn render_error(frame: &mut Frame, error: &str, theme: &Theme) {
     let area = frame.area();
 
     let text = vec![
         Line::from(Span::styled(
             format!("Error: {}", error),
-            Style::default().fg(theme::ERROR),
+            Style::default().fg(theme.error),
         )),
         Line::from(""),
         Line::from(Span::styled(
             "Press any key to exit",
-            Style::default().fg(theme::TEXT_DIM),
+            Style::default().fg(theme.text_dim),
         )),
     ];
 
@@ -74,3 +113,85 @@ pub fn render_error(frame: &mut Frame, error: &str) {
 
     frame.render_widget(paragraph, chunks[1]);
 }
+
+/// Height of the help bar the toasts stack above
+const HELP_BAR_HEIGHT: u16 = 3;
+
+/// Widest a toast grows before its text is cut short
+const MAX_TOAST_WIDTH: u16 = 60;
+
+/// Render earlier status messages stacked above the help bar, newest lowest
+///
+/// The newest message stays in the help bar itself; these are the ones it
+/// would otherwise have replaced before they were read.
+pub fn render_toasts(frame: &mut Frame, toasts: &Toasts, theme: &Theme) {
+    let area = frame.area();
+    let width = area.width.min(MAX_TOAST_WIDTH);
+    let mut bottom = area.bottom().saturating_sub(HELP_BAR_HEIGHT);
+
+    for msg in toasts.earlier().rev() {
+        if bottom <= area.top() {
+            break;
+        }
+        bottom -= 1;
+        let color = match msg.level {
+            StatusLevel::Info => theme.info,
+            StatusLevel::Success => theme.success,
+            StatusLevel::Warning => theme.warning,
+            StatusLevel::Error => theme.error,
+        };
+        let text = truncate_with_ellipsis(&msg.text, usize::from(width.saturating_sub(2)));
+        let line = Line::from(Span::styled(
+            format!(" {text} "),
+            Style::default().fg(color),
+        ));
+        let row = Rect::new(area.right() - width, bottom, width, 1);
+        frame.render_widget(Clear, row);
+        frame.render_widget(Paragraph::new(line).alignment(Alignment::Right), row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::StatusMessage;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn detects_terminals_below_minimum() {
+        assert!(!is_too_small(Rect::new(0, 0, MIN_WIDTH, MIN_HEIGHT)));
+        assert!(is_too_small(Rect::new(0, 0, MIN_WIDTH - 1, 50)));
+        assert!(is_too_small(Rect::new(0, 0, 200, MIN_HEIGHT - 1)));
+    }
+
+    #[test]
+    fn too_small_screen_renders_in_tiny_terminals() {
+        for (width, height) in [(1, 1), (12, 3), (39, 9)] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal
+                .draw(|frame| render_too_small(frame, &Theme::default()))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn earlier_toasts_stack_above_the_help_bar() {
+        let mut toasts = Toasts::default();
+        toasts.push(StatusMessage::error("Failed to load nixpkgs"));
+        toasts.push(StatusMessage::success("Updated home-manager"));
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|frame| render_toasts(frame, &toasts, &Theme::default()))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = buffer
+            .content()
+            .chunks(80)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect();
+        assert!(rows[16].contains("Failed to load nixpkgs"));
+        assert!(!rows.iter().any(|row| row.contains("Updated home-manager")));
+    }
+}
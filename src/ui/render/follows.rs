@@ -0,0 +1,187 @@
+//! Follows graph rendering
+
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::state::FollowsGraphState;
+use crate::model::{InputTree, StatusLevel, StatusMessage};
+use crate::ui::theme::Theme;
+
+/// Render the follows graph view
+pub fn render_follows_graph(
+    frame: &mut Frame,
+    graph: &FollowsGraphState,
+    status_message: Option<&StatusMessage>,
+    theme: &Theme,
+) {
+    let area = frame.area();
+    let suggestion_lines: usize = graph
+        .duplicates
+        .iter()
+        .map(|duplicate| duplicate.suggestions.len() + 1)
+        .sum();
+    let suggestions_height = if suggestion_lines == 0 {
+        0
+    } else {
+        u16::try_from(suggestion_lines + 2)
+            .unwrap_or(u16::MAX)
+            .min(area.height / 2)
+    };
+    let chunks = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Length(suggestions_height),
+        Constraint::Length(3),
+    ])
+    .split(area);
+
+    render_tree(frame, graph, chunks[0], theme);
+    if suggestions_height > 0 {
+        render_suggestions(frame, graph, chunks[1], theme);
+    }
+    render_follows_help_bar(frame, status_message, chunks[2], theme);
+}
+
+/// Render the sources locked more than once and the `follows` lines that
+/// would leave one copy of each
+fn render_suggestions(frame: &mut Frame, graph: &FollowsGraphState, area: Rect, theme: &Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.warning))
+        .title(" Duplicate inputs: add to flake.nix ")
+        .title_style(Style::default().fg(theme.warning));
+
+    let lines: Vec<Line> = graph
+        .duplicates
+        .iter()
+        .flat_map(|duplicate| {
+            let heading = Line::from(vec![
+                Span::styled(
+                    duplicate.source.clone(),
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(" locked {} times", duplicate.copies),
+                    Style::default().fg(theme.text_muted),
+                ),
+            ]);
+            std::iter::once(heading).chain(duplicate.suggestions.iter().map(|suggestion| {
+                Line::styled(format!("  {}", suggestion), Style::default().fg(theme.sha))
+            }))
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Render every input as an indented tree, marking what each one follows
+fn render_tree(frame: &mut Frame, graph: &FollowsGraphState, area: Rect, theme: &Theme) {
+    let (follows, own) = graph.follows_counts();
+    let title = if graph.trees.is_some() {
+        format!(
+            " Follows graph: {} transitive inputs, {} follow, {} locked separately ",
+            follows + own,
+            follows,
+            own
+        )
+    } else {
+        " Follows graph ".to_string()
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title(title)
+        .title_style(Style::default().fg(theme.text));
+
+    let Some(trees) = graph.trees.as_deref() else {
+        let msg = Paragraph::new("Reading flake.lock...")
+            .style(Style::default().fg(theme.text_muted))
+            .alignment(Alignment::Center)
+            .block(block);
+        frame.render_widget(msg, area);
+        return;
+    };
+
+    let lines: Vec<Line> = InputTree::lines(trees)
+        .into_iter()
+        .map(|line| {
+            let tree = line.tree;
+            let mut name_style = Style::default().fg(theme.text);
+            if line.depth == 0 {
+                name_style = name_style.add_modifier(Modifier::BOLD);
+            }
+            let mut spans = vec![
+                Span::styled(line.prefix, Style::default().fg(theme.border)),
+                Span::styled(tree.name.clone(), name_style),
+            ];
+            if let Some(path) = &tree.follows {
+                spans.push(Span::styled(
+                    format!(" → follows {}", path.join("/")),
+                    Style::default().fg(theme.success),
+                ));
+            } else {
+                if let Some(rev) = &tree.rev {
+                    let short = rev.get(..7).unwrap_or(rev);
+                    spans.push(Span::styled(
+                        format!(" {}", short),
+                        Style::default().fg(theme.sha),
+                    ));
+                }
+                if line.depth > 0 {
+                    spans.push(Span::styled(
+                        " own lock",
+                        Style::default().fg(theme.warning),
+                    ));
+                }
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines).block(block).scroll((graph.scroll, 0));
+    frame.render_widget(paragraph, area);
+}
+
+/// Render the follows graph help bar
+fn render_follows_help_bar(
+    frame: &mut Frame,
+    status_message: Option<&StatusMessage>,
+    area: Rect,
+    theme: &Theme,
+) {
+    let shortcuts = [("j/k", "scroll"), ("q/esc", "back")];
+    let mut spans: Vec<Span> = shortcuts
+        .iter()
+        .flat_map(|(key, desc)| {
+            vec![
+                Span::styled(*key, Style::default().fg(theme.key_hint)),
+                Span::styled(format!(" {} ", desc), Style::default().fg(theme.text_dim)),
+            ]
+        })
+        .collect();
+
+    if let Some(msg) = status_message {
+        let color = match msg.level {
+            StatusLevel::Info => theme.info,
+            StatusLevel::Success => theme.success,
+            StatusLevel::Warning => theme.warning,
+            StatusLevel::Error => theme.error,
+        };
+        spans.push(Span::styled(
+            format!(" | {}", msg.text),
+            Style::default().fg(color),
+        ));
+    }
+
+    let help = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border)),
+    );
+
+    frame.render_widget(help, area);
+}
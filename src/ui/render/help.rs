@@ -0,0 +1,69 @@
+//! Key bindings overlay rendering
+
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::app::keymap::{help_sections, HelpSection};
+use crate::ui::theme::Theme;
+
+/// Render every view's key bindings over the whole screen, scrolled by
+/// `scroll` lines. `scroll` is clamped so the last binding stays in view.
+pub fn render_help(frame: &mut Frame, scroll: &mut u16, theme: &Theme) {
+    let area = frame.area();
+    frame.render_widget(Clear, area);
+
+    let lines = help_lines(&help_sections(), theme);
+    let height = area.height.saturating_sub(2);
+    let max = u16::try_from(lines.len())
+        .unwrap_or(u16::MAX)
+        .saturating_sub(height);
+    *scroll = (*scroll).min(max);
+
+    let dim = Style::default().fg(theme.text_dim);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(" Key bindings ")
+        .title_style(Style::default().fg(theme.text))
+        .title_bottom(Line::styled(" j/k scroll · ?/q/esc close ", dim).right_aligned())
+        .style(Style::default().bg(theme.bg_dark));
+    frame.render_widget(
+        Paragraph::new(lines).block(block).scroll((*scroll, 0)),
+        area,
+    );
+}
+
+fn help_lines(sections: &[HelpSection], theme: &Theme) -> Vec<Line<'static>> {
+    let width = sections
+        .iter()
+        .flat_map(|section| &section.rows)
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = Vec::new();
+    for section in sections {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::styled(
+            section.title,
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ));
+        lines.extend(section.rows.iter().map(|(keys, description)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("  {:<width$}  ", keys, width = width),
+                    Style::default().fg(theme.key_hint),
+                ),
+                Span::styled(*description, Style::default().fg(theme.text)),
+            ])
+        }));
+    }
+    lines
+}
@@ -1,150 +1,688 @@
 //! List view rendering
 
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+
 use ratatui::{
     layout::{Constraint, Layout, Rect},
     style::{Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Row, Table},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap},
     Frame,
 };
 
-use crate::app::state::ListState;
-use crate::model::{FlakeInput, StatusLevel, StatusMessage, UpdateStatus};
-use crate::ui::theme;
+use crate::app::command::COMMANDS;
+use crate::app::state::{Announcements, CommandLine, FlakeTabs, InputSearch, ListRow, ListState};
+use crate::model::{FlakeInput, StatusLevel, StatusMessage, TreeLine, UpdateStatus};
+use crate::ui::columns::{ListColumn, ListColumns};
+use crate::ui::theme::Theme;
+use crate::util::format::format_datetime;
 use crate::util::text::truncate_with_ellipsis;
-use crate::util::time::format_relative;
+use crate::util::time::{format_relative, format_relative_datetime, format_relative_short};
 
 use super::common::get_spinner_frame;
+use super::onboarding::render_empty_flake;
+use super::tasks::format_elapsed;
+
+/// Below this width the list switches to the compact two-line layout
+const COMPACT_WIDTH: u16 = 80;
+
+/// Widest the NAME and URL columns grow to fit their longest value
+const MAX_NAME_WIDTH: usize = 40;
+const MAX_URL_WIDTH: usize = 60;
+const MAX_VERSION_WIDTH: usize = 24;
+
+/// Transitive inputs locked longer ago than this are marked as stale
+const STALE_AFTER_SECS: i64 = 180 * 24 * 60 * 60;
+
+/// Announcement lines shown below the list in accessible mode
+const ANNOUNCEMENT_LINES: u16 = 4;
+
+/// Widest the input detail pane grows, taking at most half the screen
+const DETAIL_WIDTH: u16 = 60;
+
+/// App state the list view is drawn with, besides the list itself
+pub struct ListContext<'a> {
+    pub columns: &'a ListColumns,
+    pub status_message: Option<&'a StatusMessage>,
+    pub tick_count: u64,
+    /// Recent announcements, set in accessible mode
+    pub announcements: Option<&'a Announcements>,
+    /// Open flakes, set when there is more than one
+    pub tabs: Option<&'a FlakeTabs>,
+    pub theme: &'a Theme,
+}
+
+/// Render the list view.
+///
+/// With `announcements` (accessible mode) the view drops borders and
+/// spinners, and shows the latest announcements between the table and the
+/// help line. With `tabs` a line above the table names each open flake.
+pub fn render_list(frame: &mut Frame, list: &mut ListState, context: &ListContext) {
+    let ListContext {
+        columns,
+        status_message,
+        tick_count,
+        announcements,
+        tabs,
+        theme,
+    } = *context;
+    let mut area = frame.area();
+    if let Some(tabs) = tabs {
+        let chunks = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).split(area);
+        render_tab_bar(frame, tabs, list, chunks[0], theme);
+        area = chunks[1];
+    }
+    let Some(announcements) = announcements else {
+        let chunks = Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).split(area);
+        render_table_and_detail(frame, list, columns, chunks[0], tick_count, false, theme);
+        render_help_bar(
+            frame,
+            list,
+            status_message,
+            chunks[1],
+            tick_count,
+            false,
+            theme,
+        );
+        return;
+    };
+
+    let chunks = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Length(ANNOUNCEMENT_LINES),
+        Constraint::Length(1),
+    ])
+    .split(area);
+    render_table_and_detail(frame, list, columns, chunks[0], tick_count, true, theme);
+    let lines: Vec<Line> = announcements
+        .recent(ANNOUNCEMENT_LINES as usize)
+        .map(|line| Line::styled(line, Style::default().fg(theme.text)))
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[1]);
+    render_help_bar(
+        frame,
+        list,
+        status_message,
+        chunks[2],
+        tick_count,
+        true,
+        theme,
+    );
+}
+
+/// Render one tab per open flake, named after its directory, with the
+/// number of inputs that have updates
+fn render_tab_bar(
+    frame: &mut Frame,
+    tabs: &FlakeTabs,
+    shown: &ListState,
+    area: Rect,
+    theme: &Theme,
+) {
+    let mut spans = vec![Span::raw(" ")];
+    for (idx, tab) in tabs.tabs().iter().enumerate() {
+        let active = idx == tabs.active();
+        let list = if active {
+            Some(shown)
+        } else {
+            tab.list.as_ref()
+        };
+        let path = list.map_or(tab.path.as_path(), |list| list.flake.path.as_path());
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into(),
+        );
+        let behind = list.map_or(0, |list| {
+            list.update_statuses
+                .values()
+                .filter(|status| matches!(status, UpdateStatus::Behind(_)))
+                .count()
+        });
+        let label = match behind {
+            0 => format!(" {} ", name),
+            n => format!(" {} +{} ", name, n),
+        };
+        let style = if active {
+            Style::default()
+                .fg(theme.bg_dark)
+                .bg(theme.accent)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.text_muted)
+        };
+        spans.push(Span::styled(label, style));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::styled(
+        "tab/shift+tab switch",
+        Style::default().fg(theme.text_dim),
+    ));
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
 
-/// Render the list view
-pub fn render_list(
+/// Render the input table, with the detail pane beside it when open
+fn render_table_and_detail(
     frame: &mut Frame,
     list: &mut ListState,
-    status_message: Option<&StatusMessage>,
+    columns: &ListColumns,
+    area: Rect,
+    tick_count: u64,
+    plain: bool,
+    theme: &Theme,
+) {
+    if !list.detail || list.flake.inputs.is_empty() {
+        render_input_table(frame, list, columns, area, tick_count, plain, theme);
+        return;
+    }
+    let chunks = Layout::horizontal([
+        Constraint::Min(0),
+        Constraint::Length(DETAIL_WIDTH.min(area.width / 2)),
+    ])
+    .split(area);
+    render_input_table(frame, list, columns, chunks[0], tick_count, plain, theme);
+    render_input_detail(frame, list, chunks[1], plain, theme);
+}
+
+/// Render the details of the input under the cursor
+fn render_input_detail(
+    frame: &mut Frame,
+    list: &ListState,
+    area: Rect,
+    plain: bool,
+    theme: &Theme,
+) {
+    let Some(input) = list
+        .current_index()
+        .and_then(|idx| list.flake.inputs.get(idx))
+    else {
+        return;
+    };
+    let mut lines = Vec::new();
+    let mut field = |label: &str, value: String| {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<11}", label),
+                Style::default().fg(theme.text_dim),
+            ),
+            Span::styled(value, Style::default().fg(theme.text)),
+        ]));
+    };
+
+    if plain {
+        field("name", input.name().to_string());
+    }
+    field("type", input.type_display().to_string());
+    if let FlakeInput::Git(git) = input {
+        field("forge", git.repo().forge_name().to_string());
+        if let Some(owner_repo) = git.repo().owner_repo() {
+            field("repository", owner_repo);
+        }
+        if let Some(host) = git.repo().host() {
+            field("host", host.to_string());
+        }
+        field(
+            "branch",
+            git.reference().unwrap_or("default branch").to_string(),
+        );
+    }
+    if let Some(url) = input.url() {
+        field("url", url.to_string());
+    }
+    if let Some(rev) = input.locked_rev() {
+        field("rev", rev.to_string());
+    }
+    if let Some(modified) = input
+        .last_modified()
+        .filter(|&ts| ts > 0)
+        .and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0))
+    {
+        field(
+            "modified",
+            format!(
+                "{} UTC ({})",
+                format_datetime(modified),
+                format_relative_datetime(modified)
+            ),
+        );
+    }
+    if let Some(version) = list.versions.get(input.name()) {
+        field("version", version.clone());
+    }
+    if let FlakeInput::Follows(follows) = input {
+        field("follows", follows.target());
+    }
+    let followers = list.flake.followers(input.name());
+    if !followers.is_empty() {
+        field("followed by", followers.join(", "));
+    }
+    if let Some(path) = list.clone_paths.get(input.name()) {
+        field("clone", path.display().to_string());
+    }
+    if let Some(UpdateStatus::Error(err)) = list.update_statuses.get(input.name()) {
+        lines.push(Line::from(""));
+        lines.extend(
+            err.lines()
+                .map(|line| Line::styled(line.to_string(), Style::default().fg(theme.error))),
+        );
+    }
+
+    let block = if plain {
+        Block::default()
+    } else {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .title(format!(" {} ", input.name()))
+            .title_style(Style::default().fg(theme.text))
+    };
+    let detail = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    frame.render_widget(detail, area);
+}
+
+/// Render the input table, without borders or title when `plain`
+fn render_input_table(
+    frame: &mut Frame,
+    list: &mut ListState,
+    columns: &ListColumns,
+    area: Rect,
     tick_count: u64,
+    plain: bool,
+    theme: &Theme,
 ) {
-    let area = frame.area();
-    let chunks = Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).split(area);
+    if list.flake.inputs.is_empty() {
+        render_empty_flake(frame, &list.flake.path, area, theme);
+        return;
+    }
+
+    // Narrow terminals get two lines per input instead of six columns.
+    let compact = area.width < COMPACT_WIDTH;
+    let row_height: u16 = if compact { 2 } else { 1 };
+    let borders = if plain { 0 } else { 2 };
+    let chrome = if compact { borders } else { borders + 1 };
+
+    // Only build rows for the visible window so large flakes stay cheap to draw.
+    let visible = (area.height.saturating_sub(chrome) / row_height) as usize;
+    let list_rows = list.rows();
+    let selected = list.current_index().and_then(|current| {
+        list_rows
+            .iter()
+            .position(|row| matches!(row, ListRow::Input(idx) if *idx == current))
+            .map(|row| row + list.transitive_row())
+    });
+    let (start, end) = visible_window(
+        list.table_state.offset(),
+        selected.unwrap_or(0),
+        list_rows.len(),
+        visible,
+    );
 
-    render_input_table(frame, list, chunks[0], tick_count);
-    render_help_bar(frame, list, status_message, chunks[1], tick_count);
+    let rows: Vec<Row> = list_rows[start..end]
+        .iter()
+        .map(|row| {
+            let cells = match row {
+                ListRow::Input(idx) => {
+                    input_cells(list, &list.flake.inputs[*idx], tick_count, plain, theme)
+                }
+                ListRow::Transitive(line) => transitive_cells(line, theme),
+            };
+            if compact {
+                Row::new(vec![
+                    Text::from(cells.checkbox),
+                    Text::from(vec![
+                        cells.name,
+                        Line::from(vec![cells.rev, Span::raw("  "), cells.updated]),
+                    ]),
+                    Text::from(cells.status),
+                ])
+                .height(2)
+            } else {
+                let mut row = vec![Line::from(cells.checkbox.clone())];
+                row.extend(columns.as_slice().iter().map(|&column| cells.cell(column)));
+                Row::new(row)
+            }
+        })
+        .collect();
+
+    let table = if compact {
+        let widths = [
+            Constraint::Length(4),
+            Constraint::Min(10),
+            Constraint::Length(6),
+        ];
+        Table::new(rows, widths)
+    } else {
+        let widths = column_widths(columns.as_slice(), list);
+        let header = Row::new(
+            std::iter::once(" ").chain(columns.as_slice().iter().map(|column| column.header())),
+        )
+        .style(Style::default().fg(theme.text_dim));
+        Table::new(rows, widths).header(header)
+    };
+
+    let title = list.flake.path.to_string_lossy();
+    let mut block = if plain {
+        Block::default()
+    } else {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .title(format!(" {} ", title))
+            .title_style(Style::default().fg(theme.text))
+    };
+    if !compact && !plain {
+        block = block.title(
+            Line::styled(
+                format!(" refreshed {} ", format_relative_short(list.refreshed_at)),
+                Style::default().fg(theme.text_dim),
+            )
+            .right_aligned(),
+        );
+    }
+    let table = table.block(block).row_highlight_style(
+        Style::default()
+            .bg(theme.bg_highlight)
+            .fg(theme.cursor)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let mut window_state = TableState::default().with_selected(selected.map(|idx| idx - start));
+    frame.render_stateful_widget(table, area, &mut window_state);
+    *list.table_state.offset_mut() = start;
 }
 
-/// Render the input table
-fn render_input_table(frame: &mut Frame, list: &mut ListState, area: Rect, tick_count: u64) {
-    let header = Row::new(vec![" ", "NAME", "TYPE", "REV", "UPDATED", "STATUS"])
-        .style(Style::default().fg(theme::TEXT_DIM));
+/// Styled cells for one input, shared by the wide and compact layouts
+struct InputCells<'a> {
+    checkbox: Span<'static>,
+    name: Line<'a>,
+    kind: Span<'a>,
+    rev: Span<'a>,
+    updated: Span<'static>,
+    status: Span<'static>,
+    url: Span<'a>,
+    version: Span<'a>,
+}
+
+impl<'a> InputCells<'a> {
+    /// The cell for `column` in the wide layout
+    fn cell(&self, column: ListColumn) -> Line<'a> {
+        match column {
+            ListColumn::Name => self.name.clone(),
+            ListColumn::Type => Line::from(self.kind.clone()),
+            ListColumn::Rev => Line::from(self.rev.clone()),
+            ListColumn::Updated => Line::from(self.updated.clone()),
+            ListColumn::Status => Line::from(self.status.clone()),
+            ListColumn::Url => Line::from(self.url.clone()),
+            ListColumn::Version => Line::from(self.version.clone()),
+        }
+    }
+}
 
-    let rows: Vec<Row> = list
-        .flake
+/// Text of `columns` for every input, as the wide layout shows them without
+/// animation, for printing the list view
+pub fn plain_rows(list: &ListState, columns: &ListColumns) -> Vec<Vec<String>> {
+    list.flake
         .inputs
         .iter()
         .map(|input| {
-            let is_selected = list
-                .selected
+            let cells = input_cells(list, input, 0, true, &Theme::default());
+            columns
+                .as_slice()
                 .iter()
-                .any(|name| name.as_str() == input.name());
-            let checkbox = if is_selected { "[x]" } else { "[ ]" };
-            let checkbox_style = if is_selected {
-                Style::default()
-                    .fg(theme::SELECTED)
-                    .add_modifier(Modifier::BOLD)
-            } else {
-                Style::default().fg(theme::TEXT_DIM)
-            };
+                .map(|&column| cells.cell(column).to_string())
+                .collect()
+        })
+        .collect()
+}
 
-            let type_color = match input {
-                FlakeInput::Git(_) => theme::TYPE_GIT,
-                FlakeInput::Path(_) => theme::TYPE_PATH,
-                FlakeInput::Other(_) => theme::TYPE_OTHER,
-            };
+/// Widths for the checkbox and `columns`.
+///
+/// NAME, URL, and VERSION fit their longest value up to a cap; the last
+/// column takes any leftover space.
+fn column_widths(columns: &[ListColumn], list: &ListState) -> Vec<Constraint> {
+    let inputs = &list.flake.inputs;
+    let fit = |widths: &mut dyn Iterator<Item = usize>, header: &str, max: usize| {
+        widths.max().unwrap_or(0).max(header.len()).min(max) as u16
+    };
 
-            let status = list
-                .update_statuses
-                .get(input.name())
-                .cloned()
-                .unwrap_or_default();
+    let mut constraints = vec![Constraint::Length(5)];
+    for (idx, &column) in columns.iter().enumerate() {
+        let width = match column {
+            ListColumn::Name => fit(
+                // The theme only colors the name, so any one measures it
+                &mut inputs
+                    .iter()
+                    .map(|input| name_cell(input, &Theme::default()).width()),
+                column.header(),
+                MAX_NAME_WIDTH,
+            ),
+            ListColumn::Url => fit(
+                &mut inputs
+                    .iter()
+                    .map(|input| input.url().map_or(1, |url| url.chars().count())),
+                column.header(),
+                MAX_URL_WIDTH,
+            ),
+            ListColumn::Version => fit(
+                &mut inputs.iter().map(|input| {
+                    list.versions
+                        .get(input.name())
+                        .map_or(1, |version| version.chars().count())
+                }),
+                column.header(),
+                MAX_VERSION_WIDTH,
+            ),
+            ListColumn::Type => 12,
+            ListColumn::Rev => 10,
+            ListColumn::Updated => 14,
+            ListColumn::Status => 6,
+        };
+        constraints.push(if idx + 1 == columns.len() {
+            Constraint::Min(width)
+        } else {
+            Constraint::Length(width)
+        });
+    }
+    constraints
+}
 
-            let status_display = match &status {
-                UpdateStatus::Checking | UpdateStatus::Updating => {
-                    get_spinner_frame(tick_count).to_string()
-                }
-                _ => status.display(),
-            };
+fn input_cells<'a>(
+    list: &'a ListState,
+    input: &'a FlakeInput,
+    tick_count: u64,
+    plain: bool,
+    theme: &Theme,
+) -> InputCells<'a> {
+    let is_selected = list.selected.contains(input.name());
+    let checkbox = if is_selected { "[x]" } else { "[ ]" };
+    let checkbox_style = if is_selected {
+        Style::default()
+            .fg(theme.selected)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(theme.text_dim)
+    };
 
-            let status_color = match &status {
-                UpdateStatus::Unknown => theme::TEXT_DIM,
-                UpdateStatus::Checking => theme::TEXT_DIM,
-                UpdateStatus::Updating => theme::INFO,
-                UpdateStatus::UpToDate => theme::TEXT_DIM,
-                UpdateStatus::Behind(_) => theme::SUCCESS,
-                UpdateStatus::Error(_) => theme::WARNING,
-            };
+    let type_color = match input {
+        FlakeInput::Git(_) => theme.type_git,
+        FlakeInput::Path(_) => theme.type_path,
+        FlakeInput::Other(_) => theme.type_other,
+        FlakeInput::Follows(_) => theme.text_dim,
+    };
 
-            Row::new(vec![
-                Span::styled(checkbox, checkbox_style),
-                Span::styled(input.name(), Style::default().fg(theme::TEXT)),
-                Span::styled(input.type_display(), Style::default().fg(type_color)),
-                Span::styled(
-                    input.short_rev().unwrap_or("-"),
-                    Style::default().fg(theme::ACCENT),
-                ),
-                Span::styled(
-                    input
-                        .last_modified()
-                        .map(format_relative)
-                        .unwrap_or_else(|| "-".to_string()),
-                    Style::default().fg(theme::TEXT_MUTED),
-                ),
-                Span::styled(status_display, Style::default().fg(status_color)),
-            ])
-        })
-        .collect();
+    let status = list.status_for(input).cloned().unwrap_or_default();
+
+    let status_display = match &status {
+        UpdateStatus::Checking | UpdateStatus::Updating if !plain => {
+            get_spinner_frame(tick_count).to_string()
+        }
+        _ => status.display(),
+    };
+
+    let status_color = match &status {
+        UpdateStatus::Unknown => theme.text_dim,
+        UpdateStatus::Checking => theme.text_dim,
+        UpdateStatus::Queued => theme.text_dim,
+        UpdateStatus::Updating => theme.info,
+        UpdateStatus::Updated => theme.success,
+        UpdateStatus::UpToDate => theme.text_dim,
+        UpdateStatus::Behind(_) => theme.success,
+        UpdateStatus::Error(_) => theme.warning,
+        UpdateStatus::RateLimited(_) => theme.text_dim,
+    };
 
-    let widths = [
-        Constraint::Length(5),
-        Constraint::Length(35),
-        Constraint::Length(12),
-        Constraint::Length(10),
-        Constraint::Length(14),
-        Constraint::Min(6),
+    // Inputs that gained commits since an earlier check stand out
+    let status_style = if list.new_updates.contains(input.name()) {
+        Style::default()
+            .fg(status_color)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(status_color)
+    };
+
+    InputCells {
+        checkbox: Span::styled(checkbox, checkbox_style),
+        name: name_cell(input, theme),
+        kind: Span::styled(input.type_display(), Style::default().fg(type_color)),
+        rev: Span::styled(
+            input.short_rev().unwrap_or("-"),
+            Style::default().fg(theme.accent),
+        ),
+        updated: Span::styled(
+            input
+                .last_modified()
+                .map(format_relative)
+                .unwrap_or_else(|| "-".to_string()),
+            Style::default().fg(theme.text_muted),
+        ),
+        status: Span::styled(status_display, status_style),
+        url: Span::styled(
+            input.url().unwrap_or("-"),
+            Style::default().fg(theme.text_muted),
+        ),
+        version: Span::styled(
+            list.versions.get(input.name()).map_or("-", String::as_str),
+            Style::default().fg(theme.text),
+        ),
+    }
+}
+
+/// Cells for an input that an expanded input declares: its place in the
+/// tree, its own lock, and how old that lock is
+fn transitive_cells<'a>(line: &TreeLine<'a>, theme: &Theme) -> InputCells<'a> {
+    let tree = line.tree;
+    let mut name = vec![
+        Span::styled(line.prefix.clone(), Style::default().fg(theme.border)),
+        Span::styled(tree.name.as_str(), Style::default().fg(theme.text_muted)),
     ];
+    if let Some(path) = &tree.follows {
+        name.push(Span::styled(
+            format!(" → follows {}", path.join("/")),
+            Style::default().fg(theme.text_dim),
+        ));
+    }
 
-    let title = list.flake.path.to_string_lossy();
-    let table = Table::new(rows, widths)
-        .header(header)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(theme::BORDER))
-                .title(format!(" {} ", title))
-                .title_style(Style::default().fg(theme::TEXT)),
-        )
-        .row_highlight_style(
-            Style::default()
-                .bg(theme::BG_HIGHLIGHT)
-                .fg(theme::CURSOR)
-                .add_modifier(Modifier::BOLD),
-        );
+    let stale = tree
+        .last_modified
+        .is_some_and(|modified| Utc::now().timestamp() - modified > STALE_AFTER_SECS);
+    let updated_color = if stale {
+        theme.warning
+    } else {
+        theme.text_muted
+    };
 
-    frame.render_stateful_widget(table, area, &mut list.table_state);
+    InputCells {
+        checkbox: Span::raw(""),
+        name: Line::from(name),
+        kind: Span::raw(""),
+        rev: Span::styled(
+            tree.rev
+                .as_deref()
+                .map_or("", |rev| rev.get(..7).unwrap_or(rev)),
+            Style::default().fg(theme.text_dim),
+        ),
+        updated: Span::styled(
+            tree.last_modified.map(format_relative).unwrap_or_default(),
+            Style::default().fg(updated_color),
+        ),
+        status: Span::styled(
+            if stale { "stale" } else { "" },
+            Style::default().fg(theme.warning),
+        ),
+        url: Span::styled(
+            tree.source.as_deref().unwrap_or(""),
+            Style::default().fg(theme.text_muted),
+        ),
+        version: Span::raw(""),
+    }
 }
 
-/// Render the help bar
+/// Input name, with the followed input for `follows` aliases
+fn name_cell<'a>(input: &'a FlakeInput, theme: &Theme) -> Line<'a> {
+    let name = Span::styled(input.name(), Style::default().fg(theme.text));
+    match input {
+        FlakeInput::Follows(follows) => Line::from(vec![
+            name,
+            Span::styled(
+                format!(" → follows {}", follows.target()),
+                Style::default().fg(theme.text_dim),
+            ),
+        ]),
+        _ => Line::from(name),
+    }
+}
+
+/// Range of rows to draw so `selected` stays inside a window of `height` rows.
+///
+/// Keeps the previous `offset` while the selection is visible, like a
+/// scrolling table would.
+fn visible_window(offset: usize, selected: usize, len: usize, height: usize) -> (usize, usize) {
+    let height = height.max(1);
+    let mut start = offset.min(len.saturating_sub(1));
+    if selected < start {
+        start = selected;
+    } else if selected >= start + height {
+        start = selected + 1 - height;
+    }
+    // Don't leave empty rows below the last input when scrolled to the end.
+    start = start.min(len.saturating_sub(height));
+    (start, (start + height).min(len))
+}
+
+/// Render the help bar, without a border when `plain`
 fn render_help_bar(
     frame: &mut Frame,
     list: &ListState,
     status_message: Option<&StatusMessage>,
     area: Rect,
     tick_count: u64,
+    plain: bool,
+    theme: &Theme,
 ) {
+    if let Some(command_line) = &list.command_line {
+        render_command_line(frame, command_line, area, plain, theme);
+        return;
+    }
+    if let Some(search) = list.search.as_ref().filter(|search| search.editing) {
+        render_search_line(frame, list, search, area, plain, theme);
+        return;
+    }
+
     let shortcuts = [
         ("j/k", "nav"),
         ("space", "select"),
+        ("/", "filter"),
+        ("i", "details"),
         ("u", "update"),
         ("U", "all"),
         ("c", "history"),
         ("r", "refresh"),
+        (":", "command"),
+        ("T", "tasks"),
+        ("?", "help"),
         ("q", "quit"),
     ];
 
@@ -152,16 +690,39 @@ fn render_help_bar(
         .iter()
         .flat_map(|(key, desc)| {
             vec![
-                Span::styled(*key, Style::default().fg(theme::KEY_HINT)),
-                Span::styled(format!(" {} ", desc), Style::default().fg(theme::TEXT_DIM)),
+                Span::styled(*key, Style::default().fg(theme.key_hint)),
+                Span::styled(format!(" {} ", desc), Style::default().fg(theme.text_dim)),
             ]
         })
         .collect();
 
+    if let Some(search) = &list.search {
+        spans.push(Span::styled(
+            format!(
+                " | /{} ({} of {})",
+                search.query,
+                list.shown_count(),
+                list.input_count()
+            ),
+            Style::default().fg(theme.accent),
+        ));
+    }
+
     if !list.selected.is_empty() {
         spans.push(Span::styled(
             format!(" | {} selected", list.selected.len()),
-            Style::default().fg(theme::SELECTED),
+            Style::default().fg(theme.selected),
+        ));
+    }
+
+    if let Some((count, wait)) = list.rate_limit_wait(Instant::now()) {
+        spans.push(Span::styled(
+            format!(
+                " | rate limited: {} check(s) retry in {}",
+                count,
+                format_elapsed(wait)
+            ),
+            Style::default().fg(theme.warning),
         ));
     }
 
@@ -174,20 +735,25 @@ fn render_help_bar(
             let truncated = truncate_with_ellipsis(err, 60);
             spans.push(Span::styled(
                 format!(" | {}", truncated),
-                Style::default().fg(theme::ERROR),
+                Style::default().fg(theme.error),
+            ));
+        } else if let Some(path) = list.clone_paths.get(input.name()) {
+            spans.push(Span::styled(
+                format!(" | checked in clone {}", path.display()),
+                Style::default().fg(theme.text_muted),
             ));
         }
     }
 
     if let Some(msg) = status_message {
         let color = match msg.level {
-            StatusLevel::Info => theme::INFO,
-            StatusLevel::Success => theme::SUCCESS,
-            StatusLevel::Warning => theme::WARNING,
-            StatusLevel::Error => theme::ERROR,
+            StatusLevel::Info => theme.info,
+            StatusLevel::Success => theme.success,
+            StatusLevel::Warning => theme.warning,
+            StatusLevel::Error => theme.error,
         };
         // Add spinner for info messages (indicates in-progress operation)
-        let spinner = if msg.level == StatusLevel::Info {
+        let spinner = if msg.level == StatusLevel::Info && !plain {
             format!("{} ", get_spinner_frame(tick_count))
         } else {
             String::new()
@@ -198,11 +764,289 @@ fn render_help_bar(
         ));
     }
 
-    let help = Paragraph::new(Line::from(spans)).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme::BORDER)),
-    );
+    let mut help = Paragraph::new(Line::from(spans));
+    if !plain {
+        help = help.block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border)),
+        );
+    }
 
     frame.render_widget(help, area);
 }
+
+/// Render the `/` search being typed in place of the help bar
+fn render_search_line(
+    frame: &mut Frame,
+    list: &ListState,
+    search: &InputSearch,
+    area: Rect,
+    plain: bool,
+    theme: &Theme,
+) {
+    let mut spans = vec![
+        Span::styled("/", Style::default().fg(theme.key_hint)),
+        Span::styled(search.query.as_str(), Style::default().fg(theme.text)),
+    ];
+    if !plain {
+        spans.push(Span::styled("█", Style::default().fg(theme.cursor)));
+    }
+    spans.push(Span::styled(
+        format!(
+            "  {} of {} · enter keep · esc clear",
+            list.shown_count(),
+            list.input_count()
+        ),
+        Style::default().fg(theme.text_dim),
+    ));
+
+    let mut prompt = Paragraph::new(Line::from(spans));
+    if !plain {
+        prompt = prompt.block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.accent)),
+        );
+    }
+
+    frame.render_widget(prompt, area);
+}
+
+/// Render the `:` prompt in place of the help bar
+fn render_command_line(
+    frame: &mut Frame,
+    command_line: &CommandLine,
+    area: Rect,
+    plain: bool,
+    theme: &Theme,
+) {
+    let mut spans = vec![
+        Span::styled(":", Style::default().fg(theme.key_hint)),
+        Span::styled(command_line.input.as_str(), Style::default().fg(theme.text)),
+    ];
+    if !plain {
+        spans.push(Span::styled("█", Style::default().fg(theme.cursor)));
+    }
+
+    if let Some(completion) = &command_line.completion {
+        spans.push(Span::raw("  "));
+        for (idx, candidate) in completion.candidates.iter().enumerate() {
+            let style = if idx == completion.index {
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text_dim)
+            };
+            spans.push(Span::styled(format!("{} ", candidate), style));
+        }
+    } else {
+        let name = command_line.input.split_whitespace().next().unwrap_or("");
+        if let Some(spec) = COMMANDS.iter().find(|spec| spec.name == name) {
+            spans.push(Span::styled(
+                format!("  {} — {}", spec.args, spec.description),
+                Style::default().fg(theme.text_dim),
+            ));
+        } else if command_line.input.is_empty() {
+            spans.push(Span::styled(
+                "  tab to list commands",
+                Style::default().fg(theme.text_dim),
+            ));
+        }
+    }
+
+    let mut prompt = Paragraph::new(Line::from(spans));
+    if !plain {
+        prompt = prompt.block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.accent)),
+        );
+    }
+
+    frame.render_widget(prompt, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::InputName;
+    use crate::testing::synthetic_flake;
+    use ratatui::{backend::TestBackend, Terminal};
+    use std::path::PathBuf;
+
+    #[test]
+    fn visible_window_follows_selection() {
+        assert_eq!(visible_window(0, 0, 1000, 20), (0, 20));
+        assert_eq!(visible_window(0, 19, 1000, 20), (0, 20));
+        assert_eq!(visible_window(0, 20, 1000, 20), (1, 21));
+        assert_eq!(visible_window(50, 10, 1000, 20), (10, 30));
+        assert_eq!(visible_window(990, 999, 1000, 20), (980, 1000));
+        assert_eq!(visible_window(0, 2, 3, 20), (0, 3));
+    }
+
+    /// Draw `list` on a `width` by `height` terminal with `context`, one
+    /// line of text per row
+    fn screen_with(list: &mut ListState, context: &ListContext, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| render_list(frame, list, context))
+            .unwrap();
+        let lines: Vec<String> = terminal
+            .backend()
+            .buffer()
+            .content()
+            .chunks(width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect();
+        lines.join("\n")
+    }
+
+    /// Draw `list` with the default columns and theme
+    fn screen(list: &mut ListState, width: u16, height: u16) -> String {
+        let context = ListContext {
+            columns: &ListColumns::default(),
+            status_message: None,
+            tick_count: 0,
+            announcements: None,
+            tabs: None,
+            theme: &Theme::default(),
+        };
+        screen_with(list, &context, width, height)
+    }
+
+    #[test]
+    fn renders_large_flake_around_cursor() {
+        let mut list = ListState::new(synthetic_flake(2000));
+        for _ in 0..1500 {
+            list.cursor_down();
+        }
+
+        let screen = screen(&mut list, 100, 30);
+        let offset = list.table_state.offset();
+        assert!(offset <= 1500 && 1500 < offset + 24);
+        assert!(screen.contains("input-1500"));
+        assert!(!screen.contains("input-0000"));
+    }
+
+    #[test]
+    fn narrow_terminals_use_two_line_rows() {
+        let mut list = ListState::new(synthetic_flake(20));
+        let screen = screen(&mut list, 50, 20);
+
+        let lines: Vec<&str> = screen.lines().collect();
+        let name_row = lines
+            .iter()
+            .position(|line| line.contains("input-0000"))
+            .unwrap();
+        // The rev sits under the name, and the wide header is gone.
+        assert!(lines[name_row + 1].contains("0000000"));
+        assert!(!screen.contains("UPDATED"));
+        // 15 lines inside the table borders hold 7 two-line rows.
+        assert!(screen.contains("input-0006"));
+        assert!(!screen.contains("input-0007"));
+    }
+
+    #[test]
+    fn renders_configured_columns_in_order() {
+        let mut list = ListState::new(synthetic_flake(3));
+        let columns = ListColumns::new(vec![ListColumn::Url, ListColumn::Name]).unwrap();
+        let context = ListContext {
+            columns: &columns,
+            status_message: None,
+            tick_count: 0,
+            announcements: None,
+            tabs: None,
+            theme: &Theme::default(),
+        };
+        let screen = screen_with(&mut list, &context, 100, 10);
+
+        let url = screen.find("URL").unwrap();
+        assert!(url < screen.find("NAME").unwrap());
+        assert!(!screen.contains("UPDATED"));
+        assert!(screen.contains("input-0002"));
+        assert!(screen.contains("refreshed now"));
+    }
+
+    #[test]
+    fn version_column_shows_evaluated_versions() {
+        let mut list = ListState::new(synthetic_flake(2));
+        list.versions
+            .insert("input-0001".to_string(), "24.11pre-git".to_string());
+        let columns =
+            ListColumns::new(vec![ListColumn::Name, ListColumn::Rev, ListColumn::Version]).unwrap();
+        let context = ListContext {
+            columns: &columns,
+            status_message: None,
+            tick_count: 0,
+            announcements: None,
+            tabs: None,
+            theme: &Theme::default(),
+        };
+        let screen = screen_with(&mut list, &context, 100, 10);
+
+        assert!(screen.contains("VERSION"));
+        let row = |name: &str| screen.lines().find(|line| line.contains(name)).unwrap();
+        assert!(row("input-0001").contains("24.11pre-git"));
+        assert!(row("input-0000")
+            .trim_end()
+            .trim_end_matches('│')
+            .trim_end()
+            .ends_with('-'));
+    }
+
+    #[test]
+    fn detail_pane_shows_full_metadata_of_current_input() {
+        let mut list = ListState::new(synthetic_flake(3));
+        list.cursor_down();
+        list.update_statuses.insert(
+            InputName::new("input-0001").unwrap(),
+            UpdateStatus::Error("fetch failed:\nremote hung up".to_string()),
+        );
+
+        assert!(!screen(&mut list, 140, 20).contains("repository"));
+        list.detail = true;
+        let screen = screen(&mut list, 140, 20);
+        assert!(screen.contains(" input-0001 "));
+        assert!(screen.contains("synthetic/input-0001"));
+        assert!(screen.contains(&format!("{:040x}", 1)));
+        assert!(screen.contains("remote hung up"));
+    }
+
+    #[test]
+    fn help_bar_names_fallback_clone() {
+        let mut list = ListState::new(synthetic_flake(3));
+        let name = InputName::new("input-0000").unwrap();
+        list.clone_paths
+            .insert(name, PathBuf::from("/cache/melt/git/input-0000"));
+
+        let screen = screen(&mut list, 160, 10);
+        assert!(screen.contains("checked in clone /cache/melt/git/input-0000"));
+    }
+
+    #[test]
+    fn accessible_mode_draws_plain_text_and_announcements() {
+        let mut list = ListState::new(synthetic_flake(3));
+        list.update_statuses.insert(
+            InputName::new("input-0000").unwrap(),
+            UpdateStatus::Checking,
+        );
+        let mut announcements = Announcements::default();
+        announcements.push("input-0001: 42 commits behind");
+        let context = ListContext {
+            columns: &ListColumns::default(),
+            status_message: None,
+            tick_count: 7,
+            announcements: Some(&announcements),
+            tabs: None,
+            theme: &Theme::default(),
+        };
+        let screen = screen_with(&mut list, &context, 100, 12);
+
+        assert!(screen.contains("input-0001: 42 commits behind"));
+        assert!(screen.contains("..."));
+        assert!(!screen.contains(get_spinner_frame(7)));
+        assert!(!screen.contains('│') && !screen.contains('─'));
+    }
+}
@@ -0,0 +1,231 @@
+//! flake.lock history rendering
+
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table},
+    Frame,
+};
+
+use crate::app::state::LockHistoryState;
+use crate::model::{LockChangeKind, StatusLevel, StatusMessage};
+use crate::ui::theme::Theme;
+use crate::util::text::truncate_with_ellipsis;
+use crate::util::time::format_relative;
+
+/// Render the flake.lock history view
+pub fn render_lock_history(
+    frame: &mut Frame,
+    history: &mut LockHistoryState,
+    status_message: Option<&StatusMessage>,
+    theme: &Theme,
+) {
+    let area = frame.area();
+    let chunks = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Length(8),
+        Constraint::Length(3),
+    ])
+    .split(area);
+
+    render_revisions_table(frame, history, chunks[0], theme);
+    render_changes(frame, history, chunks[1], theme);
+    render_lock_history_help_bar(frame, history, status_message, chunks[2], theme);
+}
+
+/// Render the table of commits that changed flake.lock
+fn render_revisions_table(
+    frame: &mut Frame,
+    history: &mut LockHistoryState,
+    area: Rect,
+    theme: &Theme,
+) {
+    let title = match (&history.focus, history.focus_only) {
+        (Some(focus), true) => format!(" flake.lock history of {} ", focus),
+        _ => " flake.lock history ".to_string(),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title(title)
+        .title_style(Style::default().fg(theme.text));
+
+    let visible = history.visible();
+    if visible.is_empty() {
+        let text = if history.revisions.is_none() {
+            "Reading git history..."
+        } else {
+            "No commits changed flake.lock"
+        };
+        let msg = Paragraph::new(text)
+            .style(Style::default().fg(theme.text_muted))
+            .alignment(Alignment::Center)
+            .block(block);
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    let rows: Vec<Row> = visible
+        .iter()
+        .map(|revision| {
+            let changes = revision
+                .changes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            Row::new(vec![
+                Span::styled(
+                    revision.short_sha().to_string(),
+                    Style::default().fg(theme.sha),
+                ),
+                Span::styled(
+                    format_relative(revision.date),
+                    Style::default().fg(theme.text_dim),
+                ),
+                Span::styled(
+                    truncate_with_ellipsis(&revision.summary, 50),
+                    Style::default().fg(theme.text),
+                ),
+                Span::styled(changes, Style::default().fg(theme.text_muted)),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(9),
+        Constraint::Length(16),
+        Constraint::Length(52),
+        Constraint::Min(20),
+    ];
+
+    let table = Table::new(rows, widths).block(block).row_highlight_style(
+        Style::default()
+            .bg(theme.bg_highlight)
+            .fg(theme.cursor)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    frame.render_stateful_widget(table, area, &mut history.table_state);
+}
+
+/// Render how each input moved in the selected commit
+fn render_changes(frame: &mut Frame, history: &LockHistoryState, area: Rect, theme: &Theme) {
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border));
+    let Some(revision) = history.selected_revision() else {
+        frame.render_widget(block, area);
+        return;
+    };
+    block = block
+        .title(format!(" {} by {} ", revision.short_sha(), revision.author))
+        .title_style(Style::default().fg(theme.text));
+
+    let lines: Vec<Line> = if revision.changes.is_empty() {
+        vec![Line::styled(
+            "No root input changed its locked revision",
+            Style::default().fg(theme.text_muted),
+        )]
+    } else {
+        revision
+            .changes
+            .iter()
+            .map(|change| {
+                let focused = history.focus.as_deref() == Some(change.input.as_str());
+                let mut name_style = Style::default().fg(theme.text);
+                if focused {
+                    name_style = name_style.fg(theme.accent).add_modifier(Modifier::BOLD);
+                }
+                let (movement, color) = match &change.kind {
+                    LockChangeKind::Added { rev } => (
+                        format!("added at {}", rev.as_deref().unwrap_or("?")),
+                        theme.success,
+                    ),
+                    LockChangeKind::Removed { rev } => (
+                        format!("removed from {}", rev.as_deref().unwrap_or("?")),
+                        theme.error,
+                    ),
+                    LockChangeKind::Changed { from, to } => (
+                        format!(
+                            "{} → {}",
+                            from.as_deref().unwrap_or("?"),
+                            to.as_deref().unwrap_or("?")
+                        ),
+                        theme.sha,
+                    ),
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:<24}", change.input), name_style),
+                    Span::styled(movement, Style::default().fg(color)),
+                ])
+            })
+            .collect()
+    };
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Render the flake.lock history help bar
+fn render_lock_history_help_bar(
+    frame: &mut Frame,
+    history: &LockHistoryState,
+    status_message: Option<&StatusMessage>,
+    area: Rect,
+    theme: &Theme,
+) {
+    let mut spans: Vec<Span> = Vec::new();
+    if let Some(revision) = history.selected_revision().filter(|_| history.confirming) {
+        spans.push(Span::styled(
+            format!(
+                "Restore flake.lock from {}? The current one can be brought back with z. ",
+                revision.short_sha()
+            ),
+            Style::default().fg(theme.warning),
+        ));
+        spans.push(Span::styled("y", Style::default().fg(theme.key_hint)));
+        spans.push(Span::styled(" yes ", Style::default().fg(theme.text_dim)));
+        spans.push(Span::styled("n", Style::default().fg(theme.key_hint)));
+        spans.push(Span::styled(" no", Style::default().fg(theme.text_dim)));
+    } else {
+        let focus = if history.focus_only {
+            "all"
+        } else {
+            "this input"
+        };
+        let shortcuts = [
+            ("j/k", "nav"),
+            ("enter", "restore"),
+            ("i", focus),
+            ("q/esc", "back"),
+        ];
+        spans.extend(shortcuts.iter().flat_map(|(key, desc)| {
+            vec![
+                Span::styled(*key, Style::default().fg(theme.key_hint)),
+                Span::styled(format!(" {} ", desc), Style::default().fg(theme.text_dim)),
+            ]
+        }));
+
+        if let Some(msg) = status_message {
+            let color = match msg.level {
+                StatusLevel::Info => theme.info,
+                StatusLevel::Success => theme.success,
+                StatusLevel::Warning => theme.warning,
+                StatusLevel::Error => theme.error,
+            };
+            spans.push(Span::styled(
+                format!(" | {}", msg.text),
+                Style::default().fg(color),
+            ));
+        }
+    }
+
+    let help = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border)),
+    );
+
+    frame.render_widget(help, area);
+}
@@ -3,10 +3,32 @@
 //! This module contains all the rendering logic for the application,
 //! separated by view type.
 
+mod activity;
+mod build;
 mod changelog;
+mod closure;
 mod common;
+mod follows;
+mod help;
 mod list;
+mod lock_history;
+mod onboarding;
+mod picker;
+mod stats;
+mod tasks;
+mod template;
 
+pub use activity::render_activity;
+pub use build::render_build;
 pub use changelog::render_changelog;
-pub use common::{render_error, render_loading};
-pub use list::render_list;
+pub use closure::render_closure_diff;
+pub use common::{is_too_small, render_error, render_loading, render_toasts, render_too_small};
+pub use follows::render_follows_graph;
+pub use help::render_help;
+pub use list::{plain_rows, render_list, ListContext};
+pub use lock_history::render_lock_history;
+pub use onboarding::render_no_flake;
+pub use picker::{render_branch_picker, render_flake_picker};
+pub use stats::render_stats;
+pub use tasks::render_tasks;
+pub use template::render_template_picker;
@@ -0,0 +1,93 @@
+//! Guidance screens for a missing or empty flake
+
+use std::path::Path;
+
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::ui::theme::Theme;
+
+/// Example input shown to users whose flake has none yet
+const EXAMPLE_INPUT: &str = r#"inputs.nixpkgs.url = "github:NixOS/nixpkgs/nixos-unstable";"#;
+
+fn key_line(key: &str, desc: &str, theme: &Theme) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{:>5}", key), Style::default().fg(theme.key_hint)),
+        Span::styled(format!("  {}", desc), Style::default().fg(theme.text_muted)),
+    ])
+}
+
+/// Render the screen shown when no `flake.nix` exists at the opened path
+pub fn render_no_flake(frame: &mut Frame, path: &Path, theme: &Theme) {
+    let area = frame.area();
+
+    let text = vec![
+        Line::from(Span::styled(
+            "No flake.nix found",
+            Style::default()
+                .fg(theme.warning)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            path.display().to_string(),
+            Style::default().fg(theme.text_dim),
+        )),
+        Line::from(""),
+        key_line("i", "Create a flake here from a template", theme),
+        key_line("r", "Try again (after creating flake.nix)", theme),
+        key_line("q", "Quit", theme),
+        Line::from(""),
+        Line::from(Span::styled(
+            "To open another flake, run `melt /path/to/flake`",
+            Style::default().fg(theme.text_dim),
+        )),
+    ];
+
+    let chunks = Layout::vertical([
+        Constraint::Percentage(30),
+        Constraint::Length(text.len() as u16),
+        Constraint::Min(0),
+    ])
+    .split(area);
+
+    frame.render_widget(Paragraph::new(text).alignment(Alignment::Center), chunks[1]);
+}
+
+/// Render guidance in place of the input table when a flake has no inputs
+pub fn render_empty_flake(frame: &mut Frame, path: &Path, area: Rect, theme: &Theme) {
+    let text = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            "This flake has no inputs yet",
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Add one to flake.nix, for example:",
+            Style::default().fg(theme.text_muted),
+        )),
+        Line::from(Span::styled(EXAMPLE_INPUT, Style::default().fg(theme.sha))),
+        Line::from(""),
+        key_line("r", "Refresh after editing flake.nix", theme),
+        key_line("f", "Switch to another flake in this directory", theme),
+        key_line("q", "Quit", theme),
+    ];
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title(format!(" {} ", path.display()))
+        .title_style(Style::default().fg(theme.text));
+
+    frame.render_widget(
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .block(block),
+        area,
+    );
+}
@@ -0,0 +1,240 @@
+//! Flake and branch picker rendering
+
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table},
+    Frame,
+};
+
+use crate::app::state::{BranchPickerState, FlakePickerState};
+use crate::model::{StatusLevel, StatusMessage};
+use crate::ui::theme::Theme;
+
+/// Render the flake picker view
+pub fn render_flake_picker(
+    frame: &mut Frame,
+    picker: &mut FlakePickerState,
+    status_message: Option<&StatusMessage>,
+    theme: &Theme,
+) {
+    let area = frame.area();
+    let chunks = Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).split(area);
+
+    render_flake_table(frame, picker, chunks[0], theme);
+    render_picker_help_bar(frame, picker, status_message, chunks[1], theme);
+}
+
+/// Render the table of discovered flakes
+fn render_flake_table(frame: &mut Frame, picker: &mut FlakePickerState, area: Rect, theme: &Theme) {
+    let rows: Vec<Row> = picker
+        .flakes
+        .iter()
+        .map(|flake| {
+            let is_current = picker.current.as_deref() == Some(flake.as_path());
+            let marker = if is_current { "●" } else { " " };
+            Row::new(vec![
+                Span::styled(marker, Style::default().fg(theme.selected)),
+                Span::styled(picker.display_path(flake), Style::default().fg(theme.text)),
+            ])
+        })
+        .collect();
+
+    let widths = [Constraint::Length(3), Constraint::Min(20)];
+
+    let title = format!(" Flakes in {} ", picker.root.to_string_lossy());
+    let table = Table::new(rows, widths)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .title(title)
+                .title_style(Style::default().fg(theme.text)),
+        )
+        .row_highlight_style(
+            Style::default()
+                .bg(theme.bg_highlight)
+                .fg(theme.cursor)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    frame.render_stateful_widget(table, area, &mut picker.table_state);
+}
+
+/// Render the flake picker help bar
+fn render_picker_help_bar(
+    frame: &mut Frame,
+    picker: &FlakePickerState,
+    status_message: Option<&StatusMessage>,
+    area: Rect,
+    theme: &Theme,
+) {
+    let back = if picker.parent_list.is_some() {
+        ("q/esc", "back")
+    } else {
+        ("q", "quit")
+    };
+    let shortcuts = [("j/k", "nav"), ("enter", "open"), back];
+
+    let mut spans: Vec<Span> = shortcuts
+        .iter()
+        .flat_map(|(key, desc)| {
+            vec![
+                Span::styled(*key, Style::default().fg(theme.key_hint)),
+                Span::styled(format!(" {} ", desc), Style::default().fg(theme.text_dim)),
+            ]
+        })
+        .collect();
+
+    spans.push(Span::styled(
+        format!(" | {} flakes", picker.flakes.len()),
+        Style::default().fg(theme.text_muted),
+    ));
+
+    if let Some(msg) = status_message {
+        let color = match msg.level {
+            StatusLevel::Info => theme.info,
+            StatusLevel::Success => theme.success,
+            StatusLevel::Warning => theme.warning,
+            StatusLevel::Error => theme.error,
+        };
+        spans.push(Span::styled(
+            format!(" | {}", msg.text),
+            Style::default().fg(color),
+        ));
+    }
+
+    let help = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border)),
+    );
+
+    frame.render_widget(help, area);
+}
+
+/// Render the branch picker view
+pub fn render_branch_picker(
+    frame: &mut Frame,
+    picker: &mut BranchPickerState,
+    status_message: Option<&StatusMessage>,
+    theme: &Theme,
+) {
+    let area = frame.area();
+    let chunks = Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).split(area);
+
+    render_branch_table(frame, picker, chunks[0], theme);
+    render_branch_help_bar(frame, picker, status_message, chunks[1], theme);
+}
+
+/// Render the table of upstream branches
+fn render_branch_table(
+    frame: &mut Frame,
+    picker: &mut BranchPickerState,
+    area: Rect,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title(format!(" Branches of {} ", picker.input.name()))
+        .title_style(Style::default().fg(theme.text));
+
+    let branches = match &picker.branches {
+        Some(branches) if !branches.is_empty() => branches,
+        loaded => {
+            let text = if loaded.is_none() {
+                "Loading branches..."
+            } else {
+                "No branches found"
+            };
+            let msg = Paragraph::new(text)
+                .style(Style::default().fg(theme.text_muted))
+                .alignment(Alignment::Center)
+                .block(block);
+            frame.render_widget(msg, area);
+            return;
+        }
+    };
+
+    let current = picker.input.reference();
+    let rows: Vec<Row> = branches
+        .iter()
+        .map(|branch| {
+            let marker = if current == Some(branch.as_str()) {
+                "●"
+            } else {
+                " "
+            };
+            Row::new(vec![
+                Span::styled(marker, Style::default().fg(theme.selected)),
+                Span::styled(branch.as_str(), Style::default().fg(theme.text)),
+            ])
+        })
+        .collect();
+
+    let widths = [Constraint::Length(3), Constraint::Min(20)];
+    let table = Table::new(rows, widths).block(block).row_highlight_style(
+        Style::default()
+            .bg(theme.bg_highlight)
+            .fg(theme.cursor)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    frame.render_stateful_widget(table, area, &mut picker.table_state);
+}
+
+/// Render the branch picker help bar
+fn render_branch_help_bar(
+    frame: &mut Frame,
+    picker: &BranchPickerState,
+    status_message: Option<&StatusMessage>,
+    area: Rect,
+    theme: &Theme,
+) {
+    let shortcuts: &[(&str, &str)] = if picker.switching.is_some() {
+        &[]
+    } else {
+        &[("j/k", "nav"), ("enter", "track"), ("q/esc", "back")]
+    };
+
+    let mut spans: Vec<Span> = shortcuts
+        .iter()
+        .flat_map(|(key, desc)| {
+            vec![
+                Span::styled(*key, Style::default().fg(theme.key_hint)),
+                Span::styled(format!(" {} ", desc), Style::default().fg(theme.text_dim)),
+            ]
+        })
+        .collect();
+
+    spans.push(Span::styled(
+        format!(
+            " | tracking {}",
+            picker.input.reference().unwrap_or("the default branch")
+        ),
+        Style::default().fg(theme.text_muted),
+    ));
+
+    if let Some(msg) = status_message {
+        let color = match msg.level {
+            StatusLevel::Info => theme.info,
+            StatusLevel::Success => theme.success,
+            StatusLevel::Warning => theme.warning,
+            StatusLevel::Error => theme.error,
+        };
+        spans.push(Span::styled(
+            format!(" | {}", msg.text),
+            Style::default().fg(color),
+        ));
+    }
+
+    let help = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border)),
+    );
+
+    frame.render_widget(help, area);
+}
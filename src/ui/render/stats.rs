@@ -0,0 +1,105 @@
+//! Cache metrics pane rendering
+
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::service::CacheStats;
+use crate::ui::theme::Theme;
+use crate::util::format::{format_bytes, format_count};
+
+/// Render the session's cache metrics centered over the current view
+pub fn render_stats(frame: &mut Frame, stats: &CacheStats, theme: &Theme) {
+    let rows = stat_rows(stats);
+    let area = frame.area();
+    let width = area.width.saturating_sub(4).min(48);
+    let height = area.height.saturating_sub(2).min(rows.len() as u16 + 4);
+    let pane_area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    frame.render_widget(Clear, pane_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(" Cache metrics ")
+        .title_style(Style::default().fg(theme.text))
+        .style(Style::default().bg(theme.bg_dark));
+    let inner = block.inner(pane_area);
+    frame.render_widget(block, pane_area);
+
+    let chunks = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).split(inner);
+
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(
+                    format!(" {:<16}", label),
+                    Style::default().fg(theme.text_dim),
+                ),
+                Span::styled(value, Style::default().fg(theme.text)),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled("q/esc", Style::default().fg(theme.key_hint)),
+            Span::styled(" close ", Style::default().fg(theme.text_dim)),
+        ])),
+        chunks[1],
+    );
+}
+
+fn stat_rows(stats: &CacheStats) -> Vec<(&'static str, String)> {
+    let hit_rate = stats
+        .hit_rate()
+        .map(|rate| format!("{:.0}%", rate * 100.0))
+        .unwrap_or_else(|| "-".to_string());
+    vec![
+        ("Clone hits", format_count(stats.clone_hits as usize)),
+        ("Clone misses", format_count(stats.clone_misses as usize)),
+        ("Hit rate", hit_rate),
+        ("Git fetched", format_bytes(stats.git_bytes)),
+        ("API requests", format_count(stats.api_requests as usize)),
+        ("API fetched", format_bytes(stats.api_bytes)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn shows_totals_and_hit_rate() {
+        let stats = CacheStats {
+            clone_hits: 3,
+            clone_misses: 1,
+            git_bytes: 2048,
+            api_requests: 12,
+            api_bytes: 100,
+        };
+        let mut terminal = Terminal::new(TestBackend::new(60, 14)).unwrap();
+        terminal
+            .draw(|frame| render_stats(frame, &stats, &Theme::default()))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("Cache metrics"));
+        assert!(text.contains("75%"));
+        assert!(text.contains("2.0 KiB"));
+        assert!(text.contains("100 B"));
+    }
+}
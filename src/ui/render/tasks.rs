@@ -0,0 +1,126 @@
+//! Tasks pane rendering
+
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table},
+    Frame,
+};
+
+use crate::app::scheduler::{Task, TaskStatus};
+use crate::app::state::TasksPaneState;
+use crate::ui::theme::Theme;
+
+/// Render the tasks pane centered over the current view
+pub fn render_tasks(
+    frame: &mut Frame,
+    tasks: &[Task],
+    pane: &mut TasksPaneState,
+    now: Instant,
+    theme: &Theme,
+) {
+    let area = frame.area();
+    let width = area.width.saturating_sub(4).min(72);
+    let height = area
+        .height
+        .saturating_sub(2)
+        .min(tasks.len().max(1) as u16 + 4);
+    let pane_area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    frame.render_widget(Clear, pane_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(format!(" Tasks ({} active) ", active_count(tasks)))
+        .title_style(Style::default().fg(theme.text))
+        .style(Style::default().bg(theme.bg_dark));
+    let inner = block.inner(pane_area);
+    frame.render_widget(block, pane_area);
+
+    let chunks = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).split(inner);
+
+    if tasks.is_empty() {
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                " No background tasks",
+                Style::default().fg(theme.text_dim),
+            )),
+            chunks[0],
+        );
+    } else {
+        let rows: Vec<Row> = tasks
+            .iter()
+            .map(|task| {
+                let (status, color) = status_cell(&task.status, theme);
+                let elapsed = task.elapsed(now).map(format_elapsed).unwrap_or_default();
+                Row::new(vec![
+                    Span::styled(status, Style::default().fg(color)),
+                    Span::styled(task.label.clone(), Style::default().fg(theme.text)),
+                    Span::styled(elapsed, Style::default().fg(theme.text_muted)),
+                ])
+            })
+            .collect();
+
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Min(20),
+            Constraint::Length(8),
+        ];
+        let table = Table::new(rows, widths).row_highlight_style(
+            Style::default()
+                .bg(theme.bg_highlight)
+                .fg(theme.cursor)
+                .add_modifier(Modifier::BOLD),
+        );
+        frame.render_stateful_widget(table, chunks[0], &mut pane.table_state);
+    }
+
+    let shortcuts = [("j/k", "nav"), ("x", "cancel"), ("q/esc", "close")];
+    let spans: Vec<Span> = shortcuts
+        .iter()
+        .flat_map(|(key, desc)| {
+            vec![
+                Span::styled(*key, Style::default().fg(theme.key_hint)),
+                Span::styled(format!(" {} ", desc), Style::default().fg(theme.text_dim)),
+            ]
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(Line::from(spans)), chunks[1]);
+}
+
+fn active_count(tasks: &[Task]) -> usize {
+    tasks
+        .iter()
+        .filter(|task| !task.status.is_finished())
+        .count()
+}
+
+fn status_cell(status: &TaskStatus, theme: &Theme) -> (String, ratatui::style::Color) {
+    match status {
+        TaskStatus::Queued => ("queued".to_string(), theme.text_dim),
+        TaskStatus::Running => ("running".to_string(), theme.info),
+        TaskStatus::Retrying { attempt, .. } => (format!("retry {}", attempt), theme.warning),
+        TaskStatus::Succeeded => ("done".to_string(), theme.success),
+        TaskStatus::Failed(_) => ("failed".to_string(), theme.error),
+        TaskStatus::Cancelled => ("cancelled".to_string(), theme.text_dim),
+    }
+}
+
+/// Short elapsed time, e.g. `4.2s` or `3m07s`
+pub(super) fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", elapsed.as_secs_f64())
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
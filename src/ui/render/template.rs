@@ -0,0 +1,137 @@
+//! Template picker rendering
+
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table},
+    Frame,
+};
+
+use crate::app::state::{TemplatePickerMode, TemplatePickerState};
+use crate::model::{StatusLevel, StatusMessage};
+use crate::ui::theme::Theme;
+
+use super::common::get_spinner_frame;
+
+/// Render the template picker view
+pub fn render_template_picker(
+    frame: &mut Frame,
+    picker: &mut TemplatePickerState,
+    status_message: Option<&StatusMessage>,
+    tick_count: u64,
+    theme: &Theme,
+) {
+    let area = frame.area();
+    let chunks = Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).split(area);
+
+    render_template_table(frame, picker, chunks[0], tick_count, theme);
+    render_template_help_bar(frame, status_message, chunks[1], tick_count, theme);
+}
+
+/// Render the table of available templates
+fn render_template_table(
+    frame: &mut Frame,
+    picker: &mut TemplatePickerState,
+    area: Rect,
+    tick_count: u64,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .title(format!(" New flake in {} ", picker.path.to_string_lossy()))
+        .title_style(Style::default().fg(theme.text));
+
+    if picker.mode == TemplatePickerMode::LoadingTemplates {
+        let msg = Paragraph::new(Line::from(vec![
+            Span::styled(
+                get_spinner_frame(tick_count),
+                Style::default().fg(theme.accent),
+            ),
+            Span::styled(" Loading templates...", Style::default().fg(theme.text)),
+        ]))
+        .alignment(Alignment::Center)
+        .block(block);
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    let header =
+        Row::new(vec!["TEMPLATE", "DESCRIPTION"]).style(Style::default().fg(theme.text_dim));
+
+    let rows: Vec<Row> = picker
+        .templates
+        .iter()
+        .map(|template| {
+            Row::new(vec![
+                Span::styled(template.name.as_str(), Style::default().fg(theme.accent)),
+                Span::styled(
+                    template.description.as_str(),
+                    Style::default().fg(theme.text),
+                ),
+            ])
+        })
+        .collect();
+
+    let widths = [Constraint::Length(24), Constraint::Min(20)];
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .bg(theme.bg_highlight)
+                .fg(theme.cursor)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    frame.render_stateful_widget(table, area, &mut picker.table_state);
+}
+
+/// Render the template picker help bar
+fn render_template_help_bar(
+    frame: &mut Frame,
+    status_message: Option<&StatusMessage>,
+    area: Rect,
+    tick_count: u64,
+    theme: &Theme,
+) {
+    let shortcuts = [("j/k", "nav"), ("enter", "init"), ("q", "quit")];
+
+    let mut spans: Vec<Span> = shortcuts
+        .iter()
+        .flat_map(|(key, desc)| {
+            vec![
+                Span::styled(*key, Style::default().fg(theme.key_hint)),
+                Span::styled(format!(" {} ", desc), Style::default().fg(theme.text_dim)),
+            ]
+        })
+        .collect();
+
+    if let Some(msg) = status_message {
+        let color = match msg.level {
+            StatusLevel::Info => theme.info,
+            StatusLevel::Success => theme.success,
+            StatusLevel::Warning => theme.warning,
+            StatusLevel::Error => theme.error,
+        };
+        let spinner = if msg.level == StatusLevel::Info {
+            format!("{} ", get_spinner_frame(tick_count))
+        } else {
+            String::new()
+        };
+        spans.push(Span::styled(
+            format!(" | {}{}", spinner, msg.text),
+            Style::default().fg(color),
+        ));
+    }
+
+    let help = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border)),
+    );
+
+    frame.render_widget(help, area);
+}
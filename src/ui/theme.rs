@@ -1,57 +1,179 @@
 use ratatui::style::Color;
 
-/// Catppuccin Mocha color palette
+/// Installed themes, in the order they are cycled through
+pub const THEMES: &[Theme] = &[
+    Theme::catppuccin("catppuccin-mocha", palette::MOCHA),
+    Theme::catppuccin("catppuccin-latte", palette::LATTE),
+];
+
+/// Catppuccin color palettes
 mod palette {
     use super::Color;
 
-    pub const ROSEWATER: Color = Color::Rgb(245, 224, 220);
-    pub const MAUVE: Color = Color::Rgb(203, 166, 247);
-    pub const RED: Color = Color::Rgb(243, 139, 168);
-    pub const PEACH: Color = Color::Rgb(250, 179, 135);
-    pub const YELLOW: Color = Color::Rgb(249, 226, 175);
-    pub const GREEN: Color = Color::Rgb(166, 227, 161);
-    pub const SKY: Color = Color::Rgb(137, 220, 235);
-    pub const BLUE: Color = Color::Rgb(137, 180, 250);
-    pub const LAVENDER: Color = Color::Rgb(180, 190, 254);
-
-    pub const TEXT: Color = Color::Rgb(205, 214, 244);
-    pub const SUBTEXT0: Color = Color::Rgb(166, 173, 200);
-    pub const OVERLAY1: Color = Color::Rgb(127, 132, 156);
-    pub const SURFACE1: Color = Color::Rgb(69, 71, 90);
-    pub const SURFACE0: Color = Color::Rgb(49, 50, 68);
-    pub const MANTLE: Color = Color::Rgb(24, 24, 37);
+    /// The colors of one Catppuccin flavor that melt uses
+    pub struct Palette {
+        pub rosewater: Color,
+        pub mauve: Color,
+        pub red: Color,
+        pub peach: Color,
+        pub yellow: Color,
+        pub green: Color,
+        pub sky: Color,
+        pub blue: Color,
+        pub lavender: Color,
+        pub text: Color,
+        pub subtext0: Color,
+        pub overlay1: Color,
+        pub surface1: Color,
+        pub surface0: Color,
+        pub mantle: Color,
+    }
+
+    pub const MOCHA: Palette = Palette {
+        rosewater: Color::Rgb(245, 224, 220),
+        mauve: Color::Rgb(203, 166, 247),
+        red: Color::Rgb(243, 139, 168),
+        peach: Color::Rgb(250, 179, 135),
+        yellow: Color::Rgb(249, 226, 175),
+        green: Color::Rgb(166, 227, 161),
+        sky: Color::Rgb(137, 220, 235),
+        blue: Color::Rgb(137, 180, 250),
+        lavender: Color::Rgb(180, 190, 254),
+        text: Color::Rgb(205, 214, 244),
+        subtext0: Color::Rgb(166, 173, 200),
+        overlay1: Color::Rgb(127, 132, 156),
+        surface1: Color::Rgb(69, 71, 90),
+        surface0: Color::Rgb(49, 50, 68),
+        mantle: Color::Rgb(24, 24, 37),
+    };
+
+    pub const LATTE: Palette = Palette {
+        rosewater: Color::Rgb(220, 138, 120),
+        mauve: Color::Rgb(136, 57, 239),
+        red: Color::Rgb(210, 15, 57),
+        peach: Color::Rgb(254, 100, 11),
+        yellow: Color::Rgb(223, 142, 29),
+        green: Color::Rgb(64, 160, 43),
+        sky: Color::Rgb(4, 165, 229),
+        blue: Color::Rgb(30, 102, 245),
+        lavender: Color::Rgb(114, 135, 253),
+        text: Color::Rgb(76, 79, 105),
+        subtext0: Color::Rgb(108, 111, 133),
+        overlay1: Color::Rgb(140, 143, 161),
+        surface1: Color::Rgb(188, 192, 204),
+        surface0: Color::Rgb(204, 208, 218),
+        mantle: Color::Rgb(230, 233, 239),
+    };
 }
 
-// Semantic color mappings for the UI
+/// Semantic colors the UI is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Name used in the config file
+    pub name: &'static str,
+
+    /// Background colors
+    pub bg_dark: Color,
+    pub bg_highlight: Color,
 
-/// Background colors
-pub const BG_DARK: Color = palette::MANTLE;
-pub const BG_HIGHLIGHT: Color = palette::SURFACE0;
+    /// Text colors
+    pub text: Color,
+    pub text_muted: Color,
+    pub text_dim: Color,
 
-/// Text colors
-pub const TEXT: Color = palette::TEXT;
-pub const TEXT_MUTED: Color = palette::SUBTEXT0;
-pub const TEXT_DIM: Color = palette::OVERLAY1;
+    /// UI element colors
+    pub border: Color,
 
-/// UI element colors
-pub const BORDER: Color = palette::SURFACE1;
+    /// Status colors
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    pub info: Color,
 
-/// Status colors
-pub const SUCCESS: Color = palette::GREEN;
-pub const WARNING: Color = palette::YELLOW;
-pub const ERROR: Color = palette::RED;
-pub const INFO: Color = palette::BLUE;
+    /// Accent colors
+    pub accent: Color,
+    pub selected: Color,
+    pub cursor: Color,
 
-/// Accent colors
-pub const ACCENT: Color = palette::MAUVE;
-pub const SELECTED: Color = palette::GREEN;
-pub const CURSOR: Color = palette::ROSEWATER;
+    /// Type badge colors
+    pub type_git: Color,
+    pub type_path: Color,
+    pub type_other: Color,
 
-/// Type badge colors
-pub const TYPE_GIT: Color = palette::PEACH;
-pub const TYPE_PATH: Color = palette::SKY;
-pub const TYPE_OTHER: Color = palette::OVERLAY1;
+    /// Misc
+    pub key_hint: Color,
+    pub sha: Color,
+}
+
+impl Theme {
+    const fn catppuccin(name: &'static str, palette: palette::Palette) -> Self {
+        Self {
+            name,
+            bg_dark: palette.mantle,
+            bg_highlight: palette.surface0,
+            text: palette.text,
+            text_muted: palette.subtext0,
+            text_dim: palette.overlay1,
+            border: palette.surface1,
+            success: palette.green,
+            warning: palette.yellow,
+            error: palette.red,
+            info: palette.blue,
+            accent: palette.mauve,
+            selected: palette.green,
+            cursor: palette.rosewater,
+            type_git: palette.peach,
+            type_path: palette.sky,
+            type_other: palette.overlay1,
+            key_hint: palette.lavender,
+            sha: palette.peach,
+        }
+    }
 
-/// Misc
-pub const KEY_HINT: Color = palette::LAVENDER;
-pub const SHA: Color = palette::PEACH;
+    /// The installed theme called `name`
+    pub fn named(name: &str) -> Option<Self> {
+        THEMES.iter().find(|theme| theme.name == name).copied()
+    }
+
+    /// The installed theme after this one, wrapping around
+    pub fn next(&self) -> Self {
+        let idx = THEMES
+            .iter()
+            .position(|theme| theme.name == self.name)
+            .map_or(0, |idx| idx + 1);
+        THEMES[idx % THEMES.len()]
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        THEMES[0]
+    }
+}
+
+/// Names of the installed themes, for messages listing them
+pub fn names() -> Vec<&'static str> {
+    THEMES.iter().map(|theme| theme.name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_cycles_through_every_theme() {
+        let mut theme = Theme::default();
+        let mut seen = vec![theme.name];
+        for _ in 1..THEMES.len() {
+            theme = theme.next();
+            seen.push(theme.name);
+        }
+        assert_eq!(seen, names());
+        assert_eq!(theme.next(), Theme::default());
+        assert_eq!(
+            Theme::named("catppuccin-latte").map(|theme| theme.name),
+            Some("catppuccin-latte")
+        );
+        assert_eq!(Theme::named("solarized"), None);
+    }
+}
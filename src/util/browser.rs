@@ -0,0 +1,30 @@
+//! Opening links in a web browser
+
+use std::process::{Command, Stdio};
+
+/// Open `url` with `$BROWSER`, or the platform's opener, without waiting for
+/// it to exit
+pub fn open(url: &str) -> std::io::Result<()> {
+    let program = std::env::var("BROWSER")
+        .ok()
+        .filter(|browser| !browser.trim().is_empty())
+        .unwrap_or_else(|| default_opener().to_string());
+
+    // The terminal belongs to the TUI, so the browser gets none of it
+    let mut child = Command::new(program)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+fn default_opener() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    }
+}
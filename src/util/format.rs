@@ -0,0 +1,189 @@
+//! Formatting preferences for times, weeks, counts, and sizes
+//!
+//! Preferences are set once at startup from the command line and read by
+//! every formatter, so the TUI and headless output agree.
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// How times of day are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+pub enum ClockFormat {
+    /// 14:05
+    #[default]
+    #[value(name = "24h")]
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    /// 2:05 PM
+    #[value(name = "12h")]
+    #[serde(rename = "12h")]
+    TwelveHour,
+}
+
+/// First day of the week for weekly groupings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+/// User formatting preferences
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormatPrefs {
+    pub clock: ClockFormat,
+    pub week_start: WeekStart,
+    /// Separator between groups of three digits in counts, if any
+    pub thousands_separator: Option<char>,
+}
+
+static PREFS: OnceLock<FormatPrefs> = OnceLock::new();
+
+/// Set the preferences used by every formatter. Only the first call has an effect.
+pub fn set_prefs(prefs: FormatPrefs) {
+    let _ = PREFS.set(prefs);
+}
+
+/// The preferences in effect, or the defaults if none were set
+pub fn prefs() -> FormatPrefs {
+    PREFS.get().copied().unwrap_or_default()
+}
+
+/// Format a count, e.g. a number of commits
+pub fn format_count(n: usize) -> String {
+    group_digits(n, prefs().thousands_separator)
+}
+
+/// Format a byte size with a binary unit, e.g. `3.4 MiB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Format a date and time of day
+pub fn format_datetime(dt: DateTime<Utc>) -> String {
+    format_datetime_with(dt, prefs().clock)
+}
+
+/// Format a time of day to the second, in `dt`'s time zone
+pub fn format_time<Tz: TimeZone>(dt: DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    format_time_with(dt, prefs().clock)
+}
+
+/// Midnight at the start of the week containing `dt`
+pub fn week_start_of(dt: DateTime<Utc>) -> DateTime<Utc> {
+    week_start_with(dt, prefs().week_start)
+}
+
+/// Format `n` with `separator` between groups of three digits
+pub fn group_digits(n: usize, separator: Option<char>) -> String {
+    let digits = n.to_string();
+    let Some(separator) = separator else {
+        return digits;
+    };
+
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(separator);
+        }
+        out.push(digit);
+    }
+    out
+}
+
+fn format_datetime_with(dt: DateTime<Utc>, clock: ClockFormat) -> String {
+    match clock {
+        ClockFormat::TwentyFourHour => dt.format("%Y-%m-%d %H:%M").to_string(),
+        ClockFormat::TwelveHour => dt.format("%Y-%m-%d %-I:%M %p").to_string(),
+    }
+}
+
+fn format_time_with<Tz: TimeZone>(dt: DateTime<Tz>, clock: ClockFormat) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    match clock {
+        ClockFormat::TwentyFourHour => dt.format("%H:%M:%S").to_string(),
+        ClockFormat::TwelveHour => dt.format("%-I:%M:%S %p").to_string(),
+    }
+}
+
+fn week_start_with(dt: DateTime<Utc>, start: WeekStart) -> DateTime<Utc> {
+    let days_since_start = match start {
+        WeekStart::Monday => dt.weekday().num_days_from_monday(),
+        WeekStart::Sunday => dt.weekday().num_days_from_sunday(),
+    };
+    let day = dt.date_naive() - Duration::days(i64::from(days_since_start));
+    Utc.from_utc_datetime(&day.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_digits_with_separator() {
+        assert_eq!(group_digits(1234567, Some(',')), "1,234,567");
+        assert_eq!(group_digits(123456, Some('.')), "123.456");
+        assert_eq!(group_digits(999, Some(',')), "999");
+        assert_eq!(group_digits(1234567, None), "1234567");
+    }
+
+    #[test]
+    fn formats_byte_sizes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_bytes(3 << 40), "3.0 TiB");
+    }
+
+    #[test]
+    fn formats_clock_styles() {
+        let dt = Utc.with_ymd_and_hms(2026, 10, 14, 14, 5, 0).unwrap();
+        assert_eq!(
+            format_datetime_with(dt, ClockFormat::TwentyFourHour),
+            "2026-10-14 14:05"
+        );
+        assert_eq!(
+            format_datetime_with(dt, ClockFormat::TwelveHour),
+            "2026-10-14 2:05 PM"
+        );
+        assert_eq!(
+            format_time_with(dt, ClockFormat::TwentyFourHour),
+            "14:05:00"
+        );
+        assert_eq!(format_time_with(dt, ClockFormat::TwelveHour), "2:05:00 PM");
+    }
+
+    #[test]
+    fn finds_start_of_week() {
+        // 2026-10-14 is a Wednesday
+        let dt = Utc.with_ymd_and_hms(2026, 10, 14, 14, 5, 0).unwrap();
+        assert_eq!(
+            week_start_with(dt, WeekStart::Monday),
+            Utc.with_ymd_and_hms(2026, 10, 12, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            week_start_with(dt, WeekStart::Sunday),
+            Utc.with_ymd_and_hms(2026, 10, 11, 0, 0, 0).unwrap()
+        );
+    }
+}
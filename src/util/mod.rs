@@ -1,2 +1,4 @@
+pub mod browser;
+pub mod format;
 pub mod text;
 pub mod time;
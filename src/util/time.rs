@@ -71,6 +71,33 @@ pub fn format_relative_short(dt: DateTime<Utc>) -> String {
     "now".to_string()
 }
 
+/// Parse an interval such as `90s`, `5m`, or `1h` (a bare number is seconds)
+pub fn parse_interval(s: &str) -> Result<std::time::Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+
+    let count: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid interval '{}', expected e.g. 30s, 5m, or 1h", s))?;
+    let unit_secs = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => {
+            return Err(format!(
+                "unknown interval unit '{}', expected s, m, or h",
+                unit
+            ))
+        }
+    };
+    if count == 0 {
+        return Err("interval must be greater than zero".to_string());
+    }
+
+    Ok(std::time::Duration::from_secs(count * unit_secs))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,4 +132,17 @@ mod tests {
         let dt = Utc::now() - Duration::days(3);
         assert_eq!(format_relative_short(dt), "3d ago");
     }
+
+    #[test]
+    fn test_parse_interval() {
+        use std::time::Duration;
+
+        assert_eq!(parse_interval("45"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_interval("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_interval("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_interval("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse_interval("0m").is_err());
+        assert!(parse_interval("5d").is_err());
+        assert!(parse_interval("m").is_err());
+    }
 }